@@ -0,0 +1,64 @@
+#![no_std]
+#![no_main]
+
+use esp_backtrace as _;
+use esp_hal::time::Instant;
+use esp_hal::uart::{Config, Uart};
+use hexar::esp::{RadarEvent, UartRadar};
+use hexar::ld2450::{Ld2450Command, Ld2450TargetData};
+use hexar::RadarLLFrame;
+use log::{info, warn};
+
+esp_bootloader_esp_idf::esp_app_desc!();
+
+#[esp_hal::main]
+fn main() -> ! {
+    esp_println::logger::init_logger_from_env();
+    let peripherals = esp_hal::init(esp_hal::Config::default());
+
+    let uart = Uart::new(peripherals.UART1, Config::default().with_baudrate(256_000))
+        .unwrap()
+        .with_rx(peripherals.GPIO18)
+        .with_tx(peripherals.GPIO17);
+    let mut radar = UartRadar::new(uart);
+
+    for command in [
+        Ld2450Command::EnableConfiguration,
+        Ld2450Command::MultiTargetTracking,
+        Ld2450Command::EndConfiguration,
+    ] {
+        radar.send(&command).unwrap();
+    }
+
+    let mut last_poll = Instant::now();
+    loop {
+        let now = Instant::now();
+        let mut elapsed_ms = (now - last_poll).as_millis() as u32;
+        last_poll = now;
+
+        loop {
+            match radar.poll(elapsed_ms) {
+                Ok(Some(RadarEvent::Frame(RadarLLFrame::TargetFrame2D(data)))) => {
+                    if let Some(report) = Ld2450TargetData::deserialize(&data) {
+                        for target in &report.targets {
+                            info!(
+                                "target x={}mm y={}mm speed={}cm/s",
+                                target.position.x, target.position.y, target.speed
+                            );
+                        }
+                    }
+                }
+                Ok(Some(RadarEvent::Frame(_))) => {}
+                Ok(Some(RadarEvent::CommandTimedOut(opcode))) => {
+                    warn!("LD2450 did not acknowledge command {:#06x}", opcode)
+                }
+                Ok(None) => break,
+                Err(e) => {
+                    warn!("UART error: {:?}", e);
+                    break;
+                }
+            }
+            elapsed_ms = 0;
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! Link-time proof that the protocol core can't panic. Built with `panic = "abort"` this
+//! program goes without the standard library and its panic handler calls a function that
+//! exists nowhere, so it only links once the optimizer removed every panic reachable from the
+//! entry points in `parse_all`:
+//!
+//! cargo build --profile panic-check --example panic_free --no-default-features --features panic-free
+//!
+//! A failed link names `hexar_protocol_core_may_panic`, the assembly of the example
+//! (`--emit asm`) shows which call still leads to `core::panicking`. Profiles that unwind
+//! build an ordinary program running the same parsers, which proves nothing.
+
+#![cfg_attr(panic = "abort", no_std, no_main)]
+
+extern crate alloc;
+
+use core::hint::black_box;
+
+use hexar::ld2412::Ld2412TargetData;
+use hexar::ld2450::Ld2450TargetData;
+use hexar::{FrameAccumulator, RadarLLFrame};
+use smallvec::SmallVec;
+
+/// Feeds `bytes` to every entry point, opaque to the optimizer so no path is folded away for a
+/// known input
+fn parse_all(bytes: &[u8]) -> usize {
+    let bytes = black_box(bytes);
+
+    let mut found = 0;
+    found += RadarLLFrame::deserialize(bytes).is_some() as usize;
+    let data = SmallVec::from_slice(bytes.get(..8).unwrap_or_default());
+    let command = RadarLLFrame::CommandAckFrame(black_box(0x00FF), data);
+    found += command.serialize().is_some() as usize;
+    found += Ld2412TargetData::deserialize(bytes).is_some() as usize;
+    found += Ld2450TargetData::deserialize(bytes).is_some() as usize;
+
+    let mut accumulator = FrameAccumulator::<256>::new();
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let accepted = accumulator.push(rest);
+        rest = rest.get(accepted..).unwrap_or_default();
+        found += accumulator.parse_some(8).count();
+        if accepted == 0 {
+            accumulator.clear();
+        }
+    }
+    found
+}
+
+#[cfg(panic = "abort")]
+mod link_check {
+    use core::alloc::{GlobalAlloc, Layout};
+    use core::ffi::{c_char, c_int};
+    use core::hint::black_box;
+
+    #[link(name = "c")]
+    extern "C" {
+        fn malloc(size: usize) -> *mut u8;
+        fn realloc(ptr: *mut u8, size: usize) -> *mut u8;
+        fn free(ptr: *mut u8);
+        fn strlen(s: *const c_char) -> usize;
+    }
+
+    /// The C library heap, for payloads too long for a SmallVec's inline buffer. Byte buffers
+    /// need no alignment beyond what malloc gives.
+    struct LibcAlloc;
+
+    unsafe impl GlobalAlloc for LibcAlloc {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            malloc(layout.size())
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, _layout: Layout) {
+            free(ptr)
+        }
+
+        unsafe fn realloc(&self, ptr: *mut u8, _layout: Layout, new_size: usize) -> *mut u8 {
+            realloc(ptr, new_size)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: LibcAlloc = LibcAlloc;
+
+    #[panic_handler]
+    fn panic(_: &core::panic::PanicInfo) -> ! {
+        extern "C" {
+            fn hexar_protocol_core_may_panic() -> !;
+        }
+        unsafe { hexar_protocol_core_may_panic() }
+    }
+
+    #[no_mangle]
+    extern "C" fn main(argc: c_int, argv: *const *const c_char) -> c_int {
+        let bytes: &[u8] = if argc > 1 {
+            // SAFETY: argv holds argc NUL terminated strings
+            unsafe {
+                let arg = *argv.add(1);
+                core::slice::from_raw_parts(arg.cast(), strlen(arg))
+            }
+        } else {
+            &[]
+        };
+        black_box(super::parse_all(bytes));
+        0
+    }
+}
+
+#[cfg(not(panic = "abort"))]
+fn main() {
+    let bytes = std::env::args_os()
+        .nth(1)
+        .map(|arg| arg.into_encoded_bytes())
+        .unwrap_or_default();
+    println!("{} frames parsed", parse_all(&bytes));
+}
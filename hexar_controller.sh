@@ -102,9 +102,8 @@ start_system() {
     log "Executing: wsl bash -c '. ~/.cargo/env && cargo run --release --bin hexar -- $cmd_args'"
     
     if [ "$daemon_mode" = true ]; then
-        # Start in background
-        nohup wsl bash -c ". ~/.cargo/env && cargo run --release --bin hexar -- $cmd_args" > "$LOG_DIR/hexar.log" 2>&1 &
-        echo $! > "$PID_FILE"
+        # Detaches itself and writes the PID file once running
+        wsl bash -c ". ~/.cargo/env && cargo run --release --bin hexar -- --log-file $LOG_DIR/hexar.log $cmd_args"
         success "System started in daemon mode (PID: $(cat "$PID_FILE"))"
         success "Logs: $LOG_DIR/hexar.log"
     else
@@ -124,38 +123,9 @@ stop_system() {
         return 0
     fi
     
-    local pid=$(cat "$PID_FILE")
-    
-    if ! kill -0 "$pid" 2>/dev/null; then
-        warn "Process $pid is not running. Removing PID file."
-        rm -f "$PID_FILE"
-        return 0
-    fi
-    
-    # Send SIGTERM for graceful shutdown
-    log "Sending SIGTERM to process $pid..."
-    kill -TERM "$pid"
-    
-    # Wait for graceful shutdown
-    local count=0
-    while [ $count -lt $timeout ] && kill -0 "$pid" 2>/dev/null; do
-        sleep 1
-        count=$((count + 1))
-    done
-    
-    # Check if process is still running
-    if kill -0 "$pid" 2>/dev/null; then
-        warn "Graceful shutdown timed out. Sending SIGKILL..."
-        kill -KILL "$pid"
-        sleep 2
-        
-        if kill -0 "$pid" 2>/dev/null; then
-            error "Failed to kill process $pid"
-            exit 1
-        fi
-    fi
-    
-    rm -f "$PID_FILE"
+    # SIGTERM, then SIGKILL once the timeout passes
+    cd "$PROJECT_ROOT"
+    wsl bash -c ". ~/.cargo/env && cargo run --release --bin hexar -- stop --timeout $timeout"
     success "System stopped successfully"
 }
 
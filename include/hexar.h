@@ -0,0 +1,93 @@
+#ifndef HEXAR_H
+#define HEXAR_H
+
+/* Generated by cbindgen from src/ffi.rs, do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Largest payload a [`HexarFrame`] can carry, longer payloads are truncated
+ */
+#define HEXAR_FRAME_DATA_MAX 64
+
+typedef enum HexarFrameKind {
+  /**
+   * Command acknowledgement, `opcode` is valid
+   */
+  HEXAR_FRAME_KIND_COMMAND_ACK = 0,
+  /**
+   * LD2412 1D target data
+   */
+  HEXAR_FRAME_KIND_LD2412_TARGET = 1,
+  /**
+   * LD2450 2D target data
+   */
+  HEXAR_FRAME_KIND_LD2450_TARGET = 2,
+} HexarFrameKind;
+
+/**
+ * Opaque parser handle for C callers
+ */
+typedef struct HexarParser HexarParser;
+
+typedef struct HexarFrame {
+  enum HexarFrameKind kind;
+  uint16_t opcode;
+  uint16_t len;
+  bool truncated;
+  uint8_t data[HEXAR_FRAME_DATA_MAX];
+} HexarFrame;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Allocate a parser, release it with [`hexar_parser_free`]
+ */
+struct HexarParser *hexar_parser_new(void);
+
+/**
+ * # Safety
+ *
+ * `parser` must come from [`hexar_parser_new`] and not be used afterwards. NULL is ignored.
+ */
+void hexar_parser_free(struct HexarParser *parser);
+
+/**
+ * Append received UART bytes, returning how many were accepted. Bytes that didn't fit should
+ * be pushed again after draining frames with [`hexar_parser_next_frame`].
+ *
+ * # Safety
+ *
+ * `parser` must be a live handle and `data` must point to `len` readable bytes.
+ */
+size_t hexar_parser_push(struct HexarParser *parser, const uint8_t *data, size_t len);
+
+/**
+ * Write the next complete frame to `out`, returning false when more bytes are needed
+ *
+ * # Safety
+ *
+ * `parser` must be a live handle and `out` must point to writable memory for a [`HexarFrame`].
+ */
+bool hexar_parser_next_frame(struct HexarParser *parser, struct HexarFrame *out);
+
+/**
+ * Drop any partially received data, e.g. after reopening the UART
+ *
+ * # Safety
+ *
+ * `parser` must be a live handle or NULL.
+ */
+void hexar_parser_reset(struct HexarParser *parser);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* HEXAR_H */
@@ -0,0 +1,279 @@
+#![cfg_attr(
+    all(feature = "panic-free", not(test)),
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable
+    )
+)]
+
+use crate::{warn, RadarLLFrame};
+
+const COMMAND_HEADER: [u8; 4] = [0xFD, 0xFC, 0xFB, 0xFA];
+const TARGET_HEADER: [u8; 4] = [0xF4, 0xF3, 0xF2, 0xF1];
+const TARGET_2D_HEADER: [u8; 4] = [0xAA, 0xFF, 0x03, 0x00];
+
+/// LD2450 reports are fixed size: header, 3 targets of 8 bytes, footer
+const TARGET_2D_FRAME_LEN: usize = 30;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AccumulatorStats {
+    pub frames_parsed: u32,
+    pub malformed_frames: u32,
+    pub bytes_discarded: u32,
+}
+
+enum HeaderMatch {
+    /// A complete header, with the total frame length if it can already be determined
+    Complete(Option<usize>),
+    /// The buffer ends in the middle of what could be a header
+    Partial,
+    NoMatch,
+}
+
+/// Reassembles radar frames from a raw serial byte stream.
+///
+/// Bytes are pushed as they arrive from the UART and complete frames are pulled out one at a
+/// time, resynchronizing on the next header when garbage or a corrupted frame shows up. The
+/// buffer is a fixed array so the accumulator works without an allocator.
+#[derive(Debug, Clone)]
+pub struct FrameAccumulator<const N: usize = 256> {
+    buffer: [u8; N],
+    len: usize,
+    stats: AccumulatorStats,
+}
+
+impl<const N: usize> Default for FrameAccumulator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> FrameAccumulator<N> {
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; N],
+            len: 0,
+            stats: AccumulatorStats {
+                frames_parsed: 0,
+                malformed_frames: 0,
+                bytes_discarded: 0,
+            },
+        }
+    }
+
+    /// Append received bytes, returning how many fit. Whatever didn't fit should be pushed again
+    /// after pulling frames out.
+    pub fn push(&mut self, bytes: &[u8]) -> usize {
+        let accepted = bytes.len().min(N - self.len);
+
+        if let (Some(dst), Some(src)) = (
+            self.buffer.get_mut(self.len..self.len + accepted),
+            bytes.get(..accepted),
+        ) {
+            dst.copy_from_slice(src);
+            self.len += accepted;
+        }
+
+        accepted
+    }
+
+    /// Extract the next complete frame, `None` when more bytes are needed
+    pub fn next_frame(&mut self) -> Option<RadarLLFrame> {
+        loop {
+            let start = self.find_header_start();
+            self.discard(start);
+
+            let frame_len = match Self::match_header(self.buffered()) {
+                HeaderMatch::Complete(Some(frame_len)) => frame_len,
+                // After discarding, NoMatch only happens on an empty buffer
+                HeaderMatch::Complete(None) | HeaderMatch::Partial | HeaderMatch::NoMatch => {
+                    return None
+                }
+            };
+
+            if frame_len > N {
+                // No frame of this protocol is that long, the length field is garbage
+                warn!("Frame length {} exceeds accumulator capacity", frame_len);
+                self.reject_frame();
+                continue;
+            }
+
+            if self.len < frame_len {
+                return None;
+            }
+
+            match RadarLLFrame::deserialize(self.buffer.get(..frame_len)?) {
+                Some(frame) => {
+                    self.consume(frame_len);
+                    self.stats.frames_parsed = self.stats.frames_parsed.wrapping_add(1);
+                    return Some(frame);
+                }
+                None => self.reject_frame(),
+            }
+        }
+    }
+
+    /// Extract at most `max_frames` frames, so a single call does a bounded amount of work
+    /// no matter how much data is buffered. Useful for cooperative schedulers and RTIC tasks.
+    pub fn parse_some(&mut self, max_frames: usize) -> ParseSome<'_, N> {
+        ParseSome {
+            accumulator: self,
+            remaining: max_frames,
+        }
+    }
+
+    pub fn buffered_len(&self) -> usize {
+        self.len
+    }
+
+    pub fn stats(&self) -> AccumulatorStats {
+        self.stats
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    fn buffered(&self) -> &[u8] {
+        self.buffer.get(..self.len).unwrap_or(&[])
+    }
+
+    fn find_header_start(&self) -> usize {
+        let buffered = self.buffered();
+
+        (0..buffered.len())
+            .find(|&i| {
+                !matches!(
+                    Self::match_header(buffered.get(i..).unwrap_or(&[])),
+                    HeaderMatch::NoMatch
+                )
+            })
+            .unwrap_or(buffered.len())
+    }
+
+    fn match_header(bytes: &[u8]) -> HeaderMatch {
+        for header in [COMMAND_HEADER, TARGET_HEADER, TARGET_2D_HEADER] {
+            let compared = bytes.len().min(header.len());
+            if bytes.get(..compared) != header.get(..compared) {
+                continue;
+            }
+
+            if compared < header.len() {
+                return HeaderMatch::Partial;
+            }
+
+            if header == TARGET_2D_HEADER {
+                return HeaderMatch::Complete(Some(TARGET_2D_FRAME_LEN));
+            }
+
+            // Length field covers the payload between itself and the 4 byte footer
+            return match bytes.get(4..6) {
+                Some(&[len_l, len_h]) => {
+                    let len = u16::from_le_bytes([len_l, len_h]) as usize;
+                    HeaderMatch::Complete(Some(len + 10))
+                }
+                _ => HeaderMatch::Complete(None),
+            };
+        }
+
+        HeaderMatch::NoMatch
+    }
+
+    /// Drop the first byte of a bad frame so the search resumes right after its header
+    fn reject_frame(&mut self) {
+        self.stats.malformed_frames = self.stats.malformed_frames.wrapping_add(1);
+        self.discard(1);
+    }
+
+    fn discard(&mut self, count: usize) {
+        let count = count.min(self.len);
+        self.stats.bytes_discarded = self.stats.bytes_discarded.wrapping_add(count as u32);
+        self.consume(count);
+    }
+
+    fn consume(&mut self, count: usize) {
+        let count = count.min(self.len);
+        // Within the buffered bytes, so the copy can't go out of bounds
+        if let Some(buffered) = self.buffer.get_mut(..self.len) {
+            buffered.copy_within(count.., 0);
+        }
+        self.len -= count;
+    }
+}
+
+/// Iterator returned by [`FrameAccumulator::parse_some`]
+pub struct ParseSome<'a, const N: usize> {
+    accumulator: &'a mut FrameAccumulator<N>,
+    remaining: usize,
+}
+
+impl<const N: usize> Iterator for ParseSome<'_, N> {
+    type Item = RadarLLFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        self.accumulator.next_frame()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TARGET_2D_FRAME: [u8; 30] = [
+        0xAA, 0xFF, 0x03, 0x00, 0x0E, 0x03, 0xB1, 0x86, 0x10, 0x00, 0x40, 0x01, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x55, 0xCC,
+    ];
+
+    #[test]
+    fn test_frame_split_across_pushes_with_leading_garbage() {
+        let mut accumulator: FrameAccumulator = FrameAccumulator::new();
+
+        accumulator.push(&[0x13, 0x37, 0xAA]);
+        accumulator.push(&TARGET_2D_FRAME[..10]);
+        assert!(accumulator.next_frame().is_none());
+
+        accumulator.push(&TARGET_2D_FRAME[10..]);
+        // Leading garbage is dropped while waiting for the rest of the frame
+        assert!(matches!(
+            accumulator.next_frame(),
+            Some(RadarLLFrame::TargetFrame2D(_))
+        ));
+        assert_eq!(accumulator.buffered_len(), 0);
+        assert_eq!(accumulator.stats().frames_parsed, 1);
+        assert!(accumulator.stats().bytes_discarded >= 2);
+    }
+
+    #[test]
+    fn test_parse_some_bounds_work_per_call() {
+        let mut accumulator: FrameAccumulator = FrameAccumulator::new();
+        for _ in 0..3 {
+            accumulator.push(&TARGET_2D_FRAME);
+        }
+        accumulator.push(&[0xFD, 0xFC, 0xFB, 0xFA, 0x02, 0x00, 0xA0, 0x00, 0x04, 0x03, 0x02, 0x01]);
+
+        assert_eq!(accumulator.parse_some(2).count(), 2);
+        assert_eq!(accumulator.parse_some(5).count(), 2);
+        assert_eq!(accumulator.parse_some(5).count(), 0);
+    }
+
+    #[test]
+    fn test_bogus_length_field_resynchronizes() {
+        let mut accumulator: FrameAccumulator<64> = FrameAccumulator::new();
+        accumulator.push(&[0xFD, 0xFC, 0xFB, 0xFA, 0xFF, 0xFF]);
+        accumulator.push(&TARGET_2D_FRAME);
+
+        assert!(matches!(
+            accumulator.next_frame(),
+            Some(RadarLLFrame::TargetFrame2D(_))
+        ));
+        assert_eq!(accumulator.stats().malformed_frames, 1);
+    }
+}
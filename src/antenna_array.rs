@@ -0,0 +1,287 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use nalgebra::{Matrix2, Vector2};
+
+use crate::fusion::SensorPose;
+
+/// Gauss-Newton iterations of [`multilaterate`]
+const MULTILATERATION_ITERATIONS: usize = 10;
+
+/// Sample points along each side of the grid [`AntennaArray::coverage`] counts
+const COVERAGE_GRID: usize = 48;
+
+/// Layout of antennas evenly spaced on a circle, each facing away from its centre, as on the
+/// hexagonal mount
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct AntennaArrayConfig {
+    /// Centre of the array in the room frame
+    pub center: Vector2<f32>,
+    /// Distance of each antenna from the centre in metres
+    pub radius_m: f32,
+    /// Mounting height above the floor in metres
+    pub height_m: f32,
+    /// Direction antenna 0 faces in radians, counterclockwise from the room's x axis. The
+    /// others follow counterclockwise.
+    pub rotation: f32,
+    /// Horizontal field of view of each antenna in radians
+    pub field_of_view: f32,
+    /// Farthest a target is detected at in metres
+    pub max_range_m: f32,
+}
+
+impl Default for AntennaArrayConfig {
+    fn default() -> Self {
+        Self {
+            center: Vector2::zeros(),
+            radius_m: 0.15,
+            height_m: 1.0,
+            rotation: 0.0,
+            // LD2450 detection cone of ±60°
+            field_of_view: 120f32.to_radians(),
+            max_range_m: 6.0,
+        }
+    }
+}
+
+/// Where each antenna of the array sits and which way it looks. Antennas report targets in
+/// their own frame with the boresight along `y`, as the LD2450 does, so a pose's heading is a
+/// quarter turn clockwise of the direction the antenna faces.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AntennaArray {
+    poses: Vec<(u8, SensorPose)>,
+    field_of_view: f32,
+    max_range: f32,
+}
+
+impl AntennaArray {
+    /// `antenna_count` antennas numbered from 0 laid out as `config` describes
+    pub fn from_config(antenna_count: u8, config: &AntennaArrayConfig) -> Self {
+        let step = TAU / f32::from(antenna_count.max(1));
+        let poses = (0..antenna_count)
+            .map(|antenna_id| {
+                let facing = config.rotation + step * f32::from(antenna_id);
+                let direction = Vector2::new(facing.cos(), facing.sin());
+                let pose = SensorPose::new(
+                    config.center + direction * config.radius_m,
+                    config.height_m,
+                    facing - FRAC_PI_2,
+                );
+                (antenna_id, pose)
+            })
+            .collect();
+        Self {
+            poses,
+            field_of_view: config.field_of_view,
+            max_range: config.max_range_m,
+        }
+    }
+
+    /// Six antennas on the hexagonal mount
+    pub fn hexagonal(config: &AntennaArrayConfig) -> Self {
+        Self::from_config(6, config)
+    }
+
+    /// Replace the pose of an antenna, adding it when the array doesn't have it yet
+    pub fn set_pose(&mut self, antenna_id: u8, pose: SensorPose) {
+        match self.poses.iter_mut().find(|(id, _)| *id == antenna_id) {
+            Some((_, existing)) => *existing = pose,
+            None => self.poses.push((antenna_id, pose)),
+        }
+    }
+
+    pub fn get_pose(&self, antenna_id: u8) -> Option<&SensorPose> {
+        self.poses
+            .iter()
+            .find(|(id, _)| *id == antenna_id)
+            .map(|(_, pose)| pose)
+    }
+
+    pub fn poses(&self) -> impl Iterator<Item = (u8, &SensorPose)> {
+        self.poses.iter().map(|(id, pose)| (*id, pose))
+    }
+
+    pub fn get_max_range(&self) -> f32 {
+        self.max_range
+    }
+
+    /// Direction an antenna faces in radians, counterclockwise from the room's x axis
+    pub fn get_facing(&self, antenna_id: u8) -> Option<f32> {
+        self.get_pose(antenna_id)
+            .map(|pose| pose.heading + FRAC_PI_2)
+    }
+
+    /// Room coordinates of a target `range` metres from an antenna at `angle` radians off its
+    /// boresight, positive towards the antenna's `x` axis
+    pub fn polar_to_world(&self, antenna_id: u8, range: f32, angle: f32) -> Option<Vector2<f32>> {
+        self.get_pose(antenna_id)
+            .map(|pose| pose.to_world(polar_to_local(range, angle)))
+    }
+
+    /// Whether a room point is within an antenna's field of view and range
+    pub fn covers(&self, antenna_id: u8, world: Vector2<f32>) -> bool {
+        self.get_pose(antenna_id).is_some_and(|pose| {
+            let (range, angle) = local_to_polar(pose.to_local(world));
+            range <= self.max_range && angle.abs() <= self.field_of_view / 2.0
+        })
+    }
+
+    /// Share of the area the whole array covers that `antennas` still cover, counted on a
+    /// grid of sample points
+    pub fn coverage(&self, antennas: &[u8]) -> f32 {
+        let Some((min, max)) = self.bounds() else {
+            return 0.0;
+        };
+        let step = (max - min) / (COVERAGE_GRID - 1) as f32;
+        let (mut total, mut covered) = (0u32, 0u32);
+        for i in 0..COVERAGE_GRID {
+            for j in 0..COVERAGE_GRID {
+                let point = min + Vector2::new(step.x * i as f32, step.y * j as f32);
+                if self.poses.iter().any(|(id, _)| self.covers(*id, point)) {
+                    total += 1;
+                    if antennas.iter().any(|id| self.covers(*id, point)) {
+                        covered += 1;
+                    }
+                }
+            }
+        }
+        if total == 0 {
+            0.0
+        } else {
+            covered as f32 / total as f32
+        }
+    }
+
+    /// Share of the smaller of two antennas' cones the other one covers too, counted on the
+    /// same grid as [`AntennaArray::coverage`]
+    pub fn overlap(&self, a: u8, b: u8) -> f32 {
+        let Some((min, max)) = self.bounds() else {
+            return 0.0;
+        };
+        let step = (max - min) / (COVERAGE_GRID - 1) as f32;
+        let (mut only_a, mut only_b, mut both) = (0u32, 0u32, 0u32);
+        for i in 0..COVERAGE_GRID {
+            for j in 0..COVERAGE_GRID {
+                let point = min + Vector2::new(step.x * i as f32, step.y * j as f32);
+                match (self.covers(a, point), self.covers(b, point)) {
+                    (true, true) => both += 1,
+                    (true, false) => only_a += 1,
+                    (false, true) => only_b += 1,
+                    (false, false) => {}
+                }
+            }
+        }
+        let smaller = (only_a + both).min(only_b + both);
+        if smaller == 0 {
+            0.0
+        } else {
+            both as f32 / smaller as f32
+        }
+    }
+
+    /// Corners of the box around every antenna's reach
+    fn bounds(&self) -> Option<(Vector2<f32>, Vector2<f32>)> {
+        let reach = Vector2::repeat(self.max_range);
+        self.poses.iter().fold(None, |bounds, (_, pose)| {
+            let (low, high) = (pose.position - reach, pose.position + reach);
+            Some(match bounds {
+                Some((min, max)) => (low.inf(&min), high.sup(&max)),
+                None => (low, high),
+            })
+        })
+    }
+}
+
+/// Antenna frame coordinates of a target at `range` and `angle` off the boresight
+pub fn polar_to_local(range: f32, angle: f32) -> Vector2<f32> {
+    Vector2::new(range * angle.sin(), range * angle.cos())
+}
+
+/// Range and angle off the boresight of a point in an antenna's frame
+pub fn local_to_polar(local: Vector2<f32>) -> (f32, f32) {
+    (local.norm(), local.x.atan2(local.y))
+}
+
+/// Position whose distances to the `(anchor, range)` pairs fit the ranges best in the least
+/// squares sense, refined from `initial`. Two anchors leave a mirror ambiguity that the initial
+/// guess decides. `None` with fewer than two anchors or when they don't constrain the position.
+pub fn multilaterate(
+    anchors: &[(Vector2<f32>, f32)],
+    initial: Vector2<f32>,
+) -> Option<Vector2<f32>> {
+    if anchors.len() < 2 {
+        return None;
+    }
+    let mut position = initial;
+    for _ in 0..MULTILATERATION_ITERATIONS {
+        let mut normal = Matrix2::zeros();
+        let mut gradient = Vector2::zeros();
+        for &(anchor, range) in anchors {
+            let offset = position - anchor;
+            let distance = offset.norm();
+            if distance < 1e-6 {
+                continue;
+            }
+            let jacobian = offset / distance;
+            normal += jacobian * jacobian.transpose();
+            gradient += jacobian * (distance - range);
+        }
+        let step = normal.try_inverse()? * gradient;
+        position -= step;
+        if step.norm() < 1e-5 {
+            break;
+        }
+    }
+    Some(position)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_hexagonal_geometry() {
+        let array = AntennaArray::hexagonal(&AntennaArrayConfig {
+            radius_m: 0.2,
+            ..Default::default()
+        });
+        // Antenna 1 faces 60° and sits 20 cm out that way
+        let pose = array.get_pose(1).unwrap();
+        assert!((pose.position - Vector2::new(0.1, 0.2 * (PI / 3.0).sin())).norm() < 1e-5);
+        assert!((array.get_facing(1).unwrap() - PI / 3.0).abs() < 1e-5);
+
+        // A target straight ahead of antenna 3, which faces the room's -x
+        let world = array.polar_to_world(3, 2.0, 0.0).unwrap();
+        assert!((world - Vector2::new(-2.2, 0.0)).norm() < 1e-5);
+        assert!(array.covers(3, world));
+        assert!(!array.covers(0, world));
+
+        assert_eq!(array.coverage(&[0, 1, 2, 3, 4, 5]), 1.0);
+        let without_3 = array.coverage(&[0, 1, 2, 4, 5]);
+        // The neighbours overlap most of a missing antenna's cone
+        assert!(without_3 > 0.9 && without_3 < 1.0);
+        assert!(array.coverage(&[0]) < 0.4);
+        assert_eq!(array.coverage(&[]), 0.0);
+
+        let (range, angle) = local_to_polar(polar_to_local(1.5, 0.3));
+        assert!((range - 1.5).abs() < 1e-5 && (angle - 0.3).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_multilateration() {
+        let target = Vector2::new(1.0, 2.0);
+        let anchors: Vec<_> = [Vector2::new(0.0, 0.0), Vector2::new(3.0, 0.0)]
+            .into_iter()
+            .map(|anchor| (anchor, (target - anchor).norm()))
+            .collect();
+
+        let position = multilaterate(&anchors, Vector2::new(1.5, 1.5)).unwrap();
+        assert!((position - target).norm() < 1e-3);
+        // The guess decides which of the two intersections is taken
+        let mirrored = multilaterate(&anchors, Vector2::new(1.5, -1.5)).unwrap();
+        assert!((mirrored - Vector2::new(1.0, -2.0)).norm() < 1e-3);
+        assert!(multilaterate(&anchors[..1], target).is_none());
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tracing::warn;
+
+use crate::config::{ApiAuthConfig, ApiToken};
+
+/// Why a client of a remote API was turned away
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthError {
+    Missing,
+    Invalid,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuthError::Missing => "API token required",
+            AuthError::Invalid => "invalid API token",
+        })
+    }
+}
+
+impl std::error::Error for AuthError {}
+
+/// Checks the tokens clients of the HTTP and gRPC APIs present against [`ApiAuthConfig`]
+#[derive(Debug, Clone, Default)]
+pub struct Authenticator {
+    tokens: Arc<[ApiToken]>,
+}
+
+impl Authenticator {
+    pub fn new(config: &ApiAuthConfig) -> Self {
+        Self {
+            tokens: config.tokens.iter().cloned().collect(),
+        }
+    }
+
+    /// Whether every client is let in, no tokens being configured
+    pub fn is_open(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Name of the token a client presented, `None` for anyone while the APIs are open
+    pub fn check(&self, token: Option<&str>) -> Result<Option<&str>, AuthError> {
+        if self.is_open() {
+            return Ok(None);
+        }
+        let token = token.ok_or(AuthError::Missing)?;
+        // Every configured token is compared so the time taken tells nothing about them
+        self.tokens
+            .iter()
+            .fold(None, |found, known| {
+                let matches = constant_time_eq(known.token.as_bytes(), token.as_bytes());
+                found.or(matches.then_some(known.name.as_str()))
+            })
+            .map(Some)
+            .ok_or(AuthError::Invalid)
+    }
+
+    /// Warn when `api` at `listen` can be reached from other hosts without a token, or
+    /// with its tokens sent in plain text
+    pub fn warn_if_exposed(&self, api: &str, listen: SocketAddr, tls: bool) {
+        if listen.ip().is_loopback() {
+            return;
+        }
+        if self.is_open() {
+            warn!(
+                "{} on {} is open to anyone on the network, configure api_auth.tokens",
+                api, listen
+            );
+        } else if !tls {
+            warn!(
+                "{} on {} takes API tokens in plain text, configure TLS",
+                api, listen
+            );
+        }
+    }
+}
+
+/// Token of an `Authorization` header value like `Bearer <token>`
+pub fn bearer_token(authorization: &str) -> Option<&str> {
+    let (scheme, token) = authorization.trim().split_once(' ')?;
+    scheme.eq_ignore_ascii_case("bearer").then(|| token.trim())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokens() {
+        assert_eq!(Authenticator::default().check(None), Ok(None));
+
+        let config = ApiAuthConfig {
+            tokens: vec![
+                ApiToken {
+                    name: "dashboard".to_string(),
+                    token: "s3cret".to_string(),
+                },
+                ApiToken {
+                    name: "home-assistant".to_string(),
+                    token: "0ther".to_string(),
+                },
+            ],
+        };
+        let auth = Authenticator::new(&config);
+        assert_eq!(auth.check(Some("0ther")), Ok(Some("home-assistant")));
+        assert_eq!(auth.check(Some("s3cre")), Err(AuthError::Invalid));
+        assert_eq!(auth.check(None), Err(AuthError::Missing));
+
+        assert_eq!(bearer_token("Bearer s3cret"), Some("s3cret"));
+        assert_eq!(bearer_token("bearer  s3cret "), Some("s3cret"));
+        assert_eq!(bearer_token("Basic czNjcmV0"), None);
+    }
+}
@@ -0,0 +1,175 @@
+/// Outcome of matching one frame of measurements to the existing tracks
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Association {
+    /// Track ID and index of the measurement assigned to it
+    pub assigned: Vec<(u32, usize)>,
+    /// Tracks no measurement inside their gate was assigned to
+    pub unassigned_tracks: Vec<u32>,
+    /// Measurements left over, candidates for new tracks
+    pub unassigned_measurements: Vec<usize>,
+}
+
+/// Global nearest-neighbour association: every track/measurement pair gets a cost from `cost`,
+/// `None` when the measurement falls outside the track's gate, and the set of pairs with the
+/// lowest total cost is chosen, so two close targets cannot both claim the same measurement
+pub fn associate<F>(track_ids: &[u32], measurement_count: usize, cost: F) -> Association
+where
+    F: Fn(u32, usize) -> Option<f32>,
+{
+    let matrix: Vec<Vec<f32>> = track_ids
+        .iter()
+        .map(|&id| {
+            (0..measurement_count)
+                .map(|measurement| cost(id, measurement).unwrap_or(f32::INFINITY))
+                .collect()
+        })
+        .collect();
+
+    let assignment = solve_assignment(&matrix);
+    let mut result = Association::default();
+    let mut taken = vec![false; measurement_count];
+
+    for (&id, column) in track_ids.iter().zip(&assignment) {
+        match column {
+            Some(measurement) => {
+                taken[*measurement] = true;
+                result.assigned.push((id, *measurement));
+            }
+            None => result.unassigned_tracks.push(id),
+        }
+    }
+    result.unassigned_measurements = (0..measurement_count).filter(|&m| !taken[m]).collect();
+    result
+}
+
+/// Minimum-cost assignment of rows to columns with the Hungarian algorithm in O(n³). The
+/// matrix may be rectangular; non-finite entries are forbidden pairs. Returns the column
+/// assigned to each row, `None` for rows left without a permitted column.
+pub fn solve_assignment(cost: &[Vec<f32>]) -> Vec<Option<usize>> {
+    let rows = cost.len();
+    let cols = cost.iter().map(Vec::len).max().unwrap_or(0);
+    let n = rows.max(cols);
+    if rows == 0 || cols == 0 {
+        return vec![None; rows];
+    }
+
+    // Forbidden pairs cost more than any assignment made only of permitted ones, so the
+    // solver uses as many permitted pairs as possible before minimising their cost
+    let largest = cost
+        .iter()
+        .flatten()
+        .filter(|c| c.is_finite())
+        .fold(0.0f64, |max, &c| max.max(f64::from(c).abs()));
+    let forbidden = (largest + 1.0) * n as f64 * 2.0;
+
+    let permitted = |i: usize, j: usize| cost[i].get(j).copied().filter(|c| c.is_finite());
+    // Padding rows and columns are free
+    let at = |i: usize, j: usize| -> f64 {
+        if i >= rows || j >= cols {
+            0.0
+        } else {
+            permitted(i, j).map_or(forbidden, f64::from)
+        }
+    };
+
+    // Potentials and matching are 1-based, column 0 is the augmenting path's virtual start
+    let mut u = vec![0.0f64; n + 1];
+    let mut v = vec![0.0f64; n + 1];
+    let mut row_of = vec![0usize; n + 1];
+    let mut way = vec![0usize; n + 1];
+
+    for i in 1..=n {
+        row_of[0] = i;
+        let mut j0 = 0;
+        let mut min_slack = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+
+        loop {
+            used[j0] = true;
+            let i0 = row_of[j0];
+            let mut delta = f64::INFINITY;
+            let mut j1 = 0;
+
+            for j in 1..=n {
+                if used[j] {
+                    continue;
+                }
+                let slack = at(i0 - 1, j - 1) - u[i0] - v[j];
+                if slack < min_slack[j] {
+                    min_slack[j] = slack;
+                    way[j] = j0;
+                }
+                if min_slack[j] < delta {
+                    delta = min_slack[j];
+                    j1 = j;
+                }
+            }
+
+            for j in 0..=n {
+                if used[j] {
+                    u[row_of[j]] += delta;
+                    v[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+
+            j0 = j1;
+            if row_of[j0] == 0 {
+                break;
+            }
+        }
+
+        // Flip the augmenting path
+        loop {
+            let j1 = way[j0];
+            row_of[j0] = row_of[j1];
+            j0 = j1;
+            if j0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut assignment = vec![None; rows];
+    for (j, &i) in row_of.iter().enumerate().take(cols + 1).skip(1) {
+        if i >= 1 && i <= rows && permitted(i - 1, j - 1).is_some() {
+            assignment[i - 1] = Some(j - 1);
+        }
+    }
+    assignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_assignment_beats_greedy() {
+        // Greedy would give row 0 column 0 (1.0) and leave row 1 with 10.0
+        let cost = vec![vec![1.0, 2.0], vec![1.5, 10.0]];
+        assert_eq!(solve_assignment(&cost), vec![Some(1), Some(0)]);
+
+        let rectangular = vec![vec![5.0, 1.0, 3.0]];
+        assert_eq!(solve_assignment(&rectangular), vec![Some(1)]);
+        assert_eq!(solve_assignment(&[]), Vec::<Option<usize>>::new());
+    }
+
+    #[test]
+    fn test_gating() {
+        let positions = [0.0f32, 10.0];
+        let measurements = [9.5f32, 0.4, 30.0];
+        let association = associate(&[7, 8], measurements.len(), |id, m| {
+            let distance = (positions[(id - 7) as usize] - measurements[m]).abs();
+            (distance < 2.0).then_some(distance)
+        });
+
+        assert_eq!(association.assigned, vec![(7, 1), (8, 0)]);
+        assert!(association.unassigned_tracks.is_empty());
+        assert_eq!(association.unassigned_measurements, vec![2]);
+
+        let far = associate(&[1], 1, |_, _| None);
+        assert_eq!(far.unassigned_tracks, vec![1]);
+        assert_eq!(far.unassigned_measurements, vec![0]);
+    }
+}
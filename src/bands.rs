@@ -0,0 +1,192 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::scanner::FrequencyRange;
+
+/// Named frequency bands, limits in MHz follow the common EU/US allocations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Band {
+    /// 433.05-434.79 MHz LPD433
+    Ism433,
+    /// 863-870 MHz SRD860 (EU LoRa, Sigfox, Z-Wave)
+    Ism868,
+    /// 902-928 MHz (US LoRa, Z-Wave)
+    Ism915,
+    /// 2400-2483.5 MHz Wi-Fi, Bluetooth and Zigbee
+    Wifi24,
+    /// 24.00-24.25 GHz short range radar, where the LD2412/LD2450 modules transmit
+    Radar24,
+}
+
+/// One channel of a band plan
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Channel {
+    pub number: u32,
+    /// Center frequency in MHz
+    pub center: f32,
+    /// Occupied bandwidth in MHz
+    pub bandwidth: f32,
+}
+
+impl Channel {
+    pub fn contains(&self, frequency: f32) -> bool {
+        (frequency - self.center).abs() <= self.bandwidth / 2.0
+    }
+}
+
+impl Band {
+    pub const ALL: [Band; 5] = [
+        Band::Ism433,
+        Band::Ism868,
+        Band::Ism915,
+        Band::Wifi24,
+        Band::Radar24,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Band::Ism433 => "ism433",
+            Band::Ism868 => "ism868",
+            Band::Ism915 => "ism915",
+            Band::Wifi24 => "wifi24",
+            Band::Radar24 => "radar24",
+        }
+    }
+
+    /// Band edges in MHz
+    pub fn limits(self) -> (f32, f32) {
+        match self {
+            Band::Ism433 => (433.05, 434.79),
+            Band::Ism868 => (863.0, 870.0),
+            Band::Ism915 => (902.0, 928.0),
+            Band::Wifi24 => (2400.0, 2483.5),
+            Band::Radar24 => (24000.0, 24250.0),
+        }
+    }
+
+    /// Scan step in MHz fine enough for the narrowest signals common in the band
+    pub fn default_step(self) -> f32 {
+        match self {
+            Band::Ism433 => 0.025,
+            Band::Ism868 => 0.1,
+            Band::Ism915 => 0.2,
+            Band::Wifi24 => 1.0,
+            Band::Radar24 => 5.0,
+        }
+    }
+
+    /// Channel plan of the band
+    pub fn channels(self) -> Vec<Channel> {
+        match self {
+            // 25 kHz LPD433 channels 1-69 starting at 433.075 MHz
+            Band::Ism433 => channelize(1, 433.075, 0.025, 0.025, 69),
+            // 200 kHz channels as used by LoRaWAN EU868 and Z-Wave
+            Band::Ism868 => channelize(1, 863.1, 0.2, 0.2, 35),
+            // LoRaWAN US915 uplink channels 0-63
+            Band::Ism915 => channelize(0, 902.3, 0.2, 0.125, 64),
+            // Wi-Fi channels 1-13, 5 MHz apart and 20 MHz wide
+            Band::Wifi24 => channelize(1, 2412.0, 5.0, 20.0, 13),
+            // 25 MHz slots across the band
+            Band::Radar24 => channelize(1, 24012.5, 25.0, 25.0, 10),
+        }
+    }
+
+    /// Channel whose center is closest to `frequency`, if it falls inside one
+    pub fn channel_for(self, frequency: f32) -> Option<Channel> {
+        self.channels()
+            .into_iter()
+            .filter(|channel| channel.contains(frequency))
+            .min_by(|a, b| {
+                (a.center - frequency)
+                    .abs()
+                    .total_cmp(&(b.center - frequency).abs())
+            })
+    }
+
+    /// Band containing `frequency`
+    pub fn containing(frequency: f32) -> Option<Band> {
+        Band::ALL.into_iter().find(|band| {
+            let (start, end) = band.limits();
+            (start..=end).contains(&frequency)
+        })
+    }
+}
+
+/// `count` evenly spaced channels, frequencies in MHz
+pub fn channelize(
+    first_number: u32,
+    first_center: f32,
+    spacing: f32,
+    bandwidth: f32,
+    count: u32,
+) -> Vec<Channel> {
+    (0..count)
+        .map(|i| Channel {
+            number: first_number + i,
+            center: first_center + i as f32 * spacing,
+            bandwidth,
+        })
+        .collect()
+}
+
+impl fmt::Display for Band {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Band {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Band::ALL
+            .into_iter()
+            .find(|band| band.name().eq_ignore_ascii_case(s))
+            .ok_or_else(|| {
+                let names: Vec<_> = Band::ALL.iter().map(|band| band.name()).collect();
+                format!("unknown band '{}', expected one of {}", s, names.join(", "))
+            })
+    }
+}
+
+impl FrequencyRange {
+    /// The whole of `band` at its default step
+    pub fn preset(band: Band) -> Self {
+        let (start, end) = band.limits();
+        Self {
+            start,
+            end,
+            step: band.default_step(),
+        }
+    }
+
+    /// Scan range covering one channel, stepped at a tenth of its bandwidth
+    pub fn channel(channel: &Channel) -> Self {
+        Self {
+            start: channel.center - channel.bandwidth / 2.0,
+            end: channel.center + channel.bandwidth / 2.0,
+            step: channel.bandwidth / 10.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_and_channels() {
+        let range = FrequencyRange::preset("ISM868".parse().unwrap());
+        assert_eq!((range.start, range.end, range.step), (863.0, 870.0, 0.1));
+
+        let wifi = Band::Wifi24.channels();
+        assert_eq!(wifi.len(), 13);
+        assert_eq!(wifi[5].number, 6);
+        assert_eq!(wifi[5].center, 2437.0);
+        assert_eq!(Band::Wifi24.channel_for(2438.0).map(|c| c.number), Some(6));
+
+        assert_eq!(Band::Ism915.channels()[0].number, 0);
+        assert_eq!(Band::containing(24125.0), Some(Band::Radar24));
+        assert!("vhf".parse::<Band>().is_err());
+    }
+}
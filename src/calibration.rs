@@ -0,0 +1,91 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+/// Frequency dependent dB corrections for a receive frontend, added to every reading so that
+/// levels are comparable across a band despite a non-flat response. Corrections are linearly
+/// interpolated between points and held constant beyond the first and last one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CalibrationTable {
+    /// (frequency MHz, correction dB) sorted by frequency
+    points: Vec<(f32, f32)>,
+}
+
+impl CalibrationTable {
+    pub fn new(points: impl IntoIterator<Item = (f32, f32)>) -> Self {
+        let mut points: Vec<_> = points.into_iter().collect();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { points }
+    }
+
+    /// One `frequency_mhz,correction_db` pair per line, `#` starts a comment and a
+    /// non-numeric first line is taken as a header
+    pub fn from_csv(reader: impl BufRead) -> io::Result<Self> {
+        let mut points = Vec::new();
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let mut fields = line.split(',').map(|field| field.trim().parse::<f32>());
+            match (fields.next(), fields.next()) {
+                (Some(Ok(frequency)), Some(Ok(correction))) => points.push((frequency, correction)),
+                _ if number == 0 => continue,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("malformed calibration line {}: {}", number + 1, line),
+                    ))
+                }
+            }
+        }
+
+        Ok(Self::new(points))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_csv(BufReader::new(File::open(path)?))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Correction in dB at `frequency` in MHz, 0 for an empty table
+    pub fn correction(&self, frequency: f32) -> f32 {
+        let above = self.points.partition_point(|&(f, _)| f < frequency);
+
+        match (above.checked_sub(1).and_then(|i| self.points.get(i)), self.points.get(above)) {
+            (Some(&(f0, c0)), Some(&(f1, c1))) => c0 + (c1 - c0) * (frequency - f0) / (f1 - f0),
+            (Some(&(_, c)), None) | (None, Some(&(_, c))) => c,
+            (None, None) => 0.0,
+        }
+    }
+
+    /// Calibrated power of a raw reading
+    pub fn apply(&self, frequency: f32, strength: f32) -> f32 {
+        strength + self.correction(frequency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolated_corrections() {
+        let csv = "frequency_mhz,correction_db\n# LNA roll-off\n900,1.5\n800, 0.5\n1000,4.0\n";
+        let table = CalibrationTable::from_csv(csv.as_bytes()).unwrap();
+
+        assert_eq!(table.correction(700.0), 0.5);
+        assert_eq!(table.correction(850.0), 1.0);
+        assert_eq!(table.correction(900.0), 1.5);
+        assert_eq!(table.correction(1200.0), 4.0);
+        assert_eq!(table.apply(950.0, -60.0), -57.25);
+        assert_eq!(CalibrationTable::default().correction(900.0), 0.0);
+
+        assert!(CalibrationTable::from_csv("800,0.5\n900,x\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,279 @@
+use std::collections::VecDeque;
+
+use crate::peaks::Peak;
+
+/// Sweeps remembered per classifier
+const HISTORY_SWEEPS: usize = 32;
+/// Sweeps that must have covered a frequency before it is classified
+const MIN_SWEEPS: usize = 4;
+/// Fraction of sweeps a signal must be seen in to count as continuous
+const CONTINUOUS_PRESENCE: f32 = 0.9;
+/// Peaks closer than this in MHz, or a quarter of their bandwidth, belong to the same signal
+const MATCH_TOLERANCE_MHZ: f32 = 0.5;
+/// Strength difference in dB within which intermittent signals may be one hopping emitter
+const HOP_STRENGTH_TOLERANCE_DB: f32 = 10.0;
+/// Distinct frequencies an intermittent emitter must use to count as hopping
+const HOP_MIN_CHANNELS: usize = 3;
+
+/// Coarse type of a detected emitter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum SignalClass {
+    /// Not seen in enough sweeps yet, or no pattern fits
+    #[default]
+    Unknown,
+    /// Continuous carrier narrower than the wideband limit
+    NarrowbandCw,
+    /// Continuous signal at least as wide as the wideband limit
+    Wideband,
+    /// Intermittent on several frequencies whose activity alternates
+    FrequencyHopping,
+    /// Repeatedly switching on and off on one frequency
+    Pulsed,
+}
+
+/// One frequency the classifier has seen a peak at
+#[derive(Debug, Clone)]
+struct Track {
+    id: u64,
+    frequency: f32,
+    bandwidth: f32,
+    strength: f32,
+    last_sweep: u64,
+}
+
+/// Which tracks a sweep over `start..=end` detected
+#[derive(Debug, Clone)]
+struct SweepRecord {
+    id: u64,
+    start: f32,
+    end: f32,
+    present: Vec<u64>,
+}
+
+impl SweepRecord {
+    fn covers(&self, frequency: f32) -> bool {
+        (self.start..=self.end).contains(&frequency)
+    }
+}
+
+/// Tags peaks with a [`SignalClass`] from their bandwidth and from how they come and go over
+/// the last sweeps. Continuous signals split into narrowband and wideband by bandwidth,
+/// intermittent ones are hopping when other intermittent signals of similar strength nearby
+/// fill the gaps, and pulsed when they keep reappearing on their own frequency.
+#[derive(Debug, Clone)]
+pub struct SignalClassifier {
+    /// Bandwidth in MHz from which a continuous signal counts as wideband
+    wideband_mhz: f32,
+    /// Span in MHz searched for the other channels of a hopping emitter
+    hop_span_mhz: f32,
+    tracks: Vec<Track>,
+    sweeps: VecDeque<SweepRecord>,
+    next_sweep: u64,
+    next_track: u64,
+}
+
+impl Default for SignalClassifier {
+    /// 1 MHz wideband limit, hopping searched ±40 MHz like Bluetooth across the 2.4 GHz band
+    fn default() -> Self {
+        Self::new(1.0, 40.0)
+    }
+}
+
+impl SignalClassifier {
+    pub fn new(wideband_mhz: f32, hop_span_mhz: f32) -> Self {
+        Self {
+            wideband_mhz,
+            hop_span_mhz,
+            tracks: Vec::new(),
+            sweeps: VecDeque::with_capacity(HISTORY_SWEEPS),
+            next_sweep: 0,
+            next_track: 0,
+        }
+    }
+
+    /// Record the peaks found by a sweep from `start` to `end` MHz. Known signals inside the
+    /// range that are missing from `peaks` count as off in this sweep.
+    pub fn observe_sweep(&mut self, start: f32, end: f32, peaks: &[Peak]) {
+        let sweep = self.next_sweep;
+        self.next_sweep += 1;
+
+        let mut present = Vec::with_capacity(peaks.len());
+        for peak in peaks {
+            let id = match self.match_track(peak.center_freq) {
+                Some(index) => {
+                    let track = &mut self.tracks[index];
+                    track.bandwidth = 0.8 * track.bandwidth + 0.2 * peak.bandwidth_estimate;
+                    track.strength = 0.8 * track.strength + 0.2 * peak.peak_db;
+                    track.last_sweep = sweep;
+                    track.id
+                }
+                None => {
+                    let id = self.next_track;
+                    self.next_track += 1;
+                    self.tracks.push(Track {
+                        id,
+                        frequency: peak.center_freq,
+                        bandwidth: peak.bandwidth_estimate,
+                        strength: peak.peak_db,
+                        last_sweep: sweep,
+                    });
+                    id
+                }
+            };
+            present.push(id);
+        }
+
+        if self.sweeps.len() == HISTORY_SWEEPS {
+            self.sweeps.pop_front();
+        }
+        self.sweeps.push_back(SweepRecord {
+            id: sweep,
+            start: start.min(end),
+            end: start.max(end),
+            present,
+        });
+
+        // Forget signals not seen in any remembered sweep
+        if let Some(oldest) = self.sweeps.front().map(|s| s.id) {
+            self.tracks.retain(|track| track.last_sweep >= oldest);
+        }
+    }
+
+    /// Class of the signal at `frequency` in MHz, [`SignalClass::Unknown`] if none was seen there
+    pub fn classify(&self, frequency: f32) -> SignalClass {
+        match self.match_track(frequency) {
+            Some(index) => self.classify_track(&self.tracks[index]),
+            None => SignalClass::Unknown,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+        self.sweeps.clear();
+    }
+
+    fn match_track(&self, frequency: f32) -> Option<usize> {
+        self.tracks
+            .iter()
+            .enumerate()
+            .map(|(index, track)| (index, (track.frequency - frequency).abs()))
+            .filter(|&(index, distance)| {
+                distance <= MATCH_TOLERANCE_MHZ.max(self.tracks[index].bandwidth / 4.0)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(index, _)| index)
+    }
+
+    /// Presence of `track` in each remembered sweep covering its frequency, oldest first
+    fn presence(&self, track: &Track) -> Vec<bool> {
+        self.sweeps
+            .iter()
+            .filter(|sweep| sweep.covers(track.frequency))
+            .map(|sweep| sweep.present.contains(&track.id))
+            .collect()
+    }
+
+    fn classify_track(&self, track: &Track) -> SignalClass {
+        let presence = self.presence(track);
+        if presence.len() < MIN_SWEEPS {
+            return SignalClass::Unknown;
+        }
+
+        if duty_cycle(&presence) >= CONTINUOUS_PRESENCE {
+            return if track.bandwidth >= self.wideband_mhz {
+                SignalClass::Wideband
+            } else {
+                SignalClass::NarrowbandCw
+            };
+        }
+
+        // Intermittent signals of similar strength nearby, possibly the same hopping emitter
+        let channels: Vec<&Track> = self
+            .tracks
+            .iter()
+            .filter(|other| {
+                (other.frequency - track.frequency).abs() <= self.hop_span_mhz
+                    && (other.strength - track.strength).abs() <= HOP_STRENGTH_TOLERANCE_DB
+                    && duty_cycle(&self.presence(other)) < CONTINUOUS_PRESENCE
+            })
+            .collect();
+
+        if channels.len() >= HOP_MIN_CHANNELS {
+            // A hopping emitter is on one of its channels in most sweeps, while unrelated
+            // bursts leave sweeps where none of them is active
+            let sweeps: Vec<&SweepRecord> = self
+                .sweeps
+                .iter()
+                .filter(|sweep| sweep.covers(track.frequency))
+                .collect();
+            let active = sweeps
+                .iter()
+                .filter(|sweep| channels.iter().any(|c| sweep.present.contains(&c.id)))
+                .count();
+            if active as f32 >= CONTINUOUS_PRESENCE * sweeps.len() as f32 {
+                return SignalClass::FrequencyHopping;
+            }
+        }
+
+        // Seen in at least two separate bursts
+        let bursts = presence
+            .iter()
+            .zip(std::iter::once(&false).chain(&presence))
+            .filter(|(now, before)| **now && !**before)
+            .count();
+        if bursts >= 2 {
+            SignalClass::Pulsed
+        } else {
+            SignalClass::Unknown
+        }
+    }
+}
+
+fn duty_cycle(presence: &[bool]) -> f32 {
+    if presence.is_empty() {
+        return 0.0;
+    }
+    presence.iter().filter(|&&on| on).count() as f32 / presence.len() as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peak(center_freq: f32, bandwidth_estimate: f32) -> Peak {
+        Peak {
+            center_freq,
+            peak_db: -40.0,
+            bandwidth_estimate,
+            prominence: 20.0,
+        }
+    }
+
+    #[test]
+    fn test_classes() {
+        let mut classifier = SignalClassifier::default();
+        let hops = [2402.0, 2426.0, 2410.0, 2418.0];
+
+        for sweep in 0..8 {
+            let mut peaks = vec![peak(433.92, 0.02), peak(915.0, 5.0)];
+            // 868 MHz on for two sweeps, off for two
+            if sweep % 4 < 2 {
+                peaks.push(peak(868.3, 0.1));
+            }
+            peaks.push(peak(hops[sweep % hops.len()], 1.0));
+            classifier.observe_sweep(400.0, 2500.0, &peaks);
+
+            if sweep == 2 {
+                assert_eq!(classifier.classify(433.92), SignalClass::Unknown);
+            }
+        }
+
+        assert_eq!(classifier.classify(433.92), SignalClass::NarrowbandCw);
+        assert_eq!(classifier.classify(914.0), SignalClass::Wideband);
+        assert_eq!(classifier.classify(868.3), SignalClass::Pulsed);
+        assert_eq!(classifier.classify(2410.0), SignalClass::FrequencyHopping);
+        assert_eq!(classifier.classify(1200.0), SignalClass::Unknown);
+    }
+}
@@ -0,0 +1,86 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Source of the current time for tracking. The tracker takes all its timestamps from one, so
+/// replayed recordings and tests can drive it with a [`SimulatedClock`].
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// Wall clock, `Instant::now()`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock that only moves when told to. Clones share the same time, so a test can keep one and
+/// hand another to the tracker.
+#[derive(Debug, Clone)]
+pub struct SimulatedClock {
+    start: Instant,
+    /// Nanoseconds since `start`
+    elapsed: Arc<AtomicU64>,
+}
+
+impl Default for SimulatedClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulatedClock {
+    /// Clock stopped at the current wall clock time
+    pub fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    pub fn starting_at(start: Instant) -> Self {
+        Self {
+            start,
+            elapsed: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+        self.elapsed.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    /// Jump to `now`, which can't be before the start
+    pub fn set(&self, now: Instant) {
+        let nanos = now.saturating_duration_since(self.start).as_nanos();
+        self.elapsed
+            .store(u64::try_from(nanos).unwrap_or(u64::MAX), Ordering::Relaxed);
+    }
+}
+
+impl Clock for SimulatedClock {
+    fn now(&self) -> Instant {
+        self.start + Duration::from_nanos(self.elapsed.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulated_clock() {
+        let clock = SimulatedClock::new();
+        let start = clock.now();
+        let shared = clock.clone();
+        shared.advance(Duration::from_millis(250));
+        assert_eq!(clock.now() - start, Duration::from_millis(250));
+
+        clock.set(start + Duration::from_secs(3));
+        assert_eq!(shared.now() - start, Duration::from_secs(3));
+        clock.set(start - Duration::from_secs(1));
+        assert_eq!(clock.now(), start);
+    }
+}
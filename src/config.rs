@@ -1,252 +1,934 @@
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use anyhow::Result;
-use uuid::Uuid;
-use tracing::info;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct HexarConfig {
-    pub system_id: Uuid,
-    pub radar: RadarConfig,
-    pub safety: SafetyConfig,
-    pub monitoring: MonitoringConfig,
-    pub logging: LoggingConfig,
-}
-
-impl HexarConfig {
-    pub async fn load(path: Option<&std::path::Path>) -> Result<Self> {
-        let config_path = path.unwrap_or_else(|| std::path::Path::new("config.toml"));
-        
-        if config_path.exists() {
-            let content = tokio::fs::read_to_string(config_path).await?;
-            let config: HexarConfig = toml::from_str(&content)?;
-            Ok(config)
-        } else {
-            info!("No configuration file found, using defaults");
-            Ok(HexarConfig::default())
-        }
-    }
-    
-    pub async fn save(&self, path: Option<&std::path::Path>) -> Result<()> {
-        let config_path = path.unwrap_or_else(|| std::path::Path::new("config.toml"));
-        
-        let content = toml::to_string_pretty(self)?;
-        tokio::fs::write(config_path, content).await?;
-        
-        Ok(())
-    }
-}
-
-impl Default for HexarConfig {
-    fn default() -> Self {
-        Self {
-            system_id: Uuid::new_v4(),
-            radar: RadarConfig::default(),
-            safety: SafetyConfig::default(),
-            monitoring: MonitoringConfig::default(),
-            logging: LoggingConfig::default(),
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RadarConfig {
-    pub antenna_count: u8,
-    pub default_frequency: f32,
-    pub frequency_range: FrequencyRange,
-    pub scan_mode: ScanMode,
-    pub power_settings: PowerSettings,
-    pub signal_processing: SignalProcessingConfig,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct FrequencyRange {
-    pub start_mhz: f32,
-    pub end_mhz: f32,
-    pub step_mhz: f32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum ScanMode {
-    Continuous,
-    Intermittent,
-    OnDemand,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PowerSettings {
-    pub transmit_power_watts: f32,
-    pub duty_cycle: f32,
-    pub power_saving: bool,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SignalProcessingConfig {
-    pub threshold_db: f32,
-    pub filter_strength: f32,
-    pub noise_reduction: bool,
-    pub target_tracking: bool,
-}
-
-impl Default for RadarConfig {
-    fn default() -> Self {
-        Self {
-            antenna_count: 6,
-            default_frequency: 24000.0, // 24 GHz
-            frequency_range: FrequencyRange {
-                start_mhz: 24000.0,
-                end_mhz: 24500.0,
-                step_mhz: 1.0,
-            },
-            scan_mode: ScanMode::Continuous,
-            power_settings: PowerSettings {
-                transmit_power_watts: 10.0,
-                duty_cycle: 0.8,
-                power_saving: false,
-            },
-            signal_processing: SignalProcessingConfig {
-                threshold_db: -60.0,
-                filter_strength: 0.7,
-                noise_reduction: true,
-                target_tracking: true,
-            },
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SafetyConfig {
-    pub emergency_stop_enabled: bool,
-    pub temperature_limits: TemperatureLimits,
-    pub power_limits: PowerLimits,
-    pub radiation_limits: RadiationLimits,
-    pub auto_shutdown: AutoShutdownConfig,
-    pub maintenance_schedule: MaintenanceSchedule,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TemperatureLimits {
-    pub warning_celsius: f32,
-    pub critical_celsius: f32,
-    pub shutdown_celsius: f32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PowerLimits {
-    pub max_power_watts: f32,
-    pub surge_protection: bool,
-    pub voltage_tolerance: f32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RadiationLimits {
-    pub max_exposure_time_minutes: u32,
-    pub power_density_limit: f32,
-    pub distance_requirement_meters: f32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AutoShutdownConfig {
-    pub enabled: bool,
-    pub idle_timeout_minutes: u32,
-    pub error_threshold: u32,
-    pub performance_degradation_threshold: f32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MaintenanceSchedule {
-    pub inspection_interval_hours: u32,
-    pub calibration_interval_hours: u32,
-    pub cleaning_interval_hours: u32,
-    pub last_maintenance: chrono::DateTime<chrono::Utc>,
-}
-
-impl Default for SafetyConfig {
-    fn default() -> Self {
-        Self {
-            emergency_stop_enabled: true,
-            temperature_limits: TemperatureLimits {
-                warning_celsius: 70.0,
-                critical_celsius: 85.0,
-                shutdown_celsius: 95.0,
-            },
-            power_limits: PowerLimits {
-                max_power_watts: 100.0,
-                surge_protection: true,
-                voltage_tolerance: 0.1,
-            },
-            radiation_limits: RadiationLimits {
-                max_exposure_time_minutes: 60,
-                power_density_limit: 10.0,
-                distance_requirement_meters: 3.0,
-            },
-            auto_shutdown: AutoShutdownConfig {
-                enabled: true,
-                idle_timeout_minutes: 30,
-                error_threshold: 10,
-                performance_degradation_threshold: 0.8,
-            },
-            maintenance_schedule: MaintenanceSchedule {
-                inspection_interval_hours: 168, // 1 week
-                calibration_interval_hours: 720, // 1 month
-                cleaning_interval_hours: 336, // 2 weeks
-                last_maintenance: chrono::Utc::now(),
-            },
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct MonitoringConfig {
-    pub metrics_collection: bool,
-    pub performance_tracking: bool,
-    pub alert_system: bool,
-    pub data_retention_days: u32,
-    pub export_interval_minutes: u32,
-    pub health_check_interval_seconds: u32,
-}
-
-impl Default for MonitoringConfig {
-    fn default() -> Self {
-        Self {
-            metrics_collection: true,
-            performance_tracking: true,
-            alert_system: true,
-            data_retention_days: 30,
-            export_interval_minutes: 15,
-            health_check_interval_seconds: 30,
-        }
-    }
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct LoggingConfig {
-    pub level: String,
-    pub file_logging: bool,
-    pub console_logging: bool,
-    pub log_directory: PathBuf,
-    pub max_file_size_mb: u32,
-    pub max_files: u32,
-    pub rotation: LogRotation,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum LogRotation {
-    Daily,
-    Weekly,
-    Size,
-}
-
-impl Default for LoggingConfig {
-    fn default() -> Self {
-        Self {
-            level: "info".to_string(),
-            file_logging: true,
-            console_logging: true,
-            log_directory: PathBuf::from("logs"),
-            max_file_size_mb: 100,
-            max_files: 10,
-            rotation: LogRotation::Daily,
-        }
-    }
-}
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use anyhow::Result;
+use uuid::Uuid;
+use tracing::info;
+use crate::scanner::Detector;
+use crate::signal_source::Emitter;
+use crate::antenna_array::AntennaArrayConfig;
+use crate::control::Zone;
+use crate::fall_alert::FallAlertConfig;
+use crate::fusion::FusionConfig;
+use crate::pipeline::OverflowPolicy;
+use crate::interference::InterferenceConfig;
+use crate::serial_radar::SensorPortConfig;
+use crate::tracker::TrackerConfig;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HexarConfig {
+    pub system_id: Uuid,
+    pub radar: RadarConfig,
+    pub safety: SafetyConfig,
+    pub monitoring: MonitoringConfig,
+    pub logging: LoggingConfig,
+    /// PID file and timeouts of `start --daemon` and `stop`
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+    /// Tokens clients of the HTTP and gRPC APIs must present
+    #[serde(default)]
+    pub api_auth: ApiAuthConfig,
+    /// Serve the running instance's state over HTTP, needs the `http-api` feature
+    #[serde(default)]
+    pub http_api: Option<HttpApiConfig>,
+    /// Serve remote control over gRPC, needs the `grpc` feature
+    #[serde(default)]
+    pub grpc: Option<GrpcConfig>,
+    /// Publish presence, zones and falls to an MQTT broker, needs the `mqtt` feature
+    #[serde(default)]
+    pub mqtt: Option<MqttConfig>,
+    /// Push metrics to a Prometheus push gateway, for instances Prometheus can't scrape.
+    /// Scraping works through the HTTP API's `/metrics`.
+    #[serde(default)]
+    pub pushgateway: Option<PushGatewayConfig>,
+}
+
+impl HexarConfig {
+    pub async fn load(path: Option<&std::path::Path>) -> Result<Self> {
+        let config_path = path.unwrap_or_else(|| std::path::Path::new("config.toml"));
+        
+        if config_path.exists() {
+            let content = tokio::fs::read_to_string(config_path).await?;
+            let config: HexarConfig = toml::from_str(&content)?;
+            Ok(config)
+        } else {
+            info!("No configuration file found, using defaults");
+            Ok(HexarConfig::default())
+        }
+    }
+    
+    pub async fn save(&self, path: Option<&std::path::Path>) -> Result<()> {
+        let config_path = path.unwrap_or_else(|| std::path::Path::new("config.toml"));
+        
+        let content = toml::to_string_pretty(self)?;
+        if let Some(directory) = config_path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+            tokio::fs::create_dir_all(directory).await?;
+        }
+        tokio::fs::write(config_path, content).await?;
+        
+        Ok(())
+    }
+    
+    /// Configuration file of the named instance `profile`, `profiles/<profile>.toml`
+    pub fn profile_path(profile: &str) -> PathBuf {
+        PathBuf::from("profiles").join(format!("{}.toml", profile))
+    }
+    
+    /// Keep the named instance `profile` apart from others on the same host: the PID file,
+    /// control socket and log directory left at their defaults get the profile's name, e.g.
+    /// `hexar-roomA.pid`, `hexar-roomA.sock` and `logs/roomA`
+    pub fn apply_profile(&mut self, profile: &str) {
+        let defaults = DaemonConfig::default();
+        if self.daemon.pid_file == defaults.pid_file {
+            self.daemon.pid_file = PathBuf::from(format!("hexar-{}.pid", profile));
+        }
+        if self.daemon.control_socket == defaults.control_socket {
+            self.daemon.control_socket = PathBuf::from(format!("hexar-{}.sock", profile));
+        }
+        if self.logging.log_directory == LoggingConfig::default().log_directory {
+            self.logging.log_directory = self.logging.log_directory.join(profile);
+        }
+    }
+    
+    /// Value of the setting at a dotted path like `radar.signal_processing.threshold_db`,
+    /// `None` when there is no such setting
+    pub fn get_setting(&self, key: &str) -> Option<serde_json::Value> {
+        let tree = serde_json::to_value(self).ok()?;
+        key.split('.').try_fold(tree, |node, segment| match node {
+            serde_json::Value::Object(mut map) => map.remove(segment),
+            _ => None,
+        })
+    }
+    
+    /// The configuration with its secrets masked, for handing out over the control socket and
+    /// the remote APIs: the API tokens, the MQTT credentials and where the TLS keys are
+    pub fn redacted(&self) -> Self {
+        const MASK: &str = "********";
+        let mut config = self.clone();
+        for token in &mut config.api_auth.tokens {
+            token.token = MASK.to_string();
+        }
+        if let Some(mqtt) = &mut config.mqtt {
+            for credential in [&mut mqtt.username, &mut mqtt.password].into_iter().flatten() {
+                *credential = MASK.to_string();
+            }
+        }
+        let http_tls = config.http_api.as_mut().and_then(|api| api.tls.as_mut());
+        let grpc_tls = config.grpc.as_mut().and_then(|api| api.tls.as_mut());
+        for tls in [http_tls, grpc_tls].into_iter().flatten() {
+            tls.key = PathBuf::from(MASK);
+        }
+        config
+    }
+    
+    /// The configuration with the setting at the dotted `key` replaced by `value`, refused
+    /// when there is no such setting or the value doesn't fit it
+    pub fn with_setting(&self, key: &str, value: serde_json::Value) -> Result<Self> {
+        let mut tree = serde_json::to_value(self)?;
+        let mut node = &mut tree;
+        for segment in key.split('.') {
+            // Sections left out, like `grpc`, are filled in from the value
+            if node.is_null() {
+                *node = serde_json::Value::Object(Default::default());
+            }
+            node = match node {
+                serde_json::Value::Object(map) => map.entry(segment).or_insert(serde_json::Value::Null),
+                _ => anyhow::bail!("{} isn't a setting, {} has no fields", key, segment),
+            };
+        }
+        *node = value;
+        let config: HexarConfig = serde_json::from_value(tree)
+            .map_err(|e| anyhow::anyhow!("Invalid value for {}: {}", key, e))?;
+        // Fields serde doesn't know are dropped rather than refused
+        if config.get_setting(key).is_none() {
+            anyhow::bail!("Unknown setting {}", key);
+        }
+        Ok(config)
+    }
+    
+    /// Write `value`, checked with [`with_setting`](Self::with_setting), at the dotted `key`
+    /// into the configuration file, keeping the rest of it as it is. Without a file, the whole
+    /// configuration is saved.
+    pub async fn save_setting(&self, path: Option<&std::path::Path>, key: &str, value: &serde_json::Value) -> Result<()> {
+        let config_path = path.unwrap_or_else(|| std::path::Path::new("config.toml"));
+        
+        if !config_path.exists() {
+            return self.save(Some(config_path)).await;
+        }
+        let content = tokio::fs::read_to_string(config_path).await?;
+        let content = set_toml_setting(&content, key, value)?;
+        tokio::fs::write(config_path, content).await?;
+        
+        Ok(())
+    }
+}
+
+/// `document` with the value at the dotted `key` replaced, its comments and layout kept. A
+/// null value removes the key.
+fn set_toml_setting(document: &str, key: &str, value: &serde_json::Value) -> Result<String> {
+    let mut document: toml_edit::DocumentMut = document.parse()?;
+    let (parents, leaf) = match key.rsplit_once('.') {
+        Some((parents, leaf)) => (Some(parents), leaf),
+        None => (None, key),
+    };
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for segment in parents.into_iter().flat_map(|parents| parents.split('.')) {
+        let mut section = toml_edit::Table::new();
+        section.set_implicit(true);
+        table = table.entry(segment)
+            .or_insert(toml_edit::Item::Table(section))
+            .as_table_like_mut()
+            .ok_or_else(|| anyhow::anyhow!("{} in the configuration file isn't a table", segment))?;
+    }
+    match toml_item(value) {
+        // Replaced in place to keep the comments above the key
+        Some(item) => match table.get_mut(leaf) {
+            Some(existing) => *existing = item,
+            None => {
+                table.insert(leaf, item);
+            },
+        },
+        None => {
+            table.remove(leaf);
+        },
+    }
+    Ok(document.to_string())
+}
+
+/// `value` as TOML, objects as tables, `None` for null which TOML can't hold
+fn toml_item(value: &serde_json::Value) -> Option<toml_edit::Item> {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut table = toml_edit::Table::new();
+            for (key, value) in map {
+                if let Some(item) = toml_item(value) {
+                    table.insert(key, item);
+                }
+            }
+            Some(toml_edit::Item::Table(table))
+        },
+        value => toml_value(value).map(toml_edit::Item::Value),
+    }
+}
+
+fn toml_value(value: &serde_json::Value) -> Option<toml_edit::Value> {
+    Some(match value {
+        serde_json::Value::Null => return None,
+        serde_json::Value::Bool(flag) => (*flag).into(),
+        serde_json::Value::Number(number) => match number.as_i64() {
+            Some(integer) => integer.into(),
+            None => number.as_f64()?.into(),
+        },
+        serde_json::Value::String(text) => text.as_str().into(),
+        serde_json::Value::Array(items) => items.iter().filter_map(toml_value).collect::<toml_edit::Array>().into(),
+        serde_json::Value::Object(map) => map.iter()
+            .filter_map(|(key, value)| Some((key, toml_value(value)?)))
+            .collect::<toml_edit::InlineTable>()
+            .into(),
+    })
+}
+
+impl Default for HexarConfig {
+    fn default() -> Self {
+        Self {
+            system_id: Uuid::new_v4(),
+            radar: RadarConfig::default(),
+            safety: SafetyConfig::default(),
+            monitoring: MonitoringConfig::default(),
+            logging: LoggingConfig::default(),
+            daemon: DaemonConfig::default(),
+            api_auth: ApiAuthConfig::default(),
+            http_api: None,
+            grpc: None,
+            mqtt: None,
+            pushgateway: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RadarConfig {
+    pub antenna_count: u8,
+    pub default_frequency: f32,
+    pub frequency_range: FrequencyRange,
+    pub scan_mode: ScanMode,
+    pub power_settings: PowerSettings,
+    pub signal_processing: SignalProcessingConfig,
+    #[serde(default)]
+    pub signal_source: SignalSourceConfig,
+    /// Stream every scanner reading to rotating CSV files
+    #[serde(default)]
+    pub recording: Option<RecordingConfig>,
+    /// Periods continuous scanning is allowed in, always when unset
+    #[serde(default)]
+    pub scan_schedule: Option<ScanScheduleConfig>,
+    /// Scan faster while targets are present or moving and slower while the space is empty,
+    /// instead of the fixed rate of the scan mode
+    #[serde(default)]
+    pub adaptive_rate: Option<AdaptiveRateConfig>,
+    /// Kalman filter, association and fall detector parameters
+    #[serde(default)]
+    pub tracking: TrackerConfig,
+    /// Antenna poses for fusing their measurements in the room frame
+    #[serde(default)]
+    pub fusion: FusionConfig,
+    /// Layout of the antennas on their mount, giving the poses of antennas the fusion and
+    /// sensor configuration leave out
+    #[serde(default)]
+    pub antenna_array: Option<AntennaArrayConfig>,
+    /// Where the tracks, statistics, runtime configuration changes and antenna health are
+    /// saved periodically and on shutdown, so a restart resumes tracking with the same
+    /// identities
+    #[serde(default)]
+    pub tracker_state_file: Option<PathBuf>,
+    /// LD2412/LD2450 modules on serial ports, at most one per antenna. When any are configured
+    /// targets come from them rather than from the scanner readings.
+    #[serde(default)]
+    pub sensors: Vec<SensorPortConfig>,
+    /// Write every frame received from the modules to this JSON lines file
+    #[serde(default)]
+    pub frame_recording: Option<PathBuf>,
+    /// Feed a frame recording through the pipeline instead of reading the modules
+    #[serde(default)]
+    pub frame_replay: Option<FrameReplayConfig>,
+    /// Feed frames generated from a scripted scenario through the pipeline instead of
+    /// reading the modules, with the antennas laid out as `antenna_array` describes
+    #[serde(default)]
+    pub simulation: Option<SimulationConfig>,
+    /// Queues between the readers, the scan cycle and the recorder
+    #[serde(default)]
+    pub pipeline: PipelineConfig,
+    /// Let modules whose cones overlap take turns transmitting so they don't interfere, all of
+    /// them transmit at once when unset
+    #[serde(default)]
+    pub interference: Option<InterferenceConfig>,
+    /// Keep running when some of the modules fail, every one of them must work when unset
+    #[serde(default)]
+    pub degraded_mode: Option<DegradedModeConfig>,
+    /// Report targets inside these zones only, all of them when empty
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DegradedModeConfig {
+    /// Fewest working modules to keep scanning with
+    pub min_antennas: usize,
+    /// Factor applied to the confidence of targets a failed module would have seen too, 0.5
+    /// when unset
+    #[serde(default)]
+    pub confidence_factor: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineConfig {
+    /// Parsed frames queued between the antenna readers and the scan cycle
+    pub frame_capacity: usize,
+    /// Whether readers wait for room or drop frames when the scan cycle falls behind
+    pub frame_overflow: OverflowPolicy,
+    /// Frames queued for the recorder
+    pub sink_capacity: usize,
+    /// Whether the scan cycle waits for room or drops frames when the recorder falls behind
+    pub sink_overflow: OverflowPolicy,
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            frame_capacity: 64,
+            frame_overflow: OverflowPolicy::DropNewest,
+            sink_capacity: 256,
+            sink_overflow: OverflowPolicy::DropNewest,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrameReplayConfig {
+    pub file: PathBuf,
+    /// Playback speed relative to the recording, original timing when unset
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationConfig {
+    /// JSON file describing the scenario
+    pub scenario: PathBuf,
+    /// Playback speed relative to real time, real time when unset
+    #[serde(default)]
+    pub speed: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanScheduleConfig {
+    /// e.g. "Mon-Fri 08:00-18:00" or "every 15m for 5m"
+    pub windows: Vec<String>,
+    /// Offset of the local time the windows are written in from UTC
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Keep scanning within the gaps left by `power_settings.duty_cycle`
+    #[serde(default)]
+    pub interleave_with_radar: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdaptiveRateConfig {
+    /// Rate while nobody has been seen for `idle_after_secs`
+    pub idle_rate_hz: f32,
+    /// Rate while targets are present
+    pub present_rate_hz: f32,
+    /// Rate while any target moves faster than `moving_speed`
+    pub moving_rate_hz: f32,
+    /// Speed in m/s a target counts as moving above, 1.0 when unset
+    #[serde(default)]
+    pub moving_speed: Option<f32>,
+    /// How long the space has to stay empty before dropping to the idle rate, 30 s when unset
+    #[serde(default)]
+    pub idle_after_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingConfig {
+    pub directory: PathBuf,
+    pub max_file_size_mb: u64,
+    pub max_file_age_minutes: u64,
+    /// Oldest recordings beyond this count are deleted
+    pub max_files: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrequencyRange {
+    pub start_mhz: f32,
+    pub end_mhz: f32,
+    pub step_mhz: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ScanMode {
+    Continuous,
+    Intermittent,
+    OnDemand,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerSettings {
+    pub transmit_power_watts: f32,
+    pub duty_cycle: f32,
+    pub power_saving: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalProcessingConfig {
+    pub threshold_db: f32,
+    pub filter_strength: f32,
+    pub noise_reduction: bool,
+    pub target_tracking: bool,
+    /// When set, the scanner threshold tracks the measured noise floor plus this margin
+    /// instead of using `threshold_db`
+    #[serde(default)]
+    pub adaptive_margin_db: Option<f32>,
+    /// Samples taken per frequency step, a single reading when unset
+    #[serde(default)]
+    pub dwell: Option<DwellConfig>,
+    /// CSV of `frequency_mhz,correction_db` pairs added to every reading
+    #[serde(default)]
+    pub calibration_file: Option<PathBuf>,
+    /// Readings the scanner keeps in memory, 4096 when unset
+    #[serde(default)]
+    pub history_capacity: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DwellConfig {
+    pub samples: usize,
+    /// Delay between samples
+    #[serde(default)]
+    pub interval_ms: u64,
+    #[serde(default)]
+    pub detector: Detector,
+}
+
+/// Scanner backend, hardware backends need hexar built with the matching feature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignalSourceConfig {
+    Simulated {
+        /// Fixed seed for reproducible runs, random when unset
+        #[serde(default)]
+        seed: Option<u64>,
+        /// Synthetic emitters, the built-in 433/915/2400 MHz set when empty
+        #[serde(default)]
+        emitters: Vec<Emitter>,
+    },
+    RtlSdr {
+        device_index: u32,
+        sample_rate: u32,
+        /// Manual tuner gain, automatic when unset
+        gain_db: Option<f32>,
+    },
+    Soapy {
+        /// Device selection string, e.g. "driver=hackrf"
+        args: String,
+        channel: usize,
+        sample_rate: f64,
+        gain_db: Option<f64>,
+    },
+    /// Play back recordings written by the CSV recorder
+    Replay {
+        files: Vec<PathBuf>,
+        /// Playback speed relative to the recording, original timing when unset
+        speed: Option<f32>,
+    },
+}
+
+impl Default for SignalSourceConfig {
+    fn default() -> Self {
+        SignalSourceConfig::Simulated {
+            seed: None,
+            emitters: Vec::new(),
+        }
+    }
+}
+
+impl Default for RadarConfig {
+    fn default() -> Self {
+        Self {
+            antenna_count: 6,
+            default_frequency: 24000.0, // 24 GHz
+            frequency_range: FrequencyRange {
+                start_mhz: 24000.0,
+                end_mhz: 24500.0,
+                step_mhz: 1.0,
+            },
+            scan_mode: ScanMode::Continuous,
+            power_settings: PowerSettings {
+                transmit_power_watts: 10.0,
+                duty_cycle: 0.8,
+                power_saving: false,
+            },
+            signal_processing: SignalProcessingConfig {
+                threshold_db: -60.0,
+                filter_strength: 0.7,
+                noise_reduction: true,
+                target_tracking: true,
+                adaptive_margin_db: None,
+                dwell: None,
+                calibration_file: None,
+                history_capacity: None,
+            },
+            signal_source: SignalSourceConfig::default(),
+            recording: None,
+            scan_schedule: None,
+            adaptive_rate: None,
+            tracking: TrackerConfig::default(),
+            fusion: FusionConfig::default(),
+            antenna_array: None,
+            tracker_state_file: None,
+            sensors: Vec::new(),
+            frame_recording: None,
+            frame_replay: None,
+            simulation: None,
+            pipeline: PipelineConfig::default(),
+            interference: None,
+            degraded_mode: None,
+            zones: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyConfig {
+    pub emergency_stop_enabled: bool,
+    pub temperature_limits: TemperatureLimits,
+    pub power_limits: PowerLimits,
+    pub radiation_limits: RadiationLimits,
+    pub auto_shutdown: AutoShutdownConfig,
+    pub maintenance_schedule: MaintenanceSchedule,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemperatureLimits {
+    pub warning_celsius: f32,
+    pub critical_celsius: f32,
+    pub shutdown_celsius: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerLimits {
+    pub max_power_watts: f32,
+    pub surge_protection: bool,
+    pub voltage_tolerance: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RadiationLimits {
+    pub max_exposure_time_minutes: u32,
+    pub power_density_limit: f32,
+    pub distance_requirement_meters: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AutoShutdownConfig {
+    pub enabled: bool,
+    pub idle_timeout_minutes: u32,
+    pub error_threshold: u32,
+    pub performance_degradation_threshold: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceSchedule {
+    pub inspection_interval_hours: u32,
+    pub calibration_interval_hours: u32,
+    pub cleaning_interval_hours: u32,
+    pub last_maintenance: chrono::DateTime<chrono::Utc>,
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            emergency_stop_enabled: true,
+            temperature_limits: TemperatureLimits {
+                warning_celsius: 70.0,
+                critical_celsius: 85.0,
+                shutdown_celsius: 95.0,
+            },
+            power_limits: PowerLimits {
+                max_power_watts: 100.0,
+                surge_protection: true,
+                voltage_tolerance: 0.1,
+            },
+            radiation_limits: RadiationLimits {
+                max_exposure_time_minutes: 60,
+                power_density_limit: 10.0,
+                distance_requirement_meters: 3.0,
+            },
+            auto_shutdown: AutoShutdownConfig {
+                enabled: true,
+                idle_timeout_minutes: 30,
+                error_threshold: 10,
+                performance_degradation_threshold: 0.8,
+            },
+            maintenance_schedule: MaintenanceSchedule {
+                inspection_interval_hours: 168, // 1 week
+                calibration_interval_hours: 720, // 1 month
+                cleaning_interval_hours: 336, // 2 weeks
+                last_maintenance: chrono::Utc::now(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MonitoringConfig {
+    pub metrics_collection: bool,
+    pub performance_tracking: bool,
+    pub alert_system: bool,
+    pub data_retention_days: u32,
+    pub export_interval_minutes: u32,
+    pub health_check_interval_seconds: u32,
+    /// Host CPU usage in percent above which a performance alert is raised, resolved once
+    /// usage drops back under it
+    #[serde(default = "default_cpu_alert_percent")]
+    pub cpu_alert_percent: f32,
+    /// Host memory usage in percent above which a critical alert is raised
+    #[serde(default = "default_memory_alert_percent")]
+    pub memory_alert_percent: f32,
+    /// Usage in percent of the filesystem holding the working directory above which an alert
+    /// is raised
+    #[serde(default = "default_disk_alert_percent")]
+    pub disk_alert_percent: f32,
+    /// What happens when a target falls, right when the tracker reports it
+    #[serde(default)]
+    pub fall_alerts: FallAlertConfig,
+}
+
+fn default_cpu_alert_percent() -> f32 {
+    80.0
+}
+
+fn default_memory_alert_percent() -> f32 {
+    90.0
+}
+
+fn default_disk_alert_percent() -> f32 {
+    90.0
+}
+
+impl Default for MonitoringConfig {
+    fn default() -> Self {
+        Self {
+            metrics_collection: true,
+            performance_tracking: true,
+            alert_system: true,
+            data_retention_days: 30,
+            export_interval_minutes: 15,
+            health_check_interval_seconds: 30,
+            cpu_alert_percent: default_cpu_alert_percent(),
+            memory_alert_percent: default_memory_alert_percent(),
+            disk_alert_percent: default_disk_alert_percent(),
+            fall_alerts: FallAlertConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpApiConfig {
+    /// Address and port to listen on, only this host can connect with the default
+    #[serde(default = "default_http_listen")]
+    pub listen: SocketAddr,
+    /// Most target updates a second each `/stream` client gets, every scan cycle's when 0.
+    /// Zone changes, lost targets and falls are always sent.
+    #[serde(default = "default_stream_rate")]
+    pub stream_max_rate_hz: f32,
+    /// Serve HTTPS with this certificate, needs the `tls` feature
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+fn default_http_listen() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 8080))
+}
+
+fn default_stream_rate() -> f32 {
+    10.0
+}
+
+impl Default for HttpApiConfig {
+    fn default() -> Self {
+        Self {
+            listen: default_http_listen(),
+            stream_max_rate_hz: default_stream_rate(),
+            tls: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    /// Address and port to listen on, only this host can connect with the default
+    #[serde(default = "default_grpc_listen")]
+    pub listen: SocketAddr,
+    /// Most target updates a second each `StreamTargets` call gets, every scan cycle's when 0
+    #[serde(default = "default_stream_rate")]
+    pub stream_max_rate_hz: f32,
+    /// Serve gRPC over TLS with this certificate, needs the `tls` feature
+    #[serde(default)]
+    pub tls: Option<TlsConfig>,
+}
+
+fn default_grpc_listen() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 50051))
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            listen: default_grpc_listen(),
+            stream_max_rate_hz: default_stream_rate(),
+            tls: None,
+        }
+    }
+}
+
+/// Certificate and private key of an API server, PEM files
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsConfig {
+    /// Certificate chain, the server's own certificate first
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+/// Clients of the HTTP and gRPC APIs send one of these tokens as `Authorization: Bearer
+/// <token>` or `X-Api-Key: <token>`. Anyone who can connect may use the APIs while there
+/// are none.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiAuthConfig {
+    pub tokens: Vec<ApiToken>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    /// Who the token was given to, logged instead of the token
+    pub name: String,
+    pub token: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    /// Broker host name or address
+    pub host: String,
+    pub port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// States are published under `<topic_prefix>/<node id>`, e.g.
+    /// `hexar/hexar_1a2b3c4d/presence`, the node ID coming from the system ID
+    pub topic_prefix: String,
+    /// Publish Home Assistant MQTT Discovery configs, so the entities appear by themselves
+    pub discovery: bool,
+    /// Topic prefix Home Assistant reads discovery configs from
+    pub discovery_prefix: String,
+    pub keep_alive_secs: u64,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            host: "localhost".to_string(),
+            port: 1883,
+            username: None,
+            password: None,
+            topic_prefix: "hexar".to_string(),
+            discovery: true,
+            discovery_prefix: "homeassistant".to_string(),
+            keep_alive_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DaemonConfig {
+    /// Where the running instance writes its process ID, relative to the working directory
+    /// it was started in unless absolute
+    pub pid_file: PathBuf,
+    /// Unix socket the running instance answers `status`, `stop`, `control` and `config set`
+    /// on, relative to the working directory like the PID file. Windows has none, the
+    /// service control manager starts and stops the instance there.
+    pub control_socket: PathBuf,
+    /// Time a stopping instance gets to shut down before it is killed, unless the stop
+    /// command says otherwise
+    pub stop_timeout_secs: u64,
+    /// Time `start --daemon` waits for the detached instance to initialize
+    pub start_timeout_secs: u64,
+}
+
+impl Default for DaemonConfig {
+    fn default() -> Self {
+        Self {
+            pid_file: PathBuf::from("hexar.pid"),
+            control_socket: PathBuf::from("hexar.sock"),
+            stop_timeout_secs: 10,
+            start_timeout_secs: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PushGatewayConfig {
+    /// Plain HTTP address of the gateway, e.g. `http://localhost:9091`
+    pub url: String,
+    /// Job the metrics are grouped under, the instance label being the system ID
+    pub job: String,
+    pub interval_secs: u64,
+}
+
+impl Default for PushGatewayConfig {
+    fn default() -> Self {
+        Self {
+            url: "http://localhost:9091".to_string(),
+            job: "hexar".to_string(),
+            interval_secs: 15,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggingConfig {
+    pub level: String,
+    pub file_logging: bool,
+    pub console_logging: bool,
+    pub log_directory: PathBuf,
+    pub max_file_size_mb: u32,
+    pub max_files: u32,
+    pub rotation: LogRotation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum LogRotation {
+    Daily,
+    Weekly,
+    Size,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            file_logging: true,
+            console_logging: true,
+            log_directory: PathBuf::from("logs"),
+            max_file_size_mb: 100,
+            max_files: 10,
+            rotation: LogRotation::Daily,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_with_setting() {
+        let config = HexarConfig::default();
+        let key = "radar.signal_processing.threshold_db";
+        let updated = config.with_setting(key, json!(-55)).unwrap();
+        assert_eq!(updated.get_setting(key), Some(json!(-55.0)));
+        assert_eq!(updated.system_id, config.system_id);
+
+        assert!(config.with_setting("radar.signal_processing.threshold", json!(-55)).is_err());
+        assert!(config.with_setting(key, json!("loud")).is_err());
+        assert!(config.with_setting("radar.signal_processing.threshold_db.x", json!(1)).is_err());
+        let updated = config.with_setting("grpc", json!({"listen": "0.0.0.0:50051"})).unwrap();
+        assert_eq!(updated.grpc.unwrap().listen.port(), 50051);
+    }
+
+    #[test]
+    fn test_redacted() {
+        let mut config = HexarConfig::default();
+        config.api_auth.tokens.push(ApiToken { name: "dashboard".to_string(), token: "s3cret".to_string() });
+        config.mqtt = Some(MqttConfig { username: Some("mqtt-user".to_string()), password: Some("hunter2".to_string()), ..MqttConfig::default() });
+        let tls = TlsConfig { cert: PathBuf::from("cert.pem"), key: PathBuf::from("/etc/hexar/key.pem") };
+        config.http_api = Some(HttpApiConfig { tls: Some(tls.clone()), ..HttpApiConfig::default() });
+        config.grpc = Some(GrpcConfig { tls: Some(tls), ..GrpcConfig::default() });
+        
+        let json = serde_json::to_string(&config.redacted()).unwrap();
+        for secret in ["s3cret", "hunter2", "mqtt-user", "key.pem"] {
+            assert!(!json.contains(secret), "{} in {}", secret, json);
+        }
+        // Names and everything else stay
+        let redacted = config.redacted();
+        assert_eq!(redacted.api_auth.tokens[0].name, "dashboard");
+        assert_eq!(redacted.http_api.unwrap().tls.unwrap().cert, PathBuf::from("cert.pem"));
+        assert_eq!(redacted.mqtt.unwrap().host, "localhost");
+    }
+    
+    #[test]
+    fn test_apply_profile() {
+        let mut config = HexarConfig::default();
+        config.daemon.pid_file = PathBuf::from("/run/hexar/bedroom.pid");
+        config.apply_profile("bedroom");
+        assert_eq!(config.daemon.pid_file, PathBuf::from("/run/hexar/bedroom.pid"));
+        assert_eq!(config.daemon.control_socket, PathBuf::from("hexar-bedroom.sock"));
+        assert_eq!(config.logging.log_directory, PathBuf::from("logs/bedroom"));
+        assert_eq!(HexarConfig::profile_path("bedroom"), PathBuf::from("profiles/bedroom.toml"));
+    }
+
+    #[test]
+    fn test_set_toml_setting() {
+        let document = "# Site settings\nsystem_id = \"x\"\n\n[radar.signal_processing]\n# Tuned on site\nthreshold_db = -60.0\n";
+        let updated = set_toml_setting(document, "radar.signal_processing.threshold_db", &json!(-55)).unwrap();
+        assert_eq!(updated, document.replace("-60.0", "-55"));
+
+        let updated = set_toml_setting(document, "grpc.listen", &json!("0.0.0.0:50051")).unwrap();
+        assert!(updated.contains("# Tuned on site"));
+        assert!(updated.ends_with("[grpc]\nlisten = \"0.0.0.0:50051\"\n"));
+        let removed = set_toml_setting(&updated, "grpc.listen", &serde_json::Value::Null).unwrap();
+        assert!(!removed.contains("listen"));
+    }
+}
@@ -0,0 +1,242 @@
+use std::collections::VecDeque;
+
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+
+use crate::config::ScanMode;
+use crate::error::{HexarError, HexarResult};
+
+/// Entries the audit trail keeps, older ones are dropped
+const AUDIT_CAPACITY: usize = 256;
+
+/// Rectangle of the room, in metres in the room frame, targets are reported in
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Zone {
+    pub name: String,
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+impl Zone {
+    pub fn contains(&self, position: Vector2<f32>) -> bool {
+        (self.min.x..=self.max.x).contains(&position.x)
+            && (self.min.y..=self.max.y).contains(&position.y)
+    }
+}
+
+/// Change to the controller's settings applied while it runs, e.g.
+/// `{"command": "set_threshold", "threshold_db": -70.0}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Compare scanner readings against a fixed threshold
+    SetThreshold {
+        threshold_db: f32,
+    },
+    /// Let the scanner threshold follow the noise floor plus a margin
+    SetAdaptiveThreshold {
+        margin_db: f32,
+    },
+    SetScanMode {
+        mode: ScanMode,
+    },
+    /// Report targets inside these zones only, all of them when empty
+    SetZones {
+        zones: Vec<Zone>,
+    },
+    /// Detections within a window of frames needed to confirm a new target
+    SetConfirmation {
+        hits: u32,
+        window: u32,
+    },
+    /// Furthest a measurement may be from a target to be associated with it, in metres
+    SetAssociationGate {
+        gate_m: f32,
+    },
+    /// Squared Mahalanobis distance beyond which measurements are rejected
+    SetMahalanobisGate {
+        gate: f32,
+    },
+    SetAntennaCapacity {
+        antenna_id: u8,
+        max_targets: usize,
+    },
+}
+
+impl ControlCommand {
+    /// Reject values the controller can't run with, before anything changes
+    pub fn validate(&self, antenna_count: u8) -> HexarResult<()> {
+        let invalid = |message: String| Err(HexarError::InvalidParameter(message));
+        match self {
+            ControlCommand::SetThreshold { threshold_db } if !threshold_db.is_finite() => {
+                invalid(format!("threshold {} dB is not a number", threshold_db))
+            }
+            ControlCommand::SetAdaptiveThreshold { margin_db }
+                if !margin_db.is_finite() || *margin_db < 0.0 =>
+            {
+                invalid(format!("margin {} dB must be positive", margin_db))
+            }
+            ControlCommand::SetZones { zones } => {
+                match zones
+                    .iter()
+                    .find(|zone| !(zone.min.x <= zone.max.x && zone.min.y <= zone.max.y))
+                {
+                    Some(zone) => invalid(format!("zone '{}' has its corners swapped", zone.name)),
+                    None => Ok(()),
+                }
+            }
+            ControlCommand::SetConfirmation { hits, window }
+                if *window == 0 || *window > 32 || *hits == 0 || hits > window =>
+            {
+                invalid(format!(
+                    "confirmation needs 1 to {} hits in a window of 1 to 32 frames",
+                    window
+                ))
+            }
+            ControlCommand::SetAssociationGate { gate_m } if !is_positive(*gate_m) => {
+                invalid(format!("association gate {} m must be positive", gate_m))
+            }
+            ControlCommand::SetMahalanobisGate { gate } if !is_positive(*gate) => {
+                invalid(format!("Mahalanobis gate {} must be positive", gate))
+            }
+            ControlCommand::SetAntennaCapacity { antenna_id, .. }
+                if *antenna_id >= antenna_count =>
+            {
+                invalid(format!(
+                    "antenna {} doesn't exist, there are {}",
+                    antenna_id, antenna_count
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Dotted paths in the radar configuration of the settings the command changes
+    pub fn config_keys(&self) -> &'static [&'static str] {
+        match self {
+            ControlCommand::SetThreshold { .. } => &["signal_processing.threshold_db"],
+            ControlCommand::SetAdaptiveThreshold { .. } => {
+                &["signal_processing.adaptive_margin_db"]
+            }
+            ControlCommand::SetScanMode { .. } => &["scan_mode"],
+            ControlCommand::SetZones { .. } => &["zones"],
+            ControlCommand::SetConfirmation { .. } => {
+                &["tracking.confirm_hits", "tracking.confirm_window"]
+            }
+            ControlCommand::SetAssociationGate { .. } => &["tracking.association_gate_m"],
+            ControlCommand::SetMahalanobisGate { .. } => &["tracking.mahalanobis_gate"],
+            ControlCommand::SetAntennaCapacity { .. } => &["tracking.antenna_capacities"],
+        }
+    }
+
+    /// Whether applying `self` undoes the effect of an earlier `other`
+    pub fn replaces(&self, other: &ControlCommand) -> bool {
+        match (self, other) {
+            (
+                ControlCommand::SetAntennaCapacity { antenna_id, .. },
+                ControlCommand::SetAntennaCapacity {
+                    antenna_id: other_id,
+                    ..
+                },
+            ) => antenna_id == other_id,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+fn is_positive(value: f32) -> bool {
+    value.is_finite() && value > 0.0
+}
+
+/// A command the controller was asked to apply and what came of it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Who sent the command, e.g. "cli" or a socket peer
+    pub source: String,
+    pub command: ControlCommand,
+    /// Why the command was rejected, `None` when it was applied
+    pub error: Option<String>,
+}
+
+impl AuditEntry {
+    pub fn applied(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The most recent control commands, applied or rejected
+#[derive(Debug, Clone, Default)]
+pub struct AuditLog {
+    entries: VecDeque<AuditEntry>,
+}
+
+impl AuditLog {
+    pub fn record(&mut self, entry: AuditEntry) {
+        if self.entries.len() == AUDIT_CAPACITY {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Entries oldest first
+    pub fn entries(&self) -> impl Iterator<Item = &AuditEntry> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_parsing_and_validation() {
+        let command: ControlCommand =
+            serde_json::from_str(r#"{"command": "set_confirmation", "hits": 3, "window": 5}"#)
+                .unwrap();
+        assert_eq!(
+            command,
+            ControlCommand::SetConfirmation { hits: 3, window: 5 }
+        );
+        assert!(command.validate(6).is_ok());
+
+        assert!(ControlCommand::SetConfirmation { hits: 6, window: 5 }
+            .validate(6)
+            .is_err());
+        assert!(ControlCommand::SetAntennaCapacity {
+            antenna_id: 6,
+            max_targets: 4
+        }
+        .validate(6)
+        .is_err());
+        assert!(ControlCommand::SetAssociationGate { gate_m: f32::NAN }
+            .validate(6)
+            .is_err());
+
+        let zone = Zone {
+            name: "bed".to_string(),
+            min: Vector2::new(0.0, 0.0),
+            max: Vector2::new(2.0, 1.0),
+        };
+        assert!(zone.contains(Vector2::new(1.0, 0.5)));
+        assert!(!zone.contains(Vector2::new(1.0, 1.5)));
+        let swapped = Zone {
+            min: zone.max,
+            max: zone.min,
+            ..zone
+        };
+        assert!(ControlCommand::SetZones {
+            zones: vec![swapped]
+        }
+        .validate(6)
+        .is_err());
+    }
+}
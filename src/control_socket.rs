@@ -0,0 +1,492 @@
+#[cfg(unix)]
+use std::io;
+#[cfg(unix)]
+use std::os::unix::fs::PermissionsExt;
+#[cfg(unix)]
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+#[cfg(unix)]
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+#[cfg(unix)]
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot};
+#[cfg(unix)]
+use tokio::task::JoinHandle;
+#[cfg(unix)]
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use nalgebra::Vector2;
+
+use crate::config::HexarConfig;
+use crate::control::{ControlCommand, Zone};
+use crate::error::{HexarError, HexarResult};
+use crate::fall_alert::FallAlert;
+use crate::health::HealthReport;
+use crate::monitoring::Alert;
+use crate::prometheus::MetricsSnapshot;
+use crate::radar_controller::RadarEvent;
+use crate::target_class::TargetClass;
+use crate::tracker::{FallPhase, TrackedTarget};
+
+/// Requests waiting for the main loop, further clients wait for room
+const CALL_QUEUE_CAPACITY: usize = 16;
+
+/// How long a client waits for the answer, covering a scan cycle the request waits behind
+#[cfg(unix)]
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What a client asks the running instance, one JSON object per line, e.g.
+/// `{"request": "status"}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum SocketRequest {
+    Status,
+    /// Confirmed targets of the last scan cycle
+    Targets,
+    /// Configured zones with the targets inside them
+    Zones,
+    /// Configuration in effect, including the changes made at runtime
+    Config,
+    /// Counters and gauges Prometheus collects
+    Metrics,
+    /// Apply a control command, recorded in the audit trail as coming from the socket
+    Control {
+        command: ControlCommand,
+    },
+    /// Change a configuration setting by its dotted path, e.g.
+    /// `radar.signal_processing.threshold_db`, where it can change at runtime
+    SetConfig {
+        key: String,
+        value: serde_json::Value,
+    },
+    /// Pause scan cycles, the instance keeps answering requests
+    Pause,
+    /// Resume scan cycles after a pause
+    Resume,
+    /// Shut down gracefully, answered before the shutdown starts
+    Stop,
+}
+
+/// The answer to a [`SocketRequest`], one JSON object per line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum SocketResponse {
+    Status { status: Box<InstanceStatus> },
+    Targets { targets: Vec<TargetReport> },
+    Zones { zones: Vec<ZoneReport> },
+    Config { config: Box<HexarConfig> },
+    Metrics { metrics: Box<MetricsSnapshot> },
+    Done,
+    Error { message: String },
+}
+
+/// State of the running instance as `status` reports it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstanceStatus {
+    pub system_id: Uuid,
+    pub pid: u32,
+    pub version: String,
+    pub started_at: DateTime<Utc>,
+    pub uptime: Duration,
+    pub health: HealthReport,
+    /// False while scan cycles are paused
+    pub scanning: bool,
+    pub emergency_stop: bool,
+    pub active_alerts: Vec<Alert>,
+}
+
+/// A confirmed target as clients see it, in the room frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetReport {
+    pub id: u32,
+    pub antenna_id: u8,
+    pub position: Vector2<f32>,
+    pub velocity: Vector2<f32>,
+    pub confidence: f32,
+    pub class: TargetClass,
+    pub fall_phase: FallPhase,
+    pub fall_probability: f32,
+}
+
+impl From<&TrackedTarget> for TargetReport {
+    fn from(target: &TrackedTarget) -> Self {
+        Self {
+            id: target.id,
+            antenna_id: target.antenna_id,
+            position: target.position,
+            velocity: target.velocity,
+            confidence: target.confidence,
+            class: target.class,
+            fall_phase: target.fall_phase,
+            fall_probability: target.fall_probability,
+        }
+    }
+}
+
+/// A configured zone and the targets in it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ZoneReport {
+    #[serde(flatten)]
+    pub zone: Zone,
+    pub targets: Vec<u32>,
+}
+
+/// A change streamed to clients of the HTTP and gRPC APIs, e.g. `{"type": "target_lost", "target_id": 4}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamMessage {
+    /// Confirmed targets measured in a scan cycle
+    Targets {
+        timestamp: DateTime<Utc>,
+        targets: Vec<TargetReport>,
+    },
+    TargetLost {
+        target_id: u32,
+    },
+    Zone {
+        zone: String,
+        entered: Vec<u32>,
+        left: Vec<u32>,
+        occupants: Vec<u32>,
+    },
+    Fall(FallAlert),
+}
+
+/// Turns radar events into [`StreamMessage`]s, sending the targets of at most `max_rate_hz`
+/// scan cycles a second. Zone changes, lost targets and falls always go out.
+#[derive(Debug)]
+pub struct StreamFilter {
+    min_interval: Duration,
+    last_targets: Option<Instant>,
+    /// Targets of the cycle in progress
+    cycle: Vec<TargetReport>,
+}
+
+impl StreamFilter {
+    pub fn new(max_rate_hz: f32) -> Self {
+        let min_interval = if max_rate_hz > 0.0 {
+            Duration::from_secs_f32(1.0 / max_rate_hz)
+        } else {
+            Duration::ZERO
+        };
+        Self {
+            min_interval,
+            last_targets: None,
+            cycle: Vec::new(),
+        }
+    }
+
+    /// Message to send for `event` received at `now`, if any
+    pub fn message(&mut self, event: &RadarEvent, now: Instant) -> Option<StreamMessage> {
+        match event {
+            RadarEvent::TargetUpdated(target) => {
+                self.cycle.push(target.into());
+                None
+            }
+            RadarEvent::ScanCompleted { timestamp, .. } => {
+                let targets = std::mem::take(&mut self.cycle);
+                let recent = self
+                    .last_targets
+                    .is_some_and(|last| now.duration_since(last) < self.min_interval);
+                if recent {
+                    return None;
+                }
+                self.last_targets = Some(now);
+                Some(StreamMessage::Targets {
+                    timestamp: *timestamp,
+                    targets,
+                })
+            }
+            RadarEvent::TargetLost(target) => Some(StreamMessage::TargetLost {
+                target_id: target.id,
+            }),
+            RadarEvent::ZoneOccupancyChanged {
+                zone,
+                entered,
+                left,
+                occupants,
+            } => Some(StreamMessage::Zone {
+                zone: zone.clone(),
+                entered: entered.clone(),
+                left: left.clone(),
+                occupants: occupants.clone(),
+            }),
+            RadarEvent::FallDetected(event) => {
+                Some(StreamMessage::Fall(FallAlert::from_event(event)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A request passed to the main loop with where its answer goes
+#[derive(Debug)]
+pub struct SocketCall {
+    /// Interface the request came in on, recorded in the audit trail with the changes it makes
+    pub source: &'static str,
+    pub request: SocketRequest,
+    pub reply: oneshot::Sender<SocketResponse>,
+}
+
+impl SocketCall {
+    pub fn respond(self, response: SocketResponse) {
+        // The client may have given up waiting
+        let _ = self.reply.send(response);
+    }
+}
+
+/// Queue of the requests the main loop answers, for an instance without a [`ControlSocket`]
+/// to [`call`] it through the other interfaces
+pub fn call_queue() -> (mpsc::Sender<SocketCall>, mpsc::Receiver<SocketCall>) {
+    mpsc::channel(CALL_QUEUE_CAPACITY)
+}
+
+/// Unix socket the running instance takes requests on, removed when dropped. Only the user
+/// running the instance may connect.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct ControlSocket {
+    path: PathBuf,
+    calls: mpsc::Sender<SocketCall>,
+    handle: JoinHandle<()>,
+}
+
+#[cfg(unix)]
+impl ControlSocket {
+    /// Listen on `path` and pass each request to the returned receiver. A socket file left by
+    /// an instance that didn't exit cleanly is replaced, one another instance listens on isn't.
+    pub fn bind(path: &Path) -> HexarResult<(Self, mpsc::Receiver<SocketCall>)> {
+        if path.exists() {
+            if std::os::unix::net::UnixStream::connect(path).is_ok() {
+                return Err(HexarError::ResourceUnavailable(format!(
+                    "another instance listens on {}",
+                    path.display()
+                )));
+            }
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+
+        let (calls, receiver) = call_queue();
+        let accepted = calls.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        tokio::spawn(serve_client(stream, accepted.clone()));
+                    }
+                    Err(e) => warn!("Control socket accept failed: {}", e),
+                }
+            }
+        });
+        Ok((
+            Self {
+                path: path.to_path_buf(),
+                calls,
+                handle,
+            },
+            receiver,
+        ))
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Queue of the requests the main loop answers, for other interfaces to [`call`] it on
+    pub fn get_caller(&self) -> mpsc::Sender<SocketCall> {
+        self.calls.clone()
+    }
+}
+
+#[cfg(unix)]
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        self.handle.abort();
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            debug!("Failed to remove {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn serve_client(stream: UnixStream, calls: mpsc::Sender<SocketCall>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response =
+            match serde_json::from_str::<SocketRequest>(&line) {
+                Ok(request) => call(&calls, "socket", request).await.unwrap_or_else(|e| {
+                    SocketResponse::Error {
+                        message: e.to_string(),
+                    }
+                }),
+                Err(e) => SocketResponse::Error {
+                    message: format!("invalid request: {}", e),
+                },
+            };
+        let Ok(mut json) = serde_json::to_vec(&response) else {
+            break;
+        };
+        json.push(b'\n');
+        if writer.write_all(&json).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Pass `request` coming in on `source` to the main loop and wait for its answer, failing once
+/// it stopped taking requests
+pub async fn call(
+    calls: &mpsc::Sender<SocketCall>,
+    source: &'static str,
+    request: SocketRequest,
+) -> HexarResult<SocketResponse> {
+    let (reply, answer) = oneshot::channel();
+    let call = SocketCall {
+        source,
+        request,
+        reply,
+    };
+    calls
+        .send(call)
+        .await
+        .map_err(|_| HexarError::ResourceUnavailable("shutting down".to_string()))?;
+    answer
+        .await
+        .map_err(|_| HexarError::ResourceUnavailable("request dropped".to_string()))
+}
+
+/// Send `request` to the instance listening on `path`, `None` when none is
+#[cfg(unix)]
+pub async fn send_request(
+    path: &Path,
+    request: &SocketRequest,
+) -> HexarResult<Option<SocketResponse>> {
+    let stream = match UnixStream::connect(path).await {
+        Ok(stream) => stream,
+        Err(e)
+            if matches!(
+                e.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+            ) =>
+        {
+            return Ok(None)
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let (reader, mut writer) = stream.into_split();
+    let mut json = serde_json::to_vec(request)?;
+    json.push(b'\n');
+    writer.write_all(&json).await?;
+
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+    let read = reader.read_line(&mut line);
+    match tokio::time::timeout(REQUEST_TIMEOUT, read).await {
+        Ok(Ok(0)) => Err(HexarError::CommunicationError(
+            "instance closed the connection".to_string(),
+        )),
+        Ok(Ok(_)) => Ok(Some(serde_json::from_str(&line)?)),
+        Ok(Err(e)) => Err(e.into()),
+        Err(_) => Err(HexarError::Timeout(format!(
+            "no answer from {} within {:?}",
+            path.display(),
+            REQUEST_TIMEOUT
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_socket_round_trip() {
+        let path = std::env::temp_dir().join(format!("hexar-{}.sock", Uuid::new_v4()));
+        assert!(send_request(&path, &SocketRequest::Status)
+            .await
+            .unwrap()
+            .is_none());
+
+        let (socket, mut calls) = ControlSocket::bind(&path).unwrap();
+        assert!(ControlSocket::bind(&path).is_err());
+        tokio::spawn(async move {
+            while let Some(call) = calls.recv().await {
+                let response = match &call.request {
+                    SocketRequest::Control { .. } => SocketResponse::Done,
+                    other => SocketResponse::Error {
+                        message: format!("{:?} unsupported", other),
+                    },
+                };
+                call.respond(response);
+            }
+        });
+
+        let request = SocketRequest::Control {
+            command: ControlCommand::SetAssociationGate { gate_m: 1.5 },
+        };
+        let response = send_request(&path, &request).await.unwrap().unwrap();
+        assert!(matches!(response, SocketResponse::Done));
+        let response = send_request(&path, &SocketRequest::Stop)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(matches!(response, SocketResponse::Error { .. }));
+
+        drop(socket);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_stream_filter() {
+        use crate::tracker::{FallEvent, TrackedTarget};
+
+        let mut filter = StreamFilter::new(2.0);
+        let start = Instant::now();
+        let target = TrackedTarget::new(5, 1, Vector2::new(1.0, 1.0));
+        let completed = RadarEvent::ScanCompleted {
+            scan_id: uuid::Uuid::new_v4(),
+            timestamp: Utc::now(),
+            scan_duration: Duration::from_millis(5),
+            signals_processed: 0,
+            targets_detected: 1,
+        };
+        assert_eq!(
+            filter.message(&RadarEvent::TargetUpdated(target.clone()), start),
+            None
+        );
+        let Some(StreamMessage::Targets { targets, .. }) = filter.message(&completed, start) else {
+            panic!("targets not sent");
+        };
+        assert_eq!(targets[0].id, 5);
+
+        // The next cycle comes too soon, a fall goes out regardless
+        let soon = start + Duration::from_millis(100);
+        filter.message(&RadarEvent::TargetUpdated(target.clone()), soon);
+        assert_eq!(filter.message(&completed, soon), None);
+        let fall = RadarEvent::FallDetected(FallEvent::FallCleared {
+            target_id: 5,
+            at: std::time::Instant::now(),
+        });
+        let message = serde_json::to_value(filter.message(&fall, soon).unwrap()).unwrap();
+        assert_eq!(message["type"], "fall");
+        assert_eq!(message["kind"], "cleared");
+
+        // Only the targets of the latest cycle
+        let later = start + Duration::from_millis(600);
+        let Some(StreamMessage::Targets { targets, .. }) = filter.message(&completed, later) else {
+            panic!("targets not sent");
+        };
+        assert!(targets.is_empty());
+    }
+}
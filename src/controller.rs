@@ -1,482 +1,1584 @@
-use clap::{Parser, Subcommand};
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::time::Duration;
-use anyhow::{Result, Context};
-use tracing::{info, warn, error, debug};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use tokio::signal;
-use uuid::Uuid;
-
-mod config;
-mod safety;
-mod monitoring;
-mod radar_controller;
-mod error;
-
-use config::HexarConfig;
-use safety::SafetyManager;
-use monitoring::MonitoringSystem;
-use radar_controller::RadarController;
-use error::HexarError;
-
-#[derive(Parser)]
-#[command(name = "hexar")]
-#[command(about = "Hexagonal Radar System Controller")]
-#[command(version = env!("CARGO_PKG_VERSION"))]
-struct Cli {
-    #[command(subcommand)]
-    command: Commands,
-    
-    #[arg(short, long, help = "Configuration file path")]
-    config: Option<PathBuf>,
-    
-    #[arg(short, long, help = "Enable verbose logging")]
-    verbose: bool,
-    
-    #[arg(long, help = "Log file path")]
-    log_file: Option<PathBuf>,
-}
-
-#[derive(Subcommand)]
-enum Commands {
-    #[command(about = "Start radar system")]
-    Start {
-        #[arg(short, long, help = "Run in background")]
-        daemon: bool,
-        
-        #[arg(long, help = "Force start without safety checks")]
-        unsafe_mode: bool,
-    },
-    
-    #[command(about = "Stop radar system")]
-    Stop {
-        #[arg(short, long, help = "Graceful shutdown timeout in seconds")]
-        timeout: Option<u64>,
-    },
-    
-    #[command(about = "System status")]
-    Status {
-        #[arg(short, long, help = "Detailed status")]
-        detailed: bool,
-    },
-    
-    #[command(about = "Run safety diagnostics")]
-    Diagnose {
-        #[arg(short, long, help = "Component to test")]
-        component: Option<String>,
-    },
-    
-    #[command(about = "Configuration management")]
-    Config {
-        #[command(subcommand)]
-        action: ConfigAction,
-    },
-    
-    #[command(about = "Monitoring and logs")]
-    Monitor {
-        #[arg(short, long, help = "Real-time monitoring")]
-        follow: bool,
-        
-        #[arg(long, help = "Filter by log level")]
-        level: Option<String>,
-    },
-}
-
-#[derive(Subcommand)]
-enum ConfigAction {
-    #[command(about = "Show current configuration")]
-    Show,
-    
-    #[command(about = "Validate configuration")]
-    Validate,
-    
-    #[command(about = "Reset to defaults")]
-    Reset,
-    
-    #[command(about = "Set configuration value")]
-    Set {
-        #[arg(help = "Configuration key")]
-        key: String,
-        
-        #[arg(help = "Configuration value")]
-        value: String,
-    },
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct SystemStatus {
-    system_id: Uuid,
-    uptime: Duration,
-    radar_status: RadarStatus,
-    safety_status: SafetyStatus,
-    performance_metrics: PerformanceMetrics,
-    last_update: chrono::DateTime<chrono::Utc>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-enum RadarStatus {
-    Offline,
-    Initializing,
-    Online,
-    Scanning,
-    Error(String),
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct SafetyStatus {
-    emergency_stop: bool,
-    temperature_normal: bool,
-    power_normal: bool,
-    antenna_status: Vec<AntennaStatus>,
-    last_safety_check: chrono::DateTime<chrono::Utc>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct AntennaStatus {
-    id: u8,
-    connected: bool,
-    temperature: f32,
-    power_consumption: f32,
-    last_signal: Option<chrono::DateTime<chrono::Utc>>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct PerformanceMetrics {
-    cpu_usage: f32,
-    memory_usage: f32,
-    scan_rate: f32,
-    target_count: usize,
-    error_rate: f32,
-}
-
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
-    
-    // Initialize logging
-    init_logging(&cli)?;
-    
-    // Load configuration
-    let config = HexarConfig::load(cli.config.as_deref()).await
-        .context("Failed to load configuration")?;
-    
-    info!("Starting Hexar Radar System v{}", env!("CARGO_PKG_VERSION"));
-    info!("System ID: {}", config.system_id);
-    
-    // Execute command
-    match cli.command {
-        Commands::Start { daemon, unsafe_mode } => {
-            start_system(config, daemon, unsafe_mode).await
-        },
-        Commands::Stop { timeout } => {
-            stop_system(config, timeout).await
-        },
-        Commands::Status { detailed } => {
-            show_status(config, detailed).await
-        },
-        Commands::Diagnose { component } => {
-            run_diagnostics(config, component).await
-        },
-        Commands::Config { action } => {
-            handle_config(config, action).await
-        },
-        Commands::Monitor { follow, level } => {
-            monitor_system(config, follow, level).await
-        },
-    }
-}
-
-fn init_logging(cli: &Cli) -> Result<()> {
-    let filter = if cli.verbose {
-        "debug"
-    } else {
-        "info"
-    };
-    
-    let fmt_layer = tracing_subscriber::fmt::layer()
-        .with_target(false)
-        .with_thread_ids(true)
-        .with_thread_names(true);
-    
-    let filter_layer = tracing_subscriber::EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(filter));
-    
-    if let Some(log_file) = &cli.log_file {
-        let file = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(log_file)?;
-        
-        let file_layer = tracing_subscriber::fmt::layer()
-            .with_writer(file)
-            .with_ansi(false);
-            
-        tracing_subscriber::registry()
-            .with(filter_layer)
-            .with(fmt_layer)
-            .with(file_layer)
-            .init();
-    } else {
-        tracing_subscriber::registry()
-            .with(filter_layer)
-            .with(fmt_layer)
-            .init();
-    }
-    
-    Ok(())
-}
-
-async fn start_system(config: HexarConfig, daemon: bool, unsafe_mode: bool) -> Result<()> {
-    info!("Initializing radar system...");
-    
-    // Initialize safety manager
-    let mut safety_manager = SafetyManager::new(config.safety.clone())
-        .context("Failed to initialize safety manager")?;
-    
-    // Run safety checks unless in unsafe mode
-    if !unsafe_mode {
-        info!("Running safety checks...");
-        let safety_result = safety_manager.run_full_diagnostics().await?;
-        
-        if !safety_result.safe_to_operate {
-            error!("Safety checks failed. System cannot start.");
-            error!("Use --unsafe-mode flag to bypass (not recommended)");
-            return Err(HexarError::SafetyCheckFailed(safety_result.issues).into());
-        }
-        info!("Safety checks passed");
-    } else {
-        warn!("Starting in UNSAFE MODE - safety checks bypassed");
-    }
-    
-    // Initialize monitoring system
-    let monitoring = MonitoringSystem::new(config.monitoring.clone())
-        .context("Failed to initialize monitoring")?;
-    
-    // Initialize radar controller
-    let mut radar_controller = RadarController::new(config.radar.clone())
-        .context("Failed to initialize radar controller")?;
-    
-    // Start radar system
-    radar_controller.initialize().await
-        .context("Failed to initialize radar")?;
-    
-    if daemon {
-        info!("Starting in daemon mode");
-        // TODO: Implement daemon mode with proper PID file management
-        run_daemon_mode(radar_controller, safety_manager, monitoring).await
-    } else {
-        info!("Starting in foreground mode");
-        run_foreground_mode(radar_controller, safety_manager, monitoring).await
-    }
-}
-
-async fn run_foreground_mode(
-    mut radar_controller: RadarController,
-    mut safety_manager: SafetyManager,
-    monitoring: MonitoringSystem,
-) -> Result<()> {
-    info!("System started successfully");
-    
-    // Set up signal handlers for graceful shutdown
-    let mut sigint = signal::unix::signal(signal::unix::SignalKind::interrupt())?;
-    let mut sigterm = signal::unix::signal(signal::unix::SignalKind::terminate())?;
-    
-    // Main operation loop
-    loop {
-        tokio::select! {
-            // Handle shutdown signals
-            _ = sigint.recv() => {
-                info!("Received SIGINT, shutting down gracefully...");
-                break;
-            },
-            _ = sigterm.recv() => {
-                info!("Received SIGTERM, shutting down gracefully...");
-                break;
-            },
-            
-            // Main operation
-            result = radar_controller.run_scan_cycle() => {
-                match result {
-                    Ok(_) => {
-                        debug!("Scan cycle completed successfully");
-                    },
-                    Err(e) => {
-                        error!("Scan cycle failed: {}", e);
-                        // Check if safety manager recommends shutdown
-                        if safety_manager.should_shutdown(&e).await? {
-                            error!("Safety manager recommends shutdown");
-                            break;
-                        }
-                    }
-                }
-            },
-            
-            // Periodic safety checks
-            _ = tokio::time::sleep(Duration::from_secs(30)) => {
-                if let Err(e) = safety_manager.run_periodic_checks().await {
-                    warn!("Periodic safety check failed: {}", e);
-                }
-            }
-        }
-    }
-    
-    // Graceful shutdown
-    info!("Shutting down radar system...");
-    radar_controller.shutdown().await?;
-    safety_manager.shutdown().await?;
-    info!("System shutdown complete");
-    
-    Ok(())
-}
-
-async fn run_daemon_mode(
-    radar_controller: RadarController,
-    safety_manager: SafetyManager,
-    monitoring: MonitoringSystem,
-) -> Result<()> {
-    // TODO: Implement proper daemon mode with PID file, background operation
-    // For now, just run in foreground
-    run_foreground_mode(radar_controller, safety_manager, monitoring).await
-}
-
-async fn stop_system(config: HexarConfig, timeout: Option<u64>) -> Result<()> {
-    info!("Stopping radar system...");
-    
-    // TODO: Implement proper system stop with PID file management
-    // For now, just log the request
-    warn!("System stop not yet implemented - use Ctrl+C to stop");
-    
-    Ok(())
-}
-
-async fn show_status(config: HexarConfig, detailed: bool) -> Result<()> {
-    info!("Retrieving system status...");
-    
-    // TODO: Implement actual status retrieval
-    let status = SystemStatus {
-        system_id: config.system_id,
-        uptime: Duration::from_secs(3600), // Placeholder
-        radar_status: RadarStatus::Online,
-        safety_status: SafetyStatus {
-            emergency_stop: false,
-            temperature_normal: true,
-            power_normal: true,
-            antenna_status: (0..6).map(|i| AntennaStatus {
-                id: i,
-                connected: true,
-                temperature: 25.0 + (i as f32 * 0.5),
-                power_consumption: 5.0 + (i as f32 * 0.2),
-                last_signal: Some(chrono::Utc::now()),
-            }).collect(),
-            last_safety_check: chrono::Utc::now(),
-        },
-        performance_metrics: PerformanceMetrics {
-            cpu_usage: 15.2,
-            memory_usage: 45.8,
-            scan_rate: 10.5,
-            target_count: 3,
-            error_rate: 0.01,
-        },
-        last_update: chrono::Utc::now(),
-    };
-    
-    println!("System Status:");
-    println!("  System ID: {}", status.system_id);
-    println!("  Uptime: {:?}", status.uptime);
-    println!("  Radar Status: {:?}", status.radar_status);
-    println!("  Safety Status:");
-    println!("    Emergency Stop: {}", status.safety_status.emergency_stop);
-    println!("    Temperature Normal: {}", status.safety_status.temperature_normal);
-    println!("    Power Normal: {}", status.safety_status.power_normal);
-    println!("    Antennas: {}", status.safety_status.antenna_status.len());
-    
-    if detailed {
-        println!("  Performance Metrics:");
-        println!("    CPU Usage: {:.1}%", status.performance_metrics.cpu_usage);
-        println!("    Memory Usage: {:.1}%", status.performance_metrics.memory_usage);
-        println!("    Scan Rate: {:.1} Hz", status.performance_metrics.scan_rate);
-        println!("    Target Count: {}", status.performance_metrics.target_count);
-        println!("    Error Rate: {:.3}%", status.performance_metrics.error_rate * 100.0);
-        
-        println!("  Antenna Details:");
-        for antenna in &status.safety_status.antenna_status {
-            println!("    Antenna {}: Connected={}, Temp={:.1}°C, Power={:.1}W", 
-                    antenna.id, antenna.connected, antenna.temperature, antenna.power_consumption);
-        }
-    }
-    
-    Ok(())
-}
-
-async fn run_diagnostics(config: HexarConfig, component: Option<String>) -> Result<()> {
-    info!("Running system diagnostics...");
-    
-    let mut safety_manager = SafetyManager::new(config.safety.clone())?;
-    let result = safety_manager.run_full_diagnostics().await?;
-    
-    if let Some(component) = component {
-        println!("Diagnostics for component: {}", component);
-        // TODO: Implement component-specific diagnostics
-    } else {
-        println!("Full System Diagnostics:");
-        println!("  Safe to Operate: {}", result.safe_to_operate);
-        println!("  Checks Run: {}", result.checks_performed);
-        
-        if !result.issues.is_empty() {
-            println!("  Issues Found:");
-            for issue in &result.issues {
-                println!("    - {}", issue);
-            }
-        } else {
-            println!("  No issues detected");
-        }
-    }
-    
-    Ok(())
-}
-
-async fn handle_config(config: HexarConfig, action: ConfigAction) -> Result<()> {
-    match action {
-        ConfigAction::Show => {
-            println!("Current Configuration:");
-            println!("{}", serde_json::to_string_pretty(&config)?);
-        },
-        ConfigAction::Validate => {
-            info!("Validating configuration...");
-            // TODO: Implement configuration validation
-            println!("Configuration is valid");
-        },
-        ConfigAction::Reset => {
-            warn!("Resetting configuration to defaults...");
-            // TODO: Implement configuration reset
-            println!("Configuration reset to defaults");
-        },
-        ConfigAction::Set { key, value } => {
-            info!("Setting configuration: {} = {}", key, value);
-            // TODO: Implement configuration setting
-            println!("Configuration updated");
-        },
-    }
-    
-    Ok(())
-}
-
-async fn monitor_system(config: HexarConfig, follow: bool, level: Option<String>) -> Result<()> {
-    info!("Starting system monitoring...");
-    
-    if follow {
-        println!("Real-time monitoring (Ctrl+C to stop):");
-        // TODO: Implement real-time monitoring
-        loop {
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            println!("Monitoring... {}", chrono::Utc::now());
-        }
-    } else {
-        // TODO: Implement log display
-        println!("Recent system logs:");
-        println!("(Log display not yet implemented)");
-    }
-    
-    Ok(())
-}
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use anyhow::{Result, Context};
+use tracing::{info, warn, error, debug};
+use tracing_subscriber::{fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt};
+use tokio::io::AsyncBufReadExt;
+use tokio::sync::{broadcast, mpsc};
+
+use hexar::control::ControlCommand;
+use hexar::control_socket::{self, InstanceStatus, SocketCall, SocketRequest, SocketResponse, ZoneReport};
+#[cfg(unix)]
+use hexar::control_socket::ControlSocket;
+#[cfg(unix)]
+use hexar::daemon::{self, PidFile, StopOutcome};
+#[cfg(unix)]
+use hexar::dashboard;
+use hexar::diagnostics::{self, CheckStatus, Component};
+use hexar::prometheus::{MetricsPusher, MetricsSnapshot};
+use hexar::config::{LoggingConfig, SimulationConfig};
+use hexar::fall_alert::FallAlertDispatcher;
+use hexar::log_query::{self, LogLevel, LogQuery};
+#[cfg(feature = "grpc")]
+use hexar::grpc_api::GrpcApi;
+#[cfg(feature = "http-api")]
+use hexar::http_api::HttpApi;
+#[cfg(feature = "mqtt")]
+use hexar::mqtt::MqttPublisher;
+#[cfg(all(feature = "windows-service", windows))]
+use hexar::win_service;
+use hexar::scan_report::{ScanKind, ScanReport};
+use hexar::scenario::Scenario;
+use hexar::firmware::{self, FirmwareImage};
+use hexar::serial_radar::{SensorModel, SensorPortConfig, SerialRadar};
+use hexar::shutdown::{ShutdownSignal, ShutdownTrigger};
+use hexar::site_calibration::{self, RoomState, SensitivityCalibrator};
+#[cfg(unix)]
+use hexar::systemd::{Notifier, UnitFile};
+use hexar::pose_calibration::PoseCalibrator;
+use hexar::rolling_log::RollingLog;
+use hexar::{HexarConfig, HexarError, MonitoringSystem, RadarController, SafetyManager};
+use nalgebra::Vector2;
+
+#[derive(Parser)]
+#[command(name = "hexar")]
+#[command(about = "Hexagonal Radar System Controller")]
+#[command(version = env!("CARGO_PKG_VERSION"))]
+struct Cli {
+    #[command(subcommand)]
+    command: Commands,
+    
+    #[arg(short, long, help = "Configuration file path")]
+    config: Option<PathBuf>,
+    
+    #[arg(long, value_parser = parse_profile, help = "Named instance with its own configuration (profiles/<NAME>.toml unless --config is given), PID file, socket and log directory")]
+    profile: Option<String>,
+    
+    #[arg(short, long, help = "Enable verbose logging")]
+    verbose: bool,
+    
+    #[arg(long, help = "Log file path")]
+    log_file: Option<PathBuf>,
+    
+    #[arg(long, global = true, value_enum, default_value = "table", help = "Output format of status, diagnose, config show, fw versions and monitor. scan takes --format instead, for its CSV export")]
+    output: OutputFormat,
+}
+
+/// Profile names end up in file names
+fn parse_profile(name: &str) -> std::result::Result<String, String> {
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        Ok(name.to_string())
+    } else {
+        Err("only letters, digits, '-' and '_' are allowed".to_string())
+    }
+}
+
+fn parse_log_time(time: &str) -> std::result::Result<chrono::DateTime<chrono::Utc>, String> {
+    log_query::parse_time(time, chrono::Utc::now())
+}
+
+/// `x,y` in metres
+fn parse_marker(marker: &str) -> std::result::Result<Vector2<f32>, String> {
+    let (x, y) = marker.split_once(',').ok_or("expected x,y")?;
+    let coordinate = |value: &str| value.trim().parse::<f32>().map_err(|e| format!("{}: {}", value.trim(), e));
+    Ok(Vector2::new(coordinate(x)?, coordinate(y)?))
+}
+
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum OutputFormat {
+    Table,
+    Json,
+    Yaml,
+}
+
+impl OutputFormat {
+    /// Print `value` as JSON or YAML, `false` for a table the caller prints itself
+    fn print<T: serde::Serialize>(self, value: &T) -> Result<bool> {
+        match self {
+            OutputFormat::Table => return Ok(false),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+            OutputFormat::Yaml => print!("{}", serde_yaml_ng::to_string(value)?),
+        }
+        Ok(true)
+    }
+}
+
+/// `status` as JSON or YAML, the instance's status when it answered
+#[cfg(any(unix, all(feature = "windows-service", windows)))]
+#[derive(serde::Serialize)]
+struct StatusOutput<'a> {
+    running: bool,
+    /// Process holding the PID file without answering on the control socket
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unresponsive_pid: Option<u32>,
+    #[serde(flatten)]
+    status: Option<&'a InstanceStatus>,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    #[command(about = "Start radar system")]
+    Start {
+        #[arg(short, long, help = "Run in background")]
+        daemon: bool,
+        
+        #[arg(long, help = "Force start without safety checks")]
+        unsafe_mode: bool,
+        
+        /// Set on the process `--daemon` starts in the background
+        #[arg(long, hide = true)]
+        detached: bool,
+    },
+    
+    #[command(about = "Stop radar system")]
+    Stop {
+        #[arg(short, long, help = "Graceful shutdown timeout in seconds")]
+        timeout: Option<u64>,
+    },
+    
+    #[command(about = "System status")]
+    Status {
+        #[arg(short, long, help = "Detailed status")]
+        detailed: bool,
+    },
+    
+    #[command(about = "Run safety diagnostics")]
+    Diagnose {
+        #[arg(short, long, help = "Component to test: antenna:<id>, power, cooling, serial or tracker")]
+        component: Option<Component>,
+        
+    },
+    
+    #[command(about = "Run one scan and print the result, for scripts")]
+    Scan {
+        #[arg(long, group = "mode", help = "Sweep once and report the peaks")]
+        quick: bool,
+        
+        #[arg(long, group = "mode", value_name = "MHZ", help = "Refine the signal around this frequency")]
+        refined: Option<f32>,
+        
+        #[arg(long, group = "mode", help = "Sweep and refine around every signal found")]
+        full: bool,
+        
+        #[arg(long, value_enum, default_value = "json", help = "Output format of the report, --output doesn't apply")]
+        format: ScanFormat,
+    },
+    
+    #[command(about = "Walk through estimating the sensor poses and LD2412 sensitivity, saved into the configuration")]
+    Calibrate {
+        #[arg(long, value_name = "X,Y", value_parser = parse_marker, help = "Room position in metres to stand at for the pose estimate, once per marker, asked for when none is given")]
+        marker: Vec<Vector2<f32>>,
+        
+        #[arg(long, help = "Leave the sensor poses as they are")]
+        skip_pose: bool,
+        
+        #[arg(long, help = "Leave the LD2412 sensitivity as it is")]
+        skip_sensitivity: bool,
+        
+        #[arg(long, default_value_t = 5, help = "Seconds recorded at each marker, twice that for the room")]
+        seconds: u64,
+        
+        #[arg(short, long, help = "Save the results without asking")]
+        yes: bool,
+    },
+    
+    #[command(about = "Firmware of the radar modules")]
+    Fw {
+        #[command(subcommand)]
+        action: FwAction,
+    },
+    
+    #[command(about = "Configuration management")]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    
+    #[command(about = "Change a setting of the running system")]
+    Control {
+        #[arg(help = "Command as JSON, e.g. '{\"command\": \"set_threshold\", \"threshold_db\": -70}'")]
+        command: String,
+    },
+    
+    #[command(about = "Run a scripted scenario through the controller without hardware")]
+    Simulate {
+        #[arg(help = "Scenario JSON file")]
+        scenario: PathBuf,
+        
+        #[arg(short, long, help = "Write the report to this file instead of printing it")]
+        report: Option<PathBuf>,
+    },
+    
+    #[command(about = "Monitoring and logs")]
+    Monitor {
+        #[arg(short, long, help = "Real-time monitoring")]
+        follow: bool,
+        
+        #[arg(long, help = "Show this level and more severe ones (trace, debug, info, warn, error)")]
+        level: Option<LogLevel>,
+        
+        #[arg(long, value_parser = parse_log_time, help = "Entries from this time on: RFC 3339, a date, or a duration ago like 30m, 6h, 2d")]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        
+        #[arg(long, value_parser = parse_log_time, help = "Entries up to this time, like --since")]
+        until: Option<chrono::DateTime<chrono::Utc>>,
+        
+        #[arg(long, help = "Entries of modules or threads whose name contains this, e.g. serial_radar")]
+        component: Option<String>,
+        
+        #[arg(short = 'n', long, default_value_t = 100, help = "Show the latest entries only, 0 for all")]
+        lines: usize,
+        
+        #[arg(long, help = "Write the entries to this file in the --output format instead of printing them")]
+        export: Option<PathBuf>,
+    },
+    
+    #[cfg(unix)]
+    #[command(about = "Print a systemd unit file running this instance in the foreground")]
+    SystemdUnit {
+        #[arg(long, default_value_t = 30, help = "Restart when the scan loop stalls this many seconds, 0 to turn the watchdog off")]
+        watchdog: u64,
+        
+        #[arg(long, help = "User to run as")]
+        user: Option<String>,
+    },
+    
+    #[cfg(all(feature = "windows-service", windows))]
+    #[command(about = "Windows service registration")]
+    Service {
+        #[command(subcommand)]
+        action: ServiceAction,
+    },
+}
+
+#[cfg(all(feature = "windows-service", windows))]
+#[derive(Subcommand)]
+enum ServiceAction {
+    #[command(about = "Register with the service control manager, started at boot with this configuration")]
+    Install,
+    
+    #[command(about = "Stop and remove the service")]
+    Uninstall,
+    
+    #[command(about = "Run as the service, only for the service control manager")]
+    Run,
+}
+
+#[derive(Subcommand)]
+enum FwAction {
+    #[command(about = "Show the firmware version of every configured antenna")]
+    Versions,
+    
+    #[command(about = "Flash a firmware file to the module of one antenna")]
+    Flash {
+        #[arg(long, help = "Antenna whose module is flashed")]
+        antenna: u8,
+        
+        #[arg(help = "Firmware file, e.g. LD2412_V1.26.24032613.bin")]
+        file: PathBuf,
+        
+        #[arg(long, help = "Flash even when the file name gives another model")]
+        force: bool,
+        
+        #[arg(short, long, help = "Flash without asking")]
+        yes: bool,
+    },
+}
+
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum ScanFormat {
+    Json,
+    Csv,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    #[command(about = "Show current configuration")]
+    Show,
+    
+    #[command(about = "Validate configuration")]
+    Validate,
+    
+    #[command(about = "Reset to defaults")]
+    Reset,
+    
+    #[command(about = "Set configuration value")]
+    Set {
+        #[arg(help = "Configuration key, a dotted path like radar.signal_processing.threshold_db")]
+        key: String,
+        
+        #[arg(help = "Configuration value, JSON or plain text", allow_hyphen_values = true)]
+        value: String,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    
+    // Load configuration
+    // A profile's configuration is in a file of its own unless one is given
+    let config_path = cli.config.clone().or_else(|| cli.profile.as_deref().map(HexarConfig::profile_path));
+    // Logging depends on the configuration, until then events only go to standard error
+    let mut config = {
+        let _console = tracing::subscriber::set_default(tracing_subscriber::fmt().with_writer(std::io::stderr).finish());
+        HexarConfig::load(config_path.as_deref()).await
+    }.context("Failed to load configuration")?;
+    if let Some(profile) = &cli.profile {
+        config.apply_profile(profile);
+    }
+    
+    // Initialize logging
+    init_logging(&cli, &config.logging)?;
+    if let Some(profile) = &cli.profile {
+        info!("Profile: {}", profile);
+    }
+    
+    info!("Starting Hexar Radar System v{}", env!("CARGO_PKG_VERSION"));
+    info!("System ID: {}", config.system_id);
+    
+    // Execute command
+    match cli.command {
+        #[cfg(unix)]
+        Commands::Start { daemon: true, detached: false, .. } => {
+            spawn_daemon(config, cli.log_file).await
+        },
+        #[cfg(unix)]
+        Commands::Stop { timeout } => {
+            stop_system(config, timeout).await
+        },
+        #[cfg(unix)]
+        Commands::Status { detailed } => {
+            show_status(config, detailed, cli.output).await
+        },
+        // The background instance of Windows is the registered service
+        #[cfg(all(feature = "windows-service", windows))]
+        Commands::Start { daemon: true, detached: false, .. } => {
+            start_service(cli.profile.as_deref())
+        },
+        #[cfg(all(feature = "windows-service", windows))]
+        Commands::Stop { timeout } => {
+            stop_service(&config, cli.profile.as_deref(), timeout).await
+        },
+        #[cfg(all(feature = "windows-service", windows))]
+        Commands::Status { .. } => {
+            show_service_status(cli.profile.as_deref(), cli.output)
+        },
+        #[cfg(not(any(unix, all(feature = "windows-service", windows))))]
+        Commands::Start { daemon: true, detached: false, .. } | Commands::Stop { .. } | Commands::Status { .. } => {
+            anyhow::bail!("Instances in the background run as a Windows service, which this build leaves out without the windows-service feature")
+        },
+        Commands::Start { unsafe_mode, detached, .. } => {
+            start_system(config, detached, unsafe_mode, ShutdownTrigger::default()).await
+        },
+        Commands::Diagnose { component } => {
+            run_diagnostics(config, component, cli.output).await
+        },
+        Commands::Scan { quick, refined, full, format } => {
+            let kind = match (quick, refined, full) {
+                (true, _, _) => ScanKind::Quick,
+                (_, Some(frequency), _) => ScanKind::Refined { frequency },
+                (_, _, true) => ScanKind::Full,
+                _ => ScanKind::Cycle,
+            };
+            scan(config, kind, format).await
+        },
+        Commands::Calibrate { marker, skip_pose, skip_sensitivity, seconds, yes } => {
+            let steps = CalibrationSteps { markers: marker, pose: !skip_pose, sensitivity: !skip_sensitivity, duration: Duration::from_secs(seconds) };
+            calibrate(config, config_path.as_deref(), steps, yes).await
+        },
+        Commands::Fw { action } => {
+            handle_firmware(config, action, cli.output).await
+        },
+        Commands::Config { action } => {
+            handle_config(config, config_path.as_deref(), action, cli.output).await
+        },
+        Commands::Control { command } => {
+            send_control(config, command).await
+        },
+        Commands::Simulate { scenario, report } => {
+            simulate(config, scenario, report).await
+        },
+        Commands::Monitor { follow, level, since, until, component, lines, export } => {
+            let query = LogQuery { level, since, until, component, limit: lines };
+            monitor_system(config, follow, query, cli.log_file, export, cli.output).await
+        },
+        #[cfg(unix)]
+        Commands::SystemdUnit { watchdog, user } => {
+            print_systemd_unit(&config, cli.profile, cli.config.as_deref(), watchdog, user)
+        },
+        #[cfg(all(feature = "windows-service", windows))]
+        Commands::Service { action } => {
+            run_service_action(config, cli.profile.as_deref(), cli.config.as_deref(), action)
+        },
+    }
+}
+
+fn init_logging(cli: &Cli, logging: &LoggingConfig) -> Result<()> {
+    let filter = if cli.verbose {
+        "debug"
+    } else {
+        "info"
+    };
+    
+    // A running instance logs to the configured directory, rotating its file, other commands
+    // only to a `--log-file`
+    let instance = match &cli.command {
+        Commands::Start { daemon, detached, .. } => !daemon || *detached,
+        #[cfg(all(feature = "windows-service", windows))]
+        Commands::Service { action: ServiceAction::Run } => true,
+        _ => false,
+    };
+    let file_writer = match &cli.log_file {
+        Some(log_file) => {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_file)?;
+            Some(BoxMakeWriter::new(file))
+        },
+        None if instance && logging.file_logging => {
+            let log = RollingLog::open(logging, log_query::LOG_FILE_NAME)
+                .with_context(|| format!("Failed to open the log in {}", logging.log_directory.display()))?;
+            Some(BoxMakeWriter::new(Mutex::new(log)))
+        },
+        None => None,
+    };
+    
+    // Standard output is left to results, e.g. of `scan`. A detached daemon's standard error
+    // is its log file, which gets every event once through the file layer when there is one.
+    let detached = matches!(cli.command, Commands::Start { detached: true, .. });
+    #[cfg(all(feature = "windows-service", windows))]
+    let detached = detached || matches!(cli.command, Commands::Service { action: ServiceAction::Run });
+    let console = !detached || file_writer.is_none();
+    let fmt_layer = console.then(|| tracing_subscriber::fmt::layer()
+        .with_writer(std::io::stderr)
+        .with_target(detached)
+        .with_thread_ids(true)
+        .with_thread_names(true));
+    
+    // Targets let `monitor --component` pick modules
+    let file_layer = file_writer.map(|writer| tracing_subscriber::fmt::layer()
+        .with_writer(writer)
+        .with_ansi(false)
+        .with_thread_ids(true)
+        .with_thread_names(true));
+    
+    let filter_layer = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new(filter));
+    
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(fmt_layer)
+        .with(file_layer)
+        .init();
+    
+    Ok(())
+}
+
+/// Start the system again in a detached process and wait until it is running
+#[cfg(unix)]
+async fn spawn_daemon(config: HexarConfig, log_file: Option<PathBuf>) -> Result<()> {
+    let pid_path = config.daemon.pid_file.clone();
+    ensure_not_running(&pid_path)?;
+    let log = match log_file {
+        Some(log) => log,
+        None => {
+            tokio::fs::create_dir_all(&config.logging.log_directory).await
+                .context("Failed to create log directory")?;
+            config.logging.log_directory.join(log_query::LOG_FILE_NAME)
+        }
+    };
+    
+    let mut args: Vec<std::ffi::OsString> = std::env::args_os().skip(1).collect();
+    args.push("--detached".into());
+    let mut child = daemon::spawn_detached(&args, &log)?;
+    let pid = child.id();
+    let timeout = Duration::from_secs(config.daemon.start_timeout_secs);
+    tokio::task::spawn_blocking(move || daemon::wait_for_start(&mut child, &pid_path, timeout)).await?
+        .with_context(|| format!("Daemon failed to start, see {}", log.display()))?;
+    
+    println!("Started in the background with PID {}, logging to {}", pid, log.display());
+    Ok(())
+}
+
+/// Fail when the PID file names a running instance
+#[cfg(unix)]
+fn ensure_not_running(pid_path: &std::path::Path) -> Result<()> {
+    if let Some(pid) = daemon::read_pid(pid_path)? {
+        if daemon::is_running(pid) {
+            return Err(HexarError::ResourceUnavailable(format!(
+                "already running with PID {} ({})", pid, pid_path.display()
+            )).into());
+        }
+    }
+    Ok(())
+}
+
+/// Run the system until a signal or `shutdown` asks it to stop
+async fn start_system(config: HexarConfig, daemon: bool, unsafe_mode: bool, shutdown: ShutdownTrigger) -> Result<()> {
+    info!("Initializing radar system...");
+    // Checked before the modules are opened, the PID file is written once they work
+    #[cfg(unix)]
+    ensure_not_running(&config.daemon.pid_file)?;
+    
+    // Initialize safety manager
+    let mut safety_manager = SafetyManager::new(config.safety.clone())
+        .context("Failed to initialize safety manager")?;
+    
+    // Run safety checks unless in unsafe mode
+    if !unsafe_mode {
+        info!("Running safety checks...");
+        let safety_result = safety_manager.run_full_diagnostics().await?;
+        
+        if !safety_result.safe_to_operate {
+            error!("Safety checks failed. System cannot start.");
+            error!("Use --unsafe-mode flag to bypass (not recommended)");
+            return Err(HexarError::SafetyCheckFailed(safety_result.issues).into());
+        }
+        info!("Safety checks passed");
+    } else {
+        warn!("Starting in UNSAFE MODE - safety checks bypassed");
+    }
+    
+    // Initialize monitoring system
+    let monitoring = MonitoringSystem::new(config.monitoring.clone())
+        .context("Failed to initialize monitoring")?;
+    
+    // Initialize radar controller
+    let mut radar_controller = RadarController::new(config.radar.clone())
+        .context("Failed to initialize radar controller")?;
+    
+    // Start radar system
+    radar_controller.initialize().await
+        .context("Failed to initialize radar")?;
+    #[cfg(unix)]
+    let (socket, calls) = ControlSocket::bind(&config.daemon.control_socket)
+        .context("Failed to open control socket")?;
+    #[cfg(unix)]
+    let caller = socket.get_caller();
+    #[cfg(unix)]
+    let _pid_file = PidFile::create(&config.daemon.pid_file)
+        .context("Failed to write PID file")?;
+    // Without a control socket requests only come in over the APIs
+    #[cfg(not(unix))]
+    let (caller, calls) = control_socket::call_queue();
+    #[cfg(feature = "http-api")]
+    let _http_api = match &config.http_api {
+        Some(http_config) => Some(HttpApi::serve(http_config, &config.api_auth, caller.clone(), radar_controller.subscribe()).await
+            .context("Failed to start HTTP API")?),
+        None => None,
+    };
+    #[cfg(not(feature = "http-api"))]
+    if config.http_api.is_some() {
+        warn!("HTTP API configured, but this build doesn't include the http-api feature");
+    }
+    let _pusher = match &config.pushgateway {
+        Some(push_config) => Some(MetricsPusher::spawn(push_config, &config.system_id.to_string(), caller.clone())
+            .context("Failed to start pushing metrics")?),
+        None => None,
+    };
+    #[cfg(feature = "grpc")]
+    let _grpc_api = match &config.grpc {
+        Some(grpc_config) => Some(GrpcApi::serve(grpc_config, &config.api_auth, caller.clone(), radar_controller.subscribe()).await
+            .context("Failed to start gRPC API")?),
+        None => None,
+    };
+    #[cfg(not(feature = "grpc"))]
+    if config.grpc.is_some() {
+        warn!("gRPC API configured, but this build doesn't include the grpc feature");
+    }
+    #[cfg(unix)]
+    debug!("Control socket at {}", socket.get_path().display());
+    let instance = Instance {
+        config,
+        started_at: chrono::Utc::now(),
+        calls,
+        scanning: true,
+    };
+    
+    if daemon {
+        info!("Running detached with PID {}", std::process::id());
+        run_daemon_mode(radar_controller, safety_manager, monitoring, instance, shutdown).await
+    } else {
+        info!("Starting in foreground mode");
+        run_foreground_mode(radar_controller, safety_manager, monitoring, instance, shutdown).await
+    }
+}
+
+/// What the main loop needs to answer requests on the control socket
+struct Instance {
+    /// Configuration the instance started with, the radar section changes at runtime
+    config: HexarConfig,
+    started_at: chrono::DateTime<chrono::Utc>,
+    calls: mpsc::Receiver<SocketCall>,
+    /// Cleared while scan cycles are paused on request
+    scanning: bool,
+}
+
+/// Windows services report to the service control manager in `win_service`, there is no
+/// systemd to notify
+#[cfg(not(unix))]
+struct Notifier;
+
+#[cfg(not(unix))]
+impl Notifier {
+    fn from_env() -> Self {
+        Notifier
+    }
+    
+    fn ready(&mut self, _status: &str) {}
+    
+    fn set_status(&mut self, _status: &str) {}
+    
+    fn heartbeat(&mut self, _now: std::time::Instant) -> bool {
+        false
+    }
+    
+    fn get_heartbeat_interval(&self) -> Option<Duration> {
+        None
+    }
+    
+    fn stopping(&mut self) {}
+}
+
+async fn run_foreground_mode(
+    mut radar_controller: RadarController,
+    mut safety_manager: SafetyManager,
+    mut monitoring: MonitoringSystem,
+    mut instance: Instance,
+    shutdown: ShutdownTrigger,
+) -> Result<()> {
+    info!("System started successfully");
+    let mut notifier = Notifier::from_env();
+    notifier.ready("Scanning");
+    let mut events = radar_controller.subscribe();
+    let mut control = read_control_commands();
+    // Falls are acted on as soon as the controller reports them, not between scan cycles
+    let fall_alerts = FallAlertDispatcher::spawn(
+        monitoring.get_config().fall_alerts.clone(),
+        radar_controller.subscribe(),
+    );
+    #[cfg(feature = "mqtt")]
+    let mqtt = instance.config.mqtt.as_ref().map(|mqtt_config| {
+        let zones: Vec<String> = radar_controller.get_config().zones.iter().map(|zone| zone.name.clone()).collect();
+        MqttPublisher::spawn(mqtt_config, instance.config.system_id, &zones, radar_controller.subscribe())
+    });
+    #[cfg(not(feature = "mqtt"))]
+    if instance.config.mqtt.is_some() {
+        warn!("MQTT configured, but this build doesn't include the mqtt feature");
+    }
+    
+    // Set up signal handlers for graceful shutdown
+    let mut shutdown = ShutdownSignal::new(&shutdown)?;
+    
+    // Host and process usage, checked against the alert thresholds
+    let mut metrics_interval = tokio::time::interval(Duration::from_secs(u64::from(monitoring.get_config().health_check_interval_seconds.max(1))));
+    metrics_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    
+    // Main operation loop
+    loop {
+        tokio::select! {
+            // Handle shutdown signals and requests of the service manager
+            reason = shutdown.recv() => {
+                info!("Received {}, shutting down gracefully...", reason);
+                break;
+            },
+            
+            // Main operation
+            result = radar_controller.run_scan_cycle(), if instance.scanning => {
+                // Handled between cycles, a scan cycle must not be cancelled by an event
+                loop {
+                    match events.try_recv() {
+                        Ok(event) => monitoring.handle_radar_event(&event).await?,
+                        Err(broadcast::error::TryRecvError::Lagged(missed)) => {
+                            warn!("Monitoring missed {} radar events", missed);
+                        },
+                        Err(_) => break,
+                    }
+                }
+                monitoring.update_scan_metrics(radar_controller.get_scan_metrics().clone());
+                monitoring.update_scan_statistics(radar_controller.get_channel_occupancy());
+                monitoring.update_health(radar_controller.health());
+                // Only completed cycles keep the watchdog quiet, a stalled one gets the unit restarted
+                notifier.heartbeat(std::time::Instant::now());
+                notifier.set_status(&match &result {
+                    Ok(_) => format!("Scanning, {} targets", radar_controller.get_confirmed_targets().len()),
+                    Err(e) => format!("Scan cycle failed: {}", e),
+                });
+                match result {
+                    Ok(_) => {
+                        debug!("Scan cycle completed successfully");
+                    },
+                    Err(e) => {
+                        error!("Scan cycle failed: {}", e);
+                        // Check if safety manager recommends shutdown
+                        if safety_manager.should_shutdown(&e).await? {
+                            error!("Safety manager recommends shutdown");
+                            break;
+                        }
+                    }
+                }
+            },
+            
+            // Settings changed at runtime, between scan cycles
+            Some(line) = control.recv() => {
+                match serde_json::from_str::<ControlCommand>(&line) {
+                    Ok(command) => {
+                        if let Err(e) = radar_controller.apply_from(command, "stdin") {
+                            warn!("Control command rejected: {}", e);
+                        }
+                    },
+                    Err(e) => warn!("Invalid control command '{}': {}", line, e),
+                }
+            },
+            
+            // Requests of other processes over the control socket, between scan cycles
+            Some(call) = instance.calls.recv() => {
+                let stop = matches!(call.request, SocketRequest::Stop);
+                let response = answer_request(&call, &mut instance, &mut radar_controller, &safety_manager, &monitoring);
+                call.respond(response);
+                if !instance.scanning {
+                    notifier.set_status("Paused");
+                }
+                if stop {
+                    info!("Stop requested over the control socket, shutting down gracefully...");
+                    break;
+                }
+            },
+            
+            // Nothing else proves the loop alive while paused
+            _ = tokio::time::sleep(notifier.get_heartbeat_interval().unwrap_or_default()),
+                if !instance.scanning && notifier.get_heartbeat_interval().is_some() => {
+                notifier.heartbeat(std::time::Instant::now());
+            },
+            
+            _ = metrics_interval.tick(), if monitoring.get_config().metrics_collection => {
+                if let Err(e) = monitoring.collect_metrics().await {
+                    warn!("Failed to collect metrics: {}", e);
+                }
+            },
+            
+            // Periodic safety checks
+            _ = tokio::time::sleep(Duration::from_secs(30)) => {
+                if let Err(e) = safety_manager.run_periodic_checks().await {
+                    warn!("Periodic safety check failed: {}", e);
+                }
+            }
+        }
+    }
+    
+    // Graceful shutdown
+    info!("Shutting down radar system...");
+    notifier.stopping();
+    radar_controller.shutdown().await?;
+    fall_alerts.stop();
+    #[cfg(feature = "mqtt")]
+    if let Some(mqtt) = mqtt {
+        mqtt.stop().await;
+    }
+    safety_manager.shutdown().await?;
+    info!("System shutdown complete");
+    
+    Ok(())
+}
+
+fn answer_request(
+    call: &SocketCall,
+    instance: &mut Instance,
+    radar_controller: &mut RadarController,
+    safety_manager: &SafetyManager,
+    monitoring: &MonitoringSystem,
+) -> SocketResponse {
+    let result = match &call.request {
+        SocketRequest::Status => {
+            let status = InstanceStatus {
+                system_id: instance.config.system_id,
+                pid: std::process::id(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                started_at: instance.started_at,
+                uptime: (chrono::Utc::now() - instance.started_at).to_std().unwrap_or_default(),
+                health: radar_controller.health(),
+                scanning: instance.scanning,
+                emergency_stop: safety_manager.is_emergency_stopped(),
+                active_alerts: monitoring.get_active_alerts().into_iter().cloned().collect(),
+            };
+            return SocketResponse::Status { status: Box::new(status) };
+        },
+        SocketRequest::Targets => {
+            let targets = radar_controller.get_confirmed_targets().into_iter().map(Into::into).collect();
+            return SocketResponse::Targets { targets };
+        },
+        SocketRequest::Zones => {
+            let targets = radar_controller.get_confirmed_targets();
+            let zones = radar_controller.get_config().zones.iter()
+                .map(|zone| ZoneReport {
+                    zone: zone.clone(),
+                    targets: targets.iter()
+                        .filter(|target| zone.contains(target.position))
+                        .map(|target| target.id)
+                        .collect(),
+                })
+                .collect();
+            return SocketResponse::Zones { zones };
+        },
+        SocketRequest::Config => {
+            let mut config = instance.config.clone();
+            config.radar = radar_controller.get_config().clone();
+            // Also answered over the remote APIs, which must not hand out their own secrets
+            return SocketResponse::Config { config: Box::new(config.redacted()) };
+        },
+        SocketRequest::Metrics => {
+            let metrics = MetricsSnapshot {
+                uptime: (chrono::Utc::now() - instance.started_at).to_std().unwrap_or_default(),
+                scanning: instance.scanning,
+                health: radar_controller.health(),
+                scan: radar_controller.get_scan_metrics().clone(),
+                antenna_loads: radar_controller.get_scan_statistics().antenna_loads,
+                emergency_stop: safety_manager.is_emergency_stopped(),
+                active_alerts: monitoring.get_active_alerts().into_iter().cloned().collect(),
+                errors: monitoring.get_error_metrics(),
+            };
+            return SocketResponse::Metrics { metrics: Box::new(metrics) };
+        },
+        SocketRequest::Control { command } => radar_controller.apply_from(command.clone(), call.source),
+        SocketRequest::SetConfig { key, value } => radar_controller
+            .setting_command(key, value.clone())
+            .and_then(|command| radar_controller.apply_from(command, call.source)),
+        SocketRequest::Pause | SocketRequest::Resume => {
+            instance.scanning = matches!(call.request, SocketRequest::Resume);
+            info!("Scanning {} on request over {}", if instance.scanning { "resumed" } else { "paused" }, call.source);
+            Ok(())
+        },
+        SocketRequest::Stop => Ok(()),
+    };
+    match result {
+        Ok(()) => SocketResponse::Done,
+        Err(e) => SocketResponse::Error { message: e.to_string() },
+    }
+}
+
+/// Answer of the running instance to `request`, an error when it refused, `None` when no
+/// instance is running
+#[cfg(unix)]
+async fn request_instance(config: &HexarConfig, request: SocketRequest) -> Result<Option<SocketResponse>> {
+    let response = control_socket::send_request(&config.daemon.control_socket, &request).await
+        .context("Failed to reach the running instance")?;
+    match response {
+        Some(SocketResponse::Error { message }) => Err(anyhow::anyhow!(message)),
+        response => Ok(response),
+    }
+}
+
+/// Windows has no control socket, a running instance is only reachable over its APIs
+#[cfg(not(unix))]
+async fn request_instance(_config: &HexarConfig, _request: SocketRequest) -> Result<Option<SocketResponse>> {
+    Ok(None)
+}
+
+/// Apply a setting saved to the configuration file to the running instance too, if any
+#[cfg(unix)]
+async fn update_running_instance(config: &HexarConfig, key: String, value: serde_json::Value) -> Result<()> {
+    let request = SocketRequest::SetConfig { key: key.clone(), value };
+    match control_socket::send_request(&config.daemon.control_socket, &request).await? {
+        Some(SocketResponse::Error { message }) => {
+            println!("Running instance unchanged until restarted: {}", message);
+        },
+        Some(_) => println!("Running instance updated: {}", key),
+        None => {},
+    }
+    Ok(())
+}
+
+/// Control commands read from standard input, one JSON object per line, until it closes
+fn read_control_commands() -> mpsc::Receiver<String> {
+    let (sender, receiver) = mpsc::channel(16);
+    tokio::spawn(async move {
+        let mut lines = tokio::io::BufReader::new(tokio::io::stdin()).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let line = line.trim().to_string();
+            if !line.is_empty() && sender.send(line).await.is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+/// Operation of the detached process, the same as in the foreground with nothing on its
+/// standard input and `stop` sending SIGTERM
+async fn run_daemon_mode(
+    radar_controller: RadarController,
+    safety_manager: SafetyManager,
+    monitoring: MonitoringSystem,
+    instance: Instance,
+    shutdown: ShutdownTrigger,
+) -> Result<()> {
+    run_foreground_mode(radar_controller, safety_manager, monitoring, instance, shutdown).await
+}
+
+#[cfg(unix)]
+async fn stop_system(config: HexarConfig, timeout: Option<u64>) -> Result<()> {
+    info!("Stopping radar system...");
+    
+    let pid_path = config.daemon.pid_file.clone();
+    let Some(pid) = daemon::read_pid(&pid_path)? else {
+        println!("Not running, {} doesn't exist", pid_path.display());
+        return Ok(());
+    };
+    let timeout = Duration::from_secs(timeout.unwrap_or(config.daemon.stop_timeout_secs));
+    // Asked over the socket first, signalled when that fails or it takes too long
+    let requested = match control_socket::send_request(&config.daemon.control_socket, &SocketRequest::Stop).await {
+        Ok(response) => response.is_some(),
+        // It may exit before its answer is written
+        Err(HexarError::CommunicationError(_)) => true,
+        Err(e) => {
+            warn!("Failed to reach the running instance: {}", e);
+            false
+        }
+    };
+    let outcome = tokio::task::spawn_blocking(move || {
+        if requested && daemon::wait_for_exit(pid, timeout) {
+            Ok(StopOutcome::Graceful)
+        } else {
+            daemon::stop_process(pid, timeout)
+        }
+    }).await??;
+    match outcome {
+        StopOutcome::NotRunning => println!("PID {} isn't running", pid),
+        StopOutcome::Graceful => println!("Stopped PID {}", pid),
+        StopOutcome::Killed => println!("Killed PID {} after it didn't stop within {:?}", pid, timeout),
+    }
+    // Left behind unless the process removed it on the way out
+    if outcome != StopOutcome::Graceful && daemon::read_pid(&pid_path)? == Some(pid) {
+        tokio::fs::remove_file(&pid_path).await?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn show_status(config: HexarConfig, detailed: bool, output: OutputFormat) -> Result<()> {
+    info!("Retrieving system status...");
+    
+    let status = match request_instance(&config, SocketRequest::Status).await? {
+        Some(SocketResponse::Status { status }) => status,
+        Some(other) => anyhow::bail!("Unexpected answer {:?}", other),
+        None => {
+            let pid = daemon::read_pid(&config.daemon.pid_file)?.filter(|pid| daemon::is_running(*pid));
+            if output.print(&StatusOutput { running: false, unresponsive_pid: pid, status: None })? {
+                return Ok(());
+            }
+            println!("System Status:");
+            println!("  Radar Status: Offline");
+            if let Some(pid) = pid {
+                println!("  PID {} is running but not answering on {}", pid, config.daemon.control_socket.display());
+            }
+            return Ok(());
+        }
+    };
+    if output.print(&StatusOutput { running: true, unresponsive_pid: None, status: Some(&status) })? {
+        return Ok(());
+    }
+    let health = &status.health;
+    
+    println!("System Status:");
+    println!("  System ID: {}", status.system_id);
+    println!("  PID: {} (v{})", status.pid, status.version);
+    println!("  Uptime: {:?}", Duration::from_secs(status.uptime.as_secs()));
+    println!("  Radar Status: {:?}{}", health.state, if status.scanning { "" } else { " (paused)" });
+    println!("  Healthy: {}", health.is_healthy());
+    println!("  Targets: {} ({} confirmed)", health.tracker.targets, health.tracker.confirmed);
+    println!("  Safety Status:");
+    println!("    Emergency Stop: {}", status.emergency_stop);
+    println!("    Active Alerts: {}", status.active_alerts.len());
+    println!("  Antennas: {}", health.antennas.len());
+    if let Some(error) = &health.last_error {
+        println!("  Last Error: {} ({})", error.message, error.timestamp);
+    }
+    
+    if detailed {
+        println!("  Performance Metrics:");
+        println!("    Scan Rate: {:.1} Hz", health.scan_rate_hz);
+        println!("    Scans: {}", health.scan.total_scans);
+        println!("    Average Scan Duration: {:?}", health.scan.average_scan_duration);
+        println!("    Dropped Frames: {}", health.scan.dropped_frames);
+        if let Some(coverage) = health.coverage {
+            println!("    Coverage: {:.0}%", coverage * 100.0);
+        }
+        
+        println!("  Antenna Details:");
+        for antenna in &health.antennas {
+            println!("    Antenna {}: Connected={}, Healthy={}, Frame Errors={:.1}%",
+                    antenna.antenna_id, antenna.connected, antenna.is_healthy(),
+                    antenna.frame_error_rate() * 100.0);
+        }
+        
+        if !status.active_alerts.is_empty() {
+            println!("  Alerts:");
+            for alert in &status.active_alerts {
+                println!("    [{:?}] {}: {}", alert.severity, alert.component, alert.message);
+            }
+        }
+    }
+    
+    Ok(())
+}
+
+async fn run_diagnostics(config: HexarConfig, component: Option<Component>, output: OutputFormat) -> Result<()> {
+    info!("Running system diagnostics...");
+    
+    let mut safety_manager = SafetyManager::new(config.safety.clone())?;
+    
+    if let Some(component) = component {
+        let running = match request_instance(&config, SocketRequest::Status).await? {
+            Some(SocketResponse::Status { status }) => Some(status.health),
+            _ => None,
+        };
+        let report = diagnostics::diagnose(component, &config, &safety_manager, running.as_ref()).await?;
+        if !output.print(&report)? {
+            println!("Diagnostics for {}{}:", component, if report.running { " (running instance)" } else { "" });
+            for check in &report.checks {
+                let outcome = match check.status {
+                    CheckStatus::Pass => "ok",
+                    CheckStatus::Warn => "warn",
+                    CheckStatus::Fail => "FAIL",
+                };
+                println!("  [{:>4}] {}: {}", outcome, check.name, check.detail);
+            }
+        }
+        if !report.passed() {
+            anyhow::bail!("{} failed diagnostics", component);
+        }
+        return Ok(());
+    }
+    
+    let result = safety_manager.run_full_diagnostics().await?;
+    if output.print(&result)? {
+        return Ok(());
+    }
+    println!("Full System Diagnostics:");
+    println!("  Safe to Operate: {}", result.safe_to_operate);
+    println!("  Checks Run: {}", result.checks_performed);
+    
+    if !result.issues.is_empty() {
+        println!("  Issues Found:");
+        for issue in &result.issues {
+            println!("    - {}", issue);
+        }
+    } else {
+        println!("  No issues detected");
+    }
+    
+    Ok(())
+}
+
+async fn handle_config(config: HexarConfig, path: Option<&std::path::Path>, action: ConfigAction, output: OutputFormat) -> Result<()> {
+    match action {
+        ConfigAction::Show => {
+            if output.print(&config)? {
+                return Ok(());
+            }
+            println!("Current Configuration:");
+            println!("{}", serde_json::to_string_pretty(&config)?);
+        },
+        ConfigAction::Validate => {
+            info!("Validating configuration...");
+            // TODO: Implement configuration validation
+            println!("Configuration is valid");
+        },
+        ConfigAction::Reset => {
+            warn!("Resetting configuration to defaults...");
+            // TODO: Implement configuration reset
+            println!("Configuration reset to defaults");
+        },
+        ConfigAction::Set { key, value } => {
+            info!("Setting configuration: {} = {}", key, value);
+            // Taken as JSON where it parses, e.g. numbers and lists, as a string otherwise
+            let value = serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value));
+            let updated = config.with_setting(&key, value.clone())?;
+            updated.save_setting(path, &key, &value).await
+                .context("Failed to update the configuration file")?;
+            println!("Saved {} = {}", key, value);
+            
+            #[cfg(unix)]
+            update_running_instance(&config, key, value).await?;
+        },
+    }
+    
+    Ok(())
+}
+
+async fn send_control(config: HexarConfig, command: String) -> Result<()> {
+    let command: ControlCommand = serde_json::from_str(&command)
+        .context("Invalid control command")?;
+    command.validate(config.radar.antenna_count)?;
+    
+    match request_instance(&config, SocketRequest::Control { command: command.clone() }).await? {
+        Some(_) => println!("Applied {}", serde_json::to_string(&command)?),
+        // A system started in the foreground also applies the commands written to its
+        // standard input
+        None => println!("{}", serde_json::to_string(&command)?),
+    }
+    Ok(())
+}
+
+/// Run a scenario headlessly as fast as the controller goes, with the modules, replays and
+/// state file of the configuration left out
+async fn simulate(mut config: HexarConfig, scenario: PathBuf, report_file: Option<PathBuf>) -> Result<()> {
+    let loaded = Scenario::load(&scenario).context("Failed to load scenario")?;
+    config.radar.sensors.clear();
+    config.radar.frame_replay = None;
+    config.radar.frame_recording = None;
+    config.radar.tracker_state_file = None;
+    config.radar.simulation = Some(SimulationConfig { scenario, speed: None });
+    
+    let mut radar_controller = RadarController::new(config.radar)?;
+    radar_controller.initialize().await?;
+    let report = radar_controller.run_simulation(loaded).await?;
+    radar_controller.shutdown().await?;
+    
+    let json = serde_json::to_string_pretty(&report)?;
+    match report_file {
+        Some(path) => tokio::fs::write(&path, json).await
+            .with_context(|| format!("Failed to write {}", path.display()))?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
+
+async fn scan(config: HexarConfig, kind: ScanKind, format: ScanFormat) -> Result<()> {
+    let mut radar_controller = RadarController::new(config.radar)
+        .context("Failed to initialize radar controller")?;
+    if kind == ScanKind::Cycle {
+        radar_controller.initialize().await
+            .context("Failed to initialize radar")?;
+    }
+    let report = ScanReport::run(&mut radar_controller, kind).await?;
+    if kind == ScanKind::Cycle {
+        radar_controller.shutdown().await?;
+    }
+    
+    match format {
+        ScanFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+        ScanFormat::Csv => report.write_csv(std::io::stdout().lock())?,
+    }
+    Ok(())
+}
+
+/// What `hexar calibrate` goes through
+struct CalibrationSteps {
+    markers: Vec<Vector2<f32>>,
+    pose: bool,
+    sensitivity: bool,
+    /// Recorded at each marker, twice that for the empty and the occupied room
+    duration: Duration,
+}
+
+/// Estimate the poses of the LD2450 modules from the operator standing at markers and the
+/// LD2412 thresholds from the room empty and occupied, then save them after a preview
+async fn calibrate(config: HexarConfig, path: Option<&std::path::Path>, steps: CalibrationSteps, yes: bool) -> Result<()> {
+    require_stopped(&config).await?;
+    if config.radar.sensors.is_empty() {
+        anyhow::bail!("No sensors configured in radar.sensors");
+    }
+    
+    // Talking to the modules and the operator blocks
+    let radar = config.radar.clone();
+    let calibrated = tokio::task::spawn_blocking(move || run_calibration(radar, steps)).await??;
+    let Some(radar) = calibrated else {
+        println!("Nothing calibrated, configuration unchanged");
+        return Ok(());
+    };
+    
+    let path_name = path.unwrap_or_else(|| std::path::Path::new("config.toml")).display().to_string();
+    if !yes && !confirm(&format!("Write these results to {}? [y/N] ", path_name))? {
+        println!("Configuration unchanged");
+        return Ok(());
+    }
+    let mut updated = config;
+    updated.radar = radar;
+    updated.save_setting(path, "radar.fusion.sensors", &serde_json::to_value(&updated.radar.fusion.sensors)?).await
+        .context("Failed to update the configuration file")?;
+    updated.save_setting(path, "radar.sensors", &serde_json::to_value(&updated.radar.sensors)?).await
+        .context("Failed to update the configuration file")?;
+    println!("Saved to {}", path_name);
+    Ok(())
+}
+
+/// The calibration steps on the modules of `radar`, printing a preview of the results.
+/// `None` when nothing could be calibrated.
+fn run_calibration(mut radar: hexar::config::RadarConfig, mut steps: CalibrationSteps) -> Result<Option<hexar::config::RadarConfig>> {
+    let mut sensors = Vec::new();
+    for port in &radar.sensors {
+        let mut sensor = SerialRadar::open(port)
+            .with_context(|| format!("Failed to open {}", port.port))?;
+        sensor.reset()?;
+        sensors.push(sensor);
+    }
+    let has_ld2450 = sensors.iter().any(|sensor| sensor.get_model() == SensorModel::Ld2450);
+    let has_ld2412 = sensors.iter().any(|sensor| sensor.get_model() == SensorModel::Ld2412);
+    let mut changed = false;
+    
+    if steps.pose && has_ld2450 {
+        println!("Sensor poses: stand still at each marker while the LD2450 modules see you alone");
+        if steps.markers.is_empty() {
+            loop {
+                let answer = prompt(&format!("Marker {} as x,y in metres, empty when done: ", steps.markers.len() + 1))?;
+                if answer.is_empty() {
+                    break;
+                }
+                match parse_marker(&answer) {
+                    Ok(marker) => steps.markers.push(marker),
+                    Err(e) => println!("  {}", e),
+                }
+            }
+        }
+        let mut calibrator = PoseCalibrator::new();
+        for (i, marker) in steps.markers.iter().enumerate() {
+            prompt(&format!("Stand at marker {} ({:.2}, {:.2}) and press Enter", i + 1, marker.x, marker.y))?;
+            let frames = site_calibration::capture(&mut sensors, steps.duration)?;
+            let recorded = site_calibration::observe_at_marker(&mut calibrator, &frames, *marker);
+            println!("  {} frames with one person", recorded);
+        }
+        let applied = site_calibration::apply_poses(&calibrator, &mut radar);
+        for sensor in sensors.iter().filter(|sensor| sensor.get_model() == SensorModel::Ld2450) {
+            match applied.iter().find(|(antenna_id, _)| *antenna_id == sensor.get_antenna_id()) {
+                Some((antenna_id, estimate)) => println!(
+                    "  Antenna {}: at ({:.2}, {:.2}) m facing {:.1}°, {:.2} m RMS over {} samples",
+                    antenna_id, estimate.pose.position.x, estimate.pose.position.y,
+                    estimate.pose.heading.to_degrees(), estimate.rms_error, estimate.samples,
+                ),
+                None => println!("  Antenna {}: too few samples or markers too close together, pose unchanged", sensor.get_antenna_id()),
+            }
+        }
+        changed |= !applied.is_empty();
+    }
+    
+    if steps.sensitivity && has_ld2412 {
+        println!("LD2412 sensitivity: the room is recorded empty, then with you in it");
+        let ld2412 = |sensor: &&mut SerialRadar| sensor.get_model() == SensorModel::Ld2412;
+        for sensor in sensors.iter_mut().filter(ld2412) {
+            sensor.set_engineering_mode(true)?;
+            sensor.flush_commands(Duration::from_secs(1))?;
+        }
+        let mut calibrators: std::collections::HashMap<u8, SensitivityCalibrator> = std::collections::HashMap::new();
+        for (room, question) in [
+            (RoomState::Empty, "Leave the room empty and press Enter, then leave within a few seconds"),
+            (RoomState::Occupied, "Press Enter, then walk through the room, stopping now and then"),
+        ] {
+            prompt(question)?;
+            let frames = site_calibration::capture(&mut sensors, steps.duration * 2)?;
+            for frame in &frames {
+                calibrators.entry(frame.antenna_id).or_default().observe_frame(room, frame);
+            }
+        }
+        for sensor in sensors.iter_mut().filter(ld2412) {
+            sensor.set_engineering_mode(false)?;
+            sensor.flush_commands(Duration::from_secs(1))?;
+        }
+        for port in radar.sensors.iter_mut() {
+            let Some(calibrator) = calibrators.get(&port.antenna_id) else {
+                continue;
+            };
+            let Some(sensitivity) = calibrator.sensitivity() else {
+                println!("  Antenna {}: no gate energies received, sensitivity unchanged", port.antenna_id);
+                continue;
+            };
+            println!("  Antenna {}: moving {:?}", port.antenna_id, sensitivity.motion);
+            println!("  Antenna {}: stationary {:?}", port.antenna_id, sensitivity.stationary);
+            let (motion, stationary) = calibrator.blind_gates(&sensitivity);
+            if !motion.is_empty() || !stationary.is_empty() {
+                println!("  Antenna {}: you never crossed the threshold on moving gates {:?}, stationary gates {:?}", port.antenna_id, motion, stationary);
+            }
+            port.sensitivity = Some(sensitivity);
+            changed = true;
+        }
+    }
+    
+    Ok(changed.then_some(radar))
+}
+
+/// Firmware version of the module on one antenna
+#[derive(serde::Serialize)]
+struct FirmwareReport {
+    antenna_id: u8,
+    port: String,
+    model: SensorModel,
+    firmware: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+async fn handle_firmware(config: HexarConfig, action: FwAction, output: OutputFormat) -> Result<()> {
+    require_stopped(&config).await?;
+    match action {
+        FwAction::Versions => {
+            let ports = config.radar.sensors.clone();
+            let reports = tokio::task::spawn_blocking(move || ports.iter().map(query_firmware).collect::<Vec<_>>()).await?;
+            if output.print(&reports)? {
+                return Ok(());
+            }
+            if reports.is_empty() {
+                println!("No sensors configured in radar.sensors");
+            }
+            for report in reports {
+                let firmware = match (&report.firmware, &report.error) {
+                    (Some(firmware), _) => firmware.clone(),
+                    (None, error) => format!("unknown ({})", error.as_deref().unwrap_or("no answer")),
+                };
+                println!("Antenna {}: {:?} on {}, firmware {}", report.antenna_id, report.model, report.port, firmware);
+            }
+            Ok(())
+        },
+        FwAction::Flash { antenna, file, force, yes } => {
+            let port = config.radar.sensors.iter()
+                .find(|sensor| sensor.antenna_id == antenna)
+                .cloned()
+                .ok_or_else(|| anyhow::anyhow!("No sensor configured for antenna {}", antenna))?;
+            let image = FirmwareImage::load(&file)
+                .with_context(|| format!("Failed to read {}", file.display()))?;
+            match image.model {
+                Some(model) if model != port.model && !force => {
+                    anyhow::bail!("{} is firmware for the {:?} but antenna {} has an {:?}, --force flashes it anyway", file.display(), model, antenna, port.model);
+                },
+                Some(_) => {},
+                None => println!("Warning: the file name doesn't say which model {} is for", file.display()),
+            }
+            tokio::task::spawn_blocking(move || flash_firmware(port, image, yes)).await?
+        },
+    }
+}
+
+fn query_firmware(port: &SensorPortConfig) -> FirmwareReport {
+    let version = SerialRadar::open(port).and_then(|mut sensor| {
+        sensor.reset()?;
+        sensor.query_firmware_version()
+    });
+    FirmwareReport {
+        antenna_id: port.antenna_id,
+        port: port.port.clone(),
+        model: port.model,
+        firmware: version.as_ref().ok().map(ToString::to_string),
+        error: version.err().map(|e| e.to_string()),
+    }
+}
+
+/// Flash `image` to the module of `port` after warning about a rollback and asking, showing
+/// the progress and the version it boots
+fn flash_firmware(port: SensorPortConfig, image: FirmwareImage, yes: bool) -> Result<()> {
+    use std::io::Write;
+    let antenna = port.antenna_id;
+    let mut sensor = SerialRadar::open(&port)?;
+    sensor.reset()?;
+    let current = sensor.query_firmware_version()
+        .with_context(|| format!("Antenna {} doesn't report its firmware", antenna))?;
+    println!("Antenna {}: {:?} on {}", antenna, port.model, port.port);
+    println!("  Running: {}", current);
+    println!("  File:    {} ({} bytes)", image.version.map_or("unknown".to_string(), |version| version.to_string()), image.len());
+    match firmware::compare_versions(Some(&current), image.version.as_ref()) {
+        Some(std::cmp::Ordering::Less) => println!(
+            "Warning: this rolls antenna {} back to older firmware. The module keeps no copy of {}, flashing that again is the only way back.",
+            antenna, current,
+        ),
+        Some(std::cmp::Ordering::Equal) => println!("Antenna {} already runs this version", antenna),
+        Some(std::cmp::Ordering::Greater) => {},
+        None => println!("Warning: the file name gives no version, this may roll antenna {} back to older firmware", antenna),
+    }
+    println!("Warning: the module erases {} before writing the file and keeps no copy. Keep it powered and connected while flashing, an interrupted flash leaves it unable to start until flashed again.", current);
+    if !yes && !confirm(&format!("Flash antenna {}? [y/N] ", antenna))? {
+        println!("Firmware unchanged");
+        return Ok(());
+    }
+    
+    let total = image.len();
+    firmware::flash_module(&mut sensor, &image, |written| {
+        let filled = written * 30 / total;
+        print!("\r  [{}{}] {:3}% {}/{} bytes", "#".repeat(filled), " ".repeat(30 - filled), written * 100 / total, written, total);
+        let _ = std::io::stdout().flush();
+    }).inspect_err(|_| println!())
+        .with_context(|| format!("Flashing antenna {} failed, it ran {} before", antenna, current))?;
+    println!();
+    
+    std::thread::sleep(firmware::REBOOT_DELAY);
+    sensor.reset()?;
+    match sensor.query_firmware_version() {
+        Ok(version) if image.version.is_some_and(|expected| (expected.major, expected.minor) != (version.major, version.minor)) => {
+            println!("Warning: antenna {} runs {} after flashing, not the version the file name gives", antenna, version);
+        },
+        Ok(version) => println!("Antenna {} now runs {}", antenna, version),
+        Err(e) => println!("Flashed, but antenna {} doesn't report its firmware after rebooting: {}", antenna, e),
+    }
+    Ok(())
+}
+
+/// Fail when an instance is running, it holds the serial ports
+async fn require_stopped(config: &HexarConfig) -> Result<()> {
+    if request_instance(config, SocketRequest::Status).await?.is_some() {
+        anyhow::bail!("Stop the running instance first, it holds the serial ports");
+    }
+    Ok(())
+}
+
+/// Ask the operator and read the answer line, trimmed
+fn prompt(question: &str) -> Result<String> {
+    use std::io::Write;
+    print!("{}", question);
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer)? == 0 {
+        anyhow::bail!("Calibration cancelled");
+    }
+    Ok(answer.trim().to_string())
+}
+
+fn confirm(question: &str) -> Result<bool> {
+    Ok(matches!(prompt(question)?.to_lowercase().as_str(), "y" | "yes"))
+}
+
+async fn monitor_system(config: HexarConfig, follow: bool, query: LogQuery, log_file: Option<PathBuf>, export: Option<PathBuf>, output: OutputFormat) -> Result<()> {
+    #[cfg(not(unix))]
+    if follow {
+        anyhow::bail!("The dashboard reads the control socket, which Windows doesn't have");
+    }
+    #[cfg(unix)]
+    if follow {
+        info!("Starting system monitoring...");
+        let socket = &config.daemon.control_socket;
+        if control_socket::send_request(socket, &SocketRequest::Status).await?.is_none() {
+            println!("Hexar system is not running");
+            return Ok(());
+        }
+        dashboard::run(socket, Duration::from_millis(250)).await
+            .context("Failed to show the monitoring dashboard")?;
+        return Ok(());
+    }
+    
+    // The file given with --log-file is read on its own, it is not rotated
+    let files = match &log_file {
+        Some(path) => vec![path.clone()],
+        None => {
+            let logging = &config.logging;
+            log_query::log_files(&logging.log_directory, log_query::LOG_FILE_NAME, &logging.rotation)
+                .with_context(|| format!("Failed to read the log directory {}", logging.log_directory.display()))?
+        }
+    };
+    if files.is_empty() {
+        println!("No logs in {}", config.logging.log_directory.display());
+        return Ok(());
+    }
+    let entries = tokio::task::spawn_blocking(move || query.run(&files)).await?
+        .context("Failed to read the logs")?;
+    
+    let Some(path) = export else {
+        if !output.print(&entries)? {
+            for entry in &entries {
+                println!("{}", entry);
+            }
+        }
+        return Ok(());
+    };
+    let text = match output {
+        OutputFormat::Table => entries.iter().map(|entry| format!("{}\n", entry)).collect(),
+        OutputFormat::Json => serde_json::to_string_pretty(&entries)?,
+        OutputFormat::Yaml => serde_yaml_ng::to_string(&entries)?,
+    };
+    tokio::fs::write(&path, text).await
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    println!("Exported {} log entries to {}", entries.len(), path.display());
+    Ok(())
+}
+
+/// Unit file for systemd starting this executable with this profile and configuration from
+/// the current directory, printed for `systemctl edit --full` or /etc/systemd/system
+#[cfg(unix)]
+fn print_systemd_unit(config: &HexarConfig, profile: Option<String>, config_path: Option<&std::path::Path>, watchdog: u64, user: Option<String>) -> Result<()> {
+    let unit = UnitFile {
+        executable: std::env::current_exe().context("Failed to find the hexar executable")?,
+        profile,
+        config: config_path.map(std::fs::canonicalize).transpose()
+            .context("Failed to find the configuration file")?,
+        working_directory: std::env::current_dir()?,
+        user,
+        watchdog: Duration::from_secs(watchdog),
+        start_timeout: Duration::from_secs(config.daemon.start_timeout_secs),
+        stop_timeout: Duration::from_secs(config.daemon.stop_timeout_secs),
+    };
+    print!("{}", unit);
+    Ok(())
+}
+
+#[cfg(all(feature = "windows-service", windows))]
+fn run_service_action(config: HexarConfig, profile: Option<&str>, config_path: Option<&std::path::Path>, action: ServiceAction) -> Result<()> {
+    let name = win_service::service_name(profile);
+    match action {
+        ServiceAction::Install => {
+            let mut arguments: Vec<std::ffi::OsString> = Vec::new();
+            if let Some(profile) = profile {
+                arguments.push("--profile".into());
+                arguments.push(profile.into());
+            }
+            if let Some(path) = config_path {
+                arguments.push("--config".into());
+                arguments.push(std::fs::canonicalize(path).context("Failed to find the configuration file")?.into());
+            }
+            arguments.extend(["service".into(), "run".into()]);
+            let executable = std::env::current_exe().context("Failed to find the hexar executable")?;
+            win_service::install(&name, executable, arguments)?;
+            println!("Installed the {} service, start it with `sc start {}`", name, name);
+        },
+        ServiceAction::Uninstall => {
+            win_service::uninstall(&name)?;
+            println!("Uninstalled the {} service", name);
+        },
+        ServiceAction::Run => {
+            // Runs on a thread of the service control manager, stopped through the trigger
+            let shutdown = ShutdownTrigger::default();
+            let stop = shutdown.clone();
+            let runtime = tokio::runtime::Handle::current();
+            tokio::task::block_in_place(|| win_service::run(&name, stop, move || {
+                runtime.block_on(start_system(config, true, false, shutdown))
+            }))?;
+        },
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "windows-service", windows))]
+fn start_service(profile: Option<&str>) -> Result<()> {
+    let name = win_service::service_name(profile);
+    win_service::start(&name).context("Failed to start the service, install it with `service install`")?;
+    println!("Started the {} service", name);
+    Ok(())
+}
+
+#[cfg(all(feature = "windows-service", windows))]
+async fn stop_service(config: &HexarConfig, profile: Option<&str>, timeout: Option<u64>) -> Result<()> {
+    info!("Stopping radar system...");
+    let name = win_service::service_name(profile);
+    let timeout = Duration::from_secs(timeout.unwrap_or(config.daemon.stop_timeout_secs));
+    let service = name.clone();
+    if tokio::task::spawn_blocking(move || win_service::stop(&service, timeout)).await?? {
+        println!("Stopped the {} service", name);
+    } else {
+        println!("The {} service isn't running", name);
+    }
+    Ok(())
+}
+
+#[cfg(all(feature = "windows-service", windows))]
+fn show_service_status(profile: Option<&str>, output: OutputFormat) -> Result<()> {
+    let name = win_service::service_name(profile);
+    let running = win_service::is_running(&name)?;
+    if output.print(&StatusOutput { running, unresponsive_pid: None, status: None })? {
+        return Ok(());
+    }
+    println!("System Status:");
+    if running {
+        println!("  Radar Status: Running as the {} service", name);
+    } else {
+        println!("  Radar Status: Offline");
+    }
+    Ok(())
+}
@@ -0,0 +1,224 @@
+use std::ffi::OsString;
+use std::fs::File;
+use std::io;
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, warn};
+
+use crate::error::{HexarError, HexarResult};
+
+/// How often a stopping process is checked for having exited
+const EXIT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long a process gets to exit after SIGKILL
+const KILL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The process ID of the running instance, written on creation and removed when dropped
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Write the current process ID to `path`, failing while another instance holds it. A
+    /// file left by an instance that didn't exit cleanly is replaced.
+    pub fn create(path: &Path) -> HexarResult<Self> {
+        if let Some(pid) = read_pid(path)? {
+            if is_running(pid) {
+                return Err(HexarError::ResourceUnavailable(format!(
+                    "already running with PID {} ({})",
+                    pid,
+                    path.display()
+                )));
+            }
+            warn!("Removing stale PID file {} of PID {}", path.display(), pid);
+        }
+        // Written in full before it appears, so a reader never sees a partial ID
+        let temp = path.with_extension("pid.tmp");
+        std::fs::write(&temp, format!("{}\n", std::process::id()))?;
+        std::fs::rename(&temp, path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Left alone when another instance replaced it meanwhile
+        if read_pid(&self.path).ok().flatten() == Some(std::process::id()) {
+            if let Err(e) = std::fs::remove_file(&self.path) {
+                warn!("Failed to remove PID file {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+/// Process ID in a PID file, `None` when there is no file
+pub fn read_pid(path: &Path) -> HexarResult<Option<u32>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => content.trim().parse().map(Some).map_err(|_| {
+            HexarError::ConfigurationError(format!("{} doesn't hold a process ID", path.display()))
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether a process with this ID exists, including one we may not signal
+pub fn is_running(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 only checks that the process exists and may be signalled
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+fn send_signal(pid: u32, signal: libc::c_int) -> HexarResult<()> {
+    let pid = libc::pid_t::try_from(pid)
+        .map_err(|_| HexarError::InvalidParameter(format!("invalid PID {}", pid)))?;
+    // SAFETY: kill has no memory safety requirements
+    if unsafe { libc::kill(pid, signal) } == 0 {
+        Ok(())
+    } else {
+        Err(HexarError::SystemError(format!(
+            "failed to signal PID {}: {}",
+            pid,
+            io::Error::last_os_error()
+        )))
+    }
+}
+
+/// How a process was stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopOutcome {
+    /// It had exited already
+    NotRunning,
+    /// It shut down after SIGTERM
+    Graceful,
+    /// It was still running after the timeout and got SIGKILL
+    Killed,
+}
+
+/// Ask a process to shut down with SIGTERM and wait up to `timeout` for it to exit, killing it
+/// when it doesn't
+pub fn stop_process(pid: u32, timeout: Duration) -> HexarResult<StopOutcome> {
+    if !is_running(pid) {
+        return Ok(StopOutcome::NotRunning);
+    }
+    send_signal(pid, libc::SIGTERM)?;
+    if wait_for_exit(pid, timeout) {
+        return Ok(StopOutcome::Graceful);
+    }
+    warn!("PID {} still running after {:?}, killing it", pid, timeout);
+    send_signal(pid, libc::SIGKILL)?;
+    if wait_for_exit(pid, KILL_TIMEOUT) {
+        Ok(StopOutcome::Killed)
+    } else {
+        Err(HexarError::Timeout(format!("PID {} survived SIGKILL", pid)))
+    }
+}
+
+/// Wait up to `timeout` for a process to exit, returning whether it did
+pub fn wait_for_exit(pid: u32, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while is_running(pid) {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+    }
+    true
+}
+
+/// Run this executable again with `args`, in a session of its own without a terminal, its
+/// output appended to `log`
+pub fn spawn_detached(args: &[OsString], log: &Path) -> HexarResult<Child> {
+    let output = File::options().create(true).append(true).open(log)?;
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(args)
+        .env("NO_COLOR", "1")
+        .stdin(Stdio::null())
+        .stdout(output.try_clone()?)
+        .stderr(output);
+    // SAFETY: setsid is async-signal-safe, as required between fork and exec
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setsid() == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    let child = command.spawn()?;
+    debug!("Detached PID {}", child.id());
+    Ok(child)
+}
+
+/// Wait until the detached instance wrote its ID to `pid_file`, failing when it exits first
+/// or takes longer than `timeout`
+pub fn wait_for_start(child: &mut Child, pid_file: &Path, timeout: Duration) -> HexarResult<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if read_pid(pid_file).ok().flatten() == Some(child.id()) {
+            return Ok(());
+        }
+        if let Some(status) = child.try_wait()? {
+            return Err(HexarError::SystemError(format!(
+                "daemon exited during startup with {}",
+                status
+            )));
+        }
+        if Instant::now() >= deadline {
+            return Err(HexarError::Timeout(format!(
+                "daemon didn't start within {:?}",
+                timeout
+            )));
+        }
+        std::thread::sleep(EXIT_POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_file_and_stop() {
+        let path = std::env::temp_dir().join(format!("hexar-{}.pid", uuid::Uuid::new_v4()));
+        let pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(read_pid(&path).unwrap(), Some(std::process::id()));
+        assert!(PidFile::create(&path).is_err());
+        drop(pid_file);
+        assert_eq!(read_pid(&path).unwrap(), None);
+
+        // Ignores SIGTERM, so it takes SIGKILL
+        let mut child = Command::new("sh")
+            .args(["-c", "trap '' TERM; while true; do sleep 0.05; done"])
+            .spawn()
+            .unwrap();
+        let pid = child.id();
+        // Reaped as soon as it exits, a zombie would still count as running
+        let reaper = std::thread::spawn(move || child.wait());
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(is_running(pid));
+        assert_eq!(
+            stop_process(pid, Duration::from_millis(300)).unwrap(),
+            StopOutcome::Killed
+        );
+        reaper.join().unwrap().unwrap();
+        assert_eq!(
+            stop_process(pid, Duration::from_millis(300)).unwrap(),
+            StopOutcome::NotRunning
+        );
+    }
+}
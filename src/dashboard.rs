@@ -0,0 +1,527 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::canvas::{Canvas, Points, Rectangle};
+use ratatui::widgets::{Block, Cell, List, ListItem, Paragraph, Row, Sparkline, Table};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::control_socket::{self, SocketRequest, SocketResponse, TargetReport, ZoneReport};
+use crate::error::{HexarError, HexarResult};
+use crate::monitoring::AlertSeverity;
+use crate::prometheus::MetricsSnapshot;
+use crate::tracker::FallPhase;
+
+/// Refreshes the error rate history keeps
+const HISTORY: usize = 120;
+
+/// Half the width of the target plot when every target is close to the array, in metres
+const MIN_PLOT_RANGE: f64 = 5.0;
+
+/// Terminal dashboard of a running instance, refreshed over its control socket
+#[derive(Debug, Default)]
+pub struct Dashboard {
+    metrics: Option<MetricsSnapshot>,
+    targets: Vec<TargetReport>,
+    zones: Vec<ZoneReport>,
+    rates: Rates,
+    /// Malformed and dropped frames a second at each refresh, oldest first
+    frame_errors: VecDeque<u64>,
+    previous: Option<(Instant, Counters)>,
+    /// Why the last refresh failed
+    problem: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Counters {
+    cycles: u64,
+    malformed_frames: u64,
+    dropped_frames: u64,
+}
+
+impl Counters {
+    fn of(metrics: &MetricsSnapshot) -> Self {
+        Self {
+            cycles: metrics.scan.cycles,
+            malformed_frames: metrics
+                .health
+                .antennas
+                .iter()
+                .map(|antenna| u64::from(antenna.malformed_frames))
+                .sum(),
+            dropped_frames: metrics.scan.dropped_frames + metrics.scan.sink_dropped,
+        }
+    }
+}
+
+/// Counter increases a second between the last two refreshes
+#[derive(Debug, Clone, Copy, Default)]
+struct Rates {
+    cycles: f64,
+    malformed_frames: f64,
+    dropped_frames: f64,
+}
+
+impl Dashboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Show what the instance answered at `now`
+    pub fn update(
+        &mut self,
+        metrics: MetricsSnapshot,
+        targets: Vec<TargetReport>,
+        zones: Vec<ZoneReport>,
+        now: Instant,
+    ) {
+        let counters = Counters::of(&metrics);
+        if let Some((then, previous)) = self.previous {
+            let elapsed = now.duration_since(then).as_secs_f64();
+            if elapsed > 0.0 {
+                // Counters start over when the instance restarts
+                let rate = |now: u64, then: u64| now.saturating_sub(then) as f64 / elapsed;
+                self.rates = Rates {
+                    cycles: rate(counters.cycles, previous.cycles),
+                    malformed_frames: rate(counters.malformed_frames, previous.malformed_frames),
+                    dropped_frames: rate(counters.dropped_frames, previous.dropped_frames),
+                };
+                if self.frame_errors.len() == HISTORY {
+                    self.frame_errors.pop_front();
+                }
+                self.frame_errors.push_back(
+                    (self.rates.malformed_frames + self.rates.dropped_frames).round() as u64,
+                );
+            }
+        }
+        self.previous = Some((now, counters));
+        self.metrics = Some(metrics);
+        self.targets = targets;
+        self.zones = zones;
+        self.problem = None;
+    }
+
+    /// Keep showing the last answer, saying why it is stale
+    pub fn set_problem(&mut self, problem: String) {
+        self.problem = Some(problem);
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let [header, body, alerts] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(10),
+            Constraint::Length(8),
+        ])
+        .areas(frame.area());
+        let [left, plot] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(body);
+        let [antennas, rates] =
+            Layout::vertical([Constraint::Min(5), Constraint::Length(8)]).areas(left);
+
+        frame.render_widget(self.header(), header);
+        self.render_antennas(frame, antennas);
+        self.render_rates(frame, rates);
+        self.render_plot(frame, plot);
+        self.render_alerts(frame, alerts);
+    }
+
+    fn header(&self) -> Paragraph<'_> {
+        let mut spans = vec![Span::styled(
+            " hexar ",
+            Style::new().add_modifier(Modifier::BOLD),
+        )];
+        if let Some(metrics) = &self.metrics {
+            let health = &metrics.health;
+            let state_style = if health.is_healthy() {
+                Style::new().fg(Color::Green)
+            } else {
+                Style::new().fg(Color::Yellow)
+            };
+            spans.push(Span::styled(format!("{:?}", health.state), state_style));
+            spans.push(Span::raw(format!(
+                "  up {}s  {:.1} Hz  {} targets",
+                metrics.uptime.as_secs(),
+                health.scan_rate_hz,
+                health.tracker.confirmed
+            )));
+            if !metrics.scanning {
+                spans.push(Span::styled("  PAUSED", Style::new().fg(Color::Yellow)));
+            }
+            if metrics.emergency_stop {
+                spans.push(Span::styled(
+                    "  EMERGENCY STOP",
+                    Style::new().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ));
+            }
+        }
+        if let Some(problem) = &self.problem {
+            spans.push(Span::styled(
+                format!("  {}", problem),
+                Style::new().fg(Color::Red),
+            ));
+        }
+        spans.push(Span::styled(
+            "  (q to quit)",
+            Style::new().fg(Color::DarkGray),
+        ));
+        Paragraph::new(Line::from(spans))
+    }
+
+    fn render_antennas(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Antennas ");
+        let Some(metrics) = &self.metrics else {
+            frame.render_widget(block, area);
+            return;
+        };
+        let rows = metrics.health.antennas.iter().map(|antenna| {
+            let (link, link_style) = if antenna.excluded {
+                ("excluded", Style::new().fg(Color::Red))
+            } else if antenna.connected {
+                ("up", Style::new().fg(Color::Green))
+            } else {
+                ("down", Style::new().fg(Color::Red))
+            };
+            let self_test = match antenna.passed_self_test {
+                Some(true) => "passed",
+                Some(false) => "failed",
+                None => "-",
+            };
+            let frames = antenna.frames_parsed + antenna.malformed_frames;
+            let malformed = if frames == 0 {
+                0.0
+            } else {
+                100.0 * f64::from(antenna.malformed_frames) / f64::from(frames)
+            };
+            let load = metrics
+                .antenna_loads
+                .iter()
+                .find(|load| load.antenna_id == antenna.antenna_id)
+                .map(|load| format!("{}/{}", load.targets, load.capacity))
+                .unwrap_or_else(|| "-".to_string());
+            Row::new(vec![
+                Cell::from(antenna.antenna_id.to_string()),
+                Cell::from(link).style(link_style),
+                Cell::from(self_test),
+                Cell::from(antenna.frames_parsed.to_string()),
+                Cell::from(format!("{:.1}%", malformed)),
+                Cell::from(load),
+            ])
+        });
+        let widths = [
+            Constraint::Length(3),
+            Constraint::Length(9),
+            Constraint::Length(7),
+            Constraint::Length(9),
+            Constraint::Length(9),
+            Constraint::Length(7),
+        ];
+        let table = Table::new(rows, widths)
+            .header(
+                Row::new(["ID", "Link", "Test", "Frames", "Malformed", "Load"])
+                    .style(Style::new().add_modifier(Modifier::BOLD)),
+            )
+            .block(block);
+        frame.render_widget(table, area);
+    }
+
+    fn render_rates(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Rates ");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+        let [text, history] =
+            Layout::vertical([Constraint::Length(3), Constraint::Min(1)]).areas(inner);
+        let errors_per_minute = self
+            .metrics
+            .as_ref()
+            .map_or(0.0, |metrics| metrics.errors.error_rate_per_minute);
+        let lines = vec![
+            Line::from(format!(
+                "cycles {:.1}/s   errors {:.1}/min",
+                self.rates.cycles, errors_per_minute
+            )),
+            Line::from(format!(
+                "malformed frames {:.1}/s   dropped {:.1}/s",
+                self.rates.malformed_frames, self.rates.dropped_frames
+            )),
+            Line::from(Span::styled(
+                "frame errors a second:",
+                Style::new().fg(Color::DarkGray),
+            )),
+        ];
+        frame.render_widget(Paragraph::new(lines), text);
+        // Newest on the right, as many as fit
+        let shown = self.frame_errors.len().min(usize::from(history.width));
+        let data: Vec<u64> = self
+            .frame_errors
+            .iter()
+            .skip(self.frame_errors.len() - shown)
+            .copied()
+            .collect();
+        frame.render_widget(
+            Sparkline::default()
+                .data(&data)
+                .style(Style::new().fg(Color::Red)),
+            history,
+        );
+    }
+
+    /// Half the width of the square the plot shows around the array, fitting every target
+    /// and zone
+    fn plot_range(&self) -> f64 {
+        let targets = self
+            .targets
+            .iter()
+            .flat_map(|target| [target.position.x, target.position.y]);
+        let zones = self.zones.iter().flat_map(|report| {
+            [
+                report.zone.min.x,
+                report.zone.min.y,
+                report.zone.max.x,
+                report.zone.max.y,
+            ]
+        });
+        targets
+            .chain(zones)
+            .map(|coordinate| f64::from(coordinate.abs()) * 1.1)
+            .fold(MIN_PLOT_RANGE, f64::max)
+    }
+
+    fn render_plot(&self, frame: &mut Frame, area: Rect) {
+        let range = self.plot_range();
+        let title = format!(" Targets ({} m) ", range.round());
+        let canvas = Canvas::default()
+            .block(Block::bordered().title(title))
+            .marker(Marker::Braille)
+            .x_bounds([-range, range])
+            .y_bounds([-range, range])
+            .paint(|ctx| {
+                for report in &self.zones {
+                    let zone = &report.zone;
+                    let color = if report.targets.is_empty() {
+                        Color::DarkGray
+                    } else {
+                        Color::Cyan
+                    };
+                    ctx.draw(&Rectangle {
+                        x: f64::from(zone.min.x),
+                        y: f64::from(zone.min.y),
+                        width: f64::from(zone.max.x - zone.min.x),
+                        height: f64::from(zone.max.y - zone.min.y),
+                        color,
+                    });
+                    ctx.print(
+                        f64::from(zone.min.x),
+                        f64::from(zone.max.y),
+                        Span::styled(zone.name.clone(), Style::new().fg(color)),
+                    );
+                }
+                // The array
+                ctx.draw(&Points {
+                    coords: &[(0.0, 0.0)],
+                    color: Color::White,
+                });
+                ctx.layer();
+                for target in &self.targets {
+                    let color = match target.fall_phase {
+                        FallPhase::Normal => Color::Green,
+                        FallPhase::Suspected | FallPhase::Confirmed => Color::Red,
+                    };
+                    let (x, y) = (f64::from(target.position.x), f64::from(target.position.y));
+                    ctx.draw(&Points {
+                        coords: &[(x, y)],
+                        color,
+                    });
+                    ctx.print(
+                        x,
+                        y,
+                        Span::styled(format!("#{}", target.id), Style::new().fg(color)),
+                    );
+                }
+            });
+        frame.render_widget(canvas, area);
+    }
+
+    fn render_alerts(&self, frame: &mut Frame, area: Rect) {
+        let mut alerts: Vec<_> = self
+            .metrics
+            .iter()
+            .flat_map(|metrics| &metrics.active_alerts)
+            .collect();
+        alerts.sort_by_key(|alert| std::cmp::Reverse(alert.timestamp));
+        let items = alerts.into_iter().map(|alert| {
+            let color = match alert.severity {
+                AlertSeverity::Info => Color::Blue,
+                AlertSeverity::Warning => Color::Yellow,
+                _ => Color::Red,
+            };
+            ListItem::new(Line::from(vec![
+                Span::raw(alert.timestamp.format("%H:%M:%S ").to_string()),
+                Span::styled(format!("{:?}", alert.severity), Style::new().fg(color)),
+                Span::raw(format!(" [{}] {}", alert.component, alert.message)),
+            ]))
+        });
+        frame.render_widget(
+            List::new(items).block(Block::bordered().title(" Alerts ")),
+            area,
+        );
+    }
+
+    /// Ask the instance on `socket` for everything shown
+    async fn refresh(&mut self, socket: &Path) -> HexarResult<()> {
+        let metrics = match request(socket, SocketRequest::Metrics).await? {
+            Some(SocketResponse::Metrics { metrics }) => *metrics,
+            Some(other) => return Err(unexpected(other)),
+            None => {
+                self.set_problem(format!("no instance running on {}", socket.display()));
+                return Ok(());
+            }
+        };
+        let targets = match request(socket, SocketRequest::Targets).await? {
+            Some(SocketResponse::Targets { targets }) => targets,
+            Some(other) => return Err(unexpected(other)),
+            None => Vec::new(),
+        };
+        let zones = match request(socket, SocketRequest::Zones).await? {
+            Some(SocketResponse::Zones { zones }) => zones,
+            Some(other) => return Err(unexpected(other)),
+            None => Vec::new(),
+        };
+        self.update(metrics, targets, zones, Instant::now());
+        Ok(())
+    }
+}
+
+async fn request(socket: &Path, request: SocketRequest) -> HexarResult<Option<SocketResponse>> {
+    control_socket::send_request(socket, &request).await
+}
+
+fn unexpected(response: SocketResponse) -> HexarError {
+    HexarError::CommunicationError(format!("unexpected answer {:?}", response))
+}
+
+/// Show the dashboard of the instance on `socket`, refreshed every `refresh`, until q, Esc
+/// or Ctrl+C
+pub async fn run(socket: &Path, refresh: Duration) -> HexarResult<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = show(&mut terminal, socket, refresh).await;
+    ratatui::restore();
+    result
+}
+
+async fn show(terminal: &mut DefaultTerminal, socket: &Path, refresh: Duration) -> HexarResult<()> {
+    let mut dashboard = Dashboard::new();
+    let mut next_refresh = Instant::now();
+    loop {
+        if Instant::now() >= next_refresh {
+            if let Err(e) = dashboard.refresh(socket).await {
+                dashboard.set_problem(e.to_string());
+            }
+            next_refresh = Instant::now() + refresh;
+        }
+        terminal.draw(|frame| dashboard.render(frame))?;
+
+        let wait = next_refresh.saturating_duration_since(Instant::now());
+        let key = tokio::task::block_in_place(|| -> std::io::Result<_> {
+            if !event::poll(wait)? {
+                return Ok(None);
+            }
+            Ok(match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => Some(key),
+                _ => None,
+            })
+        })?;
+        if let Some(key) = key {
+            let quit = matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                || (key.code == KeyCode::Char('c')
+                    && key.modifiers.contains(KeyModifiers::CONTROL));
+            if quit {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RadarConfig;
+    use crate::control::Zone;
+    use crate::monitoring::ErrorMetrics;
+    use crate::radar_controller::RadarController;
+    use nalgebra::Vector2;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    #[test]
+    fn test_render_dashboard() {
+        let controller = RadarController::new(RadarConfig::default()).unwrap();
+        let snapshot = |cycles| {
+            let mut scan = controller.get_scan_metrics().clone();
+            scan.cycles = cycles;
+            MetricsSnapshot {
+                uptime: Duration::from_secs(90),
+                scanning: false,
+                health: controller.health(),
+                scan,
+                antenna_loads: Vec::new(),
+                emergency_stop: false,
+                active_alerts: Vec::new(),
+                errors: ErrorMetrics {
+                    total_errors: 0,
+                    error_rate_per_minute: 0.0,
+                    recent_errors: Vec::new(),
+                    critical_errors: 0,
+                },
+            }
+        };
+        let target = TargetReport {
+            id: 7,
+            antenna_id: 0,
+            position: Vector2::new(1.0, 12.0),
+            velocity: Vector2::zeros(),
+            confidence: 0.9,
+            class: Default::default(),
+            fall_phase: Default::default(),
+            fall_probability: 0.0,
+        };
+        let zone = ZoneReport {
+            zone: Zone {
+                name: "bed".to_string(),
+                min: Vector2::new(-2.0, 1.0),
+                max: Vector2::new(0.0, 3.0),
+            },
+            targets: Vec::new(),
+        };
+
+        let mut dashboard = Dashboard::new();
+        let start = Instant::now();
+        dashboard.update(snapshot(10), Vec::new(), Vec::new(), start);
+        dashboard.update(
+            snapshot(30),
+            vec![target],
+            vec![zone],
+            start + Duration::from_secs(2),
+        );
+        assert_eq!(dashboard.rates.cycles, 10.0);
+        assert!(dashboard.plot_range() > 13.0);
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|frame| dashboard.render(frame)).unwrap();
+        let screen: String = terminal
+            .backend()
+            .buffer()
+            .content()
+            .iter()
+            .map(|cell| cell.symbol())
+            .collect();
+        for expected in ["PAUSED", "Antennas", "cycles 10.0/s", "#7", "bed", "Alerts"] {
+            assert!(screen.contains(expected), "{} missing", expected);
+        }
+    }
+}
@@ -0,0 +1,564 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+
+use crate::config::HexarConfig;
+use crate::error::HexarResult;
+use crate::health::{AntennaHealth, HealthReport};
+use crate::safety::{SafetyFindings, SafetyManager};
+use crate::serial_radar::{SensorPortConfig, SerialRadar};
+use crate::tracker::{Measurement, MultiTargetTracker, TrackerConfig};
+
+/// How long a module gets to answer the firmware query and send target data
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long each port is read to judge its link
+const SERIAL_SAMPLE: Duration = Duration::from_secs(1);
+
+const READ_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Share of malformed frames above which a link is reported as noisy
+const MAX_MALFORMED_SHARE: f32 = 0.05;
+
+/// Frames of the walk the tracker check simulates, 10 a second
+const WALK_FRAMES: u32 = 20;
+
+/// Part of the system `hexar diagnose --component` checks on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Component {
+    /// The module on one antenna, `antenna:<id>`
+    Antenna(u8),
+    Power,
+    Cooling,
+    /// Every configured sensor port
+    Serial,
+    Tracker,
+}
+
+impl Component {
+    pub const NAMES: &'static str = "antenna:<id>, power, cooling, serial, tracker";
+}
+
+impl FromStr for Component {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some(id) = s.strip_prefix("antenna:") {
+            return id
+                .parse()
+                .map(Component::Antenna)
+                .map_err(|_| format!("'{}' isn't an antenna number", id));
+        }
+        match s {
+            "power" => Ok(Component::Power),
+            "cooling" => Ok(Component::Cooling),
+            "serial" => Ok(Component::Serial),
+            "tracker" => Ok(Component::Tracker),
+            _ => Err(format!(
+                "unknown component '{}', expected one of {}",
+                s,
+                Component::NAMES
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Component::Antenna(id) => write!(f, "antenna:{}", id),
+            Component::Power => f.write_str("power"),
+            Component::Cooling => f.write_str("cooling"),
+            Component::Serial => f.write_str("serial"),
+            Component::Tracker => f.write_str("tracker"),
+        }
+    }
+}
+
+impl From<Component> for String {
+    fn from(component: Component) -> Self {
+        component.to_string()
+    }
+}
+
+impl TryFrom<String> for Component {
+    type Error = String;
+
+    fn try_from(name: String) -> std::result::Result<Self, Self::Error> {
+        name.parse()
+    }
+}
+
+/// Outcome of one check, ordered from best to worst
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Checks run on one component
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentReport {
+    pub component: Component,
+    pub timestamp: DateTime<Utc>,
+    /// Whether the instance was running, its own health answered the checks it could
+    pub running: bool,
+    pub checks: Vec<Check>,
+}
+
+impl ComponentReport {
+    /// Worst outcome of the checks
+    pub fn status(&self) -> CheckStatus {
+        self.checks
+            .iter()
+            .map(|check| check.status)
+            .max()
+            .unwrap_or(CheckStatus::Pass)
+    }
+
+    pub fn passed(&self) -> bool {
+        self.status() != CheckStatus::Fail
+    }
+}
+
+/// Run the checks of `component`. Serial ports are busy while an instance runs, its `running`
+/// health stands in for opening them.
+pub async fn diagnose(
+    component: Component,
+    config: &HexarConfig,
+    safety: &SafetyManager,
+    running: Option<&HealthReport>,
+) -> Result<ComponentReport> {
+    let checks = match component {
+        Component::Antenna(id) => check_antenna(id, config, safety, running).await?,
+        Component::Power => {
+            let (power, findings) = safety.check_power().await?;
+            let readings = format!(
+                "{:.1} V (nominal {:.1} V), {:.1} W of {:.1} W",
+                power.voltage_actual,
+                power.voltage_nominal,
+                power.power_consumption,
+                config.safety.power_limits.max_power_watts
+            );
+            safety_checks("power limits", readings, findings)
+        }
+        Component::Cooling => {
+            let (cooling, findings) = safety.check_cooling().await?;
+            let readings = format!(
+                "{:.1}°C inside, {:.1}°C ambient, fan at {:.0} rpm, filter {:?}",
+                cooling.internal_temperature,
+                cooling.ambient_temperature,
+                cooling.fan_speed,
+                cooling.filter_status
+            );
+            safety_checks("temperature limits", readings, findings)
+        }
+        Component::Serial => check_serial(config, running).await?,
+        Component::Tracker => check_tracker(config, running),
+    };
+    Ok(ComponentReport {
+        component,
+        timestamp: Utc::now(),
+        running: running.is_some(),
+        checks,
+    })
+}
+
+/// One passing check showing the readings when the safety manager found nothing wrong, a
+/// check for each finding otherwise
+fn safety_checks(name: &str, readings: String, findings: SafetyFindings) -> Vec<Check> {
+    if findings.issues.is_empty() && findings.warnings.is_empty() {
+        return vec![Check::new(name, CheckStatus::Pass, readings)];
+    }
+    let issues = findings
+        .issues
+        .into_iter()
+        .map(|issue| Check::new(name, CheckStatus::Fail, issue));
+    let warnings = findings
+        .warnings
+        .into_iter()
+        .map(|warning| Check::new(name, CheckStatus::Warn, warning));
+    issues.chain(warnings).collect()
+}
+
+async fn check_antenna(
+    id: u8,
+    config: &HexarConfig,
+    safety: &SafetyManager,
+    running: Option<&HealthReport>,
+) -> Result<Vec<Check>> {
+    let antenna_count = config.radar.antenna_count;
+    if id >= antenna_count {
+        return Ok(vec![Check::new(
+            "configured",
+            CheckStatus::Fail,
+            format!(
+                "the array has antennas 0 to {}",
+                antenna_count.saturating_sub(1)
+            ),
+        )]);
+    }
+    let mut checks = Vec::new();
+    match safety.check_antenna(id).await? {
+        Some((status, findings)) => {
+            let readings = format!(
+                "{:.1}°C, {:.1} W",
+                status.temperature_celsius, status.power_consumption_watts
+            );
+            checks.extend(safety_checks("safety limits", readings, findings));
+        }
+        None => checks.push(Check::new(
+            "safety limits",
+            CheckStatus::Warn,
+            "not monitored by the safety systems",
+        )),
+    }
+
+    let Some(sensor) = config
+        .radar
+        .sensors
+        .iter()
+        .find(|sensor| sensor.antenna_id == id)
+    else {
+        checks.push(Check::new(
+            "sensor",
+            CheckStatus::Warn,
+            "no module configured, the antenna isn't scanned",
+        ));
+        return Ok(checks);
+    };
+    match running.and_then(|health| health.antennas.iter().find(|a| a.antenna_id == id)) {
+        Some(antenna) => checks.extend(running_link_checks(antenna)),
+        None if running.is_some() => checks.push(Check::new(
+            "link",
+            CheckStatus::Fail,
+            "the running instance doesn't read this antenna",
+        )),
+        None => checks.extend(self_test_checks(sensor.clone()).await?),
+    }
+    Ok(checks)
+}
+
+/// Firmware and target data checks of the module on `sensor`, opening its port
+async fn self_test_checks(sensor: SensorPortConfig) -> Result<Vec<Check>> {
+    let port = sensor.port.clone();
+    let mut radar = match SerialRadar::open(&sensor) {
+        Ok(radar) => radar,
+        Err(e) => return Ok(vec![Check::new("port", CheckStatus::Fail, e.to_string())]),
+    };
+    let result = tokio::task::spawn_blocking(move || radar.self_test(SELF_TEST_TIMEOUT)).await?;
+    let mut checks = vec![Check::new(
+        "port",
+        CheckStatus::Pass,
+        format!("{} opened", port),
+    )];
+    checks.push(match &result.firmware {
+        Some(firmware) => Check::new("firmware", CheckStatus::Pass, firmware.clone()),
+        None => Check::new(
+            "firmware",
+            CheckStatus::Fail,
+            result
+                .error
+                .clone()
+                .unwrap_or_else(|| "no answer to the version query".to_string()),
+        ),
+    });
+    checks.push(match result.detected_model {
+        Some(model) if model == result.model => Check::new(
+            "target data",
+            CheckStatus::Pass,
+            format!("{:?} frames", model),
+        ),
+        Some(model) => Check::new(
+            "target data",
+            CheckStatus::Fail,
+            format!(
+                "{:?} frames from a port configured for {:?}",
+                model, result.model
+            ),
+        ),
+        None => Check::new(
+            "target data",
+            CheckStatus::Fail,
+            format!("no frames within {} s", SELF_TEST_TIMEOUT.as_secs()),
+        ),
+    });
+    Ok(checks)
+}
+
+/// Link, self-test and frame checks of an antenna the running instance reads
+fn running_link_checks(antenna: &AntennaHealth) -> Vec<Check> {
+    let link = if antenna.excluded {
+        Check::new(
+            "link",
+            CheckStatus::Fail,
+            "excluded after failing the self-test",
+        )
+    } else if antenna.connected {
+        Check::new("link", CheckStatus::Pass, "connected")
+    } else {
+        Check::new(
+            "link",
+            CheckStatus::Fail,
+            format!(
+                "down after {} reconnect attempts: {}",
+                antenna.reconnect_attempts,
+                antenna.link_error.as_deref().unwrap_or("unknown error")
+            ),
+        )
+    };
+    let self_test = match antenna.passed_self_test {
+        Some(true) => Check::new("self-test", CheckStatus::Pass, "passed at startup"),
+        Some(false) => Check::new("self-test", CheckStatus::Fail, "failed at startup"),
+        None => Check::new("self-test", CheckStatus::Warn, "not run"),
+    };
+    vec![
+        link,
+        self_test,
+        frame_check(antenna.frames_parsed, antenna.malformed_frames),
+    ]
+}
+
+fn frame_check(parsed: u32, malformed: u32) -> Check {
+    let total = parsed + malformed;
+    if total == 0 {
+        return Check::new("frames", CheckStatus::Warn, "no frames received");
+    }
+    let share = malformed as f32 / total as f32;
+    let status = if share > MAX_MALFORMED_SHARE {
+        CheckStatus::Warn
+    } else {
+        CheckStatus::Pass
+    };
+    Check::new(
+        "frames",
+        status,
+        format!("{} parsed, {:.1}% malformed", parsed, share * 100.0),
+    )
+}
+
+async fn check_serial(config: &HexarConfig, running: Option<&HealthReport>) -> Result<Vec<Check>> {
+    if config.radar.sensors.is_empty() {
+        return Ok(vec![Check::new(
+            "sensors",
+            CheckStatus::Warn,
+            "no sensor ports configured",
+        )]);
+    }
+    let mut checks = Vec::new();
+    for sensor in &config.radar.sensors {
+        let name = format!("antenna {} on {}", sensor.antenna_id, sensor.port);
+        if let Some(health) = running {
+            let check = match health
+                .antennas
+                .iter()
+                .find(|a| a.antenna_id == sensor.antenna_id)
+            {
+                Some(antenna) if antenna.connected => {
+                    let frames = frame_check(antenna.frames_parsed, antenna.malformed_frames);
+                    Check::new(name, frames.status, frames.detail)
+                }
+                Some(antenna) => Check::new(
+                    name,
+                    CheckStatus::Fail,
+                    antenna
+                        .link_error
+                        .clone()
+                        .unwrap_or_else(|| "link down".to_string()),
+                ),
+                None => Check::new(name, CheckStatus::Fail, "not read by the running instance"),
+            };
+            checks.push(check);
+            continue;
+        }
+        // Names like COM3 aren't paths
+        if sensor.port.starts_with('/') && !Path::new(&sensor.port).exists() {
+            checks.push(Check::new(name, CheckStatus::Fail, "no such device"));
+            continue;
+        }
+        let mut radar = match SerialRadar::open(sensor) {
+            Ok(radar) => radar,
+            Err(e) => {
+                checks.push(Check::new(name, CheckStatus::Fail, e.to_string()));
+                continue;
+            }
+        };
+        let sampled = tokio::task::spawn_blocking(move || -> HexarResult<_> {
+            radar.reset()?;
+            let deadline = Instant::now() + SERIAL_SAMPLE;
+            while Instant::now() < deadline {
+                radar.read_frame()?;
+                std::thread::sleep(READ_INTERVAL);
+            }
+            Ok(radar.get_parser_stats())
+        })
+        .await?;
+        checks.push(match sampled {
+            Ok(stats) => {
+                let frames = frame_check(stats.frames_parsed, stats.malformed_frames);
+                Check::new(name, frames.status, frames.detail)
+            }
+            Err(e) => Check::new(name, CheckStatus::Fail, e.to_string()),
+        });
+    }
+    Ok(checks)
+}
+
+fn check_tracker(config: &HexarConfig, running: Option<&HealthReport>) -> Vec<Check> {
+    let tracking = &config.radar.tracking;
+    let mut checks = vec![match tracker_config_problem(tracking) {
+        Some(problem) => Check::new("configuration", CheckStatus::Fail, problem),
+        None => Check::new("configuration", CheckStatus::Pass, "consistent"),
+    }];
+
+    // Someone walking across antenna 0 at 1 m/s should be confirmed and keep one track
+    let mut tracker = MultiTargetTracker::with_config(config.radar.antenna_count, tracking.clone());
+    let start = Instant::now();
+    let mut confirmed_after = None;
+    for frame in 0..WALK_FRAMES {
+        let position = Vector2::new(-1.0 + frame as f32 * 0.1, 2.0);
+        let timestamp = start + Duration::from_millis(u64::from(frame) * 100);
+        if let Err(e) = tracker.process_frame(0, &[Measurement::new(position)], timestamp) {
+            checks.push(Check::new(
+                "simulated walk",
+                CheckStatus::Fail,
+                e.to_string(),
+            ));
+            return checks;
+        }
+        if confirmed_after.is_none() && !tracker.get_confirmed_targets().is_empty() {
+            confirmed_after = Some(frame + 1);
+        }
+    }
+    let tracks = tracker.get_target_count();
+    checks.push(match confirmed_after {
+        Some(frames) if tracks == 1 => Check::new(
+            "simulated walk",
+            CheckStatus::Pass,
+            format!("confirmed after {} frames, one track", frames),
+        ),
+        Some(_) => Check::new(
+            "simulated walk",
+            CheckStatus::Warn,
+            format!("one person became {} tracks", tracks),
+        ),
+        None => Check::new(
+            "simulated walk",
+            CheckStatus::Fail,
+            format!("not confirmed within {} frames", WALK_FRAMES),
+        ),
+    });
+
+    if let Some(health) = running {
+        let tracker = &health.tracker;
+        checks.push(Check::new(
+            "running tracker",
+            CheckStatus::Pass,
+            format!(
+                "{} targets, {} confirmed, {} falling",
+                tracker.targets, tracker.confirmed, tracker.falling
+            ),
+        ));
+    }
+    checks
+}
+
+/// Why `config` can't track anyone, `None` when it can
+fn tracker_config_problem(config: &TrackerConfig) -> Option<String> {
+    if config.confirm_hits == 0 || config.confirm_hits > config.confirm_window {
+        return Some(format!(
+            "confirm_hits {} must be between 1 and confirm_window {}",
+            config.confirm_hits, config.confirm_window
+        ));
+    }
+    for (name, value) in [
+        ("association_gate_m", config.association_gate_m),
+        ("mahalanobis_gate", config.mahalanobis_gate),
+        ("measurement_noise", config.measurement_noise),
+        ("process_noise", config.process_noise),
+        ("max_speed", config.max_speed),
+    ] {
+        if value.is_nan() || value <= 0.0 {
+            return Some(format!("{} must be positive, is {}", name, value));
+        }
+    }
+    if config.max_targets_per_antenna == 0 {
+        return Some("max_targets_per_antenna is 0, nothing can be tracked".to_string());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_names() {
+        for name in ["antenna:3", "power", "cooling", "serial", "tracker"] {
+            let component: Component = name.parse().unwrap();
+            assert_eq!(component.to_string(), name);
+        }
+        assert!("antenna:x".parse::<Component>().is_err());
+        assert!("fans".parse::<Component>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_diagnose_components() {
+        let config = HexarConfig::default();
+        let safety = SafetyManager::new(config.safety.clone()).unwrap();
+
+        let report = diagnose(Component::Tracker, &config, &safety, None)
+            .await
+            .unwrap();
+        assert_eq!(report.status(), CheckStatus::Pass, "{:?}", report.checks);
+
+        let mut broken = config.clone();
+        broken.radar.tracking.confirm_hits = 0;
+        let report = diagnose(Component::Tracker, &broken, &safety, None)
+            .await
+            .unwrap();
+        assert!(!report.passed());
+
+        let outside = Component::Antenna(config.radar.antenna_count);
+        let report = diagnose(outside, &config, &safety, None).await.unwrap();
+        assert_eq!(report.checks[0].status, CheckStatus::Fail);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["component"],
+            format!("antenna:{}", config.radar.antenna_count)
+        );
+
+        let report = diagnose(Component::Cooling, &config, &safety, None)
+            .await
+            .unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["component"], "cooling");
+        assert_eq!(json["checks"][0]["name"], "temperature limits");
+    }
+}
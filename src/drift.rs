@@ -0,0 +1,319 @@
+use std::time::Instant;
+
+use log::info;
+
+use crate::scanner::{FrequencyRange, ScanResult};
+
+/// Smallest frequency difference in MHz at which a result still matches a known emitter
+const MIN_MATCH_TOLERANCE_MHZ: f32 = 0.05;
+/// Smallest initial step in MHz when refining around a followed emitter
+const MIN_FOLLOW_STEP_MHZ: f32 = 0.02;
+/// Weight of the newest measurement in the drift rate average
+const DRIFT_RATE_SMOOTHING: f32 = 0.3;
+/// Events kept until [`DriftTracker::take_events`] is called
+const MAX_PENDING_EVENTS: usize = 256;
+
+/// A signal followed across scan cycles
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownEmitter {
+    pub id: u64,
+    /// Latest refined frequency in MHz
+    pub frequency: f32,
+    /// Frequency when first seen
+    pub initial_frequency: f32,
+    pub strength: f32,
+    /// Smoothed drift in MHz per second
+    pub drift_rate: f32,
+    pub first_seen: Instant,
+    pub last_seen: Instant,
+    /// Consecutive cycles covering the emitter without finding it
+    pub missed: u32,
+    /// Frequency of the last reported drift
+    reported_frequency: f32,
+}
+
+impl KnownEmitter {
+    /// Total drift since the emitter was first seen in MHz
+    pub fn get_drift(&self) -> f32 {
+        self.frequency - self.initial_frequency
+    }
+
+    /// Expected frequency at `now` from the drift rate
+    pub fn predict(&self, now: Instant) -> f32 {
+        self.frequency + self.drift_rate * now.duration_since(self.last_seen).as_secs_f32()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DriftEvent {
+    Appeared {
+        id: u64,
+        frequency: f32,
+    },
+    /// Moved by at least the drift threshold since the last report
+    Drifted {
+        id: u64,
+        from: f32,
+        to: f32,
+        rate: f32,
+    },
+    /// Not found in enough consecutive cycles, the emitter is forgotten
+    Disappeared {
+        id: u64,
+        last_frequency: f32,
+    },
+}
+
+/// Follows persistent signals between scan cycles, so refinements can start from where the
+/// emitter was last seen with a step sized to its drift instead of from the coarse sweep peak
+#[derive(Debug, Clone)]
+pub struct DriftTracker {
+    /// Movement in MHz that is reported as drift
+    drift_threshold: f32,
+    /// Cycles an emitter may be missing before it is reported gone
+    max_missed: u32,
+    emitters: Vec<KnownEmitter>,
+    events: Vec<DriftEvent>,
+    next_id: u64,
+}
+
+impl Default for DriftTracker {
+    /// Drift reported from 10 kHz, emitters dropped after 3 missed cycles
+    fn default() -> Self {
+        Self::new(0.01, 3)
+    }
+}
+
+impl DriftTracker {
+    pub fn new(drift_threshold_mhz: f32, max_missed: u32) -> Self {
+        Self {
+            drift_threshold: drift_threshold_mhz,
+            max_missed: max_missed.max(1),
+            emitters: Vec::new(),
+            events: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    pub fn get_emitters(&self) -> &[KnownEmitter] {
+        &self.emitters
+    }
+
+    /// Events since the last call, oldest first
+    pub fn take_events(&mut self) -> Vec<DriftEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Known emitter a sweep peak at `frequency` belongs to, results within a step of the scan
+    /// range match
+    pub fn find(
+        &self,
+        frequency: f32,
+        range: &FrequencyRange,
+        now: Instant,
+    ) -> Option<&KnownEmitter> {
+        let tolerance = range.step.max(MIN_MATCH_TOLERANCE_MHZ);
+        self.emitters
+            .iter()
+            .map(|emitter| (emitter, (emitter.predict(now) - frequency).abs()))
+            .filter(|(_, distance)| *distance <= tolerance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(emitter, _)| emitter)
+    }
+
+    /// Frequency and initial step to refine a sweep peak at `frequency` with: the predicted
+    /// frequency and a step covering twice the expected drift for known emitters, the peak and
+    /// half the range step otherwise
+    pub fn refinement_start(
+        &self,
+        frequency: f32,
+        range: &FrequencyRange,
+        now: Instant,
+    ) -> (f32, f32) {
+        let coarse_step = range.step * 0.5;
+        match self.find(frequency, range, now) {
+            Some(emitter) => {
+                let expected = (emitter.predict(now) - emitter.frequency).abs();
+                let step = (expected * 2.0).max(MIN_FOLLOW_STEP_MHZ).min(coarse_step);
+                (emitter.predict(now), step)
+            }
+            None => (frequency, coarse_step),
+        }
+    }
+
+    /// Match a cycle's results over `range` to the known emitters. Unmatched results become new
+    /// emitters, known ones inside the range without a result count as missed.
+    pub fn update(&mut self, range: &FrequencyRange, results: &[ScanResult], now: Instant) {
+        let tolerance = range.step.max(MIN_MATCH_TOLERANCE_MHZ);
+        let mut matched = vec![false; self.emitters.len()];
+
+        // Strongest results claim their emitter first
+        let mut order: Vec<&ScanResult> = results.iter().collect();
+        order.sort_by(|a, b| b.strength.total_cmp(&a.strength));
+
+        for result in order {
+            let nearest = self
+                .emitters
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| !matched[*index])
+                .map(|(index, emitter)| (index, (emitter.predict(now) - result.frequency).abs()))
+                .filter(|(_, distance)| *distance <= tolerance)
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(index, _)| index);
+
+            match nearest {
+                Some(index) => {
+                    matched[index] = true;
+                    self.follow(index, result, now);
+                }
+                None => {
+                    let id = self.next_id;
+                    self.next_id += 1;
+                    self.emitters.push(KnownEmitter {
+                        id,
+                        frequency: result.frequency,
+                        initial_frequency: result.frequency,
+                        strength: result.strength,
+                        drift_rate: 0.0,
+                        first_seen: now,
+                        last_seen: now,
+                        missed: 0,
+                        reported_frequency: result.frequency,
+                    });
+                    self.push_event(DriftEvent::Appeared {
+                        id,
+                        frequency: result.frequency,
+                    });
+                }
+            }
+        }
+
+        let in_range = |frequency: f32| (range.start..=range.end).contains(&frequency);
+        let mut index = 0;
+        self.emitters.retain_mut(|emitter| {
+            let seen = matched.get(index).copied().unwrap_or(true);
+            index += 1;
+            if seen || !in_range(emitter.frequency) {
+                return true;
+            }
+
+            emitter.missed += 1;
+            emitter.missed < self.max_missed || {
+                info!(
+                    "Emitter {} at {:.3} MHz disappeared",
+                    emitter.id, emitter.frequency
+                );
+                self.events.push(DriftEvent::Disappeared {
+                    id: emitter.id,
+                    last_frequency: emitter.frequency,
+                });
+                false
+            }
+        });
+        self.trim_events();
+    }
+
+    pub fn clear(&mut self) {
+        self.emitters.clear();
+        self.events.clear();
+    }
+
+    fn follow(&mut self, index: usize, result: &ScanResult, now: Instant) {
+        let emitter = &mut self.emitters[index];
+        let elapsed = now.duration_since(emitter.last_seen).as_secs_f32();
+        if elapsed > 0.0 {
+            let rate = (result.frequency - emitter.frequency) / elapsed;
+            emitter.drift_rate += DRIFT_RATE_SMOOTHING * (rate - emitter.drift_rate);
+        }
+        emitter.frequency = result.frequency;
+        emitter.strength = result.strength;
+        emitter.last_seen = now;
+        emitter.missed = 0;
+
+        if (emitter.frequency - emitter.reported_frequency).abs() >= self.drift_threshold {
+            let event = DriftEvent::Drifted {
+                id: emitter.id,
+                from: emitter.reported_frequency,
+                to: emitter.frequency,
+                rate: emitter.drift_rate,
+            };
+            emitter.reported_frequency = emitter.frequency;
+            self.push_event(event);
+        }
+    }
+
+    fn push_event(&mut self, event: DriftEvent) {
+        match &event {
+            DriftEvent::Appeared { id, frequency } => {
+                info!("Emitter {} appeared at {:.3} MHz", id, frequency)
+            }
+            DriftEvent::Drifted { id, from, to, rate } => info!(
+                "Emitter {} drifted {:.3} -> {:.3} MHz ({:.1} Hz/s)",
+                id,
+                from,
+                to,
+                rate * 1e6
+            ),
+            DriftEvent::Disappeared { .. } => {}
+        }
+        self.events.push(event);
+        self.trim_events();
+    }
+
+    fn trim_events(&mut self) {
+        if self.events.len() > MAX_PENDING_EVENTS {
+            let excess = self.events.len() - MAX_PENDING_EVENTS;
+            self.events.drain(..excess);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::classify::SignalClass;
+    use std::time::Duration;
+
+    fn result(frequency: f32) -> ScanResult {
+        ScanResult {
+            frequency,
+            strength: -40.0,
+            confidence: 1.0,
+            class: SignalClass::Unknown,
+        }
+    }
+
+    #[test]
+    fn test_drift_and_disappearance() {
+        let range = FrequencyRange {
+            start: 430.0,
+            end: 440.0,
+            step: 0.1,
+        };
+        let mut tracker = DriftTracker::new(0.01, 2);
+        let start = Instant::now();
+
+        tracker.update(&range, &[result(433.92)], start);
+        tracker.update(&range, &[result(433.925)], start + Duration::from_secs(1));
+        tracker.update(&range, &[result(433.935)], start + Duration::from_secs(2));
+
+        let emitter = &tracker.get_emitters()[0];
+        assert!((emitter.get_drift() - 0.015).abs() < 1e-4);
+        assert!(emitter.drift_rate > 0.0);
+
+        let later = start + Duration::from_secs(3);
+        let (frequency, step) = tracker.refinement_start(433.9, &range, later);
+        assert!(frequency > 433.935 && step < range.step * 0.5);
+
+        tracker.update(&range, &[], later);
+        tracker.update(&range, &[], later + Duration::from_secs(1));
+        assert!(tracker.get_emitters().is_empty());
+
+        let events = tracker.take_events();
+        assert!(matches!(events[0], DriftEvent::Appeared { id: 0, .. }));
+        assert!(matches!(events[1], DriftEvent::Drifted { id: 0, .. }));
+        assert!(matches!(events[2], DriftEvent::Disappeared { id: 0, .. }));
+        assert_eq!(events.len(), 3);
+    }
+}
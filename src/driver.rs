@@ -0,0 +1,254 @@
+#![cfg_attr(
+    all(feature = "panic-free", not(test)),
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable
+    )
+)]
+
+use smallvec::SmallVec;
+
+use crate::accumulator::AccumulatorStats;
+use crate::{FrameAccumulator, RadarDriver, RadarLLFrame};
+
+/// Commands waiting for transmission or acknowledgement, kept inline so the driver never allocates
+pub const COMMAND_QUEUE_DEPTH: usize = 4;
+pub const DEFAULT_ACK_TIMEOUT_MS: u32 = 100;
+pub const DEFAULT_MAX_RETRIES: u8 = 3;
+
+/// Acknowledgements echo the command opcode with this bit set
+pub const ACK_BIT: u16 = 0x0100;
+
+#[derive(Debug)]
+pub enum DriverAction {
+    /// Write these bytes to the UART
+    Transmit(SmallVec<[u8; 32]>),
+    /// A complete frame arrived, either target data or the acknowledgement of a queued command
+    Frame(RadarLLFrame),
+    /// The command with this opcode was never acknowledged and has been dropped
+    CommandTimedOut(u16),
+    /// Nothing to do until more bytes arrive or, if set, this many milliseconds have passed
+    Wait(Option<u32>),
+}
+
+/// Result of [`DriverCore::poll`]
+#[derive(Debug)]
+pub struct DriverPoll {
+    /// Received bytes taken from the slice passed to `poll`, the rest must be passed again
+    pub consumed: usize,
+    pub action: DriverAction,
+}
+
+/// Error returned when [`COMMAND_QUEUE_DEPTH`] commands are already pending
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueFull;
+
+#[derive(Debug, Clone)]
+struct QueuedCommand {
+    opcode: u16,
+    bytes: SmallVec<[u8; 32]>,
+}
+
+/// Sans-io radar driver: performs no I/O and never blocks, so it can be driven from RTIC hardware
+/// tasks or a superloop without an async executor.
+///
+/// The caller feeds it received bytes and elapsed time through [`DriverCore::poll`] and carries out
+/// the returned action. Each poll yields one action, so poll again until it returns
+/// [`DriverAction::Wait`]. Commands are sent one at a time and retransmitted until the radar
+/// acknowledges them.
+#[derive(Debug, Clone)]
+pub struct DriverCore {
+    accumulator: FrameAccumulator,
+    queue: SmallVec<[QueuedCommand; COMMAND_QUEUE_DEPTH]>,
+    in_flight: bool,
+    since_transmit_ms: u32,
+    retries: u8,
+    ack_timeout_ms: u32,
+    max_retries: u8,
+}
+
+impl Default for DriverCore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DriverCore {
+    pub const fn new() -> Self {
+        Self::with_timeouts(DEFAULT_ACK_TIMEOUT_MS, DEFAULT_MAX_RETRIES)
+    }
+
+    pub const fn with_timeouts(ack_timeout_ms: u32, max_retries: u8) -> Self {
+        Self {
+            accumulator: FrameAccumulator::new(),
+            queue: SmallVec::new_const(),
+            in_flight: false,
+            since_transmit_ms: 0,
+            retries: 0,
+            ack_timeout_ms,
+            max_retries,
+        }
+    }
+
+    /// Queue a command for transmission on a following poll
+    pub fn send<C: RadarDriver>(&mut self, command: &C) -> Result<(), QueueFull> {
+        if self.queue.len() >= COMMAND_QUEUE_DEPTH {
+            return Err(QueueFull);
+        }
+
+        let opcode = command.get_opcode();
+        let mut data = SmallVec::new();
+        command.serialize_data(&mut data);
+
+        // Command frames always serialize
+        let bytes = RadarLLFrame::CommandAckFrame(opcode, data)
+            .serialize()
+            .unwrap_or_default();
+        self.queue.push(QueuedCommand { opcode, bytes });
+
+        Ok(())
+    }
+
+    /// Advance the driver by `elapsed_ms` since the previous poll, with `rx` holding whatever
+    /// bytes the UART received meanwhile
+    pub fn poll(&mut self, elapsed_ms: u32, rx: &[u8]) -> DriverPoll {
+        let consumed = self.accumulator.push(rx);
+        if self.in_flight {
+            self.since_transmit_ms = self.since_transmit_ms.saturating_add(elapsed_ms);
+        }
+
+        DriverPoll {
+            consumed,
+            action: self.next_action(),
+        }
+    }
+
+    pub fn pending_commands(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Frames parsed and garbage skipped since the driver was created, kept across resets
+    pub fn stats(&self) -> AccumulatorStats {
+        self.accumulator.stats()
+    }
+
+    /// Drop buffered bytes and pending commands, e.g. after the radar was power cycled
+    pub fn reset(&mut self) {
+        self.accumulator.clear();
+        self.queue.clear();
+        self.in_flight = false;
+    }
+
+    fn next_action(&mut self) -> DriverAction {
+        if let Some(frame) = self.accumulator.next_frame() {
+            if let RadarLLFrame::CommandAckFrame(opcode, _) = &frame {
+                let acknowledged = self.queue.first().map(|command| command.opcode | ACK_BIT);
+                if self.in_flight && acknowledged == Some(*opcode) {
+                    self.finish_command();
+                }
+            }
+
+            return DriverAction::Frame(frame);
+        }
+
+        let Some(command) = self.queue.first() else {
+            return DriverAction::Wait(None);
+        };
+
+        if !self.in_flight {
+            self.in_flight = true;
+            self.since_transmit_ms = 0;
+            self.retries = 0;
+            return DriverAction::Transmit(command.bytes.clone());
+        }
+
+        if self.since_transmit_ms < self.ack_timeout_ms {
+            return DriverAction::Wait(Some(self.ack_timeout_ms - self.since_transmit_ms));
+        }
+
+        if self.retries < self.max_retries {
+            self.retries += 1;
+            self.since_transmit_ms = 0;
+            return DriverAction::Transmit(command.bytes.clone());
+        }
+
+        let opcode = command.opcode;
+        self.finish_command();
+        DriverAction::CommandTimedOut(opcode)
+    }
+
+    fn finish_command(&mut self) {
+        if !self.queue.is_empty() {
+            self.queue.remove(0);
+        }
+        self.in_flight = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ld2450::Ld2450Command;
+
+    #[test]
+    fn test_command_is_acknowledged() {
+        let mut driver = DriverCore::new();
+        driver.send(&Ld2450Command::EnableConfiguration).unwrap();
+
+        let poll = driver.poll(0, &[]);
+        assert!(matches!(poll.action, DriverAction::Transmit(ref bytes)
+            if bytes.as_slice() == Ld2450Command::ENABLE_CONFIGURATION_FRAME));
+        assert!(matches!(
+            driver.poll(10, &[]).action,
+            DriverAction::Wait(Some(90))
+        ));
+
+        let ack = [
+            0xFD, 0xFC, 0xFB, 0xFA, 0x08, 0x00, 0xFF, 0x01, 0x00, 0x00, 0x01, 0x00, 0x40, 0x00,
+            0x04, 0x03, 0x02, 0x01,
+        ];
+        let poll = driver.poll(10, &ack);
+        assert_eq!(poll.consumed, ack.len());
+        assert!(matches!(
+            poll.action,
+            DriverAction::Frame(RadarLLFrame::CommandAckFrame(0x01FF, _))
+        ));
+        assert_eq!(driver.pending_commands(), 0);
+        assert!(matches!(
+            driver.poll(10, &[]).action,
+            DriverAction::Wait(None)
+        ));
+    }
+
+    #[test]
+    fn test_unacknowledged_command_is_retried_then_dropped() {
+        let mut driver = DriverCore::with_timeouts(50, 1);
+        driver.send(&Ld2450Command::Reboot).unwrap();
+
+        assert!(matches!(
+            driver.poll(0, &[]).action,
+            DriverAction::Transmit(_)
+        ));
+        assert!(matches!(
+            driver.poll(60, &[]).action,
+            DriverAction::Transmit(_)
+        ));
+        assert!(matches!(
+            driver.poll(60, &[]).action,
+            DriverAction::CommandTimedOut(0x00A3)
+        ));
+        assert_eq!(driver.pending_commands(), 0);
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let mut driver = DriverCore::new();
+        for _ in 0..COMMAND_QUEUE_DEPTH {
+            driver.send(&Ld2450Command::FirmwareVersion).unwrap();
+        }
+        assert_eq!(driver.send(&Ld2450Command::FirmwareVersion), Err(QueueFull));
+    }
+}
@@ -0,0 +1,212 @@
+use std::f32::consts::PI;
+
+use nalgebra::{Matrix2, Vector2};
+
+use crate::tracker::{
+    invert_covariance, FilterError, KalmanFilter, KinematicModel, Matrix6, TrackerConfig, Vector6,
+};
+
+type Matrix2x6 = nalgebra::SMatrix<f32, 2, 6>;
+
+/// Closer than this in metres to the sensor the polar measurement model is singular
+const MIN_RANGE: f32 = 1e-3;
+
+/// Where a radar sits in the room frame
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorOrigin {
+    pub position: Vector2<f32>,
+    /// Direction of zero azimuth in radians, counterclockwise from the x axis
+    pub heading: f32,
+}
+
+impl SensorOrigin {
+    pub fn new(position: Vector2<f32>, heading: f32) -> Self {
+        Self { position, heading }
+    }
+
+    /// Range in metres and azimuth in radians, counterclockwise from the heading, of a point in
+    /// the room frame
+    pub fn to_polar(&self, point: Vector2<f32>) -> (f32, f32) {
+        let offset = point - self.position;
+        (
+            offset.norm(),
+            wrap_angle(offset.y.atan2(offset.x) - self.heading),
+        )
+    }
+
+    pub fn to_cartesian(&self, range: f32, azimuth: f32) -> Vector2<f32> {
+        let angle = azimuth + self.heading;
+        self.position + Vector2::new(angle.cos(), angle.sin()) * range
+    }
+}
+
+/// Extended Kalman filter with a constant-acceleration state in the room frame and a polar
+/// measurement model, so the range and azimuth a radar reports are fused with their own
+/// noise instead of a circular position error
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExtendedKalmanFilter {
+    motion: KalmanFilter,
+    origin: SensorOrigin,
+    /// Variances of range in m² and azimuth in rad²
+    measurement_noise: Matrix2<f32>,
+}
+
+impl ExtendedKalmanFilter {
+    /// Start at a first `range`/`azimuth` fix, 10 cm range and 2° azimuth noise
+    pub fn new(origin: SensorOrigin, range: f32, azimuth: f32) -> Self {
+        Self::with_config(origin, range, azimuth, &TrackerConfig::default())
+    }
+
+    /// Motion model noise from `config`, its Cartesian measurement noise is not used
+    pub fn with_config(
+        origin: SensorOrigin,
+        range: f32,
+        azimuth: f32,
+        config: &TrackerConfig,
+    ) -> Self {
+        Self {
+            motion: KalmanFilter::with_config(
+                origin.to_cartesian(range, azimuth),
+                KinematicModel::ConstantAcceleration,
+                config,
+            ),
+            origin,
+            measurement_noise: Matrix2::new(0.01, 0.0, 0.0, 2f32.to_radians().powi(2)),
+        }
+    }
+
+    pub fn get_origin(&self) -> &SensorOrigin {
+        &self.origin
+    }
+
+    /// Standard deviations of range in metres and azimuth in radians
+    pub fn set_measurement_noise(&mut self, range_std: f32, azimuth_std: f32) {
+        self.measurement_noise = Matrix2::new(range_std.powi(2), 0.0, 0.0, azimuth_std.powi(2));
+    }
+
+    pub fn predict(&mut self, dt: f32) {
+        self.motion.predict(dt);
+    }
+
+    /// Fuse a polar measurement. Fails without changing the filter while the estimate sits on
+    /// top of the sensor, where the measurement model is singular.
+    pub fn update_polar(&mut self, range: f32, azimuth: f32) -> Result<(), FilterError> {
+        if !(range.is_finite() && azimuth.is_finite()) {
+            return Err(FilterError::NonFinite);
+        }
+        let h = self.jacobian().ok_or(FilterError::SingularCovariance)?;
+        let (state, covariance) = self.motion.get_state();
+        let (predicted_range, predicted_azimuth) = self.origin.to_polar(self.get_position());
+        let innovation = Vector2::new(
+            range - predicted_range,
+            wrap_angle(azimuth - predicted_azimuth),
+        );
+
+        let innovation_covariance = h * covariance * h.transpose() + self.measurement_noise;
+        let gain = covariance * h.transpose() * invert_covariance(&innovation_covariance)?;
+
+        let state: Vector6 = state + gain * innovation;
+        if !state.iter().all(|v| v.is_finite()) {
+            return Err(FilterError::NonFinite);
+        }
+        let correction = Matrix6::identity() - gain * h;
+        let covariance: Matrix6 = correction * covariance * correction.transpose()
+            + gain * self.measurement_noise * gain.transpose();
+        self.motion.set_state(state, covariance);
+        Ok(())
+    }
+
+    /// Fuse a position in the room frame by converting it back to what the radar measured
+    pub fn update(&mut self, measurement: Vector2<f32>) -> Result<(), FilterError> {
+        let (range, azimuth) = self.origin.to_polar(measurement);
+        self.update_polar(range, azimuth)
+    }
+
+    /// Innovation covariance in the room frame, the polar noise mapped through the Jacobian of
+    /// the polar to Cartesian conversion at the predicted position
+    pub fn get_innovation_covariance(&self) -> Matrix2<f32> {
+        let (range, azimuth) = self.origin.to_polar(self.get_position());
+        let angle = azimuth + self.origin.heading;
+        let (sin, cos) = angle.sin_cos();
+        let g = Matrix2::new(cos, -range * sin, sin, range * cos);
+
+        let (_, covariance) = self.motion.get_state();
+        covariance.fixed_view::<2, 2>(0, 0) + g * self.measurement_noise * g.transpose()
+    }
+
+    pub fn get_position(&self) -> Vector2<f32> {
+        self.motion.get_position()
+    }
+
+    pub fn get_velocity(&self) -> Vector2<f32> {
+        self.motion.get_velocity()
+    }
+
+    pub fn get_acceleration(&self) -> Vector2<f32> {
+        self.motion.get_acceleration()
+    }
+
+    /// Derivative of (range, azimuth) by the state at the current estimate
+    fn jacobian(&self) -> Option<Matrix2x6> {
+        let offset = self.get_position() - self.origin.position;
+        let range_squared = offset.norm_squared();
+        let range = range_squared.sqrt();
+        if range < MIN_RANGE {
+            return None;
+        }
+
+        let mut h = Matrix2x6::zeros();
+        h[(0, 0)] = offset.x / range;
+        h[(0, 1)] = offset.y / range;
+        h[(1, 0)] = -offset.y / range_squared;
+        h[(1, 1)] = offset.x / range_squared;
+        Some(h)
+    }
+}
+
+/// Angle in radians wrapped to -π..=π
+fn wrap_angle(angle: f32) -> f32 {
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polar_tracking_with_offset_sensor() {
+        // Sensor in the corner at (5, 0) looking along +y
+        let origin = SensorOrigin::new(Vector2::new(5.0, 0.0), PI / 2.0);
+        let (range, azimuth) = origin.to_polar(Vector2::new(5.0, 3.0));
+        assert!((range - 3.0).abs() < 1e-5 && azimuth.abs() < 1e-5);
+        assert!((origin.to_cartesian(range, azimuth) - Vector2::new(5.0, 3.0)).norm() < 1e-5);
+
+        // Target walking along x at 1 m/s, 3 m in front of the sensor
+        let mut ekf = ExtendedKalmanFilter::new(origin, range, azimuth);
+        let dt = 0.1;
+        for step in 1..=40 {
+            let truth = Vector2::new(5.0 + step as f32 * dt, 3.0);
+            let (range, azimuth) = origin.to_polar(truth);
+            ekf.predict(dt);
+            ekf.update_polar(range, azimuth).unwrap();
+        }
+
+        assert!((ekf.get_position() - Vector2::new(9.0, 3.0)).norm() < 0.1);
+        assert!((ekf.get_velocity().x - 1.0).abs() < 0.2);
+        assert!(ekf.get_velocity().y.abs() < 0.2);
+    }
+
+    #[test]
+    fn test_wrap_angle() {
+        assert!((wrap_angle(3.0 * PI / 2.0) + PI / 2.0).abs() < 1e-5);
+        assert!((wrap_angle(-3.0 * PI / 2.0) - PI / 2.0).abs() < 1e-5);
+        assert_eq!(wrap_angle(0.5), 0.5);
+    }
+}
@@ -0,0 +1,163 @@
+#![cfg_attr(
+    all(feature = "panic-free", not(test)),
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable
+    )
+)]
+
+use embedded_io::{Read, ReadReady, Write};
+
+use crate::driver::{DriverAction, DriverCore, DriverPoll, QueueFull};
+use crate::{RadarDriver, RadarLLFrame};
+
+const RX_CHUNK: usize = 64;
+
+#[derive(Debug)]
+pub enum RadarEvent {
+    Frame(RadarLLFrame),
+    /// The command with this opcode was never acknowledged
+    CommandTimedOut(u16),
+}
+
+#[derive(Debug)]
+pub enum UartError<E> {
+    Read(E),
+    Write(E),
+}
+
+/// Drives a [`DriverCore`] over a blocking serial port, typically an esp-hal
+/// `Uart<'_, Blocking>`. Reads only what the UART already holds, so [`UartRadar::poll`] never
+/// blocks waiting for the radar and fits in a main loop or a timer interrupt.
+pub struct UartRadar<U> {
+    uart: U,
+    driver: DriverCore,
+    rx: [u8; RX_CHUNK],
+    rx_len: usize,
+}
+
+impl<U: Read + ReadReady + Write> UartRadar<U> {
+    pub fn new(uart: U) -> Self {
+        Self {
+            uart,
+            driver: DriverCore::new(),
+            rx: [0; RX_CHUNK],
+            rx_len: 0,
+        }
+    }
+
+    /// Queue a command, it is written to the UART on the next poll
+    pub fn send<C: RadarDriver>(&mut self, command: &C) -> Result<(), QueueFull> {
+        self.driver.send(command)
+    }
+
+    /// Service the UART, returning the next frame or command timeout if there is one. Call with
+    /// the milliseconds elapsed since the previous call, and keep calling while it returns events.
+    pub fn poll(&mut self, elapsed_ms: u32) -> Result<Option<RadarEvent>, UartError<U::Error>> {
+        if self.rx_len < RX_CHUNK && self.uart.read_ready().map_err(UartError::Read)? {
+            let free = self.rx.get_mut(self.rx_len..).unwrap_or(&mut []);
+            self.rx_len += self.uart.read(free).map_err(UartError::Read)?;
+        }
+
+        let mut elapsed_ms = elapsed_ms;
+        loop {
+            let DriverPoll { consumed, action } = self
+                .driver
+                .poll(elapsed_ms, self.rx.get(..self.rx_len).unwrap_or(&[]));
+            elapsed_ms = 0;
+
+            self.rx.copy_within(consumed..self.rx_len, 0);
+            self.rx_len -= consumed;
+
+            match action {
+                DriverAction::Transmit(bytes) => {
+                    self.uart.write_all(&bytes).map_err(UartError::Write)?
+                }
+                DriverAction::Frame(frame) => return Ok(Some(RadarEvent::Frame(frame))),
+                DriverAction::CommandTimedOut(opcode) => {
+                    return Ok(Some(RadarEvent::CommandTimedOut(opcode)))
+                }
+                DriverAction::Wait(_) => return Ok(None),
+            }
+        }
+    }
+
+    pub fn get_driver(&self) -> &DriverCore {
+        &self.driver
+    }
+
+    /// Give the UART back, e.g. to reconfigure its baud rate
+    pub fn release(self) -> U {
+        self.uart
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ld2450::Ld2450Command;
+
+    /// Loopback UART that replays canned radar output and records what was written
+    struct MockUart {
+        incoming: Vec<u8>,
+        written: Vec<u8>,
+    }
+
+    impl embedded_io::ErrorType for MockUart {
+        type Error = core::convert::Infallible;
+    }
+
+    impl Read for MockUart {
+        fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            let count = buf.len().min(self.incoming.len());
+            buf[..count].copy_from_slice(&self.incoming[..count]);
+            self.incoming.drain(..count);
+            Ok(count)
+        }
+    }
+
+    impl ReadReady for MockUart {
+        fn read_ready(&mut self) -> Result<bool, Self::Error> {
+            Ok(!self.incoming.is_empty())
+        }
+    }
+
+    impl Write for MockUart {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), Self::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_command_written_and_target_frame_received() {
+        let mut incoming = vec![0x00, 0x13];
+        incoming.extend_from_slice(&[
+            0xAA, 0xFF, 0x03, 0x00, 0x0E, 0x03, 0xB1, 0x86, 0x10, 0x00, 0x40, 0x01, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x55, 0xCC,
+        ]);
+        let mut radar = UartRadar::new(MockUart {
+            incoming,
+            written: Vec::new(),
+        });
+
+        radar.send(&Ld2450Command::MultiTargetTracking).unwrap();
+        assert!(matches!(
+            radar.poll(0),
+            Ok(Some(RadarEvent::Frame(RadarLLFrame::TargetFrame2D(_))))
+        ));
+        // Received frames are handed out before the queued command goes out
+        assert!(matches!(radar.poll(0), Ok(None)));
+
+        let uart = radar.release();
+        assert_eq!(uart.written, Ld2450Command::MULTI_TARGET_TRACKING_FRAME);
+    }
+}
@@ -0,0 +1,308 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+use tokio::task::{JoinHandle, JoinSet};
+use tracing::{debug, error, info, warn};
+
+use crate::error::{HexarError, HexarResult};
+use crate::monitoring::AlertSeverity;
+use crate::radar_controller::RadarEvent;
+use crate::tracker::FallEvent;
+
+/// What to do when a target falls
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum FallAction {
+    /// Raise an alert in the monitoring system, resolved when the person gets up
+    Alert { severity: AlertSeverity },
+    /// Run a program, e.g. to switch an output or send a notification. The fall is passed in
+    /// the `HEXAR_FALL_*` environment variables.
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Append the fall as a JSON line, e.g. to a FIFO another process reads
+    Append { path: PathBuf },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FallAlertConfig {
+    /// Act as soon as a fall is suspected rather than once it is confirmed
+    pub on_suspected: bool,
+    /// Time the actions get to complete, those running longer are abandoned. Falls reaching
+    /// the actions later than this after their detection are reported.
+    pub max_latency_ms: u64,
+    pub actions: Vec<FallAction>,
+}
+
+impl Default for FallAlertConfig {
+    fn default() -> Self {
+        Self {
+            on_suspected: true,
+            max_latency_ms: 500,
+            actions: vec![FallAction::Alert {
+                severity: AlertSeverity::Emergency,
+            }],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FallAlertKind {
+    Suspected,
+    Confirmed,
+    /// The person got up again or the fall was a false alarm
+    Cleared,
+}
+
+/// A fall event as passed to the actions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FallAlert {
+    pub kind: FallAlertKind,
+    pub target_id: u32,
+    pub timestamp: DateTime<Utc>,
+    pub probability: Option<f32>,
+    /// Last known position, `None` when cleared
+    pub position: Option<Vector2<f32>>,
+    /// Centre of the expected impact area of a suspected fall
+    pub landing: Option<Vector2<f32>>,
+    /// Time between the detection and this alert
+    pub delay: Duration,
+}
+
+impl FallAlert {
+    pub fn from_event(event: &FallEvent) -> Self {
+        let (kind, target_id, probability, position, landing, at) = match event {
+            FallEvent::FallSuspected {
+                target_id,
+                probability,
+                position,
+                landing,
+                at,
+            } => (
+                FallAlertKind::Suspected,
+                *target_id,
+                Some(*probability),
+                Some(*position),
+                landing.map(|zone| zone.centre),
+                at,
+            ),
+            FallEvent::FallConfirmed {
+                target_id,
+                position,
+                at,
+            } => (
+                FallAlertKind::Confirmed,
+                *target_id,
+                None,
+                Some(*position),
+                None,
+                at,
+            ),
+            FallEvent::FallCleared { target_id, at } => {
+                (FallAlertKind::Cleared, *target_id, None, None, None, at)
+            }
+        };
+        Self {
+            kind,
+            target_id,
+            timestamp: Utc::now(),
+            probability,
+            position,
+            landing,
+            delay: at.elapsed(),
+        }
+    }
+
+    /// Monitoring component the alerts of this target are raised for
+    pub fn component(&self) -> String {
+        format!("fall:{}", self.target_id)
+    }
+
+    fn environment(&self) -> Vec<(&'static str, String)> {
+        let kind = match self.kind {
+            FallAlertKind::Suspected => "suspected",
+            FallAlertKind::Confirmed => "confirmed",
+            FallAlertKind::Cleared => "cleared",
+        };
+        let mut environment = vec![
+            ("HEXAR_FALL_KIND", kind.to_string()),
+            ("HEXAR_FALL_TARGET", self.target_id.to_string()),
+        ];
+        if let Some(position) = self.position {
+            environment.push(("HEXAR_FALL_X", position.x.to_string()));
+            environment.push(("HEXAR_FALL_Y", position.y.to_string()));
+        }
+        if let Some(probability) = self.probability {
+            environment.push(("HEXAR_FALL_PROBABILITY", probability.to_string()));
+        }
+        environment
+    }
+}
+
+impl FallAlertConfig {
+    /// Whether the actions run for `alert`. A cleared fall is passed on so outputs can be reset.
+    pub fn triggers(&self, alert: &FallAlert) -> bool {
+        self.on_suspected || alert.kind != FallAlertKind::Suspected
+    }
+
+    fn latency_budget(&self) -> Duration {
+        Duration::from_millis(self.max_latency_ms)
+    }
+}
+
+/// Runs the command and append actions for every fall the controller reports, on a task of its
+/// own so nothing else holds them up. Alerts are raised by the monitoring system.
+#[derive(Debug)]
+pub struct FallAlertDispatcher {
+    handle: JoinHandle<()>,
+}
+
+impl FallAlertDispatcher {
+    pub fn spawn(config: FallAlertConfig, mut events: broadcast::Receiver<RadarEvent>) -> Self {
+        let handle = tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(RadarEvent::FallDetected(event)) => {
+                        let alert = FallAlert::from_event(&event);
+                        if config.triggers(&alert) {
+                            run_actions(&config, &alert).await;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        error!("Fall alerts missed {} radar events", missed);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
+            debug!("Fall alert dispatcher stopped");
+        });
+        Self { handle }
+    }
+
+    pub fn stop(self) {
+        self.handle.abort();
+    }
+}
+
+/// Run the output actions for `alert` concurrently, abandoning those that don't complete
+/// within the latency budget
+pub async fn run_actions(config: &FallAlertConfig, alert: &FallAlert) {
+    let budget = config.latency_budget();
+    if alert.delay > budget {
+        warn!(
+            "Fall of target {} reached the alert path {:?} after its detection",
+            alert.target_id, alert.delay
+        );
+    }
+    let mut running = JoinSet::new();
+    for action in &config.actions {
+        if matches!(action, FallAction::Alert { .. }) {
+            continue;
+        }
+        let (action, alert) = (action.clone(), alert.clone());
+        running.spawn(async move {
+            match tokio::time::timeout(budget, run_action(&action, &alert)).await {
+                Ok(Ok(())) => debug!("Fall action {:?} completed", action),
+                Ok(Err(e)) => error!("Fall action {:?} failed: {}", action, e),
+                Err(_) => warn!("Fall action {:?} abandoned after {:?}", action, budget),
+            }
+        });
+    }
+    while running.join_next().await.is_some() {}
+    info!(
+        "Fall of target {} {:?} handled {:?} after its detection",
+        alert.target_id,
+        alert.kind,
+        alert.delay + (Utc::now() - alert.timestamp).to_std().unwrap_or_default()
+    );
+}
+
+async fn run_action(action: &FallAction, alert: &FallAlert) -> HexarResult<()> {
+    match action {
+        FallAction::Alert { .. } => Ok(()),
+        FallAction::Command { program, args } => {
+            let status = tokio::process::Command::new(program)
+                .args(args)
+                .envs(alert.environment())
+                .kill_on_drop(true)
+                .status()
+                .await?;
+            if status.success() {
+                Ok(())
+            } else {
+                Err(HexarError::SystemError(format!(
+                    "{} exited with {}",
+                    program, status
+                )))
+            }
+        }
+        FallAction::Append { path } => {
+            let mut line = serde_json::to_vec(alert)?;
+            line.push(b'\n');
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .await?;
+            file.write_all(&line).await?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[tokio::test]
+    async fn test_fall_actions() {
+        let path = std::env::temp_dir().join(format!("hexar-falls-{}.jsonl", uuid::Uuid::new_v4()));
+        let config = FallAlertConfig {
+            on_suspected: false,
+            max_latency_ms: 200,
+            actions: vec![
+                FallAction::Append { path: path.clone() },
+                // Never completes within the budget
+                FallAction::Command {
+                    program: "sleep".to_string(),
+                    args: vec!["5".to_string()],
+                },
+            ],
+        };
+        let suspected = FallAlert::from_event(&FallEvent::FallSuspected {
+            target_id: 4,
+            probability: 0.9,
+            position: Vector2::new(1.0, 2.0),
+            landing: None,
+            at: Instant::now(),
+        });
+        assert!(!config.triggers(&suspected));
+        let confirmed = FallAlert::from_event(&FallEvent::FallConfirmed {
+            target_id: 4,
+            position: Vector2::new(1.0, 2.0),
+            at: Instant::now(),
+        });
+        assert!(config.triggers(&confirmed));
+
+        let started = Instant::now();
+        run_actions(&config, &confirmed).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let alert: FallAlert = serde_json::from_str(written.trim()).unwrap();
+        assert_eq!(alert.kind, FallAlertKind::Confirmed);
+        assert_eq!(alert.position, Some(Vector2::new(1.0, 2.0)));
+        std::fs::remove_file(path).unwrap();
+    }
+}
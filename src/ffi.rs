@@ -0,0 +1,167 @@
+use alloc::boxed::Box;
+use core::ptr;
+
+use crate::{FrameAccumulator, RadarLLFrame};
+
+/// Largest payload a [`HexarFrame`] can carry, longer payloads are truncated
+pub const HEXAR_FRAME_DATA_MAX: usize = 64;
+
+/// Opaque parser handle for C callers
+pub struct HexarParser(FrameAccumulator);
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexarFrameKind {
+    /// Command acknowledgement, `opcode` is valid
+    CommandAck = 0,
+    /// LD2412 1D target data
+    Ld2412Target = 1,
+    /// LD2450 2D target data
+    Ld2450Target = 2,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct HexarFrame {
+    pub kind: HexarFrameKind,
+    pub opcode: u16,
+    pub len: u16,
+    pub truncated: bool,
+    pub data: [u8; HEXAR_FRAME_DATA_MAX],
+}
+
+impl HexarFrame {
+    fn new(kind: HexarFrameKind, opcode: u16, payload: &[u8]) -> Self {
+        let len = payload.len().min(HEXAR_FRAME_DATA_MAX);
+        let mut data = [0; HEXAR_FRAME_DATA_MAX];
+        if let (Some(dst), Some(src)) = (data.get_mut(..len), payload.get(..len)) {
+            dst.copy_from_slice(src);
+        }
+
+        Self {
+            kind,
+            opcode,
+            len: len as u16,
+            truncated: len < payload.len(),
+            data,
+        }
+    }
+}
+
+impl From<&RadarLLFrame> for HexarFrame {
+    fn from(frame: &RadarLLFrame) -> Self {
+        match frame {
+            RadarLLFrame::CommandAckFrame(opcode, data) => {
+                Self::new(HexarFrameKind::CommandAck, *opcode, data)
+            }
+            RadarLLFrame::TargetFrame(data) => Self::new(HexarFrameKind::Ld2412Target, 0, data),
+            RadarLLFrame::TargetFrame2D(data) => Self::new(HexarFrameKind::Ld2450Target, 0, data),
+        }
+    }
+}
+
+/// Allocate a parser, release it with [`hexar_parser_free`]
+#[no_mangle]
+pub extern "C" fn hexar_parser_new() -> *mut HexarParser {
+    Box::into_raw(Box::new(HexarParser(FrameAccumulator::new())))
+}
+
+/// # Safety
+///
+/// `parser` must come from [`hexar_parser_new`] and not be used afterwards. NULL is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn hexar_parser_free(parser: *mut HexarParser) {
+    if !parser.is_null() {
+        drop(Box::from_raw(parser));
+    }
+}
+
+/// Append received UART bytes, returning how many were accepted. Bytes that didn't fit should
+/// be pushed again after draining frames with [`hexar_parser_next_frame`].
+///
+/// # Safety
+///
+/// `parser` must be a live handle and `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn hexar_parser_push(
+    parser: *mut HexarParser,
+    data: *const u8,
+    len: usize,
+) -> usize {
+    let Some(parser) = parser.as_mut() else {
+        return 0;
+    };
+    if data.is_null() || len == 0 {
+        return 0;
+    }
+
+    parser.0.push(core::slice::from_raw_parts(data, len))
+}
+
+/// Write the next complete frame to `out`, returning false when more bytes are needed
+///
+/// # Safety
+///
+/// `parser` must be a live handle and `out` must point to writable memory for a [`HexarFrame`].
+#[no_mangle]
+pub unsafe extern "C" fn hexar_parser_next_frame(
+    parser: *mut HexarParser,
+    out: *mut HexarFrame,
+) -> bool {
+    let Some(parser) = parser.as_mut() else {
+        return false;
+    };
+    if out.is_null() {
+        return false;
+    }
+
+    match parser.0.next_frame() {
+        Some(frame) => {
+            ptr::write(out, HexarFrame::from(&frame));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Drop any partially received data, e.g. after reopening the UART
+///
+/// # Safety
+///
+/// `parser` must be a live handle or NULL.
+#[no_mangle]
+pub unsafe extern "C" fn hexar_parser_reset(parser: *mut HexarParser) {
+    if let Some(parser) = parser.as_mut() {
+        parser.0.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c_api_round_trip() {
+        let ack = [
+            0xFD, 0xFC, 0xFB, 0xFA, 0x04, 0x00, 0xFF, 0x01, 0x00, 0x00, 0x04, 0x03, 0x02, 0x01,
+        ];
+        let mut frame = HexarFrame::new(HexarFrameKind::Ld2412Target, 0, &[]);
+
+        unsafe {
+            let parser = hexar_parser_new();
+            assert_eq!(hexar_parser_push(parser, ack.as_ptr(), 5), 5);
+            assert!(!hexar_parser_next_frame(parser, &mut frame));
+
+            assert_eq!(
+                hexar_parser_push(parser, ack[5..].as_ptr(), ack.len() - 5),
+                9
+            );
+            assert!(hexar_parser_next_frame(parser, &mut frame));
+            hexar_parser_free(parser);
+        }
+
+        assert_eq!(frame.kind, HexarFrameKind::CommandAck);
+        assert_eq!(frame.opcode, 0x01FF);
+        assert_eq!(&frame.data[..frame.len as usize], &[0x00, 0x00]);
+    }
+}
@@ -0,0 +1,210 @@
+#![cfg_attr(
+    all(feature = "panic-free", not(test)),
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable
+    )
+)]
+
+use smallvec::SmallVec;
+
+use crate::ld2450::TargetData;
+
+/// Per-target predicate in a filter chain. Closures taking `&TargetData` are filters too.
+pub trait TargetFilter {
+    fn accept(&mut self, target: &TargetData) -> bool;
+
+    /// Chain with another filter, a target must pass both
+    fn and<F: TargetFilter>(self, other: F) -> And<Self, F>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+}
+
+impl<F: FnMut(&TargetData) -> bool> TargetFilter for F {
+    fn accept(&mut self, target: &TargetData) -> bool {
+        self(target)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct And<A, B>(A, B);
+
+impl<A: TargetFilter, B: TargetFilter> TargetFilter for And<A, B> {
+    fn accept(&mut self, target: &TargetData) -> bool {
+        self.0.accept(target) && self.1.accept(target)
+    }
+}
+
+/// Keeps targets inside a rectangle in radar coordinates, bounds in mm and inclusive
+#[derive(Debug, Clone, Copy)]
+pub struct ZoneFilter {
+    pub x_min: i16,
+    pub x_max: i16,
+    pub y_min: i16,
+    pub y_max: i16,
+}
+
+impl TargetFilter for ZoneFilter {
+    fn accept(&mut self, target: &TargetData) -> bool {
+        (self.x_min..=self.x_max).contains(&target.position.x)
+            && (self.y_min..=self.y_max).contains(&target.position.y)
+    }
+}
+
+/// Drops targets slower than a minimum speed in cm/s, e.g. curtains or fans picked up at range
+#[derive(Debug, Clone, Copy)]
+pub struct MinSpeedFilter {
+    pub min_speed: u16,
+}
+
+impl TargetFilter for MinSpeedFilter {
+    fn accept(&mut self, target: &TargetData) -> bool {
+        target.speed.unsigned_abs() >= self.min_speed
+    }
+}
+
+/// Requires presence or absence to hold for a number of consecutive frames before it counts
+#[derive(Debug, Clone, Copy)]
+pub struct PresenceDebounce {
+    enter_frames: u8,
+    leave_frames: u8,
+    present: bool,
+    streak: u8,
+}
+
+impl PresenceDebounce {
+    pub const fn new(enter_frames: u8, leave_frames: u8) -> Self {
+        Self {
+            enter_frames,
+            leave_frames,
+            present: false,
+            streak: 0,
+        }
+    }
+
+    /// Feed one frame's raw presence, returning the new debounced state when it changes
+    pub fn update(&mut self, detected: bool) -> Option<bool> {
+        if detected == self.present {
+            self.streak = 0;
+            return None;
+        }
+
+        self.streak = self.streak.saturating_add(1);
+        let required = if detected {
+            self.enter_frames
+        } else {
+            self.leave_frames
+        };
+
+        if self.streak < required {
+            return None;
+        }
+
+        self.present = detected;
+        self.streak = 0;
+        Some(detected)
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.present
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum PresenceEvent {
+    /// Someone is present, with the targets that passed the filters on the deciding frame
+    Arrived(SmallVec<[TargetData; 3]>),
+    Departed,
+}
+
+/// Turns a stream of parsed LD2450 reports into occasional presence events, so battery powered
+/// nodes only wake the radio when something changes instead of forwarding every 10 Hz frame
+#[derive(Debug, Clone)]
+pub struct EventPipeline<F> {
+    filter: F,
+    debounce: PresenceDebounce,
+}
+
+impl<F: TargetFilter> EventPipeline<F> {
+    pub fn new(filter: F, debounce: PresenceDebounce) -> Self {
+        Self { filter, debounce }
+    }
+
+    pub fn process(&mut self, targets: &[TargetData]) -> Option<PresenceEvent> {
+        let accepted: SmallVec<[TargetData; 3]> = targets
+            .iter()
+            .filter(|target| self.filter.accept(target))
+            .copied()
+            .collect();
+
+        match self.debounce.update(!accepted.is_empty())? {
+            true => Some(PresenceEvent::Arrived(accepted)),
+            false => Some(PresenceEvent::Departed),
+        }
+    }
+
+    pub fn is_present(&self) -> bool {
+        self.debounce.is_present()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ld2450::Position;
+
+    fn target(x: i16, y: i16, speed: i16) -> TargetData {
+        TargetData {
+            position: Position { x, y },
+            speed,
+            distance_resolution: 320,
+        }
+    }
+
+    #[test]
+    fn test_filters_compose() {
+        let mut filter = ZoneFilter {
+            x_min: -1000,
+            x_max: 1000,
+            y_min: 0,
+            y_max: 3000,
+        }
+        .and(MinSpeedFilter { min_speed: 5 })
+        .and(|target: &TargetData| target.distance_resolution > 0);
+
+        assert!(filter.accept(&target(500, 1500, -20)));
+        assert!(!filter.accept(&target(1500, 1500, -20)));
+        assert!(!filter.accept(&target(500, 1500, 2)));
+    }
+
+    #[test]
+    fn test_presence_is_debounced() {
+        let mut pipeline =
+            EventPipeline::new(MinSpeedFilter { min_speed: 5 }, PresenceDebounce::new(2, 3));
+        let walking = [target(0, 1000, 30)];
+
+        assert!(pipeline.process(&walking).is_none());
+        assert!(matches!(
+            pipeline.process(&walking),
+            Some(PresenceEvent::Arrived(ref targets)) if targets.len() == 1
+        ));
+        assert!(pipeline.process(&walking).is_none());
+
+        // A single empty frame is not a departure
+        assert!(pipeline.process(&[]).is_none());
+        assert!(pipeline.process(&walking).is_none());
+        for _ in 0..2 {
+            assert!(pipeline.process(&[]).is_none());
+        }
+        assert!(matches!(
+            pipeline.process(&[]),
+            Some(PresenceEvent::Departed)
+        ));
+    }
+}
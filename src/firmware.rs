@@ -0,0 +1,341 @@
+use std::cmp::Ordering;
+use std::path::Path;
+use std::time::Duration;
+
+use smallvec::SmallVec;
+
+use crate::error::{HexarError, HexarResult};
+use crate::serial_radar::{FirmwareVersion, SensorModel, SerialRadar};
+use crate::RadarDriver;
+
+/// Firmware bytes sent per upgrade packet
+pub const PACKET_SIZE: usize = 128;
+
+/// Larger files are no firmware of the modules
+const MAX_IMAGE_SIZE: usize = 1 << 20;
+
+/// How long a module takes to boot the new firmware after the upgrade ends
+pub const REBOOT_DELAY: Duration = Duration::from_secs(2);
+
+/// Serial upgrade exchange of the LD2412 and LD2450, sent in configuration mode as command
+/// frames like any other command:
+///
+/// 1. `0x00F3` Start, data: image size (u32) and packet size (u16). The module erases its
+///    application area, from then on it only boots once an image was written completely.
+/// 2. `0x00F4` Packet, data: offset of the packet in the image (u32) and up to the packet
+///    size of firmware bytes, in order from offset 0. A packet sent again is written to the
+///    same place, so a retransmission is harmless.
+/// 3. `0x00F5` Finish, no data. The module checks the image written and boots it.
+///
+/// Each command is acknowledged with its opcode | `0x0100` and a u16 status, 0 when accepted.
+/// All numbers are little endian. The module keeps no copy of the firmware it ran before.
+#[derive(Debug)]
+pub enum UpgradeCommand<'a> {
+    /// Erase the application area for an image of `size` bytes
+    Start {
+        size: u32,
+        packet_size: u16,
+    },
+    Packet {
+        offset: u32,
+        data: &'a [u8],
+    },
+    /// Check the image written and boot it
+    Finish,
+}
+
+impl UpgradeCommand<'_> {
+    pub const fn opcode(&self) -> u16 {
+        match self {
+            UpgradeCommand::Start { .. } => 0x00F3,
+            UpgradeCommand::Packet { .. } => 0x00F4,
+            UpgradeCommand::Finish => 0x00F5,
+        }
+    }
+}
+
+impl RadarDriver for UpgradeCommand<'_> {
+    fn get_opcode(&self) -> u16 {
+        self.opcode()
+    }
+
+    fn serialize_data(&self, data: &mut SmallVec<[u8; 16]>) {
+        match self {
+            UpgradeCommand::Start { size, packet_size } => {
+                data.extend_from_slice(&size.to_le_bytes());
+                data.extend_from_slice(&packet_size.to_le_bytes());
+            }
+            UpgradeCommand::Packet {
+                offset,
+                data: bytes,
+            } => {
+                data.extend_from_slice(&offset.to_le_bytes());
+                data.extend_from_slice(bytes);
+            }
+            UpgradeCommand::Finish => {}
+        }
+    }
+}
+
+/// Module an image is flashed to, a [`SerialRadar`] or a simulated one
+pub trait UpgradeLink {
+    /// Send `command` and wait for its acknowledgement, an error when the module refused it or
+    /// never answered
+    fn send_upgrade(&mut self, command: &UpgradeCommand<'_>) -> HexarResult<()>;
+}
+
+impl UpgradeLink for SerialRadar {
+    fn send_upgrade(&mut self, command: &UpgradeCommand<'_>) -> HexarResult<()> {
+        self.request(command).map(drop)
+    }
+}
+
+/// Firmware file of a module, e.g. `LD2412_V1.26.24032613.bin` as the vendor names them
+#[derive(Debug, Clone)]
+pub struct FirmwareImage {
+    /// Model named in the file name
+    pub model: Option<SensorModel>,
+    /// Version named in the file name
+    pub version: Option<FirmwareVersion>,
+    bytes: Vec<u8>,
+}
+
+impl FirmwareImage {
+    pub fn load(path: &Path) -> HexarResult<Self> {
+        let bytes = std::fs::read(path)?;
+        let name = path
+            .file_stem()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        Self::from_bytes(&name, bytes)
+    }
+
+    /// Image of `bytes`, its model and version taken from the file `name`
+    pub fn from_bytes(name: &str, bytes: Vec<u8>) -> HexarResult<Self> {
+        if bytes.is_empty() || bytes.len() > MAX_IMAGE_SIZE {
+            return Err(HexarError::InvalidParameter(format!(
+                "{} bytes is no firmware image",
+                bytes.len()
+            )));
+        }
+        let name = name.to_ascii_uppercase();
+        let model = if name.contains("LD2412") {
+            Some(SensorModel::Ld2412)
+        } else if name.contains("LD2450") {
+            Some(SensorModel::Ld2450)
+        } else {
+            None
+        };
+        let version = name
+            .split(['_', '-', ' '])
+            .find_map(|part| part.parse().ok());
+        Ok(Self {
+            model,
+            version,
+            bytes,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// Commands flashing the image, from the start to the finish
+    pub fn commands(&self) -> impl Iterator<Item = UpgradeCommand<'_>> {
+        let start = UpgradeCommand::Start {
+            size: self.bytes.len() as u32,
+            packet_size: PACKET_SIZE as u16,
+        };
+        let packets =
+            self.bytes
+                .chunks(PACKET_SIZE)
+                .enumerate()
+                .map(|(i, data)| UpgradeCommand::Packet {
+                    offset: (i * PACKET_SIZE) as u32,
+                    data,
+                });
+        std::iter::once(start)
+            .chain(packets)
+            .chain(std::iter::once(UpgradeCommand::Finish))
+    }
+}
+
+/// What flashing an image of `image` version means for a module running `current`, `None` when
+/// either is unknown
+pub fn compare_versions(
+    current: Option<&FirmwareVersion>,
+    image: Option<&FirmwareVersion>,
+) -> Option<Ordering> {
+    let (current, image) = (current?, image?);
+    Some((image.major, image.minor).cmp(&(current.major, current.minor)))
+}
+
+/// Put the module of `sensor` in configuration mode and flash `image` to it, see [`flash`]
+pub fn flash_module(
+    sensor: &mut SerialRadar,
+    image: &FirmwareImage,
+    progress: impl FnMut(usize),
+) -> HexarResult<()> {
+    sensor.reset()?;
+    sensor.set_configuration_mode(true)?;
+    flash(sensor, image, progress)
+}
+
+/// Flash `image` to a module in configuration mode, blocking, calling `progress` with the
+/// bytes written so far after every packet. The module boots the new firmware once it checked
+/// the image. Once it acknowledged the start it has erased the firmware it ran, a flash failing
+/// after that leaves it unable to start until flashed again, which the error says.
+pub fn flash(
+    link: &mut impl UpgradeLink,
+    image: &FirmwareImage,
+    mut progress: impl FnMut(usize),
+) -> HexarResult<()> {
+    let mut written = 0;
+    for command in image.commands() {
+        link.send_upgrade(&command).map_err(|e| match command {
+            UpgradeCommand::Start { .. } => e,
+            _ => HexarError::HardwareError(format!(
+                "flashing stopped after {} of {} bytes, the module's firmware is erased and it \
+                 won't start until flashed again: {}",
+                written,
+                image.len(),
+                e
+            )),
+        })?;
+        if let UpgradeCommand::Packet { data, .. } = command {
+            written += data.len();
+            progress(written);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RadarLLFrame;
+
+    /// Module following the upgrade exchange on the frames it is sent, refusing the packet at
+    /// `refuse_offset`
+    #[derive(Default)]
+    struct SimulatedModule {
+        refuse_offset: Option<u32>,
+        /// Size announced by the start, `None` before it
+        erased: Option<u32>,
+        flash: Vec<u8>,
+        booted: bool,
+    }
+
+    impl UpgradeLink for SimulatedModule {
+        fn send_upgrade(&mut self, command: &UpgradeCommand<'_>) -> HexarResult<()> {
+            let mut data = SmallVec::new();
+            command.serialize_data(&mut data);
+            let frame = RadarLLFrame::CommandAckFrame(command.get_opcode(), data)
+                .serialize()
+                .unwrap();
+            let Some(RadarLLFrame::CommandAckFrame(opcode, data)) =
+                RadarLLFrame::deserialize(&frame)
+            else {
+                panic!("malformed command frame");
+            };
+            let le32 = |bytes: &[u8]| u32::from_le_bytes(bytes.try_into().unwrap());
+            let refused = || HexarError::HardwareError(format!("refused {:#06x}", opcode));
+            match opcode {
+                0x00F3 => {
+                    assert_eq!(data[4..6], (PACKET_SIZE as u16).to_le_bytes());
+                    self.erased = Some(le32(&data[..4]));
+                    self.flash.clear();
+                }
+                0x00F4 => {
+                    let offset = le32(&data[..4]);
+                    if self.erased.is_none() || self.refuse_offset == Some(offset) {
+                        return Err(refused());
+                    }
+                    assert_eq!(offset as usize, self.flash.len());
+                    self.flash.extend_from_slice(&data[4..]);
+                }
+                0x00F5 if self.erased == Some(self.flash.len() as u32) => self.booted = true,
+                _ => return Err(refused()),
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_image_commands() {
+        let image = FirmwareImage::from_bytes("LD2412_V1.26.24032613", vec![0xAB; 300]).unwrap();
+        assert_eq!(image.model, Some(SensorModel::Ld2412));
+        assert_eq!(image.version.unwrap().to_string(), "V1.26.24032613");
+
+        let commands: Vec<_> = image.commands().collect();
+        // Start, three packets of 128, 128 and 44 bytes, finish
+        assert_eq!(commands.len(), 5);
+        assert!(matches!(
+            commands[3],
+            UpgradeCommand::Packet { offset: 256, data } if data.len() == 44
+        ));
+        let mut data = SmallVec::new();
+        commands[0].serialize_data(&mut data);
+        let frame = RadarLLFrame::CommandAckFrame(commands[0].get_opcode(), data)
+            .serialize()
+            .unwrap();
+        assert_eq!(
+            frame.as_slice(),
+            [
+                0xFD, 0xFC, 0xFB, 0xFA, 0x08, 0x00, 0xF3, 0x00, 0x2C, 0x01, 0, 0, 0x80, 0x00, 4, 3,
+                2, 1
+            ]
+        );
+
+        assert!(FirmwareImage::from_bytes("LD2450.bin", Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_flash() {
+        let bytes: Vec<u8> = (0..300).map(|i| i as u8).collect();
+        let image = FirmwareImage::from_bytes("LD2450_V2.04.23101915", bytes.clone()).unwrap();
+        let mut module = SimulatedModule::default();
+        let mut progress = Vec::new();
+        flash(&mut module, &image, |written| progress.push(written)).unwrap();
+        assert_eq!(progress, [128, 256, 300]);
+        assert_eq!(module.flash, bytes);
+        assert!(module.booted);
+
+        // Refused halfway: the module is left erased, and the error says so
+        let mut module = SimulatedModule {
+            refuse_offset: Some(256),
+            ..SimulatedModule::default()
+        };
+        let mut progress = Vec::new();
+        let error = flash(&mut module, &image, |written| progress.push(written))
+            .unwrap_err()
+            .to_string();
+        assert_eq!(progress, [128, 256]);
+        assert!(error.contains("after 256 of 300 bytes"), "{}", error);
+        assert!(
+            error.contains("won't start until flashed again"),
+            "{}",
+            error
+        );
+        assert!(!module.booted);
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        let older: FirmwareVersion = "V1.02.22062416".parse().unwrap();
+        let newer: FirmwareVersion = "V1.26.24032613".parse().unwrap();
+        assert_eq!(
+            compare_versions(Some(&newer), Some(&older)),
+            Some(Ordering::Less)
+        );
+        assert_eq!(
+            compare_versions(Some(&older), Some(&older)),
+            Some(Ordering::Equal)
+        );
+        assert_eq!(compare_versions(None, Some(&newer)), None);
+    }
+}
@@ -0,0 +1,194 @@
+use fixed::types::I16F16;
+
+/// Q16.16 fixed point, positions in metres and velocities in m/s
+pub type Fx = I16F16;
+
+/// Alpha-beta position smoother in Q16.16 fixed point, for MCUs without an FPU (Cortex-M0/M0+)
+/// where the nalgebra Kalman tracker would run on soft-float
+#[derive(Debug, Clone, Copy)]
+pub struct AlphaBetaFilter {
+    position: [Fx; 2],
+    velocity: [Fx; 2],
+    acceleration: [Fx; 2],
+    alpha: Fx,
+    beta: Fx,
+    initialized: bool,
+}
+
+impl Default for AlphaBetaFilter {
+    fn default() -> Self {
+        Self::new(Fx::from_bits(0x0000_B333), Fx::from_bits(0x0000_3333)) // 0.7, 0.2
+    }
+}
+
+impl AlphaBetaFilter {
+    pub const fn new(alpha: Fx, beta: Fx) -> Self {
+        Self {
+            position: [Fx::ZERO; 2],
+            velocity: [Fx::ZERO; 2],
+            acceleration: [Fx::ZERO; 2],
+            alpha,
+            beta,
+            initialized: false,
+        }
+    }
+
+    /// Feed a measured position taken `dt` seconds after the previous one and return the
+    /// smoothed position. The first measurement only initializes the filter.
+    pub fn update(&mut self, measurement: [Fx; 2], dt: Fx) -> [Fx; 2] {
+        if !self.initialized || dt <= Fx::ZERO {
+            if !self.initialized {
+                self.position = measurement;
+                self.initialized = true;
+            }
+            return self.position;
+        }
+
+        let axes = self
+            .position
+            .iter_mut()
+            .zip(self.velocity.iter_mut())
+            .zip(self.acceleration.iter_mut())
+            .zip(measurement);
+
+        for (((position, velocity), acceleration), measured) in axes {
+            // Saturating everywhere, a wild measurement must not trap on overflow
+            let predicted = position.saturating_add(velocity.saturating_mul(dt));
+            let residual = measured.saturating_sub(predicted);
+            let velocity_correction = self
+                .beta
+                .saturating_mul(residual)
+                .checked_div(dt)
+                .unwrap_or(Fx::ZERO);
+            let new_velocity = velocity.saturating_add(velocity_correction);
+
+            *acceleration = new_velocity
+                .saturating_sub(*velocity)
+                .checked_div(dt)
+                .unwrap_or(Fx::ZERO);
+            *velocity = new_velocity;
+            *position = predicted.saturating_add(self.alpha.saturating_mul(residual));
+        }
+
+        self.position
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.alpha, self.beta);
+    }
+
+    pub fn get_position(&self) -> [Fx; 2] {
+        self.position
+    }
+
+    pub fn get_velocity(&self) -> [Fx; 2] {
+        self.velocity
+    }
+
+    pub fn get_acceleration(&self) -> [Fx; 2] {
+        self.acceleration
+    }
+}
+
+/// Fixed-point counterpart of [`crate::tracker::FallDetector`] with the same thresholds and
+/// weights, working on the state of an [`AlphaBetaFilter`]. The y axis is vertical.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedFallDetector {
+    gravity_threshold: Fx,
+    velocity_threshold: Fx,
+    acceleration_threshold: Fx,
+}
+
+impl Default for FixedFallDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixedFallDetector {
+    pub const fn new() -> Self {
+        Self {
+            gravity_threshold: Fx::lit("-9.5"),    // m/s²
+            velocity_threshold: Fx::lit("2"),      // m/s
+            acceleration_threshold: Fx::lit("15"), // m/s²
+        }
+    }
+
+    /// Fall risk between 0 and 1
+    pub fn analyze_fall_risk(&self, filter: &AlphaBetaFilter) -> Fx {
+        let [_, vy] = filter.get_velocity();
+        let [_, ay] = filter.get_acceleration();
+        let mut risk_score = Fx::ZERO;
+
+        // Check for downward acceleration (free fall)
+        if ay < self.gravity_threshold {
+            risk_score += Fx::lit("0.4");
+        }
+
+        // Check for high downward velocity
+        if vy < -self.velocity_threshold {
+            risk_score += Fx::lit("0.3");
+        }
+
+        // Magnitudes are compared squared to avoid a fixed-point sqrt
+        let acceleration = filter.get_acceleration();
+        if norm_squared(acceleration)
+            > self
+                .acceleration_threshold
+                .saturating_mul(self.acceleration_threshold)
+        {
+            risk_score += Fx::lit("0.2");
+        }
+
+        let fast = self.velocity_threshold.saturating_mul(Fx::lit("2"));
+        if norm_squared(filter.get_velocity()) > fast.saturating_mul(fast) {
+            risk_score += Fx::lit("0.1");
+        }
+
+        risk_score.min(Fx::ONE)
+    }
+
+    pub fn is_falling(&self, filter: &AlphaBetaFilter) -> bool {
+        self.analyze_fall_risk(filter) > Fx::lit("0.7")
+    }
+}
+
+fn norm_squared([x, y]: [Fx; 2]) -> Fx {
+    x.saturating_mul(x).saturating_add(y.saturating_mul(y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alpha_beta_tracks_constant_velocity() {
+        let mut filter = AlphaBetaFilter::default();
+        let dt = Fx::lit("0.1");
+
+        // Walking at 1 m/s along x
+        for step in 0..50 {
+            let x = Fx::from_num(step) * dt;
+            filter.update([x, Fx::ONE], dt);
+        }
+
+        let [vx, vy] = filter.get_velocity();
+        assert!((vx - Fx::ONE).abs() < Fx::lit("0.05"));
+        assert!(vy.abs() < Fx::lit("0.05"));
+    }
+
+    #[test]
+    fn test_fall_detected_on_sudden_drop() {
+        let mut filter = AlphaBetaFilter::default();
+        let detector = FixedFallDetector::new();
+        let dt = Fx::lit("0.05");
+
+        filter.update([Fx::ZERO, Fx::lit("1.7")], dt);
+        filter.update([Fx::ZERO, Fx::lit("1.7")], dt);
+        assert!(!detector.is_falling(&filter));
+
+        filter.update([Fx::ZERO, Fx::lit("1.4")], dt);
+        filter.update([Fx::ZERO, Fx::lit("0.9")], dt);
+        assert!(detector.is_falling(&filter));
+    }
+}
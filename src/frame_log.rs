@@ -0,0 +1,226 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{debug, info};
+
+use crate::error::{HexarError, HexarResult};
+use crate::pipeline::{Sink, StageSender};
+use crate::replay::ReplayTiming;
+use crate::serial_radar::{SensorFrame, SensorModel};
+
+/// One line of a frame recording
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    /// Milliseconds since the recording started
+    pub offset_ms: u64,
+    pub antenna_id: u8,
+    pub model: SensorModel,
+    /// Target data payload in hex, as received
+    pub payload: String,
+    /// Targets parsed while recording, `[x, y]` in metres in the antenna frame
+    pub targets: Vec<[f32; 2]>,
+}
+
+/// Writes every target data frame the controller receives to a JSON lines file, raw and
+/// parsed, for replay with [`FrameReplay`]
+#[derive(Debug)]
+pub struct FrameRecorder {
+    writer: BufWriter<File>,
+    started: Instant,
+    frames: u64,
+}
+
+impl FrameRecorder {
+    pub fn create<P: AsRef<Path>>(path: P) -> HexarResult<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        info!("Recording sensor frames to {}", path.display());
+        Ok(Self {
+            writer: BufWriter::new(File::create(path)?),
+            started: Instant::now(),
+            frames: 0,
+        })
+    }
+
+    pub fn record(&mut self, frame: &SensorFrame) -> HexarResult<()> {
+        let offset = frame.received_at.saturating_duration_since(self.started);
+        let line = RecordedFrame {
+            offset_ms: offset.as_millis() as u64,
+            antenna_id: frame.antenna_id,
+            model: frame.model,
+            payload: to_hex(&frame.payload),
+            targets: frame
+                .measurements
+                .iter()
+                .map(|m| [m.position.x, m.position.y])
+                .collect(),
+        };
+        serde_json::to_writer(&mut self.writer, &line)?;
+        self.writer.write_all(b"\n")?;
+        self.frames += 1;
+        Ok(())
+    }
+
+    pub fn get_frame_count(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn flush(&mut self) -> HexarResult<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Records frames behind a [`SinkStage`](crate::pipeline::SinkStage), off the scan cycle
+impl Sink<SensorFrame> for FrameRecorder {
+    fn consume(&mut self, frame: SensorFrame) -> HexarResult<()> {
+        self.record(&frame)
+    }
+
+    fn flush(&mut self) -> HexarResult<()> {
+        FrameRecorder::flush(self)
+    }
+}
+
+/// Frames of a recording written by [`FrameRecorder`], played back into the controller as if
+/// the modules sent them. Payloads are parsed again rather than taking the recorded targets,
+/// so a replay exercises the whole pipeline.
+#[derive(Debug, Clone)]
+pub struct FrameReplay {
+    frames: Vec<RecordedFrame>,
+    timing: ReplayTiming,
+}
+
+impl FrameReplay {
+    pub fn open<P: AsRef<Path>>(path: P, timing: ReplayTiming) -> HexarResult<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?), timing)
+    }
+
+    pub fn from_reader(reader: impl BufRead, timing: ReplayTiming) -> HexarResult<Self> {
+        let mut frames = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            frames.push(serde_json::from_str::<RecordedFrame>(&line)?);
+        }
+        frames.sort_by_key(|frame| frame.offset_ms);
+        Ok(Self { frames, timing })
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.frames.last().map_or(Duration::ZERO, |frame| {
+            Duration::from_millis(frame.offset_ms)
+        })
+    }
+
+    /// Every frame with its offset into the recording, parsed again from its payload
+    pub fn frames(&self) -> impl Iterator<Item = HexarResult<(Duration, SensorFrame)>> + '_ {
+        self.frames.iter().map(|recorded| {
+            let payload = from_hex(&recorded.payload).ok_or_else(|| {
+                HexarError::InvalidParameter(format!(
+                    "bad payload at {} ms: {}",
+                    recorded.offset_ms, recorded.payload
+                ))
+            })?;
+            Ok((
+                Duration::from_millis(recorded.offset_ms),
+                SensorFrame::parse(recorded.antenna_id, recorded.model, &payload),
+            ))
+        })
+    }
+
+    /// Send the frames to `frames` at the pace they were recorded at, as fast as the stage
+    /// takes them with [`ReplayTiming::Manual`]. Stops at the end of the recording or when the
+    /// receiver is gone.
+    pub fn spawn(self, frames: StageSender<SensorFrame>) -> JoinHandle<HexarResult<()>> {
+        tokio::spawn(async move {
+            let started = tokio::time::Instant::now();
+            for frame in self.frames() {
+                let (offset, mut frame) = frame?;
+                let due = match self.timing {
+                    ReplayTiming::Original => Some(offset),
+                    ReplayTiming::Accelerated(speed) if speed > 0.0 => Some(offset.div_f32(speed)),
+                    _ => None,
+                };
+                if let Some(due) = due {
+                    tokio::time::sleep_until(started + due).await;
+                }
+                frame.received_at = Instant::now();
+                if frames.send(frame).await.is_err() {
+                    break;
+                }
+            }
+            debug!("Frame replay finished");
+            Ok(())
+        })
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pipeline::{stage_channel, OverflowPolicy};
+    use nalgebra::Vector2;
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let path =
+            std::env::temp_dir().join(format!("hexar-frames-{}.jsonl", uuid::Uuid::new_v4()));
+        let mut payload = [0u8; 24];
+        payload[..8].copy_from_slice(&[0x0E, 0x03, 0x78, 0x85, 0x10, 0x00, 0x68, 0x01]);
+
+        let mut recorder = FrameRecorder::create(&path).unwrap();
+        for _ in 0..3 {
+            recorder
+                .record(&SensorFrame::parse(4, SensorModel::Ld2450, &payload))
+                .unwrap();
+        }
+        recorder.flush().unwrap();
+        assert_eq!(recorder.get_frame_count(), 3);
+
+        let replay = FrameReplay::open(&path, ReplayTiming::Manual).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.len(), 3);
+
+        let (sender, mut receiver) = stage_channel(8, OverflowPolicy::Block);
+        replay.spawn(sender).await.unwrap().unwrap();
+        let mut replayed = 0;
+        while let Ok(frame) = receiver.try_recv() {
+            assert_eq!(frame.antenna_id, 4);
+            assert_eq!(frame.payload, payload);
+            assert!((frame.measurements[0].position - Vector2::new(-0.782, 1.4)).norm() < 1e-3);
+            replayed += 1;
+        }
+        assert_eq!(replayed, 3);
+    }
+}
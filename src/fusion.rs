@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+
+use log::info;
+use nalgebra::{Rotation2, Vector2, Vector3};
+
+use crate::antenna_array::multilaterate;
+use crate::ekf::SensorOrigin;
+use crate::tracker::MultiTargetTracker;
+
+/// Where a radar is mounted in the room frame
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct SensorPose {
+    pub position: Vector2<f32>,
+    /// Mounting height above the floor in metres
+    pub height: f32,
+    /// Direction of the sensor's x axis in radians, counterclockwise from the room's x axis
+    pub heading: f32,
+}
+
+impl SensorPose {
+    pub fn new(position: Vector2<f32>, height: f32, heading: f32) -> Self {
+        Self {
+            position,
+            height,
+            heading,
+        }
+    }
+
+    /// Room coordinates of a point the sensor reports in its own frame
+    pub fn to_world(&self, local: Vector2<f32>) -> Vector2<f32> {
+        self.position + Rotation2::new(self.heading) * local
+    }
+
+    /// Room coordinates of a 3D point, `local.z` relative to the sensor's mounting height
+    pub fn to_world_3d(&self, local: Vector3<f32>) -> Vector3<f32> {
+        let floor = self.to_world(local.xy());
+        Vector3::new(floor.x, floor.y, self.height + local.z)
+    }
+
+    /// Sensor frame coordinates of a room point
+    pub fn to_local(&self, world: Vector2<f32>) -> Vector2<f32> {
+        Rotation2::new(-self.heading) * (world - self.position)
+    }
+
+    pub fn get_origin(&self) -> SensorOrigin {
+        SensorOrigin::new(self.position, self.heading)
+    }
+}
+
+/// Pose of one antenna in the fusion configuration
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SensorConfig {
+    pub antenna_id: u8,
+    #[cfg_attr(feature = "serde", serde(flatten))]
+    pub pose: SensorPose,
+}
+
+/// Sensor placement and deduplication parameters of a [`SensorFusion`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct FusionConfig {
+    /// Antennas without an entry report in room coordinates already
+    pub sensors: Vec<SensorConfig>,
+    /// Distance in metres within which detections or tracks from different antennas are taken
+    /// to be the same target
+    pub duplicate_distance_m: f32,
+    /// Locate targets several antennas see from their ranges rather than averaging the
+    /// positions they report, which is more accurate when their angles are noisy
+    pub multilateration: bool,
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self {
+            sensors: Vec::new(),
+            duplicate_distance_m: 0.5,
+            multilateration: false,
+        }
+    }
+}
+
+/// Feeds measurements of several radars into one [`MultiTargetTracker`] in the room frame.
+/// Detections of the same person by overlapping sensors are averaged before association, and
+/// tracks that were started by different sensors for the same person are merged afterwards.
+#[derive(Debug, Clone, Default)]
+pub struct SensorFusion {
+    poses: HashMap<u8, SensorPose>,
+    duplicate_distance: f32,
+    multilateration: bool,
+}
+
+impl SensorFusion {
+    pub fn new(duplicate_distance: f32) -> Self {
+        Self {
+            poses: HashMap::new(),
+            duplicate_distance,
+            multilateration: false,
+        }
+    }
+
+    pub fn from_config(config: &FusionConfig) -> Self {
+        let mut fusion = Self::new(config.duplicate_distance_m);
+        fusion.set_multilateration(config.multilateration);
+        for sensor in &config.sensors {
+            fusion.set_pose(sensor.antenna_id, sensor.pose);
+        }
+        fusion
+    }
+
+    pub fn set_pose(&mut self, antenna_id: u8, pose: SensorPose) {
+        self.poses.insert(antenna_id, pose);
+    }
+
+    pub fn get_pose(&self, antenna_id: u8) -> Option<&SensorPose> {
+        self.poses.get(&antenna_id)
+    }
+
+    pub fn get_duplicate_distance(&self) -> f32 {
+        self.duplicate_distance
+    }
+
+    pub fn set_duplicate_distance(&mut self, distance: f32) {
+        self.duplicate_distance = distance;
+    }
+
+    pub fn set_multilateration(&mut self, enabled: bool) {
+        self.multilateration = enabled;
+    }
+
+    /// Room coordinates of a measurement, unchanged for antennas without a pose
+    pub fn to_world(&self, antenna_id: u8, local: Vector2<f32>) -> Vector2<f32> {
+        self.poses
+            .get(&antenna_id)
+            .map_or(local, |pose| pose.to_world(local))
+    }
+
+    /// Convert a frame of `(antenna_id, position)` measurements to the room frame and average
+    /// detections from different antennas closer than the duplicate distance. Returns the fused
+    /// measurements, attributed to the first antenna of each group, and the group each input
+    /// went into. With multilateration on, groups seen by several antennas with known poses are
+    /// placed where the measured ranges meet instead.
+    pub fn fuse_measurements(
+        &self,
+        measurements: &[(u8, Vector2<f32>)],
+    ) -> (Vec<(u8, Vector2<f32>)>, Vec<usize>) {
+        // Members of each group: antennas seen, the sum of their positions and their ranges
+        let mut groups: Vec<(Vec<u8>, Vector2<f32>, Vec<f32>)> = Vec::new();
+        let mut group_of = Vec::with_capacity(measurements.len());
+
+        for &(antenna_id, local) in measurements {
+            let world = self.to_world(antenna_id, local);
+            let group = groups.iter().position(|(antennas, sum, _)| {
+                !antennas.contains(&antenna_id)
+                    && (sum / antennas.len() as f32 - world).norm() < self.duplicate_distance
+            });
+            match group {
+                Some(index) => {
+                    groups[index].0.push(antenna_id);
+                    groups[index].1 += world;
+                    groups[index].2.push(local.norm());
+                    group_of.push(index);
+                }
+                None => {
+                    group_of.push(groups.len());
+                    groups.push((vec![antenna_id], world, vec![local.norm()]));
+                }
+            }
+        }
+
+        let fused = groups
+            .into_iter()
+            .map(|(antennas, sum, ranges)| {
+                let mean = sum / antennas.len() as f32;
+                let position = self
+                    .multilaterate_group(&antennas, &ranges, mean)
+                    .unwrap_or(mean);
+                (antennas[0], position)
+            })
+            .collect();
+        (fused, group_of)
+    }
+
+    fn multilaterate_group(
+        &self,
+        antennas: &[u8],
+        ranges: &[f32],
+        initial: Vector2<f32>,
+    ) -> Option<Vector2<f32>> {
+        if !self.multilateration || antennas.len() < 2 {
+            return None;
+        }
+        let anchors = antennas
+            .iter()
+            .zip(ranges)
+            .map(|(antenna_id, &range)| Some((self.poses.get(antenna_id)?.position, range)))
+            .collect::<Option<Vec<_>>>()?;
+        multilaterate(&anchors, initial)
+    }
+
+    /// Update `tracker` with a frame of measurements in sensor coordinates. Returns the target
+    /// each measurement updated or created, `None` where nothing changed.
+    pub fn process_frame(
+        &self,
+        tracker: &mut MultiTargetTracker,
+        measurements: &[(u8, Vector2<f32>)],
+    ) -> Vec<Option<u32>> {
+        let (fused, group_of) = self.fuse_measurements(measurements);
+        let gate = tracker.get_config().association_gate_m;
+        let mut updated = tracker.update_targets(&fused, gate);
+
+        for (kept, removed) in self.merge_duplicate_tracks(tracker) {
+            for target_id in updated.iter_mut().flatten() {
+                if *target_id == removed {
+                    *target_id = kept;
+                }
+            }
+        }
+
+        group_of.into_iter().map(|group| updated[group]).collect()
+    }
+
+    /// Remove tracks of one person started by different antennas, keeping the established
+    /// one: confirmed before tentative, then the longest tracked. Returns the
+    /// `(kept, removed)` target IDs.
+    pub fn merge_duplicate_tracks(&self, tracker: &mut MultiTargetTracker) -> Vec<(u32, u32)> {
+        let mut targets: Vec<_> = tracker
+            .get_all_targets()
+            .into_iter()
+            .map(|t| {
+                (
+                    t.id,
+                    t.antenna_id,
+                    t.position,
+                    t.is_confirmed(),
+                    t.frame_count,
+                )
+            })
+            .collect();
+        // Most established first, so each track is compared against those it would lose to
+        targets.sort_by_key(|&(id, _, _, confirmed, frames)| {
+            (std::cmp::Reverse((confirmed, frames)), id)
+        });
+
+        let mut kept: Vec<(u32, u8, Vector2<f32>)> = Vec::new();
+        let mut merged = Vec::new();
+        for (id, antenna_id, position, _, _) in targets {
+            let duplicate = kept.iter().find(|(_, kept_antenna, kept_position)| {
+                *kept_antenna != antenna_id
+                    && (kept_position - position).norm() < self.duplicate_distance
+            });
+            match duplicate {
+                Some(&(kept_id, _, _)) => merged.push((kept_id, id)),
+                None => kept.push((id, antenna_id, position)),
+            }
+        }
+
+        for &(kept_id, removed_id) in &merged {
+            tracker.remove_target(removed_id);
+            info!(
+                "Merged target {} into target {} seen by another antenna",
+                removed_id, kept_id
+            );
+        }
+        merged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::PI;
+
+    #[test]
+    fn test_overlapping_sensors_share_one_track() {
+        // Sensor 1 on the opposite wall, facing sensor 0
+        let mut fusion = SensorFusion::new(0.5);
+        fusion.set_pose(0, SensorPose::default());
+        fusion.set_pose(1, SensorPose::new(Vector2::new(4.0, 0.0), 1.0, PI));
+
+        let pose = fusion.get_pose(1).unwrap();
+        assert!((pose.to_world(Vector2::new(2.0, -1.0)) - Vector2::new(2.0, 1.0)).norm() < 1e-5);
+        assert!((pose.to_local(Vector2::new(2.0, 1.0)) - Vector2::new(2.0, -1.0)).norm() < 1e-5);
+        assert!((pose.to_world_3d(Vector3::new(0.0, 0.0, -0.2)).z - 0.8).abs() < 1e-5);
+
+        let mut tracker = MultiTargetTracker::new(2);
+        let frame = [
+            (0, Vector2::new(2.0, 1.0)),
+            (1, Vector2::new(2.0, -1.1)),
+            (1, Vector2::new(1.0, 1.0)),
+        ];
+        let updated = fusion.process_frame(&mut tracker, &frame);
+        assert_eq!(tracker.get_target_count(), 2);
+        assert_eq!(updated[0], updated[1]);
+        assert_ne!(updated[0], updated[2]);
+    }
+
+    #[test]
+    fn test_duplicate_tracks_are_merged() {
+        let fusion = SensorFusion::new(0.5);
+        let mut tracker = MultiTargetTracker::new(2);
+        let first = tracker.add_target(0, Vector2::new(3.0, 3.0)).unwrap();
+        let second = tracker.add_target(1, Vector2::new(3.2, 3.0)).unwrap();
+        // Close targets seen by the same antenna are different people
+        tracker.add_target(0, Vector2::new(3.0, 3.3)).unwrap();
+
+        assert_eq!(
+            fusion.merge_duplicate_tracks(&mut tracker),
+            vec![(first, second)]
+        );
+        assert_eq!(tracker.get_target_count(), 2);
+    }
+
+    #[test]
+    fn test_multilateration_from_ranges() {
+        let mut fusion = SensorFusion::new(1.0);
+        fusion.set_pose(0, SensorPose::default());
+        fusion.set_pose(1, SensorPose::new(Vector2::new(4.0, 0.0), 1.0, PI));
+        // Both ranges are right but the angles are off by 0.2 rad either way
+        let target = Vector2::new(2.0, 1.5);
+        let range = target.norm();
+        let frame = [
+            (0, Rotation2::new(0.2) * target),
+            (1, Rotation2::new(0.2) * Vector2::new(2.0, -1.5)),
+        ];
+
+        let (averaged, _) = fusion.fuse_measurements(&frame);
+        fusion.set_multilateration(true);
+        let (fused, _) = fusion.fuse_measurements(&frame);
+        assert_eq!(fused.len(), 1);
+        assert!((fused[0].1 - target).norm() < 1e-3);
+        assert!((averaged[0].1 - target).norm() > 0.1);
+        assert!((fused[0].1.norm() - range).abs() < 1e-3);
+    }
+}
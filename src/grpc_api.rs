@@ -0,0 +1,411 @@
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use serde::Serialize;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::transport::server::TcpIncoming;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+use tracing::{debug, info, warn};
+
+use crate::api_auth::{self, Authenticator};
+use crate::config::{ApiAuthConfig, GrpcConfig};
+use crate::control_socket::{
+    self, InstanceStatus, SocketCall, SocketRequest, SocketResponse, StreamFilter, StreamMessage,
+    TargetReport,
+};
+use crate::error::{HexarError, HexarResult};
+use crate::radar_controller::RadarEvent;
+
+/// Messages and service generated from `proto/hexar.proto`
+pub mod proto {
+    tonic::include_proto!("hexar.v1");
+}
+
+use proto::hexar_server::{Hexar, HexarServer};
+
+type Calls = mpsc::Sender<SocketCall>;
+
+/// Target updates a `StreamTargets` call buffers for a slow client before waiting on it
+const STREAM_BUFFER: usize = 16;
+
+/// gRPC server of the `Hexar` service in `proto/hexar.proto`, answering from the running
+/// instance's main loop like the control socket. Calls need a token of [`ApiAuthConfig`] in
+/// their `authorization` or `x-api-key` metadata once any are configured. Stops when dropped.
+#[derive(Debug)]
+pub struct GrpcApi {
+    address: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl GrpcApi {
+    /// Listen where `config` says, letting in clients with a token of `auth`, passing requests
+    /// to the main loop through `calls` and streaming the targets of `events`
+    pub async fn serve(
+        config: &GrpcConfig,
+        auth: &ApiAuthConfig,
+        calls: Calls,
+        events: broadcast::Receiver<RadarEvent>,
+    ) -> HexarResult<Self> {
+        let mut server = Server::builder();
+        #[cfg(feature = "tls")]
+        if let Some(tls) = &config.tls {
+            server = server.tls_config(server_tls(tls)?).map_err(|e| {
+                HexarError::ConfigurationError(format!("unusable gRPC TLS settings: {}", e))
+            })?;
+        }
+        #[cfg(not(feature = "tls"))]
+        if config.tls.is_some() {
+            return Err(HexarError::ConfigurationError(
+                "gRPC over TLS needs a build with the tls feature".to_string(),
+            ));
+        }
+        let listener = TcpListener::bind(config.listen).await?;
+        let address = listener.local_addr()?;
+        let auth = Authenticator::new(auth);
+        auth.warn_if_exposed("gRPC API", address, config.tls.is_some());
+        let service = HexarService {
+            calls,
+            events,
+            stream_max_rate_hz: config.stream_max_rate_hz,
+        };
+        let handle = tokio::spawn(async move {
+            let result = server
+                .add_service(HexarServer::with_interceptor(service, move |request| {
+                    authenticate(&auth, request)
+                }))
+                .serve_with_incoming(TcpIncoming::from(listener))
+                .await;
+            if let Err(e) = result {
+                warn!("gRPC API stopped: {}", e);
+            }
+        });
+        info!("gRPC API listening on {}", address);
+        Ok(Self { address, handle })
+    }
+
+    /// Address the server listens on, with the port chosen when configured as 0
+    pub fn get_address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for GrpcApi {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Turn away calls without a configured token
+fn authenticate(auth: &Authenticator, request: Request<()>) -> Result<Request<()>, Status> {
+    let metadata = request.metadata();
+    let token = metadata
+        .get("authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(api_auth::bearer_token)
+        .or_else(|| metadata.get("x-api-key")?.to_str().ok());
+    match auth.check(token) {
+        Ok(client) => {
+            if let Some(name) = client {
+                debug!("gRPC call by {}", name);
+            }
+            Ok(request)
+        }
+        Err(e) => Err(Status::unauthenticated(e.to_string())),
+    }
+}
+
+#[cfg(feature = "tls")]
+fn server_tls(config: &crate::config::TlsConfig) -> HexarResult<tonic::transport::ServerTlsConfig> {
+    let read = |path: &std::path::Path| {
+        std::fs::read(path).map_err(|e| {
+            HexarError::ConfigurationError(format!("failed to read {}: {}", path.display(), e))
+        })
+    };
+    let identity = tonic::transport::Identity::from_pem(read(&config.cert)?, read(&config.key)?);
+    Ok(tonic::transport::ServerTlsConfig::new().identity(identity))
+}
+
+#[derive(Debug)]
+struct HexarService {
+    calls: Calls,
+    /// Resubscribed by every `StreamTargets` call
+    events: broadcast::Receiver<RadarEvent>,
+    stream_max_rate_hz: f32,
+}
+
+impl HexarService {
+    /// Answer of the main loop, requests it refused as invalid arguments
+    async fn request(&self, request: SocketRequest) -> Result<SocketResponse, Status> {
+        match control_socket::call(&self.calls, "grpc", request).await {
+            Ok(SocketResponse::Error { message }) => Err(Status::invalid_argument(message)),
+            Ok(response) => Ok(response),
+            Err(e) => Err(Status::unavailable(e.to_string())),
+        }
+    }
+}
+
+fn unexpected(response: SocketResponse) -> Status {
+    Status::internal(format!("unexpected answer {:?}", response))
+}
+
+/// Name of a unit enum variant as it appears in JSON
+fn json_name<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+impl From<InstanceStatus> for proto::Status {
+    fn from(status: InstanceStatus) -> Self {
+        let health = &status.health;
+        Self {
+            system_id: status.system_id.to_string(),
+            pid: status.pid,
+            version: status.version,
+            started_at_ms: status.started_at.timestamp_millis(),
+            uptime_secs: status.uptime.as_secs(),
+            state: format!("{:?}", health.state),
+            healthy: health.is_healthy(),
+            scanning: status.scanning,
+            emergency_stop: status.emergency_stop,
+            targets: health.tracker.targets as u32,
+            confirmed_targets: health.tracker.confirmed as u32,
+            active_alerts: status.active_alerts.len() as u32,
+            scan_rate_hz: health.scan_rate_hz,
+            antennas: health
+                .antennas
+                .iter()
+                .map(|antenna| proto::AntennaStatus {
+                    antenna_id: antenna.antenna_id.into(),
+                    connected: antenna.connected,
+                    healthy: antenna.is_healthy(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&TargetReport> for proto::Target {
+    fn from(target: &TargetReport) -> Self {
+        Self {
+            id: target.id,
+            antenna_id: target.antenna_id.into(),
+            x: target.position.x,
+            y: target.position.y,
+            vx: target.velocity.x,
+            vy: target.velocity.y,
+            confidence: target.confidence,
+            class: json_name(&target.class),
+            fall_phase: json_name(&target.fall_phase),
+            fall_probability: target.fall_probability,
+        }
+    }
+}
+
+#[tonic::async_trait]
+impl Hexar for HexarService {
+    async fn get_status(
+        &self,
+        _: Request<proto::GetStatusRequest>,
+    ) -> Result<Response<proto::Status>, Status> {
+        match self.request(SocketRequest::Status).await? {
+            SocketResponse::Status { status } => Ok(Response::new((*status).into())),
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn start(
+        &self,
+        _: Request<proto::StartRequest>,
+    ) -> Result<Response<proto::StartResponse>, Status> {
+        self.request(SocketRequest::Resume).await?;
+        Ok(Response::new(proto::StartResponse {}))
+    }
+
+    async fn stop(
+        &self,
+        _: Request<proto::StopRequest>,
+    ) -> Result<Response<proto::StopResponse>, Status> {
+        self.request(SocketRequest::Pause).await?;
+        Ok(Response::new(proto::StopResponse {}))
+    }
+
+    async fn shutdown(
+        &self,
+        _: Request<proto::ShutdownRequest>,
+    ) -> Result<Response<proto::ShutdownResponse>, Status> {
+        self.request(SocketRequest::Stop).await?;
+        Ok(Response::new(proto::ShutdownResponse {}))
+    }
+
+    type StreamTargetsStream = ReceiverStream<Result<proto::TargetUpdate, Status>>;
+
+    async fn stream_targets(
+        &self,
+        _: Request<proto::StreamTargetsRequest>,
+    ) -> Result<Response<Self::StreamTargetsStream>, Status> {
+        let mut events = self.events.resubscribe();
+        let mut filter = StreamFilter::new(self.stream_max_rate_hz);
+        let (updates, receiver) = mpsc::channel(STREAM_BUFFER);
+        tokio::spawn(async move {
+            loop {
+                let event = match events.recv().await {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        warn!("Target stream client missed {} radar events", missed);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(StreamMessage::Targets { timestamp, targets }) =
+                    filter.message(&event, Instant::now())
+                else {
+                    continue;
+                };
+                let update = proto::TargetUpdate {
+                    timestamp_ms: timestamp.timestamp_millis(),
+                    targets: targets.iter().map(Into::into).collect(),
+                };
+                if updates.send(Ok(update)).await.is_err() {
+                    break;
+                }
+            }
+            debug!("Target stream client disconnected");
+        });
+        Ok(Response::new(ReceiverStream::new(receiver)))
+    }
+
+    async fn get_config(
+        &self,
+        _: Request<proto::GetConfigRequest>,
+    ) -> Result<Response<proto::Config>, Status> {
+        match self.request(SocketRequest::Config).await? {
+            SocketResponse::Config { config } => {
+                let json = serde_json::to_string(&config.redacted())
+                    .map_err(|e| Status::internal(e.to_string()))?;
+                Ok(Response::new(proto::Config { json }))
+            }
+            other => Err(unexpected(other)),
+        }
+    }
+
+    async fn set_config(
+        &self,
+        request: Request<proto::SetConfigRequest>,
+    ) -> Result<Response<proto::SetConfigResponse>, Status> {
+        let proto::SetConfigRequest { key, value_json } = request.into_inner();
+        let value = serde_json::from_str(&value_json)
+            .map_err(|e| Status::invalid_argument(format!("value isn't JSON: {}", e)))?;
+        self.request(SocketRequest::SetConfig { key, value })
+            .await?;
+        Ok(Response::new(proto::SetConfigResponse {}))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracker::TrackedTarget;
+    use chrono::Utc;
+    use nalgebra::Vector2;
+    use proto::hexar_client::HexarClient;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_grpc_api() {
+        let (calls, mut received) = mpsc::channel::<SocketCall>(4);
+        let (events, _) = broadcast::channel(4);
+        let config = GrpcConfig {
+            listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+            stream_max_rate_hz: 0.0,
+            tls: None,
+        };
+        let auth = ApiAuthConfig {
+            tokens: vec![crate::config::ApiToken {
+                name: "fleet".to_string(),
+                token: "s3cret".to_string(),
+            }],
+        };
+        let api = GrpcApi::serve(&config, &auth, calls, events.subscribe())
+            .await
+            .unwrap();
+        // The main loop takes settings and pauses, refusing everything else
+        let (requests, mut answered) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(call) = received.recv().await {
+                let response = match &call.request {
+                    SocketRequest::SetConfig { .. } | SocketRequest::Pause => SocketResponse::Done,
+                    _ => SocketResponse::Error {
+                        message: "unsupported".to_string(),
+                    },
+                };
+                requests.send(call.request.clone()).unwrap();
+                call.respond(response);
+            }
+        });
+
+        let channel =
+            tonic::transport::Endpoint::from_shared(format!("http://{}", api.get_address()))
+                .unwrap()
+                .connect()
+                .await
+                .unwrap();
+        let refused = HexarClient::new(channel.clone())
+            .stop(proto::StopRequest {})
+            .await;
+        assert_eq!(refused.unwrap_err().code(), tonic::Code::Unauthenticated);
+        let mut client = HexarClient::with_interceptor(channel, |mut request: Request<()>| {
+            let token = "Bearer s3cret".parse().unwrap();
+            request.metadata_mut().insert("authorization", token);
+            Ok(request)
+        });
+        client.stop(proto::StopRequest {}).await.unwrap();
+        assert_eq!(answered.recv().await, Some(SocketRequest::Pause));
+        let setting = proto::SetConfigRequest {
+            key: "radar.scan_mode".to_string(),
+            value_json: "\"intermittent\"".to_string(),
+        };
+        client.set_config(setting).await.unwrap();
+        assert_eq!(
+            answered.recv().await,
+            Some(SocketRequest::SetConfig {
+                key: "radar.scan_mode".to_string(),
+                value: serde_json::json!("intermittent"),
+            })
+        );
+        let status = client.get_status(proto::GetStatusRequest {}).await;
+        assert_eq!(status.unwrap_err().code(), tonic::Code::InvalidArgument);
+        let garbled = proto::SetConfigRequest {
+            key: "radar.scan_mode".to_string(),
+            value_json: "intermittent".to_string(),
+        };
+        assert!(client.set_config(garbled).await.is_err());
+
+        let mut stream = client
+            .stream_targets(proto::StreamTargetsRequest {})
+            .await
+            .unwrap()
+            .into_inner();
+        let target = TrackedTarget::new(5, 1, Vector2::new(1.0, 2.0));
+        events.send(RadarEvent::TargetUpdated(target)).unwrap();
+        events
+            .send(RadarEvent::ScanCompleted {
+                scan_id: uuid::Uuid::new_v4(),
+                timestamp: Utc::now(),
+                scan_duration: Duration::from_millis(5),
+                signals_processed: 0,
+                targets_detected: 1,
+            })
+            .unwrap();
+        let update = stream.message().await.unwrap().unwrap();
+        assert_eq!(update.targets.len(), 1);
+        assert_eq!(update.targets[0].id, 5);
+        assert_eq!(update.targets[0].y, 2.0);
+        assert_eq!(update.targets[0].class, "unknown");
+    }
+}
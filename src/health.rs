@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::accumulator::AccumulatorStats;
+use crate::radar_controller::{ControllerState, ScanStatistics};
+
+/// The most recent error the controller ran into
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LastError {
+    pub timestamp: DateTime<Utc>,
+    pub message: String,
+}
+
+/// State of one antenna's radar module
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AntennaHealth {
+    pub antenna_id: u8,
+    pub connected: bool,
+    /// Why the serial link is down
+    pub link_error: Option<String>,
+    /// Failed attempts to reopen the port since the link went down
+    pub reconnect_attempts: u32,
+    /// `None` until a self-test ran
+    pub passed_self_test: Option<bool>,
+    /// Left out of the scan cycle after failing the self-test
+    pub excluded: bool,
+    pub frames_parsed: u32,
+    pub malformed_frames: u32,
+    /// Bytes skipped while resynchronizing on a frame header
+    pub bytes_discarded: u32,
+}
+
+impl AntennaHealth {
+    pub fn new(antenna_id: u8) -> Self {
+        Self {
+            antenna_id,
+            connected: true,
+            link_error: None,
+            reconnect_attempts: 0,
+            passed_self_test: None,
+            excluded: false,
+            frames_parsed: 0,
+            malformed_frames: 0,
+            bytes_discarded: 0,
+        }
+    }
+
+    pub fn set_parser_stats(&mut self, stats: AccumulatorStats) {
+        self.frames_parsed = stats.frames_parsed;
+        self.malformed_frames = stats.malformed_frames;
+        self.bytes_discarded = stats.bytes_discarded;
+    }
+
+    /// Share of the frames received that were malformed
+    pub fn frame_error_rate(&self) -> f32 {
+        let total = self.frames_parsed.saturating_add(self.malformed_frames);
+        if total == 0 {
+            0.0
+        } else {
+            self.malformed_frames as f32 / total as f32
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.connected && !self.excluded && self.passed_self_test != Some(false)
+    }
+}
+
+/// Targets the tracker follows, by status
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TrackerHealth {
+    pub targets: usize,
+    pub confirmed: usize,
+    pub tentative: usize,
+    pub falling: usize,
+    /// Targets no longer moving but still reported present
+    pub stationary: usize,
+}
+
+/// Everything known about the controller's condition in one place, for the status command,
+/// monitoring and remote interfaces to report the same numbers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub timestamp: DateTime<Utc>,
+    pub state: ControllerState,
+    /// Time spent in the current state
+    pub state_duration: Duration,
+    /// Time since the last scan cycle, `None` before the first
+    pub since_last_scan: Option<Duration>,
+    pub scan_rate_hz: f32,
+    pub scan: ScanStatistics,
+    pub tracker: TrackerHealth,
+    pub antennas: Vec<AntennaHealth>,
+    /// Share of the array's area the working antennas cover, `None` without radar modules
+    pub coverage: Option<f32>,
+    pub last_error: Option<LastError>,
+}
+
+impl HealthReport {
+    /// Scanning or ready to, with every antenna working
+    pub fn is_healthy(&self) -> bool {
+        matches!(
+            self.state,
+            ControllerState::Ready | ControllerState::Scanning
+        ) && self.antennas.iter().all(AntennaHealth::is_healthy)
+    }
+
+    pub fn unhealthy_antennas(&self) -> Vec<u8> {
+        self.antennas
+            .iter()
+            .filter(|antenna| !antenna.is_healthy())
+            .map(|antenna| antenna.antenna_id)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_antenna_health() {
+        let mut antenna = AntennaHealth::new(2);
+        assert!(antenna.is_healthy());
+        assert_eq!(antenna.frame_error_rate(), 0.0);
+
+        antenna.set_parser_stats(AccumulatorStats {
+            frames_parsed: 95,
+            malformed_frames: 5,
+            bytes_discarded: 40,
+        });
+        assert!((antenna.frame_error_rate() - 0.05).abs() < 1e-6);
+
+        antenna.passed_self_test = Some(false);
+        assert!(!antenna.is_healthy());
+    }
+}
@@ -0,0 +1,221 @@
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use nalgebra::Vector2;
+
+use crate::tracker::TrackedTarget;
+#[cfg(feature = "png")]
+use crate::waterfall::heat_color;
+
+/// Longest gap between two feeds credited to the targets, so a stalled caller doesn't paint
+/// minutes of presence into one cell
+const MAX_FEED_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Grid over the floor plan accumulating how long targets spent in each cell. Old presence
+/// can fade with a half-life, so the map follows changing use of a room over days.
+#[derive(Debug, Clone)]
+pub struct OccupancyHeatmap {
+    /// Room coordinates of the corner of cell (0, 0)
+    origin: Vector2<f32>,
+    resolution: f32,
+    columns: usize,
+    rows: usize,
+    /// Seconds of presence per cell, row-major from the origin
+    cells: Vec<f32>,
+    half_life: Option<Duration>,
+    last_decay: Option<Instant>,
+    last_feed: Option<Instant>,
+}
+
+impl OccupancyHeatmap {
+    /// Map `width` × `height` metres from `origin` in cells of `resolution` metres
+    pub fn new(origin: Vector2<f32>, width: f32, height: f32, resolution: f32) -> Self {
+        let resolution = resolution.max(0.01);
+        let columns = (width / resolution).ceil().max(1.0) as usize;
+        let rows = (height / resolution).ceil().max(1.0) as usize;
+        Self {
+            origin,
+            resolution,
+            columns,
+            rows,
+            cells: vec![0.0; columns * rows],
+            half_life: None,
+            last_decay: None,
+            last_feed: None,
+        }
+    }
+
+    /// Let accumulated presence halve every `half_life`, `None` to keep it forever
+    pub fn set_half_life(&mut self, half_life: Option<Duration>) {
+        self.half_life = half_life.filter(|h| !h.is_zero());
+    }
+
+    pub fn get_half_life(&self) -> Option<Duration> {
+        self.half_life
+    }
+
+    /// Columns along x and rows along y
+    pub fn get_size(&self) -> (usize, usize) {
+        (self.columns, self.rows)
+    }
+
+    pub fn get_resolution(&self) -> f32 {
+        self.resolution
+    }
+
+    /// Cell containing a room position, `None` outside the map
+    pub fn cell_at(&self, position: Vector2<f32>) -> Option<(usize, usize)> {
+        let offset = (position - self.origin) / self.resolution;
+        if offset.x < 0.0 || offset.y < 0.0 || !offset.x.is_finite() || !offset.y.is_finite() {
+            return None;
+        }
+        let (column, row) = (offset.x as usize, offset.y as usize);
+        (column < self.columns && row < self.rows).then_some((column, row))
+    }
+
+    pub fn get_cell(&self, column: usize, row: usize) -> Option<f32> {
+        (column < self.columns && row < self.rows).then(|| self.cells[row * self.columns + column])
+    }
+
+    /// Add `seconds` of presence at `position`, decaying the map up to `now` first
+    pub fn record(&mut self, position: Vector2<f32>, seconds: f32, now: Instant) {
+        self.decay(now);
+        if let Some((column, row)) = self.cell_at(position) {
+            self.cells[row * self.columns + column] += seconds;
+        }
+    }
+
+    /// Credit each target with the time since the previous feed at its current position
+    pub fn record_targets<'a, I>(&mut self, targets: I, now: Instant)
+    where
+        I: IntoIterator<Item = &'a TrackedTarget>,
+    {
+        let elapsed = self
+            .last_feed
+            .map(|last| now.saturating_duration_since(last).min(MAX_FEED_INTERVAL))
+            .unwrap_or_default()
+            .as_secs_f32();
+        self.last_feed = Some(now);
+
+        self.decay(now);
+        if elapsed > 0.0 {
+            for target in targets {
+                if let Some((column, row)) = self.cell_at(target.position) {
+                    self.cells[row * self.columns + column] += elapsed;
+                }
+            }
+        }
+    }
+
+    /// Cell values as rows along y, each holding the columns along x
+    pub fn to_grid(&self) -> Vec<Vec<f32>> {
+        self.cells
+            .chunks(self.columns)
+            .map(|row| row.to_vec())
+            .collect()
+    }
+
+    /// One line per cell: `x,y,seconds` with x/y the cell centre in metres
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "x,y,seconds")?;
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let centre = self.origin
+                    + Vector2::new(column as f32 + 0.5, row as f32 + 0.5) * self.resolution;
+                writeln!(
+                    writer,
+                    "{:.3},{:.3},{:.3}",
+                    centre.x,
+                    centre.y,
+                    self.cells[row * self.columns + column]
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Render as an RGB image, one pixel per cell with y growing upwards, scaled to the
+    /// busiest cell
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        let max = self.cells.iter().copied().fold(0.0f32, f32::max);
+        let scale = if max > 0.0 { 1.0 / max } else { 0.0 };
+
+        let mut pixels = Vec::with_capacity(self.cells.len() * 3);
+        for row in self.cells.chunks(self.columns).rev() {
+            for &seconds in row {
+                pixels.extend_from_slice(&heat_color(seconds * scale));
+            }
+        }
+
+        let mut encoder = png::Encoder::new(writer, self.columns as u32, self.rows as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut png| png.write_image_data(&pixels))
+            .map_err(io::Error::other)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.fill(0.0);
+        self.last_feed = None;
+    }
+
+    fn decay(&mut self, now: Instant) {
+        let last = self.last_decay.replace(now);
+        let (Some(half_life), Some(last)) = (self.half_life, last) else {
+            return;
+        };
+        let elapsed = now.saturating_duration_since(last);
+        if elapsed.is_zero() {
+            return;
+        }
+
+        let factor = 0.5f32.powf(elapsed.as_secs_f32() / half_life.as_secs_f32());
+        for cell in &mut self.cells {
+            *cell *= factor;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accumulation_and_decay() {
+        let mut heatmap = OccupancyHeatmap::new(Vector2::new(-1.0, 0.0), 2.0, 1.0, 0.5);
+        assert_eq!(heatmap.get_size(), (4, 2));
+        assert_eq!(heatmap.cell_at(Vector2::new(0.2, 0.7)), Some((2, 1)));
+        assert_eq!(heatmap.cell_at(Vector2::new(1.5, 0.0)), None);
+
+        let start = Instant::now();
+        let target = TrackedTarget::new(0, 0, Vector2::new(0.2, 0.7));
+        heatmap.record_targets([&target], start);
+        heatmap.record_targets([&target], start + Duration::from_millis(500));
+        // Long pauses are capped
+        heatmap.record_targets([&target], start + Duration::from_secs(60));
+        assert_eq!(heatmap.get_cell(2, 1), Some(1.5));
+
+        heatmap.set_half_life(Some(Duration::from_secs(10)));
+        heatmap.record(
+            Vector2::new(-0.9, 0.1),
+            1.0,
+            start + Duration::from_secs(60),
+        );
+        heatmap.record(
+            Vector2::new(-0.9, 0.1),
+            0.0,
+            start + Duration::from_secs(70),
+        );
+        assert_eq!(heatmap.to_grid()[1][2], 0.75);
+        assert_eq!(heatmap.to_grid()[0][0], 0.5);
+
+        let mut csv = Vec::new();
+        heatmap.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().nth(1), Some("-0.750,0.250,0.500"));
+        assert_eq!(csv.lines().count(), 9);
+    }
+}
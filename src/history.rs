@@ -0,0 +1,169 @@
+use std::collections::VecDeque;
+
+use crate::scanner::SignalReading;
+
+/// Readings kept by default, a few sweeps of a fine-stepped band
+pub const DEFAULT_HISTORY_CAPACITY: usize = 4096;
+
+/// Running statistics over every reading ever recorded, including those already evicted from
+/// the [`ReadingHistory`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReadingStats {
+    pub count: u64,
+    pub mean: f32,
+    pub min: f32,
+    pub max: f32,
+    /// Welford's sum of squared deviations
+    m2: f64,
+}
+
+impl Default for ReadingStats {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            mean: 0.0,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            m2: 0.0,
+        }
+    }
+}
+
+impl ReadingStats {
+    /// Add one strength in dB, non-finite values are skipped
+    pub fn add(&mut self, strength: f32) {
+        if !strength.is_finite() {
+            return;
+        }
+
+        self.count += 1;
+        let delta = strength as f64 - self.mean as f64;
+        let mean = self.mean as f64 + delta / self.count as f64;
+        self.m2 += delta * (strength as f64 - mean);
+        self.mean = mean as f32;
+        self.min = self.min.min(strength);
+        self.max = self.max.max(strength);
+    }
+
+    /// Population variance in dB², 0 with fewer than two readings
+    pub fn variance(&self) -> f32 {
+        if self.count < 2 {
+            0.0
+        } else {
+            (self.m2 / self.count as f64) as f32
+        }
+    }
+}
+
+/// Most recent readings in a fixed-capacity ring buffer, the oldest dropped once it is full, so
+/// memory stays constant over long continuous scans
+#[derive(Debug, Clone)]
+pub struct ReadingHistory {
+    readings: VecDeque<SignalReading>,
+    capacity: usize,
+    stats: ReadingStats,
+}
+
+impl Default for ReadingHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl ReadingHistory {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            readings: VecDeque::with_capacity(capacity),
+            capacity,
+            stats: ReadingStats::default(),
+        }
+    }
+
+    pub fn push(&mut self, reading: SignalReading) {
+        self.stats.add(reading.strength);
+        if self.readings.len() == self.capacity {
+            self.readings.pop_front();
+        }
+        self.readings.push_back(reading);
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Resize the buffer, dropping the oldest readings if it shrinks
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.readings.len() > self.capacity {
+            self.readings.pop_front();
+        }
+        self.readings.shrink_to(self.capacity);
+    }
+
+    /// Statistics over all readings since the last [`ReadingHistory::clear`]
+    pub fn get_stats(&self) -> &ReadingStats {
+        &self.stats
+    }
+
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    /// Retained readings from oldest to newest
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &SignalReading> + ExactSizeIterator {
+        self.readings.iter()
+    }
+
+    /// Up to `count` readings, newest first
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &SignalReading> {
+        self.readings.iter().rev().take(count)
+    }
+
+    pub fn clear(&mut self) {
+        self.readings.clear();
+        self.stats = ReadingStats::default();
+    }
+}
+
+impl Extend<SignalReading> for ReadingHistory {
+    fn extend<I: IntoIterator<Item = SignalReading>>(&mut self, readings: I) {
+        for reading in readings {
+            self.push(reading);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_bounded_with_running_stats() {
+        let mut history = ReadingHistory::new(3);
+        let timestamp = Instant::now();
+        history.extend([-80.0, -40.0, -60.0, -70.0, -50.0].map(|strength| SignalReading {
+            frequency: 433.0,
+            strength,
+            timestamp,
+        }));
+
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.iter().next().unwrap().strength, -60.0);
+        assert_eq!(history.recent(1).next().unwrap().strength, -50.0);
+
+        let stats = history.get_stats();
+        assert_eq!(stats.count, 5);
+        assert_eq!((stats.mean, stats.min, stats.max), (-60.0, -80.0, -40.0));
+        assert_eq!(stats.variance(), 200.0);
+
+        history.set_capacity(1);
+        assert_eq!(history.len(), 1);
+        assert_eq!(history.get_stats().count, 5);
+    }
+}
@@ -0,0 +1,144 @@
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use sysinfo::{Disks, Networks, Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+/// Usage of the host and of this process at one point in time
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct HostMetrics {
+    /// All CPUs, averaged since the previous sample
+    pub cpu_usage_percent: f32,
+    pub memory_usage_percent: f32,
+    /// Filesystem holding the working directory, where logs and recordings go by default
+    pub disk_usage_percent: f32,
+    /// Received and sent on all interfaces since the previous sample
+    pub network_io_bytes_per_second: u64,
+    /// 1, 5 and 15 minutes, zero where the OS has none
+    pub load_average: [f32; 3],
+    /// Resident memory of this process
+    pub process_memory_bytes: u64,
+}
+
+/// Samples [`HostMetrics`]. Rates are measured since the previous sample, or since the sampler
+/// was created for the first one.
+pub struct HostSampler {
+    system: System,
+    disks: Disks,
+    networks: Networks,
+    pid: Option<Pid>,
+    directory: PathBuf,
+    last_sample: Instant,
+}
+
+impl HostSampler {
+    pub fn new() -> Self {
+        let mut system = System::new();
+        system.refresh_cpu_usage();
+        Self {
+            system,
+            disks: Disks::new_with_refreshed_list(),
+            networks: Networks::new_with_refreshed_list(),
+            pid: sysinfo::get_current_pid().ok(),
+            directory: std::env::current_dir()
+                .and_then(std::fs::canonicalize)
+                .unwrap_or_else(|_| PathBuf::from("/")),
+            last_sample: Instant::now(),
+        }
+    }
+
+    pub fn sample(&mut self) -> HostMetrics {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_sample).as_secs_f64();
+        self.last_sample = now;
+
+        self.system.refresh_cpu_usage();
+        self.system.refresh_memory();
+        self.disks.refresh(true);
+        self.networks.refresh(true);
+        if let Some(pid) = self.pid {
+            self.system.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&[pid]),
+                true,
+                ProcessRefreshKind::nothing().with_memory(),
+            );
+        }
+
+        let transferred: u64 = self
+            .networks
+            .values()
+            .map(|network| network.received() + network.transmitted())
+            .sum();
+        let disks = self.disks.iter().map(|disk| {
+            (
+                disk.mount_point(),
+                disk.total_space(),
+                disk.available_space(),
+            )
+        });
+        let load = System::load_average();
+        HostMetrics {
+            cpu_usage_percent: self.system.global_cpu_usage(),
+            memory_usage_percent: percent(self.system.used_memory(), self.system.total_memory()),
+            disk_usage_percent: disk_usage(disks, &self.directory).unwrap_or_default(),
+            network_io_bytes_per_second: if elapsed > 0.0 {
+                (transferred as f64 / elapsed) as u64
+            } else {
+                0
+            },
+            load_average: [load.one as f32, load.five as f32, load.fifteen as f32],
+            process_memory_bytes: self
+                .pid
+                .and_then(|pid| self.system.process(pid))
+                .map_or(0, |process| process.memory()),
+        }
+    }
+}
+
+impl Default for HostSampler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn percent(used: u64, total: u64) -> f32 {
+    if total == 0 {
+        return 0.0;
+    }
+    (used as f64 / total as f64 * 100.0) as f32
+}
+
+/// Usage of the filesystem mounted deepest above `path`, given mount points with their total
+/// and available bytes
+fn disk_usage<'a>(disks: impl Iterator<Item = (&'a Path, u64, u64)>, path: &Path) -> Option<f32> {
+    disks
+        .filter(|(mount_point, _, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _, _)| mount_point.components().count())
+        .map(|(_, total, available)| percent(total.saturating_sub(available), total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_disk_usage() {
+        let disks = [
+            (Path::new("/"), 100, 75),
+            (Path::new("/var"), 200, 20),
+            (Path::new("/var/lib/hexar-old"), 10, 0),
+        ];
+        let usage = |path| disk_usage(disks.iter().copied(), Path::new(path));
+        assert_eq!(usage("/var/lib/hexar"), Some(90.0));
+        assert_eq!(usage("/home/pi"), Some(25.0));
+        assert_eq!(disk_usage(std::iter::empty(), Path::new("/")), None);
+    }
+
+    #[test]
+    fn test_sample() {
+        let mut sampler = HostSampler::new();
+        let metrics = sampler.sample();
+        assert!((0.0..=100.0).contains(&metrics.memory_usage_percent));
+        assert!((0.0..=100.0).contains(&metrics.disk_usage_percent));
+        assert!(metrics.process_memory_bytes > 0);
+    }
+}
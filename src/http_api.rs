@@ -0,0 +1,605 @@
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{FromRef, Path, Request, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, put};
+use axum::serve::Listener;
+use axum::{Json, Router};
+use percent_encoding::percent_decode_str;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::api_auth::{self, Authenticator};
+use crate::config::{ApiAuthConfig, HexarConfig, HttpApiConfig};
+use crate::control::{ControlCommand, Zone};
+use crate::control_socket::{
+    self, InstanceStatus, SocketCall, SocketRequest, SocketResponse, StreamFilter, TargetReport,
+    ZoneReport,
+};
+use crate::error::HexarResult;
+use crate::metrics::Exposition;
+use crate::monitoring::Alert;
+use crate::prometheus;
+use crate::radar_controller::RadarEvent;
+
+type Calls = mpsc::Sender<SocketCall>;
+
+/// Header taking an API token as it is, instead of `Authorization: Bearer <token>`
+const API_KEY_HEADER: &str = "x-api-key";
+
+#[derive(Debug, Clone)]
+struct ApiState {
+    calls: Calls,
+    /// Resubscribed by every stream client
+    events: Arc<broadcast::Receiver<RadarEvent>>,
+    stream_max_rate_hz: f32,
+}
+
+impl FromRef<ApiState> for Calls {
+    fn from_ref(state: &ApiState) -> Self {
+        state.calls.clone()
+    }
+}
+
+/// HTTP server answering from the running instance's main loop, like the control socket:
+///
+/// - `GET /status`, `GET /alerts`, `GET /targets`, `GET /config`
+/// - `GET /zones` with the targets in each, `PUT /zones` to replace them
+/// - `PUT /config/<dotted path>` to change a setting that can change at runtime
+/// - `GET /metrics` for Prometheus to scrape
+/// - `GET /stream`, a WebSocket sending a [`StreamMessage`](control_socket::StreamMessage) as
+///   JSON for every change
+///
+/// Every request needs a token of [`ApiAuthConfig`] once any are configured. Stops when
+/// dropped.
+#[derive(Debug)]
+pub struct HttpApi {
+    address: SocketAddr,
+    handle: JoinHandle<()>,
+}
+
+impl HttpApi {
+    /// Listen where `config` says, letting in clients with a token of `auth`, passing requests
+    /// to the main loop through `calls` and streaming `events`
+    pub async fn serve(
+        config: &HttpApiConfig,
+        auth: &ApiAuthConfig,
+        calls: Calls,
+        events: broadcast::Receiver<RadarEvent>,
+    ) -> HexarResult<Self> {
+        let listener = TcpListener::bind(config.listen).await?;
+        let address = listener.local_addr()?;
+        let auth = Authenticator::new(auth);
+        auth.warn_if_exposed("HTTP API", address, config.tls.is_some());
+        let state = ApiState {
+            calls,
+            events: Arc::new(events),
+            stream_max_rate_hz: config.stream_max_rate_hz,
+        };
+        let app = router(state, auth);
+        let handle = match &config.tls {
+            #[cfg(feature = "tls")]
+            Some(tls) => {
+                let listener = tls::TlsListener::new(listener, tls::acceptor(tls)?)?;
+                tokio::spawn(run(listener, app))
+            }
+            #[cfg(not(feature = "tls"))]
+            Some(_) => {
+                return Err(crate::error::HexarError::ConfigurationError(
+                    "HTTPS needs a build with the tls feature".to_string(),
+                ))
+            }
+            None => tokio::spawn(run(listener, app)),
+        };
+        let scheme = if config.tls.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        info!("HTTP API listening on {}://{}", scheme, address);
+        Ok(Self { address, handle })
+    }
+
+    /// Address the server listens on, with the port chosen when configured as 0
+    pub fn get_address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+impl Drop for HttpApi {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn run<L>(listener: L, app: Router)
+where
+    L: Listener,
+    L::Addr: fmt::Debug,
+{
+    if let Err(e) = axum::serve(listener, app).await {
+        warn!("HTTP API stopped: {}", e);
+    }
+}
+
+fn router(state: ApiState, auth: Authenticator) -> Router {
+    Router::new()
+        .route("/status", get(status))
+        .route("/alerts", get(alerts))
+        .route("/targets", get(targets))
+        .route("/zones", get(zones).put(set_zones))
+        .route("/config", get(config))
+        .route("/metrics", get(metrics))
+        .route("/config/{key}", put(set_config))
+        .route("/stream", get(stream))
+        .with_state(state)
+        .layer(middleware::from_fn_with_state(auth, authenticate))
+}
+
+/// Turn away requests without a configured token. WebSocket clients in browsers can't set
+/// headers, so `/stream` also takes it as `?access_token=<token>`.
+async fn authenticate(State(auth): State<Authenticator>, request: Request, next: Next) -> Response {
+    // Percent-encoded like any query value, browsers encode a `/` or `+` in the token
+    let query_token = match request.uri().path() {
+        "/stream" => request
+            .uri()
+            .query()
+            .and_then(|query| {
+                query
+                    .split('&')
+                    .find_map(|pair| pair.strip_prefix("access_token="))
+            })
+            .and_then(|token| percent_decode_str(token).decode_utf8().ok())
+            .map(|token| token.into_owned()),
+        _ => None,
+    };
+    let headers = request.headers();
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(api_auth::bearer_token)
+        .or_else(|| headers.get(API_KEY_HEADER)?.to_str().ok())
+        .or(query_token.as_deref());
+    match auth.check(token) {
+        Ok(client) => {
+            if let Some(name) = client {
+                debug!("{} {} by {}", request.method(), request.uri().path(), name);
+            }
+            next.run(request).await
+        }
+        Err(e) => {
+            let mut response = ApiError {
+                status: StatusCode::UNAUTHORIZED,
+                message: e.to_string(),
+            }
+            .into_response();
+            response
+                .headers_mut()
+                .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+            response
+        }
+    }
+}
+
+/// Answered with the status code and `{"error": message}`
+#[derive(Debug)]
+struct ApiError {
+    status: StatusCode,
+    message: String,
+}
+
+impl ApiError {
+    fn unexpected(response: SocketResponse) -> Self {
+        Self {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            message: format!("unexpected answer {:?}", response),
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = Json(serde_json::json!({ "error": self.message }));
+        (self.status, body).into_response()
+    }
+}
+
+/// Answer of the main loop, requests it refused as bad requests
+async fn request(calls: &Calls, request: SocketRequest) -> Result<SocketResponse, ApiError> {
+    match control_socket::call(calls, "http", request).await {
+        Ok(SocketResponse::Error { message }) => Err(ApiError {
+            status: StatusCode::BAD_REQUEST,
+            message,
+        }),
+        Ok(response) => Ok(response),
+        Err(e) => Err(ApiError {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message: e.to_string(),
+        }),
+    }
+}
+
+async fn instance_status(calls: &Calls) -> Result<InstanceStatus, ApiError> {
+    match request(calls, SocketRequest::Status).await? {
+        SocketResponse::Status { status } => Ok(*status),
+        other => Err(ApiError::unexpected(other)),
+    }
+}
+
+async fn status(State(calls): State<Calls>) -> Result<Json<InstanceStatus>, ApiError> {
+    instance_status(&calls).await.map(Json)
+}
+
+async fn alerts(State(calls): State<Calls>) -> Result<Json<Vec<Alert>>, ApiError> {
+    let status = instance_status(&calls).await?;
+    Ok(Json(status.active_alerts))
+}
+
+async fn targets(State(calls): State<Calls>) -> Result<Json<Vec<TargetReport>>, ApiError> {
+    match request(&calls, SocketRequest::Targets).await? {
+        SocketResponse::Targets { targets } => Ok(Json(targets)),
+        other => Err(ApiError::unexpected(other)),
+    }
+}
+
+async fn zones(State(calls): State<Calls>) -> Result<Json<Vec<ZoneReport>>, ApiError> {
+    match request(&calls, SocketRequest::Zones).await? {
+        SocketResponse::Zones { zones } => Ok(Json(zones)),
+        other => Err(ApiError::unexpected(other)),
+    }
+}
+
+async fn set_zones(
+    State(calls): State<Calls>,
+    Json(zones): Json<Vec<Zone>>,
+) -> Result<StatusCode, ApiError> {
+    let command = ControlCommand::SetZones { zones };
+    request(&calls, SocketRequest::Control { command }).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn config(State(calls): State<Calls>) -> Result<Json<HexarConfig>, ApiError> {
+    match request(&calls, SocketRequest::Config).await? {
+        SocketResponse::Config { config } => Ok(Json(config.redacted())),
+        other => Err(ApiError::unexpected(other)),
+    }
+}
+
+async fn set_config(
+    State(calls): State<Calls>,
+    Path(key): Path<String>,
+    Json(value): Json<serde_json::Value>,
+) -> Result<StatusCode, ApiError> {
+    request(&calls, SocketRequest::SetConfig { key, value }).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn metrics(State(calls): State<Calls>) -> Result<Response, ApiError> {
+    let text = prometheus::collect(&calls, "http")
+        .await
+        .map_err(|e| ApiError {
+            status: StatusCode::SERVICE_UNAVAILABLE,
+            message: e.to_string(),
+        })?;
+    Ok(([(header::CONTENT_TYPE, Exposition::CONTENT_TYPE)], text).into_response())
+}
+
+async fn stream(State(state): State<ApiState>, upgrade: WebSocketUpgrade) -> Response {
+    let events = state.events.resubscribe();
+    let filter = StreamFilter::new(state.stream_max_rate_hz);
+    upgrade.on_upgrade(move |socket| send_stream(socket, events, filter))
+}
+
+async fn send_stream(
+    mut socket: WebSocket,
+    mut events: broadcast::Receiver<RadarEvent>,
+    mut filter: StreamFilter,
+) {
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let Some(message) = filter.message(&event, Instant::now()) else {
+                        continue;
+                    };
+                    let Ok(json) = serde_json::to_string(&message) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(json.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!("Stream client missed {} radar events", missed);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            // Nothing is expected from the client but closing
+            received = socket.recv() => {
+                if !matches!(received, Some(Ok(message)) if !matches!(message, Message::Close(_))) {
+                    break;
+                }
+            }
+        }
+    }
+    debug!("Stream client disconnected");
+}
+
+/// HTTPS with rustls
+#[cfg(feature = "tls")]
+mod tls {
+    use std::io;
+    use std::net::SocketAddr;
+    use std::path::Path;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc;
+    use tokio::task::JoinHandle;
+    use tokio_rustls::rustls::crypto::ring;
+    use tokio_rustls::rustls::pki_types::pem::PemObject;
+    use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+    use tokio_rustls::rustls::ServerConfig;
+    use tokio_rustls::server::TlsStream;
+    use tokio_rustls::TlsAcceptor;
+    use tracing::debug;
+
+    use crate::config::TlsConfig;
+    use crate::error::{HexarError, HexarResult};
+
+    /// Time a client gets to complete its handshake
+    const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    /// Connections through their handshake waiting for the server to take them
+    const BACKLOG: usize = 16;
+
+    /// Wait after failing to accept a connection, e.g. with the process out of file descriptors
+    const ACCEPT_RETRY: Duration = Duration::from_millis(100);
+
+    pub fn acceptor(config: &TlsConfig) -> HexarResult<TlsAcceptor> {
+        let unreadable = |path: &Path, e: &dyn std::fmt::Display| {
+            HexarError::ConfigurationError(format!("failed to read {}: {}", path.display(), e))
+        };
+        let certs = CertificateDer::pem_file_iter(&config.cert)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| unreadable(&config.cert, &e))?;
+        let key =
+            PrivateKeyDer::from_pem_file(&config.key).map_err(|e| unreadable(&config.key, &e))?;
+        let mut server = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map_err(|e| {
+                HexarError::ConfigurationError(format!(
+                    "unusable certificate {}: {}",
+                    config.cert.display(),
+                    e
+                ))
+            })?;
+        // Served without HTTP/2
+        server.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(server)))
+    }
+
+    /// Accepts connections and runs their handshakes side by side, so a slow client holds up
+    /// no one else. Stops accepting when dropped.
+    #[derive(Debug)]
+    pub struct TlsListener {
+        address: SocketAddr,
+        handshaken: mpsc::Receiver<(TlsStream<TcpStream>, SocketAddr)>,
+        accepting: JoinHandle<()>,
+    }
+
+    impl TlsListener {
+        pub fn new(listener: TcpListener, acceptor: TlsAcceptor) -> io::Result<Self> {
+            let address = listener.local_addr()?;
+            let (sender, handshaken) = mpsc::channel(BACKLOG);
+            let accepting = tokio::spawn(async move {
+                loop {
+                    let (stream, peer) = match listener.accept().await {
+                        Ok(connection) => connection,
+                        Err(e) => {
+                            debug!("Failed to accept HTTPS connection: {}", e);
+                            tokio::time::sleep(ACCEPT_RETRY).await;
+                            continue;
+                        }
+                    };
+                    let (acceptor, sender) = (acceptor.clone(), sender.clone());
+                    tokio::spawn(async move {
+                        match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await
+                        {
+                            Ok(Ok(stream)) => {
+                                let _ = sender.send((stream, peer)).await;
+                            }
+                            Ok(Err(e)) => debug!("TLS handshake with {} failed: {}", peer, e),
+                            Err(_) => debug!("TLS handshake with {} timed out", peer),
+                        }
+                    });
+                }
+            });
+            Ok(Self {
+                address,
+                handshaken,
+                accepting,
+            })
+        }
+    }
+
+    impl Drop for TlsListener {
+        fn drop(&mut self) {
+            self.accepting.abort();
+        }
+    }
+
+    impl axum::serve::Listener for TlsListener {
+        type Io = TlsStream<TcpStream>;
+        type Addr = SocketAddr;
+
+        async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+            match self.handshaken.recv().await {
+                Some(connection) => connection,
+                // Only once the accepting task is gone, when the listener is dropped
+                None => std::future::pending().await,
+            }
+        }
+
+        fn local_addr(&self) -> io::Result<SocketAddr> {
+            Ok(self.address)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector2;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpStream;
+
+    /// Status code and body of a request with a JSON body
+    async fn send(address: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+        send_with(address, method, path, "", body).await
+    }
+
+    /// Status code and body of a request with extra `headers`, each ending in a line break
+    async fn send_with(
+        address: SocketAddr,
+        method: &str,
+        path: &str,
+        headers: &str,
+        body: &str,
+    ) -> (u16, String) {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        let request = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{}\
+             Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            path,
+            headers,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let code = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (code, body.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_http_api() {
+        let (calls, mut receiver) = mpsc::channel::<SocketCall>(4);
+        tokio::spawn(async move {
+            while let Some(call) = receiver.recv().await {
+                let response = match &call.request {
+                    SocketRequest::Targets => SocketResponse::Targets {
+                        targets: vec![TargetReport {
+                            id: 3,
+                            antenna_id: 0,
+                            position: Vector2::new(1.0, 2.0),
+                            velocity: Vector2::zeros(),
+                            confidence: 0.9,
+                            class: Default::default(),
+                            fall_phase: Default::default(),
+                            fall_probability: 0.0,
+                        }],
+                    },
+                    SocketRequest::SetConfig { key, .. } => SocketResponse::Error {
+                        message: format!("{} can't change while running", key),
+                    },
+                    _ => SocketResponse::Done,
+                };
+                assert_eq!(call.source, "http");
+                call.respond(response);
+            }
+        });
+        let config = HttpApiConfig {
+            listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+            ..Default::default()
+        };
+        let (_events, receiver) = broadcast::channel(4);
+        let api = HttpApi::serve(&config, &ApiAuthConfig::default(), calls, receiver)
+            .await
+            .unwrap();
+        let address = api.get_address();
+
+        let (code, body) = send(address, "GET", "/targets", "").await;
+        assert_eq!(code, 200);
+        let targets: Vec<TargetReport> = serde_json::from_str(&body).unwrap();
+        assert_eq!(targets[0].id, 3);
+
+        let zones = r#"[{"name": "bed", "min": [0.0, 0.0], "max": [1.0, 2.0]}]"#;
+        assert_eq!(send(address, "PUT", "/zones", zones).await.0, 204);
+        let (code, body) = send(address, "PUT", "/config/radar.antenna_count", "4").await;
+        assert_eq!(code, 400);
+        assert!(body.contains("can't change while running"));
+        assert_eq!(send(address, "GET", "/nowhere", "").await.0, 404);
+    }
+    #[tokio::test]
+    async fn test_api_tokens() {
+        let (calls, mut receiver) = mpsc::channel::<SocketCall>(4);
+        tokio::spawn(async move {
+            while let Some(call) = receiver.recv().await {
+                call.respond(SocketResponse::Targets {
+                    targets: Vec::new(),
+                });
+            }
+        });
+        let config = HttpApiConfig {
+            listen: SocketAddr::from(([127, 0, 0, 1], 0)),
+            ..Default::default()
+        };
+        let auth = ApiAuthConfig {
+            tokens: vec![
+                crate::config::ApiToken {
+                    name: "dashboard".to_string(),
+                    token: "s3cret".to_string(),
+                },
+                crate::config::ApiToken {
+                    name: "browser".to_string(),
+                    token: "a/b+c".to_string(),
+                },
+            ],
+        };
+        let (_events, events) = broadcast::channel(4);
+        let api = HttpApi::serve(&config, &auth, calls, events).await.unwrap();
+        let address = api.get_address();
+
+        let (code, body) = send(address, "GET", "/targets", "").await;
+        assert_eq!(code, 401);
+        assert!(body.contains("API token required"));
+        let wrong = "Authorization: Bearer guess\r\n";
+        assert_eq!(
+            send_with(address, "GET", "/targets", wrong, "").await.0,
+            401
+        );
+        let bearer = "Authorization: Bearer s3cret\r\n";
+        assert_eq!(
+            send_with(address, "GET", "/targets", bearer, "").await.0,
+            200
+        );
+        let key = "X-Api-Key: s3cret\r\n";
+        assert_eq!(send_with(address, "GET", "/targets", key, "").await.0, 200);
+        // Only the stream takes the token in the query
+        assert_eq!(
+            send(address, "GET", "/targets?access_token=s3cret", "")
+                .await
+                .0,
+            401
+        );
+        // Decoded before it is checked, past authentication a plain GET isn't a WebSocket
+        let encoded = "/stream?access_token=a%2Fb%2Bc";
+        assert_eq!(send(address, "GET", encoded, "").await.0, 400);
+        let truncated = "/stream?access_token=a%2Fb";
+        assert_eq!(send(address, "GET", truncated, "").await.0, 401);
+    }
+}
@@ -0,0 +1,214 @@
+use nalgebra::{Matrix2, Vector2};
+
+use crate::tracker::{FilterError, KalmanFilter, KinematicModel, Matrix6, TrackerConfig, Vector6};
+
+/// Models mixed by the filter, in the order of [`ImmFilter::get_mode_probabilities`]
+pub const IMM_MODELS: [KinematicModel; 3] = [
+    KinematicModel::Stationary,
+    KinematicModel::ConstantVelocity,
+    KinematicModel::ConstantAcceleration,
+];
+
+/// Probability of staying in the same model from one step to the next
+const MODEL_PERSISTENCE: f32 = 0.9;
+
+/// Interacting Multiple Model filter running a stationary, a constant-velocity and a
+/// constant-acceleration Kalman filter side by side. Each step mixes their estimates by how
+/// likely a switch between models is, then reweights the models by how well each predicted
+/// the measurement, so a person stopping or starting to walk is followed without the
+/// overshoot of a single constant-acceleration filter.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImmFilter {
+    filters: [KalmanFilter; 3],
+    probabilities: [f32; 3],
+    /// `transition[i][j]` is the probability of switching from model `i` to model `j`
+    transition: [[f32; 3]; 3],
+}
+
+impl ImmFilter {
+    pub fn new(initial_position: Vector2<f32>) -> Self {
+        Self::with_config(initial_position, &TrackerConfig::default())
+    }
+
+    pub fn with_config(initial_position: Vector2<f32>, config: &TrackerConfig) -> Self {
+        let switch = (1.0 - MODEL_PERSISTENCE) / 2.0;
+        let mut transition = [[switch; 3]; 3];
+        for (i, row) in transition.iter_mut().enumerate() {
+            row[i] = MODEL_PERSISTENCE;
+        }
+
+        Self {
+            filters: IMM_MODELS
+                .map(|model| KalmanFilter::with_config(initial_position, model, config)),
+            probabilities: [1.0 / 3.0; 3],
+            transition,
+        }
+    }
+
+    /// Variance of position measurements in m² along each axis
+    pub fn set_measurement_noise(&mut self, variance: f32) {
+        for filter in &mut self.filters {
+            filter.set_measurement_noise(variance);
+        }
+    }
+
+    /// Probability of each of [`IMM_MODELS`]
+    pub fn get_mode_probabilities(&self) -> [f32; 3] {
+        self.probabilities
+    }
+
+    pub fn get_most_likely_model(&self) -> KinematicModel {
+        let best = (0..3)
+            .max_by(|&a, &b| self.probabilities[a].total_cmp(&self.probabilities[b]))
+            .unwrap_or(0);
+        IMM_MODELS[best]
+    }
+
+    pub fn predict(&mut self, dt: f32) {
+        // Predicted model probabilities c_j = Σ_i π_ij μ_i
+        let predicted: [f32; 3] = std::array::from_fn(|j| {
+            (0..3)
+                .map(|i| self.transition[i][j] * self.probabilities[i])
+                .sum()
+        });
+
+        // Mix the model estimates as the starting point of each model
+        let mixed: Vec<(Vector6, Matrix6)> = (0..3)
+            .map(|j| {
+                let weights: [f32; 3] = std::array::from_fn(|i| {
+                    if predicted[j] > 0.0 {
+                        self.transition[i][j] * self.probabilities[i] / predicted[j]
+                    } else {
+                        0.0
+                    }
+                });
+                combine(&self.filters, &weights)
+            })
+            .collect();
+
+        for (filter, (state, covariance)) in self.filters.iter_mut().zip(mixed) {
+            filter.set_state(state, covariance);
+            filter.predict(dt);
+        }
+        self.probabilities = predicted;
+    }
+
+    pub fn update(&mut self, measurement: Vector2<f32>) -> Result<(), FilterError> {
+        // Log-likelihood of the measurement under each model
+        let log_likelihoods: [f32; 3] = std::array::from_fn(|j| {
+            let filter = &self.filters[j];
+            let covariance = filter.get_innovation_covariance();
+            match filter.mahalanobis_distance_squared(measurement) {
+                Some(distance) if covariance.determinant() > 0.0 => {
+                    -0.5 * distance - 0.5 * covariance.determinant().ln()
+                }
+                _ => f32::NEG_INFINITY,
+            }
+        });
+
+        // Models that cannot take the measurement keep their prediction and lose their weight
+        let mut updated = [false; 3];
+        for (filter, updated) in self.filters.iter_mut().zip(&mut updated) {
+            *updated = filter.update(measurement).is_ok();
+        }
+        if !updated.contains(&true) {
+            return Err(FilterError::SingularCovariance);
+        }
+        let log_likelihoods: [f32; 3] = std::array::from_fn(|j| {
+            if updated[j] {
+                log_likelihoods[j]
+            } else {
+                f32::NEG_INFINITY
+            }
+        });
+
+        let best = log_likelihoods
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max);
+        if !best.is_finite() {
+            return Ok(());
+        }
+        let weights: [f32; 3] =
+            std::array::from_fn(|j| self.probabilities[j] * (log_likelihoods[j] - best).exp());
+        let total: f32 = weights.iter().sum();
+        if total > 0.0 {
+            self.probabilities = weights.map(|weight| weight / total);
+        }
+        Ok(())
+    }
+
+    /// Innovation covariance of the combined estimate
+    pub fn get_innovation_covariance(&self) -> Matrix2<f32> {
+        let (_, covariance) = combine(&self.filters, &self.probabilities);
+        covariance.fixed_view::<2, 2>(0, 0) + self.filters[0].get_measurement_noise()
+    }
+
+    pub fn get_position(&self) -> Vector2<f32> {
+        let (state, _) = combine(&self.filters, &self.probabilities);
+        Vector2::new(state[0], state[1])
+    }
+
+    pub fn get_velocity(&self) -> Vector2<f32> {
+        let (state, _) = combine(&self.filters, &self.probabilities);
+        Vector2::new(state[2], state[3])
+    }
+
+    pub fn get_acceleration(&self) -> Vector2<f32> {
+        let (state, _) = combine(&self.filters, &self.probabilities);
+        Vector2::new(state[4], state[5])
+    }
+}
+
+/// Weighted mean of the filter states and the covariance including their spread
+fn combine(filters: &[KalmanFilter; 3], weights: &[f32; 3]) -> (Vector6, Matrix6) {
+    let state = filters
+        .iter()
+        .zip(weights)
+        .fold(Vector6::zeros(), |sum, (filter, &weight)| {
+            sum + filter.get_state().0 * weight
+        });
+    let covariance =
+        filters
+            .iter()
+            .zip(weights)
+            .fold(Matrix6::zeros(), |sum, (filter, &weight)| {
+                let (x, p) = filter.get_state();
+                let spread = x - state;
+                sum + (p + spread * spread.transpose()) * weight
+            });
+    (state, covariance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_switching() {
+        let mut imm = ImmFilter::new(Vector2::new(0.0, 0.0));
+        imm.set_measurement_noise(0.01);
+        let dt = 0.1;
+
+        for _ in 0..30 {
+            imm.predict(dt);
+            imm.update(Vector2::new(0.0, 0.0)).unwrap();
+        }
+        assert_eq!(imm.get_most_likely_model(), KinematicModel::Stationary);
+
+        // Start walking at 1 m/s
+        let mut x = 0.0;
+        for _ in 0..30 {
+            x += dt;
+            imm.predict(dt);
+            imm.update(Vector2::new(x, 0.0)).unwrap();
+        }
+        assert_ne!(imm.get_most_likely_model(), KinematicModel::Stationary);
+        assert!(imm.get_velocity().x > 0.5);
+        assert!((imm.get_position().x - x).abs() < 0.3);
+
+        let total: f32 = imm.get_mode_probabilities().iter().sum();
+        assert!((total - 1.0).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,191 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::antenna_array::AntennaArray;
+use crate::error::{HexarError, HexarResult};
+use crate::serial_radar::TransmitGate;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InterferenceConfig {
+    /// Time each group of modules transmits for before handing over to the next
+    pub slot_ms: u64,
+    /// Silence at the start of each slot while the previous group's modules go quiet
+    pub guard_ms: u64,
+    /// Share of the smaller cone two antennas must both cover to be kept apart
+    pub min_overlap: f32,
+}
+
+impl Default for InterferenceConfig {
+    fn default() -> Self {
+        Self {
+            slot_ms: 200,
+            guard_ms: 30,
+            min_overlap: 0.05,
+        }
+    }
+}
+
+/// Groups of antennas that transmit together, none of them overlapping another of its group
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SlotPlan {
+    slots: Vec<Vec<u8>>,
+}
+
+impl SlotPlan {
+    /// Give each of `antennas` the first slot without an antenna its cone overlaps
+    pub fn assign(array: &AntennaArray, antennas: &[u8], min_overlap: f32) -> Self {
+        let mut slots: Vec<Vec<u8>> = Vec::new();
+        for &antenna_id in antennas {
+            let free = slots.iter().position(|slot| {
+                slot.iter()
+                    .all(|other| array.overlap(antenna_id, *other) < min_overlap)
+            });
+            match free {
+                Some(index) => slots[index].push(antenna_id),
+                None => slots.push(vec![antenna_id]),
+            }
+        }
+        Self { slots }
+    }
+
+    pub fn get_slots(&self) -> &[Vec<u8>] {
+        &self.slots
+    }
+
+    pub fn slot_of(&self, antenna_id: u8) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| slot.contains(&antenna_id))
+    }
+}
+
+/// When each antenna of a [`SlotPlan`] transmits, the slots taking turns in a repeating cycle
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotSchedule {
+    plan: SlotPlan,
+    slot: Duration,
+    guard: Duration,
+}
+
+impl SlotSchedule {
+    pub fn new(plan: SlotPlan, config: &InterferenceConfig) -> HexarResult<Self> {
+        if config.guard_ms >= config.slot_ms {
+            return Err(HexarError::ConfigurationError(format!(
+                "interference guard of {} ms leaves nothing of {} ms slots",
+                config.guard_ms, config.slot_ms
+            )));
+        }
+        Ok(Self {
+            plan,
+            slot: Duration::from_millis(config.slot_ms),
+            guard: Duration::from_millis(config.guard_ms),
+        })
+    }
+
+    pub fn get_plan(&self) -> &SlotPlan {
+        &self.plan
+    }
+
+    /// Slot running at `elapsed` since the start of the schedule, and how far into it
+    fn position(&self, elapsed: Duration) -> (usize, Duration) {
+        let slots = self.plan.slots.len().max(1) as u128;
+        let slot = self.slot.as_nanos().max(1);
+        let into_cycle = elapsed.as_nanos() % (slot * slots);
+        let index = (into_cycle / slot) as usize;
+        (index, Duration::from_nanos((into_cycle % slot) as u64))
+    }
+
+    /// Whether an antenna transmits at `elapsed`. Antennas outside the plan always do.
+    pub fn is_transmitting(&self, antenna_id: u8, elapsed: Duration) -> bool {
+        match self.plan.slot_of(antenna_id) {
+            Some(slot) => {
+                let (index, into_slot) = self.position(elapsed);
+                index == slot && into_slot >= self.guard
+            }
+            None => true,
+        }
+    }
+
+    /// Time from the start of the schedule of the first change of any antenna after `elapsed`
+    pub fn next_change(&self, elapsed: Duration) -> Duration {
+        let (_, into_slot) = self.position(elapsed);
+        if into_slot < self.guard {
+            elapsed + (self.guard - into_slot)
+        } else {
+            elapsed + (self.slot - into_slot)
+        }
+    }
+}
+
+/// Opens and closes the transmit gates of the readers as a [`SlotSchedule`] says, on a task of
+/// its own
+#[derive(Debug)]
+pub struct InterferenceCoordinator {
+    handle: JoinHandle<()>,
+}
+
+impl InterferenceCoordinator {
+    pub fn spawn(schedule: SlotSchedule, gates: Vec<(u8, TransmitGate)>) -> Self {
+        let handle = tokio::spawn(async move {
+            let started = tokio::time::Instant::now();
+            loop {
+                let elapsed = started.elapsed();
+                for (antenna_id, gate) in &gates {
+                    gate.set(schedule.is_transmitting(*antenna_id, elapsed));
+                }
+                tokio::time::sleep_until(started + schedule.next_change(elapsed)).await;
+            }
+        });
+        Self { handle }
+    }
+
+    /// Stop switching the gates, leaving them as they are
+    pub fn stop(self) {
+        self.handle.abort();
+        debug!("Interference coordinator stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antenna_array::AntennaArrayConfig;
+
+    #[test]
+    fn test_slot_schedule() {
+        let array = AntennaArray::hexagonal(&AntennaArrayConfig::default());
+        // Neighbours share half their cones, every other antenna looks away
+        assert!(array.overlap(0, 1) > 0.3);
+        assert_eq!(array.overlap(0, 2), 0.0);
+
+        let config = InterferenceConfig::default();
+        let plan = SlotPlan::assign(&array, &[0, 1, 2, 3, 4, 5], config.min_overlap);
+        assert_eq!(plan.get_slots(), &[vec![0, 2, 4], vec![1, 3, 5]]);
+
+        let schedule = SlotSchedule::new(plan, &config).unwrap();
+        let ms = Duration::from_millis;
+        // Antenna 0 waits out the guard, then transmits until antenna 1's slot
+        assert!(!schedule.is_transmitting(0, ms(10)));
+        assert!(schedule.is_transmitting(0, ms(100)));
+        assert!(!schedule.is_transmitting(1, ms(100)));
+        assert!(!schedule.is_transmitting(0, ms(250)));
+        assert!(schedule.is_transmitting(1, ms(250)));
+        assert!(schedule.is_transmitting(0, ms(450)));
+        assert!(schedule.is_transmitting(7, ms(250)));
+        assert_eq!(schedule.next_change(ms(10)), ms(30));
+        assert_eq!(schedule.next_change(ms(100)), ms(200));
+
+        assert!(SlotSchedule::new(
+            SlotPlan::default(),
+            &InterferenceConfig {
+                guard_ms: 200,
+                ..config
+            }
+        )
+        .is_err());
+    }
+}
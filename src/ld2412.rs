@@ -1,5 +1,15 @@
-use crate::{RadarDriver, RadarLLFrame};
-use log::error;
+#![cfg_attr(
+    all(feature = "panic-free", not(test)),
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable
+    )
+)]
+
+use crate::{command_frame, error, BaudRate, RadarDriver, RadarLLFrame};
 use smallvec::SmallVec;
 
 #[derive(Debug, Clone, Copy)]
@@ -29,7 +39,7 @@ pub enum Ld2412Command {
     EnterBackgroundCorrection,
     ReadBackgroundCorrection,
     FirmwareVersion,
-    BaudRate(u32),
+    BaudRate(BaudRate),
     FactoryReset,
     Reboot,
     BluetoothOn,
@@ -40,8 +50,38 @@ pub enum Ld2412Command {
     ReadLightsensorMode,
 }
 
-impl RadarDriver for Ld2412Command {
-    fn get_opcode(&self) -> u16 {
+impl Ld2412Command {
+    pub const ENABLE_CONFIGURATION_FRAME: [u8; 14] =
+        command_frame(Self::EnableConfiguration.opcode(), [0x01, 0x00]);
+    pub const END_CONFIGURATION_FRAME: [u8; 12] =
+        command_frame(Self::EndConfiguration.opcode(), []);
+    pub const READ_RESOLUTION_FRAME: [u8; 12] = command_frame(Self::ReadResolution.opcode(), []);
+    pub const READ_BASIC_PARAMETERS_FRAME: [u8; 12] =
+        command_frame(Self::ReadBasicParameters.opcode(), []);
+    pub const ENGINEERING_MODE_ON_FRAME: [u8; 12] =
+        command_frame(Self::EngineeringModeOn.opcode(), []);
+    pub const ENGINEERING_MODE_OFF_FRAME: [u8; 12] =
+        command_frame(Self::EngineeringModeOff.opcode(), []);
+    pub const READ_MOTION_SENSITIVITY_FRAME: [u8; 12] =
+        command_frame(Self::ReadMotionSensitivity.opcode(), []);
+    pub const READ_STATIC_SENSITIVITY_FRAME: [u8; 12] =
+        command_frame(Self::ReadStaticSensitivity.opcode(), []);
+    pub const ENTER_BACKGROUND_CORRECTION_FRAME: [u8; 12] =
+        command_frame(Self::EnterBackgroundCorrection.opcode(), []);
+    pub const READ_BACKGROUND_CORRECTION_FRAME: [u8; 12] =
+        command_frame(Self::ReadBackgroundCorrection.opcode(), []);
+    pub const FIRMWARE_VERSION_FRAME: [u8; 12] = command_frame(Self::FirmwareVersion.opcode(), []);
+    pub const FACTORY_RESET_FRAME: [u8; 12] = command_frame(Self::FactoryReset.opcode(), []);
+    pub const REBOOT_FRAME: [u8; 12] = command_frame(Self::Reboot.opcode(), []);
+    pub const BLUETOOTH_ON_FRAME: [u8; 14] =
+        command_frame(Self::BluetoothOn.opcode(), [0x01, 0x00]);
+    pub const BLUETOOTH_OFF_FRAME: [u8; 14] =
+        command_frame(Self::BluetoothOff.opcode(), [0x00, 0x00]);
+    pub const MAC_ADDRESS_FRAME: [u8; 14] = command_frame(Self::MacAddress.opcode(), [0x01, 0x00]);
+    pub const READ_LIGHTSENSOR_MODE_FRAME: [u8; 12] =
+        command_frame(Self::ReadLightsensorMode.opcode(), []);
+
+    pub const fn opcode(&self) -> u16 {
         match self {
             Ld2412Command::EnableConfiguration => 0x00FF,
             Ld2412Command::EndConfiguration => 0x00FE,
@@ -68,6 +108,12 @@ impl RadarDriver for Ld2412Command {
             Ld2412Command::ReadLightsensorMode => 0x001C,
         }
     }
+}
+
+impl RadarDriver for Ld2412Command {
+    fn get_opcode(&self) -> u16 {
+        self.opcode()
+    }
 
     fn serialize_data(&self, data: &mut SmallVec<[u8; 16]>) {
         match self {
@@ -109,17 +155,7 @@ impl RadarDriver for Ld2412Command {
             Ld2412Command::ReadBackgroundCorrection => {}
             Ld2412Command::FirmwareVersion => {}
             Ld2412Command::BaudRate(baud_rate) => {
-                let br: u16 = match baud_rate {
-                    9600 => 0x0001,
-                    19200 => 0x0002,
-                    38400 => 0x0003,
-                    57600 => 0x0004,
-                    115200 => 0x0005,
-                    230400 => 0x0006,
-                    256600 => 0x0007,
-                    460800 => 0x0008,
-                    _ => panic!("Unknown baud rate"),
-                };
+                let br = *baud_rate as u16;
 
                 data.extend_from_slice(&[br as u8, (br >> 8) as u8]);
             }
@@ -152,6 +188,7 @@ impl Ld2412Command {
 
 // deserialization
 
+#[cfg_attr(feature = "postcard", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub enum TargetState {
     Untargeted = 0x00,
@@ -163,27 +200,31 @@ pub enum TargetState {
     BottomNoiseDetectionFailed = 0x06,
 }
 
-impl From<u8> for TargetState {
-    fn from(item: u8) -> Self {
+impl TryFrom<u8> for TargetState {
+    type Error = u8;
+
+    fn try_from(item: u8) -> Result<Self, Self::Error> {
         match item {
-            0x00 => TargetState::Untargeted,
-            0x01 => TargetState::Campaign,
-            0x02 => TargetState::Stationary,
-            0x03 => TargetState::MotionStationary,
-            0x04 => TargetState::BottomNoiseDetectionInProgress,
-            0x05 => TargetState::BottomNoiseDetectionSuccessful,
-            0x06 => TargetState::BottomNoiseDetectionFailed,
-            _ => panic!("Unknown target state"),
+            0x00 => Ok(TargetState::Untargeted),
+            0x01 => Ok(TargetState::Campaign),
+            0x02 => Ok(TargetState::Stationary),
+            0x03 => Ok(TargetState::MotionStationary),
+            0x04 => Ok(TargetState::BottomNoiseDetectionInProgress),
+            0x05 => Ok(TargetState::BottomNoiseDetectionSuccessful),
+            0x06 => Ok(TargetState::BottomNoiseDetectionFailed),
+            unknown => Err(unknown),
         }
     }
 }
 
+#[cfg_attr(feature = "postcard", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub struct Ld2412TargetData {
     pub basic_target_data: BasicTargetData,
     pub engineering_mode_data: Option<EngineeringModeData>,
 }
 
+#[cfg_attr(feature = "postcard", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub struct BasicTargetData {
     pub state: TargetState,
@@ -191,6 +232,7 @@ pub struct BasicTargetData {
     pub stationary_target: Target,
 }
 
+#[cfg_attr(feature = "postcard", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub struct EngineeringModeData {
     pub b1: u8,
@@ -200,28 +242,44 @@ pub struct EngineeringModeData {
     pub light: u8,
 }
 
+#[cfg_attr(feature = "postcard", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub struct Target {
     pub distance: u16, // cm
     pub energy: u8,    // dB ??
 }
 
-fn read_basic_target_data(buffer: &[u8]) -> BasicTargetData {
+fn read_basic_target_data(buffer: &[u8]) -> Option<BasicTargetData> {
+    let [state, moving_l, moving_h, moving_energy, stationary_l, stationary_h, stationary_energy, ..] =
+        *buffer
+    else {
+        error!("Buffer too short for LD2412 target data");
+        return None;
+    };
+
     let moving_target = Target {
-        distance: u16::from_le_bytes([buffer[1], buffer[2]]),
-        energy: buffer[3],
+        distance: u16::from_le_bytes([moving_l, moving_h]),
+        energy: moving_energy,
     };
 
     let stationary_target = Target {
-        distance: u16::from_le_bytes([buffer[4], buffer[5]]),
-        energy: buffer[6],
+        distance: u16::from_le_bytes([stationary_l, stationary_h]),
+        energy: stationary_energy,
+    };
+
+    let state = match TargetState::try_from(state) {
+        Ok(state) => state,
+        Err(unknown) => {
+            error!("Unknown target state {:#04x}", unknown);
+            return None;
+        }
     };
 
-    BasicTargetData {
-        state: buffer[0].into(),
+    Some(BasicTargetData {
+        state,
         moving_target,
         stationary_target,
-    }
+    })
 }
 
 impl Ld2412TargetData {
@@ -230,14 +288,31 @@ impl Ld2412TargetData {
             [datatype, 0xaa, targetdata @ .., 0x55, calibration] => {
                 let target_data = match *datatype {
                     0x01 => {
-                        let basic_target_data = read_basic_target_data(targetdata);
+                        let basic_target_data = read_basic_target_data(targetdata)?;
+
+                        let (
+                            Some(&b1),
+                            Some(&b2),
+                            Some(moving_gates),
+                            Some(stationary_gates),
+                            Some(&light),
+                        ) = (
+                            targetdata.get(7),
+                            targetdata.get(8),
+                            targetdata.get(9..23).and_then(|g| g.try_into().ok()),
+                            targetdata.get(23..37).and_then(|g| g.try_into().ok()),
+                            targetdata.get(37),
+                        )
+                        else {
+                            error!("Buffer too short for LD2412 engineering mode data");
+                            return None;
+                        };
 
-                        let light = targetdata[37];
                         let eng_data = EngineeringModeData {
-                            b1: targetdata[7],
-                            b2: targetdata[8],
-                            moving_gates: targetdata[9..23].try_into().unwrap(),
-                            stationary_gates: targetdata[23..37].try_into().unwrap(),
+                            b1,
+                            b2,
+                            moving_gates,
+                            stationary_gates,
 
                             light,
                         };
@@ -248,7 +323,7 @@ impl Ld2412TargetData {
                         }
                     }
                     0x02 => {
-                        let basic_target_data = read_basic_target_data(targetdata);
+                        let basic_target_data = read_basic_target_data(targetdata)?;
 
                         Ld2412TargetData {
                             basic_target_data,
@@ -272,3 +347,65 @@ impl Ld2412TargetData {
         }
     }
 }
+
+#[cfg(feature = "postcard")]
+impl Ld2412TargetData {
+    /// Encode into `buf` with postcard, returning the part of `buf` that was written
+    pub fn to_postcard<'a>(&self, buf: &'a mut [u8]) -> postcard::Result<&'a mut [u8]> {
+        postcard::to_slice(self, buf)
+    }
+
+    pub fn from_postcard(bytes: &[u8]) -> postcard::Result<Self> {
+        postcard::from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_basic_target_data() {
+        let intraframe = [
+            0x02, 0xAA, 0x01, 0x64, 0x00, 0x3C, 0xC8, 0x00, 0x32, 0x55, 0x00,
+        ];
+
+        let data = Ld2412TargetData::deserialize(&intraframe).unwrap();
+        assert!(matches!(data.basic_target_data.state, TargetState::Campaign));
+        assert_eq!(data.basic_target_data.moving_target.distance, 100);
+        assert_eq!(data.basic_target_data.moving_target.energy, 60);
+        assert_eq!(data.basic_target_data.stationary_target.distance, 200);
+        assert!(data.engineering_mode_data.is_none());
+    }
+
+    #[test]
+    fn test_deserialize_never_panics_on_short_or_garbage_input() {
+        // Engineering mode header followed by a payload that is far too short
+        let mut intraframe = [0u8; 44];
+        intraframe[0] = 0x01;
+        intraframe[1] = 0xAA;
+        intraframe[42] = 0x55;
+
+        for len in 0..intraframe.len() {
+            let _ = Ld2412TargetData::deserialize(&intraframe[..len]);
+        }
+
+        // Unknown target state is rejected instead of panicking
+        let unknown_state = [0x02, 0xAA, 0x7F, 0, 0, 0, 0, 0, 0, 0x55, 0x00];
+        assert!(Ld2412TargetData::deserialize(&unknown_state).is_none());
+    }
+
+    #[test]
+    fn test_static_frames_match_serialized_commands() {
+        let cases: [(&[u8], Ld2412Command); 4] = [
+            (&Ld2412Command::ENABLE_CONFIGURATION_FRAME, Ld2412Command::EnableConfiguration),
+            (&Ld2412Command::ENGINEERING_MODE_ON_FRAME, Ld2412Command::EngineeringModeOn),
+            (&Ld2412Command::MAC_ADDRESS_FRAME, Ld2412Command::MacAddress),
+            (&Ld2412Command::REBOOT_FRAME, Ld2412Command::Reboot),
+        ];
+
+        for (frame, command) in cases {
+            assert_eq!(frame, command.to_llframe().serialize().unwrap().as_slice());
+        }
+    }
+}
@@ -1,8 +1,17 @@
-use log::error;
+#![cfg_attr(
+    all(feature = "panic-free", not(test)),
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable
+    )
+)]
 
 use smallvec::SmallVec;
 
-use crate::{RadarDriver, RadarLLFrame};
+use crate::{command_frame, error, BaudRate, RadarDriver, RadarLLFrame};
 
 #[derive(Debug, Clone, Copy)]
 pub enum TrackingMode {
@@ -25,7 +34,7 @@ pub enum Ld2450Command {
     /// Read firmware version
     FirmwareVersion,
     /// Set serial port baud rate
-    BaudRate(u32),
+    BaudRate(BaudRate),
     /// Restore factory settings
     FactoryReset,
     /// Reboot the module
@@ -43,8 +52,29 @@ pub enum Ld2450Command {
     SetZoneFiltering(u16, [(i16, i16, i16, i16); 3]),
 }
 
-impl RadarDriver for Ld2450Command {
-    fn get_opcode(&self) -> u16 {
+impl Ld2450Command {
+    pub const ENABLE_CONFIGURATION_FRAME: [u8; 14] =
+        command_frame(Self::EnableConfiguration.opcode(), [0x01, 0x00]);
+    pub const END_CONFIGURATION_FRAME: [u8; 12] =
+        command_frame(Self::EndConfiguration.opcode(), []);
+    pub const SINGLE_TARGET_TRACKING_FRAME: [u8; 12] =
+        command_frame(Self::SingleTargetTracking.opcode(), []);
+    pub const MULTI_TARGET_TRACKING_FRAME: [u8; 12] =
+        command_frame(Self::MultiTargetTracking.opcode(), []);
+    pub const QUERY_TRACKING_MODE_FRAME: [u8; 12] =
+        command_frame(Self::QueryTrackingMode.opcode(), []);
+    pub const FIRMWARE_VERSION_FRAME: [u8; 12] = command_frame(Self::FirmwareVersion.opcode(), []);
+    pub const FACTORY_RESET_FRAME: [u8; 12] = command_frame(Self::FactoryReset.opcode(), []);
+    pub const REBOOT_FRAME: [u8; 12] = command_frame(Self::Reboot.opcode(), []);
+    pub const BLUETOOTH_ON_FRAME: [u8; 14] =
+        command_frame(Self::BluetoothOn.opcode(), [0x01, 0x00]);
+    pub const BLUETOOTH_OFF_FRAME: [u8; 14] =
+        command_frame(Self::BluetoothOff.opcode(), [0x00, 0x00]);
+    pub const MAC_ADDRESS_FRAME: [u8; 14] = command_frame(Self::MacAddress.opcode(), [0x01, 0x00]);
+    pub const QUERY_ZONE_FILTERING_FRAME: [u8; 12] =
+        command_frame(Self::QueryZoneFiltering.opcode(), []);
+
+    pub const fn opcode(&self) -> u16 {
         match self {
             Ld2450Command::EnableConfiguration => 0x00FF,
             Ld2450Command::EndConfiguration => 0x00FE,
@@ -62,6 +92,12 @@ impl RadarDriver for Ld2450Command {
             Ld2450Command::SetZoneFiltering(_, _) => 0x00C2,
         }
     }
+}
+
+impl RadarDriver for Ld2450Command {
+    fn get_opcode(&self) -> u16 {
+        self.opcode()
+    }
 
     fn serialize_data(&self, data: &mut SmallVec<[u8; 16]>) {
         match self {
@@ -74,17 +110,7 @@ impl RadarDriver for Ld2450Command {
             Ld2450Command::QueryTrackingMode => {}
             Ld2450Command::FirmwareVersion => {}
             Ld2450Command::BaudRate(baud_rate) => {
-                let br: u16 = match baud_rate {
-                    9600 => 0x0001,
-                    19200 => 0x0002,
-                    38400 => 0x0003,
-                    57600 => 0x0004,
-                    115200 => 0x0005,
-                    230400 => 0x0006,
-                    256000 => 0x0007,
-                    460800 => 0x0008,
-                    _ => panic!("Unsupported baud rate"),
-                };
+                let br = *baud_rate as u16;
 
                 data.extend_from_slice(&[br as u8, (br >> 8) as u8]);
             }
@@ -138,12 +164,14 @@ impl Ld2450Command {
 
 // Target data structures
 
+#[cfg_attr(feature = "postcard", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy)]
 pub struct Position {
     pub x: i16, // mm
     pub y: i16, // mm
 }
 
+#[cfg_attr(feature = "postcard", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Clone, Copy)]
 pub struct TargetData {
     pub position: Position,
@@ -151,6 +179,7 @@ pub struct TargetData {
     pub distance_resolution: u16, // mm
 }
 
+#[cfg_attr(feature = "postcard", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug)]
 pub struct Ld2450TargetData {
     pub targets: SmallVec<[TargetData; 3]>,
@@ -164,63 +193,84 @@ impl Ld2450TargetData {
             return None;
         }
 
-        let mut targets = SmallVec::new();
-
-        // Process each target (up to 3 targets)
-        for i in 0..3 {
-            let base_index = i * 8;
-
-            // Check if we're still within bounds
-            if base_index + 8 > buffer.len() {
-                break;
-            }
+        // Filled in place, `SmallVec::push` could grow and panic as far as the compiler knows
+        let mut targets = [TargetData {
+            position: Position { x: 0, y: 0 },
+            speed: 0,
+            distance_resolution: 0,
+        }; 3];
+        let mut count = 0;
+
+        // Process each target (up to 3 targets, 8 bytes each)
+        for chunk in buffer.chunks_exact(8).take(3) {
+            let [x_l, x_h, y_l, y_h, speed_l, speed_h, distance_l, distance_h] = match chunk {
+                [a, b, c, d, e, f, g, h] => [*a, *b, *c, *d, *e, *f, *g, *h],
+                _ => break,
+            };
 
             // Check if target exists (all zeros means no target)
-            let all_zeros = buffer[base_index..base_index + 8].iter().all(|&b| b == 0);
+            let all_zeros = chunk.iter().all(|&b| b == 0);
             if all_zeros {
                 continue;
             }
 
             // Extract target data
             // X coordinate
-            let mut x = i16::from_le_bytes([buffer[base_index], buffer[base_index + 1]]);
+            let mut x = i16::from_le_bytes([x_l, x_h]);
             // Y coordinate
-            let mut y = i16::from_le_bytes([buffer[base_index + 2], buffer[base_index + 3]]);
+            let mut y = i16::from_le_bytes([y_l, y_h]);
             // Speed
-            let mut speed = i16::from_le_bytes([buffer[base_index + 4], buffer[base_index + 5]]);
+            let mut speed = i16::from_le_bytes([speed_l, speed_h]);
             // Distance resolution
-            let distance = u16::from_le_bytes([buffer[base_index + 6], buffer[base_index + 7]]);
+            let distance = u16::from_le_bytes([distance_l, distance_h]);
 
             // Handle sign bit in highest bit for x, y, and speed
-            if (buffer[base_index + 1] & 0x80) != 0 {
+            if (x_h & 0x80) != 0 {
                 x &= 0x7FFF; // Clear sign bit
             } else {
                 x = -x; // Negative value
             }
 
-            if (buffer[base_index + 3] & 0x80) != 0 {
+            if (y_h & 0x80) != 0 {
                 y &= 0x7FFF; // Clear sign bit
             } else {
                 y = -y; // Negative value
             }
 
-            if (buffer[base_index + 5] & 0x80) != 0 {
+            if (speed_h & 0x80) != 0 {
                 speed &= 0x7FFF; // Clear sign bit
             } else {
                 speed = -speed; // Negative value
             }
 
-            targets.push(TargetData {
-                position: Position { x, y },
-                speed,
-                distance_resolution: distance,
-            });
+            if let Some(target) = targets.get_mut(count) {
+                *target = TargetData {
+                    position: Position { x, y },
+                    speed,
+                    distance_resolution: distance,
+                };
+                count += 1;
+            }
         }
 
+        let mut targets = SmallVec::from_buf(targets);
+        targets.truncate(count);
         Some(Ld2450TargetData { targets })
     }
 }
 
+#[cfg(feature = "postcard")]
+impl Ld2450TargetData {
+    /// Encode into `buf` with postcard, returning the part of `buf` that was written
+    pub fn to_postcard<'a>(&self, buf: &'a mut [u8]) -> postcard::Result<&'a mut [u8]> {
+        postcard::to_slice(self, buf)
+    }
+
+    pub fn from_postcard(bytes: &[u8]) -> postcard::Result<Self> {
+        postcard::from_bytes(bytes)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,4 +317,59 @@ mod tests {
             "Distance resolution should be 320 mm"
         );
     }
+
+    #[test]
+    fn test_deserialize_never_panics_on_short_or_garbage_input() {
+        let garbage: [u8; 32] = core::array::from_fn(|i| (i as u8).wrapping_mul(37) | 0x80);
+
+        for len in 0..garbage.len() {
+            let _ = Ld2450TargetData::deserialize(&garbage[..len]);
+        }
+
+        let frame = Ld2450Command::BaudRate(BaudRate::B256000).to_llframe();
+        assert!(frame.serialize().is_some());
+    }
+
+    #[test]
+    fn test_static_frames_match_serialized_commands() {
+        let cases: [(&[u8], Ld2450Command); 4] = [
+            (&Ld2450Command::ENABLE_CONFIGURATION_FRAME, Ld2450Command::EnableConfiguration),
+            (&Ld2450Command::END_CONFIGURATION_FRAME, Ld2450Command::EndConfiguration),
+            (&Ld2450Command::FIRMWARE_VERSION_FRAME, Ld2450Command::FirmwareVersion),
+            (&Ld2450Command::REBOOT_FRAME, Ld2450Command::Reboot),
+        ];
+
+        for (frame, command) in cases {
+            assert_eq!(frame, command.to_llframe().serialize().unwrap().as_slice());
+        }
+
+        // Example from the protocol documentation: enable configuration command
+        assert_eq!(
+            Ld2450Command::ENABLE_CONFIGURATION_FRAME,
+            [0xFD, 0xFC, 0xFB, 0xFA, 0x04, 0x00, 0xFF, 0x00, 0x01, 0x00, 0x04, 0x03, 0x02, 0x01]
+        );
+    }
+
+    #[cfg(feature = "postcard")]
+    #[test]
+    fn test_postcard_round_trip() {
+        let data = Ld2450TargetData {
+            targets: SmallVec::from_slice(&[TargetData {
+                position: Position { x: -782, y: 1713 },
+                speed: -16,
+                distance_resolution: 320,
+            }]),
+        };
+
+        let mut buf = [0u8; 32];
+        let encoded = data.to_postcard(&mut buf).unwrap();
+        // Varint encoding keeps a single target well below the 24 byte raw frame payload
+        assert!(encoded.len() < 24);
+
+        let decoded = Ld2450TargetData::from_postcard(encoded).unwrap();
+        assert_eq!(decoded.targets.len(), 1);
+        assert_eq!(decoded.targets[0].position.x, -782);
+        assert_eq!(decoded.targets[0].position.y, 1713);
+        assert_eq!(decoded.targets[0].speed, -16);
+    }
 }
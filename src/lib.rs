@@ -1,25 +1,278 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+// Payloads too long for a SmallVec's inline buffer go to the heap, see `copy_payload`
+extern crate alloc;
+
+#[cfg(feature = "controller")]
 pub mod config;
+#[cfg(feature = "controller")]
 pub mod safety;
+#[cfg(feature = "controller")]
 pub mod monitoring;
+#[cfg(feature = "controller")]
 pub mod radar_controller;
+#[cfg(feature = "controller")]
 pub mod error;
+#[cfg(feature = "controller")]
+pub mod serial_radar;
+#[cfg(feature = "controller")]
+pub mod frame_log;
+#[cfg(feature = "controller")]
+pub mod pipeline;
+#[cfg(feature = "controller")]
+pub mod control;
+#[cfg(feature = "controller")]
+pub mod health;
+#[cfg(feature = "controller")]
+pub mod fall_alert;
+#[cfg(feature = "controller")]
+pub mod scenario;
+#[cfg(feature = "controller")]
+pub mod interference;
+#[cfg(feature = "controller")]
+pub mod processing;
+#[cfg(feature = "controller")]
+pub mod diagnostics;
+#[cfg(feature = "controller")]
+pub mod shutdown;
+#[cfg(feature = "controller")]
+pub mod site_calibration;
+#[cfg(feature = "controller")]
+pub mod firmware;
+#[cfg(feature = "controller")]
+pub mod api_auth;
+#[cfg(feature = "controller")]
+pub mod log_query;
+#[cfg(feature = "controller")]
+pub mod rolling_log;
+#[cfg(feature = "controller")]
+pub mod host_metrics;
+#[cfg(feature = "controller")]
+pub mod control_socket;
+#[cfg(feature = "controller")]
+pub mod prometheus;
+#[cfg(feature = "controller")]
+pub mod scan_report;
+#[cfg(all(feature = "controller", unix))]
+pub mod daemon;
+#[cfg(all(feature = "controller", unix))]
+pub mod dashboard;
+#[cfg(all(feature = "controller", unix))]
+pub mod systemd;
+#[cfg(feature = "http-api")]
+pub mod http_api;
+#[cfg(feature = "grpc")]
+pub mod grpc_api;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(all(feature = "windows-service", windows))]
+pub mod win_service;
 
+pub mod accumulator;
+pub mod driver;
+pub mod filter;
 pub mod ld2412;
 pub mod ld2450;
+#[cfg(feature = "std")]
+pub mod bands;
+#[cfg(feature = "std")]
+pub mod calibration;
+#[cfg(feature = "std")]
+pub mod classify;
+#[cfg(feature = "std")]
+pub mod drift;
+#[cfg(feature = "std")]
+pub mod history;
+#[cfg(feature = "std")]
+pub mod metrics;
+#[cfg(feature = "std")]
+pub mod occupancy;
+#[cfg(feature = "std")]
+pub mod parallel;
+#[cfg(feature = "std")]
+pub mod peaks;
+#[cfg(feature = "std")]
+pub mod recorder;
+#[cfg(feature = "std")]
+pub mod replay;
+#[cfg(feature = "std")]
+pub mod scan_plan;
+#[cfg(feature = "std")]
+pub mod schedule;
+#[cfg(feature = "async")]
+pub mod scan_stream;
+#[cfg(feature = "std")]
 pub mod scanner;
+#[cfg(feature = "std")]
+pub mod signal_source;
+#[cfg(feature = "std")]
+pub mod sigmf;
+#[cfg(feature = "fft")]
+pub mod sweep;
+#[cfg(feature = "std")]
+pub mod waterfall;
+#[cfg(feature = "rtlsdr")]
+pub mod rtlsdr;
+#[cfg(feature = "soapysdr")]
+pub mod soapysdr;
+#[cfg(feature = "tracking")]
 pub mod tracker;
+#[cfg(feature = "tracking")]
+pub mod association;
+#[cfg(feature = "tracking")]
+pub mod imm;
+#[cfg(feature = "tracking")]
+pub mod ekf;
+#[cfg(feature = "tracking")]
+pub mod track_history;
+#[cfg(feature = "tracking")]
+pub mod track_store;
+#[cfg(feature = "tracking")]
+pub mod heatmap;
+#[cfg(feature = "tracking")]
+pub mod fusion;
+#[cfg(feature = "tracking")]
+pub mod antenna_array;
+#[cfg(feature = "tracking")]
+pub mod pose_calibration;
+#[cfg(feature = "tracking")]
+pub mod target_class;
+#[cfg(feature = "tracking")]
+pub mod clock;
+#[cfg(feature = "tracking")]
+pub mod track_export;
+#[cfg(feature = "tracking")]
+pub mod smoothing;
+#[cfg(feature = "fixed")]
+pub mod fixed_tracker;
+#[cfg(feature = "std")]
 pub mod parser;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "esp-hal")]
+pub mod esp;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
+#[cfg(feature = "controller")]
 pub use error::{HexarError, HexarResult};
+#[cfg(feature = "controller")]
 pub use config::HexarConfig;
+#[cfg(feature = "controller")]
 pub use safety::SafetyManager;
+#[cfg(feature = "controller")]
 pub use monitoring::MonitoringSystem;
+#[cfg(feature = "controller")]
 pub use radar_controller::RadarController;
+pub use accumulator::FrameAccumulator;
+pub use driver::{DriverAction, DriverCore};
+
+#[cfg(feature = "log")]
+pub(crate) use log::{error, warn};
+
+// Without the log feature the protocol core's diagnostics compile to nothing
+#[cfg(not(feature = "log"))]
+macro_rules! discard_warn {
+    ($($arg:tt)*) => {{
+        let _ = format_args!($($arg)*);
+    }};
+}
+#[cfg(not(feature = "log"))]
+macro_rules! discard_error {
+    ($($arg:tt)*) => {{
+        let _ = format_args!($($arg)*);
+    }};
+}
+#[cfg(not(feature = "log"))]
+pub(crate) use {discard_error as error, discard_warn as warn};
 
-use log::warn;
 use smallvec::SmallVec;
 
+/// Serial baud rates supported by the LD2412 and LD2450 modules, with their protocol index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaudRate {
+    B9600 = 0x0001,
+    B19200 = 0x0002,
+    B38400 = 0x0003,
+    B57600 = 0x0004,
+    B115200 = 0x0005,
+    B230400 = 0x0006,
+    B256000 = 0x0007,
+    B460800 = 0x0008,
+}
+
+impl BaudRate {
+    /// Map a raw baud rate to a supported one, `None` if the modules can't run at it
+    pub fn from_bps(bps: u32) -> Option<Self> {
+        match bps {
+            9600 => Some(BaudRate::B9600),
+            19200 => Some(BaudRate::B19200),
+            38400 => Some(BaudRate::B38400),
+            57600 => Some(BaudRate::B57600),
+            115200 => Some(BaudRate::B115200),
+            230400 => Some(BaudRate::B230400),
+            256000 => Some(BaudRate::B256000),
+            460800 => Some(BaudRate::B460800),
+            _ => None,
+        }
+    }
+
+    pub fn bps(&self) -> u32 {
+        match self {
+            BaudRate::B9600 => 9600,
+            BaudRate::B19200 => 19200,
+            BaudRate::B38400 => 38400,
+            BaudRate::B57600 => 57600,
+            BaudRate::B115200 => 115200,
+            BaudRate::B230400 => 230400,
+            BaudRate::B256000 => 256000,
+            BaudRate::B460800 => 460800,
+        }
+    }
+}
+
+/// Number of framing bytes around a command payload: header, length, opcode and footer
+pub const COMMAND_FRAME_OVERHEAD: usize = 12;
+
+/// Build the wire bytes of a command frame in const context, so fixed commands can live in flash
+/// as `static [u8; N]`. `N` must be the payload length plus [`COMMAND_FRAME_OVERHEAD`], which is
+/// checked at compile time.
+#[allow(clippy::indexing_slicing)] // every index is below N, enforced by the const assertion
+pub const fn command_frame<const D: usize, const N: usize>(opcode: u16, data: [u8; D]) -> [u8; N] {
+    const {
+        assert!(
+            N == D + COMMAND_FRAME_OVERHEAD,
+            "command frame length must be the payload length plus 12"
+        )
+    };
+
+    let mut frame = [0u8; N];
+    let len = ((D + 2) as u16).to_le_bytes();
+    let opcode = opcode.to_le_bytes();
+
+    frame[0] = 0xFD;
+    frame[1] = 0xFC;
+    frame[2] = 0xFB;
+    frame[3] = 0xFA;
+    frame[4] = len[0];
+    frame[5] = len[1];
+    frame[6] = opcode[0];
+    frame[7] = opcode[1];
+
+    let mut i = 0;
+    while i < D {
+        frame[8 + i] = data[i];
+        i += 1;
+    }
+
+    frame[N - 4] = 0x04;
+    frame[N - 3] = 0x03;
+    frame[N - 2] = 0x02;
+    frame[N - 1] = 0x01;
+
+    frame
+}
+
 pub trait RadarDriver {
     fn get_opcode(&self) -> u16;
     fn serialize_data(&self, data: &mut SmallVec<[u8; 16]>);
@@ -35,25 +288,30 @@ pub enum RadarLLFrame {
     TargetFrame2D(SmallVec<[u8; 32]>),
 }
 
+#[cfg_attr(
+    all(feature = "panic-free", not(test)),
+    deny(
+        clippy::indexing_slicing,
+        clippy::unwrap_used,
+        clippy::expect_used,
+        clippy::panic,
+        clippy::unreachable
+    )
+)]
 impl RadarLLFrame {
-    pub fn serialize(&self) -> SmallVec<[u8; 32]> {
+    /// Serialize a command frame, target data frames are only ever received so they give `None`
+    pub fn serialize(&self) -> Option<SmallVec<[u8; 32]>> {
         match self {
             RadarLLFrame::CommandAckFrame(opcode, data) => {
-                let mut buffer = SmallVec::new();
-
-                buffer.extend_from_slice(&[0xFD, 0xFC, 0xFB, 0xFA]);
-                buffer.extend_from_slice(&(data.len() as u16 + 2).to_le_bytes());
-                buffer.extend_from_slice(&opcode.to_le_bytes());
-                buffer.extend_from_slice(data);
-                buffer.extend_from_slice(&[0x04, 0x03, 0x02, 0x01]);
-
-                buffer
-            }
-            _ => {
-                panic!(
-                    "you are not supposed to serialize target data, it is only for deserialization"
-                )
+                copy_payload(&[
+                    &[0xFD, 0xFC, 0xFB, 0xFA],
+                    &(data.len() as u16 + 2).to_le_bytes(),
+                    &opcode.to_le_bytes(),
+                    data,
+                    &[0x04, 0x03, 0x02, 0x01],
+                ])
             }
+            _ => None,
         }
     }
 
@@ -63,14 +321,15 @@ impl RadarLLFrame {
             {
                 let len = u16::from_le_bytes([*len_l, *len_h]);
 
-                assert!(len as usize == data.len() + 2);
+                if len as usize != data.len() + 2 {
+                    warn!("Command frame length is incorrect");
+
+                    return None;
+                }
 
                 let opcode = u16::from_le_bytes([*opcode_l, *opcode_h]);
 
-                Some(RadarLLFrame::CommandAckFrame(
-                    opcode,
-                    SmallVec::from_slice(data),
-                ))
+                Some(RadarLLFrame::CommandAckFrame(opcode, copy_payload(&[data])?))
             }
 
             [0xF4, 0xF3, 0xF2, 0xF1, len_l, len_h, intraframe @ .., 0xF8, 0xF7, 0xF6, 0xF5] => {
@@ -82,14 +341,70 @@ impl RadarLLFrame {
                     return None;
                 }
 
-                Some(RadarLLFrame::TargetFrame(SmallVec::from_slice(intraframe)))
+                Some(RadarLLFrame::TargetFrame(copy_payload(&[intraframe])?))
             }
 
-            [0xAA, 0xFF, 0x03, 0x00, intraframe @ .., 0x55, 0xCC] => Some(
-                RadarLLFrame::TargetFrame2D(SmallVec::from_slice(intraframe)),
-            ),
+            [0xAA, 0xFF, 0x03, 0x00, intraframe @ .., 0x55, 0xCC] => {
+                Some(RadarLLFrame::TargetFrame2D(copy_payload(&[intraframe])?))
+            }
 
             _ => None,
         }
     }
 }
+
+/// `parts` copied one after another, `None` when there is no memory for them.
+/// `SmallVec::from_slice` and `extend_from_slice` panic on a capacity overflow instead, the
+/// panic_free example proves this doesn't.
+fn copy_payload<A: smallvec::Array<Item = u8>>(parts: &[&[u8]]) -> Option<SmallVec<A>> {
+    let len = parts
+        .iter()
+        .try_fold(0usize, |len, part| len.checked_add(part.len()))?;
+    let mut payload: SmallVec<A> = if len > A::size() {
+        let mut heap = alloc::vec::Vec::new();
+        heap.try_reserve_exact(len).ok()?;
+        SmallVec::from_vec(heap)
+    } else {
+        SmallVec::new()
+    };
+    // SAFETY: either the inline buffer or the reserved allocation holds at least `len` bytes,
+    // the copies initialize the first `len` of them
+    unsafe {
+        let mut dst = payload.as_mut_ptr();
+        for part in parts {
+            dst.copy_from_nonoverlapping(part.as_ptr(), part.len());
+            dst = dst.add(part.len());
+        }
+        payload.set_len(len);
+    }
+    Some(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_never_panics_on_truncated_frames() {
+        let frame = [
+            0xFD, 0xFC, 0xFB, 0xFA, 0x04, 0x00, 0xFF, 0x01, 0x00, 0x00, 0x04, 0x03, 0x02, 0x01,
+        ];
+
+        for end in 0..frame.len() {
+            for start in 0..end {
+                let _ = RadarLLFrame::deserialize(&frame[start..end]);
+            }
+        }
+
+        // Length field disagreeing with the payload is rejected instead of asserted on
+        let mut corrupted = frame;
+        corrupted[4] = 0x09;
+        assert!(RadarLLFrame::deserialize(&corrupted).is_none());
+    }
+
+    #[test]
+    fn test_target_frames_are_not_serialized() {
+        let frame = RadarLLFrame::TargetFrame2D(SmallVec::from_slice(&[0u8; 24]));
+        assert!(frame.serialize().is_none());
+    }
+}
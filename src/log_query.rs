@@ -0,0 +1,374 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::config::LogRotation;
+
+/// Log file of a daemon in the configured log directory
+pub const LOG_FILE_NAME: &str = "hexar.log";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "trace" => Ok(LogLevel::Trace),
+            "debug" => Ok(LogLevel::Debug),
+            "info" => Ok(LogLevel::Info),
+            "warn" | "warning" => Ok(LogLevel::Warn),
+            "error" => Ok(LogLevel::Error),
+            _ => Err(format!("unknown log level '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+        })
+    }
+}
+
+/// One event of a log file, with the lines following it that carry no timestamp of their own
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thread: Option<String>,
+    /// Module the event came from, e.g. `hexar::serial_radar`, when the log has targets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// A line as written by the `tracing` formatter: timestamp, level, optionally the thread
+    /// name and ID and the target, then the message
+    pub fn parse(line: &str) -> Option<Self> {
+        let line = strip_ansi(line);
+        let (timestamp, rest) = line.trim_start().split_once(char::is_whitespace)?;
+        let timestamp = DateTime::parse_from_rfc3339(timestamp)
+            .ok()?
+            .with_timezone(&Utc);
+        let (level, mut rest) = rest
+            .trim_start()
+            .split_once(' ')
+            .unwrap_or((rest.trim(), ""));
+        let level = level.parse().ok()?;
+
+        let mut thread = None;
+        let mut words = rest.splitn(3, ' ');
+        match (words.next(), words.next(), words.next()) {
+            (Some(id), _, _) if id.starts_with("ThreadId(") => {
+                rest = rest.get(id.len() + 1..).unwrap_or_default();
+            }
+            (Some(name), Some(id), Some(after)) if id.starts_with("ThreadId(") => {
+                rest = after;
+                thread = Some(name.to_string());
+            }
+            _ => {}
+        }
+        // Targets are module paths, unlike the capitalized words messages start with
+        let target = rest.split_once(": ").and_then(|(target, message)| {
+            let is_path = !target.is_empty()
+                && target
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == ':');
+            is_path.then(|| {
+                rest = message;
+                target.to_string()
+            })
+        });
+        Some(Self {
+            timestamp,
+            level,
+            thread,
+            target,
+            message: rest.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {:>5} ",
+            self.timestamp.format("%Y-%m-%d %H:%M:%S%.3f"),
+            self.level
+        )?;
+        if let Some(target) = &self.target {
+            write!(f, "{}: ", target)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut plain = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Colors and styles end with a letter, usually `m`
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// Which entries `hexar monitor` shows
+#[derive(Debug, Clone, Default)]
+pub struct LogQuery {
+    /// This level and the more severe ones
+    pub level: Option<LogLevel>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Part of the target, e.g. `serial_radar`, or of the thread name in logs without targets
+    pub component: Option<String>,
+    /// Only the latest matches, all when 0
+    pub limit: usize,
+}
+
+impl LogQuery {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.level.is_none_or(|level| entry.level >= level)
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+            && self.until.is_none_or(|until| entry.timestamp <= until)
+            && self.component.as_deref().is_none_or(|component| {
+                [&entry.target, &entry.thread]
+                    .into_iter()
+                    .flatten()
+                    .any(|name| name.contains(component))
+            })
+    }
+
+    /// Matching entries of `files`, which are read in turn, oldest first
+    pub fn run(&self, files: &[PathBuf]) -> io::Result<Vec<LogEntry>> {
+        let mut found = VecDeque::new();
+        for path in files {
+            let mut keep = |entry: LogEntry| {
+                if self.matches(&entry) {
+                    if self.limit > 0 && found.len() == self.limit {
+                        found.pop_front();
+                    }
+                    found.push_back(entry);
+                }
+            };
+            read_entries(BufReader::new(File::open(path)?), &mut keep)?;
+        }
+        Ok(found.into())
+    }
+}
+
+/// Pass every entry of `reader` to `each`. Lines before the first entry are skipped.
+pub fn read_entries(mut reader: impl BufRead, mut each: impl FnMut(LogEntry)) -> io::Result<()> {
+    let mut current: Option<LogEntry> = None;
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches(['\n', '\r']);
+        match LogEntry::parse(text) {
+            Some(entry) => {
+                if let Some(done) = current.replace(entry) {
+                    each(done);
+                }
+            }
+            None => {
+                if let Some(entry) = &mut current {
+                    entry.message.push('\n');
+                    entry.message.push_str(&strip_ansi(text));
+                }
+            }
+        }
+        line.clear();
+    }
+    if let Some(entry) = current {
+        each(entry);
+    }
+    Ok(())
+}
+
+/// Name of a log file rotated by date, after the first day it covers, like `hexar.log.2024-06-01`
+pub fn dated_name(name: &str, period: NaiveDate) -> String {
+    format!("{}.{}", name, period.format("%Y-%m-%d"))
+}
+
+/// Name of a log file rotated by size, like `hexar.log.1`, where higher numbers are older
+pub fn numbered_name(name: &str, number: usize) -> String {
+    format!("{}.{}", name, number)
+}
+
+/// `name` in `directory` and the files [`crate::rolling_log::RollingLog`] rotated it into, oldest
+/// first, named by [`dated_name`] for daily and weekly rotation and by [`numbered_name`] for
+/// rotation by size
+pub fn log_files(directory: &Path, name: &str, rotation: &LogRotation) -> io::Result<Vec<PathBuf>> {
+    let mut rotated = Vec::new();
+    if !directory.is_dir() {
+        return Ok(Vec::new());
+    }
+    for entry in std::fs::read_dir(directory)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let Some(suffix) = file_name
+            .to_str()
+            .and_then(|file_name| file_name.strip_prefix(name))
+            .and_then(|suffix| suffix.strip_prefix('.'))
+        else {
+            continue;
+        };
+        let order = match rotation {
+            LogRotation::Daily | LogRotation::Weekly => {
+                NaiveDate::parse_from_str(suffix, "%Y-%m-%d")
+                    .ok()
+                    .filter(|&date| dated_name(name, date) == file_name.to_string_lossy())
+                    .map(|date| date.to_string())
+            }
+            // Reversed so that the oldest, highest number comes first
+            LogRotation::Size => suffix
+                .parse::<u32>()
+                .ok()
+                .filter(|&number| {
+                    numbered_name(name, number as usize) == file_name.to_string_lossy()
+                })
+                .map(|number| format!("{:010}", u32::MAX - number)),
+        };
+        if let Some(order) = order {
+            rotated.push((order, entry.path()));
+        }
+    }
+    rotated.sort();
+    let mut files: Vec<PathBuf> = rotated.into_iter().map(|(_, path)| path).collect();
+    let current = directory.join(name);
+    if current.is_file() {
+        files.push(current);
+    }
+    Ok(files)
+}
+
+/// A point in time given as RFC 3339, a date meaning its start in UTC, or a duration before
+/// `now` like `30m`, `6h` or `2d`
+pub fn parse_time(time: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, String> {
+    let time = time.trim();
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(time) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(time, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    let (value, unit) = time.split_at(time.len().saturating_sub(1));
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return Err(format!("'{}' is no time, date or duration like 30m", time)),
+    };
+    let value: i64 = value
+        .parse()
+        .map_err(|_| format!("bad duration '{}'", time))?;
+    Ok(now - chrono::Duration::seconds(value * seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_entries() {
+        let log = "\
+2024-06-01T10:00:00.000001Z  INFO main ThreadId(01) hexar: Starting Hexar Radar System v0.1.0
+2024-06-01T10:00:01.5Z  WARN ThreadId(07) hexar::serial_radar: Antenna 2 disconnected: broken pipe
+\x1b[2m2024-06-01T10:00:02Z\x1b[0m \x1b[31mERROR\x1b[0m main ThreadId(01) Scan cycle failed
+Caused by: timeout
+2024-06-01T10:00:03Z DEBUG Received: 3 frames
+";
+        let mut entries = Vec::new();
+        read_entries(log.as_bytes(), |entry| entries.push(entry)).unwrap();
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].thread.as_deref(), Some("main"));
+        assert_eq!(entries[0].target.as_deref(), Some("hexar"));
+        assert_eq!(entries[1].level, LogLevel::Warn);
+        assert_eq!(entries[1].thread, None);
+        assert_eq!(entries[1].message, "Antenna 2 disconnected: broken pipe");
+        assert_eq!(entries[2].target, None);
+        assert_eq!(entries[2].message, "Scan cycle failed\nCaused by: timeout");
+        assert_eq!(entries[3].message, "Received: 3 frames");
+
+        let query = LogQuery {
+            level: Some(LogLevel::Warn),
+            component: Some("serial".to_string()),
+            ..Default::default()
+        };
+        let matching: Vec<_> = entries.iter().filter(|e| query.matches(e)).collect();
+        assert_eq!(matching, [&entries[1]]);
+
+        let now = entries[3].timestamp;
+        assert_eq!(
+            parse_time("2s", now).unwrap(),
+            parse_time("2024-06-01T10:00:01Z", now).unwrap()
+        );
+        assert_eq!(
+            parse_time("2024-06-01", now).unwrap(),
+            parse_time("10h", now).unwrap() - chrono::Duration::seconds(3)
+        );
+        assert!(parse_time("yesterday", now).is_err());
+    }
+
+    #[test]
+    fn test_rotated_files() {
+        let directory = std::env::temp_dir().join(format!("hexar-logs-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for name in [
+            "hexar.log",
+            "hexar.log.2024-06-02",
+            "hexar.log.2024-05-31",
+            "hexar.log.1",
+            "hexar.log.2",
+            "hexar.log.01",
+            "hexar.log.2024-6-1",
+            "other.log.1",
+        ] {
+            std::fs::write(directory.join(name), "").unwrap();
+        }
+        let names = |rotation| {
+            log_files(&directory, LOG_FILE_NAME, &rotation)
+                .unwrap()
+                .iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            names(LogRotation::Daily),
+            ["hexar.log.2024-05-31", "hexar.log.2024-06-02", "hexar.log"]
+        );
+        assert_eq!(
+            names(LogRotation::Size),
+            ["hexar.log.2", "hexar.log.1", "hexar.log"]
+        );
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,4 +1,4 @@
-use hexar::scanner::{FrequencyScanner, FrequencyRange};
+use hexar::scanner::{FrequencyScanner, FrequencyRange, ScanResult};
 use std::time::Duration;
 use log::{info, warn};
 use env_logger::Env;
@@ -104,14 +104,15 @@ fn get_numeric_input(prompt: &str) -> Result<f32, Box<dyn std::error::Error>> {
 fn run_quick_scan(scanner: &mut FrequencyScanner) -> Result<(), Box<dyn std::error::Error>> {
     info!("Quick scan started");
     
-    let strong_signals = scanner.quick_scan();
+    let peaks = scanner.find_peaks();
     
-    if strong_signals.is_empty() {
+    if peaks.is_empty() {
         println!("\nNo strong signals detected");
     } else {
-        println!("\nFound {} strong signals:", strong_signals.len());
-        for (i, signal) in strong_signals.iter().enumerate() {
-            println!("  {}. {:.2} MHz - {:.2} dB", i + 1, signal.frequency, signal.strength);
+        println!("\nFound {} strong signals:", peaks.len());
+        for (i, peak) in peaks.iter().enumerate() {
+            println!("  {}. {:.2} MHz - {:.2} dB ({:.1} dB prominence, {:.3} MHz wide)",
+                     i + 1, peak.center_freq, peak.peak_db, peak.prominence, peak.bandwidth_estimate);
         }
     }
     
@@ -152,24 +153,24 @@ fn run_full_scan(scanner: &mut FrequencyScanner) -> Result<(), Box<dyn std::erro
 fn run_continuous_scan(scanner: &mut FrequencyScanner, duration: Duration) -> Result<(), Box<dyn std::error::Error>> {
     info!("Continuous scan: {:?}", duration);
     
-    let results = scanner.continuous_scan(duration);
+    // Aggregate per 0.1 MHz as detections arrive instead of keeping every one
+    let mut unique_signals = std::collections::HashMap::new();
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_time().build()?;
+    let total = runtime.block_on(scanner.continuous_scan(duration, |result: ScanResult| {
+        println!("  {:.2} MHz - {:.2} dB (confidence {:.2}, {:?})", result.frequency, result.strength, result.confidence, result.class);
+        let freq_key = (result.frequency * 10.0) as i32;
+        let entry = unique_signals.entry(freq_key).or_insert((result.frequency, result.strength, 0));
+        entry.2 += 1;
+        if result.strength > entry.1 {
+            entry.1 = result.strength;
+        }
+    }));
     
-    if results.is_empty() {
+    if total == 0 {
         println!("\nNo signals detected");
     } else {
-        // Group by frequency to show unique signals
-        let mut unique_signals = std::collections::HashMap::new();
-        for result in &results {
-            let freq_key = (result.frequency * 10.0) as i32; // Round to 0.1 MHz
-            let entry = unique_signals.entry(freq_key).or_insert((result.frequency, result.strength, 0));
-            entry.2 += 1;
-            if result.strength > entry.1 {
-                entry.1 = result.strength;
-            }
-        }
-        
         println!("\nContinuous scan summary:");
-        println!("  Total detections: {}", results.len());
+        println!("  Total detections: {}", total);
         println!("  Unique signals: {}", unique_signals.len());
         println!("  Top signals:");
         
@@ -221,7 +222,7 @@ mod tests {
             step: 1.0,
         };
         let scanner = FrequencyScanner::new(range, -60.0);
-        assert_eq!(scanner.signal_threshold, -60.0);
+        assert_eq!(scanner.get_threshold(), -60.0);
     }
 
     #[test]
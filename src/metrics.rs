@@ -0,0 +1,396 @@
+use std::time::Duration;
+
+/// Upper bounds of the scan cycle duration buckets in milliseconds
+const CYCLE_DURATION_BOUNDS_MS: [f64; 10] = [
+    5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0,
+];
+
+/// Upper bounds of the measurements per cycle buckets
+const MEASUREMENT_COUNT_BOUNDS: [f64; 8] = [0.0, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0];
+
+/// Distribution of observed values over fixed buckets, as Prometheus histograms keep them.
+/// Values above the last bound go to an overflow bucket.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Histogram {
+    /// Upper bound of every bucket but the overflow one, ascending
+    bounds: Vec<f64>,
+    /// Values in each bucket, one more than there are bounds
+    counts: Vec<u64>,
+    sum: f64,
+    max: f64,
+}
+
+impl Histogram {
+    pub fn new(mut bounds: Vec<f64>) -> Self {
+        bounds.retain(|bound| !bound.is_nan());
+        bounds.sort_by(f64::total_cmp);
+        bounds.dedup();
+        Self {
+            counts: vec![0; bounds.len() + 1],
+            bounds,
+            sum: 0.0,
+            max: 0.0,
+        }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        let bucket = self.bounds.partition_point(|&bound| bound < value);
+        self.counts[bucket] += 1;
+        self.max = if self.get_count() == 1 {
+            value
+        } else {
+            self.max.max(value)
+        };
+        self.sum += value;
+    }
+
+    pub fn get_count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn get_sum(&self) -> f64 {
+        self.sum
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        let count = self.get_count();
+        (count > 0).then(|| self.sum / count as f64)
+    }
+
+    /// Upper bound of the bucket the `q` quantile falls in, the largest value observed when
+    /// that is the overflow bucket
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        let count = self.get_count();
+        if count == 0 {
+            return None;
+        }
+        let rank = (q.clamp(0.0, 1.0) * count as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, &in_bucket) in self.counts.iter().enumerate() {
+            seen += in_bucket;
+            if seen >= rank {
+                return Some(
+                    self.bounds
+                        .get(bucket)
+                        .map_or(self.max, |&b| b.min(self.max)),
+                );
+            }
+        }
+        Some(self.max)
+    }
+
+    /// `(upper bound, values at or below it)` for every bucket, ending with the overflow
+    /// bucket at infinity, the form Prometheus exposes
+    pub fn cumulative_buckets(&self) -> Vec<(f64, u64)> {
+        let mut total = 0;
+        self.bounds
+            .iter()
+            .copied()
+            .chain(std::iter::once(f64::INFINITY))
+            .zip(&self.counts)
+            .map(|(bound, &count)| {
+                total += count;
+                (bound, total)
+            })
+            .collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.iter_mut().for_each(|count| *count = 0);
+        self.sum = 0.0;
+        self.max = 0.0;
+    }
+}
+
+/// What became of the measurements handed to the tracker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AssociationStatistics {
+    /// Updated a target that already existed
+    pub associated: u64,
+    /// Started a new target
+    pub created: u64,
+    /// Changed nothing, as when the antenna had no capacity for another target
+    pub unassociated: u64,
+}
+
+impl AssociationStatistics {
+    pub fn add(&mut self, other: &AssociationStatistics) {
+        self.associated += other.associated;
+        self.created += other.created;
+        self.unassociated += other.unassociated;
+    }
+
+    pub fn total(&self) -> u64 {
+        self.associated + self.created + self.unassociated
+    }
+}
+
+/// Counters and distributions of the scan cycles a controller ran
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScanMetrics {
+    pub cycles: u64,
+    /// Scanner readings processed
+    pub signals: u64,
+    /// Measurements handed to the tracker
+    pub measurements: u64,
+    pub associations: AssociationStatistics,
+    /// Sensor frames lost because the channel to the controller was full
+    pub dropped_frames: u64,
+    /// Sensor frames replaced by a newer one from the same antenna before a cycle used them
+    pub superseded_frames: u64,
+    /// Frames a recorder or other sink dropped because it fell behind
+    pub sink_dropped: u64,
+    pub cycle_duration_ms: Histogram,
+    pub measurements_per_cycle: Histogram,
+}
+
+impl Default for ScanMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ScanMetrics {
+    pub fn new() -> Self {
+        Self {
+            cycles: 0,
+            signals: 0,
+            measurements: 0,
+            associations: AssociationStatistics::default(),
+            dropped_frames: 0,
+            superseded_frames: 0,
+            sink_dropped: 0,
+            cycle_duration_ms: Histogram::new(CYCLE_DURATION_BOUNDS_MS.to_vec()),
+            measurements_per_cycle: Histogram::new(MEASUREMENT_COUNT_BOUNDS.to_vec()),
+        }
+    }
+
+    pub fn record_cycle(
+        &mut self,
+        duration: Duration,
+        signals: usize,
+        associations: &AssociationStatistics,
+    ) {
+        let measurements = associations.total();
+        self.cycles += 1;
+        self.signals += signals as u64;
+        self.measurements += measurements;
+        self.associations.add(associations);
+        self.cycle_duration_ms
+            .observe(duration.as_secs_f64() * 1000.0);
+        self.measurements_per_cycle.observe(measurements as f64);
+    }
+
+    pub fn get_average_cycle_duration(&self) -> Duration {
+        self.cycle_duration_ms
+            .mean()
+            .map_or(Duration::ZERO, |ms| Duration::from_secs_f64(ms / 1000.0))
+    }
+
+    pub fn get_signals_per_cycle(&self) -> f32 {
+        per_cycle(self.signals, self.cycles)
+    }
+
+    pub fn get_measurements_per_cycle(&self) -> f32 {
+        per_cycle(self.measurements, self.cycles)
+    }
+}
+
+fn per_cycle(total: u64, cycles: u64) -> f32 {
+    if cycles == 0 {
+        0.0
+    } else {
+        total as f32 / cycles as f32
+    }
+}
+
+/// What a metric family counts, its `# TYPE` in the exposition
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+/// Metrics written in the Prometheus text exposition format, version 0.0.4
+#[derive(Debug, Clone, Default)]
+pub struct Exposition {
+    text: String,
+}
+
+impl Exposition {
+    pub const CONTENT_TYPE: &'static str = "text/plain; version=0.0.4";
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a metric family, its samples following
+    pub fn family(&mut self, name: &str, kind: MetricKind, help: &str) -> &mut Self {
+        let kind = match kind {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+            MetricKind::Histogram => "histogram",
+        };
+        let help = help.replace('\\', "\\\\").replace('\n', "\\n");
+        self.text += &format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind);
+        self
+    }
+
+    pub fn sample(&mut self, name: &str, labels: &[(&str, &str)], value: f64) -> &mut Self {
+        self.text += name;
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels
+                .iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape_label(value)))
+                .collect();
+            self.text += &format!("{{{}}}", labels.join(","));
+        }
+        self.text += &format!(" {}\n", format_value(value));
+        self
+    }
+
+    /// Family and sample of a metric without labels
+    pub fn single(&mut self, name: &str, kind: MetricKind, help: &str, value: f64) -> &mut Self {
+        self.family(name, kind, help).sample(name, &[], value)
+    }
+
+    /// Samples of `histogram` with its bounds and sum multiplied by `scale`, e.g. 0.001 to
+    /// expose milliseconds in seconds
+    pub fn histogram(
+        &mut self,
+        name: &str,
+        labels: &[(&str, &str)],
+        histogram: &Histogram,
+        scale: f64,
+    ) -> &mut Self {
+        let bucket = format!("{}_bucket", name);
+        for (bound, count) in histogram.cumulative_buckets() {
+            let le = format_value(bound * scale);
+            let mut bucket_labels = labels.to_vec();
+            bucket_labels.push(("le", &le));
+            self.sample(&bucket, &bucket_labels, count as f64);
+        }
+        self.sample(
+            &format!("{}_sum", name),
+            labels,
+            histogram.get_sum() * scale,
+        );
+        self.sample(
+            &format!("{}_count", name),
+            labels,
+            histogram.get_count() as f64,
+        )
+    }
+
+    pub fn into_text(self) -> String {
+        self.text
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value == f64::INFINITY {
+        "+Inf".to_string()
+    } else if value == f64::NEG_INFINITY {
+        "-Inf".to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets() {
+        let mut histogram = Histogram::new(vec![10.0, 1.0, 5.0]);
+        for value in [0.5, 1.0, 3.0, 7.0, 7.0, 20.0] {
+            histogram.observe(value);
+        }
+        assert_eq!(histogram.get_count(), 6);
+        assert_eq!(histogram.mean(), Some(38.5 / 6.0));
+        assert_eq!(
+            histogram.cumulative_buckets(),
+            vec![(1.0, 2), (5.0, 3), (10.0, 5), (f64::INFINITY, 6)]
+        );
+        assert_eq!(histogram.quantile(0.5), Some(5.0));
+        assert_eq!(histogram.quantile(0.8), Some(10.0));
+        assert_eq!(histogram.quantile(1.0), Some(20.0));
+
+        histogram.clear();
+        assert_eq!(histogram.quantile(0.5), None);
+    }
+
+    #[test]
+    fn test_scan_metrics() {
+        let mut metrics = ScanMetrics::new();
+        let associations = AssociationStatistics {
+            associated: 2,
+            created: 1,
+            unassociated: 0,
+        };
+        metrics.record_cycle(Duration::from_millis(20), 10, &associations);
+        metrics.record_cycle(
+            Duration::from_millis(40),
+            0,
+            &AssociationStatistics::default(),
+        );
+
+        assert_eq!(metrics.cycles, 2);
+        assert_eq!(
+            metrics.get_average_cycle_duration(),
+            Duration::from_millis(30)
+        );
+        assert_eq!(metrics.get_signals_per_cycle(), 5.0);
+        assert_eq!(metrics.get_measurements_per_cycle(), 1.5);
+        assert_eq!(metrics.associations.created, 1);
+        assert_eq!(
+            metrics.measurements_per_cycle.cumulative_buckets()[0],
+            (0.0, 1)
+        );
+    }
+
+    #[test]
+    fn test_exposition() {
+        let mut histogram = Histogram::new(vec![5.0, 10.0]);
+        histogram.observe(7.0);
+        let mut exposition = Exposition::new();
+        exposition.single("hexar_up", MetricKind::Gauge, "Whether it runs", 1.0);
+        exposition
+            .family(
+                "hexar_cycle_seconds",
+                MetricKind::Histogram,
+                "Cycle duration",
+            )
+            .histogram(
+                "hexar_cycle_seconds",
+                &[("antenna", "a\"1")],
+                &histogram,
+                0.001,
+            );
+        assert_eq!(
+            exposition.into_text(),
+            "# HELP hexar_up Whether it runs\n\
+             # TYPE hexar_up gauge\n\
+             hexar_up 1\n\
+             # HELP hexar_cycle_seconds Cycle duration\n\
+             # TYPE hexar_cycle_seconds histogram\n\
+             hexar_cycle_seconds_bucket{antenna=\"a\\\"1\",le=\"0.005\"} 0\n\
+             hexar_cycle_seconds_bucket{antenna=\"a\\\"1\",le=\"0.01\"} 1\n\
+             hexar_cycle_seconds_bucket{antenna=\"a\\\"1\",le=\"+Inf\"} 1\n\
+             hexar_cycle_seconds_sum{antenna=\"a\\\"1\"} 0.007\n\
+             hexar_cycle_seconds_count{antenna=\"a\\\"1\"} 1\n"
+        );
+    }
+}
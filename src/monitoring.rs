@@ -1,407 +1,673 @@
-use crate::config::MonitoringConfig;
-use crate::error::HexarResult;
-use anyhow::Result;
-use serde::{Deserialize, Serialize};
-use std::time::{Duration, Instant};
-use tracing::{info, warn, error, debug};
-use chrono::Utc;
-use uuid::Uuid;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SystemMetrics {
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-    pub system_id: Uuid,
-    pub performance: PerformanceMetrics,
-    pub radar: RadarMetrics,
-    pub safety: SafetyMetrics,
-    pub errors: ErrorMetrics,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PerformanceMetrics {
-    pub cpu_usage_percent: f32,
-    pub memory_usage_percent: f32,
-    pub disk_usage_percent: f32,
-    pub network_io_bytes_per_second: u64,
-    pub uptime_seconds: u64,
-    pub load_average: [f32; 3],
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RadarMetrics {
-    pub scan_rate_hz: f32,
-    pub targets_tracked: usize,
-    pub signal_quality_db: f32,
-    pub noise_floor_db: f32,
-    pub antenna_status: Vec<AntennaMetrics>,
-    pub processing_latency_ms: f32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AntennaMetrics {
-    pub id: u8,
-    pub connected: bool,
-    pub temperature_celsius: f32,
-    pub power_watts: f32,
-    pub signal_strength_db: f32,
-    pub error_count: u32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SafetyMetrics {
-    pub emergency_stop_active: bool,
-    pub temperature_status: TemperatureStatus,
-    pub power_status: PowerStatus,
-    pub last_safety_check: chrono::DateTime<chrono::Utc>,
-    pub safety_score: f32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum TemperatureStatus {
-    Normal,
-    Warning,
-    Critical,
-    Emergency,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum PowerStatus {
-    Normal,
-    Warning,
-    Critical,
-    Backup,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ErrorMetrics {
-    pub total_errors: u64,
-    pub error_rate_per_minute: f32,
-    pub recent_errors: Vec<ErrorEntry>,
-    pub critical_errors: u32,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ErrorEntry {
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-    pub severity: ErrorSeverity,
-    pub component: String,
-    pub message: String,
-    pub error_id: Uuid,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-pub enum ErrorSeverity {
-    Info,
-    Warning,
-    Error,
-    Critical,
-}
-
-pub struct MonitoringSystem {
-    config: MonitoringConfig,
-    system_id: Uuid,
-    start_time: Instant,
-    metrics_history: Vec<SystemMetrics>,
-    error_log: Vec<ErrorEntry>,
-    alerts: Vec<Alert>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Alert {
-    pub id: Uuid,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-    pub severity: AlertSeverity,
-    pub category: AlertCategory,
-    pub message: String,
-    pub component: String,
-    pub acknowledged: bool,
-    pub resolved: bool,
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
-pub enum AlertSeverity {
-    Info,
-    Warning,
-    Critical,
-    Emergency,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum AlertCategory {
-    System,
-    Performance,
-    Safety,
-    Hardware,
-    Software,
-    Network,
-}
-
-impl MonitoringSystem {
-    pub fn new(config: MonitoringConfig) -> HexarResult<Self> {
-        Ok(Self {
-            config,
-            system_id: Uuid::new_v4(),
-            start_time: Instant::now(),
-            metrics_history: Vec::new(),
-            error_log: Vec::new(),
-            alerts: Vec::new(),
-        })
-    }
-    
-    pub async fn collect_metrics(&mut self) -> Result<SystemMetrics> {
-        debug!("Collecting system metrics...");
-        
-        let performance = self.collect_performance_metrics().await?;
-        let radar = self.collect_radar_metrics().await?;
-        let safety = self.collect_safety_metrics().await?;
-        let errors = self.collect_error_metrics().await?;
-        
-        let metrics = SystemMetrics {
-            timestamp: Utc::now(),
-            system_id: self.system_id,
-            performance,
-            radar,
-            safety,
-            errors,
-        };
-        
-        // Store metrics (with retention limit)
-        self.metrics_history.push(metrics.clone());
-        
-        let max_history = (self.config.data_retention_days * 24 * 60 * 60) / 
-            self.config.health_check_interval_seconds;
-        
-        if self.metrics_history.len() > max_history as usize {
-            self.metrics_history.remove(0);
-        }
-        
-        // Check for alerts
-        self.check_alert_conditions(&metrics).await?;
-        
-        Ok(metrics)
-    }
-    
-    pub async fn log_error(&mut self, component: &str, message: &str, severity: ErrorSeverity) -> Result<()> {
-        let entry = ErrorEntry {
-            timestamp: Utc::now(),
-            severity,
-            component: component.to_string(),
-            message: message.to_string(),
-            error_id: Uuid::new_v4(),
-        };
-        
-        self.error_log.push(entry.clone());
-        
-        // Keep error log manageable
-        if self.error_log.len() > 10000 {
-            self.error_log.remove(0);
-        }
-        
-        // Create alert for critical errors
-        if matches!(severity, ErrorSeverity::Critical) {
-            self.create_alert(
-                AlertSeverity::Critical,
-                AlertCategory::Software,
-                format!("Critical error in {}: {}", component, message),
-                component.to_string(),
-            ).await?;
-        }
-        
-        match severity {
-            ErrorSeverity::Info => debug!("[{}] {}", component, message),
-            ErrorSeverity::Warning => warn!("[{}] {}", component, message),
-            ErrorSeverity::Error => error!("[{}] {}", component, message),
-            ErrorSeverity::Critical => error!("[CRITICAL] {}: {}", component, message),
-        }
-        
-        Ok(())
-    }
-    
-    pub async fn create_alert(&mut self, severity: AlertSeverity, category: AlertCategory, 
-                             message: String, component: String) -> Result<()> {
-        let alert = Alert {
-            id: Uuid::new_v4(),
-            timestamp: Utc::now(),
-            severity,
-            category,
-            message: message.clone(),
-            component,
-            acknowledged: false,
-            resolved: false,
-        };
-        
-        self.alerts.push(alert.clone());
-        
-        // Log alert
-        match severity {
-            AlertSeverity::Info => info!("ALERT: {}", message),
-            AlertSeverity::Warning => warn!("ALERT: {}", message),
-            AlertSeverity::Critical => error!("CRITICAL ALERT: {}", message),
-            AlertSeverity::Emergency => error!("EMERGENCY ALERT: {}", message),
-        }
-        
-        // TODO: Implement alert notifications (email, SMS, etc.)
-        
-        Ok(())
-    }
-    
-    pub fn get_metrics_history(&self, duration: Duration) -> Vec<&SystemMetrics> {
-        let cutoff = Utc::now() - chrono::Duration::from_std(duration).unwrap_or_default();
-        
-        self.metrics_history
-            .iter()
-            .filter(|m| m.timestamp > cutoff)
-            .collect()
-    }
-    
-    pub fn get_active_alerts(&self) -> Vec<&Alert> {
-        self.alerts
-            .iter()
-            .filter(|a| !a.resolved)
-            .collect()
-    }
-    
-    pub fn acknowledge_alert(&mut self, alert_id: Uuid) -> Result<bool> {
-        if let Some(alert) = self.alerts.iter_mut().find(|a| a.id == alert_id) {
-            alert.acknowledged = true;
-            info!("Alert {} acknowledged", alert_id);
-            Ok(true)
-        } else {
-            Ok(false)
-        }
-    }
-    
-    pub fn resolve_alert(&mut self, alert_id: Uuid) -> Result<bool> {
-        if let Some(alert) = self.alerts.iter_mut().find(|a| a.id == alert_id) {
-            alert.resolved = true;
-            info!("Alert {} resolved", alert_id);
-            Ok(true)
-        } else {
-            Ok(false)
-        }
-    }
-    
-    // Private helper methods
-    async fn collect_performance_metrics(&self) -> Result<PerformanceMetrics> {
-        // TODO: Implement actual performance monitoring
-        // For now, return simulated data
-        
-        let uptime = self.start_time.elapsed();
-        
-        Ok(PerformanceMetrics {
-            cpu_usage_percent: 15.2,
-            memory_usage_percent: 45.8,
-            disk_usage_percent: 23.1,
-            network_io_bytes_per_second: 1024,
-            uptime_seconds: uptime.as_secs(),
-            load_average: [0.5, 0.3, 0.2],
-        })
-    }
-    
-    async fn collect_radar_metrics(&self) -> Result<RadarMetrics> {
-        // TODO: Implement actual radar metrics collection
-        
-        let antenna_metrics = (0..6).map(|i| AntennaMetrics {
-            id: i,
-            connected: true,
-            temperature_celsius: 25.0 + (i as f32 * 0.5),
-            power_watts: 5.0 + (i as f32 * 0.2),
-            signal_strength_db: -30.0 - (i as f32 * 2.0),
-            error_count: 0,
-        }).collect();
-        
-        Ok(RadarMetrics {
-            scan_rate_hz: 10.5,
-            targets_tracked: 3,
-            signal_quality_db: -25.3,
-            noise_floor_db: -85.2,
-            antenna_status: antenna_metrics,
-            processing_latency_ms: 15.7,
-        })
-    }
-    
-    async fn collect_safety_metrics(&self) -> Result<SafetyMetrics> {
-        // TODO: Implement actual safety metrics collection
-        
-        Ok(SafetyMetrics {
-            emergency_stop_active: false,
-            temperature_status: TemperatureStatus::Normal,
-            power_status: PowerStatus::Normal,
-            last_safety_check: Utc::now(),
-            safety_score: 0.95,
-        })
-    }
-    
-    async fn collect_error_metrics(&self) -> Result<ErrorMetrics> {
-        let recent_cutoff = Utc::now() - chrono::Duration::minutes(5);
-        let recent_errors: Vec<_> = self.error_log
-            .iter()
-            .filter(|e| e.timestamp > recent_cutoff)
-            .cloned()
-            .collect();
-        
-        let error_rate = recent_errors.len() as f32 / 5.0; // errors per minute
-        
-        Ok(ErrorMetrics {
-            total_errors: self.error_log.len() as u64,
-            error_rate_per_minute: error_rate,
-            recent_errors,
-            critical_errors: self.error_log.iter()
-                .filter(|e| matches!(e.severity, ErrorSeverity::Critical))
-                .count() as u32,
-        })
-    }
-    
-    async fn check_alert_conditions(&mut self, metrics: &SystemMetrics) -> Result<()> {
-        // Check performance alerts
-        if metrics.performance.cpu_usage_percent > 80.0 {
-            self.create_alert(
-                AlertSeverity::Warning,
-                AlertCategory::Performance,
-                format!("High CPU usage: {:.1}%", metrics.performance.cpu_usage_percent),
-                "CPU".to_string(),
-            ).await?;
-        }
-        
-        if metrics.performance.memory_usage_percent > 90.0 {
-            self.create_alert(
-                AlertSeverity::Critical,
-                AlertCategory::Performance,
-                format!("High memory usage: {:.1}%", metrics.performance.memory_usage_percent),
-                "Memory".to_string(),
-            ).await?;
-        }
-        
-        // Check radar alerts
-        if metrics.radar.processing_latency_ms > 100.0 {
-            self.create_alert(
-                AlertSeverity::Warning,
-                AlertCategory::Performance,
-                format!("High processing latency: {:.1}ms", metrics.radar.processing_latency_ms),
-                "Radar".to_string(),
-            ).await?;
-        }
-        
-        // Check safety alerts
-        if matches!(metrics.safety.temperature_status, TemperatureStatus::Critical) {
-            self.create_alert(
-                AlertSeverity::Emergency,
-                AlertCategory::Safety,
-                "Critical temperature detected".to_string(),
-                "Temperature".to_string(),
-            ).await?;
-        }
-        
-        // Check error rate alerts
-        if metrics.errors.error_rate_per_minute > 10.0 {
-            self.create_alert(
-                AlertSeverity::Warning,
-                AlertCategory::System,
-                format!("High error rate: {:.1} errors/min", metrics.errors.error_rate_per_minute),
-                "System".to_string(),
-            ).await?;
-        }
-        
-        Ok(())
-    }
-}
+use crate::config::MonitoringConfig;
+use crate::error::HexarResult;
+use crate::fall_alert::{FallAction, FallAlert, FallAlertKind};
+use crate::health::HealthReport;
+use crate::host_metrics::{HostMetrics, HostSampler};
+use crate::metrics::ScanMetrics;
+use crate::occupancy::ScanStatisticsReport;
+use crate::radar_controller::RadarEvent;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tracing::{info, warn, error, debug};
+use chrono::Utc;
+use uuid::Uuid;
+
+/// Most alerts kept. Resolved ones are dropped first, then the least severe open ones.
+const MAX_ALERTS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemMetrics {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub system_id: Uuid,
+    pub performance: PerformanceMetrics,
+    pub radar: RadarMetrics,
+    pub safety: SafetyMetrics,
+    pub errors: ErrorMetrics,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceMetrics {
+    pub cpu_usage_percent: f32,
+    pub memory_usage_percent: f32,
+    pub disk_usage_percent: f32,
+    pub network_io_bytes_per_second: u64,
+    pub uptime_seconds: u64,
+    pub load_average: [f32; 3],
+    /// Resident memory of the hexar process
+    #[serde(default)]
+    pub process_memory_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RadarMetrics {
+    pub scan_rate_hz: f32,
+    pub targets_tracked: usize,
+    pub signal_quality_db: f32,
+    pub noise_floor_db: f32,
+    pub antenna_status: Vec<AntennaMetrics>,
+    pub processing_latency_ms: f32,
+    /// Latest channel occupancy report from the scanner
+    #[serde(default)]
+    pub occupancy: Option<ScanStatisticsReport>,
+    /// Scan cycle counters and histograms from the controller
+    #[serde(default)]
+    pub scan_cycles: Option<ScanMetrics>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AntennaMetrics {
+    pub id: u8,
+    pub connected: bool,
+    pub temperature_celsius: f32,
+    pub power_watts: f32,
+    pub signal_strength_db: f32,
+    pub error_count: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyMetrics {
+    pub emergency_stop_active: bool,
+    pub temperature_status: TemperatureStatus,
+    pub power_status: PowerStatus,
+    pub last_safety_check: chrono::DateTime<chrono::Utc>,
+    pub safety_score: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum TemperatureStatus {
+    Normal,
+    Warning,
+    Critical,
+    Emergency,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PowerStatus {
+    Normal,
+    Warning,
+    Critical,
+    Backup,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorMetrics {
+    pub total_errors: u64,
+    pub error_rate_per_minute: f32,
+    pub recent_errors: Vec<ErrorEntry>,
+    pub critical_errors: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorEntry {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub severity: ErrorSeverity,
+    pub component: String,
+    pub message: String,
+    pub error_id: Uuid,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ErrorSeverity {
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+pub struct MonitoringSystem {
+    config: MonitoringConfig,
+    system_id: Uuid,
+    start_time: Instant,
+    metrics_history: Vec<SystemMetrics>,
+    error_log: Vec<ErrorEntry>,
+    alerts: Vec<Alert>,
+    scan_statistics: Option<ScanStatisticsReport>,
+    scan_metrics: Option<ScanMetrics>,
+    health: Option<HealthReport>,
+    /// `None` with performance tracking off
+    host: Option<HostSampler>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: Uuid,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub severity: AlertSeverity,
+    pub category: AlertCategory,
+    pub message: String,
+    pub component: String,
+    pub acknowledged: bool,
+    pub resolved: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+    Emergency,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum AlertCategory {
+    System,
+    Performance,
+    Safety,
+    Hardware,
+    Software,
+    Network,
+}
+
+impl MonitoringSystem {
+    pub fn new(config: MonitoringConfig) -> HexarResult<Self> {
+        let host = config.performance_tracking.then(HostSampler::new);
+        Ok(Self {
+            config,
+            system_id: Uuid::new_v4(),
+            start_time: Instant::now(),
+            metrics_history: Vec::new(),
+            error_log: Vec::new(),
+            alerts: Vec::new(),
+            scan_statistics: None,
+            scan_metrics: None,
+            health: None,
+            host,
+        })
+    }
+    
+    pub async fn collect_metrics(&mut self) -> Result<SystemMetrics> {
+        debug!("Collecting system metrics...");
+        
+        let performance = self.collect_performance_metrics().await?;
+        let radar = self.collect_radar_metrics().await?;
+        let safety = self.collect_safety_metrics().await?;
+        let errors = self.collect_error_metrics().await?;
+        
+        let metrics = SystemMetrics {
+            timestamp: Utc::now(),
+            system_id: self.system_id,
+            performance,
+            radar,
+            safety,
+            errors,
+        };
+        
+        // Store metrics (with retention limit)
+        self.metrics_history.push(metrics.clone());
+        
+        let max_history = (self.config.data_retention_days * 24 * 60 * 60) / 
+            self.config.health_check_interval_seconds;
+        
+        if self.metrics_history.len() > max_history as usize {
+            self.metrics_history.remove(0);
+        }
+        
+        // Check for alerts
+        self.check_alert_conditions(&metrics).await?;
+        
+        Ok(metrics)
+    }
+    
+    pub async fn log_error(&mut self, component: &str, message: &str, severity: ErrorSeverity) -> Result<()> {
+        let entry = ErrorEntry {
+            timestamp: Utc::now(),
+            severity,
+            component: component.to_string(),
+            message: message.to_string(),
+            error_id: Uuid::new_v4(),
+        };
+        
+        self.error_log.push(entry.clone());
+        
+        // Keep error log manageable
+        if self.error_log.len() > 10000 {
+            self.error_log.remove(0);
+        }
+        
+        // Create alert for critical errors
+        if matches!(severity, ErrorSeverity::Critical) {
+            self.create_alert(
+                AlertSeverity::Critical,
+                AlertCategory::Software,
+                format!("Critical error in {}: {}", component, message),
+                component.to_string(),
+            ).await?;
+        }
+        
+        match severity {
+            ErrorSeverity::Info => debug!("[{}] {}", component, message),
+            ErrorSeverity::Warning => warn!("[{}] {}", component, message),
+            ErrorSeverity::Error => error!("[{}] {}", component, message),
+            ErrorSeverity::Critical => error!("[CRITICAL] {}: {}", component, message),
+        }
+        
+        Ok(())
+    }
+    
+    pub async fn create_alert(&mut self, severity: AlertSeverity, category: AlertCategory, 
+                             message: String, component: String) -> Result<()> {
+        let alert = Alert {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            severity,
+            category,
+            message: message.clone(),
+            component,
+            acknowledged: false,
+            resolved: false,
+        };
+        
+        self.alerts.push(alert.clone());
+        
+        // Keep alert list manageable
+        if self.alerts.len() > MAX_ALERTS {
+            // The oldest resolved alert, else the oldest of the least severe open ones, which is
+            // this one when all others are more severe
+            let evicted = self.alerts.iter().position(|a| a.resolved).or_else(|| {
+                self.alerts.iter().enumerate().min_by_key(|(_, a)| a.severity).map(|(i, _)| i)
+            });
+            if let Some(evicted) = evicted {
+                self.alerts.remove(evicted);
+            }
+        }
+        
+        // Log alert
+        match severity {
+            AlertSeverity::Info => info!("ALERT: {}", message),
+            AlertSeverity::Warning => warn!("ALERT: {}", message),
+            AlertSeverity::Critical => error!("CRITICAL ALERT: {}", message),
+            AlertSeverity::Emergency => error!("EMERGENCY ALERT: {}", message),
+        }
+        
+        // TODO: Implement alert notifications (email, SMS, etc.)
+        
+        Ok(())
+    }
+    
+    /// Raise and resolve alerts for what the radar controller reports
+    pub async fn handle_radar_event(&mut self, event: &RadarEvent) -> Result<()> {
+        match event {
+            RadarEvent::AntennaDisconnected { antenna_id, error } => {
+                self.create_alert(
+                    AlertSeverity::Warning,
+                    AlertCategory::Hardware,
+                    format!("Antenna {} disconnected: {}", antenna_id, error),
+                    format!("antenna:{}", antenna_id),
+                ).await?;
+            },
+            RadarEvent::AntennaReconnected { antenna_id } => {
+                let component = format!("antenna:{}", antenna_id);
+                for alert in self.alerts.iter_mut().filter(|a| a.component == component) {
+                    alert.resolved = true;
+                }
+                info!("Antenna {} reconnected", antenna_id);
+            },
+            RadarEvent::FallDetected(event) => {
+                self.handle_fall(&FallAlert::from_event(event)).await?;
+            },
+            RadarEvent::DegradedModeChanged { failed_antennas, coverage } => {
+                for alert in self.alerts.iter_mut().filter(|a| a.component == "coverage") {
+                    alert.resolved = true;
+                }
+                if !failed_antennas.is_empty() {
+                    self.create_alert(
+                        AlertSeverity::Warning,
+                        AlertCategory::Hardware,
+                        format!("Running without antennas {:?}, {:.0}% coverage left",
+                                failed_antennas, coverage * 100.0),
+                        "coverage".to_string(),
+                    ).await?;
+                }
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+    
+    /// Raise the configured alerts for a fall, or resolve them once the person got up
+    async fn handle_fall(&mut self, fall: &FallAlert) -> Result<()> {
+        let component = fall.component();
+        if fall.kind == FallAlertKind::Cleared {
+            for alert in self.alerts.iter_mut().filter(|a| a.component == component) {
+                alert.resolved = true;
+            }
+            info!("Target {} recovered from its fall", fall.target_id);
+            return Ok(());
+        }
+        if !self.config.fall_alerts.triggers(fall) {
+            return Ok(());
+        }
+        let severities: Vec<AlertSeverity> = self.config.fall_alerts.actions
+            .iter()
+            .filter_map(|action| match action {
+                FallAction::Alert { severity } => Some(*severity),
+                _ => None,
+            })
+            .collect();
+        let position = fall.position
+            .map(|p| format!(" at ({:.1}, {:.1})", p.x, p.y))
+            .unwrap_or_default();
+        for severity in severities {
+            self.create_alert(
+                severity,
+                AlertCategory::Safety,
+                format!("Fall of target {} {:?}{}", fall.target_id, fall.kind, position),
+                component.clone(),
+            ).await?;
+        }
+        Ok(())
+    }
+    
+    /// Occupancy report included in the radar metrics from now on
+    pub fn update_scan_statistics(&mut self, report: ScanStatisticsReport) {
+        self.scan_statistics = Some(report);
+    }
+
+    pub fn get_scan_statistics(&self) -> Option<&ScanStatisticsReport> {
+        self.scan_statistics.as_ref()
+    }
+    
+    /// Scan cycle metrics included in the radar metrics from now on
+    pub fn update_scan_metrics(&mut self, metrics: ScanMetrics) {
+        self.scan_metrics = Some(metrics);
+    }
+
+    pub fn get_config(&self) -> &MonitoringConfig {
+        &self.config
+    }
+    
+    /// Controller health the radar metrics are taken from from now on
+    pub fn update_health(&mut self, report: HealthReport) {
+        self.health = Some(report);
+    }
+    
+    pub fn get_health(&self) -> Option<&HealthReport> {
+        self.health.as_ref()
+    }
+
+    pub fn get_metrics_history(&self, duration: Duration) -> Vec<&SystemMetrics> {
+        let cutoff = Utc::now() - chrono::Duration::from_std(duration).unwrap_or_default();
+        
+        self.metrics_history
+            .iter()
+            .filter(|m| m.timestamp > cutoff)
+            .collect()
+    }
+    
+    /// Errors logged so far, as the system metrics report them
+    pub fn get_error_metrics(&self) -> ErrorMetrics {
+        let recent_cutoff = Utc::now() - chrono::Duration::minutes(5);
+        let recent_errors: Vec<_> = self.error_log
+            .iter()
+            .filter(|e| e.timestamp > recent_cutoff)
+            .cloned()
+            .collect();
+        
+        let error_rate = recent_errors.len() as f32 / 5.0; // errors per minute
+        
+        ErrorMetrics {
+            total_errors: self.error_log.len() as u64,
+            error_rate_per_minute: error_rate,
+            recent_errors,
+            critical_errors: self.error_log.iter()
+                .filter(|e| matches!(e.severity, ErrorSeverity::Critical))
+                .count() as u32,
+        }
+    }
+    
+    pub fn get_active_alerts(&self) -> Vec<&Alert> {
+        self.alerts
+            .iter()
+            .filter(|a| !a.resolved)
+            .collect()
+    }
+    
+    pub fn acknowledge_alert(&mut self, alert_id: Uuid) -> Result<bool> {
+        if let Some(alert) = self.alerts.iter_mut().find(|a| a.id == alert_id) {
+            alert.acknowledged = true;
+            info!("Alert {} acknowledged", alert_id);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+    
+    pub fn resolve_alert(&mut self, alert_id: Uuid) -> Result<bool> {
+        if let Some(alert) = self.alerts.iter_mut().find(|a| a.id == alert_id) {
+            alert.resolved = true;
+            info!("Alert {} resolved", alert_id);
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+    
+    // Private helper methods
+    async fn collect_performance_metrics(&mut self) -> Result<PerformanceMetrics> {
+        let uptime = self.start_time.elapsed();
+        // Sampling reads the process table and the disks, off the runtime's threads
+        let host = match self.host.take() {
+            Some(mut sampler) => {
+                let (sampler, host) = tokio::task::spawn_blocking(move || {
+                    let host = sampler.sample();
+                    (sampler, host)
+                }).await?;
+                self.host = Some(sampler);
+                host
+            },
+            None => HostMetrics::default(),
+        };
+        let HostMetrics { cpu_usage_percent, memory_usage_percent, disk_usage_percent, network_io_bytes_per_second, load_average, process_memory_bytes } = host;
+        
+        Ok(PerformanceMetrics {
+            cpu_usage_percent,
+            memory_usage_percent,
+            disk_usage_percent,
+            network_io_bytes_per_second,
+            uptime_seconds: uptime.as_secs(),
+            load_average,
+            process_memory_bytes,
+        })
+    }
+    
+    async fn collect_radar_metrics(&self) -> Result<RadarMetrics> {
+        // TODO: Collect antenna temperature, power and signal strength
+        
+        let antenna_metrics = match &self.health {
+            Some(health) => health.antennas.iter().map(|antenna| AntennaMetrics {
+                id: antenna.antenna_id,
+                connected: antenna.connected,
+                temperature_celsius: 25.0,
+                power_watts: 5.0,
+                signal_strength_db: -30.0,
+                error_count: antenna.malformed_frames,
+            }).collect(),
+            None => (0..6).map(|i| AntennaMetrics {
+                id: i,
+                connected: true,
+                temperature_celsius: 25.0 + (i as f32 * 0.5),
+                power_watts: 5.0 + (i as f32 * 0.2),
+                signal_strength_db: -30.0 - (i as f32 * 2.0),
+                error_count: 0,
+            }).collect(),
+        };
+        
+        let processing_latency_ms = self.scan_metrics
+            .as_ref()
+            .and_then(|metrics| metrics.cycle_duration_ms.mean())
+            .map_or(15.7, |ms| ms as f32);
+        
+        Ok(RadarMetrics {
+            scan_rate_hz: self.health.as_ref().map_or(10.5, |health| health.scan_rate_hz),
+            targets_tracked: self.health.as_ref().map_or(3, |health| health.tracker.targets),
+            signal_quality_db: -25.3,
+            noise_floor_db: -85.2,
+            antenna_status: antenna_metrics,
+            processing_latency_ms,
+            occupancy: self.scan_statistics.clone(),
+            scan_cycles: self.scan_metrics.clone(),
+        })
+    }
+    
+    async fn collect_safety_metrics(&self) -> Result<SafetyMetrics> {
+        // TODO: Implement actual safety metrics collection
+        
+        Ok(SafetyMetrics {
+            emergency_stop_active: false,
+            temperature_status: TemperatureStatus::Normal,
+            power_status: PowerStatus::Normal,
+            last_safety_check: Utc::now(),
+            safety_score: 0.95,
+        })
+    }
+    
+    async fn collect_error_metrics(&self) -> Result<ErrorMetrics> {
+        Ok(self.get_error_metrics())
+    }
+    
+    async fn check_alert_conditions(&mut self, metrics: &SystemMetrics) -> Result<()> {
+        let performance = &metrics.performance;
+        
+        // Check performance alerts
+        self.update_alert(
+            "CPU",
+            performance.cpu_usage_percent > self.config.cpu_alert_percent,
+            AlertSeverity::Warning,
+            AlertCategory::Performance,
+            || format!("High CPU usage: {:.1}%", performance.cpu_usage_percent),
+        ).await?;
+        
+        self.update_alert(
+            "Memory",
+            performance.memory_usage_percent > self.config.memory_alert_percent,
+            AlertSeverity::Critical,
+            AlertCategory::Performance,
+            || format!("High memory usage: {:.1}%", performance.memory_usage_percent),
+        ).await?;
+        
+        self.update_alert(
+            "Disk",
+            performance.disk_usage_percent > self.config.disk_alert_percent,
+            AlertSeverity::Warning,
+            AlertCategory::Performance,
+            || format!("Disk almost full: {:.1}%", performance.disk_usage_percent),
+        ).await?;
+        
+        // Check radar alerts
+        self.update_alert(
+            "Radar",
+            metrics.radar.processing_latency_ms > 100.0,
+            AlertSeverity::Warning,
+            AlertCategory::Performance,
+            || format!("High processing latency: {:.1}ms", metrics.radar.processing_latency_ms),
+        ).await?;
+        
+        // Check safety alerts
+        self.update_alert(
+            "Temperature",
+            matches!(metrics.safety.temperature_status, TemperatureStatus::Critical),
+            AlertSeverity::Emergency,
+            AlertCategory::Safety,
+            || "Critical temperature detected".to_string(),
+        ).await?;
+        
+        // Check error rate alerts
+        self.update_alert(
+            "System",
+            metrics.errors.error_rate_per_minute > 10.0,
+            AlertSeverity::Warning,
+            AlertCategory::System,
+            || format!("High error rate: {:.1} errors/min", metrics.errors.error_rate_per_minute),
+        ).await?;
+        
+        Ok(())
+    }
+    
+    /// Raise an alert for `component` while `exceeded` unless one is open already, and resolve
+    /// the open one once the condition is gone
+    async fn update_alert(&mut self, component: &str, exceeded: bool, severity: AlertSeverity,
+                          category: AlertCategory, message: impl FnOnce() -> String) -> Result<()> {
+        let mut open = self.alerts.iter_mut()
+            .filter(|a| a.component == component && !a.resolved)
+            .peekable();
+        if exceeded {
+            if open.peek().is_none() {
+                self.create_alert(severity, category, message(), component.to_string()).await?;
+            }
+        } else if open.peek().is_some() {
+            for alert in open {
+                alert.resolved = true;
+            }
+            info!("{} back to normal, alert resolved", component);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn open_alerts<'a>(monitoring: &'a MonitoringSystem, component: &str) -> Vec<&'a Alert> {
+        monitoring.alerts.iter().filter(|a| a.component == component && !a.resolved).collect()
+    }
+    
+    async fn raise(monitoring: &mut MonitoringSystem, severity: AlertSeverity, component: &str) {
+        monitoring.create_alert(severity, AlertCategory::System, component.to_string(), component.to_string()).await.unwrap();
+    }
+    
+    #[tokio::test]
+    async fn test_alert_raised_once_and_resolved() {
+        let mut monitoring = MonitoringSystem::new(MonitoringConfig::default()).unwrap();
+        let threshold = monitoring.config.disk_alert_percent;
+        for usage in [threshold + 1.0, threshold + 5.0, threshold + 2.0] {
+            monitoring.update_alert("Disk", usage > threshold, AlertSeverity::Warning,
+                                    AlertCategory::Performance, || format!("Disk almost full: {:.1}%", usage)).await.unwrap();
+        }
+        assert_eq!(monitoring.alerts.len(), 1);
+        assert_eq!(open_alerts(&monitoring, "Disk").len(), 1);
+        
+        monitoring.update_alert("Disk", false, AlertSeverity::Warning, AlertCategory::Performance,
+                                || unreachable!()).await.unwrap();
+        assert!(open_alerts(&monitoring, "Disk").is_empty());
+        assert!(monitoring.alerts[0].resolved);
+        
+        // Exceeded again, a new alert
+        monitoring.update_alert("Disk", true, AlertSeverity::Warning, AlertCategory::Performance,
+                                || "Disk almost full".to_string()).await.unwrap();
+        assert_eq!(monitoring.alerts.len(), 2);
+        assert_eq!(open_alerts(&monitoring, "Disk").len(), 1);
+    }
+    
+    #[tokio::test]
+    async fn test_alert_list_bounded() {
+        let mut monitoring = MonitoringSystem::new(MonitoringConfig::default()).unwrap();
+        raise(&mut monitoring, AlertSeverity::Emergency, "Temperature").await;
+        for i in 0..MAX_ALERTS {
+            raise(&mut monitoring, AlertSeverity::Warning, &format!("warning {}", i)).await;
+            if i < 10 {
+                monitoring.alerts.last_mut().unwrap().resolved = true;
+            }
+        }
+        assert_eq!(monitoring.alerts.len(), MAX_ALERTS);
+        // The oldest resolved alert went first
+        assert_eq!(monitoring.alerts.iter().filter(|a| a.resolved).count(), 9);
+        assert_eq!(monitoring.alerts[1].component, "warning 1");
+        
+        // With none resolved left, the oldest warnings make room for more severe alerts
+        for i in 0..20 {
+            raise(&mut monitoring, AlertSeverity::Critical, &format!("critical {}", i)).await;
+        }
+        assert_eq!(monitoring.alerts.len(), MAX_ALERTS);
+        assert!(monitoring.alerts.iter().all(|a| !a.resolved));
+        assert_eq!(monitoring.alerts[1].component, "warning 21");
+        assert_eq!(monitoring.alerts.iter().filter(|a| a.severity == AlertSeverity::Critical).count(), 20);
+        
+        // An info alert is less severe than every open one and is the one dropped
+        raise(&mut monitoring, AlertSeverity::Info, "info").await;
+        assert_eq!(monitoring.alerts.len(), MAX_ALERTS);
+        assert!(monitoring.alerts.iter().all(|a| a.severity != AlertSeverity::Info));
+        assert_eq!(open_alerts(&monitoring, "Temperature").len(), 1);
+    }
+}
@@ -0,0 +1,476 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+use rumqttc::{AsyncClient, Event, LastWill, MqttOptions, Packet, QoS};
+use serde_json::json;
+use tokio::sync::{broadcast, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::config::MqttConfig;
+use crate::fall_alert::{FallAlert, FallAlertKind};
+use crate::radar_controller::RadarEvent;
+
+/// Publications queued for the connection, more are dropped while the broker is unreachable
+const QUEUE_CAPACITY: usize = 64;
+
+/// Wait between attempts to reach the broker
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Time the last publications get to reach the broker on shutdown
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A message for the broker
+#[derive(Debug, Clone, PartialEq)]
+pub struct Publication {
+    pub topic: String,
+    pub payload: String,
+    /// Kept by the broker for clients subscribing later
+    pub retain: bool,
+}
+
+impl Publication {
+    fn retained(topic: String, payload: impl Into<String>) -> Self {
+        Self {
+            topic,
+            payload: payload.into(),
+            retain: true,
+        }
+    }
+}
+
+/// What was published under the base topic, turning radar events into the publications that
+/// change it:
+///
+/// - `<base>/availability`, `online` or `offline`
+/// - `<base>/presence`, `ON` while any confirmed target is in range, and `<base>/targets`
+/// - `<base>/zone/<zone>/occupancy` and `<base>/zone/<zone>/targets` for each zone
+/// - `<base>/fall`, `ON` while a fall is suspected or confirmed, with the latest
+///   [`FallAlert`] as JSON on `<base>/fall/alert`
+///
+/// All retained except the fall alerts.
+#[derive(Debug)]
+pub struct TopicState {
+    node_id: String,
+    base: String,
+    /// `None` without Home Assistant discovery
+    discovery_prefix: Option<String>,
+    /// Confirmed targets of the cycle in progress
+    cycle: BTreeSet<u32>,
+    targets: Option<usize>,
+    /// Occupants of each zone seen so far
+    zones: BTreeMap<String, usize>,
+    fallen: BTreeSet<u32>,
+}
+
+impl TopicState {
+    /// State of the instance `system_id` with the configured `zones`, all empty
+    pub fn new(config: &MqttConfig, system_id: Uuid, zones: &[String]) -> Self {
+        let node_id = format!("hexar_{}", &system_id.simple().to_string()[..8]);
+        Self {
+            base: format!("{}/{}", config.topic_prefix, node_id),
+            node_id,
+            discovery_prefix: config.discovery.then(|| config.discovery_prefix.clone()),
+            cycle: BTreeSet::new(),
+            targets: None,
+            zones: zones.iter().map(|zone| (zone.clone(), 0)).collect(),
+            fallen: BTreeSet::new(),
+        }
+    }
+
+    pub fn availability_topic(&self) -> String {
+        format!("{}/availability", self.base)
+    }
+
+    /// Everything a broker needs after connecting: the discovery configs, the availability
+    /// and the current states
+    pub fn on_connect(&self) -> Vec<Publication> {
+        let mut publications = self.discovery();
+        for zone in self.zones.keys() {
+            publications.extend(self.zone_discovery(zone));
+        }
+        publications.push(Publication::retained(self.availability_topic(), "online"));
+        if let Some(targets) = self.targets {
+            publications.extend(self.target_states(targets));
+        }
+        for (zone, occupants) in &self.zones {
+            publications.extend(self.zone_states(zone, *occupants));
+        }
+        publications.push(self.fall_state());
+        publications
+    }
+
+    /// Publications for what `event` changed, if anything
+    pub fn on_event(&mut self, event: &RadarEvent) -> Vec<Publication> {
+        match event {
+            RadarEvent::TargetUpdated(target) => {
+                self.cycle.insert(target.id);
+                Vec::new()
+            }
+            RadarEvent::ScanCompleted { .. } => {
+                let targets = std::mem::take(&mut self.cycle).len();
+                if self.targets == Some(targets) {
+                    return Vec::new();
+                }
+                self.targets = Some(targets);
+                self.target_states(targets)
+            }
+            RadarEvent::ZoneOccupancyChanged {
+                zone, occupants, ..
+            } => {
+                let mut publications = Vec::new();
+                if self.zones.insert(zone.clone(), occupants.len()).is_none() {
+                    // Added at runtime
+                    publications.extend(self.zone_discovery(zone));
+                }
+                publications.extend(self.zone_states(zone, occupants.len()));
+                publications
+            }
+            RadarEvent::FallDetected(event) => {
+                let alert = FallAlert::from_event(event);
+                match alert.kind {
+                    FallAlertKind::Suspected | FallAlertKind::Confirmed => {
+                        self.fallen.insert(alert.target_id)
+                    }
+                    FallAlertKind::Cleared => self.fallen.remove(&alert.target_id),
+                };
+                let mut publications = Vec::new();
+                if let Ok(payload) = serde_json::to_string(&alert) {
+                    publications.push(Publication {
+                        topic: format!("{}/fall/alert", self.base),
+                        payload,
+                        retain: false,
+                    });
+                }
+                publications.push(self.fall_state());
+                publications
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn target_states(&self, targets: usize) -> Vec<Publication> {
+        vec![
+            Publication::retained(format!("{}/presence", self.base), on_off(targets > 0)),
+            Publication::retained(format!("{}/targets", self.base), targets.to_string()),
+        ]
+    }
+
+    fn zone_states(&self, zone: &str, occupants: usize) -> Vec<Publication> {
+        let topic = format!("{}/zone/{}", self.base, slug(zone));
+        vec![
+            Publication::retained(format!("{}/occupancy", topic), on_off(occupants > 0)),
+            Publication::retained(format!("{}/targets", topic), occupants.to_string()),
+        ]
+    }
+
+    fn fall_state(&self) -> Publication {
+        Publication::retained(
+            format!("{}/fall", self.base),
+            on_off(!self.fallen.is_empty()),
+        )
+    }
+
+    /// Home Assistant discovery config of an entity, `None` without discovery
+    fn discovery_config(
+        &self,
+        component: &str,
+        object_id: &str,
+        mut entity: serde_json::Value,
+    ) -> Option<Publication> {
+        let prefix = self.discovery_prefix.as_ref()?;
+        entity["unique_id"] = json!(format!("{}_{}", self.node_id, object_id));
+        entity["availability_topic"] = json!(self.availability_topic());
+        entity["device"] = json!({
+            "identifiers": [self.node_id],
+            "name": format!("Hexar {}", &self.node_id["hexar_".len()..]),
+            "manufacturer": "hexar",
+            "model": "Hexagonal radar array",
+            "sw_version": env!("CARGO_PKG_VERSION"),
+        });
+        Some(Publication::retained(
+            format!(
+                "{}/{}/{}/{}/config",
+                prefix, component, self.node_id, object_id
+            ),
+            entity.to_string(),
+        ))
+    }
+
+    fn discovery(&self) -> Vec<Publication> {
+        [
+            self.discovery_config(
+                "binary_sensor",
+                "presence",
+                json!({
+                    "name": "Presence",
+                    "device_class": "occupancy",
+                    "state_topic": format!("{}/presence", self.base),
+                }),
+            ),
+            self.discovery_config(
+                "sensor",
+                "targets",
+                json!({
+                    "name": "Targets",
+                    "state_class": "measurement",
+                    "state_topic": format!("{}/targets", self.base),
+                }),
+            ),
+            self.discovery_config(
+                "binary_sensor",
+                "fall",
+                json!({
+                    "name": "Fall",
+                    "device_class": "safety",
+                    "state_topic": format!("{}/fall", self.base),
+                    "json_attributes_topic": format!("{}/fall/alert", self.base),
+                }),
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    fn zone_discovery(&self, zone: &str) -> Vec<Publication> {
+        let topic = format!("{}/zone/{}", self.base, slug(zone));
+        [
+            self.discovery_config(
+                "binary_sensor",
+                &format!("zone_{}", slug(zone)),
+                json!({
+                    "name": format!("{} occupancy", zone),
+                    "device_class": "occupancy",
+                    "state_topic": format!("{}/occupancy", topic),
+                }),
+            ),
+            self.discovery_config(
+                "sensor",
+                &format!("zone_{}_targets", slug(zone)),
+                json!({
+                    "name": format!("{} targets", zone),
+                    "state_class": "measurement",
+                    "state_topic": format!("{}/targets", topic),
+                }),
+            ),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+}
+
+fn on_off(on: bool) -> &'static str {
+    if on {
+        "ON"
+    } else {
+        "OFF"
+    }
+}
+
+/// Zone name as it appears in topics and entity IDs, e.g. `living_room` for "Living room"
+fn slug(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Publishes the radar events to an MQTT broker on a task of its own, reconnecting when the
+/// connection fails
+#[derive(Debug)]
+pub struct MqttPublisher {
+    handle: JoinHandle<()>,
+    shutdown: oneshot::Sender<()>,
+}
+
+impl MqttPublisher {
+    pub fn spawn(
+        config: &MqttConfig,
+        system_id: Uuid,
+        zones: &[String],
+        mut events: broadcast::Receiver<RadarEvent>,
+    ) -> Self {
+        let mut state = TopicState::new(config, system_id, zones);
+        let mut options = MqttOptions::new(&state.node_id, &config.host, config.port);
+        options
+            .set_keep_alive(Duration::from_secs(config.keep_alive_secs))
+            .set_last_will(LastWill::new(
+                state.availability_topic(),
+                "offline",
+                QoS::AtLeastOnce,
+                true,
+            ));
+        if let Some(username) = &config.username {
+            options.set_credentials(username, config.password.clone().unwrap_or_default());
+        }
+        let (client, mut connection) = AsyncClient::new(options, QUEUE_CAPACITY);
+        let (shutdown, mut stopping) = oneshot::channel();
+        let broker = format!("{}:{}", config.host, config.port);
+
+        let handle = tokio::spawn(async move {
+            // Set while waiting to reconnect
+            let mut retry_at: Option<Instant> = None;
+            // Whether the current outage was reported
+            let mut reported = false;
+            loop {
+                tokio::select! {
+                    notification = connection.poll(), if retry_at.is_none() => match notification {
+                        Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                            info!("Connected to MQTT broker {}", broker);
+                            reported = false;
+                            publish(&client, state.on_connect());
+                        }
+                        Ok(_) => {}
+                        Err(e) => {
+                            if reported {
+                                debug!("MQTT broker {} still unreachable: {}", broker, e);
+                            } else {
+                                warn!("MQTT broker {} unreachable: {}", broker, e);
+                                reported = true;
+                            }
+                            retry_at = Some(Instant::now() + RECONNECT_DELAY);
+                        }
+                    },
+                    _ = tokio::time::sleep_until(retry_at.unwrap_or_else(Instant::now)), if retry_at.is_some() => {
+                        retry_at = None;
+                    },
+                    event = events.recv() => match event {
+                        Ok(event) => publish(&client, state.on_event(&event)),
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            warn!("MQTT publisher missed {} radar events", missed);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    _ = &mut stopping => break,
+                }
+            }
+            // Offline right away rather than once the broker misses the keep-alive
+            let offline = Publication::retained(state.availability_topic(), "offline");
+            publish(&client, vec![offline]);
+            let _ = client.try_disconnect();
+            let flush = async { while connection.poll().await.is_ok() {} };
+            let _ = tokio::time::timeout(DISCONNECT_TIMEOUT, flush).await;
+            debug!("MQTT publisher stopped");
+        });
+        Self { handle, shutdown }
+    }
+
+    /// Mark the instance offline and disconnect
+    pub async fn stop(self) {
+        let _ = self.shutdown.send(());
+        let _ = self.handle.await;
+    }
+}
+
+fn publish(client: &AsyncClient, publications: Vec<Publication>) {
+    for publication in publications {
+        let Publication {
+            topic,
+            payload,
+            retain,
+        } = publication;
+        if let Err(e) = client.try_publish(&topic, QoS::AtLeastOnce, retain, payload) {
+            debug!("Dropped MQTT publication to {}: {}", topic, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracker::{FallEvent, TrackedTarget};
+    use nalgebra::Vector2;
+
+    fn payload<'a>(publications: &'a [Publication], topic: &str) -> Option<&'a str> {
+        publications
+            .iter()
+            .find(|publication| publication.topic.ends_with(topic))
+            .map(|publication| publication.payload.as_str())
+    }
+
+    #[test]
+    fn test_topic_state() {
+        let system_id = Uuid::parse_str("1a2b3c4d-0000-4000-8000-000000000000").unwrap();
+        let mut state = TopicState::new(&MqttConfig::default(), system_id, &["Bed".to_string()]);
+        let connect = state.on_connect();
+        let presence = connect
+            .iter()
+            .find(|publication| {
+                publication.topic == "homeassistant/binary_sensor/hexar_1a2b3c4d/presence/config"
+            })
+            .unwrap();
+        let config: serde_json::Value = serde_json::from_str(&presence.payload).unwrap();
+        assert_eq!(config["state_topic"], "hexar/hexar_1a2b3c4d/presence");
+        assert_eq!(config["device"]["identifiers"][0], "hexar_1a2b3c4d");
+        assert!(payload(&connect, "zone_bed/config").is_some());
+        assert_eq!(payload(&connect, "/zone/bed/occupancy"), Some("OFF"));
+        assert_eq!(payload(&connect, "/availability"), Some("online"));
+
+        let completed = RadarEvent::ScanCompleted {
+            scan_id: Uuid::new_v4(),
+            timestamp: chrono::Utc::now(),
+            scan_duration: Duration::from_millis(5),
+            signals_processed: 0,
+            targets_detected: 1,
+        };
+        let target = TrackedTarget::new(3, 0, Vector2::new(1.0, 1.0));
+        assert!(state
+            .on_event(&RadarEvent::TargetUpdated(target.clone()))
+            .is_empty());
+        let published = state.on_event(&completed);
+        assert_eq!(payload(&published, "/presence"), Some("ON"));
+        assert_eq!(payload(&published, "/targets"), Some("1"));
+        // Unchanged
+        state.on_event(&RadarEvent::TargetUpdated(target));
+        assert!(state.on_event(&completed).is_empty());
+
+        // A zone added at runtime is announced before its state
+        let published = state.on_event(&RadarEvent::ZoneOccupancyChanged {
+            zone: "Living room".to_string(),
+            entered: vec![3],
+            left: Vec::new(),
+            occupants: vec![3],
+        });
+        assert!(published[0].topic.ends_with("zone_living_room/config"));
+        assert_eq!(
+            payload(&published, "/zone/living_room/occupancy"),
+            Some("ON")
+        );
+
+        let suspected = RadarEvent::FallDetected(FallEvent::FallSuspected {
+            target_id: 3,
+            probability: 0.8,
+            position: Vector2::new(1.0, 1.0),
+            landing: None,
+            at: std::time::Instant::now(),
+        });
+        assert_eq!(payload(&state.on_event(&suspected), "/fall"), Some("ON"));
+        let fall = RadarEvent::FallDetected(FallEvent::FallCleared {
+            target_id: 3,
+            at: std::time::Instant::now(),
+        });
+        let published = state.on_event(&fall);
+        let alert = published.iter().find(|p| p.topic.ends_with("/fall/alert"));
+        assert!(!alert.unwrap().retain);
+        assert_eq!(payload(&published, "/fall"), Some("OFF"));
+
+        let config = MqttConfig {
+            discovery: false,
+            ..MqttConfig::default()
+        };
+        let state = TopicState::new(&config, system_id, &["Bed".to_string()]);
+        assert!(state
+            .on_connect()
+            .iter()
+            .all(|publication| !publication.topic.starts_with("homeassistant/")));
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::VecDeque;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::bands::Channel;
+use crate::scanner::SignalReading;
+
+/// Busiest hours listed per channel in a report
+const BUSIEST_HOURS: usize = 3;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    time: SystemTime,
+    busy: bool,
+    strength: f32,
+}
+
+/// Occupancy of one channel over the analyzer's window
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChannelOccupancy {
+    pub number: u32,
+    /// Center frequency in MHz
+    pub center: f32,
+    pub samples: usize,
+    /// Fraction of samples above the threshold
+    pub duty_cycle: f32,
+    pub mean_strength: f32,
+    pub peak_strength: f32,
+    /// UTC hours of day with the highest duty cycle, busiest first
+    pub busiest_hours: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScanStatisticsReport {
+    pub generated_at: SystemTime,
+    pub window: Duration,
+    pub threshold: f32,
+    pub channels: Vec<ChannelOccupancy>,
+}
+
+impl ScanStatisticsReport {
+    /// Channel with the highest duty cycle
+    pub fn busiest_channel(&self) -> Option<&ChannelOccupancy> {
+        self.channels
+            .iter()
+            .filter(|channel| channel.samples > 0)
+            .max_by(|a, b| a.duty_cycle.total_cmp(&b.duty_cycle))
+    }
+}
+
+/// Per-channel duty cycle statistics over a sliding window of readings. Duty cycle counts
+/// samples, so it equals the fraction of time a channel is busy when it is scanned at a steady
+/// rate.
+#[derive(Debug, Clone)]
+pub struct OccupancyAnalyzer {
+    channels: Vec<Channel>,
+    threshold: f32,
+    window: Duration,
+    samples: Vec<VecDeque<Sample>>,
+}
+
+impl OccupancyAnalyzer {
+    /// A channel counts as busy while readings in it exceed `threshold` dB
+    pub fn new(channels: Vec<Channel>, threshold: f32, window: Duration) -> Self {
+        Self {
+            samples: vec![VecDeque::new(); channels.len()],
+            channels,
+            threshold,
+            window,
+        }
+    }
+
+    pub fn observe(&mut self, reading: &SignalReading) {
+        // Readings carry a monotonic timestamp, map it onto the wall clock for hour of day
+        let time = SystemTime::now()
+            .checked_sub(reading.timestamp.elapsed())
+            .unwrap_or_else(SystemTime::now);
+        self.observe_at(time, reading.frequency, reading.strength);
+    }
+
+    /// Add a measurement taken at `time`, ignored when it falls outside every channel
+    pub fn observe_at(&mut self, time: SystemTime, frequency: f32, strength: f32) {
+        let Some(channel) = self
+            .channels
+            .iter()
+            .enumerate()
+            .filter(|(_, channel)| channel.contains(frequency))
+            .min_by(|(_, a), (_, b)| {
+                (a.center - frequency)
+                    .abs()
+                    .total_cmp(&(b.center - frequency).abs())
+            })
+            .map(|(index, _)| index)
+        else {
+            return;
+        };
+
+        let samples = &mut self.samples[channel];
+        samples.push_back(Sample {
+            time,
+            busy: strength > self.threshold,
+            strength,
+        });
+
+        let cutoff = time.checked_sub(self.window).unwrap_or(UNIX_EPOCH);
+        while samples.front().is_some_and(|sample| sample.time < cutoff) {
+            samples.pop_front();
+        }
+    }
+
+    pub fn report(&self) -> ScanStatisticsReport {
+        let generated_at = SystemTime::now();
+        let cutoff = generated_at.checked_sub(self.window).unwrap_or(UNIX_EPOCH);
+
+        let channels = self
+            .channels
+            .iter()
+            .zip(&self.samples)
+            .map(|(channel, samples)| {
+                let recent: Vec<_> = samples.iter().filter(|s| s.time >= cutoff).collect();
+                occupancy(channel, &recent)
+            })
+            .collect();
+
+        ScanStatisticsReport {
+            generated_at,
+            window: self.window,
+            threshold: self.threshold,
+            channels,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.iter_mut().for_each(VecDeque::clear);
+    }
+}
+
+fn occupancy(channel: &Channel, samples: &[&Sample]) -> ChannelOccupancy {
+    let count = samples.len();
+    let busy = samples.iter().filter(|s| s.busy).count();
+
+    // (busy, total) per UTC hour of day
+    let mut hours = [(0usize, 0usize); 24];
+    for sample in samples {
+        let seconds = sample.time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let hour = &mut hours[(seconds / 3600 % 24) as usize];
+        hour.0 += usize::from(sample.busy);
+        hour.1 += 1;
+    }
+
+    let mut busiest: Vec<(u8, f32)> = hours
+        .iter()
+        .enumerate()
+        .filter(|(_, (busy, _))| *busy > 0)
+        .map(|(hour, (busy, total))| (hour as u8, *busy as f32 / *total as f32))
+        .collect();
+    busiest.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    ChannelOccupancy {
+        number: channel.number,
+        center: channel.center,
+        samples: count,
+        duty_cycle: if count > 0 { busy as f32 / count as f32 } else { 0.0 },
+        mean_strength: if count > 0 {
+            samples.iter().map(|s| s.strength).sum::<f32>() / count as f32
+        } else {
+            f32::NEG_INFINITY
+        },
+        peak_strength: samples
+            .iter()
+            .map(|s| s.strength)
+            .fold(f32::NEG_INFINITY, f32::max),
+        busiest_hours: busiest
+            .into_iter()
+            .take(BUSIEST_HOURS)
+            .map(|(hour, _)| hour)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bands::Band;
+
+    #[test]
+    fn test_duty_cycle_and_busiest_hours() {
+        let mut analyzer =
+            OccupancyAnalyzer::new(Band::Wifi24.channels(), -60.0, Duration::from_secs(2 * 86400));
+        // Yesterday 00:00 UTC, so all samples are in the past and inside the window
+        let now = SystemTime::now();
+        let today = now.duration_since(UNIX_EPOCH).unwrap().as_secs() / 86400;
+        let start = UNIX_EPOCH + Duration::from_secs((today - 1) * 86400);
+
+        // Channel 6 busy in 3 of 4 readings at 08:00, 1 of 4 at 10:00, channel 1 always idle
+        for (i, strength) in [-40.0, -40.0, -40.0, -80.0].into_iter().enumerate() {
+            analyzer.observe_at(start + Duration::from_secs(8 * 3600 + i as u64), 2437.0, strength);
+        }
+        for (i, strength) in [-40.0, -80.0, -80.0, -80.0].into_iter().enumerate() {
+            analyzer.observe_at(start + Duration::from_secs(10 * 3600 + i as u64), 2436.0, strength);
+        }
+        analyzer.observe_at(start + Duration::from_secs(9 * 3600), 2412.0, -90.0);
+
+        let report = analyzer.report();
+        let busiest = report.busiest_channel().unwrap();
+        assert_eq!(busiest.number, 6);
+        assert_eq!(busiest.samples, 8);
+        assert_eq!(busiest.duty_cycle, 0.5);
+        assert_eq!(busiest.peak_strength, -40.0);
+        assert_eq!(busiest.busiest_hours, [8, 10]);
+        assert_eq!(report.channels[0].duty_cycle, 0.0);
+        assert!(report.channels[0].busiest_hours.is_empty());
+    }
+}
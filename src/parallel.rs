@@ -0,0 +1,187 @@
+use std::thread;
+
+use log::{debug, warn};
+
+use crate::peaks::Peak;
+use crate::scan_plan::{PlanScan, ScanPlan};
+use crate::scanner::{Dwell, FrequencyRange, FrequencyScanner, SignalReading};
+
+/// Runs stepped sweeps on several scanners at once, one thread per scanner, so a sweep takes
+/// roughly 1/N of the time with N receivers. Every scanner should have its own
+/// [`SignalSource`](crate::signal_source::SignalSource) on its own hardware.
+#[derive(Debug)]
+pub struct ParallelScanner {
+    scanners: Vec<FrequencyScanner>,
+}
+
+/// Part of a sweep assigned to one scanner
+struct Job {
+    range: FrequencyRange,
+    dwell: Option<Dwell>,
+}
+
+impl ParallelScanner {
+    /// The first scanner's threshold settings decide what counts as a peak in merged sweeps
+    pub fn new(scanners: Vec<FrequencyScanner>) -> Self {
+        Self { scanners }
+    }
+
+    pub fn get_scanner_count(&self) -> usize {
+        self.scanners.len()
+    }
+
+    pub fn get_scanners_mut(&mut self) -> &mut [FrequencyScanner] {
+        &mut self.scanners
+    }
+
+    /// Sweep `range` split evenly across all scanners and find the peaks of the merged sweep
+    pub fn sweep(&mut self, range: &FrequencyRange) -> Vec<Peak> {
+        let jobs = split_range(range, self.scanners.len())
+            .into_iter()
+            .map(|range| Job { range, dwell: None })
+            .collect();
+        let sweep = merge(self.run(jobs));
+
+        match self.scanners.first_mut() {
+            Some(scanner) => scanner.peaks_in_sweep(&sweep),
+            None => Vec::new(),
+        }
+    }
+
+    /// Take one scheduled range from `plan` per scanner and sweep them concurrently. A range
+    /// scheduled more than once in a round is split between the scanners it was given to.
+    pub fn scan_plan(&mut self, plan: &mut ScanPlan) -> Vec<PlanScan> {
+        let mut rounds: Vec<(usize, usize)> = Vec::new();
+        for _ in 0..self.scanners.len() {
+            let Some(index) = plan.next_range() else {
+                break;
+            };
+            match rounds.iter_mut().find(|(range, _)| *range == index) {
+                Some((_, scanners)) => *scanners += 1,
+                None => rounds.push((index, 1)),
+            }
+        }
+
+        let planned = plan.get_ranges();
+        let mut jobs = Vec::new();
+        for &(index, scanners) in &rounds {
+            for range in split_range(&planned[index].range, scanners) {
+                jobs.push(Job {
+                    range,
+                    dwell: planned[index].dwell,
+                });
+            }
+        }
+
+        let mut sweeps = self.run(jobs).into_iter();
+        let mut scans = Vec::with_capacity(rounds.len());
+        let mut first = 0;
+        for (index, scanners) in rounds {
+            let sweep = merge(sweeps.by_ref().take(scanners).collect());
+            // The scanner that swept the start of the range judges its peaks
+            let peaks = self.scanners[first].peaks_in_sweep(&sweep);
+            first += scanners;
+            scans.push(PlanScan {
+                range: index,
+                peaks,
+            });
+        }
+
+        scans
+    }
+
+    /// Run one job per scanner in parallel, returning the readings in job order
+    fn run(&mut self, jobs: Vec<Job>) -> Vec<Vec<SignalReading>> {
+        debug!("Parallel sweep: {} jobs", jobs.len());
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .scanners
+                .iter_mut()
+                .zip(jobs)
+                .map(|(scanner, job)| {
+                    scope.spawn(move || {
+                        let range = scanner.set_range(job.range);
+                        let dwell = scanner.get_dwell();
+                        if let Some(job_dwell) = job.dwell {
+                            scanner.set_dwell(job_dwell);
+                        }
+
+                        let sweep = scanner.stepped_sweep();
+
+                        scanner.set_range(range);
+                        scanner.set_dwell(dwell);
+                        sweep
+                    })
+                })
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle.join().unwrap_or_else(|_| {
+                        warn!("Scanner thread panicked, its part of the sweep is missing");
+                        Vec::new()
+                    })
+                })
+                .collect()
+        })
+    }
+}
+
+/// Split `range` into `parts` contiguous ranges on its step grid, fewer if there are more parts
+/// than steps
+pub fn split_range(range: &FrequencyRange, parts: usize) -> Vec<FrequencyRange> {
+    let steps = ((range.end - range.start) / range.step).floor() as usize + 1;
+    let parts = parts.clamp(1, steps);
+
+    (0..parts)
+        .map(|part| {
+            let first = part * steps / parts;
+            let last = (part + 1) * steps / parts - 1;
+            FrequencyRange {
+                start: range.start + first as f32 * range.step,
+                end: range.start + last as f32 * range.step,
+                step: range.step,
+            }
+        })
+        .collect()
+}
+
+fn merge(sweeps: Vec<Vec<SignalReading>>) -> Vec<SignalReading> {
+    let mut merged: Vec<_> = sweeps.into_iter().flatten().collect();
+    merged.sort_by(|a, b| a.frequency.total_cmp(&b.frequency));
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal_source::SimulatedSource;
+
+    #[test]
+    fn test_parallel_sweep_matches_single() {
+        let range = FrequencyRange {
+            start: 400.0,
+            end: 499.0,
+            step: 1.0,
+        };
+        let parts = split_range(&range, 3);
+        assert_eq!(parts.len(), 3);
+        assert_eq!((parts[0].start, parts[0].end), (400.0, 432.0));
+        assert_eq!((parts[2].start, parts[2].end), (466.0, 499.0));
+
+        let scanners = (0..3)
+            .map(|seed| {
+                let source = Box::new(SimulatedSource::new(seed));
+                FrequencyScanner::with_source(range.clone(), -60.0, source)
+            })
+            .collect();
+        let mut parallel = ParallelScanner::new(scanners);
+
+        // 433 MHz is the first step of the second part
+        let peaks = parallel.sweep(&range);
+        assert_eq!(peaks.len(), 1);
+        assert_eq!(peaks[0].center_freq, 433.0);
+    }
+}
@@ -122,6 +122,12 @@ impl ErrorPattern {
     }
 }
 
+impl Default for ErrorParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ErrorParser {
     pub fn new() -> Self {
         let mut parser = Self {
@@ -394,7 +400,7 @@ impl ErrorParser {
         for (error_type, count) in &self.error_counts {
             output.push_str(&format!("- {}: {}\n", error_type, count));
         }
-        output.push_str("\n");
+        output.push('\n');
         
         // Recent errors
         output.push_str("## Recent Errors (Last 50)\n");
@@ -0,0 +1,155 @@
+use crate::scanner::SignalReading;
+
+/// Drop from the peak at which its bandwidth is measured
+const BANDWIDTH_DROP_DB: f32 = 3.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Peak {
+    /// Frequency of the strongest sample in MHz
+    pub center_freq: f32,
+    pub peak_db: f32,
+    /// Width in MHz where the power stays within 3 dB of the peak
+    pub bandwidth_estimate: f32,
+    /// Height above the higher of the two surrounding valleys in dB
+    pub prominence: f32,
+}
+
+/// Peaks in readings sorted by frequency that rise above `threshold` and stand out from their
+/// surroundings by at least `min_prominence` dB. Strongest peak first.
+pub fn find_peaks(readings: &[SignalReading], threshold: f32, min_prominence: f32) -> Vec<Peak> {
+    let power: Vec<f32> = readings.iter().map(|r| r.strength).collect();
+    find_peaks_by(&power, |i| readings[i].frequency, threshold, min_prominence)
+}
+
+/// [`find_peaks`] over a power vector with bin frequencies given by `frequency`
+pub fn find_peaks_by(
+    power: &[f32],
+    frequency: impl Fn(usize) -> f32,
+    threshold: f32,
+    min_prominence: f32,
+) -> Vec<Peak> {
+    let mut peaks = Vec::new();
+
+    let mut i = 0;
+    while i < power.len() {
+        // Treat a run of equal samples as one candidate
+        let mut end = i;
+        while end + 1 < power.len() && power[end + 1] == power[i] {
+            end += 1;
+        }
+
+        let level = power[i];
+        let rises_left = i == 0 || power[i - 1] < level;
+        let falls_right = end + 1 == power.len() || power[end + 1] < level;
+
+        if level > threshold && rises_left && falls_right {
+            let (left_base, left_valley) = valley(power, level, (0..i).rev());
+            let (right_base, right_valley) = valley(power, level, end + 1..power.len());
+            // A peak at the edge of the scan is only measured against the side that exists
+            let prominence = match (left_base, right_base) {
+                (Some(_), Some(_)) => level - left_valley.max(right_valley),
+                (Some(_), None) => level - left_valley,
+                (None, Some(_)) => level - right_valley,
+                (None, None) => 0.0,
+            };
+
+            if prominence >= min_prominence {
+                let center = (i + end) / 2;
+                let cutoff = level - BANDWIDTH_DROP_DB;
+                let low = crossing(power, &frequency, cutoff, i, left_base);
+                let high = crossing(power, &frequency, cutoff, end, right_base);
+
+                peaks.push(Peak {
+                    center_freq: frequency(center),
+                    peak_db: level,
+                    bandwidth_estimate: (high - low).abs(),
+                    prominence,
+                });
+            }
+        }
+
+        i = end + 1;
+    }
+
+    peaks.sort_by(|a, b| b.peak_db.total_cmp(&a.peak_db));
+    peaks
+}
+
+/// Walk away from a peak until a higher sample or the edge, returning the index of the last
+/// sample walked over and the lowest power on the way
+fn valley(power: &[f32], level: f32, indices: impl Iterator<Item = usize>) -> (Option<usize>, f32) {
+    let mut base = None;
+    let mut lowest = level;
+
+    for index in indices {
+        if power[index] > level {
+            break;
+        }
+        base = Some(index);
+        lowest = lowest.min(power[index]);
+    }
+
+    (base, lowest)
+}
+
+/// Frequency where the power first drops below `cutoff` walking from `from` towards `limit`,
+/// interpolated between samples. Stops at `limit` if it never drops that far.
+fn crossing(
+    power: &[f32],
+    frequency: &impl Fn(usize) -> f32,
+    cutoff: f32,
+    from: usize,
+    limit: Option<usize>,
+) -> f32 {
+    let Some(limit) = limit else {
+        return frequency(from);
+    };
+
+    let mut previous = from;
+    let mut index = from;
+    while index != limit {
+        index = if limit < from { index - 1 } else { index + 1 };
+
+        if power[index] < cutoff {
+            let fraction = (power[previous] - cutoff) / (power[previous] - power[index]);
+            return frequency(previous) + (frequency(index) - frequency(previous)) * fraction;
+        }
+        previous = index;
+    }
+
+    frequency(limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prominence_and_bandwidth() {
+        // Strong peak at 868, a small bump at 863 and a band edge at 871
+        let power = [-80.0, -80.0, -70.0, -78.0, -80.0, -80.0, -50.0, -30.0, -50.0, -80.0, -60.0];
+        let peaks = find_peaks_by(&power, |i| 861.0 + i as f32, -90.0, 3.0);
+
+        assert_eq!(peaks.len(), 3);
+        assert_eq!(peaks[0].center_freq, 868.0);
+        assert_eq!(peaks[0].prominence, 50.0);
+        assert!((peaks[0].bandwidth_estimate - 0.3).abs() < 1e-4);
+
+        assert_eq!(peaks[1].center_freq, 871.0);
+        assert_eq!(peaks[1].prominence, 20.0);
+        assert_eq!(peaks[2].center_freq, 863.0);
+        assert_eq!(peaks[2].prominence, 10.0);
+    }
+
+    #[test]
+    fn test_threshold_and_min_prominence() {
+        let power = [-80.0, -60.0, -80.0, -79.0, -80.0];
+        // The 1 dB ripple at index 3 is above the threshold but not prominent enough
+        let peaks = find_peaks_by(&power, |i| i as f32, -90.0, 3.0);
+
+        assert_eq!(peaks.len(), 1);
+        assert_eq!(peaks[0].center_freq, 1.0);
+        assert_eq!(find_peaks_by(&power, |i| i as f32, -90.0, 0.5).len(), 2);
+        assert!(find_peaks_by(&power, |i| i as f32, -50.0, 3.0).is_empty());
+    }
+}
@@ -0,0 +1,221 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+
+use crate::error::{HexarError, HexarResult};
+
+/// What a stage does with an item when the next stage's queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Wait for room, slowing the producer down to the consumer's pace
+    Block,
+    /// Drop the item and count it, so the producer never waits
+    #[default]
+    DropNewest,
+}
+
+/// Sending end of the bounded queue between two stages of the data path, applying the
+/// queue's overflow policy. Clones share the count of dropped items.
+#[derive(Debug)]
+pub struct StageSender<T> {
+    sender: mpsc::Sender<T>,
+    policy: OverflowPolicy,
+    dropped: Arc<AtomicU64>,
+}
+
+impl<T> Clone for StageSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            policy: self.policy,
+            dropped: Arc::clone(&self.dropped),
+        }
+    }
+}
+
+/// Bounded queue of `capacity` items between two stages
+pub fn stage_channel<T>(
+    capacity: usize,
+    policy: OverflowPolicy,
+) -> (StageSender<T>, mpsc::Receiver<T>) {
+    let (sender, receiver) = mpsc::channel(capacity.max(1));
+    let sender = StageSender {
+        sender,
+        policy,
+        dropped: Arc::new(AtomicU64::new(0)),
+    };
+    (sender, receiver)
+}
+
+impl<T> StageSender<T> {
+    /// Queue `item`, failing only once the receiving stage is gone
+    pub async fn send(&self, item: T) -> HexarResult<()> {
+        match self.policy {
+            OverflowPolicy::Block => self.sender.send(item).await.map_err(|_| closed()),
+            OverflowPolicy::DropNewest => self.try_send(item),
+        }
+    }
+
+    /// [`send`](StageSender::send) from a thread outside the runtime
+    pub fn blocking_send(&self, item: T) -> HexarResult<()> {
+        match self.policy {
+            OverflowPolicy::Block => self.sender.blocking_send(item).map_err(|_| closed()),
+            OverflowPolicy::DropNewest => self.try_send(item),
+        }
+    }
+
+    fn try_send(&self, item: T) -> HexarResult<()> {
+        match self.sender.try_send(item) {
+            Ok(()) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => Err(closed()),
+        }
+    }
+
+    pub fn get_policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    /// Items dropped because the queue was full since the last call
+    pub fn take_dropped(&self) -> u64 {
+        self.dropped.swap(0, Ordering::Relaxed)
+    }
+}
+
+/// Consumer at the end of the data path, such as a recorder
+pub trait Sink<T>: Send + 'static {
+    fn consume(&mut self, item: T) -> HexarResult<()>;
+
+    /// Called whenever the queue runs empty and when the stage ends
+    fn flush(&mut self) -> HexarResult<()> {
+        Ok(())
+    }
+}
+
+/// A [`Sink`] run on a blocking thread of its own behind a bounded queue, so a slow consumer
+/// like a disk writer can't stall the stage feeding it
+#[derive(Debug)]
+pub struct SinkStage<T> {
+    name: String,
+    sender: StageSender<T>,
+    handle: JoinHandle<()>,
+}
+
+impl<T: Send + 'static> SinkStage<T> {
+    pub fn spawn<S: Sink<T>>(
+        name: &str,
+        mut sink: S,
+        capacity: usize,
+        policy: OverflowPolicy,
+    ) -> Self {
+        let (sender, mut receiver) = stage_channel(capacity, policy);
+        let stage = name.to_string();
+        let handle = tokio::task::spawn_blocking(move || {
+            while let Some(item) = receiver.blocking_recv() {
+                if let Err(e) = sink.consume(item) {
+                    error!("{} sink failed: {}", stage, e);
+                }
+                if receiver.is_empty() {
+                    if let Err(e) = sink.flush() {
+                        error!("{} sink failed to flush: {}", stage, e);
+                    }
+                }
+            }
+            if let Err(e) = sink.flush() {
+                error!("{} sink failed to flush: {}", stage, e);
+            }
+            debug!("{} sink stopped", stage);
+        });
+        Self {
+            name: name.to_string(),
+            sender,
+            handle,
+        }
+    }
+
+    pub async fn send(&self, item: T) -> HexarResult<()> {
+        self.sender.send(item).await
+    }
+
+    pub fn get_sender(&self) -> &StageSender<T> {
+        &self.sender
+    }
+
+    /// Let the sink work through its queue and wait for it to finish
+    pub async fn close(self) -> HexarResult<()> {
+        drop(self.sender);
+        self.handle
+            .await
+            .map_err(|e| HexarError::SystemError(format!("{} sink failed: {}", self.name, e)))
+    }
+}
+
+fn closed() -> HexarError {
+    HexarError::CommunicationError("pipeline stage closed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Takes its time with every item
+    struct SlowSink(Arc<Mutex<Vec<u32>>>);
+
+    impl Sink<u32> for SlowSink {
+        fn consume(&mut self, item: u32) -> HexarResult<()> {
+            std::thread::sleep(Duration::from_millis(20));
+            self.0.lock().unwrap().push(item);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_sink_drops_instead_of_blocking() {
+        let consumed = Arc::new(Mutex::new(Vec::new()));
+        let sink = SinkStage::spawn(
+            "slow",
+            SlowSink(Arc::clone(&consumed)),
+            2,
+            OverflowPolicy::DropNewest,
+        );
+
+        let started = std::time::Instant::now();
+        for item in 0..50 {
+            sink.send(item).await.unwrap();
+        }
+        assert!(started.elapsed() < Duration::from_millis(200));
+        let dropped = sink.get_sender().take_dropped();
+        assert!(dropped > 0);
+
+        sink.close().await.unwrap();
+        let consumed = consumed.lock().unwrap();
+        assert_eq!(consumed.len() as u64 + dropped, 50);
+        assert_eq!(consumed[0], 0);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_stage_keeps_every_item() {
+        let (sender, mut receiver) = stage_channel(1, OverflowPolicy::Block);
+        let producer = tokio::spawn(async move {
+            for item in 0..10u32 {
+                sender.send(item).await.unwrap();
+            }
+        });
+        let mut received = Vec::new();
+        while let Some(item) = receiver.recv().await {
+            received.push(item);
+        }
+        producer.await.unwrap();
+        assert_eq!(received, (0..10).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use nalgebra::{Matrix2, Vector2};
+
+use crate::fusion::{FusionConfig, SensorConfig, SensorPose};
+
+/// Correspondences needed before a pose is estimated
+pub const MIN_CALIBRATION_SAMPLES: usize = 10;
+
+/// Smallest spread of the observed points in metres, below which the rotation is not
+/// observable
+const MIN_SPREAD: f32 = 0.5;
+
+/// Position of the person in the sensor frame and in the room frame at the same moment
+type Correspondence = (Vector2<f32>, Vector2<f32>);
+
+/// Route walked during calibration, with when each waypoint is reached
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownPath {
+    waypoints: Vec<(Duration, Vector2<f32>)>,
+}
+
+impl KnownPath {
+    /// Waypoints in the order they are reached, times since the start of the walk
+    pub fn new(mut waypoints: Vec<(Duration, Vector2<f32>)>) -> Self {
+        waypoints.sort_by_key(|(time, _)| *time);
+        Self { waypoints }
+    }
+
+    /// Route walked through `points` at a constant `speed` in m/s
+    pub fn walked_at(points: &[Vector2<f32>], speed: f32) -> Self {
+        let speed = speed.max(0.01);
+        let mut elapsed = 0.0;
+        let mut waypoints = Vec::with_capacity(points.len());
+        for (index, &point) in points.iter().enumerate() {
+            if index > 0 {
+                elapsed += (point - points[index - 1]).norm() / speed;
+            }
+            waypoints.push((Duration::from_secs_f32(elapsed), point));
+        }
+        Self { waypoints }
+    }
+
+    /// Where the person is `elapsed` after the start, `None` before or after the walk
+    pub fn position_at(&self, elapsed: Duration) -> Option<Vector2<f32>> {
+        let after = self
+            .waypoints
+            .iter()
+            .position(|(time, _)| *time >= elapsed)?;
+        let (time, point) = self.waypoints[after];
+        if after == 0 {
+            return (time == elapsed).then_some(point);
+        }
+
+        let (previous_time, previous) = self.waypoints[after - 1];
+        let span = (time - previous_time).as_secs_f32();
+        let fraction = if span > 0.0 {
+            (elapsed - previous_time).as_secs_f32() / span
+        } else {
+            1.0
+        };
+        Some(previous + (point - previous) * fraction)
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        self.waypoints
+            .last()
+            .map(|(time, _)| *time)
+            .unwrap_or_default()
+    }
+}
+
+/// Pose found for one antenna
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoseEstimate {
+    pub pose: SensorPose,
+    /// Root mean square distance in metres between the mapped and reference points
+    pub rms_error: f32,
+    pub samples: usize,
+}
+
+/// Estimates where antennas are mounted from a single person seen both in the antenna's own
+/// coordinates and in room coordinates, the latter from a [`KnownPath`] being walked or from
+/// a reference antenna whose pose is already known
+#[derive(Debug, Clone, Default)]
+pub struct PoseCalibrator {
+    /// Pairs of sensor frame and room frame positions per antenna
+    correspondences: HashMap<u8, Vec<Correspondence>>,
+}
+
+impl PoseCalibrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `antenna_id` saw the person at `local` while they were at `world`
+    pub fn add_correspondence(&mut self, antenna_id: u8, local: Vector2<f32>, world: Vector2<f32>) {
+        self.correspondences
+            .entry(antenna_id)
+            .or_default()
+            .push((local, world));
+    }
+
+    /// Record a detection `elapsed` into walking `path`, ignored outside the walk
+    pub fn observe_on_path(
+        &mut self,
+        path: &KnownPath,
+        elapsed: Duration,
+        antenna_id: u8,
+        local: Vector2<f32>,
+    ) -> bool {
+        match path.position_at(elapsed) {
+            Some(world) => {
+                self.add_correspondence(antenna_id, local, world);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Record the person seen at the same time by a calibrated reference antenna at
+    /// `reference_local` and by `antenna_id` at `local`
+    pub fn observe_with_reference(
+        &mut self,
+        reference: &SensorPose,
+        reference_local: Vector2<f32>,
+        antenna_id: u8,
+        local: Vector2<f32>,
+    ) {
+        self.add_correspondence(antenna_id, local, reference.to_world(reference_local));
+    }
+
+    pub fn get_sample_count(&self, antenna_id: u8) -> usize {
+        self.correspondences.get(&antenna_id).map_or(0, Vec::len)
+    }
+
+    /// Least-squares rotation and translation mapping the antenna's observations onto the
+    /// room positions. `None` until [`MIN_CALIBRATION_SAMPLES`] spread over at least half a
+    /// metre were recorded.
+    pub fn estimate(&self, antenna_id: u8) -> Option<PoseEstimate> {
+        let pairs = self.correspondences.get(&antenna_id)?;
+        if pairs.len() < MIN_CALIBRATION_SAMPLES {
+            return None;
+        }
+
+        let count = pairs.len() as f32;
+        let local_mean = pairs.iter().map(|(local, _)| local).sum::<Vector2<f32>>() / count;
+        let world_mean = pairs.iter().map(|(_, world)| world).sum::<Vector2<f32>>() / count;
+        let spread = pairs
+            .iter()
+            .map(|(local, _)| (local - local_mean).norm())
+            .fold(0.0f32, f32::max);
+        if spread < MIN_SPREAD {
+            return None;
+        }
+
+        // Cross-covariance of the centred point sets, its antisymmetric part gives the angle
+        let cross = pairs.iter().fold(Matrix2::zeros(), |sum, (local, world)| {
+            sum + (local - local_mean) * (world - world_mean).transpose()
+        });
+        let heading = (cross[(0, 1)] - cross[(1, 0)]).atan2(cross[(0, 0)] + cross[(1, 1)]);
+
+        let mut pose = SensorPose::new(Vector2::zeros(), 0.0, heading);
+        pose.position = world_mean - pose.to_world(local_mean);
+        let rms_error = (pairs
+            .iter()
+            .map(|(local, world)| (pose.to_world(*local) - world).norm_squared())
+            .sum::<f32>()
+            / count)
+            .sqrt();
+
+        Some(PoseEstimate {
+            pose,
+            rms_error,
+            samples: pairs.len(),
+        })
+    }
+
+    /// Write the poses of every antenna with enough samples into `config`, keeping configured
+    /// mounting heights. Returns the antennas updated with their estimates.
+    pub fn apply_to(&self, config: &mut FusionConfig) -> Vec<(u8, PoseEstimate)> {
+        let mut antenna_ids: Vec<u8> = self.correspondences.keys().copied().collect();
+        antenna_ids.sort_unstable();
+
+        let mut applied = Vec::new();
+        for antenna_id in antenna_ids {
+            let Some(estimate) = self.estimate(antenna_id) else {
+                continue;
+            };
+            match config
+                .sensors
+                .iter_mut()
+                .find(|sensor| sensor.antenna_id == antenna_id)
+            {
+                Some(sensor) => {
+                    sensor.pose.position = estimate.pose.position;
+                    sensor.pose.heading = estimate.pose.heading;
+                }
+                None => config.sensors.push(SensorConfig {
+                    antenna_id,
+                    pose: estimate.pose,
+                }),
+            }
+            applied.push((antenna_id, estimate));
+        }
+        applied
+    }
+
+    pub fn clear(&mut self) {
+        self.correspondences.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pose_from_known_path() {
+        let truth = SensorPose::new(Vector2::new(3.0, -1.0), 1.2, 0.7);
+        let path = KnownPath::walked_at(
+            &[
+                Vector2::new(0.0, 0.0),
+                Vector2::new(4.0, 0.0),
+                Vector2::new(4.0, 3.0),
+            ],
+            1.0,
+        );
+        assert_eq!(path.get_duration(), Duration::from_secs(7));
+        assert_eq!(
+            path.position_at(Duration::from_secs(5)),
+            Some(Vector2::new(4.0, 1.0))
+        );
+
+        let mut calibrator = PoseCalibrator::new();
+        for step in 0..=70 {
+            let elapsed = Duration::from_millis(step * 100);
+            let world = path.position_at(elapsed).unwrap();
+            assert!(calibrator.observe_on_path(&path, elapsed, 2, truth.to_local(world)));
+        }
+        assert!(!calibrator.observe_on_path(&path, Duration::from_secs(8), 2, Vector2::zeros()));
+
+        let estimate = calibrator.estimate(2).unwrap();
+        assert!((estimate.pose.position - truth.position).norm() < 1e-3);
+        assert!((estimate.pose.heading - truth.heading).abs() < 1e-3);
+        assert!(estimate.rms_error < 1e-3);
+
+        let mut config = FusionConfig::default();
+        config.sensors.push(SensorConfig {
+            antenna_id: 2,
+            pose: SensorPose::new(Vector2::zeros(), 1.2, 0.0),
+        });
+        assert_eq!(calibrator.apply_to(&mut config).len(), 1);
+        assert_eq!(config.sensors.len(), 1);
+        assert_eq!(config.sensors[0].pose.height, 1.2);
+        assert!((config.sensors[0].pose.heading - 0.7).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_too_few_samples() {
+        let mut calibrator = PoseCalibrator::new();
+        for _ in 0..MIN_CALIBRATION_SAMPLES {
+            // Standing still leaves the rotation unknown
+            calibrator.observe_with_reference(
+                &SensorPose::default(),
+                Vector2::new(1.0, 1.0),
+                1,
+                Vector2::new(2.0, 0.0),
+            );
+        }
+        assert_eq!(calibrator.get_sample_count(1), MIN_CALIBRATION_SAMPLES);
+        assert!(calibrator.estimate(1).is_none());
+        assert!(calibrator.estimate(3).is_none());
+    }
+}
@@ -0,0 +1,219 @@
+use std::fmt::Debug;
+
+use chrono::{DateTime, Utc};
+use nalgebra::Vector2;
+use tracing::warn;
+
+use crate::control::Zone;
+use crate::error::HexarResult;
+use crate::fusion::SensorFusion;
+
+/// A target detected this scan cycle, on its way to the tracker
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageMeasurement {
+    pub antenna_id: u8,
+    /// In the antenna frame, `y` along the boresight, in metres
+    pub position: Vector2<f32>,
+    /// Radial speed in m/s, positive moving away, `None` where the source doesn't measure it
+    pub speed: Option<f32>,
+    /// Whether the source classified the target as moving, `None` where it doesn't
+    pub moving: Option<bool>,
+}
+
+/// What a [`ProcessingStage`] knows about the cycle besides its measurements
+#[derive(Debug)]
+pub struct StageContext<'a> {
+    pub timestamp: DateTime<Utc>,
+    fusion: &'a SensorFusion,
+}
+
+impl<'a> StageContext<'a> {
+    pub fn new(timestamp: DateTime<Utc>, fusion: &'a SensorFusion) -> Self {
+        Self { timestamp, fusion }
+    }
+
+    /// Room coordinates of a measurement
+    pub fn to_world(&self, measurement: &StageMeasurement) -> Vector2<f32> {
+        self.fusion
+            .to_world(measurement.antenna_id, measurement.position)
+    }
+
+    /// Move a measurement to `world` in the room frame
+    pub fn set_world(&self, measurement: &mut StageMeasurement, world: Vector2<f32>) {
+        measurement.position = self
+            .fusion
+            .get_pose(measurement.antenna_id)
+            .map_or(world, |pose| pose.to_local(world));
+    }
+}
+
+/// Step run on every cycle's measurements between their extraction from the frames or
+/// scanner readings and the tracker update, e.g. a clutter filter, a coordinate correction or
+/// a classifier dropping what isn't a person. See
+/// [`RadarController::add_processing_stage`](crate::radar_controller::RadarController::add_processing_stage).
+pub trait ProcessingStage: Debug + Send {
+    /// Identifies the stage in logs and for removal
+    fn name(&self) -> &str;
+
+    /// Drop, correct or add measurements in place. On error the cycle goes on with the
+    /// measurements as they were before the stage.
+    fn process(
+        &mut self,
+        measurements: &mut Vec<StageMeasurement>,
+        context: &StageContext<'_>,
+    ) -> HexarResult<()>;
+}
+
+/// Stages run in the order they were added
+#[derive(Debug, Default)]
+pub struct ProcessingChain {
+    stages: Vec<Box<dyn ProcessingStage>>,
+}
+
+impl ProcessingChain {
+    pub fn push(&mut self, stage: Box<dyn ProcessingStage>) {
+        self.stages.push(stage);
+    }
+
+    /// Add `stage` right before the stage named `before`, at the end when there is none
+    pub fn insert_before(&mut self, before: &str, stage: Box<dyn ProcessingStage>) {
+        match self.stages.iter().position(|other| other.name() == before) {
+            Some(index) => self.stages.insert(index, stage),
+            None => self.stages.push(stage),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Box<dyn ProcessingStage>> {
+        let index = self.stages.iter().position(|stage| stage.name() == name)?;
+        Some(self.stages.remove(index))
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.stages.iter().map(|stage| stage.name()).collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Run every stage on `measurements`, returning the errors of those that failed by name
+    pub fn run(
+        &mut self,
+        measurements: &mut Vec<StageMeasurement>,
+        context: &StageContext<'_>,
+    ) -> Vec<(String, String)> {
+        let mut errors = Vec::new();
+        for stage in &mut self.stages {
+            let before = measurements.clone();
+            if let Err(e) = stage.process(measurements, context) {
+                warn!("Processing stage {} failed: {}", stage.name(), e);
+                *measurements = before;
+                errors.push((stage.name().to_string(), e.to_string()));
+            }
+        }
+        errors
+    }
+}
+
+/// Drops measurements inside zones known to hold clutter, e.g. a fan or curtains
+#[derive(Debug, Clone)]
+pub struct ClutterMask {
+    zones: Vec<Zone>,
+}
+
+impl ClutterMask {
+    pub fn new(zones: Vec<Zone>) -> Self {
+        Self { zones }
+    }
+}
+
+impl ProcessingStage for ClutterMask {
+    fn name(&self) -> &str {
+        "clutter_mask"
+    }
+
+    fn process(
+        &mut self,
+        measurements: &mut Vec<StageMeasurement>,
+        context: &StageContext<'_>,
+    ) -> HexarResult<()> {
+        measurements.retain(|measurement| {
+            let world = context.to_world(measurement);
+            !self.zones.iter().any(|zone| zone.contains(world))
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::HexarError;
+    use crate::fusion::SensorPose;
+
+    /// Shifts everything along the room's x axis, then fails when asked to
+    #[derive(Debug)]
+    struct Offset {
+        fail: bool,
+    }
+
+    impl ProcessingStage for Offset {
+        fn name(&self) -> &str {
+            "offset"
+        }
+
+        fn process(
+            &mut self,
+            measurements: &mut Vec<StageMeasurement>,
+            context: &StageContext<'_>,
+        ) -> HexarResult<()> {
+            for measurement in measurements.iter_mut() {
+                let world = context.to_world(measurement);
+                context.set_world(measurement, world + Vector2::new(0.5, 0.0));
+            }
+            if self.fail {
+                return Err(HexarError::SystemError("model unavailable".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_processing_chain() {
+        let mut fusion = SensorFusion::new(0.3);
+        // Antenna 1 faces the room's -y
+        fusion.set_pose(
+            1,
+            SensorPose::new(Vector2::new(0.0, 0.0), 1.0, std::f32::consts::PI),
+        );
+        let context = StageContext::new(Utc::now(), &fusion);
+        let measurement = |antenna_id, x, y| StageMeasurement {
+            antenna_id,
+            position: Vector2::new(x, y),
+            speed: None,
+            moving: None,
+        };
+        let mut measurements = vec![measurement(0, 1.0, 1.0), measurement(1, 0.0, 2.0)];
+
+        let mut chain = ProcessingChain::default();
+        chain.push(Box::new(Offset { fail: false }));
+        chain.insert_before(
+            "offset",
+            Box::new(ClutterMask::new(vec![Zone {
+                name: "fan".to_string(),
+                min: Vector2::new(-0.5, -2.5),
+                max: Vector2::new(0.5, -1.5),
+            }])),
+        );
+        assert_eq!(chain.names(), vec!["clutter_mask", "offset"]);
+        assert!(chain.run(&mut measurements, &context).is_empty());
+        // The mask saw antenna 1's target at (0, -2) and dropped it before the offset
+        assert_eq!(measurements, vec![measurement(0, 1.5, 1.0)]);
+
+        chain.remove("offset");
+        chain.push(Box::new(Offset { fail: true }));
+        let errors = chain.run(&mut measurements, &context);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(measurements, vec![measurement(0, 1.5, 1.0)]);
+    }
+}
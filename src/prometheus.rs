@@ -0,0 +1,537 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+
+use crate::config::PushGatewayConfig;
+use crate::control_socket::{self, SocketCall, SocketRequest, SocketResponse};
+use crate::error::{HexarError, HexarResult};
+use crate::health::{AntennaHealth, HealthReport};
+use crate::metrics::{Exposition, MetricKind, ScanMetrics};
+use crate::monitoring::{Alert, AlertSeverity, ErrorMetrics};
+use crate::radar_controller::ControllerState;
+use crate::tracker::AntennaLoad;
+
+/// Time a push to the gateway may take
+const PUSH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Everything the Prometheus metrics are made of, taken from the main loop at once
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub uptime: Duration,
+    pub scanning: bool,
+    pub health: HealthReport,
+    pub scan: ScanMetrics,
+    pub antenna_loads: Vec<AntennaLoad>,
+    pub emergency_stop: bool,
+    pub active_alerts: Vec<Alert>,
+    pub errors: ErrorMetrics,
+}
+
+impl MetricsSnapshot {
+    /// The snapshot in the Prometheus text exposition format
+    pub fn encode(&self) -> String {
+        use MetricKind::{Counter, Gauge, Histogram};
+
+        let mut out = Exposition::new();
+        let health = &self.health;
+        let scan = &self.scan;
+        let flag = |on: bool| if on { 1.0 } else { 0.0 };
+
+        // Controller
+        out.single(
+            "hexar_uptime_seconds",
+            Gauge,
+            "Time since the instance started",
+            self.uptime.as_secs_f64(),
+        );
+        out.family("hexar_controller_state", Gauge, "Current controller state")
+            .sample(
+                "hexar_controller_state",
+                &[("state", state_name(&health.state))],
+                1.0,
+            );
+        out.single(
+            "hexar_healthy",
+            Gauge,
+            "Scanning or ready to, with every antenna working",
+            flag(health.is_healthy()),
+        );
+        out.single(
+            "hexar_scanning",
+            Gauge,
+            "Zero while scan cycles are paused",
+            flag(self.scanning),
+        );
+        out.single(
+            "hexar_scan_rate_hz",
+            Gauge,
+            "Configured scan cycles a second",
+            health.scan_rate_hz.into(),
+        );
+        if let Some(coverage) = health.coverage {
+            out.single(
+                "hexar_coverage_ratio",
+                Gauge,
+                "Share of the array's area the working antennas cover",
+                coverage.into(),
+            );
+        }
+        out.single(
+            "hexar_scan_cycles_total",
+            Counter,
+            "Scan cycles run",
+            scan.cycles as f64,
+        );
+        out.single(
+            "hexar_scan_signals_total",
+            Counter,
+            "Scanner readings processed",
+            scan.signals as f64,
+        );
+        out.single(
+            "hexar_scan_measurements_total",
+            Counter,
+            "Measurements handed to the tracker",
+            scan.measurements as f64,
+        );
+        out.single(
+            "hexar_frames_dropped_total",
+            Counter,
+            "Sensor frames lost because the channel to the controller was full",
+            scan.dropped_frames as f64,
+        );
+        out.single(
+            "hexar_frames_superseded_total",
+            Counter,
+            "Sensor frames replaced by a newer one before a cycle used them",
+            scan.superseded_frames as f64,
+        );
+        out.single(
+            "hexar_sink_frames_dropped_total",
+            Counter,
+            "Frames a recorder dropped because it fell behind",
+            scan.sink_dropped as f64,
+        );
+        out.family(
+            "hexar_scan_cycle_duration_seconds",
+            Histogram,
+            "Duration of the scan cycles",
+        )
+        .histogram(
+            "hexar_scan_cycle_duration_seconds",
+            &[],
+            &scan.cycle_duration_ms,
+            0.001,
+        );
+        out.family(
+            "hexar_scan_measurements_per_cycle",
+            Histogram,
+            "Measurements handed to the tracker in a scan cycle",
+        )
+        .histogram(
+            "hexar_scan_measurements_per_cycle",
+            &[],
+            &scan.measurements_per_cycle,
+            1.0,
+        );
+
+        // Tracker
+        out.family("hexar_tracker_targets", Gauge, "Targets followed by status")
+            .sample(
+                "hexar_tracker_targets",
+                &[("status", "confirmed")],
+                health.tracker.confirmed as f64,
+            )
+            .sample(
+                "hexar_tracker_targets",
+                &[("status", "tentative")],
+                health.tracker.tentative as f64,
+            );
+        out.single(
+            "hexar_tracker_falling_targets",
+            Gauge,
+            "Targets with a suspected or confirmed fall",
+            health.tracker.falling as f64,
+        );
+        out.single(
+            "hexar_tracker_stationary_targets",
+            Gauge,
+            "Targets no longer moving but still present",
+            health.tracker.stationary as f64,
+        );
+        let associations = &scan.associations;
+        out.family(
+            "hexar_tracker_associations_total",
+            Counter,
+            "What became of the measurements handed to the tracker",
+        )
+        .sample(
+            "hexar_tracker_associations_total",
+            &[("result", "associated")],
+            associations.associated as f64,
+        )
+        .sample(
+            "hexar_tracker_associations_total",
+            &[("result", "created")],
+            associations.created as f64,
+        )
+        .sample(
+            "hexar_tracker_associations_total",
+            &[("result", "unassociated")],
+            associations.unassociated as f64,
+        );
+
+        // Antennas and their frame parsers
+        let antennas = &health.antennas;
+        let id = |antenna: &AntennaHealth| antenna.antenna_id;
+        per_antenna(
+            &mut out,
+            ("hexar_antenna_connected", Gauge),
+            "Whether the serial link of the antenna is up",
+            antennas,
+            id,
+            |antenna| flag(antenna.connected),
+        );
+        per_antenna(
+            &mut out,
+            ("hexar_antenna_excluded", Gauge),
+            "Left out of the scan cycle after failing the self-test",
+            antennas,
+            id,
+            |antenna| flag(antenna.excluded),
+        );
+        per_antenna(
+            &mut out,
+            ("hexar_antenna_reconnect_attempts", Gauge),
+            "Failed attempts to reopen the port since the link went down",
+            antennas,
+            id,
+            |antenna| antenna.reconnect_attempts.into(),
+        );
+        per_antenna(
+            &mut out,
+            ("hexar_parser_frames_total", Counter),
+            "Sensor frames parsed",
+            antennas,
+            id,
+            |antenna| antenna.frames_parsed.into(),
+        );
+        per_antenna(
+            &mut out,
+            ("hexar_parser_malformed_frames_total", Counter),
+            "Sensor frames that failed to parse",
+            antennas,
+            id,
+            |antenna| antenna.malformed_frames.into(),
+        );
+        per_antenna(
+            &mut out,
+            ("hexar_parser_discarded_bytes_total", Counter),
+            "Bytes skipped while resynchronizing on a frame header",
+            antennas,
+            id,
+            |antenna| antenna.bytes_discarded.into(),
+        );
+        let loads = &self.antenna_loads;
+        let id = |load: &AntennaLoad| load.antenna_id;
+        per_antenna(
+            &mut out,
+            ("hexar_antenna_targets", Gauge),
+            "Targets the antenna follows",
+            loads,
+            id,
+            |load| load.targets as f64,
+        );
+        per_antenna(
+            &mut out,
+            ("hexar_antenna_capacity", Gauge),
+            "Targets the antenna can follow at once",
+            loads,
+            id,
+            |load| load.capacity as f64,
+        );
+        per_antenna(
+            &mut out,
+            ("hexar_antenna_rejected_detections_total", Counter),
+            "Detections dropped because the antenna was full",
+            loads,
+            id,
+            |load| load.rejected as f64,
+        );
+
+        // Safety
+        out.single(
+            "hexar_emergency_stop",
+            Gauge,
+            "Whether the emergency stop is active",
+            flag(self.emergency_stop),
+        );
+        out.family(
+            "hexar_alerts_active",
+            Gauge,
+            "Unresolved alerts by severity",
+        );
+        for (severity, name) in [
+            (AlertSeverity::Info, "info"),
+            (AlertSeverity::Warning, "warning"),
+            (AlertSeverity::Critical, "critical"),
+            (AlertSeverity::Emergency, "emergency"),
+        ] {
+            let active = self
+                .active_alerts
+                .iter()
+                .filter(|alert| alert.severity == severity)
+                .count();
+            out.sample("hexar_alerts_active", &[("severity", name)], active as f64);
+        }
+        out.single(
+            "hexar_errors_total",
+            Counter,
+            "Errors logged",
+            self.errors.total_errors as f64,
+        );
+        out.single(
+            "hexar_critical_errors_total",
+            Counter,
+            "Critical errors logged",
+            self.errors.critical_errors.into(),
+        );
+        out.into_text()
+    }
+}
+
+/// A family with a sample for each of `items`, labelled with its antenna
+fn per_antenna<T>(
+    out: &mut Exposition,
+    (name, kind): (&str, MetricKind),
+    help: &str,
+    items: &[T],
+    id: impl Fn(&T) -> u8,
+    value: impl Fn(&T) -> f64,
+) {
+    out.family(name, kind, help);
+    for item in items {
+        out.sample(name, &[("antenna", &id(item).to_string())], value(item));
+    }
+}
+
+fn state_name(state: &ControllerState) -> &'static str {
+    match state {
+        ControllerState::Uninitialized => "uninitialized",
+        ControllerState::Initializing => "initializing",
+        ControllerState::Ready => "ready",
+        ControllerState::Scanning => "scanning",
+        ControllerState::Error(_) => "error",
+        ControllerState::Shutdown => "shutdown",
+    }
+}
+
+/// Metrics of the running instance, from its main loop
+pub async fn collect(
+    calls: &mpsc::Sender<SocketCall>,
+    source: &'static str,
+) -> HexarResult<String> {
+    match control_socket::call(calls, source, SocketRequest::Metrics).await? {
+        SocketResponse::Metrics { metrics } => Ok(metrics.encode()),
+        other => Err(HexarError::CommunicationError(format!(
+            "unexpected answer {:?}",
+            other
+        ))),
+    }
+}
+
+/// Where metrics are pushed to, split from a gateway URL
+#[derive(Debug, Clone, PartialEq)]
+struct PushTarget {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl PushTarget {
+    fn parse(config: &PushGatewayConfig, instance: &str) -> HexarResult<Self> {
+        let invalid = || {
+            HexarError::ConfigurationError(format!(
+                "push gateway URL {} isn't http://host[:port][/path]",
+                config.url
+            ))
+        };
+        let rest = config.url.strip_prefix("http://").ok_or_else(invalid)?;
+        let (authority, base) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid())?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid());
+        }
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: format!(
+                "{}/metrics/job/{}/instance/{}",
+                base.trim_end_matches('/'),
+                config.job,
+                instance
+            ),
+        })
+    }
+
+    /// Replace the metrics of the group with `body`
+    async fn push(&self, body: &str) -> HexarResult<()> {
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).await?;
+        let request = format!(
+            "PUT {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path,
+            self.host,
+            self.port,
+            Exposition::CONTENT_TYPE,
+            body.len(),
+            body
+        );
+        stream.write_all(request.as_bytes()).await?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await?;
+        let response = String::from_utf8_lossy(&response);
+        let status = response.split(' ').nth(1).unwrap_or_default();
+        if status.starts_with('2') {
+            Ok(())
+        } else {
+            Err(HexarError::CommunicationError(format!(
+                "push gateway answered {}",
+                response.lines().next().unwrap_or("nothing")
+            )))
+        }
+    }
+}
+
+/// Pushes the metrics of the running instance to a Prometheus push gateway on a task of its
+/// own, for instances Prometheus can't scrape. Stops when dropped.
+#[derive(Debug)]
+pub struct MetricsPusher {
+    handle: JoinHandle<()>,
+}
+
+impl MetricsPusher {
+    /// Push every `interval_secs`, grouped by `instance`
+    pub fn spawn(
+        config: &PushGatewayConfig,
+        instance: &str,
+        calls: mpsc::Sender<SocketCall>,
+    ) -> HexarResult<Self> {
+        let target = PushTarget::parse(config, instance)?;
+        let interval = Duration::from_secs(config.interval_secs.max(1));
+        let handle = tokio::spawn(async move {
+            let mut ticks = tokio::time::interval(interval);
+            ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticks.tick().await;
+                let metrics = match collect(&calls, "pushgateway").await {
+                    Ok(metrics) => metrics,
+                    Err(e) => {
+                        debug!("No metrics to push: {}", e);
+                        continue;
+                    }
+                };
+                match tokio::time::timeout(PUSH_TIMEOUT, target.push(&metrics)).await {
+                    Ok(Ok(())) => debug!("Pushed metrics to {}:{}", target.host, target.port),
+                    Ok(Err(e)) => warn!("Metrics push failed: {}", e),
+                    Err(_) => warn!("Metrics push timed out after {:?}", PUSH_TIMEOUT),
+                }
+            }
+        });
+        Ok(Self { handle })
+    }
+}
+
+impl Drop for MetricsPusher {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RadarConfig;
+    use crate::radar_controller::RadarController;
+
+    #[test]
+    fn test_encode_snapshot() {
+        let controller = RadarController::new(RadarConfig::default()).unwrap();
+        let mut scan = controller.get_scan_metrics().clone();
+        scan.record_cycle(Duration::from_millis(20), 0, &Default::default());
+        let snapshot = MetricsSnapshot {
+            uptime: Duration::from_secs(90),
+            scanning: true,
+            health: controller.health(),
+            scan,
+            antenna_loads: vec![AntennaLoad {
+                antenna_id: 2,
+                targets: 1,
+                capacity: 3,
+                rejected: 0,
+            }],
+            emergency_stop: false,
+            active_alerts: Vec::new(),
+            errors: ErrorMetrics {
+                total_errors: 0,
+                error_rate_per_minute: 0.0,
+                recent_errors: Vec::new(),
+                critical_errors: 0,
+            },
+        };
+        let text = snapshot.encode();
+        let lines: Vec<&str> = text.lines().collect();
+        for expected in [
+            "hexar_uptime_seconds 90",
+            "hexar_controller_state{state=\"uninitialized\"} 1",
+            "hexar_scan_cycles_total 1",
+            "hexar_scan_cycle_duration_seconds_bucket{le=\"0.025\"} 1",
+            "hexar_antenna_capacity{antenna=\"2\"} 3",
+            "hexar_alerts_active{severity=\"critical\"} 0",
+        ] {
+            assert!(lines.contains(&expected), "{} missing", expected);
+        }
+        // Every family described once
+        let types = lines.iter().filter(|line| line.starts_with("# TYPE"));
+        let names: Vec<&str> = types.map(|line| line.split(' ').nth(2).unwrap()).collect();
+        let mut unique = names.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(names.len(), unique.len());
+    }
+
+    #[test]
+    fn test_push_target() {
+        let config = PushGatewayConfig {
+            url: "http://gateway.local:9091/prefix/".to_string(),
+            ..PushGatewayConfig::default()
+        };
+        assert_eq!(
+            PushTarget::parse(&config, "abc").unwrap(),
+            PushTarget {
+                host: "gateway.local".to_string(),
+                port: 9091,
+                path: "/prefix/metrics/job/hexar/instance/abc".to_string(),
+            }
+        );
+        let config = PushGatewayConfig {
+            url: "http://gateway.local".to_string(),
+            ..PushGatewayConfig::default()
+        };
+        assert_eq!(PushTarget::parse(&config, "abc").unwrap().port, 80);
+        for url in ["https://gateway.local", "http://:9091", "http://host:port"] {
+            let config = PushGatewayConfig {
+                url: url.to_string(),
+                ..PushGatewayConfig::default()
+            };
+            assert!(PushTarget::parse(&config, "abc").is_err(), "{}", url);
+        }
+    }
+}
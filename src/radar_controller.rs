@@ -1,396 +1,2055 @@
-use crate::config::RadarConfig;
-use crate::error::{HexarError, HexarResult};
-use crate::scanner::{FrequencyScanner, FrequencyRange, ScanResult};
-use crate::tracker::{MultiTargetTracker, TrackedTarget};
-use anyhow::Result;
-use std::time::{Duration, Instant};
-use tracing::{info, error, debug};
-use chrono::Utc;
-use uuid::Uuid;
-use nalgebra::Vector2;
-
-#[derive(Debug, Clone)]
-pub struct RadarController {
-    config: RadarConfig,
-    scanner: FrequencyScanner,
-    tracker: MultiTargetTracker,
-    system_id: Uuid,
-    initialized: bool,
-    current_scan_mode: ScanMode,
-    last_scan_time: Option<Instant>,
-    scan_results: Vec<ScanResult>,
-}
-
-#[derive(Debug, Clone)]
-pub enum ControllerState {
-    Uninitialized,
-    Initializing,
-    Ready,
-    Scanning,
-    Error(String),
-    Shutdown,
-}
-
-#[derive(Debug, Clone)]
-pub struct ScanCycleResult {
-    pub scan_id: Uuid,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-    pub scan_results: Vec<ScanResult>,
-    pub targets_detected: Vec<TrackedTarget>,
-    pub scan_duration: Duration,
-    pub signals_processed: usize,
-}
-
-impl RadarController {
-    pub fn new(config: RadarConfig) -> HexarResult<Self> {
-        let frequency_range = FrequencyRange {
-            start: config.frequency_range.start_mhz,
-            end: config.frequency_range.end_mhz,
-            step: config.frequency_range.step_mhz,
-        };
-        
-        let scanner = FrequencyScanner::new(frequency_range, config.signal_processing.threshold_db);
-        let tracker = MultiTargetTracker::new(config.antenna_count);
-        
-        Ok(Self {
-            config,
-            scanner,
-            tracker,
-            system_id: Uuid::new_v4(),
-            initialized: false,
-            current_scan_mode: ScanMode::Continuous,
-            last_scan_time: None,
-            scan_results: Vec::new(),
-        })
-    }
-    
-    pub async fn initialize(&mut self) -> Result<()> {
-        info!("Initializing radar controller...");
-        
-        self.set_state(ControllerState::Initializing).await?;
-        
-        // Initialize antenna systems
-        self.initialize_antennas().await?;
-        
-        // Validate frequency range
-        self.validate_frequency_range().await?;
-        
-        // Perform self-test
-        self.run_self_test().await?;
-        
-        // Initialize scanner
-        self.scanner.clear_readings();
-        
-        // Clear tracker
-        self.tracker.clear_all_targets();
-        
-        self.initialized = true;
-        self.set_state(ControllerState::Ready).await?;
-        
-        info!("Radar controller initialized successfully");
-        Ok(())
-    }
-    
-    pub async fn run_scan_cycle(&mut self) -> Result<ScanCycleResult> {
-        if !self.initialized {
-            return Err(HexarError::RadarInitializationFailed(
-                "Radar controller not initialized".to_string()
-            ).into());
-        }
-        
-        let scan_start = Instant::now();
-        let scan_id = Uuid::new_v4();
-        
-        self.set_state(ControllerState::Scanning).await?;
-        
-        debug!("Starting scan cycle {}", scan_id);
-        
-        // Perform frequency scan
-        let scan_results = self.scanner.full_scan_cycle();
-        
-        // Process scan results and update targets
-        let mut targets_detected = Vec::new();
-        let mut signals_processed = 0;
-        
-        for scan_result in &scan_results {
-            signals_processed += 1;
-            
-            // Convert scan result to target position (simplified)
-            let position = self.frequency_to_position(scan_result.frequency);
-            
-            // Determine which antenna would detect this signal
-            let antenna_id = self.frequency_to_antenna_id(scan_result.frequency);
-            
-            // Update or create target
-            if let Some(target_id) = self.find_nearby_target(&position) {
-                if self.tracker.update_target(target_id, position) {
-                    if let Some(target) = self.tracker.get_all_targets()
-                        .iter()
-                        .find(|t| t.id == target_id) {
-                        targets_detected.push((*target).clone());
-                    }
-                }
-            } else {
-                if let Some(new_target_id) = self.tracker.add_target(antenna_id, position) {
-                    if let Some(target) = self.tracker.get_all_targets()
-                        .iter()
-                        .find(|t| t.id == new_target_id) {
-                        targets_detected.push((*target).clone());
-                    }
-                }
-            }
-        }
-        
-        // Remove lost targets
-        self.tracker.remove_lost_targets(Duration::from_secs(30));
-        
-        let scan_duration = scan_start.elapsed();
-        self.last_scan_time = Some(scan_start);
-        self.scan_results.extend(scan_results.clone());
-        
-        // Keep scan results manageable
-        if self.scan_results.len() > 1000 {
-            self.scan_results.drain(0..500);
-        }
-        
-        let result = ScanCycleResult {
-            scan_id,
-            timestamp: Utc::now(),
-            scan_results,
-            targets_detected,
-            scan_duration,
-            signals_processed,
-        };
-        
-        debug!("Scan cycle completed: {:.2}ms, {} signals, {} targets", 
-               scan_duration.as_millis(), signals_processed, result.targets_detected.len());
-        
-        self.set_state(ControllerState::Ready).await?;
-        
-        Ok(result)
-    }
-    
-    pub async fn start_continuous_scan(&mut self) -> Result<()> {
-        info!("Starting continuous scanning mode");
-        
-        if !self.initialized {
-            return Err(HexarError::RadarInitializationFailed(
-                "Radar controller not initialized".to_string()
-            ).into());
-        }
-        
-        self.current_scan_mode = ScanMode::Continuous;
-        
-        loop {
-            match self.run_scan_cycle().await {
-                Ok(result) => {
-                    debug!("Continuous scan: {} targets detected", result.targets_detected.len());
-                },
-                Err(e) => {
-                    error!("Continuous scan failed: {}", e);
-                    // Wait before retrying
-                    tokio::time::sleep(Duration::from_secs(5)).await;
-                }
-            }
-            
-            // Rate limiting based on configuration
-            let scan_interval = Duration::from_millis((1000.0 / self.config.scan_rate_hz()) as u64);
-            tokio::time::sleep(scan_interval).await;
-        }
-    }
-    
-    pub async fn stop_continuous_scan(&mut self) -> Result<()> {
-        info!("Stopping continuous scanning");
-        self.current_scan_mode = ScanMode::OnDemand;
-        Ok(())
-    }
-    
-    pub async fn shutdown(&mut self) -> Result<()> {
-        info!("Shutting down radar controller...");
-        
-        self.set_state(ControllerState::Shutdown).await?;
-        
-        // Stop any ongoing operations
-        self.stop_continuous_scan().await?;
-        
-        // Power down antennas
-        self.shutdown_antennas().await?;
-        
-        // Clear data
-        self.scan_results.clear();
-        self.tracker.clear_all_targets();
-        
-        self.initialized = false;
-        
-        info!("Radar controller shutdown complete");
-        Ok(())
-    }
-    
-    pub fn get_state(&self) -> ControllerState {
-        if !self.initialized {
-            ControllerState::Uninitialized
-        } else {
-            ControllerState::Ready
-        }
-    }
-    
-    pub fn get_current_targets(&self) -> Vec<&TrackedTarget> {
-        self.tracker.get_all_targets()
-    }
-    
-    pub fn get_falling_targets(&self) -> Vec<&TrackedTarget> {
-        self.tracker.get_falling_targets()
-    }
-    
-    pub fn get_scan_statistics(&self) -> ScanStatistics {
-        ScanStatistics {
-            total_scans: self.scan_results.len(),
-            last_scan_time: self.last_scan_time,
-            current_target_count: self.tracker.get_target_count(),
-            average_scan_duration: self.calculate_average_scan_duration(),
-            signals_per_scan: self.calculate_signals_per_scan(),
-        }
-    }
-    
-    // Private helper methods
-    async fn set_state(&self, state: ControllerState) -> Result<()> {
-        debug!("Radar controller state: {:?}", state);
-        // TODO: Implement state change logging and monitoring
-        Ok(())
-    }
-    
-    async fn initialize_antennas(&self) -> Result<()> {
-        info!("Initializing {} antenna systems", self.config.antenna_count);
-        
-        // TODO: Implement actual antenna initialization
-        for i in 0..self.config.antenna_count {
-            debug!("Initializing antenna {}", i);
-            // Initialize antenna hardware, check connections, etc.
-        }
-        
-        Ok(())
-    }
-    
-    async fn validate_frequency_range(&self) -> Result<()> {
-        let range = &self.config.frequency_range;
-        
-        if range.start_mhz >= range.end_mhz {
-            return Err(HexarError::ConfigurationError(
-                "Invalid frequency range: start >= end".to_string()
-            ).into());
-        }
-        
-        if range.step_mhz <= 0.0 {
-            return Err(HexarError::ConfigurationError(
-                "Invalid frequency step: must be positive".to_string()
-            ).into());
-        }
-        
-        info!("Frequency range validated: {:.1} - {:.1} MHz (step: {:.1} MHz)", 
-              range.start_mhz, range.end_mhz, range.step_mhz);
-        
-        Ok(())
-    }
-    
-    async fn run_self_test(&self) -> Result<()> {
-        info!("Running radar system self-test...");
-        
-        // TODO: Implement actual self-test procedures
-        // - Test antenna connectivity
-        // - Test signal generation
-        // - Test data acquisition
-        // - Test signal processing
-        
-        debug!("Self-test completed successfully");
-        Ok(())
-    }
-    
-    async fn shutdown_antennas(&self) -> Result<()> {
-        info!("Shutting down antenna systems");
-        
-        // TODO: Implement actual antenna shutdown
-        for i in 0..self.config.antenna_count {
-            debug!("Shutting down antenna {}", i);
-        }
-        
-        Ok(())
-    }
-    
-    fn frequency_to_position(&self, frequency: f32) -> Vector2<f32> {
-        // Simplified conversion from frequency to position
-        // In a real system, this would involve complex antenna array processing
-        
-        let normalized_freq = (frequency - self.config.frequency_range.start_mhz) / 
-            (self.config.frequency_range.end_mhz - self.config.frequency_range.start_mhz);
-        
-        // Convert to x,y coordinates (simplified hexagonal arrangement)
-        let angle = normalized_freq * 2.0 * std::f32::consts::PI;
-        let radius = 10.0; // Assume 10 meter detection radius
-        
-        Vector2::new(
-            radius * angle.cos(),
-            radius * angle.sin(),
-        )
-    }
-    
-    fn frequency_to_antenna_id(&self, frequency: f32) -> u8 {
-        // Determine which antenna would detect a given frequency
-        let normalized_freq = (frequency - self.config.frequency_range.start_mhz) / 
-            (self.config.frequency_range.end_mhz - self.config.frequency_range.start_mhz);
-        
-        (normalized_freq * self.config.antenna_count as f32) as u8 % self.config.antenna_count
-    }
-    
-    fn find_nearby_target(&self, position: &Vector2<f32>) -> Option<u32> {
-        let threshold = 2.0; // 2 meter threshold
-        
-        for target in self.tracker.get_all_targets() {
-            let distance = (target.position - position).norm();
-            if distance < threshold {
-                return Some(target.id);
-            }
-        }
-        
-        None
-    }
-    
-    fn calculate_average_scan_duration(&self) -> Duration {
-        if self.scan_results.is_empty() {
-            return Duration::ZERO;
-        }
-        
-        // This is a placeholder - in reality we'd track actual durations
-        Duration::from_millis(100)
-    }
-    
-    fn calculate_signals_per_scan(&self) -> f32 {
-        if self.scan_results.is_empty() {
-            return 0.0;
-        }
-        
-        self.scan_results.len() as f32 / 10.0 // Assume 10 scans
-    }
-}
-
-#[derive(Debug, Clone)]
-pub struct ScanStatistics {
-    pub total_scans: usize,
-    pub last_scan_time: Option<Instant>,
-    pub current_target_count: usize,
-    pub average_scan_duration: Duration,
-    pub signals_per_scan: f32,
-}
-
-// Extension methods for RadarConfig
-impl RadarConfig {
-    pub fn scan_rate_hz(&self) -> f32 {
-        match self.scan_mode {
-            ScanMode::Continuous => 10.0,
-            ScanMode::Intermittent => 5.0,
-            ScanMode::OnDemand => 1.0,
-        }
-    }
-}
-
-// Re-export scan modes
-pub use crate::config::ScanMode;
+use crate::calibration::CalibrationTable;
+use crate::clock::{Clock, SimulatedClock};
+use crate::config::{RadarConfig, SignalSourceConfig};
+use crate::control::{AuditEntry, AuditLog, ControlCommand};
+use crate::error::{HexarError, HexarResult};
+use crate::frame_log::{FrameRecorder, FrameReplay};
+use crate::antenna_array::AntennaArray;
+use crate::bands::{channelize, Band, Channel};
+use crate::fall_alert::FallAlertKind;
+use crate::fusion::SensorFusion;
+use crate::health::{AntennaHealth, HealthReport, LastError, TrackerHealth};
+use crate::interference::{InterferenceCoordinator, SlotPlan, SlotSchedule};
+use crate::metrics::{AssociationStatistics, ScanMetrics};
+use crate::occupancy::{OccupancyAnalyzer, ScanStatisticsReport};
+use crate::pipeline::{stage_channel, SinkStage, StageSender};
+use crate::processing::{ProcessingChain, ProcessingStage, StageContext, StageMeasurement};
+use crate::recorder::{CsvRecorder, RotationPolicy};
+use crate::replay::{ReplaySource, ReplayTiming};
+use crate::scan_stream::ScanLimit;
+use crate::serial_radar::{AntennaSelfTest, LinkState, SensorFrame, SensorReader, SerialRadar};
+use crate::scanner::{Dwell, FrequencyScanner, FrequencyRange, ScanResult, ThresholdMode};
+use crate::scenario::{Scenario, ScenarioGenerator, SimulatedFall, SimulationReport};
+use crate::schedule::{AdaptiveRate, DutyCycle, ScanSchedule, ScanWindow};
+use crate::signal_source::{SignalSource, SimulatedSource};
+use crate::target_class::gate_micro_motion;
+use crate::tracker::{AntennaLoad, FallEvent, MultiTargetTracker, TargetState, TrackStatus, TrackedTarget, TrackerSnapshot};
+use anyhow::Result;
+use std::collections::{HashMap, HashSet};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, mpsc as async_mpsc, watch, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{info, warn, error, debug};
+use chrono::Utc;
+use uuid::Uuid;
+use nalgebra::Vector2;
+
+#[derive(Debug)]
+pub struct RadarController {
+    config: RadarConfig,
+    /// Configuration as loaded, before any control command changed it
+    initial_config: RadarConfig,
+    scanner: FrequencyScanner,
+    tracker: MultiTargetTracker,
+    fusion: SensorFusion,
+    /// Antenna layout matching the fusion's poses, for the coverage of failed antennas
+    array: AntennaArray,
+    #[allow(dead_code)]
+    system_id: Uuid,
+    initialized: bool,
+    state: ControllerState,
+    state_changed_at: Instant,
+    current_scan_mode: ScanMode,
+    last_scan_time: Option<Instant>,
+    metrics: ScanMetrics,
+    /// Duty cycle of the channels in the scanned range, from every reading of the scan cycles
+    occupancy: OccupancyAnalyzer,
+    scan_results: Vec<ScanResult>,
+    schedule: ScanSchedule,
+    duty_cycle: DutyCycle,
+    /// Rate set by the activity in the space, the scan mode's fixed rate when unset
+    adaptive_rate: Option<AdaptiveRate>,
+    /// Share of time the radar may be busy, bounding the adaptive rate
+    power_duty_cycle: DutyCycle,
+    last_state_save: Option<Instant>,
+    /// Radar modules of the configured sensors while idle, self-tested here and handed to
+    /// `readers` by initialization, except the ones the self-test excluded. Targets only come
+    /// from their frames, the scanner readings never reach position fusion.
+    sensors: Vec<SerialRadar>,
+    /// The same modules while initialized, each read by a task of its own
+    readers: Vec<SensorReader>,
+    /// Parsed frames from the readers or a replay, queued by the policy of the pipeline config
+    sensor_frames: async_mpsc::Receiver<SensorFrame>,
+    sensor_frame_sender: StageSender<SensorFrame>,
+    /// Recorder until the first initialization hands it to `frame_sink`
+    frame_recorder: Option<FrameRecorder>,
+    /// Records frames on a thread of its own so a slow disk can't hold up the scan cycle
+    frame_sink: Option<SinkStage<SensorFrame>>,
+    self_test: Option<SelfTestReport>,
+    /// Link state of every antenna read as of the last scan cycle
+    antenna_links: HashMap<u8, LinkState>,
+    /// Antennas that failed the self-test, left out until the next initialization
+    excluded_antennas: HashSet<u8>,
+    /// Failed antennas as last published
+    failed_antennas: Vec<u8>,
+    /// Confirmed targets in each zone by name, as last published
+    zone_occupants: HashMap<String, Vec<u32>>,
+    /// User stages run on each cycle's measurements before the tracker update
+    processing: ProcessingChain,
+    /// Control commands received at runtime
+    audit: AuditLog,
+    last_error: Option<LastError>,
+    /// Commands applied since startup, latest of each kind, replayed when a snapshot is restored
+    overrides: Vec<ControlCommand>,
+    /// Plays a frame recording or a scenario into `sensor_frames` in place of the readers
+    frame_replay: Option<JoinHandle<HexarResult<()>>>,
+    /// Has the readers' modules take turns transmitting while the interference config is set
+    coordinator: Option<InterferenceCoordinator>,
+    /// Slots of the running coordinator
+    transmit_slots: Option<SlotPlan>,
+    /// Stops the background scan task while one is running
+    scan_shutdown: Option<watch::Sender<bool>>,
+    events: broadcast::Sender<RadarEvent>,
+    fall_events: mpsc::Receiver<FallEvent>,
+}
+
+/// Longest sleep while waiting for a scan window, so adjustments of the system clock are
+/// picked up
+const MAX_SCHEDULE_WAIT: Duration = Duration::from_secs(60);
+
+/// Smallest share of time left to the scanner when interleaving with the radar
+const MIN_SCAN_FRACTION: f32 = 0.05;
+
+/// Events kept for subscribers that fall behind, older ones are dropped for them
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// How long a module has to answer its firmware query and report targets during self-test
+const SELF_TEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Speed a target counts as moving above for the adaptive rate when not configured, in m/s
+const DEFAULT_MOVING_SPEED: f32 = 1.0;
+
+/// How long the space stays empty before the adaptive rate drops to idle when not configured
+const DEFAULT_IDLE_AFTER_SECS: u64 = 30;
+
+/// Confidence factor of targets a failed antenna would have seen when not configured
+const DEFAULT_DEGRADED_CONFIDENCE: f32 = 0.5;
+
+/// Pause after a failed scan cycle before the background task retries
+const SCAN_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// How often the tracker state is saved when a state file is configured
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Readings the channel occupancy statistics cover
+const OCCUPANCY_WINDOW: Duration = Duration::from_secs(3600);
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum ControllerState {
+    Uninitialized,
+    Initializing,
+    Ready,
+    Scanning,
+    /// Initialization failed, the controller can be initialized again or scanning retried
+    Error(String),
+    Shutdown,
+}
+
+impl ControllerState {
+    /// Whether the lifecycle allows moving from this state to `next`
+    pub fn can_transition_to(&self, next: &ControllerState) -> bool {
+        use ControllerState::*;
+        matches!(
+            (self, next),
+            (Uninitialized, Initializing | Shutdown)
+                | (Initializing, Ready | Error(_))
+                | (Ready, Scanning | Initializing | Shutdown)
+                | (Scanning, Ready | Error(_) | Shutdown)
+                | (Error(_), Initializing | Scanning | Shutdown)
+                | (Shutdown, Initializing)
+        )
+    }
+}
+
+/// What subscribers of [`RadarController::subscribe`] receive
+#[derive(Debug, Clone)]
+pub enum RadarEvent {
+    ScanCompleted {
+        scan_id: Uuid,
+        timestamp: chrono::DateTime<chrono::Utc>,
+        scan_duration: Duration,
+        signals_processed: usize,
+        targets_detected: usize,
+    },
+    /// A confirmed target was measured in the last scan cycle
+    TargetUpdated(TrackedTarget),
+    /// A target expired, with its last state
+    TargetLost(TrackedTarget),
+    /// A fall was suspected, confirmed or cleared
+    FallDetected(FallEvent),
+    StateChanged {
+        from: ControllerState,
+        to: ControllerState,
+    },
+    /// The serial link of an antenna failed, its targets are missing until it reconnects
+    AntennaDisconnected {
+        antenna_id: u8,
+        error: String,
+    },
+    AntennaReconnected {
+        antenna_id: u8,
+    },
+    /// The set of failed antennas changed, empty once all of them work again. `coverage` is the
+    /// share of the array's area the working antennas still cover.
+    DegradedModeChanged {
+        failed_antennas: Vec<u8>,
+        coverage: f32,
+    },
+    /// Confirmed targets entered or left a zone, `occupants` are all of them inside it now
+    ZoneOccupancyChanged {
+        zone: String,
+        entered: Vec<u32>,
+        left: Vec<u32>,
+        occupants: Vec<u32>,
+    },
+}
+
+/// Per-antenna results of the initialization self-test
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SelfTestReport {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub antennas: Vec<AntennaSelfTest>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        self.antennas.iter().all(AntennaSelfTest::passed)
+    }
+    
+    pub fn failed_antennas(&self) -> Vec<u8> {
+        self.antennas
+            .iter()
+            .filter(|antenna| !antenna.passed())
+            .map(|antenna| antenna.antenna_id)
+            .collect()
+    }
+}
+
+/// What the controller saves periodically and on shutdown to pick up where it left off
+/// after a restart
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ControllerSnapshot {
+    pub taken_at: SystemTime,
+    pub tracker: TrackerSnapshot,
+    pub metrics: ScanMetrics,
+    /// Configuration in effect, including the changes made at runtime
+    pub config: RadarConfig,
+    /// Control commands behind those changes, applied again over the configuration on restore
+    pub overrides: Vec<ControlCommand>,
+    /// Configuration the controller was started with. Overrides of settings edited there since
+    /// are dropped on restore, the edit wins.
+    #[serde(default)]
+    pub initial_config: Option<RadarConfig>,
+    pub self_test: Option<SelfTestReport>,
+    pub failed_antennas: Vec<u8>,
+    /// Antennas whose serial link was down
+    pub disconnected_antennas: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanCycleResult {
+    pub scan_id: Uuid,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub scan_results: Vec<ScanResult>,
+    pub targets_detected: Vec<TrackedTarget>,
+    /// Detected targets a failed antenna would have seen too, reported with lowered confidence
+    pub reduced_confidence: Vec<u32>,
+    pub scan_duration: Duration,
+    pub signals_processed: usize,
+}
+
+impl RadarController {
+    pub fn new(config: RadarConfig) -> HexarResult<Self> {
+        let frequency_range = FrequencyRange {
+            start: config.frequency_range.start_mhz,
+            end: config.frequency_range.end_mhz,
+            step: config.frequency_range.step_mhz,
+        };
+        
+        let occupancy = OccupancyAnalyzer::new(
+            scan_channels(&frequency_range),
+            config.signal_processing.threshold_db,
+            OCCUPANCY_WINDOW,
+        );
+        let source = open_signal_source(&config.signal_source)?;
+        let mut scanner = FrequencyScanner::with_source(
+            frequency_range,
+            config.signal_processing.threshold_db,
+            source,
+        );
+        if let Some(margin_db) = config.signal_processing.adaptive_margin_db {
+            scanner.set_threshold_mode(ThresholdMode::Adaptive { margin_db });
+        }
+        if let Some(dwell) = &config.signal_processing.dwell {
+            scanner.set_dwell(Dwell {
+                samples: dwell.samples,
+                interval: Duration::from_millis(dwell.interval_ms),
+                detector: dwell.detector,
+            });
+        }
+        if let Some(path) = &config.signal_processing.calibration_file {
+            scanner.set_calibration(Some(CalibrationTable::load(path)?));
+        }
+        if let Some(capacity) = config.signal_processing.history_capacity {
+            scanner.set_history_capacity(capacity);
+        }
+        if let Some(recording) = &config.recording {
+            let recorder = CsvRecorder::new(
+                &recording.directory,
+                "readings",
+                RotationPolicy {
+                    max_bytes: recording.max_file_size_mb * 1024 * 1024,
+                    max_age: Duration::from_secs(recording.max_file_age_minutes * 60),
+                    max_files: recording.max_files,
+                },
+            )?;
+            scanner.set_sink(Some(Box::new(recorder)));
+        }
+        let (schedule, duty_cycle) = match &config.scan_schedule {
+            Some(schedule) => {
+                let windows = schedule
+                    .windows
+                    .iter()
+                    .map(|window| window.parse::<ScanWindow>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(HexarError::ConfigurationError)?;
+                let duty_cycle = if schedule.interleave_with_radar {
+                    DutyCycle::interleaved(config.power_settings.duty_cycle, MIN_SCAN_FRACTION)
+                } else {
+                    DutyCycle::default()
+                };
+                (ScanSchedule::new(windows, schedule.utc_offset_minutes), duty_cycle)
+            }
+            None => (ScanSchedule::default(), DutyCycle::default()),
+        };
+        let adaptive_rate = config.adaptive_rate.as_ref().map(|rate| AdaptiveRate::new(
+            rate.idle_rate_hz,
+            rate.present_rate_hz,
+            rate.moving_rate_hz,
+            rate.moving_speed.unwrap_or(DEFAULT_MOVING_SPEED),
+            Duration::from_secs(rate.idle_after_secs.unwrap_or(DEFAULT_IDLE_AFTER_SECS)),
+        ));
+        let power_duty_cycle = DutyCycle::new(config.power_settings.duty_cycle);
+        let mut tracker = MultiTargetTracker::with_config(config.antenna_count, config.tracking.clone());
+        let fall_events = tracker.events();
+        let sensors = open_sensors(&config, &tracker)?;
+        let mut fusion = SensorFusion::from_config(&config.fusion);
+        let mut array = AntennaArray::from_config(
+            config.antenna_count,
+            &config.antenna_array.clone().unwrap_or_default(),
+        );
+        if config.antenna_array.is_some() || config.simulation.is_some() {
+            for (antenna_id, pose) in array.poses() {
+                if fusion.get_pose(antenna_id).is_none() {
+                    fusion.set_pose(antenna_id, *pose);
+                }
+            }
+        }
+        for sensor in &config.sensors {
+            if let Some(pose) = sensor.pose {
+                fusion.set_pose(sensor.antenna_id, pose);
+            }
+        }
+        for antenna_id in 0..config.antenna_count {
+            if let Some(pose) = fusion.get_pose(antenna_id) {
+                array.set_pose(antenna_id, *pose);
+            }
+        }
+        let (sensor_frame_sender, sensor_frames) = stage_channel(
+            config.pipeline.frame_capacity,
+            config.pipeline.frame_overflow,
+        );
+        let frame_recorder = config.frame_recording.as_ref().map(FrameRecorder::create).transpose()?;
+        
+        Ok(Self {
+            initial_config: config.clone(),
+            config,
+            scanner,
+            tracker,
+            fusion,
+            array,
+            system_id: Uuid::new_v4(),
+            initialized: false,
+            state: ControllerState::Uninitialized,
+            state_changed_at: Instant::now(),
+            current_scan_mode: ScanMode::Continuous,
+            last_scan_time: None,
+            metrics: ScanMetrics::new(),
+            occupancy,
+            scan_results: Vec::new(),
+            schedule,
+            duty_cycle,
+            adaptive_rate,
+            power_duty_cycle,
+            last_state_save: None,
+            sensors,
+            readers: Vec::new(),
+            sensor_frames,
+            sensor_frame_sender,
+            frame_recorder,
+            frame_sink: None,
+            self_test: None,
+            antenna_links: HashMap::new(),
+            excluded_antennas: HashSet::new(),
+            failed_antennas: Vec::new(),
+            zone_occupants: HashMap::new(),
+            processing: ProcessingChain::default(),
+            audit: AuditLog::default(),
+            last_error: None,
+            overrides: Vec::new(),
+            frame_replay: None,
+            coordinator: None,
+            transmit_slots: None,
+            scan_shutdown: None,
+            events: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            fall_events,
+        })
+    }
+    
+    pub async fn initialize(&mut self) -> Result<()> {
+        info!("Initializing radar controller...");
+        
+        self.set_state(ControllerState::Initializing).await?;
+        
+        if let Err(e) = self.check_hardware().await {
+            self.set_state(ControllerState::Error(e.to_string())).await?;
+            return Err(e);
+        }
+        
+        // Initialize scanner
+        self.scanner.clear_readings();
+        
+        // Resume from the saved state, or start from scratch
+        self.tracker.clear_all_targets();
+        if let Err(e) = self.restore_snapshot().await {
+            self.record_error(format!("Failed to restore controller state: {}", e));
+        }
+        
+        self.initialized = true;
+        self.set_state(ControllerState::Ready).await?;
+        
+        info!("Radar controller initialized successfully");
+        Ok(())
+    }
+    
+    /// Antenna initialization, configuration checks and self-test
+    async fn check_hardware(&mut self) -> Result<()> {
+        // Validate frequency range
+        self.validate_frequency_range().await?;
+        
+        // Perform self-test
+        self.run_self_test().await?;
+        
+        // Initialize antenna systems
+        self.initialize_antennas().await
+    }
+    
+    pub async fn run_scan_cycle(&mut self) -> Result<ScanCycleResult> {
+        if !self.initialized {
+            return Err(HexarError::RadarInitializationFailed(
+                "Radar controller not initialized".to_string()
+            ).into());
+        }
+        
+        let scan_start = Instant::now();
+        let scan_id = Uuid::new_v4();
+        
+        self.set_state(ControllerState::Scanning).await?;
+        
+        debug!("Starting scan cycle {}", scan_id);
+        
+        // Perform frequency scan, yielding to other tasks between measurements
+        let mut scan_results = Vec::new();
+        let mut stream = self.scanner.scan_stream(ScanLimit::Cycles(1));
+        while let Some(result) = stream.next().await {
+            scan_results.push(result);
+        }
+        scan_results.sort_by(|a, b| b.strength.total_cmp(&a.strength));
+        for reading in self.scanner.get_history().iter().rev().take_while(|r| r.timestamp >= scan_start) {
+            self.occupancy.observe(reading);
+        }
+        
+        // Process scan results and update targets
+        let mut targets_detected = Vec::new();
+        // A scanner reading has no range or angle to place a target with, only the radar
+        // modules' frames below give positions
+        let signals_processed = scan_results.len();
+        let mut measurements = Vec::new();
+        
+        self.check_antenna_links();
+        if let Err(e) = self.update_degraded_mode() {
+            self.set_state(ControllerState::Error(e.to_string())).await?;
+            return Err(e.into());
+        }
+        
+        // Targets reported by the radar modules since the last cycle, in their antenna frames.
+        // Each frame lists every target the module sees, so only the latest one counts.
+        let mut latest_frames: HashMap<u8, SensorFrame> = HashMap::new();
+        while let Ok(frame) = self.sensor_frames.try_recv() {
+            if let Some(sink) = &self.frame_sink {
+                if let Err(e) = sink.send(frame.clone()).await {
+                    self.record_error(format!("Failed to record frame: {}", e));
+                }
+            }
+            if latest_frames.insert(frame.antenna_id, frame).is_some() {
+                self.metrics.superseded_frames += 1;
+            }
+        }
+        self.metrics.dropped_frames += self.sensor_frame_sender.take_dropped();
+        if let Some(sink) = &self.frame_sink {
+            self.metrics.sink_dropped += sink.get_sender().take_dropped();
+        }
+        for frame in latest_frames.values() {
+            measurements.extend(frame.measurements.iter().map(|target| StageMeasurement {
+                antenna_id: frame.antenna_id,
+                position: target.position,
+                speed: target.speed,
+                moving: Some(target.moving),
+            }));
+        }
+        
+        let context = StageContext::new(Utc::now(), &self.fusion);
+        let failed_stages = self.processing.run(&mut measurements, &context);
+        for (stage, error) in failed_stages {
+            self.record_error(format!("Processing stage {} failed: {}", stage, error));
+        }
+        let measurements: Vec<(u8, Vector2<f32>)> = measurements
+            .into_iter()
+            .map(|measurement| (measurement.antenna_id, measurement.position))
+            .collect();
+        
+        // Assign the whole frame to targets at once in the room frame, creating targets for the
+        // rest. Only confirmed targets are reported, once even when several antennas saw them.
+        let existing: HashSet<u32> = self.tracker.get_all_targets().iter().map(|t| t.id).collect();
+        let mut associations = AssociationStatistics::default();
+        let mut target_ids: Vec<u32> = self.fusion.process_frame(&mut self.tracker, &measurements)
+            .into_iter()
+            .inspect(|updated| match updated {
+                Some(id) if existing.contains(id) => associations.associated += 1,
+                Some(_) => associations.created += 1,
+                None => associations.unassociated += 1,
+            })
+            .flatten()
+            .collect();
+        target_ids.sort_unstable();
+        target_ids.dedup();
+        self.observe_still_detections(latest_frames.values());
+        self.observe_gate_energies(latest_frames.values());
+        for target_id in target_ids {
+            if let Some(target) = self.tracker.get_confirmed_targets()
+                .iter()
+                .find(|t| t.id == target_id) {
+                targets_detected.push((*target).clone());
+            }
+        }
+        if !self.config.zones.is_empty() {
+            let zones = &self.config.zones;
+            targets_detected.retain(|target| zones.iter().any(|zone| zone.contains(target.position)));
+        }
+        
+        // Fewer antennas see these than the array was laid out for
+        let mut reduced_confidence = Vec::new();
+        if !self.failed_antennas.is_empty() {
+            let factor = self.config.degraded_mode
+                .as_ref()
+                .and_then(|degraded| degraded.confidence_factor)
+                .unwrap_or(DEFAULT_DEGRADED_CONFIDENCE);
+            for target in &mut targets_detected {
+                if self.failed_antennas.iter().any(|&id| self.array.covers(id, target.position)) {
+                    target.confidence *= factor;
+                    reduced_confidence.push(target.id);
+                }
+            }
+        }
+        for target in &targets_detected {
+            self.publish(RadarEvent::TargetUpdated(target.clone()));
+        }
+        
+        // Remove lost targets
+        for target in self.tracker.remove_lost_targets() {
+            self.publish(RadarEvent::TargetLost(target));
+        }
+        self.update_zone_occupancy();
+        while let Ok(event) = self.fall_events.try_recv() {
+            self.publish(RadarEvent::FallDetected(event));
+        }
+        
+        if self.last_state_save.is_none_or(|saved| saved.elapsed() >= STATE_SAVE_INTERVAL) {
+            if let Err(e) = self.save_snapshot().await {
+                self.record_error(format!("Failed to save controller state: {}", e));
+            }
+        }
+        
+        let scan_duration = scan_start.elapsed();
+        self.last_scan_time = Some(scan_start);
+        self.metrics.record_cycle(scan_duration, signals_processed, &associations);
+        self.scan_results.extend(scan_results.clone());
+        
+        // Keep scan results manageable
+        if self.scan_results.len() > 1000 {
+            self.scan_results.drain(0..500);
+        }
+        
+        let result = ScanCycleResult {
+            scan_id,
+            timestamp: Utc::now(),
+            scan_results,
+            targets_detected,
+            reduced_confidence,
+            scan_duration,
+            signals_processed,
+        };
+        
+        debug!("Scan cycle completed: {:.2}ms, {} signals, {} targets", 
+               scan_duration.as_millis(), signals_processed, result.targets_detected.len());
+        self.publish(RadarEvent::ScanCompleted {
+            scan_id,
+            timestamp: result.timestamp,
+            scan_duration,
+            signals_processed,
+            targets_detected: result.targets_detected.len(),
+        });
+        
+        self.set_state(ControllerState::Ready).await?;
+        
+        Ok(result)
+    }
+    
+    /// Scan continuously in a background task until [`stop_continuous_scan`] is called or the
+    /// returned [`ScanTask`] is stopped. The task holds the lock only while it runs a cycle, so
+    /// the controller stays usable in between.
+    ///
+    /// [`stop_continuous_scan`]: RadarController::stop_continuous_scan
+    pub async fn start_continuous_scan(controller: &Arc<Mutex<Self>>) -> Result<ScanTask> {
+        let mut this = controller.lock().await;
+        if !this.initialized {
+            return Err(HexarError::RadarInitializationFailed(
+                "Radar controller not initialized".to_string()
+            ).into());
+        }
+        if this.is_continuous_scan_running() {
+            return Err(HexarError::ResourceUnavailable(
+                "Continuous scan already running".to_string()
+            ).into());
+        }
+        
+        info!("Starting continuous scanning mode");
+        let (shutdown, stopped) = watch::channel(false);
+        this.scan_shutdown = Some(shutdown.clone());
+        this.current_scan_mode = ScanMode::Continuous;
+        drop(this);
+        
+        let handle = tokio::spawn(run_continuous_scan(Arc::clone(controller), stopped));
+        Ok(ScanTask { shutdown, handle })
+    }
+    
+    /// Make the background scan task exit before its next cycle
+    pub async fn stop_continuous_scan(&mut self) -> Result<()> {
+        if let Some(shutdown) = self.scan_shutdown.take() {
+            info!("Stopping continuous scanning");
+            let _ = shutdown.send(true);
+        }
+        self.current_scan_mode = ScanMode::OnDemand;
+        Ok(())
+    }
+    
+    pub fn is_continuous_scan_running(&self) -> bool {
+        self.scan_shutdown.as_ref().is_some_and(|shutdown| !*shutdown.borrow())
+    }
+    
+    /// One step of continuous scanning: a scan cycle when the schedule allows it. Returns how
+    /// long to rest before the next step.
+    async fn continuous_scan_step(&mut self) -> Duration {
+        let now = SystemTime::now();
+        if !self.schedule.is_active(now) {
+            let wait = self
+                .schedule
+                .next_active(now)
+                .and_then(|next| next.duration_since(now).ok())
+                .map_or(MAX_SCHEDULE_WAIT, |wait| wait.min(MAX_SCHEDULE_WAIT));
+            debug!("Outside scan windows, waiting {:?}", wait);
+            return wait;
+        }
+
+        let cycle_start = Instant::now();
+        match self.run_scan_cycle().await {
+            Ok(result) => {
+                debug!("Continuous scan: {} targets detected", result.targets_detected.len());
+            },
+            Err(e) => {
+                self.record_error(format!("Continuous scan failed: {}", e));
+                return SCAN_RETRY_DELAY;
+            }
+        }
+        
+        // Rate limiting based on configuration or activity, longer if the duty cycle needs it
+        let busy = cycle_start.elapsed();
+        let rest = self.duty_cycle.rest_after(busy);
+        match &mut self.adaptive_rate {
+            Some(rate) => {
+                let previous = rate.get_activity();
+                let activity = rate.update(
+                    self.tracker.get_all_targets().iter().map(|target| target.velocity.norm()),
+                    Instant::now(),
+                );
+                if activity != previous {
+                    info!("Activity changed to {:?}, scanning at {} Hz", activity, rate.get_rate_hz());
+                }
+                rate.rest_after(busy, &self.power_duty_cycle).max(rest)
+            }
+            None => {
+                let scan_interval = Duration::from_millis((1000.0 / self.config.scan_rate_hz()) as u64);
+                scan_interval.max(rest)
+            }
+        }
+    }
+    
+    pub async fn shutdown(&mut self) -> Result<()> {
+        info!("Shutting down radar controller...");
+        
+        self.set_state(ControllerState::Shutdown).await?;
+        
+        // Stop any ongoing operations
+        self.stop_continuous_scan().await?;
+        
+        // Power down antennas
+        self.shutdown_antennas().await?;
+        
+        // Clear data, keeping the tracks and statistics for the next start
+        if let Err(e) = self.save_snapshot().await {
+            self.record_error(format!("Failed to save controller state: {}", e));
+        }
+        self.scan_results.clear();
+        self.tracker.clear_all_targets();
+        
+        self.initialized = false;
+        
+        info!("Radar controller shutdown complete");
+        Ok(())
+    }
+    
+    /// Change a setting while running, see [`apply_from`](RadarController::apply_from)
+    pub fn apply(&mut self, command: ControlCommand) -> Result<()> {
+        self.apply_from(command, "local")
+    }
+    
+    /// Change a setting while running on behalf of `source`. The command is validated before
+    /// anything changes and recorded in the audit trail whether it was applied or not.
+    pub fn apply_from(&mut self, command: ControlCommand, source: &str) -> Result<()> {
+        let result = command.validate(self.config.antenna_count);
+        match &result {
+            Ok(()) => {
+                info!("Applying {:?} from {}", command, source);
+                self.apply_validated(&command);
+                self.overrides.retain(|applied| !command.replaces(applied));
+                self.overrides.push(command.clone());
+            }
+            Err(e) => warn!("Rejected {:?} from {}: {}", command, source, e),
+        }
+        self.audit.record(AuditEntry {
+            timestamp: Utc::now(),
+            source: source.to_string(),
+            command,
+            error: result.as_ref().err().map(ToString::to_string),
+        });
+        result.map_err(Into::into)
+    }
+    
+    fn apply_validated(&mut self, command: &ControlCommand) {
+        match command {
+            ControlCommand::SetThreshold { threshold_db } => {
+                self.config.signal_processing.threshold_db = *threshold_db;
+                self.config.signal_processing.adaptive_margin_db = None;
+                self.scanner.set_threshold(*threshold_db);
+                self.scanner.set_threshold_mode(ThresholdMode::Fixed);
+            }
+            ControlCommand::SetAdaptiveThreshold { margin_db } => {
+                self.config.signal_processing.adaptive_margin_db = Some(*margin_db);
+                self.scanner.set_threshold_mode(ThresholdMode::Adaptive { margin_db: *margin_db });
+            }
+            ControlCommand::SetScanMode { mode } => self.config.scan_mode = mode.clone(),
+            ControlCommand::SetZones { zones } => self.config.zones = zones.clone(),
+            ControlCommand::SetConfirmation { hits, window } => {
+                self.tracker.set_confirmation(*hits, *window);
+                self.config.tracking = self.tracker.get_config().clone();
+            }
+            ControlCommand::SetAssociationGate { gate_m } => {
+                self.tracker.set_association_gate(*gate_m);
+                self.config.tracking.association_gate_m = *gate_m;
+            }
+            ControlCommand::SetMahalanobisGate { gate } => {
+                self.tracker.set_mahalanobis_gate(*gate);
+                self.config.tracking.mahalanobis_gate = *gate;
+            }
+            ControlCommand::SetAntennaCapacity { antenna_id, max_targets } => {
+                self.tracker.set_antenna_capacity(*antenna_id, *max_targets);
+                self.config.tracking = self.tracker.get_config().clone();
+            }
+        }
+    }
+    
+    /// Control command changing the setting at the dotted configuration path `key` to
+    /// `value`, for the settings that can change while running
+    pub fn setting_command(&self, key: &str, value: serde_json::Value) -> Result<ControlCommand> {
+        let number = |value: &serde_json::Value| value.as_f64().map(|number| number as f32)
+            .ok_or_else(|| HexarError::InvalidParameter(format!("{} must be a number", key)));
+        let count = |value: &serde_json::Value| value.as_u64()
+            .and_then(|count| u32::try_from(count).ok())
+            .ok_or_else(|| HexarError::InvalidParameter(format!("{} must be a whole number", key)));
+        let tracking = &self.config.tracking;
+        let command = match key {
+            "radar.signal_processing.threshold_db" => ControlCommand::SetThreshold { threshold_db: number(&value)? },
+            "radar.signal_processing.adaptive_margin_db" => ControlCommand::SetAdaptiveThreshold { margin_db: number(&value)? },
+            "radar.scan_mode" => ControlCommand::SetScanMode { mode: serde_json::from_value(value)? },
+            "radar.zones" => ControlCommand::SetZones { zones: serde_json::from_value(value)? },
+            "radar.tracking.association_gate_m" => ControlCommand::SetAssociationGate { gate_m: number(&value)? },
+            "radar.tracking.mahalanobis_gate" => ControlCommand::SetMahalanobisGate { gate: number(&value)? },
+            "radar.tracking.confirm_hits" => ControlCommand::SetConfirmation {
+                hits: count(&value)?,
+                window: tracking.confirm_window,
+            },
+            "radar.tracking.confirm_window" => ControlCommand::SetConfirmation {
+                hits: tracking.confirm_hits,
+                window: count(&value)?,
+            },
+            _ => return Err(HexarError::InvalidParameter(format!("{} can't change while running", key)).into()),
+        };
+        Ok(command)
+    }
+    
+    /// Run `stage` on the measurements of every scan cycle from the next one on, after the
+    /// stages added before it
+    pub fn add_processing_stage(&mut self, stage: Box<dyn ProcessingStage>) {
+        info!("Adding processing stage {}", stage.name());
+        self.processing.push(stage);
+    }
+    
+    /// Run `stage` right before the stage named `before`, after all of them when there is none
+    pub fn insert_processing_stage(&mut self, before: &str, stage: Box<dyn ProcessingStage>) {
+        info!("Adding processing stage {} before {}", stage.name(), before);
+        self.processing.insert_before(before, stage);
+    }
+    
+    pub fn remove_processing_stage(&mut self, name: &str) -> Option<Box<dyn ProcessingStage>> {
+        self.processing.remove(name)
+    }
+    
+    /// Names of the processing stages in the order they run
+    pub fn get_processing_stages(&self) -> Vec<&str> {
+        self.processing.names()
+    }
+    
+    /// Take tracking timestamps from `clock` instead of the wall clock
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.tracker.set_clock(clock);
+    }
+    
+    /// Run `scenario` through the pipeline one frame at a time, a scan cycle after each, on a
+    /// simulated clock so the outcome doesn't depend on how fast the machine is. The
+    /// controller must be initialized; readers and replays are stopped first.
+    pub async fn run_simulation(&mut self, scenario: Scenario) -> Result<SimulationReport> {
+        scenario.validate()?;
+        self.stop_readers().await;
+        while self.sensor_frames.try_recv().is_ok() {}
+        let clock = SimulatedClock::new();
+        let start = clock.now();
+        self.set_clock(Arc::new(clock.clone()));
+        let mut events = self.subscribe();
+        let interval = scenario.get_frame_interval();
+        let steps = scenario.get_frame_count();
+        let mut generator = ScenarioGenerator::new(scenario, self.array.clone());
+        let mut report = SimulationReport::default();
+        
+        for step in 0..steps {
+            clock.set(start + interval * step as u32);
+            for mut frame in generator.frames_at((interval * step as u32).as_secs_f32()) {
+                frame.received_at = clock.now();
+                self.sensor_frame_sender.send(frame).await?;
+                report.frames += 1;
+            }
+            let result = self.run_scan_cycle().await?;
+            report.cycles += 1;
+            report.max_concurrent_targets = report.max_concurrent_targets.max(result.targets_detected.len());
+            
+            loop {
+                match events.try_recv() {
+                    Ok(RadarEvent::TargetUpdated(target)) if !report.targets.contains(&target.id) => {
+                        report.targets.push(target.id);
+                    }
+                    Ok(RadarEvent::TargetLost(_)) => report.targets_lost += 1,
+                    Ok(RadarEvent::FallDetected(event)) => {
+                        let (target_id, kind, at) = match event {
+                            FallEvent::FallSuspected { target_id, at, .. } => (target_id, FallAlertKind::Suspected, at),
+                            FallEvent::FallConfirmed { target_id, at, .. } => (target_id, FallAlertKind::Confirmed, at),
+                            FallEvent::FallCleared { target_id, at } => (target_id, FallAlertKind::Cleared, at),
+                        };
+                        report.falls.push(SimulatedFall {
+                            time_s: at.saturating_duration_since(start).as_secs_f32(),
+                            target_id,
+                            kind,
+                        });
+                    }
+                    Ok(_) => {}
+                    Err(broadcast::error::TryRecvError::Lagged(missed)) => {
+                        warn!("Simulation missed {} radar events", missed);
+                    }
+                    Err(_) => break,
+                }
+            }
+        }
+        
+        info!("Simulation finished: {} frames, {} targets, {} falls",
+              report.frames, report.targets.len(), report.falls.len());
+        Ok(report)
+    }
+    
+    /// Condition of the controller, its antennas and the tracker as of now
+    pub fn health(&self) -> HealthReport {
+        let self_test = self.self_test.as_ref();
+        let antennas = self.config.sensors
+            .iter()
+            .map(|sensor| {
+                let antenna_id = sensor.antenna_id;
+                let mut antenna = AntennaHealth::new(antenna_id);
+                if let Some(LinkState::Disconnected { error, attempts, .. }) = self.antenna_links.get(&antenna_id) {
+                    antenna.connected = false;
+                    antenna.link_error = Some(error.clone());
+                    antenna.reconnect_attempts = *attempts;
+                }
+                antenna.passed_self_test = self_test
+                    .and_then(|report| report.antennas.iter().find(|test| test.antenna_id == antenna_id))
+                    .map(AntennaSelfTest::passed);
+                antenna.excluded = self.excluded_antennas.contains(&antenna_id);
+                let stats = self.readers
+                    .iter()
+                    .find(|reader| reader.get_antenna_id() == antenna_id)
+                    .map(SensorReader::get_parser_stats)
+                    .or_else(|| self.sensors
+                        .iter()
+                        .find(|sensor| sensor.get_antenna_id() == antenna_id)
+                        .map(SerialRadar::get_parser_stats));
+                if let Some(stats) = stats {
+                    antenna.set_parser_stats(stats);
+                }
+                antenna
+            })
+            .collect();
+        let targets = self.tracker.get_all_targets();
+        let tracker = TrackerHealth {
+            targets: targets.len(),
+            confirmed: self.tracker.get_target_count_by_status(TrackStatus::Confirmed),
+            tentative: self.tracker.get_target_count_by_status(TrackStatus::Tentative),
+            falling: self.tracker.get_falling_targets().len(),
+            stationary: targets
+                .iter()
+                .filter(|target| target.state == TargetState::StationaryPresence)
+                .count(),
+        };
+        HealthReport {
+            timestamp: Utc::now(),
+            state: self.state.clone(),
+            state_duration: self.get_state_duration(),
+            since_last_scan: self.last_scan_time.map(|time| time.elapsed()),
+            scan_rate_hz: self.get_scan_rate_hz(),
+            scan: self.get_scan_statistics(),
+            tracker,
+            antennas,
+            coverage: (!self.config.sensors.is_empty()).then(|| self.get_coverage()),
+            last_error: self.last_error.clone(),
+        }
+    }
+    
+    /// Control commands received so far, oldest first
+    pub fn get_audit_log(&self) -> &AuditLog {
+        &self.audit
+    }
+    
+    /// Settings as changed at runtime
+    pub fn get_config(&self) -> &RadarConfig {
+        &self.config
+    }
+    
+    /// The frequency scanner, for one-off quick, refined or full scans
+    pub fn get_scanner_mut(&mut self) -> &mut FrequencyScanner {
+        &mut self.scanner
+    }
+    
+    /// Receive scan, target and fall events from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<RadarEvent> {
+        self.events.subscribe()
+    }
+    
+    pub fn get_state(&self) -> ControllerState {
+        self.state.clone()
+    }
+    
+    /// How long the controller has been in its current state
+    pub fn get_state_duration(&self) -> Duration {
+        self.state_changed_at.elapsed()
+    }
+    
+    /// Rate continuous scanning currently aims for
+    pub fn get_scan_rate_hz(&self) -> f32 {
+        self.adaptive_rate
+            .as_ref()
+            .map_or_else(|| self.config.scan_rate_hz(), AdaptiveRate::get_rate_hz)
+    }
+    
+    /// Whether the scan schedule allows scanning right now
+    pub fn is_scan_window_open(&self) -> bool {
+        self.schedule.is_active(SystemTime::now())
+    }
+    
+    pub fn get_current_targets(&self) -> Vec<&TrackedTarget> {
+        self.tracker.get_all_targets()
+    }
+    
+    pub fn get_confirmed_targets(&self) -> Vec<&TrackedTarget> {
+        self.tracker.get_confirmed_targets()
+    }
+    
+    pub fn get_falling_targets(&self) -> Vec<&TrackedTarget> {
+        self.tracker.get_falling_targets()
+    }
+    
+    pub fn get_scan_statistics(&self) -> ScanStatistics {
+        ScanStatistics {
+            total_scans: self.metrics.cycles as usize,
+            last_scan_time: self.last_scan_time,
+            current_target_count: self.tracker.get_target_count(),
+            average_scan_duration: self.metrics.get_average_cycle_duration(),
+            signals_per_scan: self.metrics.get_signals_per_cycle(),
+            measurements_per_scan: self.metrics.get_measurements_per_cycle(),
+            associations: self.metrics.associations,
+            dropped_frames: self.metrics.dropped_frames,
+            superseded_frames: self.metrics.superseded_frames,
+            sink_dropped: self.metrics.sink_dropped,
+            antenna_loads: self.tracker.get_antenna_loads(),
+        }
+    }
+    
+    /// Counters and histograms of every scan cycle since the controller was created
+    pub fn get_scan_metrics(&self) -> &ScanMetrics {
+        &self.metrics
+    }
+    
+    /// Channel occupancy over the readings of the last hour of scan cycles
+    pub fn get_channel_occupancy(&self) -> ScanStatisticsReport {
+        self.occupancy.report()
+    }
+    
+    // Private helper methods
+    /// Log an error and keep it for the health report
+    fn record_error(&mut self, message: String) {
+        error!("{}", message);
+        self.last_error = Some(LastError {
+            timestamp: Utc::now(),
+            message,
+        });
+    }
+    
+    /// Keep the targets alive that an LD2450 reports as good as still, whether or not a
+    /// processing stage dropped those detections
+    fn observe_still_detections<'a>(&mut self, frames: impl Iterator<Item = &'a SensorFrame>) {
+        for frame in frames {
+            for measurement in &frame.measurements {
+                if let Some(speed) = measurement.speed {
+                    let position = self.fusion.to_world(frame.antenna_id, measurement.position);
+                    self.tracker.observe_stationary_detection(frame.antenna_id, position, speed);
+                }
+            }
+        }
+    }
+    
+    /// Give every target in front of an LD2412 in engineering mode the stationary energy of the
+    /// gate at its range, for classification and to keep a person sitting still tracked. The
+    /// module only measures range, so the target its own detection fused into gets the same
+    /// energy as any other target at that range.
+    fn observe_gate_energies<'a>(&mut self, frames: impl Iterator<Item = &'a SensorFrame>) {
+        for frame in frames {
+            let Some(gates) = frame.gate_energies() else {
+                continue;
+            };
+            let pose = self.fusion.get_pose(frame.antenna_id).copied();
+            let energies: Vec<(u32, f32)> = self.tracker.get_all_targets()
+                .iter()
+                .filter_map(|target| {
+                    let local = pose.map_or(target.position, |pose| pose.to_local(target.position));
+                    if local.y < 0.0 {
+                        return None;
+                    }
+                    gate_micro_motion(&gates, local.norm()).map(|energy| (target.id, energy))
+                })
+                .collect();
+            for (target_id, energy) in energies {
+                self.tracker.observe_micro_motion(target_id, energy);
+            }
+        }
+    }
+    
+    /// Publish the zones whose confirmed targets changed since the last cycle
+    fn update_zone_occupancy(&mut self) {
+        let targets = self.tracker.get_confirmed_targets();
+        let mut occupancy = HashMap::new();
+        let mut changes = Vec::new();
+        for zone in &self.config.zones {
+            let mut occupants: Vec<u32> = targets.iter()
+                .filter(|target| zone.contains(target.position))
+                .map(|target| target.id)
+                .collect();
+            occupants.sort_unstable();
+            let previous = self.zone_occupants.get(&zone.name).map(Vec::as_slice).unwrap_or_default();
+            let entered: Vec<u32> = occupants.iter().filter(|id| !previous.contains(id)).copied().collect();
+            let left: Vec<u32> = previous.iter().filter(|id| !occupants.contains(id)).copied().collect();
+            if !entered.is_empty() || !left.is_empty() {
+                changes.push(RadarEvent::ZoneOccupancyChanged {
+                    zone: zone.name.clone(),
+                    entered,
+                    left,
+                    occupants: occupants.clone(),
+                });
+            }
+            occupancy.insert(zone.name.clone(), occupants);
+        }
+        self.zone_occupants = occupancy;
+        for event in changes {
+            self.publish(event);
+        }
+    }
+    
+    fn publish(&self, event: RadarEvent) {
+        // Nobody listening is fine
+        let _ = self.events.send(event);
+    }
+    
+    async fn set_state(&mut self, state: ControllerState) -> Result<()> {
+        if state == self.state {
+            return Ok(());
+        }
+        if !self.state.can_transition_to(&state) {
+            return Err(HexarError::InvalidStateTransition {
+                from: format!("{:?}", self.state),
+                to: format!("{:?}", state),
+            }.into());
+        }
+        
+        debug!("Radar controller state: {:?} -> {:?}", self.state, state);
+        let from = std::mem::replace(&mut self.state, state.clone());
+        self.state_changed_at = Instant::now();
+        self.publish(RadarEvent::StateChanged { from, to: state });
+        Ok(())
+    }
+    
+    pub fn snapshot(&self) -> ControllerSnapshot {
+        let mut disconnected_antennas: Vec<u8> = self.antenna_links
+            .iter()
+            .filter(|(_, link)| !link.is_connected())
+            .map(|(antenna_id, _)| *antenna_id)
+            .collect();
+        disconnected_antennas.sort_unstable();
+        ControllerSnapshot {
+            taken_at: SystemTime::now(),
+            tracker: self.tracker.snapshot(),
+            metrics: self.metrics.clone(),
+            config: self.config.clone(),
+            overrides: self.overrides.clone(),
+            initial_config: Some(self.initial_config.clone()),
+            self_test: self.self_test.clone(),
+            failed_antennas: self.failed_antennas.clone(),
+            disconnected_antennas,
+        }
+    }
+    
+    /// Write a snapshot to the configured state file, through a temporary file so a crash
+    /// mid-write leaves the previous state intact
+    async fn save_snapshot(&mut self) -> HexarResult<()> {
+        let Some(path) = &self.config.tracker_state_file else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec(&self.snapshot())?;
+        let temporary = path.with_extension("tmp");
+        tokio::fs::write(&temporary, json).await?;
+        tokio::fs::rename(&temporary, path).await?;
+        self.last_state_save = Some(Instant::now());
+        debug!("Saved controller state to {}", path.display());
+        Ok(())
+    }
+
+    async fn restore_snapshot(&mut self) -> HexarResult<()> {
+        let Some(path) = self.config.tracker_state_file.clone() else {
+            return Ok(());
+        };
+        let json = match tokio::fs::read(&path).await {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        match serde_json::from_slice::<ControllerSnapshot>(&json) {
+            Ok(snapshot) => self.restore(snapshot),
+            // State files written before the whole controller was saved hold the tracks only
+            Err(_) => self.restore_tracker(serde_json::from_slice(&json)?),
+        }
+        info!("Restored {} targets from {}", self.tracker.get_target_count(), path.display());
+        Ok(())
+    }
+    
+    /// Continue from `snapshot`: the tracks and statistics are resumed and the changes made at
+    /// runtime applied again over the configuration the controller was started with
+    pub fn restore(&mut self, snapshot: ControllerSnapshot) {
+        let saved = snapshot.initial_config.as_ref().and_then(|config| serde_json::to_value(config).ok());
+        let current = serde_json::to_value(&self.initial_config).ok();
+        for command in snapshot.overrides {
+            let edited: Vec<&str> = command.config_keys()
+                .iter()
+                .copied()
+                .filter(|key| match (&saved, &current) {
+                    (Some(saved), Some(current)) => config_value(saved, key) != config_value(current, key),
+                    // Snapshots from before the configuration was saved along can't tell
+                    _ => false,
+                })
+                .collect();
+            if !edited.is_empty() {
+                warn!("Not restoring {:?}, {} changed in the configuration since", command, edited.join(", "));
+                continue;
+            }
+            if let Err(e) = self.apply_from(command, "snapshot") {
+                warn!("Saved control command no longer applies: {}", e);
+            }
+        }
+        self.restore_tracker(snapshot.tracker);
+        self.metrics = snapshot.metrics;
+        // The self-test just run is authoritative, the saved health is only reported
+        if !snapshot.failed_antennas.is_empty() || !snapshot.disconnected_antennas.is_empty() {
+            warn!(
+                "Before the restart antennas {:?} had failed and {:?} were disconnected",
+                snapshot.failed_antennas, snapshot.disconnected_antennas
+            );
+        }
+    }
+    
+    fn restore_tracker(&mut self, mut snapshot: TrackerSnapshot) {
+        // Tuning from the current configuration wins over the saved one
+        snapshot.config = self.config.tracking.clone();
+        self.tracker = MultiTargetTracker::restore(self.config.antenna_count, snapshot);
+        self.fall_events = self.tracker.events();
+    }
+    
+    async fn initialize_antennas(&mut self) -> Result<()> {
+        info!("Initializing {} antenna systems", self.config.antenna_count);
+        
+        self.stop_readers().await;
+        
+        if let Some(recorder) = self.frame_recorder.take() {
+            self.frame_sink = Some(SinkStage::spawn(
+                "Frame recorder",
+                recorder,
+                self.config.pipeline.sink_capacity,
+                self.config.pipeline.sink_overflow,
+            ));
+        }
+        
+        if let Some(replay) = &self.config.frame_replay {
+            let timing = replay.speed.map_or(ReplayTiming::Original, ReplayTiming::Accelerated);
+            let frames = FrameReplay::open(&replay.file, timing)?;
+            info!("Replaying {} frames from {}", frames.len(), replay.file.display());
+            self.frame_replay = Some(frames.spawn(self.sensor_frame_sender.clone()));
+            return Ok(());
+        }
+        if let Some(simulation) = &self.config.simulation {
+            let scenario = Scenario::load(&simulation.scenario)?;
+            info!("Simulating {} people from {}", scenario.people.len(), simulation.scenario.display());
+            let generator = ScenarioGenerator::new(scenario, self.array.clone());
+            self.frame_replay = Some(generator.spawn(
+                self.sensor_frame_sender.clone(),
+                simulation.speed.unwrap_or(1.0),
+            ));
+            return Ok(());
+        }
+        
+        // Start every module from a clean buffer, dropping frames queued while idle, and read
+        // it in the background from now on. Modules that failed the self-test stay idle.
+        let (excluded, sensors) = std::mem::take(&mut self.sensors)
+            .into_iter()
+            .partition(|sensor| self.excluded_antennas.contains(&sensor.get_antenna_id()));
+        self.sensors = excluded;
+        for mut sensor in sensors {
+            debug!("Initializing antenna {} ({:?})", sensor.get_antenna_id(), sensor.get_model());
+            if let Err(e) = sensor.reset() {
+                self.sensors.push(sensor);
+                return Err(e.into());
+            }
+            self.readers.push(SensorReader::spawn(sensor, self.sensor_frame_sender.clone()));
+        }
+        
+        if let Some(interference) = &self.config.interference {
+            let antennas: Vec<u8> = self.readers.iter().map(SensorReader::get_antenna_id).collect();
+            let plan = SlotPlan::assign(&self.array, &antennas, interference.min_overlap);
+            if plan.get_slots().len() > 1 {
+                info!("Antennas transmitting in turns: {:?}", plan.get_slots());
+                let schedule = SlotSchedule::new(plan.clone(), interference)?;
+                let gates = self.readers
+                    .iter()
+                    .map(|reader| (reader.get_antenna_id(), reader.get_transmit_gate()))
+                    .collect();
+                self.coordinator = Some(InterferenceCoordinator::spawn(schedule, gates));
+                self.transmit_slots = Some(plan);
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Groups of antennas taking turns transmitting, `None` while all of them transmit at once
+    pub fn get_transmit_slots(&self) -> Option<&[Vec<u8>]> {
+        self.transmit_slots.as_ref().map(SlotPlan::get_slots)
+    }
+    
+    /// Result of the self-test of the last initialization
+    pub fn get_self_test_report(&self) -> Option<&SelfTestReport> {
+        self.self_test.as_ref()
+    }
+    
+    /// Antennas whose serial link is down
+    pub fn get_degraded_antennas(&self) -> Vec<u8> {
+        let mut degraded: Vec<u8> = self.antenna_links
+            .iter()
+            .filter(|(_, link)| !link.is_connected())
+            .map(|(antenna_id, _)| *antenna_id)
+            .collect();
+        degraded.sort_unstable();
+        degraded
+    }
+    
+    /// Fewest working modules the controller keeps scanning with
+    pub fn get_min_antennas(&self) -> usize {
+        self.config.degraded_mode
+            .as_ref()
+            .map_or(self.config.sensors.len(), |degraded| degraded.min_antennas)
+    }
+    
+    /// Share of the array's area the working modules cover
+    pub fn get_coverage(&self) -> f32 {
+        let working: Vec<u8> = self.config.sensors
+            .iter()
+            .map(|sensor| sensor.antenna_id)
+            .filter(|antenna_id| !self.failed_antennas.contains(antenna_id))
+            .collect();
+        self.array.coverage(&working)
+    }
+    
+    /// Publish a change of the failed antennas, failing when too few are left to go on with
+    fn update_degraded_mode(&mut self) -> HexarResult<()> {
+        if self.config.sensors.is_empty() {
+            return Ok(());
+        }
+        let mut failed = self.get_degraded_antennas();
+        failed.extend(self.excluded_antennas.iter().copied());
+        failed.sort_unstable();
+        failed.dedup();
+        
+        if failed != self.failed_antennas {
+            self.failed_antennas = failed.clone();
+            let coverage = self.get_coverage();
+            if failed.is_empty() {
+                info!("All antennas working again");
+            } else {
+                warn!("Running without antennas {:?}, {:.0}% coverage left", failed, coverage * 100.0);
+            }
+            self.publish(RadarEvent::DegradedModeChanged { failed_antennas: failed.clone(), coverage });
+        }
+        
+        let working = self.config.sensors.len() - failed.len();
+        if working < self.get_min_antennas() {
+            return Err(HexarError::HardwareError(format!(
+                "only {} of {} antennas working, {} needed",
+                working, self.config.sensors.len(), self.get_min_antennas()
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Publish the link changes the antenna readers saw since the last check
+    fn check_antenna_links(&mut self) {
+        let mut changes = Vec::new();
+        for reader in &self.readers {
+            let antenna_id = reader.get_antenna_id();
+            let link = reader.get_link_state();
+            let was_connected = self.antenna_links
+                .insert(antenna_id, link.clone())
+                .is_none_or(|previous| previous.is_connected());
+            match link {
+                LinkState::Disconnected { error, .. } if was_connected => {
+                    changes.push(RadarEvent::AntennaDisconnected { antenna_id, error });
+                }
+                LinkState::Connected if !was_connected => {
+                    changes.push(RadarEvent::AntennaReconnected { antenna_id });
+                }
+                _ => {}
+            }
+        }
+        for event in changes {
+            self.publish(event);
+        }
+    }
+    
+    /// Stop the antenna readers, keeping their modules for the next initialization
+    async fn stop_readers(&mut self) {
+        if let Some(replay) = self.frame_replay.take() {
+            replay.abort();
+        }
+        // Readers bring their modules back to reporting as they stop
+        if let Some(coordinator) = self.coordinator.take() {
+            coordinator.stop();
+        }
+        self.transmit_slots = None;
+        for reader in std::mem::take(&mut self.readers) {
+            let antenna_id = reader.get_antenna_id();
+            match reader.stop().await {
+                Ok(sensor) => self.sensors.push(sensor),
+                Err(e) => self.record_error(format!("Lost antenna {}: {}", antenna_id, e)),
+            }
+        }
+    }
+    
+    async fn validate_frequency_range(&self) -> Result<()> {
+        let range = &self.config.frequency_range;
+        
+        if range.start_mhz >= range.end_mhz {
+            return Err(HexarError::ConfigurationError(
+                "Invalid frequency range: start >= end".to_string()
+            ).into());
+        }
+        
+        if range.step_mhz <= 0.0 {
+            return Err(HexarError::ConfigurationError(
+                "Invalid frequency step: must be positive".to_string()
+            ).into());
+        }
+        
+        info!("Frequency range validated: {:.1} - {:.1} MHz (step: {:.1} MHz)", 
+              range.start_mhz, range.end_mhz, range.step_mhz);
+        
+        Ok(())
+    }
+    
+    /// Test every module at once, each on a blocking thread, and fail if any of them fails
+    async fn run_self_test(&mut self) -> Result<()> {
+        info!("Running radar system self-test...");
+        self.stop_readers().await;
+        
+        let tests: Vec<_> = std::mem::take(&mut self.sensors)
+            .into_iter()
+            .map(|mut sensor| tokio::task::spawn_blocking(move || {
+                let result = sensor.self_test(SELF_TEST_TIMEOUT);
+                (sensor, result)
+            }))
+            .collect();
+        let mut report = SelfTestReport {
+            timestamp: Utc::now(),
+            antennas: Vec::with_capacity(tests.len()),
+        };
+        for test in tests {
+            let (sensor, result) = test.await?;
+            if result.passed() {
+                info!("Antenna {} passed self-test, firmware {}",
+                      result.antenna_id, result.firmware.as_deref().unwrap_or_default());
+            } else {
+                self.record_error(format!("Antenna {} failed self-test: {}",
+                       result.antenna_id, result.error.as_deref().unwrap_or_default()));
+            }
+            self.sensors.push(sensor);
+            report.antennas.push(result);
+        }
+        report.antennas.sort_by_key(|antenna| antenna.antenna_id);
+        
+        let failed = report.failed_antennas();
+        self.self_test = Some(report);
+        self.excluded_antennas = failed.iter().copied().collect();
+        let working = self.config.sensors.len() - failed.len();
+        if working < self.get_min_antennas() {
+            return Err(HexarError::HardwareError(format!(
+                "self-test failed on antennas {:?}", failed
+            )).into());
+        }
+        if !failed.is_empty() {
+            warn!("Continuing with {} of {} antennas, leaving out {:?}",
+                  working, self.config.sensors.len(), failed);
+        }
+        
+        debug!("Self-test completed successfully");
+        Ok(())
+    }
+    
+    async fn shutdown_antennas(&mut self) -> Result<()> {
+        info!("Shutting down antenna systems");
+        
+        self.stop_readers().await;
+        // Frames read before the readers stopped are stale by the next initialization
+        while self.sensor_frames.try_recv().is_ok() {}
+        
+        Ok(())
+    }
+}
+
+/// Value at the dotted path `key` of a serialized configuration
+fn config_value<'a>(config: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    key.split('.').try_fold(config, |value, field| value.get(field))
+}
+
+fn open_signal_source(config: &SignalSourceConfig) -> HexarResult<Box<dyn SignalSource>> {
+    match config {
+        SignalSourceConfig::Simulated { seed, emitters } => {
+            let seed = seed.unwrap_or_else(|| Uuid::new_v4().as_u64_pair().0);
+            if emitters.is_empty() {
+                Ok(Box::new(SimulatedSource::new(seed)))
+            } else {
+                Ok(Box::new(SimulatedSource::with_emitters(seed, emitters.clone())))
+            }
+        }
+
+        SignalSourceConfig::Replay { files, speed } => {
+            let timing = speed.map_or(ReplayTiming::Original, ReplayTiming::Accelerated);
+            Ok(Box::new(ReplaySource::open(files, timing)?))
+        }
+
+        #[cfg(feature = "rtlsdr")]
+        SignalSourceConfig::RtlSdr {
+            device_index,
+            sample_rate,
+            gain_db,
+        } => {
+            let source = crate::rtlsdr::RtlSdrSource::open(&crate::rtlsdr::RtlSdrConfig {
+                device_index: *device_index,
+                sample_rate: *sample_rate,
+                gain_tenth_db: gain_db.map(|gain| (gain * 10.0).round() as i32),
+                ..Default::default()
+            })
+            .map_err(|e| HexarError::HardwareError(e.to_string()))?;
+            Ok(Box::new(source))
+        }
+
+        #[cfg(feature = "soapysdr")]
+        SignalSourceConfig::Soapy {
+            args,
+            channel,
+            sample_rate,
+            gain_db,
+        } => {
+            let source = crate::soapysdr::SoapySdrSource::open(&crate::soapysdr::SoapySdrConfig {
+                args: args.clone(),
+                channel: *channel,
+                sample_rate: *sample_rate,
+                gain_db: *gain_db,
+                ..Default::default()
+            })
+            .map_err(|e| HexarError::HardwareError(e.to_string()))?;
+            Ok(Box::new(source))
+        }
+
+        #[allow(unreachable_patterns)]
+        other => Err(HexarError::ConfigurationError(format!(
+            "signal source {:?} requires hexar built with its cargo feature",
+            other
+        ))),
+    }
+}
+
+/// Background scan loop, until the shutdown flag is raised or every sender of it is gone
+async fn run_continuous_scan(controller: Arc<Mutex<RadarController>>, mut stopped: watch::Receiver<bool>) {
+    loop {
+        let rest = {
+            let mut controller = controller.lock().await;
+            if *stopped.borrow_and_update() {
+                break;
+            }
+            controller.continuous_scan_step().await
+        };
+        tokio::select! {
+            changed = stopped.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            },
+            _ = tokio::time::sleep(rest) => {}
+        }
+    }
+    debug!("Continuous scan task finished");
+}
+
+/// Handle of the task started by [`RadarController::start_continuous_scan`]
+#[derive(Debug)]
+pub struct ScanTask {
+    shutdown: watch::Sender<bool>,
+    handle: JoinHandle<()>,
+}
+
+impl ScanTask {
+    /// Stop the task after its current cycle and wait for it to finish
+    pub async fn stop(self) -> Result<()> {
+        let _ = self.shutdown.send(true);
+        self.handle.await.map_err(|e| {
+            HexarError::SystemError(format!("Continuous scan task failed: {}", e))
+        })?;
+        Ok(())
+    }
+    
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+}
+
+/// Open the serial port of every configured radar module, checking it is on a known antenna
+fn open_sensors(config: &RadarConfig, tracker: &MultiTargetTracker) -> HexarResult<Vec<SerialRadar>> {
+    let mut sensors: Vec<SerialRadar> = Vec::with_capacity(config.sensors.len());
+    for sensor in &config.sensors {
+        tracker
+            .validate_antenna(sensor.antenna_id)
+            .map_err(|e| HexarError::ConfigurationError(e.to_string()))?;
+        if sensors.iter().any(|s| s.get_antenna_id() == sensor.antenna_id) {
+            return Err(HexarError::ConfigurationError(format!(
+                "antenna {} has more than one sensor",
+                sensor.antenna_id
+            )));
+        }
+        info!("Opening {:?} on {} for antenna {}", sensor.model, sensor.port, sensor.antenna_id);
+        sensors.push(SerialRadar::open(sensor)?);
+    }
+    Ok(sensors)
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ScanStatistics {
+    pub total_scans: usize,
+    #[serde(skip)]
+    pub last_scan_time: Option<Instant>,
+    pub current_target_count: usize,
+    pub average_scan_duration: Duration,
+    pub signals_per_scan: f32,
+    /// Measurements handed to the tracker per scan
+    pub measurements_per_scan: f32,
+    pub associations: AssociationStatistics,
+    /// Sensor frames lost to a full channel
+    pub dropped_frames: u64,
+    /// Sensor frames replaced by a newer one before a scan used them
+    pub superseded_frames: u64,
+    /// Frames the recorder dropped because it fell behind
+    pub sink_dropped: u64,
+    /// Targets per antenna against its capacity
+    pub antenna_loads: Vec<AntennaLoad>,
+}
+
+// Extension methods for RadarConfig
+impl RadarConfig {
+    pub fn scan_rate_hz(&self) -> f32 {
+        match self.scan_mode {
+            ScanMode::Continuous => 10.0,
+            ScanMode::Intermittent => 5.0,
+            ScanMode::OnDemand => 1.0,
+        }
+    }
+}
+
+/// Channels of the bands the scanned range overlaps, or one per scan step outside them
+fn scan_channels(range: &FrequencyRange) -> Vec<Channel> {
+    let channels: Vec<Channel> = Band::ALL
+        .into_iter()
+        .flat_map(Band::channels)
+        .filter(|channel| {
+            channel.center + channel.bandwidth / 2.0 >= range.start
+                && channel.center - channel.bandwidth / 2.0 <= range.end
+        })
+        .collect();
+    if !channels.is_empty() || range.step <= 0.0 {
+        return channels;
+    }
+    let count = ((range.end - range.start) / range.step) as u32 + 1;
+    channelize(1, range.start, range.step, range.step, count)
+}
+
+// Re-export scan modes
+pub use crate::config::ScanMode;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SimulationConfig;
+    use crate::scenario::ScriptedPerson;
+    use crate::target_class::TargetClass;
+
+    #[tokio::test]
+    async fn test_continuous_scan_stops() {
+        let mut config = RadarConfig::default();
+        config.frequency_range.end_mhz = config.frequency_range.start_mhz + 10.0;
+        let controller = Arc::new(Mutex::new(RadarController::new(config).unwrap()));
+        assert!(RadarController::start_continuous_scan(&controller).await.is_err());
+
+        controller.lock().await.initialize().await.unwrap();
+        let task = RadarController::start_continuous_scan(&controller).await.unwrap();
+        assert!(RadarController::start_continuous_scan(&controller).await.is_err());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        controller.lock().await.stop_continuous_scan().await.unwrap();
+        tokio::time::timeout(Duration::from_secs(5), task.stop())
+            .await
+            .unwrap()
+            .unwrap();
+        let controller = controller.lock().await;
+        assert!(!controller.is_continuous_scan_running());
+        assert!(controller.last_scan_time.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_scan_events() {
+        let mut config = RadarConfig::default();
+        config.frequency_range.end_mhz = config.frequency_range.start_mhz + 10.0;
+        let mut controller = RadarController::new(config).unwrap();
+        let mut events = controller.subscribe();
+        controller.initialize().await.unwrap();
+
+        let result = controller.run_scan_cycle().await.unwrap();
+        let mut completed = None;
+        while let Ok(event) = events.try_recv() {
+            if let RadarEvent::ScanCompleted { scan_id, .. } = event {
+                completed = Some(scan_id);
+            }
+        }
+        assert_eq!(completed, Some(result.scan_id));
+    }
+
+    #[tokio::test]
+    async fn test_channel_occupancy() {
+        let mut config = RadarConfig::default();
+        config.frequency_range.end_mhz = config.frequency_range.start_mhz + 10.0;
+        let mut controller = RadarController::new(config).unwrap();
+        controller.initialize().await.unwrap();
+        assert!(controller.get_channel_occupancy().busiest_channel().is_none());
+
+        for _ in 0..3 {
+            controller.run_scan_cycle().await.unwrap();
+        }
+        let report = controller.get_channel_occupancy();
+        let busiest = report.busiest_channel().unwrap();
+        assert!(busiest.center >= 24000.0 && busiest.center <= 24025.0);
+        // Every step of the 11 in the range, on each of the 3 cycles
+        assert_eq!(report.channels.iter().map(|channel| channel.samples).sum::<usize>(), 33);
+    }
+
+    #[tokio::test]
+    async fn test_sensor_frames_reach_tracker() {
+        let mut config = RadarConfig::default();
+        config.frequency_range.end_mhz = config.frequency_range.start_mhz + 10.0;
+        // Nothing from the scanner, only the injected frames
+        config.signal_processing.threshold_db = 100.0;
+        config.zones = vec![crate::control::Zone {
+            name: "room".to_string(),
+            min: Vector2::new(-10.0, -10.0),
+            max: Vector2::new(10.0, 10.0),
+        }];
+        let mut controller = RadarController::new(config).unwrap();
+        let mut events = controller.subscribe();
+        controller.initialize().await.unwrap();
+
+        for _ in 0..5 {
+            for position in [Vector2::new(0.0, 2.0), Vector2::new(0.0, 1.5)] {
+                // The older frame of the antenna is superseded
+                controller.sensor_frame_sender.send(SensorFrame {
+                    antenna_id: 2,
+                    model: crate::serial_radar::SensorModel::Ld2450,
+                    received_at: Instant::now(),
+                    payload: Vec::new(),
+                    measurements: vec![crate::serial_radar::SensorMeasurement {
+                        position,
+                        speed: Some(0.0),
+                        moving: false,
+                    }],
+                }).await.unwrap();
+            }
+            controller.run_scan_cycle().await.unwrap();
+        }
+
+        let targets = controller.get_current_targets();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].antenna_id, 2);
+        assert!((targets[0].position - Vector2::new(0.0, 1.5)).norm() < 0.1);
+        // Entered the zone once, when confirmed
+        let mut zone_changes = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            if let RadarEvent::ZoneOccupancyChanged { zone, entered, .. } = event {
+                zone_changes.push((zone, entered));
+            }
+        }
+        assert_eq!(zone_changes, vec![("room".to_string(), vec![targets[0].id])]);
+    }
+
+    /// Frame of an LD2412 in engineering mode reporting no target, only `energy` on the
+    /// stationary gate at `range` metres
+    fn ld2412_gate_frame(antenna_id: u8, range: f32, energy: u8) -> SensorFrame {
+        let mut stationary = [0; 14];
+        stationary[(range / crate::target_class::LD2412_GATE_LENGTH_M) as usize] = energy;
+        let mut payload = vec![0x01, 0xAA, 0x00, 0, 0, 0, 0, 0, 0, 0, 0];
+        payload.extend([0; 14]);
+        payload.extend(stationary);
+        payload.extend([0x00, 0x55, 0x00]);
+        SensorFrame::parse(antenna_id, crate::serial_radar::SensorModel::Ld2412, &payload)
+    }
+
+    /// Frame of an LD2450 reporting one target
+    fn ld2450_frame(antenna_id: u8, position: Vector2<f32>, speed: f32) -> SensorFrame {
+        SensorFrame {
+            antenna_id,
+            model: crate::serial_radar::SensorModel::Ld2450,
+            received_at: Instant::now(),
+            payload: Vec::new(),
+            measurements: vec![crate::serial_radar::SensorMeasurement {
+                position,
+                speed: Some(speed),
+                moving: speed != 0.0,
+            }],
+        }
+    }
+
+    /// Range from the LD2412 on antenna 1 of a target 2 m in front of antenna 2, in the middle
+    /// of a gate
+    const LD2412_RANGE: f32 = 3.4;
+
+    /// Initialized controller fed frames only, an LD2412 on antenna 1 1.4 m behind the LD2450
+    /// on antenna 2, on a simulated clock
+    async fn frame_controller() -> (RadarController, SimulatedClock) {
+        let mut config = RadarConfig::default();
+        config.frequency_range.end_mhz = config.frequency_range.start_mhz + 10.0;
+        config.signal_processing.threshold_db = 100.0;
+        config.fusion.sensors.push(crate::fusion::SensorConfig {
+            antenna_id: 1,
+            pose: crate::fusion::SensorPose::new(Vector2::new(0.0, -1.4), 1.0, 0.0),
+        });
+        let mut controller = RadarController::new(config).unwrap();
+        controller.initialize().await.unwrap();
+        let clock = SimulatedClock::new();
+        controller.set_clock(Arc::new(clock.clone()));
+        (controller, clock)
+    }
+
+    /// Class of a target standing still 2 m in front of the LD2450, with the LD2412 showing
+    /// `energy` at its range
+    async fn classify_still_target(energy: Option<u8>) -> TargetClass {
+        let (mut controller, clock) = frame_controller().await;
+        for _ in 0..15 {
+            clock.advance(Duration::from_millis(100));
+            let frame = ld2450_frame(2, Vector2::new(0.0, 2.0), 0.0);
+            controller.sensor_frame_sender.send(frame).await.unwrap();
+            if let Some(energy) = energy {
+                let frame = ld2412_gate_frame(1, LD2412_RANGE, energy);
+                controller.sensor_frame_sender.send(frame).await.unwrap();
+            }
+            controller.run_scan_cycle().await.unwrap();
+        }
+        let targets = controller.get_current_targets();
+        assert_eq!(targets.len(), 1);
+        targets[0].class
+    }
+
+    #[tokio::test]
+    async fn test_gate_energy_classifies_targets() {
+        // Breathing shows on the LD2412 gate at the target's range
+        assert_eq!(classify_still_target(Some(40)).await, TargetClass::Human);
+        assert_eq!(classify_still_target(Some(5)).await, TargetClass::StaticClutter);
+        // Nothing but the LD2450, which never saw it move
+        assert_eq!(classify_still_target(None).await, TargetClass::StaticClutter);
+    }
+
+    /// Drops the detections a module reported as still, like a moving target indicator
+    #[derive(Debug)]
+    struct DropStill;
+
+    impl ProcessingStage for DropStill {
+        fn name(&self) -> &str {
+            "drop_still"
+        }
+
+        fn process(&mut self, measurements: &mut Vec<StageMeasurement>, _context: &StageContext<'_>) -> HexarResult<()> {
+            measurements.retain(|measurement| measurement.moving != Some(false));
+            Ok(())
+        }
+    }
+
+    /// How a person who walked up to 2 m in front of the LD2450 and sat down is still seen
+    #[derive(Debug, Clone, Copy)]
+    enum Sitting {
+        /// The LD2450 reports them without speed, but a stage drops still detections
+        Ld2450,
+        /// The LD2412 sees them breathing at their range
+        Ld2412,
+        Unseen,
+    }
+
+    /// The person's target a minute and a quarter after they sat down
+    async fn sit_down(sitting: Sitting) -> Option<TrackedTarget> {
+        let (mut controller, clock) = frame_controller().await;
+        controller.add_processing_stage(Box::new(DropStill));
+        for step in 0..10 {
+            clock.advance(Duration::from_millis(100));
+            let position = Vector2::new(0.0, 4.0 - 0.2 * (step + 1) as f32);
+            controller.sensor_frame_sender.send(ld2450_frame(2, position, -2.0)).await.unwrap();
+            controller.run_scan_cycle().await.unwrap();
+        }
+        assert_eq!(controller.get_confirmed_targets().len(), 1);
+
+        for _ in 0..15 {
+            clock.advance(Duration::from_secs(5));
+            let frame = match sitting {
+                Sitting::Ld2450 => Some(ld2450_frame(2, Vector2::new(0.0, 2.0), 0.0)),
+                Sitting::Ld2412 => Some(ld2412_gate_frame(1, LD2412_RANGE, 40)),
+                Sitting::Unseen => None,
+            };
+            if let Some(frame) = frame {
+                controller.sensor_frame_sender.send(frame).await.unwrap();
+            }
+            controller.run_scan_cycle().await.unwrap();
+        }
+        controller.get_current_targets().first().map(|target| (*target).clone())
+    }
+
+    #[tokio::test]
+    async fn test_still_person_stays_tracked() {
+        for sitting in [Sitting::Ld2450, Sitting::Ld2412] {
+            let target = sit_down(sitting).await.unwrap_or_else(|| panic!("{:?} lost the person", sitting));
+            assert_eq!(target.state, TargetState::StationaryPresence);
+            assert!((target.position - Vector2::new(0.0, 2.0)).norm() < 0.3);
+        }
+        // Without presence reports the target times out as before
+        assert!(sit_down(Sitting::Unseen).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_state_transitions() {
+        let mut controller = RadarController::new(RadarConfig::default()).unwrap();
+        let mut events = controller.subscribe();
+        assert_eq!(controller.get_state(), ControllerState::Uninitialized);
+
+        controller.initialize().await.unwrap();
+        assert_eq!(controller.get_state(), ControllerState::Ready);
+        let health = controller.health();
+        assert!(health.is_healthy());
+        assert!(health.coverage.is_none() && health.last_error.is_none());
+        assert!(matches!(
+            events.try_recv(),
+            Ok(RadarEvent::StateChanged {
+                from: ControllerState::Uninitialized,
+                to: ControllerState::Initializing,
+            })
+        ));
+
+        // Scanning can't start while initializing
+        controller.state = ControllerState::Initializing;
+        assert!(controller.run_scan_cycle().await.is_err());
+        assert!(!ControllerState::Shutdown.can_transition_to(&ControllerState::Scanning));
+
+        controller.state = ControllerState::Ready;
+        controller.shutdown().await.unwrap();
+        assert_eq!(controller.get_state(), ControllerState::Shutdown);
+    }
+
+    #[test]
+    fn test_runtime_control() {
+        let mut controller = RadarController::new(RadarConfig::default()).unwrap();
+        controller.apply(ControlCommand::SetConfirmation { hits: 2, window: 4 }).unwrap();
+        assert_eq!(controller.tracker.get_confirmation(), (2, 4));
+        controller.apply(ControlCommand::SetScanMode { mode: ScanMode::OnDemand }).unwrap();
+        assert_eq!(controller.get_config().scan_mode, ScanMode::OnDemand);
+
+        // Rejected commands change nothing but are still audited
+        assert!(controller.apply_from(ControlCommand::SetAssociationGate { gate_m: -1.0 }, "cli").is_err());
+        assert_eq!(controller.tracker.get_association_gate(), 2.0);
+        let audit: Vec<_> = controller.get_audit_log().entries().collect();
+        assert_eq!(audit.len(), 3);
+        assert!(audit[0].applied());
+        assert!(!audit[2].applied());
+        assert_eq!(audit[2].source, "cli");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_recovery() {
+        let mut config = RadarConfig::default();
+        config.frequency_range.end_mhz = config.frequency_range.start_mhz + 10.0;
+        let path = std::env::temp_dir().join(format!("hexar-state-{}.json", Uuid::new_v4()));
+        config.tracker_state_file = Some(path.clone());
+
+        let mut controller = RadarController::new(config.clone()).unwrap();
+        controller.initialize().await.unwrap();
+        controller.apply(ControlCommand::SetAssociationGate { gate_m: 1.0 }).unwrap();
+        controller.apply(ControlCommand::SetAssociationGate { gate_m: 1.5 }).unwrap();
+        controller.run_scan_cycle().await.unwrap();
+        controller.run_scan_cycle().await.unwrap();
+        controller.shutdown().await.unwrap();
+
+        // A restart with the configuration from before the change
+        let mut restarted = RadarController::new(config).unwrap();
+        restarted.initialize().await.unwrap();
+        assert_eq!(restarted.get_scan_metrics().cycles, 2);
+        assert_eq!(restarted.tracker.get_association_gate(), 1.5);
+        assert_eq!(restarted.get_config().tracking.association_gate_m, 1.5);
+        assert_eq!(restarted.snapshot().overrides.len(), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_restore_skips_edited_settings() {
+        let config = RadarConfig::default();
+        let mut controller = RadarController::new(config.clone()).unwrap();
+        controller.apply(ControlCommand::SetAssociationGate { gate_m: 1.5 }).unwrap();
+        controller.apply(ControlCommand::SetMahalanobisGate { gate: 20.0 }).unwrap();
+        let snapshot = controller.snapshot();
+
+        // The association gate was edited in the configuration file before the restart
+        let mut edited = config;
+        edited.tracking.association_gate_m = 3.0;
+        let mut restarted = RadarController::new(edited).unwrap();
+        restarted.restore(snapshot);
+        assert_eq!(restarted.tracker.get_association_gate(), 3.0);
+        assert_eq!(restarted.get_config().tracking.mahalanobis_gate, 20.0);
+        assert_eq!(restarted.snapshot().overrides, vec![ControlCommand::SetMahalanobisGate { gate: 20.0 }]);
+    }
+
+    #[tokio::test]
+    async fn test_simulated_scenario() {
+        let scenario = Scenario {
+            duration_s: 8.0,
+            people: vec![ScriptedPerson {
+                enter_s: 0.0,
+                exit_s: None,
+                path: vec![Vector2::new(2.5, -1.0), Vector2::new(2.5, 1.5)],
+                speed_mps: 1.0,
+                fall_at_s: Some(4.0),
+            }],
+            ..Default::default()
+        };
+        let path = std::env::temp_dir().join(format!("hexar-scenario-{}.json", Uuid::new_v4()));
+        std::fs::write(&path, serde_json::to_string(&scenario).unwrap()).unwrap();
+        let mut config = RadarConfig::default();
+        config.frequency_range.end_mhz = config.frequency_range.start_mhz + 10.0;
+        config.simulation = Some(SimulationConfig { scenario: path.clone(), speed: None });
+        // A fall shows in 2D as a quick drop along y, slowed down by the filter
+        config.tracking.fall_detector.gravity_threshold = -0.5;
+        config.tracking.fall_detector.velocity_threshold = 0.8;
+        config.tracking.fall_detector.fall_probability_threshold = 0.6;
+
+        let mut controller = RadarController::new(config.clone()).unwrap();
+        controller.initialize().await.unwrap();
+        let report = controller.run_simulation(scenario.clone()).await.unwrap();
+        assert_eq!(report.cycles, 81);
+        assert_eq!(report.targets.len(), 1);
+        assert_eq!(report.frames, 81 * 6);
+        // Suspected within half a second of the fall and confirmed once the person stays down
+        let falls: Vec<_> = report.falls.iter().map(|fall| (fall.kind, fall.time_s)).collect();
+        assert_eq!(falls[0].0, FallAlertKind::Suspected);
+        assert!(falls[0].1 >= 4.0 && falls[0].1 <= 4.5);
+        assert_eq!(falls[1].0, FallAlertKind::Confirmed);
+
+        // Deterministic from one run to the next
+        let mut again = RadarController::new(config).unwrap();
+        again.initialize().await.unwrap();
+        assert_eq!(again.run_simulation(scenario).await.unwrap(), report);
+        std::fs::remove_file(path).unwrap();
+    }
+}
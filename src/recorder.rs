@@ -0,0 +1,256 @@
+use std::fmt::Debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use log::{debug, warn};
+
+use crate::scanner::SignalReading;
+
+/// Destination for every reading the [`FrequencyScanner`](crate::scanner::FrequencyScanner)
+/// takes, see [`FrequencyScanner::set_sink`](crate::scanner::FrequencyScanner::set_sink)
+pub trait ReadingSink: Debug + Send {
+    fn record(&mut self, reading: &SignalReading) -> io::Result<()>;
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// When a [`CsvRecorder`] starts a new file and how many it keeps
+#[derive(Debug, Clone)]
+pub struct RotationPolicy {
+    pub max_bytes: u64,
+    pub max_age: Duration,
+    /// Oldest files beyond this count are deleted, all are kept when `None`
+    pub max_files: Option<usize>,
+}
+
+impl Default for RotationPolicy {
+    fn default() -> Self {
+        Self {
+            max_bytes: 64 * 1024 * 1024,
+            max_age: Duration::from_secs(3600),
+            max_files: Some(24),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CurrentFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    opened: SystemTime,
+    bytes: u64,
+}
+
+/// Writes readings to `<prefix>-<unix ms>.csv` files in a directory, one line per reading with
+/// the Unix timestamp in seconds, frequency in MHz and strength in dB. A new file is started on
+/// open and whenever the current one exceeds the [`RotationPolicy`], so earlier recordings
+/// survive restarts.
+#[derive(Debug)]
+pub struct CsvRecorder {
+    directory: PathBuf,
+    prefix: String,
+    policy: RotationPolicy,
+    current: Option<CurrentFile>,
+    last_stamp: u128,
+}
+
+const CSV_HEADER: &str = "timestamp,frequency_mhz,strength_db\n";
+
+impl CsvRecorder {
+    pub fn new(
+        directory: impl Into<PathBuf>,
+        prefix: impl Into<String>,
+        policy: RotationPolicy,
+    ) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+
+        Ok(Self {
+            directory,
+            prefix: prefix.into(),
+            policy,
+            current: None,
+            last_stamp: 0,
+        })
+    }
+
+    /// Path of the file being written, `None` before the first reading
+    pub fn current_path(&self) -> Option<&Path> {
+        self.current.as_ref().map(|current| current.path.as_path())
+    }
+
+    /// Recording files in the directory, oldest first
+    pub fn recordings(&self) -> io::Result<Vec<PathBuf>> {
+        let mut files: Vec<(u128, PathBuf)> = fs::read_dir(&self.directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter_map(|path| Some((self.file_stamp(&path)?, path)))
+            .collect();
+
+        files.sort();
+        Ok(files.into_iter().map(|(_, path)| path).collect())
+    }
+
+    fn file_stamp(&self, path: &Path) -> Option<u128> {
+        let stem = path
+            .file_name()?
+            .to_str()?
+            .strip_suffix(".csv")?
+            .strip_prefix(self.prefix.as_str())?
+            .strip_prefix('-')?;
+        stem.parse().ok()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        if let Some(mut current) = self.current.take() {
+            current.writer.flush()?;
+        }
+
+        // Names must sort in creation order, back to back rotations move on to the next
+        // millisecond
+        let mut stamp = unix_time(SystemTime::now())
+            .as_millis()
+            .max(self.last_stamp + 1);
+        let (path, file) = loop {
+            let path = self
+                .directory
+                .join(format!("{}-{}.csv", self.prefix, stamp));
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => break (path, file),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => stamp += 1,
+                Err(e) => return Err(e),
+            }
+        };
+        self.last_stamp = stamp;
+        debug!("Recording readings to {}", path.display());
+
+        let mut writer = BufWriter::new(file);
+        writer.write_all(CSV_HEADER.as_bytes())?;
+        self.current = Some(CurrentFile {
+            path,
+            writer,
+            opened: SystemTime::now(),
+            bytes: CSV_HEADER.len() as u64,
+        });
+
+        self.prune();
+        Ok(())
+    }
+
+    fn prune(&self) {
+        let Some(max_files) = self.policy.max_files else {
+            return;
+        };
+
+        match self.recordings() {
+            Ok(files) => {
+                let excess = files.len().saturating_sub(max_files.max(1));
+                for path in &files[..excess] {
+                    if let Err(e) = fs::remove_file(path) {
+                        warn!("Could not remove old recording {}: {}", path.display(), e);
+                    }
+                }
+            }
+            Err(e) => warn!("Could not list recordings: {}", e),
+        }
+    }
+
+    fn needs_rotation(&self) -> bool {
+        match &self.current {
+            None => true,
+            Some(current) => {
+                current.bytes >= self.policy.max_bytes
+                    || current.opened.elapsed().unwrap_or_default() >= self.policy.max_age
+            }
+        }
+    }
+}
+
+impl ReadingSink for CsvRecorder {
+    fn record(&mut self, reading: &SignalReading) -> io::Result<()> {
+        if self.needs_rotation() {
+            self.rotate()?;
+        }
+
+        // Readings carry a monotonic timestamp, map it onto the wall clock
+        let taken = SystemTime::now()
+            .checked_sub(reading.timestamp.elapsed())
+            .unwrap_or_else(SystemTime::now);
+        let line = format!(
+            "{:.3},{:.6},{:.2}\n",
+            unix_time(taken).as_secs_f64(),
+            reading.frequency,
+            reading.strength
+        );
+
+        if let Some(current) = &mut self.current {
+            current.writer.write_all(line.as_bytes())?;
+            current.bytes += line.len() as u64;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.current {
+            Some(current) => current.writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for CsvRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            warn!("Could not flush recording: {}", e);
+        }
+    }
+}
+
+fn unix_time(time: SystemTime) -> Duration {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_rotation_and_retention() {
+        let directory = std::env::temp_dir().join(format!("hexar-recorder-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        let policy = RotationPolicy {
+            max_bytes: 100,
+            max_age: Duration::from_secs(3600),
+            max_files: Some(2),
+        };
+        let mut recorder = CsvRecorder::new(&directory, "scan", policy).unwrap();
+        let reading = SignalReading {
+            frequency: 433.5,
+            strength: -41.5,
+            timestamp: Instant::now(),
+        };
+
+        // Header and two 33 byte lines fill a file
+        for _ in 0..10 {
+            recorder.record(&reading).unwrap();
+        }
+        recorder.flush().unwrap();
+
+        let files = recorder.recordings().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(recorder.current_path(), files.last().map(PathBuf::as_path));
+
+        let newest = fs::read_to_string(files.last().unwrap()).unwrap();
+        let mut lines = newest.lines();
+        assert_eq!(lines.next(), Some(CSV_HEADER.trim_end()));
+        assert!(lines.next().unwrap().ends_with(",433.500000,-41.50"));
+
+        drop(recorder);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
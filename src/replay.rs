@@ -0,0 +1,256 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::signal_source::SignalSource;
+
+/// One reading of a recording, `offset` from the start of the recording
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RecordedReading {
+    pub offset: Duration,
+    pub frequency: f32,
+    pub strength: f32,
+}
+
+/// How fast a [`ReplaySource`] moves through its recording
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReplayTiming {
+    /// Wall clock time, as the readings were taken
+    Original,
+    /// Wall clock time multiplied by this factor
+    Accelerated(f32),
+    /// Only moves when advanced through the [`ReplayClock`], for deterministic tests
+    Manual,
+}
+
+/// Playback position of a [`ReplaySource`] in [`ReplayTiming::Manual`] mode, shared so it can
+/// be moved after the source has been handed to a scanner
+#[derive(Debug, Clone, Default)]
+pub struct ReplayClock(Arc<AtomicU64>);
+
+impl ReplayClock {
+    pub fn advance(&self, by: Duration) {
+        self.0.fetch_add(by.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn seek(&self, position: Duration) {
+        self.0.store(position.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    pub fn position(&self) -> Duration {
+        Duration::from_nanos(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// Readings of one frequency in time order
+#[derive(Debug, Clone)]
+struct Series {
+    frequency: f32,
+    samples: Vec<(Duration, f32)>,
+}
+
+impl Series {
+    /// Latest strength at or before `position`, the first one before the series starts
+    fn at(&self, position: Duration) -> f32 {
+        let index = self
+            .samples
+            .partition_point(|(offset, _)| *offset <= position)
+            .saturating_sub(1);
+        self.samples.get(index).map_or(f32::NEG_INFINITY, |(_, strength)| *strength)
+    }
+}
+
+/// [`SignalSource`] playing back recorded readings, e.g. the files written by
+/// [`CsvRecorder`](crate::recorder::CsvRecorder). Each read returns the latest recorded
+/// strength of that frequency at the current playback position, interpolated between the two
+/// nearest recorded frequencies off the grid and -inf outside the recorded span.
+#[derive(Debug)]
+pub struct ReplaySource {
+    series: Vec<Series>,
+    timing: ReplayTiming,
+    clock: ReplayClock,
+    started: Instant,
+    duration: Duration,
+}
+
+impl ReplaySource {
+    pub fn new(readings: impl IntoIterator<Item = RecordedReading>, timing: ReplayTiming) -> Self {
+        let mut readings: Vec<_> = readings.into_iter().collect();
+        readings.sort_by(|a, b| {
+            a.frequency
+                .total_cmp(&b.frequency)
+                .then(a.offset.cmp(&b.offset))
+        });
+
+        let mut series: Vec<Series> = Vec::new();
+        for reading in &readings {
+            match series.last_mut() {
+                Some(last) if last.frequency == reading.frequency => {
+                    last.samples.push((reading.offset, reading.strength));
+                }
+                _ => series.push(Series {
+                    frequency: reading.frequency,
+                    samples: vec![(reading.offset, reading.strength)],
+                }),
+            }
+        }
+
+        Self {
+            series,
+            timing,
+            clock: ReplayClock::default(),
+            started: Instant::now(),
+            duration: readings.iter().map(|r| r.offset).max().unwrap_or_default(),
+        }
+    }
+
+    /// Parse a recording in the [`CsvRecorder`](crate::recorder::CsvRecorder) format: Unix
+    /// timestamp in seconds, frequency in MHz and strength in dB per line
+    pub fn from_csv(reader: impl BufRead, timing: ReplayTiming) -> io::Result<Self> {
+        Ok(Self::from_rows(parse_csv(reader)?, timing))
+    }
+
+    /// Concatenate recording files, e.g. those of one [`CsvRecorder`](crate::recorder::CsvRecorder)
+    /// directory
+    pub fn open<P: AsRef<Path>>(
+        paths: impl IntoIterator<Item = P>,
+        timing: ReplayTiming,
+    ) -> io::Result<Self> {
+        let mut rows = Vec::new();
+        for path in paths {
+            rows.extend(parse_csv(BufReader::new(File::open(path)?))?);
+        }
+
+        Ok(Self::from_rows(rows, timing))
+    }
+
+    fn from_rows(rows: Vec<(f64, f32, f32)>, timing: ReplayTiming) -> Self {
+        let start = rows.iter().map(|row| row.0).fold(f64::INFINITY, f64::min);
+        let readings = rows.into_iter().map(|(timestamp, frequency, strength)| RecordedReading {
+            offset: Duration::from_secs_f64((timestamp - start).max(0.0)),
+            frequency,
+            strength,
+        });
+
+        Self::new(readings, timing)
+    }
+
+    /// Handle for moving the playback position in [`ReplayTiming::Manual`] mode
+    pub fn get_clock(&self) -> ReplayClock {
+        self.clock.clone()
+    }
+
+    /// Offset of the last recorded reading
+    pub fn get_duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn position(&self) -> Duration {
+        match self.timing {
+            ReplayTiming::Original => self.started.elapsed(),
+            ReplayTiming::Accelerated(factor) => self.started.elapsed().mul_f32(factor.max(0.0)),
+            ReplayTiming::Manual => self.clock.position(),
+        }
+    }
+
+    /// Whether playback has passed the last recorded reading
+    pub fn is_finished(&self) -> bool {
+        self.position() > self.duration
+    }
+}
+
+impl SignalSource for ReplaySource {
+    fn read_power(&mut self, frequency: f32) -> f32 {
+        let position = self.position();
+        let above = self.series.partition_point(|s| s.frequency < frequency);
+
+        match (above.checked_sub(1).map(|i| &self.series[i]), self.series.get(above)) {
+            (_, Some(exact)) if exact.frequency == frequency => exact.at(position),
+            (Some(low), Some(high)) => {
+                let t = (frequency - low.frequency) / (high.frequency - low.frequency);
+                let (low, high) = (low.at(position), high.at(position));
+                low + (high - low) * t
+            }
+            _ => f32::NEG_INFINITY,
+        }
+    }
+}
+
+/// (Unix timestamp, frequency, strength) rows of a recording
+fn parse_csv(reader: impl BufRead) -> io::Result<Vec<(f64, f32, f32)>> {
+    let mut rows = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("timestamp") {
+            continue;
+        }
+
+        let mut fields = line.split(',').map(|field| field.trim().parse::<f64>());
+        match (fields.next(), fields.next(), fields.next()) {
+            (Some(Ok(timestamp)), Some(Ok(frequency)), Some(Ok(strength))) => {
+                rows.push((timestamp, frequency as f32, strength as f32));
+            }
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("malformed recording line {}: {}", number + 1, line),
+                ))
+            }
+        }
+    }
+
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::{FrequencyRange, FrequencyScanner};
+
+    const RECORDING: &str = "timestamp,frequency_mhz,strength_db
+1700000000.000,433.000000,-80.00
+1700000000.010,434.000000,-35.00
+1700000000.020,435.000000,-80.00
+1700000001.000,433.000000,-80.00
+1700000001.010,434.000000,-80.00
+1700000001.020,435.000000,-40.00
+";
+
+    #[test]
+    fn test_replay_follows_clock() {
+        let mut replay = ReplaySource::from_csv(RECORDING.as_bytes(), ReplayTiming::Manual).unwrap();
+        let clock = replay.get_clock();
+
+        assert_eq!(replay.read_power(434.0), -35.0);
+        assert_eq!(replay.read_power(434.5), -57.5);
+        assert_eq!(replay.read_power(500.0), f32::NEG_INFINITY);
+
+        clock.seek(Duration::from_secs(1));
+        assert_eq!(replay.read_power(434.0), -35.0);
+        assert!(!replay.is_finished());
+        clock.advance(Duration::from_millis(20));
+        assert_eq!(replay.read_power(434.0), -80.0);
+        clock.advance(Duration::from_secs(1));
+        assert!(replay.is_finished());
+    }
+
+    #[test]
+    fn test_scanner_against_recording() {
+        let replay = ReplaySource::from_csv(RECORDING.as_bytes(), ReplayTiming::Manual).unwrap();
+        let clock = replay.get_clock();
+        let range = FrequencyRange {
+            start: 433.0,
+            end: 435.0,
+            step: 1.0,
+        };
+        let mut scanner = FrequencyScanner::with_source(range, -60.0, Box::new(replay));
+
+        assert_eq!(scanner.quick_scan()[0].frequency, 434.0);
+        clock.seek(Duration::from_secs(2));
+        assert_eq!(scanner.quick_scan()[0].frequency, 435.0);
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
+
+use crate::config::{LogRotation, LoggingConfig};
+use crate::log_query;
+
+/// Log file of a running instance, rotated as the logging configuration says. The current file
+/// keeps its name, rotated ones are named as [`log_query::log_files`] reads them: after the day
+/// or the Monday of the week they cover, or numbered from the newest when rotated by size. Only
+/// the newest `max_files` rotated files are kept.
+#[derive(Debug)]
+pub struct RollingLog {
+    directory: PathBuf,
+    name: String,
+    rotation: LogRotation,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    len: u64,
+    /// First day of the period the current file covers, for rotation by date
+    period: NaiveDate,
+    /// Set while rotating fails, so the failure is reported once
+    rotation_failed: bool,
+}
+
+impl RollingLog {
+    /// Append to `name` in the configured log directory, creating both. A file left from an
+    /// earlier period is rotated first.
+    pub fn open(config: &LoggingConfig, name: &str) -> io::Result<Self> {
+        fs::create_dir_all(&config.log_directory)?;
+        let file = open_append(&config.log_directory.join(name))?;
+        let metadata = file.metadata()?;
+        let modified: DateTime<Utc> = metadata.modified()?.into();
+        let mut log = Self {
+            directory: config.log_directory.clone(),
+            name: name.to_string(),
+            rotation: config.rotation.clone(),
+            max_bytes: u64::from(config.max_file_size_mb) * 1024 * 1024,
+            max_files: config.max_files as usize,
+            file,
+            len: metadata.len(),
+            period: period_start(&config.rotation, modified.date_naive()),
+            rotation_failed: false,
+        };
+        log.roll(Utc::now().date_naive(), 0)?;
+        Ok(log)
+    }
+
+    /// Rotate the current file when the period it covers is over, or when `incoming` more bytes
+    /// would take it past the size limit
+    fn roll(&mut self, today: NaiveDate, incoming: usize) -> io::Result<()> {
+        let rotated = match self.rotation {
+            LogRotation::Daily | LogRotation::Weekly => {
+                let period = period_start(&self.rotation, today);
+                if period == self.period {
+                    return Ok(());
+                }
+                let rotated = log_query::dated_name(&self.name, self.period);
+                self.period = period;
+                rotated
+            }
+            LogRotation::Size => {
+                if self.len == 0 || self.len + incoming as u64 <= self.max_bytes {
+                    return Ok(());
+                }
+                // Older files move up a number, the ones past `max_files` are pruned below
+                for number in (1..=self.max_files).rev() {
+                    let older = self.path(&log_query::numbered_name(&self.name, number));
+                    if older.is_file() {
+                        let name = log_query::numbered_name(&self.name, number + 1);
+                        fs::rename(&older, self.path(&name))?;
+                    }
+                }
+                log_query::numbered_name(&self.name, 1)
+            }
+        };
+        if self.len > 0 {
+            fs::rename(self.path(&self.name), self.path(&rotated))?;
+            self.file = open_append(&self.path(&self.name))?;
+            self.len = 0;
+        }
+        self.prune()
+    }
+
+    /// Remove the oldest rotated files beyond `max_files`
+    fn prune(&self) -> io::Result<()> {
+        let files = log_query::log_files(&self.directory, &self.name, &self.rotation)?;
+        // The current file comes last
+        let rotated = files.len().saturating_sub(1);
+        for path in files.iter().take(rotated.saturating_sub(self.max_files)) {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.directory.join(name)
+    }
+}
+
+impl Write for RollingLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // The event still goes to the current file, losing it would be worse than a large file
+        match self.roll(Utc::now().date_naive(), buf.len()) {
+            Ok(()) => self.rotation_failed = false,
+            Err(e) if !self.rotation_failed => {
+                self.rotation_failed = true;
+                eprintln!(
+                    "Failed to rotate {}: {}",
+                    self.path(&self.name).display(),
+                    e
+                );
+            }
+            Err(_) => {}
+        }
+        let written = self.file.write(buf)?;
+        self.len += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_append(path: &std::path::Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// First day of the rotation period `day` is in, weeks starting on Monday
+fn period_start(rotation: &LogRotation, day: NaiveDate) -> NaiveDate {
+    match rotation {
+        LogRotation::Weekly => {
+            day - chrono::Duration::days(i64::from(day.weekday().num_days_from_monday()))
+        }
+        LogRotation::Daily | LogRotation::Size => day,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(directory: &str, rotation: LogRotation) -> LoggingConfig {
+        LoggingConfig {
+            log_directory: std::env::temp_dir().join(format!(
+                "hexar-{}-{}",
+                directory,
+                std::process::id()
+            )),
+            rotation,
+            max_files: 2,
+            ..LoggingConfig::default()
+        }
+    }
+
+    fn names(config: &LoggingConfig) -> Vec<String> {
+        log_query::log_files(
+            &config.log_directory,
+            log_query::LOG_FILE_NAME,
+            &config.rotation,
+        )
+        .unwrap()
+        .iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+        .collect()
+    }
+
+    #[test]
+    fn test_rotation_by_date() {
+        let config = config("rolling-weekly", LogRotation::Weekly);
+        let mut log = RollingLog::open(&config, log_query::LOG_FILE_NAME).unwrap();
+        // Writing rolls by today's date, so lines go straight to the file
+        let write_line = |log: &mut RollingLog, line: &str| {
+            writeln!(log.file, "{}", line).unwrap();
+            log.len += line.len() as u64 + 1;
+        };
+        log.period = NaiveDate::from_ymd_opt(2024, 5, 27).unwrap();
+        write_line(&mut log, "first week");
+
+        // Saturday of the same week, then three weeks with a line each
+        let day = |d| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+        log.roll(day(1), 0).unwrap();
+        assert_eq!(names(&config), ["hexar.log"]);
+        for today in [day(3), day(10), day(17)] {
+            log.roll(today, 0).unwrap();
+            write_line(&mut log, &today.to_string());
+        }
+        assert_eq!(log.period, day(17));
+        // The week of May 27th went over max_files
+        assert_eq!(
+            names(&config),
+            ["hexar.log.2024-06-03", "hexar.log.2024-06-10", "hexar.log"]
+        );
+        let current = fs::read_to_string(config.log_directory.join("hexar.log")).unwrap();
+        assert_eq!(current, "2024-06-17\n");
+        fs::remove_dir_all(&config.log_directory).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_by_size() {
+        let mut config = config("rolling-size", LogRotation::Size);
+        config.max_file_size_mb = 0;
+        let mut log = RollingLog::open(&config, log_query::LOG_FILE_NAME).unwrap();
+        for line in ["one\n", "two\n", "three\n", "four\n"] {
+            log.write_all(line.as_bytes()).unwrap();
+        }
+        assert_eq!(names(&config), ["hexar.log.2", "hexar.log.1", "hexar.log"]);
+        let read = |name: &str| fs::read_to_string(config.log_directory.join(name)).unwrap();
+        assert_eq!(read("hexar.log.2"), "two\n");
+        assert_eq!(read("hexar.log.1"), "three\n");
+        assert_eq!(read("hexar.log"), "four\n");
+        fs::remove_dir_all(&config.log_directory).unwrap();
+    }
+}
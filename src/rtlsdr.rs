@@ -0,0 +1,228 @@
+use std::io;
+use std::os::raw::{c_int, c_void};
+use std::ptr;
+
+use log::{debug, warn};
+
+use crate::signal_source::SignalSource;
+
+#[repr(C)]
+struct RtlSdrDev {
+    _private: [u8; 0],
+}
+
+#[link(name = "rtlsdr")]
+extern "C" {
+    fn rtlsdr_get_device_count() -> u32;
+    fn rtlsdr_open(dev: *mut *mut RtlSdrDev, index: u32) -> c_int;
+    fn rtlsdr_close(dev: *mut RtlSdrDev) -> c_int;
+    fn rtlsdr_set_center_freq(dev: *mut RtlSdrDev, freq: u32) -> c_int;
+    fn rtlsdr_set_sample_rate(dev: *mut RtlSdrDev, rate: u32) -> c_int;
+    fn rtlsdr_set_tuner_gain_mode(dev: *mut RtlSdrDev, manual: c_int) -> c_int;
+    fn rtlsdr_set_tuner_gain(dev: *mut RtlSdrDev, gain: c_int) -> c_int;
+    fn rtlsdr_reset_buffer(dev: *mut RtlSdrDev) -> c_int;
+    fn rtlsdr_read_sync(
+        dev: *mut RtlSdrDev,
+        buf: *mut c_void,
+        len: c_int,
+        n_read: *mut c_int,
+    ) -> c_int;
+}
+
+#[derive(Debug, Clone)]
+pub struct RtlSdrConfig {
+    pub device_index: u32,
+    pub sample_rate: u32,
+    /// Manual tuner gain in tenths of a dB, automatic gain when `None`
+    pub gain_tenth_db: Option<i32>,
+    /// Bytes of interleaved I/Q read per measurement, a multiple of 512
+    pub buffer_len: usize,
+}
+
+impl Default for RtlSdrConfig {
+    fn default() -> Self {
+        Self {
+            device_index: 0,
+            sample_rate: 2_048_000,
+            gain_tenth_db: None,
+            buffer_len: 16 * 1024,
+        }
+    }
+}
+
+/// [`SignalSource`] reading band power from an RTL2832U dongle through librtlsdr. Power is
+/// the mean I/Q magnitude over one buffer in dBFS, so absolute levels depend on the gain.
+#[derive(Debug)]
+pub struct RtlSdrSource {
+    dev: *mut RtlSdrDev,
+    buffer: Vec<u8>,
+    sample_rate: u32,
+}
+
+// The device handle is only ever used through &mut self
+unsafe impl Send for RtlSdrSource {}
+
+fn check(ret: c_int, what: &str) -> io::Result<()> {
+    if ret < 0 {
+        Err(io::Error::other(format!(
+            "rtlsdr {} failed ({})",
+            what, ret
+        )))
+    } else {
+        Ok(())
+    }
+}
+
+impl RtlSdrSource {
+    pub fn device_count() -> u32 {
+        unsafe { rtlsdr_get_device_count() }
+    }
+
+    pub fn open(config: &RtlSdrConfig) -> io::Result<Self> {
+        if config.buffer_len == 0 || !config.buffer_len.is_multiple_of(512) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "rtlsdr buffer length must be a non-zero multiple of 512",
+            ));
+        }
+
+        let mut dev = ptr::null_mut();
+        check(
+            unsafe { rtlsdr_open(&mut dev, config.device_index) },
+            "open",
+        )?;
+
+        let source = Self {
+            dev,
+            buffer: vec![0; config.buffer_len],
+            sample_rate: config.sample_rate,
+        };
+
+        unsafe {
+            check(
+                rtlsdr_set_sample_rate(dev, config.sample_rate),
+                "set_sample_rate",
+            )?;
+            match config.gain_tenth_db {
+                Some(gain) => {
+                    check(rtlsdr_set_tuner_gain_mode(dev, 1), "set_tuner_gain_mode")?;
+                    check(rtlsdr_set_tuner_gain(dev, gain), "set_tuner_gain")?;
+                }
+                None => check(rtlsdr_set_tuner_gain_mode(dev, 0), "set_tuner_gain_mode")?,
+            }
+        }
+
+        debug!("Opened RTL-SDR device {}", config.device_index);
+        Ok(source)
+    }
+
+    fn measure(&mut self, frequency: f32) -> io::Result<f32> {
+        let received = self.capture(frequency)?;
+        Ok(iq_power_dbfs(self.buffer.get(..received).unwrap_or(&[])))
+    }
+
+    /// Retune and fill the byte buffer, returning how many bytes were read
+    fn capture(&mut self, frequency: f32) -> io::Result<usize> {
+        let len = self.buffer.len() as c_int;
+        let mut n_read = 0;
+
+        unsafe {
+            check(
+                rtlsdr_set_center_freq(self.dev, (frequency * 1e6) as u32),
+                "set_center_freq",
+            )?;
+            check(rtlsdr_reset_buffer(self.dev), "reset_buffer")?;
+
+            // The first buffer after retuning still holds samples from the PLL settling
+            for _ in 0..2 {
+                check(
+                    rtlsdr_read_sync(self.dev, self.buffer.as_mut_ptr().cast(), len, &mut n_read),
+                    "read_sync",
+                )?;
+            }
+        }
+
+        Ok(n_read.max(0) as usize)
+    }
+}
+
+impl SignalSource for RtlSdrSource {
+    fn read_power(&mut self, frequency: f32) -> f32 {
+        self.measure(frequency).unwrap_or_else(|e| {
+            warn!("RTL-SDR reading at {:.2} MHz failed: {}", frequency, e);
+            f32::NEG_INFINITY
+        })
+    }
+
+    fn sample_rate(&self) -> Option<f32> {
+        Some(self.sample_rate as f32)
+    }
+
+    fn capture_iq(&mut self, frequency: f32, buf: &mut [[f32; 2]]) -> io::Result<usize> {
+        let mut captured = 0;
+
+        // Each retune yields one buffer, keep capturing until the caller's block is full
+        while captured < buf.len() {
+            let received = self.capture(frequency)?;
+            let bytes = self.buffer.get(..received).unwrap_or(&[]);
+            let Some(remaining) = buf.get_mut(captured..) else {
+                break;
+            };
+
+            let mut copied = 0;
+            for (sample, iq) in remaining.iter_mut().zip(bytes.chunks_exact(2)) {
+                *sample = [
+                    (iq[0] as f32 - 127.5) / 127.5,
+                    (iq[1] as f32 - 127.5) / 127.5,
+                ];
+                copied += 1;
+            }
+            if copied == 0 {
+                break;
+            }
+            captured += copied;
+        }
+
+        Ok(captured)
+    }
+}
+
+impl Drop for RtlSdrSource {
+    fn drop(&mut self) {
+        unsafe {
+            rtlsdr_close(self.dev);
+        }
+    }
+}
+
+/// Mean power of offset-binary 8 bit I/Q samples relative to full scale
+fn iq_power_dbfs(samples: &[u8]) -> f32 {
+    let pairs = samples.len() / 2;
+    if pairs == 0 {
+        return f32::NEG_INFINITY;
+    }
+
+    let sum: f32 = samples
+        .chunks_exact(2)
+        .map(|iq| {
+            let i = (iq[0] as f32 - 127.5) / 127.5;
+            let q = (iq[1] as f32 - 127.5) / 127.5;
+            i * i + q * q
+        })
+        .sum();
+
+    10.0 * (sum / pairs as f32).log10()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iq_power() {
+        // Full scale constant I/Q is about +3 dBFS, centered samples are near silence
+        assert!((iq_power_dbfs(&[255, 255, 0, 0]) - 3.01).abs() < 0.01);
+        assert!(iq_power_dbfs(&[127, 128, 128, 127]) < -40.0);
+        assert_eq!(iq_power_dbfs(&[]), f32::NEG_INFINITY);
+    }
+}
@@ -1,334 +1,388 @@
-use crate::config::SafetyConfig;
-use crate::error::HexarResult;
-use anyhow::Result;
-use serde::{Deserialize, Serialize};
-use tracing::{info, warn, error, debug};
-use chrono::Utc;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SafetyDiagnosticsResult {
-    pub safe_to_operate: bool,
-    pub checks_performed: usize,
-    pub issues: Vec<String>,
-    pub warnings: Vec<String>,
-    pub component_status: ComponentStatus,
-    pub timestamp: chrono::DateTime<chrono::Utc>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ComponentStatus {
-    pub antennas: Vec<AntennaSafetyStatus>,
-    pub power_system: PowerSystemStatus,
-    pub cooling_system: CoolingSystemStatus,
-    pub emergency_systems: EmergencySystemStatus,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct AntennaSafetyStatus {
-    pub id: u8,
-    pub operational: bool,
-    pub temperature_celsius: f32,
-    pub power_consumption_watts: f32,
-    pub signal_strength: f32,
-    pub last_check: chrono::DateTime<chrono::Utc>,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PowerSystemStatus {
-    pub voltage_nominal: f32,
-    pub voltage_actual: f32,
-    pub current_draw: f32,
-    pub power_consumption: f32,
-    pub surge_protection_active: bool,
-    pub backup_power_available: bool,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct CoolingSystemStatus {
-    pub fan_speed: f32,
-    pub ambient_temperature: f32,
-    pub internal_temperature: f32,
-    pub cooling_efficiency: f32,
-    pub filter_status: FilterStatus,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum FilterStatus {
-    Clean,
-    Dirty,
-    Replaced,
-    Missing,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct EmergencySystemStatus {
-    pub emergency_stop_functional: bool,
-    pub fire_suppression_ready: bool,
-    pub radiation_monitoring_active: bool,
-    pub evacuation_signals_ready: bool,
-}
-
-pub struct SafetyManager {
-    config: SafetyConfig,
-    last_diagnostics: Option<SafetyDiagnosticsResult>,
-    emergency_stop_triggered: bool,
-    shutdown_requested: bool,
-}
-
-impl SafetyManager {
-    pub fn new(config: SafetyConfig) -> HexarResult<Self> {
-        Ok(Self {
-            config,
-            last_diagnostics: None,
-            emergency_stop_triggered: false,
-            shutdown_requested: false,
-        })
-    }
-    
-    pub async fn run_full_diagnostics(&mut self) -> Result<SafetyDiagnosticsResult> {
-        info!("Running comprehensive safety diagnostics...");
-        
-        let mut issues = Vec::new();
-        let mut warnings = Vec::new();
-        let mut checks_performed = 0;
-        
-        // Check antenna systems
-        let antenna_status = self.check_antenna_systems().await?;
-        checks_performed += antenna_status.len();
-        
-        for antenna in &antenna_status {
-            if !antenna.operational {
-                issues.push(format!("Antenna {} is not operational", antenna.id));
-            }
-            
-            if antenna.temperature_celsius > self.config.temperature_limits.warning_celsius {
-                warnings.push(format!(
-                    "Antenna {} temperature high: {:.1}°C", 
-                    antenna.id, antenna.temperature_celsius
-                ));
-            }
-            
-            if antenna.temperature_celsius > self.config.temperature_limits.critical_celsius {
-                issues.push(format!(
-                    "Antenna {} temperature critical: {:.1}°C", 
-                    antenna.id, antenna.temperature_celsius
-                ));
-            }
-        }
-        
-        // Check power system
-        let power_status = self.check_power_system().await?;
-        checks_performed += 1;
-        
-        let voltage_deviation = (power_status.voltage_actual - power_status.voltage_nominal).abs() 
-            / power_status.voltage_nominal;
-        
-        if voltage_deviation > self.config.power_limits.voltage_tolerance {
-            issues.push(format!(
-                "Voltage out of tolerance: {:.1}V (nominal: {:.1}V)", 
-                power_status.voltage_actual, power_status.voltage_nominal
-            ));
-        }
-        
-        if power_status.power_consumption > self.config.power_limits.max_power_watts {
-            issues.push(format!(
-                "Power consumption exceeds limit: {:.1}W (limit: {:.1}W)", 
-                power_status.power_consumption, self.config.power_limits.max_power_watts
-            ));
-        }
-        
-        // Check cooling system
-        let cooling_status = self.check_cooling_system().await?;
-        checks_performed += 1;
-        
-        if cooling_status.internal_temperature > self.config.temperature_limits.warning_celsius {
-            warnings.push(format!(
-                "Internal temperature high: {:.1}°C", 
-                cooling_status.internal_temperature
-            ));
-        }
-        
-        if matches!(cooling_status.filter_status, FilterStatus::Dirty) {
-            warnings.push("Cooling filter is dirty and needs cleaning".to_string());
-        }
-        
-        if matches!(cooling_status.filter_status, FilterStatus::Missing) {
-            issues.push("Cooling filter is missing".to_string());
-        }
-        
-        // Check emergency systems
-        let emergency_status = self.check_emergency_systems().await?;
-        checks_performed += 1;
-        
-        if !emergency_status.emergency_stop_functional {
-            issues.push("Emergency stop system is not functional".to_string());
-        }
-        
-        if !emergency_status.fire_suppression_ready {
-            issues.push("Fire suppression system is not ready".to_string());
-        }
-        
-        if !emergency_status.radiation_monitoring_active {
-            warnings.push("Radiation monitoring system is not active".to_string());
-        }
-        
-        // Check maintenance schedule
-        let maintenance_overdue = Utc::now() - self.config.maintenance_schedule.last_maintenance;
-        let inspection_interval = chrono::Duration::hours(self.config.maintenance_schedule.inspection_interval_hours as i64);
-        
-        if maintenance_overdue > inspection_interval {
-            warnings.push("Scheduled maintenance is overdue".to_string());
-        }
-        
-        let component_status = ComponentStatus {
-            antennas: antenna_status,
-            power_system: power_status,
-            cooling_system: cooling_status,
-            emergency_systems: emergency_status,
-        };
-        
-        let safe_to_operate = issues.is_empty() && !self.emergency_stop_triggered;
-        
-        let result = SafetyDiagnosticsResult {
-            safe_to_operate,
-            checks_performed,
-            issues,
-            warnings,
-            component_status,
-            timestamp: Utc::now(),
-        };
-        
-        self.last_diagnostics = Some(result.clone());
-        
-        if safe_to_operate {
-            info!("Safety diagnostics passed: {} checks performed", checks_performed);
-        } else {
-            error!("Safety diagnostics failed: {} critical issues found", result.issues.len());
-        }
-        
-        Ok(result)
-    }
-    
-    pub async fn run_periodic_checks(&mut self) -> Result<()> {
-        debug!("Running periodic safety checks...");
-        
-        // Quick checks that don't require full diagnostics
-        let power_status = self.check_power_system().await?;
-        
-        if power_status.power_consumption > self.config.power_limits.max_power_watts * 0.9 {
-            warn!("Power consumption approaching limit: {:.1}W", power_status.power_consumption);
-        }
-        
-        let cooling_status = self.check_cooling_system().await?;
-        
-        if cooling_status.internal_temperature > self.config.temperature_limits.critical_celsius {
-            error!("Critical temperature detected: {:.1}°C", cooling_status.internal_temperature);
-            self.trigger_emergency_stop("Critical temperature").await?;
-        }
-        
-        Ok(())
-    }
-    
-    pub async fn trigger_emergency_stop(&mut self, reason: &str) -> Result<()> {
-        error!("EMERGENCY STOP TRIGGERED: {}", reason);
-        self.emergency_stop_triggered = true;
-        
-        // TODO: Implement actual emergency stop procedures
-        // - Cut power to transmitters
-        // - Activate emergency signals
-        // - Log emergency event
-        // - Notify operators
-        
-        Ok(())
-    }
-    
-    pub async fn should_shutdown(&self, error: &anyhow::Error) -> Result<bool> {
-        // Check if error indicates a safety-critical condition
-        let error_string = error.to_string().to_lowercase();
-        
-        if error_string.contains("temperature") && error_string.contains("critical") {
-            return Ok(true);
-        }
-        
-        if error_string.contains("power") && error_string.contains("fail") {
-            return Ok(true);
-        }
-        
-        if error_string.contains("emergency") || error_string.contains("safety") {
-            return Ok(true);
-        }
-        
-        // Check if we've had too many errors recently
-        if let Some(last_diag) = &self.last_diagnostics {
-            // TODO: Implement error rate tracking
-        }
-        
-        Ok(false)
-    }
-    
-    pub async fn shutdown(&mut self) -> Result<()> {
-        info!("Shutting down safety manager...");
-        
-        // Perform final safety checks
-        self.run_periodic_checks().await?;
-        
-        // Log shutdown
-        info!("Safety manager shutdown complete");
-        
-        Ok(())
-    }
-    
-    // Private helper methods for component checks
-    async fn check_antenna_systems(&self) -> Result<Vec<AntennaSafetyStatus>> {
-        let mut antenna_status = Vec::new();
-        
-        // TODO: Implement actual antenna status checking
-        // For now, simulate with placeholder data
-        
-        for i in 0..6 {
-            antenna_status.push(AntennaSafetyStatus {
-                id: i,
-                operational: true,
-                temperature_celsius: 25.0 + (i as f32 * 0.5),
-                power_consumption_watts: 5.0 + (i as f32 * 0.2),
-                signal_strength: -30.0 - (i as f32 * 2.0),
-                last_check: Utc::now(),
-            });
-        }
-        
-        Ok(antenna_status)
-    }
-    
-    async fn check_power_system(&self) -> Result<PowerSystemStatus> {
-        // TODO: Implement actual power system monitoring
-        Ok(PowerSystemStatus {
-            voltage_nominal: 12.0,
-            voltage_actual: 12.1,
-            current_draw: 8.5,
-            power_consumption: 102.85,
-            surge_protection_active: false,
-            backup_power_available: true,
-        })
-    }
-    
-    async fn check_cooling_system(&self) -> Result<CoolingSystemStatus> {
-        // TODO: Implement actual cooling system monitoring
-        Ok(CoolingSystemStatus {
-            fan_speed: 1500.0,
-            ambient_temperature: 22.0,
-            internal_temperature: 35.0,
-            cooling_efficiency: 0.85,
-            filter_status: FilterStatus::Clean,
-        })
-    }
-    
-    async fn check_emergency_systems(&self) -> Result<EmergencySystemStatus> {
-        // TODO: Implement actual emergency system testing
-        Ok(EmergencySystemStatus {
-            emergency_stop_functional: true,
-            fire_suppression_ready: true,
-            radiation_monitoring_active: true,
-            evacuation_signals_ready: true,
-        })
-    }
-}
+use crate::config::SafetyConfig;
+use crate::error::HexarResult;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn, error, debug};
+use chrono::Utc;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyDiagnosticsResult {
+    pub safe_to_operate: bool,
+    pub checks_performed: usize,
+    pub issues: Vec<String>,
+    pub warnings: Vec<String>,
+    pub component_status: ComponentStatus,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+/// What a check of one component found, `issues` making it unsafe to operate
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SafetyFindings {
+    pub issues: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentStatus {
+    pub antennas: Vec<AntennaSafetyStatus>,
+    pub power_system: PowerSystemStatus,
+    pub cooling_system: CoolingSystemStatus,
+    pub emergency_systems: EmergencySystemStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AntennaSafetyStatus {
+    pub id: u8,
+    pub operational: bool,
+    pub temperature_celsius: f32,
+    pub power_consumption_watts: f32,
+    pub signal_strength: f32,
+    pub last_check: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerSystemStatus {
+    pub voltage_nominal: f32,
+    pub voltage_actual: f32,
+    pub current_draw: f32,
+    pub power_consumption: f32,
+    pub surge_protection_active: bool,
+    pub backup_power_available: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoolingSystemStatus {
+    pub fan_speed: f32,
+    pub ambient_temperature: f32,
+    pub internal_temperature: f32,
+    pub cooling_efficiency: f32,
+    pub filter_status: FilterStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum FilterStatus {
+    Clean,
+    Dirty,
+    Replaced,
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmergencySystemStatus {
+    pub emergency_stop_functional: bool,
+    pub fire_suppression_ready: bool,
+    pub radiation_monitoring_active: bool,
+    pub evacuation_signals_ready: bool,
+}
+
+pub struct SafetyManager {
+    config: SafetyConfig,
+    last_diagnostics: Option<SafetyDiagnosticsResult>,
+    emergency_stop_triggered: bool,
+    #[allow(dead_code)]
+    shutdown_requested: bool,
+}
+
+impl SafetyManager {
+    pub fn new(config: SafetyConfig) -> HexarResult<Self> {
+        Ok(Self {
+            config,
+            last_diagnostics: None,
+            emergency_stop_triggered: false,
+            shutdown_requested: false,
+        })
+    }
+    
+    pub async fn run_full_diagnostics(&mut self) -> Result<SafetyDiagnosticsResult> {
+        info!("Running comprehensive safety diagnostics...");
+        
+        let mut issues = Vec::new();
+        let mut warnings = Vec::new();
+        let mut checks_performed = 0;
+        
+        // Check antenna systems
+        let antenna_status = self.check_antenna_systems().await?;
+        checks_performed += antenna_status.len();
+        
+        for antenna in &antenna_status {
+            let findings = self.judge_antenna(antenna);
+            issues.extend(findings.issues);
+            warnings.extend(findings.warnings);
+        }
+        
+        // Check power system
+        let (power_status, findings) = self.check_power().await?;
+        checks_performed += 1;
+        issues.extend(findings.issues);
+        warnings.extend(findings.warnings);
+        
+        // Check cooling system
+        let (cooling_status, findings) = self.check_cooling().await?;
+        checks_performed += 1;
+        issues.extend(findings.issues);
+        warnings.extend(findings.warnings);
+        
+        // Check emergency systems
+        let emergency_status = self.check_emergency_systems().await?;
+        checks_performed += 1;
+        
+        if !emergency_status.emergency_stop_functional {
+            issues.push("Emergency stop system is not functional".to_string());
+        }
+        
+        if !emergency_status.fire_suppression_ready {
+            issues.push("Fire suppression system is not ready".to_string());
+        }
+        
+        if !emergency_status.radiation_monitoring_active {
+            warnings.push("Radiation monitoring system is not active".to_string());
+        }
+        
+        // Check maintenance schedule
+        let maintenance_overdue = Utc::now() - self.config.maintenance_schedule.last_maintenance;
+        let inspection_interval = chrono::Duration::hours(self.config.maintenance_schedule.inspection_interval_hours as i64);
+        
+        if maintenance_overdue > inspection_interval {
+            warnings.push("Scheduled maintenance is overdue".to_string());
+        }
+        
+        let component_status = ComponentStatus {
+            antennas: antenna_status,
+            power_system: power_status,
+            cooling_system: cooling_status,
+            emergency_systems: emergency_status,
+        };
+        
+        let safe_to_operate = issues.is_empty() && !self.emergency_stop_triggered;
+        
+        let result = SafetyDiagnosticsResult {
+            safe_to_operate,
+            checks_performed,
+            issues,
+            warnings,
+            component_status,
+            timestamp: Utc::now(),
+        };
+        
+        self.last_diagnostics = Some(result.clone());
+        
+        if safe_to_operate {
+            info!("Safety diagnostics passed: {} checks performed", checks_performed);
+        } else {
+            error!("Safety diagnostics failed: {} critical issues found", result.issues.len());
+        }
+        
+        Ok(result)
+    }
+    
+    /// Safety readings of antenna `antenna_id` and what is wrong with them, `None` for an
+    /// antenna the safety systems don't monitor
+    pub async fn check_antenna(&self, antenna_id: u8) -> Result<Option<(AntennaSafetyStatus, SafetyFindings)>> {
+        let antenna = self.check_antenna_systems().await?
+            .into_iter()
+            .find(|antenna| antenna.id == antenna_id);
+        Ok(antenna.map(|antenna| {
+            let findings = self.judge_antenna(&antenna);
+            (antenna, findings)
+        }))
+    }
+    
+    /// Power system readings and what is wrong with them
+    pub async fn check_power(&self) -> Result<(PowerSystemStatus, SafetyFindings)> {
+        let power_status = self.check_power_system().await?;
+        let mut findings = SafetyFindings::default();
+        
+        let voltage_deviation = (power_status.voltage_actual - power_status.voltage_nominal).abs() 
+            / power_status.voltage_nominal;
+        
+        if voltage_deviation > self.config.power_limits.voltage_tolerance {
+            findings.issues.push(format!(
+                "Voltage out of tolerance: {:.1}V (nominal: {:.1}V)", 
+                power_status.voltage_actual, power_status.voltage_nominal
+            ));
+        }
+        
+        if power_status.power_consumption > self.config.power_limits.max_power_watts {
+            findings.issues.push(format!(
+                "Power consumption exceeds limit: {:.1}W (limit: {:.1}W)", 
+                power_status.power_consumption, self.config.power_limits.max_power_watts
+            ));
+        }
+        
+        Ok((power_status, findings))
+    }
+    
+    /// Cooling system readings and what is wrong with them
+    pub async fn check_cooling(&self) -> Result<(CoolingSystemStatus, SafetyFindings)> {
+        let cooling_status = self.check_cooling_system().await?;
+        let mut findings = SafetyFindings::default();
+        
+        if cooling_status.internal_temperature > self.config.temperature_limits.warning_celsius {
+            findings.warnings.push(format!(
+                "Internal temperature high: {:.1}°C", 
+                cooling_status.internal_temperature
+            ));
+        }
+        
+        if matches!(cooling_status.filter_status, FilterStatus::Dirty) {
+            findings.warnings.push("Cooling filter is dirty and needs cleaning".to_string());
+        }
+        
+        if matches!(cooling_status.filter_status, FilterStatus::Missing) {
+            findings.issues.push("Cooling filter is missing".to_string());
+        }
+        
+        Ok((cooling_status, findings))
+    }
+    
+    pub async fn run_periodic_checks(&mut self) -> Result<()> {
+        debug!("Running periodic safety checks...");
+        
+        // Quick checks that don't require full diagnostics
+        let power_status = self.check_power_system().await?;
+        
+        if power_status.power_consumption > self.config.power_limits.max_power_watts * 0.9 {
+            warn!("Power consumption approaching limit: {:.1}W", power_status.power_consumption);
+        }
+        
+        let cooling_status = self.check_cooling_system().await?;
+        
+        if cooling_status.internal_temperature > self.config.temperature_limits.critical_celsius {
+            error!("Critical temperature detected: {:.1}°C", cooling_status.internal_temperature);
+            self.trigger_emergency_stop("Critical temperature").await?;
+        }
+        
+        Ok(())
+    }
+    
+    pub async fn trigger_emergency_stop(&mut self, reason: &str) -> Result<()> {
+        error!("EMERGENCY STOP TRIGGERED: {}", reason);
+        self.emergency_stop_triggered = true;
+        
+        // TODO: Implement actual emergency stop procedures
+        // - Cut power to transmitters
+        // - Activate emergency signals
+        // - Log emergency event
+        // - Notify operators
+        
+        Ok(())
+    }
+    
+    pub fn is_emergency_stopped(&self) -> bool {
+        self.emergency_stop_triggered
+    }
+    
+    pub async fn should_shutdown(&self, error: &anyhow::Error) -> Result<bool> {
+        // Check if error indicates a safety-critical condition
+        let error_string = error.to_string().to_lowercase();
+        
+        if error_string.contains("temperature") && error_string.contains("critical") {
+            return Ok(true);
+        }
+        
+        if error_string.contains("power") && error_string.contains("fail") {
+            return Ok(true);
+        }
+        
+        if error_string.contains("emergency") || error_string.contains("safety") {
+            return Ok(true);
+        }
+        
+        // Check if we've had too many errors recently
+        if let Some(_last_diag) = &self.last_diagnostics {
+            // TODO: Implement error rate tracking
+        }
+        
+        Ok(false)
+    }
+    
+    pub async fn shutdown(&mut self) -> Result<()> {
+        info!("Shutting down safety manager...");
+        
+        // Perform final safety checks
+        self.run_periodic_checks().await?;
+        
+        // Log shutdown
+        info!("Safety manager shutdown complete");
+        
+        Ok(())
+    }
+    
+    // Private helper methods for component checks
+    fn judge_antenna(&self, antenna: &AntennaSafetyStatus) -> SafetyFindings {
+        let mut findings = SafetyFindings::default();
+        
+        if !antenna.operational {
+            findings.issues.push(format!("Antenna {} is not operational", antenna.id));
+        }
+        
+        if antenna.temperature_celsius > self.config.temperature_limits.warning_celsius {
+            findings.warnings.push(format!(
+                "Antenna {} temperature high: {:.1}°C", 
+                antenna.id, antenna.temperature_celsius
+            ));
+        }
+        
+        if antenna.temperature_celsius > self.config.temperature_limits.critical_celsius {
+            findings.issues.push(format!(
+                "Antenna {} temperature critical: {:.1}°C", 
+                antenna.id, antenna.temperature_celsius
+            ));
+        }
+        
+        findings
+    }
+    
+    async fn check_antenna_systems(&self) -> Result<Vec<AntennaSafetyStatus>> {
+        let mut antenna_status = Vec::new();
+        
+        // TODO: Implement actual antenna status checking
+        // For now, simulate with placeholder data
+        
+        for i in 0..6 {
+            antenna_status.push(AntennaSafetyStatus {
+                id: i,
+                operational: true,
+                temperature_celsius: 25.0 + (i as f32 * 0.5),
+                power_consumption_watts: 5.0 + (i as f32 * 0.2),
+                signal_strength: -30.0 - (i as f32 * 2.0),
+                last_check: Utc::now(),
+            });
+        }
+        
+        Ok(antenna_status)
+    }
+    
+    async fn check_power_system(&self) -> Result<PowerSystemStatus> {
+        // TODO: Implement actual power system monitoring
+        Ok(PowerSystemStatus {
+            voltage_nominal: 12.0,
+            voltage_actual: 12.1,
+            current_draw: 8.5,
+            power_consumption: 102.85,
+            surge_protection_active: false,
+            backup_power_available: true,
+        })
+    }
+    
+    async fn check_cooling_system(&self) -> Result<CoolingSystemStatus> {
+        // TODO: Implement actual cooling system monitoring
+        Ok(CoolingSystemStatus {
+            fan_speed: 1500.0,
+            ambient_temperature: 22.0,
+            internal_temperature: 35.0,
+            cooling_efficiency: 0.85,
+            filter_status: FilterStatus::Clean,
+        })
+    }
+    
+    async fn check_emergency_systems(&self) -> Result<EmergencySystemStatus> {
+        // TODO: Implement actual emergency system testing
+        Ok(EmergencySystemStatus {
+            emergency_stop_functional: true,
+            fire_suppression_ready: true,
+            radiation_monitoring_active: true,
+            evacuation_signals_ready: true,
+        })
+    }
+}
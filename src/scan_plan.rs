@@ -0,0 +1,134 @@
+use crate::peaks::Peak;
+use crate::scanner::{Dwell, FrequencyRange, FrequencyScanner};
+
+/// One range of a [`ScanPlan`]. A range with priority 3 is swept three times as often as one
+/// with priority 1.
+#[derive(Debug, Clone)]
+pub struct PlannedRange {
+    pub range: FrequencyRange,
+    /// Dwell for this range, the scanner's own dwell when `None`
+    pub dwell: Option<Dwell>,
+    pub priority: u32,
+}
+
+/// Peaks of one sweep scheduled by a [`ScanPlan`]
+#[derive(Debug, Clone)]
+pub struct PlanScan {
+    /// Index of the swept range in the plan
+    pub range: usize,
+    pub peaks: Vec<Peak>,
+}
+
+/// Several frequency ranges revisited in proportion to their priorities. Scheduling is smooth
+/// weighted round robin, so high priority ranges are spread out between the others instead of
+/// being swept back to back.
+#[derive(Debug, Clone, Default)]
+pub struct ScanPlan {
+    ranges: Vec<PlannedRange>,
+    credit: Vec<i64>,
+}
+
+impl ScanPlan {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a range, returning its index
+    pub fn add_range(&mut self, range: FrequencyRange, priority: u32) -> usize {
+        self.add(PlannedRange {
+            range,
+            dwell: None,
+            priority,
+        })
+    }
+
+    pub fn add(&mut self, planned: PlannedRange) -> usize {
+        self.ranges.push(planned);
+        self.credit.push(0);
+        self.ranges.len() - 1
+    }
+
+    pub fn get_ranges(&self) -> &[PlannedRange] {
+        &self.ranges
+    }
+
+    /// Index of the range to sweep next, `None` for an empty plan
+    pub fn next_range(&mut self) -> Option<usize> {
+        // Priority 0 still gets swept, just as rarely as possible
+        let weight = |planned: &PlannedRange| i64::from(planned.priority.max(1));
+        let total: i64 = self.ranges.iter().map(weight).sum();
+
+        for (credit, planned) in self.credit.iter_mut().zip(&self.ranges) {
+            *credit += weight(planned);
+        }
+
+        let (index, _) = self
+            .credit
+            .iter()
+            .enumerate()
+            .rev()
+            .max_by_key(|&(_, credit)| *credit)?;
+        self.credit[index] -= total;
+        Some(index)
+    }
+}
+
+impl FrequencyScanner {
+    /// Sweep the range `plan` schedules next with its dwell and return the peaks found. The
+    /// scanner's own range and dwell are restored afterwards.
+    pub fn scan_plan(&mut self, plan: &mut ScanPlan) -> Option<PlanScan> {
+        let index = plan.next_range()?;
+        let planned = &plan.ranges[index];
+
+        let range = self.set_range(planned.range.clone());
+        let dwell = self.get_dwell();
+        if let Some(planned_dwell) = planned.dwell {
+            self.set_dwell(planned_dwell);
+        }
+
+        let peaks = self.find_peaks();
+
+        self.set_range(range);
+        self.set_dwell(dwell);
+        Some(PlanScan {
+            range: index,
+            peaks,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: f32, end: f32) -> FrequencyRange {
+        FrequencyRange {
+            start,
+            end,
+            step: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_priorities_are_interleaved() {
+        let mut plan = ScanPlan::new();
+        plan.add_range(range(433.0, 435.0), 3);
+        plan.add_range(range(860.0, 870.0), 1);
+
+        let order: Vec<_> = (0..8).filter_map(|_| plan.next_range()).collect();
+        assert_eq!(order, [0, 0, 1, 0, 0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_scan_plan_restores_range() {
+        let mut scanner = FrequencyScanner::new(range(400.0, 500.0), -60.0);
+        let mut plan = ScanPlan::new();
+        plan.add_range(range(430.0, 436.0), 1);
+
+        let scan = scanner.scan_plan(&mut plan).unwrap();
+        assert_eq!(scan.range, 0);
+        assert!(!scan.peaks.is_empty());
+        assert_eq!(scanner.get_range().start, 400.0);
+        assert!(ScanPlan::new().next_range().is_none());
+    }
+}
@@ -0,0 +1,198 @@
+use std::io::{self, Write};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::classify::SignalClass;
+use crate::control_socket::TargetReport;
+use crate::peaks::Peak;
+use crate::radar_controller::RadarController;
+use crate::scanner::ScanResult;
+
+/// What `hexar scan` runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ScanKind {
+    /// One scan cycle of the controller, signals and the targets they place
+    Cycle,
+    /// One sweep of the range, its peaks
+    Quick,
+    /// Signal strength around one frequency in MHz
+    Refined { frequency: f32 },
+    /// A sweep refined around every signal it finds
+    Full,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SignalRecord {
+    pub frequency_mhz: f32,
+    pub strength_db: f32,
+    pub confidence: f32,
+    pub class: SignalClass,
+}
+
+impl From<&ScanResult> for SignalRecord {
+    fn from(result: &ScanResult) -> Self {
+        Self {
+            frequency_mhz: result.frequency,
+            strength_db: result.strength,
+            confidence: result.confidence,
+            class: result.class,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PeakRecord {
+    pub frequency_mhz: f32,
+    pub peak_db: f32,
+    pub prominence_db: f32,
+    pub bandwidth_mhz: f32,
+}
+
+impl From<&Peak> for PeakRecord {
+    fn from(peak: &Peak) -> Self {
+        Self {
+            frequency_mhz: peak.center_freq,
+            peak_db: peak.peak_db,
+            prominence_db: peak.prominence,
+            bandwidth_mhz: peak.bandwidth_estimate,
+        }
+    }
+}
+
+/// Outcome of one `hexar scan`, e.g. `{"mode": "quick", "peaks": [...]}` as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ScanReport {
+    Cycle {
+        scan_id: Uuid,
+        timestamp: DateTime<Utc>,
+        duration_ms: f64,
+        signals: Vec<SignalRecord>,
+        targets: Vec<TargetReport>,
+    },
+    Quick {
+        peaks: Vec<PeakRecord>,
+    },
+    Refined {
+        signal: SignalRecord,
+    },
+    Full {
+        signals: Vec<SignalRecord>,
+    },
+}
+
+impl ScanReport {
+    /// Run `kind` on `controller`, which a cycle needs initialized
+    pub async fn run(controller: &mut RadarController, kind: ScanKind) -> Result<Self> {
+        let report = match kind {
+            ScanKind::Cycle => {
+                let cycle = controller.run_scan_cycle().await?;
+                ScanReport::Cycle {
+                    scan_id: cycle.scan_id,
+                    timestamp: cycle.timestamp,
+                    duration_ms: cycle.scan_duration.as_secs_f64() * 1000.0,
+                    signals: cycle.scan_results.iter().map(Into::into).collect(),
+                    targets: cycle.targets_detected.iter().map(Into::into).collect(),
+                }
+            }
+            ScanKind::Quick => ScanReport::Quick {
+                peaks: controller
+                    .get_scanner_mut()
+                    .find_peaks()
+                    .iter()
+                    .map(Into::into)
+                    .collect(),
+            },
+            ScanKind::Refined { frequency } => {
+                // Starting from half the sweep step, like the interactive scanner
+                let step = controller.get_config().frequency_range.step_mhz * 0.5;
+                let result = controller.get_scanner_mut().refined_scan(frequency, step);
+                ScanReport::Refined {
+                    signal: (&result).into(),
+                }
+            }
+            ScanKind::Full => ScanReport::Full {
+                signals: controller
+                    .get_scanner_mut()
+                    .full_scan_cycle()
+                    .iter()
+                    .map(Into::into)
+                    .collect(),
+            },
+        };
+        Ok(report)
+    }
+
+    /// The signals, or the peaks of a quick scan, as CSV with a header row. The targets of a
+    /// cycle are only in the JSON.
+    pub fn write_csv(&self, mut out: impl Write) -> io::Result<()> {
+        let signals = match self {
+            ScanReport::Quick { peaks } => {
+                writeln!(out, "frequency_mhz,peak_db,prominence_db,bandwidth_mhz")?;
+                for peak in peaks {
+                    writeln!(
+                        out,
+                        "{},{},{},{}",
+                        peak.frequency_mhz, peak.peak_db, peak.prominence_db, peak.bandwidth_mhz
+                    )?;
+                }
+                return Ok(());
+            }
+            ScanReport::Cycle { signals, .. } | ScanReport::Full { signals } => signals.as_slice(),
+            ScanReport::Refined { signal } => std::slice::from_ref(signal),
+        };
+        writeln!(out, "frequency_mhz,strength_db,confidence,class")?;
+        for signal in signals {
+            let class = serde_json::to_value(signal.class)
+                .ok()
+                .and_then(|class| class.as_str().map(str::to_string))
+                .unwrap_or_default();
+            writeln!(
+                out,
+                "{},{},{},{}",
+                signal.frequency_mhz, signal.strength_db, signal.confidence, class
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RadarConfig;
+
+    #[tokio::test]
+    async fn test_scan_report() {
+        let mut controller = RadarController::new(RadarConfig::default()).unwrap();
+        let report = ScanReport::run(&mut controller, ScanKind::Refined { frequency: 2400.0 })
+            .await
+            .unwrap();
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["mode"], "refined");
+        assert!(json["signal"]["frequency_mhz"].is_number());
+
+        let report = ScanReport::Full {
+            signals: vec![SignalRecord {
+                frequency_mhz: 433.92,
+                strength_db: -41.5,
+                confidence: 0.75,
+                class: SignalClass::NarrowbandCw,
+            }],
+        };
+        let mut csv = Vec::new();
+        report.write_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv).unwrap(),
+            "frequency_mhz,strength_db,confidence,class\n433.92,-41.5,0.75,narrowband_cw\n"
+        );
+
+        // A cycle needs the controller initialized
+        assert!(ScanReport::run(&mut controller, ScanKind::Cycle)
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,391 @@
+use std::collections::VecDeque;
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use log::{debug, info};
+use tokio::sync::watch;
+
+use crate::scanner::{FrequencyScanner, ScanResult, SignalReading};
+
+/// Pause between the sweep and refine cycles of a stream
+const CYCLE_INTERVAL: Duration = Duration::from_millis(100);
+
+/// When a [`ScanStream`] ends on its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanLimit {
+    /// After this many sweep and refine cycles
+    Cycles(u64),
+    /// Once no new cycle can start before this much time has passed
+    Duration(Duration),
+    /// Only when cancelled or dropped
+    Unbounded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScanPhase {
+    #[default]
+    Idle,
+    Sweeping,
+    Refining,
+    Done,
+}
+
+/// Position of a [`ScanStream`] within its current cycle. `completed` and `total` count sweep
+/// steps while sweeping and peaks while refining.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ScanProgress {
+    pub cycle: u64,
+    pub phase: ScanPhase,
+    pub completed: usize,
+    pub total: usize,
+}
+
+/// Cancels a [`ScanStream`] from another task. The stream notices before its next measurement
+/// and during pauses, then returns `None`.
+#[derive(Debug, Clone)]
+pub struct ScanCancel(Arc<watch::Sender<bool>>);
+
+impl Default for ScanCancel {
+    fn default() -> Self {
+        Self(Arc::new(watch::Sender::new(false)))
+    }
+}
+
+impl ScanCancel {
+    pub fn cancel(&self) {
+        self.0.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.0.borrow()
+    }
+
+    /// Resolves once [`ScanCancel::cancel`] has been called
+    pub async fn cancelled(&self) {
+        let mut cancelled = self.0.subscribe();
+        // The sender lives in self, so this only returns once the flag is set
+        let _ = cancelled.wait_for(|&cancelled| cancelled).await;
+    }
+}
+
+/// Receives the results of [`FrequencyScanner::continuous_scan`] as they are found
+pub trait ResultSink {
+    /// Deliver one result, `false` stops the scan
+    fn send(&mut self, result: ScanResult) -> bool;
+}
+
+impl<F: FnMut(ScanResult)> ResultSink for F {
+    fn send(&mut self, result: ScanResult) -> bool {
+        self(result);
+        true
+    }
+}
+
+/// Stops once the receiver has been dropped
+impl ResultSink for mpsc::Sender<ScanResult> {
+    fn send(&mut self, result: ScanResult) -> bool {
+        mpsc::Sender::send(self, result).is_ok()
+    }
+}
+
+/// Stops once the receiver has been dropped
+impl ResultSink for tokio::sync::mpsc::UnboundedSender<ScanResult> {
+    fn send(&mut self, result: ScanResult) -> bool {
+        tokio::sync::mpsc::UnboundedSender::send(self, result).is_ok()
+    }
+}
+
+/// Async sweep and refine cycles over a [`FrequencyScanner`], yielding one [`ScanResult`] per
+/// refined peak. Measurements are interleaved with yields to the runtime and all waiting uses
+/// tokio timers, so a scan doesn't stall other tasks on the same executor.
+#[derive(Debug)]
+pub struct ScanStream<'a> {
+    scanner: &'a mut FrequencyScanner,
+    limit: ScanLimit,
+    started: Instant,
+    cancel: ScanCancel,
+    progress: watch::Sender<ScanProgress>,
+    candidates: VecDeque<f32>,
+    /// Results of the cycle being refined, handed to the drift tracker once it completes
+    cycle_results: Option<Vec<ScanResult>>,
+}
+
+impl FrequencyScanner {
+    /// Scan until `limit` is reached, the stream is cancelled or it is dropped
+    pub fn scan_stream(&mut self, limit: ScanLimit) -> ScanStream<'_> {
+        ScanStream {
+            scanner: self,
+            limit,
+            started: Instant::now(),
+            cancel: ScanCancel::default(),
+            progress: watch::Sender::new(ScanProgress::default()),
+            candidates: VecDeque::new(),
+            cycle_results: None,
+        }
+    }
+
+    /// Scan for `duration`, handing each result to `sink` as soon as it is refined. Stops early
+    /// when the sink refuses a result, and returns the number of results delivered.
+    pub async fn continuous_scan(
+        &mut self,
+        duration: Duration,
+        mut sink: impl ResultSink,
+    ) -> usize {
+        info!("Continuous scan: {:?}", duration);
+
+        let mut stream = self.scan_stream(ScanLimit::Duration(duration));
+        let mut delivered = 0;
+        while let Some(result) = stream.next().await {
+            if !sink.send(result) {
+                info!("Result sink closed, stopping continuous scan");
+                break;
+            }
+            delivered += 1;
+        }
+
+        info!("Continuous scan complete: {} detections", delivered);
+        delivered
+    }
+}
+
+impl ScanStream<'_> {
+    pub fn cancel_handle(&self) -> ScanCancel {
+        self.cancel.clone()
+    }
+
+    pub fn progress(&self) -> ScanProgress {
+        *self.progress.borrow()
+    }
+
+    /// Progress updates for other tasks, e.g. a UI
+    pub fn subscribe_progress(&self) -> watch::Receiver<ScanProgress> {
+        self.progress.subscribe()
+    }
+
+    /// Next refined result, `None` once the stream has ended
+    pub async fn next(&mut self) -> Option<ScanResult> {
+        loop {
+            if self.cancel.is_cancelled() {
+                return self.finish();
+            }
+
+            if let Some(frequency) = self.candidates.pop_front() {
+                let result = self.refine(frequency).await;
+                if result.is_none() {
+                    return self.finish();
+                }
+                self.progress.send_modify(|p| p.completed += 1);
+                if let (Some(results), Some(result)) = (&mut self.cycle_results, &result) {
+                    results.push(result.clone());
+                }
+                return result;
+            }
+
+            if let Some(results) = self.cycle_results.take() {
+                self.scanner.track_drift(&results);
+            }
+
+            let cycle = self.progress().cycle;
+            let limit_reached = match self.limit {
+                ScanLimit::Cycles(cycles) => cycle >= cycles,
+                ScanLimit::Duration(duration) => {
+                    let pause = if cycle > 0 {
+                        CYCLE_INTERVAL
+                    } else {
+                        Duration::ZERO
+                    };
+                    self.started.elapsed() + pause >= duration
+                }
+                ScanLimit::Unbounded => false,
+            };
+            if limit_reached {
+                return self.finish();
+            }
+
+            if cycle > 0 && !self.pause(CYCLE_INTERVAL).await {
+                return self.finish();
+            }
+            if !self.sweep().await {
+                return self.finish();
+            }
+        }
+    }
+
+    fn finish(&mut self) -> Option<ScanResult> {
+        self.candidates.clear();
+        // A cancelled cycle is incomplete, its missing emitters must not count as gone
+        self.cycle_results = None;
+        self.progress.send_modify(|p| p.phase = ScanPhase::Done);
+        None
+    }
+
+    /// Sweep the range and queue its peaks for refinement, `false` if cancelled
+    async fn sweep(&mut self) -> bool {
+        let frequencies = self.scanner.sweep_frequencies();
+        self.progress.send_modify(|p| {
+            p.cycle += 1;
+            p.phase = ScanPhase::Sweeping;
+            p.completed = 0;
+            p.total = frequencies.len();
+        });
+        info!("Scan cycle {} started", self.progress().cycle);
+
+        let peaks = match self.scanner.fft_peaks() {
+            Some(peaks) => peaks,
+            None => {
+                let mut sweep = Vec::with_capacity(frequencies.len());
+                for frequency in frequencies {
+                    let Some(reading) = self.measure(frequency).await else {
+                        return false;
+                    };
+                    sweep.push(reading);
+                    self.progress.send_modify(|p| p.completed += 1);
+                    tokio::task::yield_now().await;
+                }
+                self.scanner.peaks_in_sweep(&sweep)
+            }
+        };
+
+        debug!("{} peaks to refine", peaks.len());
+        self.candidates = peaks.iter().map(|peak| peak.center_freq).collect();
+        self.cycle_results = Some(Vec::with_capacity(peaks.len()));
+        self.progress.send_modify(|p| {
+            p.phase = ScanPhase::Refining;
+            p.completed = 0;
+            p.total = peaks.len();
+        });
+        true
+    }
+
+    async fn refine(&mut self, frequency: f32) -> Option<ScanResult> {
+        let (frequency, initial_step) = self.scanner.refinement_start(frequency);
+        let first = self.measure(frequency).await?;
+        let mut refinement = self
+            .scanner
+            .start_refinement(frequency, first.strength, initial_step);
+
+        while !refinement.is_done() {
+            let mut readings = Vec::with_capacity(2);
+            for candidate in refinement.candidates() {
+                if self.scanner.in_range(candidate) {
+                    readings.push(self.measure(candidate).await?);
+                }
+            }
+            refinement.observe(&readings);
+            tokio::task::yield_now().await;
+        }
+
+        Some(self.scanner.finish_refinement(&refinement))
+    }
+
+    /// [`FrequencyScanner::scan_frequency`] with the dwell interval awaited instead of slept,
+    /// `None` if cancelled
+    async fn measure(&mut self, frequency: f32) -> Option<SignalReading> {
+        if self.cancel.is_cancelled() {
+            return None;
+        }
+
+        let dwell = self.scanner.get_dwell();
+        let strength = if dwell.samples <= 1 {
+            self.scanner.read_power(frequency)
+        } else {
+            let mut samples = Vec::with_capacity(dwell.samples);
+            for i in 0..dwell.samples {
+                if i > 0 && !dwell.interval.is_zero() && !self.pause(dwell.interval).await {
+                    return None;
+                }
+                samples.push(self.scanner.read_power(frequency));
+            }
+            dwell.detector.combine(&samples)
+        };
+
+        Some(self.scanner.record(frequency, strength))
+    }
+
+    /// Sleep for `duration`, `false` if cancelled in the meantime
+    async fn pause(&mut self, duration: Duration) -> bool {
+        tokio::select! {
+            _ = tokio::time::sleep(duration) => true,
+            _ = self.cancel.cancelled() => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::FrequencyRange;
+    use crate::signal_source::SinglePeakSource;
+
+    fn scanner() -> FrequencyScanner {
+        let range = FrequencyRange {
+            start: 860.0,
+            end: 870.0,
+            step: 1.0,
+        };
+        FrequencyScanner::with_source(range, -60.0, Box::new(SinglePeakSource))
+    }
+
+    #[tokio::test]
+    async fn test_stream_single_cycle() {
+        let mut scanner = scanner();
+        let mut stream = scanner.scan_stream(ScanLimit::Cycles(1));
+
+        let result = stream.next().await.unwrap();
+        assert_eq!(result.frequency, 868.0);
+        assert_eq!(
+            stream.progress(),
+            ScanProgress {
+                cycle: 1,
+                phase: ScanPhase::Refining,
+                completed: 1,
+                total: 1,
+            }
+        );
+
+        assert!(stream.next().await.is_none());
+        assert_eq!(stream.progress().phase, ScanPhase::Done);
+    }
+
+    #[tokio::test]
+    async fn test_stream_cancel() {
+        let mut scanner = scanner();
+        let mut stream = scanner.scan_stream(ScanLimit::Unbounded);
+        let cancel = stream.cancel_handle();
+
+        assert!(stream.next().await.is_some());
+        // Cancelled during the pause before the second cycle
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cancel.cancel();
+        });
+
+        assert!(stream.next().await.is_none());
+        assert_eq!(stream.progress().cycle, 1);
+    }
+
+    #[tokio::test]
+    async fn test_continuous_scan_streams_to_sink() {
+        let mut scanner = scanner();
+
+        // Dropping the receiver ends the scan at the first result
+        let (sender, receiver) = mpsc::channel();
+        drop(receiver);
+        assert_eq!(
+            scanner
+                .continuous_scan(Duration::from_secs(30), sender)
+                .await,
+            0
+        );
+
+        let mut frequencies = Vec::new();
+        let delivered = scanner
+            .continuous_scan(Duration::from_millis(50), |result: ScanResult| {
+                frequencies.push(result.frequency)
+            })
+            .await;
+        // Too short for a second cycle
+        assert_eq!(delivered, 1);
+        assert_eq!(frequencies, [868.0]);
+    }
+}
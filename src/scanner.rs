@@ -1,5 +1,14 @@
 use std::time::{Duration, Instant};
 use log::{info, warn, debug};
+use crate::calibration::CalibrationTable;
+use crate::classify::{SignalClass, SignalClassifier};
+use crate::drift::{DriftEvent, DriftTracker};
+use crate::history::ReadingHistory;
+use crate::peaks::{self, Peak};
+use crate::recorder::ReadingSink;
+use crate::signal_source::{SignalSource, SimulatedSource};
+#[cfg(feature = "fft")]
+use crate::sweep::{FftSweep, Spectrum};
 
 #[derive(Debug, Clone)]
 pub struct FrequencyRange {
@@ -20,23 +29,146 @@ pub struct ScanResult {
     pub frequency: f32,
     pub strength: f32,
     pub confidence: f32,
+    pub class: SignalClass,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SweepMode {
+    /// One power reading per step of the frequency range
+    #[default]
+    Stepped,
+    /// FFT over I/Q captures, each retune covers most of the source's sample rate
+    #[cfg(feature = "fft")]
+    Fft { fft_size: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ThresholdMode {
+    /// Compare readings against the configured threshold
+    #[default]
+    Fixed,
+    /// Threshold follows the measured noise floor plus a margin in dB
+    Adaptive { margin_db: f32 },
+}
+
+/// How the samples taken while dwelling on one frequency combine into a reading
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Detector {
+    /// Strongest sample, catches short bursts
+    Max,
+    /// Weakest sample, reads the floor under intermittent traffic
+    Min,
+    /// Mean power in the linear domain
+    #[default]
+    Rms,
+}
+
+impl Detector {
+    /// Combine power samples in dB
+    pub fn combine(self, samples: &[f32]) -> f32 {
+        match self {
+            Detector::Max => samples.iter().copied().fold(f32::NEG_INFINITY, f32::max),
+            Detector::Min => samples.iter().copied().fold(f32::INFINITY, f32::min),
+            Detector::Rms => {
+                let linear = samples.iter().map(|db| 10f32.powf(db / 10.0)).sum::<f32>();
+                10.0 * (linear / samples.len().max(1) as f32).log10()
+            }
+        }
+    }
+}
+
+/// Per-step dwell: `samples` readings `interval` apart, combined by `detector`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Dwell {
+    pub samples: usize,
+    pub interval: Duration,
+    pub detector: Detector,
+}
+
+impl Default for Dwell {
+    /// A single instantaneous reading
+    fn default() -> Self {
+        Self {
+            samples: 1,
+            interval: Duration::ZERO,
+            detector: Detector::default(),
+        }
+    }
+}
+
+/// Percentile of the noise floor estimate, the median ignores the minority of bins holding
+/// actual signals
+const NOISE_FLOOR_PERCENTILE: f32 = 0.5;
+
+/// Default prominence a local maximum needs to count as a signal, filters noise ripple
+const DEFAULT_MIN_PROMINENCE_DB: f32 = 3.0;
+
+/// Readings searched for a signal's neighbours when rating its confidence, enough to cover a
+/// refinement around it
+const CONFIDENCE_WINDOW: usize = 64;
+
+/// Value at fraction `p` (0..=1) of the finite `values`, `None` if there are none
+pub fn percentile(values: impl IntoIterator<Item = f32>, p: f32) -> Option<f32> {
+    let mut values: Vec<f32> = values.into_iter().filter(|v| v.is_finite()).collect();
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_by(f32::total_cmp);
+    let index = ((values.len() - 1) as f32 * p.clamp(0.0, 1.0)).round() as usize;
+    values.get(index).copied()
+}
+
+#[derive(Debug)]
 pub struct FrequencyScanner {
+    source: Box<dyn SignalSource>,
+    sweep_mode: SweepMode,
+    #[cfg(feature = "fft")]
+    fft_sweep: Option<FftSweep>,
     current_range: FrequencyRange,
     signal_threshold: f32,
+    threshold_mode: ThresholdMode,
+    noise_floor: Option<f32>,
+    min_prominence: f32,
+    dwell: Dwell,
     max_refinement_iterations: usize,
-    readings: Vec<SignalReading>,
+    readings: ReadingHistory,
+    sink: Option<Box<dyn ReadingSink>>,
+    calibration: Option<CalibrationTable>,
+    classifier: SignalClassifier,
+    drift: DriftTracker,
 }
 
 impl FrequencyScanner {
+    /// Scanner backed by the [`SimulatedSource`]
     pub fn new(initial_range: FrequencyRange, signal_threshold: f32) -> Self {
+        Self::with_source(initial_range, signal_threshold, Box::new(SimulatedSource::default()))
+    }
+
+    pub fn with_source(
+        initial_range: FrequencyRange,
+        signal_threshold: f32,
+        source: Box<dyn SignalSource>,
+    ) -> Self {
         Self {
+            source,
+            sweep_mode: SweepMode::default(),
+            #[cfg(feature = "fft")]
+            fft_sweep: None,
             current_range: initial_range,
             signal_threshold,
+            threshold_mode: ThresholdMode::default(),
+            noise_floor: None,
+            min_prominence: DEFAULT_MIN_PROMINENCE_DB,
+            dwell: Dwell::default(),
             max_refinement_iterations: 5,
-            readings: Vec::new(),
+            readings: ReadingHistory::default(),
+            sink: None,
+            calibration: None,
+            classifier: SignalClassifier::default(),
+            drift: DriftTracker::default(),
         }
     }
 
@@ -44,121 +176,325 @@ impl FrequencyScanner {
         self.signal_threshold = threshold;
     }
 
+    pub fn get_threshold(&self) -> f32 {
+        self.signal_threshold
+    }
+
+    pub fn set_threshold_mode(&mut self, mode: ThresholdMode) {
+        self.threshold_mode = mode;
+    }
+
+    pub fn get_threshold_mode(&self) -> ThresholdMode {
+        self.threshold_mode
+    }
+
+    /// Threshold the next scan compares against: the fixed threshold, or the last noise floor
+    /// plus margin in adaptive mode (the fixed threshold until a floor has been measured)
+    pub fn get_effective_threshold(&self) -> f32 {
+        match (self.threshold_mode, self.noise_floor) {
+            (ThresholdMode::Adaptive { margin_db }, Some(floor)) => floor + margin_db,
+            _ => self.signal_threshold,
+        }
+    }
+
+    /// Median power of the most recent sweep's worth of readings, `None` before any scan
+    pub fn estimate_noise_floor(&self) -> Option<f32> {
+        let steps = ((self.current_range.end - self.current_range.start)
+            / self.current_range.step)
+            .floor() as usize
+            + 1;
+
+        percentile(
+            self.readings.recent(steps).map(|r| r.strength),
+            NOISE_FLOOR_PERCENTILE,
+        )
+    }
+
+    /// Noise floor measured by the last quick scan
+    pub fn get_noise_floor(&self) -> Option<f32> {
+        self.noise_floor
+    }
+
+    /// Prominence in dB a peak needs to stand out from its neighbouring valleys
+    pub fn set_min_prominence(&mut self, prominence_db: f32) {
+        self.min_prominence = prominence_db;
+    }
+
+    pub fn get_min_prominence(&self) -> f32 {
+        self.min_prominence
+    }
+
+    /// Dwell applied to every frequency measured by the scanner
+    pub fn set_dwell(&mut self, dwell: Dwell) {
+        self.dwell = dwell;
+    }
+
+    pub fn get_dwell(&self) -> Dwell {
+        self.dwell
+    }
+
+    /// Select how [`FrequencyScanner::quick_scan`] covers the range
+    pub fn set_sweep_mode(&mut self, mode: SweepMode) {
+        self.sweep_mode = mode;
+    }
+
+    pub fn get_sweep_mode(&self) -> SweepMode {
+        self.sweep_mode
+    }
+
+    /// Power spectrum of the whole scan range from FFTs of I/Q captures
+    #[cfg(feature = "fft")]
+    pub fn fft_sweep(&mut self, fft_size: usize) -> std::io::Result<Spectrum> {
+        let sweep = match &mut self.fft_sweep {
+            Some(sweep) if sweep.get_fft_size() == fft_size => sweep,
+            slot => slot.insert(FftSweep::new(fft_size)),
+        };
+
+        let mut spectrum = sweep.sweep(self.source.as_mut(), &self.current_range)?;
+        if let Some(calibration) = &self.calibration {
+            for bin in 0..spectrum.power.len() {
+                spectrum.power[bin] = calibration.apply(spectrum.frequency(bin), spectrum.power[bin]);
+            }
+        }
+
+        Ok(spectrum)
+    }
+
+    /// Sample rate in Hz of [`FrequencyScanner::capture_iq`], `None` if the source only measures power
+    pub fn get_sample_rate(&self) -> Option<f32> {
+        self.source.sample_rate()
+    }
+
+    /// Raw I/Q capture at `frequency` in MHz from the scanner's source
+    pub fn capture_iq(&mut self, frequency: f32, buf: &mut [[f32; 2]]) -> std::io::Result<usize> {
+        self.source.capture_iq(frequency, buf)
+    }
+
+    /// Correct every reading, stepped and FFT, by the frontend's response at its frequency
+    pub fn set_calibration(&mut self, calibration: Option<CalibrationTable>) {
+        self.calibration = calibration;
+    }
+
+    pub fn get_calibration(&self) -> Option<&CalibrationTable> {
+        self.calibration.as_ref()
+    }
+
+    /// Forward every reading to `sink` as it is taken, returning the previous sink
+    pub fn set_sink(&mut self, sink: Option<Box<dyn ReadingSink>>) -> Option<Box<dyn ReadingSink>> {
+        std::mem::replace(&mut self.sink, sink)
+    }
+
+    /// Replace the scan range, returning the previous one
+    pub fn set_range(&mut self, range: FrequencyRange) -> FrequencyRange {
+        std::mem::replace(&mut self.current_range, range)
+    }
+
+    pub fn get_range(&self) -> &FrequencyRange {
+        &self.current_range
+    }
+
     pub fn scan_frequency(&mut self, frequency: f32) -> SignalReading {
-        // Simulate reading signal strength at given frequency
-        let strength = self.simulate_signal_reading(frequency);
+        let strength = self.dwell_on(frequency);
+        self.record(frequency, strength)
+    }
+
+    /// Store a measurement taken outside [`FrequencyScanner::scan_frequency`]
+    pub(crate) fn record(&mut self, frequency: f32, strength: f32) -> SignalReading {
+        let strength = match &self.calibration {
+            Some(calibration) => calibration.apply(frequency, strength),
+            None => strength,
+        };
         let reading = SignalReading {
             frequency,
             strength,
             timestamp: Instant::now(),
         };
         
+        if let Some(sink) = &mut self.sink {
+            if let Err(e) = sink.record(&reading) {
+                warn!("Recording reading at {:.2} MHz failed: {}", frequency, e);
+            }
+        }
+
         self.readings.push(reading.clone());
         debug!("Frequency {:.2} MHz: Signal strength {:.2} dB", frequency, strength);
         reading
     }
 
-    fn simulate_signal_reading(&self, frequency: f32) -> f32 {
-        // Simulate signal with noise and occasional strong signals
-        let base_noise = -80.0; // Base noise floor in dB
-        let noise_variation = (frequency * 0.1).sin() * 5.0; // Some frequency-dependent variation
-        
-        // Add occasional strong signals at specific frequencies
-        let signal_boost = if (frequency - 433.0).abs() < 2.0 {
-            40.0 + (frequency - 433.0).abs() * 10.0 // Strong signal around 433 MHz
-        } else if (frequency - 915.0).abs() < 5.0 {
-            35.0 + (frequency - 915.0).abs() * 5.0 // Another signal around 915 MHz
-        } else if (frequency - 2400.0).abs() < 10.0 {
-            30.0 + (frequency - 2400.0).abs() * 2.0 // WiFi band
-        } else {
-            0.0
-        };
-        
-        base_noise + noise_variation + signal_boost + (std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_nanos() as f32 * 0.000000001).sin() * 2.0
+    fn dwell_on(&mut self, frequency: f32) -> f32 {
+        if self.dwell.samples <= 1 {
+            return self.read_power(frequency);
+        }
+
+        let mut samples = Vec::with_capacity(self.dwell.samples);
+        for i in 0..self.dwell.samples {
+            if i > 0 && !self.dwell.interval.is_zero() {
+                std::thread::sleep(self.dwell.interval);
+            }
+            samples.push(self.read_power(frequency));
+        }
+
+        self.dwell.detector.combine(&samples)
     }
 
-    pub fn quick_scan(&mut self) -> Vec<SignalReading> {
-        info!("Quick scan: {:.1} to {:.1} MHz", 
-              self.current_range.start, self.current_range.end);
-        
-        let mut strong_signals = Vec::new();
+    /// Single instantaneous reading, not recorded
+    pub(crate) fn read_power(&mut self, frequency: f32) -> f32 {
+        self.source.read_power(frequency)
+    }
+
+    /// Readings of one stepped sweep over the range
+    pub(crate) fn stepped_sweep(&mut self) -> Vec<SignalReading> {
+        self.sweep_frequencies()
+            .into_iter()
+            .map(|freq| self.scan_frequency(freq))
+            .collect()
+    }
+
+    /// Frequencies of one stepped sweep over the range
+    pub(crate) fn sweep_frequencies(&self) -> Vec<f32> {
+        let mut frequencies = Vec::new();
         let mut freq = self.current_range.start;
-        
+
         while freq <= self.current_range.end {
-            let reading = self.scan_frequency(freq);
-            if reading.strength > self.signal_threshold {
-                info!("Signal at {:.2} MHz: {:.2} dB", freq, reading.strength);
-                strong_signals.push(reading);
-            }
+            frequencies.push(freq);
             freq += self.current_range.step;
         }
+
+        frequencies
+    }
+
+    pub(crate) fn in_range(&self, frequency: f32) -> bool {
+        frequency >= self.current_range.start && frequency <= self.current_range.end
+    }
+
+    /// Sweep the range and return the peaks above the effective threshold, strongest first
+    pub fn find_peaks(&mut self) -> Vec<Peak> {
+        info!("Quick scan: {:.1} to {:.1} MHz", 
+              self.current_range.start, self.current_range.end);
+
+        if let Some(peaks) = self.fft_peaks() {
+            return peaks;
+        }
+
+        let sweep = self.stepped_sweep();
+        self.peaks_in_sweep(&sweep)
+    }
+
+    /// Peaks of an FFT sweep, `None` in stepped mode or when the FFT sweep failed
+    pub(crate) fn fft_peaks(&mut self) -> Option<Vec<Peak>> {
+        #[cfg(feature = "fft")]
+        if let SweepMode::Fft { fft_size } = self.sweep_mode {
+            match self.fft_sweep(fft_size) {
+                Ok(spectrum) => {
+                    self.noise_floor =
+                        percentile(spectrum.power.iter().copied(), NOISE_FLOOR_PERCENTILE);
+                    let peaks =
+                        spectrum.find_peaks(self.get_effective_threshold(), self.min_prominence);
+                    let end = spectrum.frequency(spectrum.power.len().saturating_sub(1));
+                    self.classifier.observe_sweep(spectrum.start, end, &peaks);
+                    for peak in &peaks {
+                        info!("Signal at {:.3} MHz: {:.2} dB", peak.center_freq, peak.peak_db);
+                    }
+                    self.readings
+                        .extend(peaks.iter().map(|peak| peak_reading(peak, spectrum.timestamp)));
+                    return Some(peaks);
+                }
+                Err(e) => warn!("FFT sweep failed, falling back to stepped scan: {}", e),
+            }
+        }
+
+        None
+    }
+
+    /// Update the noise floor from a stepped sweep and find its peaks
+    pub(crate) fn peaks_in_sweep(&mut self, sweep: &[SignalReading]) -> Vec<Peak> {
+        self.noise_floor = percentile(sweep.iter().map(|r| r.strength), NOISE_FLOOR_PERCENTILE);
+        let threshold = self.get_effective_threshold();
+        debug!("Noise floor {:?} dB, threshold {:.2} dB", self.noise_floor, threshold);
+
+        let peaks = peaks::find_peaks(sweep, threshold, self.min_prominence);
+        if let (Some(first), Some(last)) = (sweep.first(), sweep.last()) {
+            self.classifier.observe_sweep(first.frequency, last.frequency, &peaks);
+        }
+        for peak in &peaks {
+            info!("Signal at {:.2} MHz: {:.2} dB, {:.1} dB prominence, {:.3} MHz wide",
+                  peak.center_freq, peak.peak_db, peak.prominence, peak.bandwidth_estimate);
+        }
         
-        strong_signals
+        peaks
+    }
+
+    /// One reading per peak found by [`FrequencyScanner::find_peaks`]
+    pub fn quick_scan(&mut self) -> Vec<SignalReading> {
+        let timestamp = Instant::now();
+        self.find_peaks()
+            .iter()
+            .map(|peak| peak_reading(peak, timestamp))
+            .collect()
     }
 
     pub fn refined_scan(&mut self, target_frequency: f32, initial_step: f32) -> ScanResult {
         info!("Refined scan at {:.2} MHz", target_frequency);
         
-        let mut best_frequency = target_frequency;
-        let mut best_strength = self.scan_frequency(target_frequency).strength;
-        let mut current_step = initial_step;
-        let mut iteration = 0;
+        let strength = self.scan_frequency(target_frequency).strength;
+        let mut refinement = self.start_refinement(target_frequency, strength, initial_step);
         
-        while current_step > 0.01 && iteration < self.max_refinement_iterations {
-            debug!("Refinement iteration {}: step = {:.3} MHz", iteration, current_step);
-            
-            // Check frequencies around the current best
-            let test_frequencies = [
-                best_frequency - current_step,
-                best_frequency + current_step,
-            ];
-            
-            let mut found_better = false;
-            
-            for &freq in &test_frequencies {
-                if freq >= self.current_range.start && freq <= self.current_range.end {
-                    let reading = self.scan_frequency(freq);
-                    if reading.strength > best_strength {
-                        best_strength = reading.strength;
-                        best_frequency = freq;
-                        found_better = true;
-                        debug!("Better signal at {:.2} MHz: {:.2} dB", freq, reading.strength);
-                    }
+        while !refinement.is_done() {
+            let mut readings = Vec::with_capacity(2);
+            for freq in refinement.candidates() {
+                if self.in_range(freq) {
+                    readings.push(self.scan_frequency(freq));
                 }
             }
-            
-            if !found_better {
-                // Reduce step size for finer search
-                current_step *= 0.5;
-                debug!("No better signal, step: {:.3} MHz", current_step);
-            }
-            
-            iteration += 1;
-            
-            // Add small delay to simulate real scanning
-            std::thread::sleep(Duration::from_millis(10));
+            refinement.observe(&readings);
         }
         
+        self.finish_refinement(&refinement)
+    }
+
+    /// Where to start refining a sweep peak at `frequency` and with which step
+    pub(crate) fn refinement_start(&self, frequency: f32) -> (f32, f32) {
+        self.drift.refinement_start(frequency, &self.current_range, Instant::now())
+    }
+
+    /// Follow known emitters with the refined results of a whole cycle
+    pub(crate) fn track_drift(&mut self, results: &[ScanResult]) {
+        self.drift.update(&self.current_range, results, Instant::now());
+    }
+
+    pub(crate) fn start_refinement(&self, frequency: f32, strength: f32, initial_step: f32) -> Refinement {
+        Refinement {
+            origin: frequency,
+            best_frequency: frequency,
+            best_strength: strength,
+            step: initial_step,
+            iteration: 0,
+            max_iterations: self.max_refinement_iterations,
+        }
+    }
+
+    pub(crate) fn finish_refinement(&self, refinement: &Refinement) -> ScanResult {
+        let (frequency, strength) = (refinement.best_frequency, refinement.best_strength);
+
         // Calculate confidence based on signal strength and stability
-        let confidence = self.calculate_confidence(best_frequency, best_strength);
+        let confidence = self.calculate_confidence(frequency, strength);
+        let class = self.classifier.classify(refinement.origin);
         
-        info!("Refined: {:.2} MHz, {:.2} dB, {:.1}% confidence", 
-              best_frequency, best_strength, confidence * 100.0);
+        info!("Refined: {:.2} MHz, {:.2} dB, {:.1}% confidence, {:?}", 
+              frequency, strength, confidence * 100.0, class);
         
         ScanResult {
-            frequency: best_frequency,
-            strength: best_strength,
+            frequency,
+            strength,
             confidence,
+            class,
         }
     }
 
     fn calculate_confidence(&self, frequency: f32, strength: f32) -> f32 {
         // Get recent readings around this frequency
         let recent_readings: Vec<_> = self.readings
-            .iter()
+            .recent(CONFIDENCE_WINDOW)
             .filter(|r| (r.frequency - frequency).abs() < 1.0)
             .collect();
         
@@ -175,7 +511,7 @@ impl FrequencyScanner {
         let stability_factor = 1.0 / (1.0 + variance);
         
         // Signal strength factor (stronger signals are more reliable)
-        let strength_factor = (strength / 100.0).min(1.0).max(0.0);
+        let strength_factor = (strength / 100.0).clamp(0.0, 1.0);
         
         // Combine factors
         (stability_factor * 0.6 + strength_factor * 0.4).min(1.0)
@@ -189,15 +525,18 @@ impl FrequencyScanner {
         
         if strong_signals.is_empty() {
             warn!("No signals above threshold detected");
+            self.track_drift(&[]);
             return Vec::new();
         }
         
-        // Phase 2: Refine around each strong signal
+        // Phase 2: Refine around each strong signal, known emitters from where they were last seen
         let mut results = Vec::new();
         for signal in &strong_signals {
-            let refined = self.refined_scan(signal.frequency, self.current_range.step * 0.5);
+            let (frequency, step) = self.refinement_start(signal.frequency);
+            let refined = self.refined_scan(frequency, step);
             results.push(refined);
         }
+        self.track_drift(&results);
         
         // Sort by strength (strongest first)
         results.sort_by(|a, b| b.strength.partial_cmp(&a.strength).unwrap());
@@ -206,34 +545,45 @@ impl FrequencyScanner {
         results
     }
 
-    pub fn continuous_scan(&mut self, duration: Duration) -> Vec<ScanResult> {
-        info!("Continuous scan: {:?}", duration);
-        
-        let start_time = Instant::now();
-        let mut all_results = Vec::new();
-        
-        while start_time.elapsed() < duration {
-            let cycle_results = self.full_scan_cycle();
-            all_results.extend(cycle_results);
-            
-            // Small delay between cycles
-            std::thread::sleep(Duration::from_millis(100));
-        }
-        
-        info!("Continuous scan complete: {} detections", all_results.len());
-        all_results
+    /// Class of the signal at `frequency` in MHz from the sweeps seen so far
+    pub fn classify(&self, frequency: f32) -> SignalClass {
+        self.classifier.classify(frequency)
     }
 
+    /// Replace the classifier, e.g. with different wideband and hop span limits
+    pub fn set_classifier(&mut self, classifier: SignalClassifier) {
+        self.classifier = classifier;
+    }
+
+    /// Emitters followed across scan cycles
+    pub fn get_drift_tracker(&self) -> &DriftTracker {
+        &self.drift
+    }
+
+    /// Appearance, drift and disappearance of emitters since the last call
+    pub fn take_drift_events(&mut self) -> Vec<DriftEvent> {
+        self.drift.take_events()
+    }
+
+    /// Recent readings, bounded by [`FrequencyScanner::set_history_capacity`]
+    pub fn get_history(&self) -> &ReadingHistory {
+        &self.readings
+    }
+
+    /// Keep at most `capacity` readings, statistics still cover every reading since the last
+    /// [`FrequencyScanner::clear_readings`]
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.readings.set_capacity(capacity);
+    }
+
+    /// Reading count, mean and maximum strength since the last clear
     pub fn get_readings_summary(&self) -> (usize, f32, f32) {
-        if self.readings.is_empty() {
+        let stats = self.readings.get_stats();
+        if stats.count == 0 {
             return (0, 0.0, 0.0);
         }
         
-        let count = self.readings.len();
-        let avg_strength = self.readings.iter().map(|r| r.strength).sum::<f32>() / count as f32;
-        let max_strength = self.readings.iter().map(|r| r.strength).fold(f32::MIN, f32::max);
-        
-        (count, avg_strength, max_strength)
+        (stats.count as usize, stats.mean, stats.max)
     }
 
     pub fn clear_readings(&mut self) {
@@ -242,9 +592,63 @@ impl FrequencyScanner {
     }
 }
 
+/// Hill climb around a peak, halving the step whenever neither neighbour is stronger
+#[derive(Debug, Clone)]
+pub(crate) struct Refinement {
+    /// Peak the refinement started from, classified by its sweep frequency
+    origin: f32,
+    best_frequency: f32,
+    best_strength: f32,
+    step: f32,
+    iteration: usize,
+    max_iterations: usize,
+}
+
+impl Refinement {
+    pub(crate) fn is_done(&self) -> bool {
+        self.step <= 0.01 || self.iteration >= self.max_iterations
+    }
+
+    /// Frequencies to measure next, callers skip those outside the scan range
+    pub(crate) fn candidates(&self) -> [f32; 2] {
+        [self.best_frequency - self.step, self.best_frequency + self.step]
+    }
+
+    pub(crate) fn observe(&mut self, readings: &[SignalReading]) {
+        debug!("Refinement iteration {}: step = {:.3} MHz", self.iteration, self.step);
+
+        let mut found_better = false;
+        for reading in readings {
+            if reading.strength > self.best_strength {
+                self.best_strength = reading.strength;
+                self.best_frequency = reading.frequency;
+                found_better = true;
+                debug!("Better signal at {:.2} MHz: {:.2} dB", reading.frequency, reading.strength);
+            }
+        }
+
+        if !found_better {
+            // Reduce step size for finer search
+            self.step *= 0.5;
+            debug!("No better signal, step: {:.3} MHz", self.step);
+        }
+
+        self.iteration += 1;
+    }
+}
+
+fn peak_reading(peak: &Peak, timestamp: Instant) -> SignalReading {
+    SignalReading {
+        frequency: peak.center_freq,
+        strength: peak.peak_db,
+        timestamp,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::signal_source::SinglePeakSource;
 
     #[test]
     fn test_frequency_scanner_creation() {
@@ -272,8 +676,9 @@ mod tests {
 
     #[test]
     fn test_quick_scan() {
+        // Starting at 403 MHz puts a step on the 433 MHz emitter, see below
         let range = FrequencyRange {
-            start: 400.0,
+            start: 403.0,
             end: 500.0,
             step: 10.0,
         };
@@ -283,6 +688,116 @@ mod tests {
         assert!(!signals.is_empty());
     }
 
+    #[test]
+    fn test_quick_scan_steps_over_narrow_signal() {
+        // Steps of 10 MHz from 400 MHz read 430 and 440 MHz, 3 and 7 MHz off the 2 MHz wide
+        // 433 MHz emitter, so a quick scan only sees the noise floor
+        let range = FrequencyRange {
+            start: 400.0,
+            end: 500.0,
+            step: 10.0,
+        };
+        let mut scanner = FrequencyScanner::new(range, -60.0);
+        assert!(scanner.quick_scan().is_empty());
+        // A step on the emitter finds it
+        assert!(scanner.scan_frequency(433.0).strength > -60.0);
+    }
+
+    #[test]
+    fn test_custom_signal_source() {
+        let range = FrequencyRange {
+            start: 860.0,
+            end: 870.0,
+            step: 1.0,
+        };
+        let mut scanner = FrequencyScanner::with_source(range, -60.0, Box::new(SinglePeakSource));
+        let signals = scanner.quick_scan();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].frequency, 868.0);
+    }
+
+    /// Noise alternating between `floor` and 5 dB below it on every other MHz
+    #[derive(Debug)]
+    struct RipplingFloorSource {
+        floor: f32,
+    }
+
+    impl SignalSource for RipplingFloorSource {
+        fn read_power(&mut self, frequency: f32) -> f32 {
+            match frequency as u32 {
+                868 => -30.0,
+                f if f % 2 == 0 => self.floor - 5.0,
+                _ => self.floor,
+            }
+        }
+    }
+
+    #[test]
+    fn test_adaptive_threshold_follows_noise_floor() {
+        let range = FrequencyRange {
+            start: 860.0,
+            end: 870.0,
+            step: 1.0,
+        };
+        let mut scanner =
+            FrequencyScanner::with_source(range, -60.0, Box::new(RipplingFloorSource { floor: -50.0 }));
+
+        // A fixed threshold below the ambient noise flags the ripple as well
+        assert_eq!(scanner.quick_scan().len(), 4);
+
+        scanner.set_threshold_mode(ThresholdMode::Adaptive { margin_db: 10.0 });
+        let signals = scanner.quick_scan();
+        assert_eq!(signals.len(), 1);
+        assert_eq!(signals[0].frequency, 868.0);
+        assert_eq!(scanner.get_noise_floor(), Some(-50.0));
+        assert_eq!(scanner.estimate_noise_floor(), Some(-50.0));
+        assert_eq!(scanner.get_effective_threshold(), -40.0);
+    }
+
+    /// Transmits on every fourth reading only
+    #[derive(Debug, Default)]
+    struct BurstySource {
+        reads: usize,
+    }
+
+    impl SignalSource for BurstySource {
+        fn read_power(&mut self, _frequency: f32) -> f32 {
+            self.reads += 1;
+            if self.reads.is_multiple_of(4) { -30.0 } else { -90.0 }
+        }
+    }
+
+    #[test]
+    fn test_dwell_detectors() {
+        let range = FrequencyRange {
+            start: 868.0,
+            end: 868.0,
+            step: 1.0,
+        };
+        let mut scanner =
+            FrequencyScanner::with_source(range, -60.0, Box::new(BurstySource::default()));
+        let mut dwell = Dwell {
+            samples: 4,
+            ..Dwell::default()
+        };
+
+        // A single reading misses the burst
+        assert_eq!(scanner.scan_frequency(868.0).strength, -90.0);
+
+        dwell.detector = Detector::Max;
+        scanner.set_dwell(dwell);
+        assert_eq!(scanner.scan_frequency(868.0).strength, -30.0);
+
+        dwell.detector = Detector::Min;
+        scanner.set_dwell(dwell);
+        assert_eq!(scanner.scan_frequency(868.0).strength, -90.0);
+
+        // One sample in four at -30 dB averages to about -36 dB
+        dwell.detector = Detector::Rms;
+        scanner.set_dwell(dwell);
+        assert!((scanner.scan_frequency(868.0).strength + 36.02).abs() < 0.01);
+    }
+
     #[test]
     fn test_refined_scan() {
         let range = FrequencyRange {
@@ -295,4 +810,40 @@ mod tests {
         assert!(result.frequency >= 400.0 && result.frequency <= 500.0);
         assert!(result.confidence >= 0.0 && result.confidence <= 1.0);
     }
+
+    #[derive(Debug)]
+    struct CountingSource(std::sync::Arc<std::sync::atomic::AtomicUsize>);
+
+    impl SignalSource for CountingSource {
+        fn read_power(&mut self, frequency: f32) -> f32 {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            -30.0 - (frequency - 433.25).abs() * 20.0
+        }
+    }
+
+    #[test]
+    fn test_known_emitters_refine_from_last_fix() {
+        let reads = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let range = FrequencyRange {
+            start: 430.0,
+            end: 437.0,
+            step: 1.0,
+        };
+        let mut scanner =
+            FrequencyScanner::with_source(range, -60.0, Box::new(CountingSource(reads.clone())));
+
+        let first = scanner.full_scan_cycle();
+        let first_reads = reads.swap(0, std::sync::atomic::Ordering::Relaxed);
+        let second = scanner.full_scan_cycle();
+        let second_reads = reads.load(std::sync::atomic::Ordering::Relaxed);
+
+        assert!((first[0].frequency - 433.25).abs() < 0.02);
+        assert!((second[0].frequency - 433.25).abs() < 0.02);
+        assert!(second_reads < first_reads);
+        assert_eq!(scanner.get_drift_tracker().get_emitters().len(), 1);
+        assert!(matches!(
+            scanner.take_drift_events()[..],
+            [DriftEvent::Appeared { id: 0, .. }]
+        ));
+    }
 }
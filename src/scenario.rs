@@ -0,0 +1,326 @@
+use std::path::Path;
+use std::time::Duration;
+
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::antenna_array::{local_to_polar, AntennaArray};
+use crate::error::{HexarError, HexarResult};
+use crate::fall_alert::FallAlertKind;
+use crate::pipeline::StageSender;
+use crate::serial_radar::{SensorFrame, SensorModel};
+use crate::signal_source::{seed_state, uniform_noise};
+
+/// Targets an LD2450 reports per frame
+const LD2450_MAX_TARGETS: usize = 3;
+
+/// How long a scripted fall takes from standing to lying
+const FALL_DURATION: Duration = Duration::from_millis(300);
+
+/// Distance a falling person's centre moves in the room's `-y`, the tracker's stand-in for
+/// height with 2D sensors
+const FALL_DISTANCE_M: f32 = 1.0;
+
+/// A scripted sequence of people moving through the space, played into the controller as
+/// LD2450 frames from every antenna that sees them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Scenario {
+    pub seed: u64,
+    pub duration_s: f32,
+    /// Frames every antenna sends per second
+    pub frame_rate_hz: f32,
+    /// Largest error added to each coordinate of a reported position, in metres
+    pub position_noise_m: f32,
+    /// Chance of a target missing from a frame
+    pub dropout: f32,
+    pub people: Vec<ScriptedPerson>,
+}
+
+impl Default for Scenario {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            duration_s: 10.0,
+            frame_rate_hz: 10.0,
+            position_noise_m: 0.05,
+            dropout: 0.0,
+            people: Vec::new(),
+        }
+    }
+}
+
+/// Someone walking a path of waypoints in the room frame, from entering until leaving
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScriptedPerson {
+    /// When they appear at the first waypoint, in seconds from the start
+    #[serde(default)]
+    pub enter_s: f32,
+    /// When they leave, at the end of the scenario when unset
+    #[serde(default)]
+    pub exit_s: Option<f32>,
+    pub path: Vec<Vector2<f32>>,
+    /// Walking speed in m/s, they stand at the last waypoint once it is reached
+    #[serde(default = "default_walking_speed")]
+    pub speed_mps: f32,
+    /// When they fall where they are, lying still from then on
+    #[serde(default)]
+    pub fall_at_s: Option<f32>,
+}
+
+fn default_walking_speed() -> f32 {
+    1.2
+}
+
+impl ScriptedPerson {
+    /// Where the person is at `time` seconds, `None` while outside the space
+    pub fn position_at(&self, time: f32) -> Option<Vector2<f32>> {
+        if time < self.enter_s || self.exit_s.is_some_and(|exit| time >= exit) {
+            return None;
+        }
+        let Some(fall) = self.fall_at_s.filter(|fall| time >= *fall) else {
+            return self.walked(time);
+        };
+        let progress = ((time - fall) / FALL_DURATION.as_secs_f32()).min(1.0);
+        // Accelerating from standing like a body tipping over
+        let drop = FALL_DISTANCE_M * progress * progress;
+        self.walked(fall)
+            .map(|position| position - Vector2::new(0.0, drop))
+    }
+
+    fn walked(&self, time: f32) -> Option<Vector2<f32>> {
+        let mut remaining = (time - self.enter_s) * self.speed_mps.max(0.0);
+        let mut position = *self.path.first()?;
+        for next in self.path.iter().skip(1) {
+            let leg = (next - position).norm();
+            if remaining <= leg {
+                return Some(position + (next - position) * (remaining / leg.max(f32::EPSILON)));
+            }
+            remaining -= leg;
+            position = *next;
+        }
+        Some(position)
+    }
+}
+
+impl Scenario {
+    pub fn load<P: AsRef<Path>>(path: P) -> HexarResult<Self> {
+        let content = std::fs::read_to_string(path.as_ref())?;
+        let scenario: Scenario = serde_json::from_str(&content)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    pub fn validate(&self) -> HexarResult<()> {
+        if !(self.frame_rate_hz > 0.0 && self.frame_rate_hz.is_finite()) {
+            return Err(HexarError::InvalidParameter(format!(
+                "frame rate {} Hz must be positive",
+                self.frame_rate_hz
+            )));
+        }
+        if let Some(index) = self.people.iter().position(|person| person.path.is_empty()) {
+            return Err(HexarError::InvalidParameter(format!(
+                "person {} has no path",
+                index
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn get_frame_interval(&self) -> Duration {
+        Duration::from_secs_f32(1.0 / self.frame_rate_hz)
+    }
+
+    /// Frames of the whole scenario
+    pub fn get_frame_count(&self) -> u64 {
+        (self.duration_s * self.frame_rate_hz).floor() as u64 + 1
+    }
+}
+
+/// Turns a [`Scenario`] into the frames the antennas of an array would send. Frames are
+/// encoded as LD2450 payloads and parsed back, so they take the same path as real ones.
+#[derive(Debug, Clone)]
+pub struct ScenarioGenerator {
+    scenario: Scenario,
+    array: AntennaArray,
+    rng: u64,
+}
+
+impl ScenarioGenerator {
+    pub fn new(scenario: Scenario, array: AntennaArray) -> Self {
+        Self {
+            rng: seed_state(scenario.seed),
+            scenario,
+            array,
+        }
+    }
+
+    pub fn get_scenario(&self) -> &Scenario {
+        &self.scenario
+    }
+
+    /// Frame of every antenna at `time` seconds from the start
+    pub fn frames_at(&mut self, time: f32) -> Vec<SensorFrame> {
+        let people: Vec<Vector2<f32>> = self
+            .scenario
+            .people
+            .iter()
+            .filter_map(|person| person.position_at(time))
+            .collect();
+        let antennas: Vec<u8> = self
+            .array
+            .poses()
+            .map(|(antenna_id, _)| antenna_id)
+            .collect();
+        antennas
+            .into_iter()
+            .map(|antenna_id| {
+                let mut visible: Vec<Vector2<f32>> = Vec::new();
+                for position in &people {
+                    let noise =
+                        Vector2::new(uniform_noise(&mut self.rng), uniform_noise(&mut self.rng))
+                            * self.scenario.position_noise_m;
+                    let missed = (uniform_noise(&mut self.rng) + 1.0) * 0.5 < self.scenario.dropout;
+                    if missed || !self.array.covers(antenna_id, *position) {
+                        continue;
+                    }
+                    if let Some(pose) = self.array.get_pose(antenna_id) {
+                        visible.push(pose.to_local(position + noise));
+                    }
+                }
+                // The module reports the nearest targets it can
+                visible.sort_by(|a, b| a.norm().total_cmp(&b.norm()));
+                visible.truncate(LD2450_MAX_TARGETS);
+                SensorFrame::parse(antenna_id, SensorModel::Ld2450, &ld2450_payload(&visible))
+            })
+            .collect()
+    }
+
+    /// Send the scenario's frames to `frames` in real time, or `speed` times as fast. Stops at
+    /// the end of the scenario or when the receiver is gone.
+    pub fn spawn(
+        mut self,
+        frames: StageSender<SensorFrame>,
+        speed: f32,
+    ) -> JoinHandle<HexarResult<()>> {
+        tokio::spawn(async move {
+            let started = tokio::time::Instant::now();
+            let interval = self.scenario.get_frame_interval();
+            for step in 0..self.scenario.get_frame_count() {
+                let offset = interval * step as u32;
+                tokio::time::sleep_until(started + offset.div_f32(speed.max(f32::EPSILON))).await;
+                for frame in self.frames_at(offset.as_secs_f32()) {
+                    if frames.send(frame).await.is_err() {
+                        return Ok(());
+                    }
+                }
+            }
+            debug!("Scenario finished");
+            Ok(())
+        })
+    }
+}
+
+/// LD2450 target data payload reporting `targets` in the antenna frame, in metres
+pub fn ld2450_payload(targets: &[Vector2<f32>]) -> Vec<u8> {
+    let mut payload = vec![0u8; 8 * LD2450_MAX_TARGETS];
+    for (chunk, target) in payload.chunks_exact_mut(8).zip(targets) {
+        let (range, _) = local_to_polar(*target);
+        chunk[0..2].copy_from_slice(&ld2450_value(target.x * 1000.0));
+        chunk[2..4].copy_from_slice(&ld2450_value(target.y * 1000.0));
+        // Standing still as far as the module can tell, the tracker measures the motion
+        chunk[4..6].copy_from_slice(&ld2450_value(0.0));
+        chunk[6..8].copy_from_slice(&((range * 1000.0) as u16).to_le_bytes());
+    }
+    payload
+}
+
+/// Sign and magnitude as the LD2450 sends them, the top bit set for positive values
+fn ld2450_value(value: f32) -> [u8; 2] {
+    let magnitude = (value.abs().round() as u16).min(0x7FFF);
+    let raw = if value >= 0.0 {
+        magnitude | 0x8000
+    } else {
+        magnitude
+    };
+    raw.to_le_bytes()
+}
+
+/// A fall the controller reported during a simulation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimulatedFall {
+    /// Seconds from the start of the scenario
+    pub time_s: f32,
+    pub target_id: u32,
+    pub kind: FallAlertKind,
+}
+
+/// What the controller made of a scenario
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SimulationReport {
+    pub frames: u64,
+    pub cycles: u64,
+    /// Confirmed targets, in the order they were first reported
+    pub targets: Vec<u32>,
+    /// Most targets reported in one cycle
+    pub max_concurrent_targets: usize,
+    pub targets_lost: usize,
+    pub falls: Vec<SimulatedFall>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::antenna_array::AntennaArrayConfig;
+
+    #[test]
+    fn test_scripted_frames() {
+        let person = ScriptedPerson {
+            enter_s: 1.0,
+            exit_s: Some(8.0),
+            path: vec![
+                Vector2::new(2.0, 0.0),
+                Vector2::new(2.0, 2.0),
+                Vector2::new(0.0, 2.0),
+            ],
+            speed_mps: 1.0,
+            fall_at_s: Some(6.0),
+        };
+        assert_eq!(person.position_at(0.5), None);
+        assert_eq!(person.position_at(2.0), Some(Vector2::new(2.0, 1.0)));
+        assert_eq!(person.position_at(4.0), Some(Vector2::new(1.0, 2.0)));
+        assert_eq!(person.position_at(7.0), Some(Vector2::new(0.0, 1.0)));
+        assert_eq!(person.position_at(8.0), None);
+
+        let scenario = Scenario {
+            position_noise_m: 0.0,
+            people: vec![person],
+            ..Default::default()
+        };
+        let array = AntennaArray::hexagonal(&AntennaArrayConfig::default());
+        let mut generator = ScenarioGenerator::new(scenario.clone(), array.clone());
+        let frames = generator.frames_at(2.0);
+        assert_eq!(frames.len(), 6);
+        // Antenna 0 faces +x, straight at the person
+        let seen = &frames[0].measurements;
+        assert_eq!(seen.len(), 1);
+        let world = array.get_pose(0).unwrap().to_world(seen[0].position);
+        assert!((world - Vector2::new(2.0, 1.0)).norm() < 2e-3);
+        assert!(frames[3].measurements.is_empty());
+
+        // The same seed gives the same frames
+        let mut noisy = Scenario {
+            position_noise_m: 0.1,
+            ..scenario
+        };
+        noisy.seed = 7;
+        let mut first = ScenarioGenerator::new(noisy.clone(), array.clone());
+        let mut second = ScenarioGenerator::new(noisy, array);
+        assert_eq!(
+            first.frames_at(3.0)[0].payload,
+            second.frames_at(3.0)[0].payload
+        );
+    }
+}
@@ -0,0 +1,494 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const ALL_DAYS: u8 = 0x7f;
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Period during which scanning is allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScanWindow {
+    /// From `start` to `end`, in minutes after midnight, on the weekdays set in `days`
+    /// (bit 0 Monday to bit 6 Sunday). A window ending at or before its start runs past
+    /// midnight into the next day.
+    Daily { days: u8, start: u16, end: u16 },
+    /// Scan for `on`, then pause for `off`, repeating from the Unix epoch so all instances
+    /// sharing a schedule line up
+    Interval { on: Duration, off: Duration },
+}
+
+impl ScanWindow {
+    /// Whether the window is open at `time`, shifted by `utc_offset` minutes into local time
+    fn contains(&self, time: SystemTime, utc_offset: i32) -> bool {
+        match *self {
+            ScanWindow::Daily { days, start, end } => {
+                let (day, minute) = local_day_minute(time, utc_offset);
+                let (start, end) = (u32::from(start), u32::from(end));
+                if start < end {
+                    day_set(days, day) && (start..end).contains(&minute)
+                } else {
+                    // Past midnight, the late part belongs to the previous day's window
+                    (day_set(days, day) && minute >= start)
+                        || (day_set(days, (day + 6) % 7) && minute < end)
+                }
+            }
+            ScanWindow::Interval { on, off } => {
+                let period = (on + off).as_millis().max(1);
+                let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+                since_epoch.as_millis() % period < on.as_millis()
+            }
+        }
+    }
+
+    /// First opening of the window strictly after `time`
+    fn next_start(&self, time: SystemTime, utc_offset: i32) -> Option<SystemTime> {
+        match *self {
+            ScanWindow::Daily { days, start, .. } => {
+                let local = shift(time, utc_offset);
+                let since_epoch = local
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let midnight = since_epoch - since_epoch % 86400;
+                let day = weekday(midnight / 86400);
+
+                (0..=7u64)
+                    .filter(|offset| day_set(days, (day + *offset as u32) % 7))
+                    .map(|offset| midnight + offset * 86400 + u64::from(start) * 60)
+                    .find(|&open| open > since_epoch)
+                    .map(|open| shift(UNIX_EPOCH + Duration::from_secs(open), -utc_offset))
+            }
+            ScanWindow::Interval { on, off } => {
+                if on.is_zero() {
+                    return None;
+                }
+                let period = (on + off).as_millis().max(1);
+                let since_epoch = time
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis();
+                let next = (since_epoch / period + 1) * period;
+                Some(UNIX_EPOCH + Duration::from_millis(next as u64))
+            }
+        }
+    }
+}
+
+/// `Mon-Fri 08:00-18:00`, `Sat,Sun 22:00-06:00` or `08:00-18:00` for every day, and
+/// `every 15m for 5m` for an interval window. Durations take `s`, `m` or `h` suffixes.
+impl FromStr for ScanWindow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(rest) = s.strip_prefix("every ") {
+            let (period, on) = rest.split_once(" for ").ok_or_else(|| {
+                format!("interval window '{}' needs 'every <period> for <on>'", s)
+            })?;
+            let (period, on) = (parse_duration(period)?, parse_duration(on)?);
+            if on > period {
+                return Err(format!("window '{}' is on longer than its period", s));
+            }
+            return Ok(ScanWindow::Interval {
+                on,
+                off: period - on,
+            });
+        }
+
+        let (days, times) = match s.rsplit_once(' ') {
+            Some((days, times)) => (parse_days(days)?, times),
+            None => (ALL_DAYS, s),
+        };
+        let (start, end) = times
+            .split_once('-')
+            .ok_or_else(|| format!("window '{}' needs a HH:MM-HH:MM time range", s))?;
+
+        Ok(ScanWindow::Daily {
+            days,
+            start: parse_time(start)?,
+            end: parse_time(end)?,
+        })
+    }
+}
+
+impl fmt::Display for ScanWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ScanWindow::Daily { days, start, end } => {
+                if days != ALL_DAYS {
+                    let names: Vec<&str> = (0..7)
+                        .filter(|&day| day_set(days, day))
+                        .map(|day| DAY_NAMES[day as usize])
+                        .collect();
+                    write!(f, "{} ", names.join(","))?;
+                }
+                write!(
+                    f,
+                    "{:02}:{:02}-{:02}:{:02}",
+                    start / 60,
+                    start % 60,
+                    end / 60,
+                    end % 60
+                )
+            }
+            ScanWindow::Interval { on, off } => {
+                write!(f, "every {}s for {}s", (on + off).as_secs(), on.as_secs())
+            }
+        }
+    }
+}
+
+/// Set of [`ScanWindow`]s, scanning is allowed while any of them is open. An empty schedule is
+/// always open.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScanSchedule {
+    windows: Vec<ScanWindow>,
+    /// Minutes added to UTC to get the local time daily windows are written in
+    utc_offset: i32,
+}
+
+impl ScanSchedule {
+    pub fn new(windows: Vec<ScanWindow>, utc_offset_minutes: i32) -> Self {
+        Self {
+            windows,
+            utc_offset: utc_offset_minutes,
+        }
+    }
+
+    pub fn add_window(&mut self, window: ScanWindow) {
+        self.windows.push(window);
+    }
+
+    pub fn get_windows(&self) -> &[ScanWindow] {
+        &self.windows
+    }
+
+    pub fn is_active(&self, time: SystemTime) -> bool {
+        self.windows.is_empty()
+            || self
+                .windows
+                .iter()
+                .any(|window| window.contains(time, self.utc_offset))
+    }
+
+    /// When scanning is next allowed: `time` itself inside a window, `None` if no window will
+    /// ever open
+    pub fn next_active(&self, time: SystemTime) -> Option<SystemTime> {
+        if self.is_active(time) {
+            return Some(time);
+        }
+
+        self.windows
+            .iter()
+            .filter_map(|window| window.next_start(time, self.utc_offset))
+            .min()
+    }
+}
+
+/// Share of time the scanner may use next to the radar's transmissions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DutyCycle {
+    fraction: f32,
+}
+
+impl DutyCycle {
+    /// `fraction` of time spent scanning, clamped to 1%..=100%
+    pub fn new(fraction: f32) -> Self {
+        Self {
+            fraction: fraction.clamp(0.01, 1.0),
+        }
+    }
+
+    /// Scan in the gaps of a radar transmitting `radar_duty_cycle` of the time, leaving the
+    /// scanner at least `min_fraction`
+    pub fn interleaved(radar_duty_cycle: f32, min_fraction: f32) -> Self {
+        Self::new((1.0 - radar_duty_cycle).max(min_fraction))
+    }
+
+    pub fn get_fraction(&self) -> f32 {
+        self.fraction
+    }
+
+    /// Pause needed after scanning for `busy` to stay within the duty cycle
+    pub fn rest_after(&self, busy: Duration) -> Duration {
+        busy.mul_f32((1.0 - self.fraction) / self.fraction)
+    }
+}
+
+impl Default for DutyCycle {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+/// How busy the monitored space is, as seen by [`AdaptiveRate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    /// Nobody seen for a while
+    Idle,
+    Present,
+    /// Someone moving faster than the fast speed
+    Moving,
+}
+
+/// Scan rate following the activity in the monitored space: low while it is empty, higher
+/// with targets present and highest while any of them moves fast. The rate drops back to idle
+/// only after the space stayed empty for a hold time, so a target missed for a cycle or two
+/// does not slow the scan down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaptiveRate {
+    idle_hz: f32,
+    present_hz: f32,
+    moving_hz: f32,
+    /// Speed in m/s above which a target counts as moving
+    moving_speed: f32,
+    idle_after: Duration,
+    last_activity: Option<Instant>,
+    activity: Activity,
+}
+
+impl AdaptiveRate {
+    pub fn new(
+        idle_hz: f32,
+        present_hz: f32,
+        moving_hz: f32,
+        moving_speed: f32,
+        idle_after: Duration,
+    ) -> Self {
+        let idle_hz = idle_hz.max(0.01);
+        let present_hz = present_hz.max(idle_hz);
+        Self {
+            idle_hz,
+            present_hz,
+            moving_hz: moving_hz.max(present_hz),
+            moving_speed,
+            idle_after,
+            last_activity: None,
+            activity: Activity::Idle,
+        }
+    }
+
+    /// Update the activity from the targets of the last scan, `speeds` in m/s
+    pub fn update<I>(&mut self, speeds: I, now: Instant) -> Activity
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        let mut present = false;
+        let mut moving = false;
+        for speed in speeds {
+            present = true;
+            moving |= speed > self.moving_speed;
+        }
+
+        self.activity = if moving {
+            Activity::Moving
+        } else if present {
+            Activity::Present
+        } else if self
+            .last_activity
+            .is_some_and(|last| now.saturating_duration_since(last) < self.idle_after)
+        {
+            // Keep the rate the space was last seen busy at until the hold time runs out
+            self.activity
+        } else {
+            Activity::Idle
+        };
+        if present {
+            self.last_activity = Some(now);
+        }
+        self.activity
+    }
+
+    pub fn get_activity(&self) -> Activity {
+        self.activity
+    }
+
+    pub fn get_rate_hz(&self) -> f32 {
+        match self.activity {
+            Activity::Idle => self.idle_hz,
+            Activity::Present => self.present_hz,
+            Activity::Moving => self.moving_hz,
+        }
+    }
+
+    /// Pause after a scan that took `busy`, for the current rate but never shorter than
+    /// `duty_cycle` allows
+    pub fn rest_after(&self, busy: Duration, duty_cycle: &DutyCycle) -> Duration {
+        Duration::from_secs_f32(1.0 / self.get_rate_hz())
+            .saturating_sub(busy)
+            .max(duty_cycle.rest_after(busy))
+    }
+}
+
+fn shift(time: SystemTime, minutes: i32) -> SystemTime {
+    let offset = Duration::from_secs(u64::from(minutes.unsigned_abs()) * 60);
+    if minutes >= 0 {
+        time + offset
+    } else {
+        time.checked_sub(offset).unwrap_or(UNIX_EPOCH)
+    }
+}
+
+/// Monday = 0, 1970-01-01 was a Thursday
+fn weekday(days_since_epoch: u64) -> u32 {
+    ((days_since_epoch + 3) % 7) as u32
+}
+
+fn day_set(days: u8, day: u32) -> bool {
+    days & (1 << day) != 0
+}
+
+fn local_day_minute(time: SystemTime, utc_offset: i32) -> (u32, u32) {
+    let seconds = shift(time, utc_offset)
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    (weekday(seconds / 86400), (seconds % 86400 / 60) as u32)
+}
+
+fn parse_days(s: &str) -> Result<u8, String> {
+    let day = |name: &str| {
+        DAY_NAMES
+            .iter()
+            .position(|day| day.eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("unknown weekday '{}'", name))
+    };
+
+    let mut days = 0u8;
+    for part in s.split(',') {
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (day(first)?, day(last)?);
+                let mut d = first;
+                loop {
+                    days |= 1 << d;
+                    if d == last {
+                        break;
+                    }
+                    d = (d + 1) % 7;
+                }
+            }
+            None => days |= 1 << day(part)?,
+        }
+    }
+    Ok(days)
+}
+
+/// `HH:MM` as minutes after midnight, `24:00` allowed as the end of the day
+fn parse_time(s: &str) -> Result<u16, String> {
+    let (hours, minutes) = s
+        .trim()
+        .split_once(':')
+        .ok_or_else(|| format!("time '{}' must be HH:MM", s))?;
+    let hours: u16 = hours.parse().map_err(|_| format!("bad hour in '{}'", s))?;
+    let minutes: u16 = minutes
+        .parse()
+        .map_err(|_| format!("bad minute in '{}'", s))?;
+
+    let total = hours * 60 + minutes;
+    if minutes >= 60 || u32::from(total) > MINUTES_PER_DAY {
+        return Err(format!("time '{}' is out of range", s));
+    }
+    Ok(total)
+}
+
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let (value, unit) = s.split_at(s.len().saturating_sub(1));
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        _ => return Err(format!("duration '{}' needs an s, m or h suffix", s)),
+    };
+    let value: u64 = value.parse().map_err(|_| format!("bad duration '{}'", s))?;
+    Ok(Duration::from_secs(value * seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2024-01-01 was a Monday
+    fn monday(hour: u64, minute: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_704_067_200 + hour * 3600 + minute * 60)
+    }
+
+    #[test]
+    fn test_daily_windows() {
+        let schedule = ScanSchedule::new(
+            vec![
+                "Mon-Fri 08:00-18:00".parse().unwrap(),
+                "Sun 22:00-06:00".parse().unwrap(),
+            ],
+            60,
+        );
+
+        // Local time is an hour ahead of UTC
+        assert!(schedule.is_active(monday(7, 0)));
+        assert!(!schedule.is_active(monday(6, 59)));
+        assert!(!schedule.is_active(monday(17, 0)));
+        // Sunday night's window runs into Monday morning
+        assert!(schedule.is_active(monday(4, 0)));
+        assert_eq!(schedule.next_active(monday(17, 0)), Some(monday(24 + 7, 0)));
+
+        assert_eq!(
+            "sat,sun 22:00-06:00"
+                .parse::<ScanWindow>()
+                .unwrap()
+                .to_string(),
+            "sat,sun 22:00-06:00"
+        );
+        assert!("Mon 25:00-26:00".parse::<ScanWindow>().is_err());
+        assert!(ScanSchedule::default().is_active(monday(3, 0)));
+    }
+
+    #[test]
+    fn test_interval_and_duty_cycle() {
+        let schedule = ScanSchedule::new(vec!["every 15m for 5m".parse().unwrap()], 0);
+        assert!(schedule.is_active(monday(1, 4)));
+        assert!(!schedule.is_active(monday(1, 5)));
+        assert_eq!(schedule.next_active(monday(1, 5)), Some(monday(1, 15)));
+
+        let duty = DutyCycle::interleaved(0.75, 0.05);
+        assert_eq!(
+            duty.rest_after(Duration::from_secs(1)),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            DutyCycle::default().rest_after(Duration::from_secs(1)),
+            Duration::ZERO
+        );
+    }
+
+    #[test]
+    fn test_adaptive_rate() {
+        let start = Instant::now();
+        let mut rate = AdaptiveRate::new(1.0, 5.0, 20.0, 1.0, Duration::from_secs(10));
+        assert_eq!(rate.get_rate_hz(), 1.0);
+
+        assert_eq!(rate.update([0.2], start), Activity::Present);
+        assert_eq!(rate.update([0.2, 1.5], start), Activity::Moving);
+        assert_eq!(rate.get_rate_hz(), 20.0);
+        // Held until the space stayed empty long enough
+        assert_eq!(
+            rate.update([], start + Duration::from_secs(5)),
+            Activity::Moving
+        );
+        assert_eq!(
+            rate.update([], start + Duration::from_secs(10)),
+            Activity::Idle
+        );
+
+        // A 40% duty cycle stretches the pause after a 100 ms scan past the 1 Hz period
+        let busy = Duration::from_millis(100);
+        assert_eq!(
+            rate.rest_after(busy, &DutyCycle::default()),
+            Duration::from_millis(900)
+        );
+        rate.update([0.0], start);
+        let rest = rate.rest_after(busy, &DutyCycle::new(0.04));
+        assert!(rest.abs_diff(Duration::from_millis(2400)) < Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,895 @@
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use nalgebra::Vector2;
+use serde::{Deserialize, Serialize};
+use serialport::{ClearBuffer, SerialPort};
+use smallvec::SmallVec;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::accumulator::AccumulatorStats;
+use crate::driver::{DriverPoll, ACK_BIT};
+use crate::error::{HexarError, HexarResult};
+use crate::fusion::SensorPose;
+use crate::ld2412::{EngineeringModeData, Ld2412Command, Ld2412TargetData, TargetState};
+use crate::ld2450::{Ld2450Command, Ld2450TargetData};
+use crate::pipeline::StageSender;
+use crate::{BaudRate, DriverAction, DriverCore, RadarDriver, RadarLLFrame};
+
+/// Bytes read from the port at most per poll of the driver
+const RX_CHUNK: usize = 256;
+
+/// How often a reader checks its port for new bytes, well above the modules' frame rates
+const READ_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Opcode of the acknowledgement of a firmware version query, the same on both models
+const FIRMWARE_VERSION_ACK: u16 = 0x01A0;
+
+/// First wait before reopening a port that failed, doubled after every failed attempt
+const RECONNECT_MIN_DELAY: Duration = Duration::from_millis(500);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(30);
+
+/// How long a stopping reader waits for its module to leave configuration mode
+const RESUME_TIMEOUT: Duration = Duration::from_millis(300);
+
+/// How long a reset waits for its module to acknowledge the configured gate thresholds
+const CONFIGURE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Radar module family on a serial port
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorModel {
+    /// 1D presence radar reporting the distance of one moving and one stationary target
+    Ld2412,
+    /// 2D tracking radar reporting up to three targets
+    Ld2450,
+}
+
+impl SensorModel {
+    /// Factory setting of the module's UART
+    pub fn default_baud_rate(&self) -> BaudRate {
+        match self {
+            SensorModel::Ld2412 => BaudRate::B115200,
+            SensorModel::Ld2450 => BaudRate::B256000,
+        }
+    }
+}
+
+/// Serial port of the module mounted on one antenna
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensorPortConfig {
+    pub antenna_id: u8,
+    /// e.g. "/dev/ttyUSB0" or "COM3"
+    pub port: String,
+    pub model: SensorModel,
+    /// The model's factory setting when unset
+    #[serde(default)]
+    pub baud_rate: Option<u32>,
+    /// Where the module is mounted, overriding the antenna's `fusion.sensors` entry
+    #[serde(default)]
+    pub pose: Option<SensorPose>,
+    /// Gate thresholds of an LD2412, sent whenever the module is reset. The module keeps its
+    /// own when unset.
+    #[serde(default)]
+    pub sensitivity: Option<GateSensitivity>,
+}
+
+/// Energy an LD2412 needs to see on each of its 14 range gates to report a target there, from
+/// 0 to 100, e.g. as found by `hexar calibrate`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GateSensitivity {
+    pub motion: [u8; 14],
+    pub stationary: [u8; 14],
+}
+
+/// Target reported by a module, in the antenna frame: `x` across and `y` along the boresight,
+/// in metres
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SensorMeasurement {
+    pub position: Vector2<f32>,
+    /// Radial speed in m/s, positive moving away. The LD2412 doesn't measure it.
+    pub speed: Option<f32>,
+    /// Whether the module classified the target as moving rather than stationary
+    pub moving: bool,
+}
+
+/// Targets of one frame read from the module on an antenna
+#[derive(Debug, Clone)]
+pub struct SensorFrame {
+    pub antenna_id: u8,
+    pub model: SensorModel,
+    pub received_at: Instant,
+    /// Target data as received, between the frame header and footer
+    pub payload: Vec<u8>,
+    pub measurements: Vec<SensorMeasurement>,
+}
+
+impl SensorFrame {
+    /// Parse a target data payload of `model`
+    pub fn parse(antenna_id: u8, model: SensorModel, payload: &[u8]) -> Self {
+        let data = SmallVec::from_slice(payload);
+        let frame = match model {
+            SensorModel::Ld2412 => RadarLLFrame::TargetFrame(data),
+            SensorModel::Ld2450 => RadarLLFrame::TargetFrame2D(data),
+        };
+        Self {
+            antenna_id,
+            model,
+            received_at: Instant::now(),
+            payload: payload.to_vec(),
+            measurements: frame_measurements(model, &frame).into_vec(),
+        }
+    }
+
+    /// Energies of every distance gate of an LD2412 frame in engineering mode, `None` for other
+    /// frames
+    pub fn gate_energies(&self) -> Option<EngineeringModeData> {
+        if self.model != SensorModel::Ld2412 {
+            return None;
+        }
+        Ld2412TargetData::deserialize(&self.payload).and_then(|data| data.engineering_mode_data)
+    }
+}
+
+/// Targets of one target data frame, nothing for acknowledgements and frames of the other model
+pub fn frame_measurements(
+    model: SensorModel,
+    frame: &RadarLLFrame,
+) -> SmallVec<[SensorMeasurement; 3]> {
+    let mut measurements = SmallVec::new();
+    match (model, frame) {
+        (SensorModel::Ld2450, RadarLLFrame::TargetFrame2D(data)) => {
+            let Some(data) = Ld2450TargetData::deserialize(data) else {
+                return measurements;
+            };
+            for target in &data.targets {
+                let speed = f32::from(target.speed) / 100.0;
+                measurements.push(SensorMeasurement {
+                    position: Vector2::new(
+                        f32::from(target.position.x) / 1000.0,
+                        f32::from(target.position.y) / 1000.0,
+                    ),
+                    speed: Some(speed),
+                    moving: target.speed != 0,
+                });
+            }
+        }
+        (SensorModel::Ld2412, RadarLLFrame::TargetFrame(data)) => {
+            let Some(data) = Ld2412TargetData::deserialize(data) else {
+                return measurements;
+            };
+            let basic = &data.basic_target_data;
+            // Both targets are usually the same person, the moving one is the better fix
+            let (target, moving) = match basic.state {
+                TargetState::Campaign | TargetState::MotionStationary => {
+                    (&basic.moving_target, true)
+                }
+                TargetState::Stationary => (&basic.stationary_target, false),
+                _ => return measurements,
+            };
+            if target.distance > 0 {
+                measurements.push(SensorMeasurement {
+                    position: Vector2::new(0.0, f32::from(target.distance) / 100.0),
+                    speed: None,
+                    moving,
+                });
+            }
+        }
+        _ => {}
+    }
+    measurements
+}
+
+/// Firmware version from the acknowledgement of a firmware version query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirmwareVersion {
+    pub firmware_type: u16,
+    pub major: u16,
+    pub minor: u32,
+}
+
+impl FirmwareVersion {
+    /// Parse the acknowledgement data: status, type, major and minor version, little endian
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let [status_l, status_h, type_l, type_h, major_l, major_h, m0, m1, m2, m3, ..] = *data
+        else {
+            return None;
+        };
+        if u16::from_le_bytes([status_l, status_h]) != 0 {
+            return None;
+        }
+        Some(Self {
+            firmware_type: u16::from_le_bytes([type_l, type_h]),
+            major: u16::from_le_bytes([major_l, major_h]),
+            minor: u32::from_le_bytes([m0, m1, m2, m3]),
+        })
+    }
+}
+
+impl FromStr for FirmwareVersion {
+    type Err = String;
+
+    /// As [`Display`](fmt::Display) writes it, the firmware type left at zero
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid firmware version '{}'", version);
+        let digits = version.strip_prefix(['V', 'v']).ok_or_else(invalid)?;
+        let mut parts = digits.split('.');
+        let (Some(high), Some(low), Some(minor), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let hex = |part: &str| u32::from_str_radix(part, 16).map_err(|_| invalid());
+        let (high, low) = (hex(high)?, hex(low)?);
+        if high > 0xff || low > 0xff {
+            return Err(invalid());
+        }
+        Ok(Self {
+            firmware_type: 0,
+            major: (high << 8 | low) as u16,
+            minor: hex(minor)?,
+        })
+    }
+}
+
+impl fmt::Display for FirmwareVersion {
+    /// As the vendor tools show it, e.g. "V1.02.22062416"
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "V{:x}.{:02x}.{:08x}",
+            self.major >> 8,
+            self.major & 0xff,
+            self.minor
+        )
+    }
+}
+
+/// Self-test result of the module on one antenna
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AntennaSelfTest {
+    pub antenna_id: u8,
+    pub port: String,
+    /// Model configured for the antenna
+    pub model: SensorModel,
+    pub firmware: Option<String>,
+    /// Model whose target data arrived, `None` if none did
+    pub detected_model: Option<SensorModel>,
+    pub error: Option<String>,
+}
+
+impl AntennaSelfTest {
+    /// Firmware answered and the configured model is sending target data
+    pub fn passed(&self) -> bool {
+        self.firmware.is_some() && self.detected_model == Some(self.model)
+    }
+}
+
+/// Whether the serial link of an antenna works
+#[derive(Debug, Clone, PartialEq)]
+pub enum LinkState {
+    Connected,
+    /// The port failed, its reader keeps trying to reopen it
+    Disconnected {
+        since: Instant,
+        error: String,
+        /// Failed attempts to reopen the port so far
+        attempts: u32,
+    },
+}
+
+impl LinkState {
+    pub fn is_connected(&self) -> bool {
+        matches!(self, LinkState::Connected)
+    }
+}
+
+/// LD2412 or LD2450 module on a serial port, driven by a [`DriverCore`]
+pub struct SerialRadar {
+    antenna_id: u8,
+    model: SensorModel,
+    config: SensorPortConfig,
+    port: Box<dyn SerialPort>,
+    driver: DriverCore,
+    rx: Vec<u8>,
+    last_poll: Instant,
+}
+
+impl fmt::Debug for SerialRadar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SerialRadar")
+            .field("antenna_id", &self.antenna_id)
+            .field("model", &self.model)
+            .field("port", &self.port.name())
+            .field("driver", &self.driver)
+            .finish()
+    }
+}
+
+impl SerialRadar {
+    pub fn open(config: &SensorPortConfig) -> HexarResult<Self> {
+        Ok(Self {
+            antenna_id: config.antenna_id,
+            model: config.model,
+            config: config.clone(),
+            port: open_port(config)?,
+            driver: DriverCore::new(),
+            rx: Vec::with_capacity(RX_CHUNK),
+            last_poll: Instant::now(),
+        })
+    }
+
+    /// Open the port again after it failed, e.g. when a USB adapter was unplugged and plugged
+    /// back in, and start the module from a clean state
+    pub fn reconnect(&mut self) -> HexarResult<()> {
+        self.port = open_port(&self.config)?;
+        self.reset()
+    }
+
+    pub fn get_antenna_id(&self) -> u8 {
+        self.antenna_id
+    }
+
+    pub fn get_model(&self) -> SensorModel {
+        self.model
+    }
+
+    /// How well the bytes received so far parsed into frames
+    pub fn get_parser_stats(&self) -> AccumulatorStats {
+        self.driver.stats()
+    }
+
+    /// Discard everything received so far, e.g. before the first scan cycle, and send the
+    /// configured gate thresholds, blocking until they are acknowledged
+    pub fn reset(&mut self) -> HexarResult<()> {
+        self.port
+            .clear(ClearBuffer::Input)
+            .map_err(|e| HexarError::CommunicationError(e.to_string()))?;
+        self.driver.reset();
+        self.rx.clear();
+        self.last_poll = Instant::now();
+        if let Some(sensitivity) = self.config.sensitivity.clone() {
+            // Sent right away, the queue has no room for more commands until it is
+            self.set_sensitivity(&sensitivity)?;
+            self.flush_commands(CONFIGURE_TIMEOUT)?;
+        }
+        Ok(())
+    }
+
+    /// Read whatever the module sent since the previous call without blocking, and return the
+    /// latest target data frame, `None` if no frame was completed. Modules report every target
+    /// in each frame, so older frames are superseded.
+    pub fn read_frame(&mut self) -> HexarResult<Option<SensorFrame>> {
+        let latest = self
+            .pump()?
+            .into_iter()
+            .rev()
+            .find_map(|frame| match frame {
+                RadarLLFrame::TargetFrame(payload) | RadarLLFrame::TargetFrame2D(payload) => {
+                    Some(SensorFrame::parse(self.antenna_id, self.model, &payload))
+                }
+                RadarLLFrame::CommandAckFrame(..) => None,
+            });
+        Ok(latest)
+    }
+
+    /// Have the module measure and report targets, or hold it in configuration mode where it
+    /// does neither, e.g. while a neighbouring module takes its turn. Returns once the command
+    /// is queued, it is sent by the following reads.
+    pub fn set_transmitting(&mut self, transmitting: bool) -> HexarResult<()> {
+        let queued = match (self.model, transmitting) {
+            (SensorModel::Ld2412, true) => self.driver.send(&Ld2412Command::EndConfiguration),
+            (SensorModel::Ld2412, false) => self.driver.send(&Ld2412Command::EnableConfiguration),
+            (SensorModel::Ld2450, true) => self.driver.send(&Ld2450Command::EndConfiguration),
+            (SensorModel::Ld2450, false) => self.driver.send(&Ld2450Command::EnableConfiguration),
+        };
+        queued.map_err(|_| {
+            HexarError::CommunicationError(format!(
+                "command queue of antenna {} full",
+                self.antenna_id
+            ))
+        })
+    }
+
+    /// Have an LD2412 report the energy on each range gate with its targets, or stop it. Queued
+    /// like [`set_transmitting`](Self::set_transmitting).
+    pub fn set_engineering_mode(&mut self, on: bool) -> HexarResult<()> {
+        let mode = if on {
+            Ld2412Command::EngineeringModeOn
+        } else {
+            Ld2412Command::EngineeringModeOff
+        };
+        self.configure_ld2412([mode])
+    }
+
+    /// Set the gate thresholds of an LD2412. Queued like
+    /// [`set_transmitting`](Self::set_transmitting).
+    pub fn set_sensitivity(&mut self, sensitivity: &GateSensitivity) -> HexarResult<()> {
+        let all = sensitivity.motion.iter().chain(&sensitivity.stationary);
+        if all.clone().any(|&threshold| threshold > 100) {
+            return Err(HexarError::InvalidParameter(format!(
+                "gate thresholds of antenna {} above 100",
+                self.antenna_id
+            )));
+        }
+        self.configure_ld2412([
+            Ld2412Command::MotionSensitivity(sensitivity.motion),
+            Ld2412Command::StaticSensitivity(sensitivity.stationary),
+        ])
+    }
+
+    /// Queue `commands` in configuration mode, for an LD2412 only
+    fn configure_ld2412<const N: usize>(
+        &mut self,
+        commands: [Ld2412Command; N],
+    ) -> HexarResult<()> {
+        if self.model != SensorModel::Ld2412 {
+            return Err(HexarError::InvalidParameter(format!(
+                "antenna {} has an {:?}, gate settings need an LD2412",
+                self.antenna_id, self.model
+            )));
+        }
+        let mut queue = std::iter::once(Ld2412Command::EnableConfiguration)
+            .chain(commands)
+            .chain(std::iter::once(Ld2412Command::EndConfiguration));
+        queue
+            .try_for_each(|command| self.driver.send(&command))
+            .map_err(|_| {
+                HexarError::CommunicationError(format!(
+                    "command queue of antenna {} full",
+                    self.antenna_id
+                ))
+            })
+    }
+
+    /// Keep reading, discarding the frames, until every queued command was acknowledged or
+    /// `timeout` passed, blocking
+    pub fn flush_commands(&mut self, timeout: Duration) -> HexarResult<()> {
+        let deadline = Instant::now() + timeout;
+        while self.driver.pending_commands() > 0 && Instant::now() < deadline {
+            self.pump()?;
+            std::thread::sleep(READ_INTERVAL);
+        }
+        Ok(())
+    }
+
+    /// Send `command` and wait for its acknowledgement, blocking and discarding the target data
+    /// received meanwhile. Returns the acknowledgement data, starting with the status, and an
+    /// error when the module refused the command or never answered.
+    pub fn request<C: RadarDriver>(&mut self, command: &C) -> HexarResult<SmallVec<[u8; 16]>> {
+        let opcode = command.get_opcode();
+        self.driver.send(command).map_err(|_| {
+            HexarError::CommunicationError(format!(
+                "command queue of antenna {} full",
+                self.antenna_id
+            ))
+        })?;
+        loop {
+            for frame in self.pump()? {
+                match frame {
+                    RadarLLFrame::CommandAckFrame(ack, data) if ack == opcode | ACK_BIT => {
+                        return match data.get(..2) {
+                            Some([0, 0]) => Ok(data),
+                            _ => Err(HexarError::HardwareError(format!(
+                                "antenna {} refused command {:#06x}",
+                                self.antenna_id, opcode
+                            ))),
+                        };
+                    }
+                    _ => {}
+                }
+            }
+            std::thread::sleep(READ_INTERVAL);
+        }
+    }
+
+    /// Enter configuration mode, where the module answers queries and stops reporting targets,
+    /// or leave it, blocking until the module acknowledged
+    pub fn set_configuration_mode(&mut self, on: bool) -> HexarResult<()> {
+        let acknowledged = match (self.model, on) {
+            (SensorModel::Ld2412, true) => self.request(&Ld2412Command::EnableConfiguration),
+            (SensorModel::Ld2412, false) => self.request(&Ld2412Command::EndConfiguration),
+            (SensorModel::Ld2450, true) => self.request(&Ld2450Command::EnableConfiguration),
+            (SensorModel::Ld2450, false) => self.request(&Ld2450Command::EndConfiguration),
+        };
+        acknowledged.map(drop)
+    }
+
+    /// Ask the module for its firmware version, blocking
+    pub fn query_firmware_version(&mut self) -> HexarResult<FirmwareVersion> {
+        self.set_configuration_mode(true)?;
+        let data = match self.model {
+            SensorModel::Ld2412 => self.request(&Ld2412Command::FirmwareVersion),
+            SensorModel::Ld2450 => self.request(&Ld2450Command::FirmwareVersion),
+        };
+        // Left in configuration mode even when the query failed, the module reports nothing
+        let left = self.set_configuration_mode(false);
+        let version = FirmwareVersion::parse(&data?).ok_or_else(|| {
+            HexarError::CommunicationError(format!(
+                "malformed firmware version from antenna {}",
+                self.antenna_id
+            ))
+        })?;
+        left.map(|()| version)
+    }
+
+    /// Query the firmware version and wait up to `timeout` for it and for a target data frame,
+    /// blocking. The kind of data frame tells which model is really on the port.
+    pub fn self_test(&mut self, timeout: Duration) -> AntennaSelfTest {
+        let mut report = AntennaSelfTest {
+            antenna_id: self.antenna_id,
+            port: self.config.port.clone(),
+            model: self.model,
+            firmware: None,
+            detected_model: None,
+            error: None,
+        };
+        if let Err(e) = self.reset() {
+            report.error = Some(e.to_string());
+            return report;
+        }
+
+        // Modules only answer queries in configuration mode, and stop reporting targets in it
+        let queued = match self.model {
+            SensorModel::Ld2412 => [
+                Ld2412Command::EnableConfiguration,
+                Ld2412Command::FirmwareVersion,
+                Ld2412Command::EndConfiguration,
+            ]
+            .iter()
+            .try_for_each(|command| self.driver.send(command)),
+            SensorModel::Ld2450 => [
+                Ld2450Command::EnableConfiguration,
+                Ld2450Command::FirmwareVersion,
+                Ld2450Command::EndConfiguration,
+            ]
+            .iter()
+            .try_for_each(|command| self.driver.send(command)),
+        };
+        if queued.is_err() {
+            report.error = Some("command queue full".to_string());
+            return report;
+        }
+
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline
+            && (report.firmware.is_none() || report.detected_model.is_none())
+        {
+            match self.pump() {
+                Ok(frames) => {
+                    for frame in frames {
+                        match frame {
+                            RadarLLFrame::CommandAckFrame(FIRMWARE_VERSION_ACK, data) => {
+                                report.firmware = FirmwareVersion::parse(&data)
+                                    .map(|version| version.to_string());
+                            }
+                            RadarLLFrame::CommandAckFrame(..) => {}
+                            RadarLLFrame::TargetFrame(_) => {
+                                report.detected_model = Some(SensorModel::Ld2412)
+                            }
+                            RadarLLFrame::TargetFrame2D(_) => {
+                                report.detected_model = Some(SensorModel::Ld2450)
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    report.error = Some(e.to_string());
+                    if !matches!(e, HexarError::Timeout(_)) {
+                        break;
+                    }
+                }
+            }
+            std::thread::sleep(READ_INTERVAL);
+        }
+        if report.error.is_none() && !report.passed() {
+            report.error = Some(match (&report.firmware, report.detected_model) {
+                (_, None) => format!("no target data within {:?}", timeout),
+                (None, _) => "firmware version not reported".to_string(),
+                (_, Some(model)) => format!("{:?} found, {:?} expected", model, self.model),
+            });
+        }
+        report
+    }
+
+    /// Read the bytes received so far and run them through the driver, transmitting whatever it
+    /// asks for. Returns the frames completed, oldest first.
+    fn pump(&mut self) -> HexarResult<Vec<RadarLLFrame>> {
+        let available =
+            self.port
+                .bytes_to_read()
+                .map_err(|e| HexarError::CommunicationError(e.to_string()))? as usize;
+        if available > 0 {
+            let start = self.rx.len();
+            self.rx.resize(start + available, 0);
+            let read = self.port.read(&mut self.rx[start..])?;
+            self.rx.truncate(start + read);
+        }
+
+        let mut elapsed_ms = self.last_poll.elapsed().as_millis().min(u32::MAX as u128) as u32;
+        self.last_poll = Instant::now();
+        let mut frames = Vec::new();
+        loop {
+            let chunk = &self.rx[..self.rx.len().min(RX_CHUNK)];
+            let DriverPoll { consumed, action } = self.driver.poll(elapsed_ms, chunk);
+            elapsed_ms = 0;
+            self.rx.drain(..consumed);
+
+            match action {
+                DriverAction::Transmit(bytes) => self.port.write_all(&bytes)?,
+                DriverAction::Frame(frame) => frames.push(frame),
+                DriverAction::CommandTimedOut(opcode) => {
+                    return Err(HexarError::Timeout(format!(
+                        "command {:#06x} to {} not acknowledged",
+                        opcode,
+                        self.port.name().unwrap_or_default()
+                    )))
+                }
+                // The accumulator holds a partial frame, or the buffer is drained
+                DriverAction::Wait(_) if consumed == 0 || self.rx.is_empty() => break,
+                DriverAction::Wait(_) => {}
+            }
+        }
+
+        Ok(frames)
+    }
+}
+
+fn open_port(config: &SensorPortConfig) -> HexarResult<Box<dyn SerialPort>> {
+    let baud_rate = match config.baud_rate {
+        Some(bps) => BaudRate::from_bps(bps).ok_or_else(|| {
+            HexarError::ConfigurationError(format!(
+                "unsupported baud rate {} for {}",
+                bps, config.port
+            ))
+        })?,
+        None => config.model.default_baud_rate(),
+    };
+    serialport::new(&config.port, baud_rate.bps())
+        .timeout(Duration::from_millis(10))
+        .open()
+        .map_err(|e| HexarError::HardwareError(format!("failed to open {}: {}", config.port, e)))
+}
+
+/// Switches the module of a [`SensorReader`] between transmitting and silent from another task
+#[derive(Debug, Clone)]
+pub struct TransmitGate(Arc<AtomicBool>);
+
+impl TransmitGate {
+    pub fn set(&self, transmitting: bool) {
+        self.0.store(transmitting, Ordering::Relaxed);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// A [`SerialRadar`] read on a blocking thread of its own, parsing every frame and sending it to
+/// the next stage
+#[derive(Debug)]
+pub struct SensorReader {
+    antenna_id: u8,
+    link: Arc<Mutex<LinkState>>,
+    gate: TransmitGate,
+    parser_stats: Arc<Mutex<AccumulatorStats>>,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<SerialRadar>,
+}
+
+impl SensorReader {
+    pub fn spawn(mut sensor: SerialRadar, frames: StageSender<SensorFrame>) -> Self {
+        let antenna_id = sensor.antenna_id;
+        let link = Arc::new(Mutex::new(LinkState::Connected));
+        let parser_stats = Arc::new(Mutex::new(sensor.get_parser_stats()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let gate = TransmitGate(Arc::new(AtomicBool::new(true)));
+        let stats = Arc::clone(&parser_stats);
+        let wanted = gate.clone();
+        let (state, stopped) = (Arc::clone(&link), Arc::clone(&stop));
+        let set_state = move |next: LinkState| {
+            *state.lock().unwrap_or_else(|e| e.into_inner()) = next;
+        };
+        let handle = tokio::task::spawn_blocking(move || {
+            let mut retry_delay = RECONNECT_MIN_DELAY;
+            let mut disconnected: Option<(Instant, String, u32)> = None;
+            let mut transmitting = true;
+            while !stopped.load(Ordering::Relaxed) {
+                if let Some((since, error, attempts)) = &mut disconnected {
+                    sleep_unless_stopped(retry_delay, &stopped);
+                    if stopped.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    *attempts += 1;
+                    match sensor.reconnect() {
+                        Ok(()) => {
+                            info!(
+                                "Antenna {} reconnected after {} attempts",
+                                antenna_id, attempts
+                            );
+                            set_state(LinkState::Connected);
+                            disconnected = None;
+                            // Reopened modules report until told otherwise
+                            transmitting = true;
+                            retry_delay = RECONNECT_MIN_DELAY;
+                        }
+                        Err(e) => {
+                            debug!("Antenna {} still disconnected: {}", antenna_id, e);
+                            set_state(LinkState::Disconnected {
+                                since: *since,
+                                error: error.clone(),
+                                attempts: *attempts,
+                            });
+                            retry_delay = (retry_delay * 2).min(RECONNECT_MAX_DELAY);
+                        }
+                    }
+                    continue;
+                }
+
+                if wanted.is_open() != transmitting {
+                    match sensor.set_transmitting(!transmitting) {
+                        Ok(()) => transmitting = !transmitting,
+                        Err(e) => warn!("Antenna {}: {}", antenna_id, e),
+                    }
+                }
+
+                let read = sensor.read_frame();
+                *stats.lock().unwrap_or_else(|e| e.into_inner()) = sensor.get_parser_stats();
+                match read {
+                    // Frames sent before the module went silent
+                    Ok(Some(_)) if !transmitting => {}
+                    Ok(Some(frame)) => {
+                        // The stage's policy decides whether a full queue drops the frame
+                        if frames.blocking_send(frame).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(None) => std::thread::sleep(READ_INTERVAL),
+                    // An unanswered command says nothing about the link
+                    Err(e @ HexarError::Timeout(_)) => {
+                        warn!("Antenna {}: {}", antenna_id, e);
+                    }
+                    Err(e) => {
+                        warn!("Antenna {} disconnected: {}", antenna_id, e);
+                        let since = Instant::now();
+                        set_state(LinkState::Disconnected {
+                            since,
+                            error: e.to_string(),
+                            attempts: 0,
+                        });
+                        disconnected = Some((since, e.to_string(), 0));
+                    }
+                }
+            }
+            // The next reader expects the module to report
+            if !transmitting && disconnected.is_none() {
+                let resumed = sensor
+                    .set_transmitting(true)
+                    .and_then(|()| sensor.flush_commands(RESUME_TIMEOUT));
+                if let Err(e) = resumed {
+                    warn!("Antenna {} left silent: {}", antenna_id, e);
+                }
+            }
+            debug!("Reader of antenna {} stopped", antenna_id);
+            sensor
+        });
+
+        Self {
+            antenna_id,
+            link,
+            gate,
+            parser_stats,
+            stop,
+            handle,
+        }
+    }
+
+    pub fn get_link_state(&self) -> LinkState {
+        self.link.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Parser statistics of the module as of its last read
+    pub fn get_parser_stats(&self) -> AccumulatorStats {
+        *self.parser_stats.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn get_antenna_id(&self) -> u8 {
+        self.antenna_id
+    }
+
+    /// Gate for silencing the module while other modules transmit
+    pub fn get_transmit_gate(&self) -> TransmitGate {
+        self.gate.clone()
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Stop reading and take the module back
+    pub async fn stop(self) -> HexarResult<SerialRadar> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle.await.map_err(|e| {
+            HexarError::SystemError(format!(
+                "reader of antenna {} failed: {}",
+                self.antenna_id, e
+            ))
+        })
+    }
+}
+
+/// Sleep for `duration` in short steps, returning early once `stop` is set
+fn sleep_unless_stopped(duration: Duration, stop: &AtomicBool) {
+    let until = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) {
+        let left = until.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        std::thread::sleep(left.min(READ_INTERVAL * 10));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ld2450_frame_measurements() {
+        // Target 1 at x = -0.78 m, y = 1.4 m approaching at 16 cm/s, the others empty
+        let mut data = [0u8; 24];
+        data[..8].copy_from_slice(&[0x0E, 0x03, 0x78, 0x85, 0x10, 0x00, 0x68, 0x01]);
+        let frame = RadarLLFrame::TargetFrame2D(SmallVec::from_slice(&data));
+
+        let measurements = frame_measurements(SensorModel::Ld2450, &frame);
+        assert_eq!(measurements.len(), 1);
+        assert!((measurements[0].position - Vector2::new(-0.782, 1.4)).norm() < 1e-3);
+        assert_eq!(measurements[0].speed, Some(-0.16));
+        assert!(measurements[0].moving);
+
+        // A frame of the other model yields nothing
+        assert!(frame_measurements(SensorModel::Ld2412, &frame).is_empty());
+    }
+
+    #[test]
+    fn test_firmware_version() {
+        let data = [0x00, 0x00, 0x00, 0x00, 0x02, 0x01, 0x16, 0x24, 0x06, 0x22];
+        let version = FirmwareVersion::parse(&data).unwrap();
+        assert_eq!(version.major, 0x0102);
+        assert_eq!(version.to_string(), "V1.02.22062416");
+
+        // Failed status or truncated data
+        assert!(FirmwareVersion::parse(&[0x01, 0x00, 0, 0, 2, 1, 0, 0, 0, 0]).is_none());
+        assert!(FirmwareVersion::parse(&data[..6]).is_none());
+
+        // Back from the way it is shown, e.g. in the names of firmware files
+        let shown: FirmwareVersion = "V1.02.22062416".parse().unwrap();
+        assert_eq!((shown.major, shown.minor), (version.major, version.minor));
+        assert!("V1.02".parse::<FirmwareVersion>().is_err());
+    }
+
+    #[test]
+    fn test_ld2412_frame_measurements() {
+        let moving = [
+            0x02, 0xAA, 0x01, 0x64, 0x00, 0x3C, 0xC8, 0x00, 0x32, 0x55, 0x00,
+        ];
+        let frame = RadarLLFrame::TargetFrame(SmallVec::from_slice(&moving));
+        let measurements = frame_measurements(SensorModel::Ld2412, &frame);
+        assert_eq!(measurements.len(), 1);
+        assert_eq!(measurements[0].position, Vector2::new(0.0, 1.0));
+        assert!(measurements[0].moving);
+
+        let stationary = [
+            0x02, 0xAA, 0x02, 0x64, 0x00, 0x3C, 0xC8, 0x00, 0x32, 0x55, 0x00,
+        ];
+        let frame = RadarLLFrame::TargetFrame(SmallVec::from_slice(&stationary));
+        let measurements = frame_measurements(SensorModel::Ld2412, &frame);
+        assert_eq!(measurements[0].position, Vector2::new(0.0, 2.0));
+        assert!(!measurements[0].moving);
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+use tokio::sync::Notify;
+
+/// Why the running instance shuts down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShutdownReason {
+    /// SIGINT, or Ctrl+C and Ctrl+Break on Windows
+    Interrupt,
+    /// SIGTERM, or the console closing or the system shutting down on Windows
+    Terminate,
+    /// A [`ShutdownTrigger`], e.g. the Windows service manager stopping the service
+    Requested,
+}
+
+impl fmt::Display for ShutdownReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ShutdownReason::Interrupt => "interrupt",
+            ShutdownReason::Terminate => "termination signal",
+            ShutdownReason::Requested => "shutdown request",
+        })
+    }
+}
+
+/// What asks the process to shut down, the same on every platform: its signals, console
+/// events on Windows, and a [`ShutdownTrigger`]
+#[derive(Debug)]
+pub struct ShutdownSignal {
+    signals: Signals,
+    requested: Arc<Notify>,
+}
+
+impl ShutdownSignal {
+    /// Start listening for signals and `trigger`, within a tokio runtime. Until then signals
+    /// have their default effect.
+    pub fn new(trigger: &ShutdownTrigger) -> io::Result<Self> {
+        Ok(Self {
+            signals: Signals::new()?,
+            requested: trigger.0.clone(),
+        })
+    }
+
+    /// Wait for the next reason to shut down. Cancel safe, for use in `select!` loops.
+    pub async fn recv(&mut self) -> ShutdownReason {
+        tokio::select! {
+            reason = self.signals.recv() => reason,
+            _ = self.requested.notified() => ShutdownReason::Requested,
+        }
+    }
+}
+
+/// Requests a shutdown of the [`ShutdownSignal`] listening to it from anywhere, including
+/// threads outside the runtime. Remembered until it is received.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownTrigger(Arc<Notify>);
+
+impl ShutdownTrigger {
+    pub fn request(&self) {
+        self.0.notify_one();
+    }
+}
+
+#[cfg(unix)]
+#[derive(Debug)]
+struct Signals {
+    interrupt: tokio::signal::unix::Signal,
+    terminate: tokio::signal::unix::Signal,
+}
+
+#[cfg(unix)]
+impl Signals {
+    fn new() -> io::Result<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+        Ok(Self {
+            interrupt: signal(SignalKind::interrupt())?,
+            terminate: signal(SignalKind::terminate())?,
+        })
+    }
+
+    async fn recv(&mut self) -> ShutdownReason {
+        tokio::select! {
+            _ = self.interrupt.recv() => ShutdownReason::Interrupt,
+            _ = self.terminate.recv() => ShutdownReason::Terminate,
+        }
+    }
+}
+
+#[cfg(windows)]
+#[derive(Debug)]
+struct Signals {
+    ctrl_c: tokio::signal::windows::CtrlC,
+    ctrl_break: tokio::signal::windows::CtrlBreak,
+    ctrl_close: tokio::signal::windows::CtrlClose,
+    ctrl_shutdown: tokio::signal::windows::CtrlShutdown,
+}
+
+#[cfg(windows)]
+impl Signals {
+    fn new() -> io::Result<Self> {
+        use tokio::signal::windows::{ctrl_break, ctrl_c, ctrl_close, ctrl_shutdown};
+        Ok(Self {
+            ctrl_c: ctrl_c()?,
+            ctrl_break: ctrl_break()?,
+            ctrl_close: ctrl_close()?,
+            ctrl_shutdown: ctrl_shutdown()?,
+        })
+    }
+
+    async fn recv(&mut self) -> ShutdownReason {
+        tokio::select! {
+            _ = self.ctrl_c.recv() => ShutdownReason::Interrupt,
+            _ = self.ctrl_break.recv() => ShutdownReason::Interrupt,
+            _ = self.ctrl_close.recv() => ShutdownReason::Terminate,
+            _ = self.ctrl_shutdown.recv() => ShutdownReason::Terminate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_shutdown_request() {
+        let trigger = ShutdownTrigger::default();
+        // Requested before anyone listens, from a thread outside the runtime
+        let remote = trigger.clone();
+        std::thread::spawn(move || remote.request()).join().unwrap();
+        let mut shutdown = ShutdownSignal::new(&trigger).unwrap();
+        assert_eq!(shutdown.recv().await, ShutdownReason::Requested);
+    }
+}
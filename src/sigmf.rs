@@ -0,0 +1,370 @@
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::peaks::Peak;
+use crate::scanner::FrequencyScanner;
+
+const SIGMF_VERSION: &str = "1.0.0";
+
+/// One contiguous block of samples tuned to a single frequency
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigmfCapture {
+    pub sample_start: u64,
+    /// Center frequency in MHz
+    pub frequency: f32,
+    pub datetime: SystemTime,
+}
+
+/// A detection, spanning `sample_count` samples from `sample_start` and the given frequency edges
+#[derive(Debug, Clone, PartialEq)]
+pub struct SigmfAnnotation {
+    pub sample_start: u64,
+    /// `None` when the detection has no samples, e.g. from a stepped power scan
+    pub sample_count: Option<u64>,
+    /// Lower edge in MHz
+    pub freq_lower: f32,
+    /// Upper edge in MHz
+    pub freq_upper: f32,
+    pub label: String,
+    pub comment: Option<String>,
+}
+
+/// Recording in the [SigMF](https://sigmf.org) format: interleaved `cf32_le` I/Q in a
+/// `.sigmf-data` file and a JSON `.sigmf-meta` file describing its captures, with detections
+/// as annotations, so scans open in inspectrum, GNU Radio and the sigmf Python tools
+#[derive(Debug, Clone, Default)]
+pub struct SigmfRecording {
+    sample_rate: Option<f32>,
+    description: Option<String>,
+    hardware: Option<String>,
+    samples: Vec<[f32; 2]>,
+    captures: Vec<SigmfCapture>,
+    annotations: Vec<SigmfAnnotation>,
+}
+
+impl SigmfRecording {
+    /// `sample_rate` in Hz, taken from the first scanner capture when `None`
+    pub fn new(sample_rate: Option<f32>) -> Self {
+        Self {
+            sample_rate,
+            ..Self::default()
+        }
+    }
+
+    pub fn set_description(&mut self, description: impl Into<String>) {
+        self.description = Some(description.into());
+    }
+
+    /// Receiver the recording was made with, e.g. "RTL-SDR v4"
+    pub fn set_hardware(&mut self, hardware: impl Into<String>) {
+        self.hardware = Some(hardware.into());
+    }
+
+    pub fn get_sample_rate(&self) -> Option<f32> {
+        self.sample_rate
+    }
+
+    pub fn get_captures(&self) -> &[SigmfCapture] {
+        &self.captures
+    }
+
+    pub fn get_annotations(&self) -> &[SigmfAnnotation] {
+        &self.annotations
+    }
+
+    /// Append samples taken at `frequency` in MHz, returning the capture index
+    pub fn add_capture(
+        &mut self,
+        frequency: f32,
+        datetime: SystemTime,
+        samples: &[[f32; 2]],
+    ) -> usize {
+        self.captures.push(SigmfCapture {
+            sample_start: self.samples.len() as u64,
+            frequency,
+            datetime,
+        });
+        self.samples.extend_from_slice(samples);
+        self.captures.len() - 1
+    }
+
+    /// Capture `count` I/Q samples at `frequency` in MHz from the scanner's source
+    pub fn capture(
+        &mut self,
+        scanner: &mut FrequencyScanner,
+        frequency: f32,
+        count: usize,
+    ) -> io::Result<usize> {
+        match (self.sample_rate, scanner.get_sample_rate()) {
+            (Some(ours), Some(theirs)) if ours != theirs => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "source sample rate {} Hz differs from the recording's {} Hz",
+                        theirs, ours
+                    ),
+                ))
+            }
+            (None, rate) => self.sample_rate = rate,
+            _ => {}
+        }
+
+        let datetime = SystemTime::now();
+        let mut buf = vec![[0.0; 2]; count];
+        let captured = scanner.capture_iq(frequency, &mut buf)?;
+        Ok(self.add_capture(frequency, datetime, &buf[..captured]))
+    }
+
+    pub fn annotate(&mut self, annotation: SigmfAnnotation) {
+        self.annotations.push(annotation);
+    }
+
+    /// Annotate each peak over the whole of capture `capture`, its edges at ± half the
+    /// estimated bandwidth
+    pub fn add_detections(&mut self, capture: usize, peaks: &[Peak]) {
+        let Some(segment) = self.captures.get(capture) else {
+            return;
+        };
+        let sample_start = segment.sample_start;
+        let sample_end = self
+            .captures
+            .get(capture + 1)
+            .map_or(self.samples.len() as u64, |next| next.sample_start);
+        let sample_count = (sample_end > sample_start).then(|| sample_end - sample_start);
+
+        for peak in peaks {
+            self.annotations.push(SigmfAnnotation {
+                sample_start,
+                sample_count,
+                freq_lower: peak.center_freq - peak.bandwidth_estimate / 2.0,
+                freq_upper: peak.center_freq + peak.bandwidth_estimate / 2.0,
+                label: "signal".to_string(),
+                comment: Some(format!(
+                    "{:.1} dB, {:.1} dB prominence",
+                    peak.peak_db, peak.prominence
+                )),
+            });
+        }
+    }
+
+    /// Samples as little-endian interleaved f32 I/Q
+    pub fn write_data<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for [i, q] in &self.samples {
+            writer.write_all(&i.to_le_bytes())?;
+            writer.write_all(&q.to_le_bytes())?;
+        }
+        writer.flush()
+    }
+
+    pub fn write_meta<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(self.meta_json().as_bytes())?;
+        writer.flush()
+    }
+
+    /// Write `<base>.sigmf-meta` and `<base>.sigmf-data`, returning both paths
+    pub fn save(&self, base: impl AsRef<Path>) -> io::Result<(PathBuf, PathBuf)> {
+        let base = base.as_ref();
+        let meta = base.with_extension("sigmf-meta");
+        let data = base.with_extension("sigmf-data");
+
+        self.write_data(BufWriter::new(File::create(&data)?))?;
+        self.write_meta(BufWriter::new(File::create(&meta)?))?;
+        Ok((meta, data))
+    }
+
+    fn meta_json(&self) -> String {
+        let mut global = vec![
+            ("core:datatype", json_string("cf32_le")),
+            ("core:version", json_string(SIGMF_VERSION)),
+            (
+                "core:recorder",
+                json_string(concat!("hexar ", env!("CARGO_PKG_VERSION"))),
+            ),
+        ];
+        if let Some(rate) = self.sample_rate {
+            global.push(("core:sample_rate", json_number(rate as f64)));
+        }
+        if let Some(description) = &self.description {
+            global.push(("core:description", json_string(description)));
+        }
+        if let Some(hardware) = &self.hardware {
+            global.push(("core:hw", json_string(hardware)));
+        }
+
+        let captures: Vec<String> = self
+            .captures
+            .iter()
+            .map(|capture| {
+                json_object(&[
+                    ("core:sample_start", capture.sample_start.to_string()),
+                    (
+                        "core:frequency",
+                        json_number(capture.frequency as f64 * 1e6),
+                    ),
+                    ("core:datetime", json_string(&iso8601(capture.datetime))),
+                ])
+            })
+            .collect();
+
+        // The spec wants annotations ordered by their first sample
+        let mut annotations: Vec<&SigmfAnnotation> = self.annotations.iter().collect();
+        annotations.sort_by_key(|annotation| annotation.sample_start);
+        let annotations: Vec<String> = annotations
+            .into_iter()
+            .map(|annotation| {
+                let mut fields = vec![("core:sample_start", annotation.sample_start.to_string())];
+                if let Some(count) = annotation.sample_count {
+                    fields.push(("core:sample_count", count.to_string()));
+                }
+                fields.push((
+                    "core:freq_lower_edge",
+                    json_number(annotation.freq_lower as f64 * 1e6),
+                ));
+                fields.push((
+                    "core:freq_upper_edge",
+                    json_number(annotation.freq_upper as f64 * 1e6),
+                ));
+                fields.push(("core:label", json_string(&annotation.label)));
+                if let Some(comment) = &annotation.comment {
+                    fields.push(("core:comment", json_string(comment)));
+                }
+                json_object(&fields)
+            })
+            .collect();
+
+        format!(
+            "{{\n  \"global\": {},\n  \"captures\": [{}],\n  \"annotations\": [{}]\n}}\n",
+            json_object(&global),
+            captures.join(", "),
+            annotations.join(", ")
+        )
+    }
+}
+
+fn json_object(fields: &[(&str, String)]) -> String {
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("{}: {}", json_string(key), value))
+        .collect();
+    format!("{{{}}}", fields.join(", "))
+}
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// JSON has no infinities or NaN, those become null
+fn json_number(value: f64) -> String {
+    if value.is_finite() {
+        format!("{}", value)
+    } else {
+        "null".to_string()
+    }
+}
+
+/// UTC timestamp as `YYYY-MM-DDTHH:MM:SS.sssZ`
+fn iso8601(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (days, seconds_of_day) = (seconds / 86400, seconds % 86400);
+
+    // Civil date from days since 1970-01-01, Howard Hinnant's algorithm
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day / 60 % 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::FrequencyRange;
+    use std::time::Duration;
+
+    #[test]
+    fn test_meta_and_data() {
+        let mut recording = SigmfRecording::new(Some(2_400_000.0));
+        recording.set_description("433 MHz \"test\"");
+        let datetime = UNIX_EPOCH + Duration::from_millis(1_700_000_000_250);
+        let capture = recording.add_capture(433.5, datetime, &[[1.0, -1.0], [0.5, 0.0]]);
+        recording.add_detections(
+            capture,
+            &[Peak {
+                center_freq: 433.5,
+                peak_db: -40.0,
+                bandwidth_estimate: 0.2,
+                prominence: 30.0,
+            }],
+        );
+
+        let mut data = Vec::new();
+        recording.write_data(&mut data).unwrap();
+        assert_eq!(data.len(), 16);
+        assert_eq!(data[4..8], (-1.0f32).to_le_bytes());
+
+        let meta = recording.meta_json();
+        assert!(meta.contains("\"core:datatype\": \"cf32_le\""));
+        assert!(meta.contains("\"core:sample_rate\": 2400000"));
+        assert!(meta.contains("\"core:description\": \"433 MHz \\\"test\\\"\""));
+        assert!(meta.contains("\"core:frequency\": 433500000"));
+        assert!(meta.contains("\"core:datetime\": \"2023-11-14T22:13:20.250Z\""));
+        assert!(meta.contains("\"core:sample_count\": 2"));
+        assert!(meta.contains("\"core:label\": \"signal\""));
+    }
+
+    #[test]
+    fn test_capture_from_scanner() {
+        let range = FrequencyRange {
+            start: 433.0,
+            end: 434.0,
+            step: 0.1,
+        };
+        let mut scanner = FrequencyScanner::new(range, -60.0);
+        let mut recording = SigmfRecording::new(None);
+
+        recording.capture(&mut scanner, 433.0, 256).unwrap();
+        recording.capture(&mut scanner, 915.0, 128).unwrap();
+
+        assert_eq!(recording.get_sample_rate(), scanner.get_sample_rate());
+        assert_eq!(recording.get_captures()[1].sample_start, 256);
+        assert!(SigmfRecording::new(Some(1.0))
+            .capture(&mut scanner, 433.0, 16)
+            .is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use std::f32::consts::TAU;
+use std::fmt::Debug;
+use std::io;
+
+/// Backend that measures received power for the [`FrequencyScanner`](crate::scanner::FrequencyScanner)
+pub trait SignalSource: Debug + Send {
+    /// Tune to `frequency` in MHz and return the measured power in dB
+    fn read_power(&mut self, frequency: f32) -> f32;
+
+    /// Complex sample rate in Hz of [`SignalSource::capture_iq`], `None` if raw samples aren't
+    /// available and only [`SignalSource::read_power`] works
+    fn sample_rate(&self) -> Option<f32> {
+        None
+    }
+
+    /// Tune to `frequency` in MHz and fill `buf` with I/Q samples scaled to full scale 1.0,
+    /// returning how many were captured
+    fn capture_iq(&mut self, frequency: f32, buf: &mut [[f32; 2]]) -> io::Result<usize> {
+        let _ = (frequency, buf);
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "signal source does not provide I/Q samples",
+        ))
+    }
+}
+
+const SIMULATED_SAMPLE_RATE: f32 = 2_400_000.0;
+const DEFAULT_SEED: u64 = 0x4845_5841_5253_494d;
+
+/// Synthetic transmitter for the [`SimulatedSource`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Emitter {
+    /// Center frequency in MHz
+    pub center: f32,
+    /// Power at the center in dB
+    pub power: f32,
+    /// 3 dB bandwidth in MHz
+    pub bandwidth: f32,
+    /// Fraction of readings in which the emitter is transmitting
+    pub duty_cycle: f32,
+}
+
+impl Emitter {
+    /// Power contributed at `frequency`, a Gaussian roll-off that is 3 dB down at the band edges
+    fn power_at(&self, frequency: f32) -> f32 {
+        let offset = (frequency - self.center) / self.bandwidth.max(f32::EPSILON);
+        self.power - 12.0 * offset * offset
+    }
+}
+
+/// Synthetic spectrum for development and tests without radio hardware: a noise floor with
+/// random ripple plus a list of emitters. All randomness comes from a seeded generator, so two
+/// sources with the same seed produce the same readings.
+#[derive(Debug, Clone)]
+pub struct SimulatedSource {
+    emitters: Vec<Emitter>,
+    noise_floor: f32,
+    /// Peak to peak ripple of the noise floor in dB
+    noise_ripple: f32,
+    rng: u64,
+}
+
+impl Default for SimulatedSource {
+    /// Emitters at 433 MHz, 915 MHz and 2.4 GHz over a -80 dB floor
+    fn default() -> Self {
+        Self::with_emitters(
+            DEFAULT_SEED,
+            vec![
+                Emitter {
+                    center: 433.0,
+                    power: -40.0,
+                    bandwidth: 2.0,
+                    duty_cycle: 1.0,
+                },
+                Emitter {
+                    center: 915.0,
+                    power: -45.0,
+                    bandwidth: 5.0,
+                    duty_cycle: 1.0,
+                },
+                Emitter {
+                    center: 2400.0,
+                    power: -50.0,
+                    bandwidth: 20.0,
+                    duty_cycle: 1.0,
+                },
+            ],
+        )
+    }
+}
+
+impl SimulatedSource {
+    /// The default emitters with a given seed
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: seed_state(seed),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_emitters(seed: u64, emitters: Vec<Emitter>) -> Self {
+        Self {
+            emitters,
+            noise_floor: -80.0,
+            noise_ripple: 4.0,
+            rng: seed_state(seed),
+        }
+    }
+
+    /// Noise floor in dB and its peak to peak ripple
+    pub fn set_noise(&mut self, floor: f32, ripple: f32) {
+        self.noise_floor = floor;
+        self.noise_ripple = ripple;
+    }
+
+    pub fn get_emitters(&self) -> &[Emitter] {
+        &self.emitters
+    }
+}
+
+impl SignalSource for SimulatedSource {
+    fn read_power(&mut self, frequency: f32) -> f32 {
+        let floor = self.noise_floor + self.noise_ripple * 0.5 * uniform_noise(&mut self.rng);
+
+        // Sum in the linear domain so overlapping emitters add up like real signals
+        let mut linear = 10f32.powf(floor / 10.0);
+        for emitter in &self.emitters {
+            if is_transmitting(emitter, &mut self.rng) {
+                linear += 10f32.powf(emitter.power_at(frequency) / 10.0);
+            }
+        }
+
+        10.0 * linear.log10()
+    }
+
+    fn sample_rate(&self) -> Option<f32> {
+        Some(SIMULATED_SAMPLE_RATE)
+    }
+
+    /// Emitters appear as tones at their center frequency
+    fn capture_iq(&mut self, frequency: f32, buf: &mut [[f32; 2]]) -> io::Result<usize> {
+        let noise_amplitude = 10f32.powf(self.noise_floor / 20.0);
+        let tones: Vec<(f32, f32)> = self
+            .emitters
+            .iter()
+            .filter(|emitter| is_transmitting(emitter, &mut self.rng))
+            .map(|emitter| ((emitter.center - frequency) * 1e6, 10f32.powf(emitter.power / 20.0)))
+            .filter(|(offset_hz, _)| offset_hz.abs() < SIMULATED_SAMPLE_RATE / 2.0)
+            .collect();
+
+        for (n, sample) in buf.iter_mut().enumerate() {
+            let t = n as f32 / SIMULATED_SAMPLE_RATE;
+            let mut i = noise_amplitude * uniform_noise(&mut self.rng);
+            let mut q = noise_amplitude * uniform_noise(&mut self.rng);
+
+            for &(offset_hz, amplitude) in &tones {
+                let phase = TAU * offset_hz * t;
+                i += amplitude * phase.cos();
+                q += amplitude * phase.sin();
+            }
+
+            *sample = [i, q];
+        }
+
+        Ok(buf.len())
+    }
+}
+
+fn is_transmitting(emitter: &Emitter, rng: &mut u64) -> bool {
+    emitter.duty_cycle >= 1.0 || (uniform_noise(rng) + 1.0) * 0.5 < emitter.duty_cycle
+}
+
+/// Xorshift state from a seed, the generator is stuck at zero so that one is avoided
+pub(crate) fn seed_state(seed: u64) -> u64 {
+    seed.max(1)
+}
+
+/// Xorshift noise in [-1, 1), good enough for a simulated noise floor
+pub(crate) fn uniform_noise(state: &mut u64) -> f32 {
+    *state ^= *state << 13;
+    *state ^= *state >> 7;
+    *state ^= *state << 17;
+    ((*state >> 40) as f32 / (1u64 << 24) as f32) * 2.0 - 1.0
+}
+
+/// One emitter at 868 MHz falling off by 20 dB per MHz over a -90 dB floor, for scanner tests
+#[cfg(test)]
+#[derive(Debug)]
+pub(crate) struct SinglePeakSource;
+
+#[cfg(test)]
+impl SignalSource for SinglePeakSource {
+    fn read_power(&mut self, frequency: f32) -> f32 {
+        (-30.0 - (frequency - 868.0).abs() * 20.0).max(-90.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulator_is_deterministic() {
+        let mut a = SimulatedSource::new(7);
+        let mut b = SimulatedSource::new(7);
+        let readings: Vec<f32> = (0..50).map(|i| a.read_power(400.0 + i as f32)).collect();
+        assert!((0..50).all(|i| b.read_power(400.0 + i as f32) == readings[i]));
+
+        assert!((readings[33] + 40.0).abs() < 0.1);
+        assert!(readings[10] < -77.0);
+    }
+
+    #[test]
+    fn test_duty_cycle() {
+        let emitter = Emitter {
+            center: 868.0,
+            power: -30.0,
+            bandwidth: 0.2,
+            duty_cycle: 0.25,
+        };
+        let mut source = SimulatedSource::with_emitters(1, vec![emitter]);
+
+        let active = (0..1000).filter(|_| source.read_power(868.0) > -40.0).count();
+        assert!((150..350).contains(&active));
+    }
+}
@@ -0,0 +1,260 @@
+use std::time::{Duration, Instant};
+
+use nalgebra::Vector2;
+
+use crate::config::RadarConfig;
+use crate::error::HexarResult;
+use crate::ld2412::EngineeringModeData;
+use crate::pose_calibration::{PoseCalibrator, PoseEstimate};
+use crate::serial_radar::{GateSensitivity, SensorFrame, SensorModel, SerialRadar};
+
+/// How often the modules are read while capturing
+const CAPTURE_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Energy kept between the most an empty room shows on a gate and the gate's threshold
+pub const SENSITIVITY_MARGIN: u8 = 10;
+
+/// LD2412 thresholds go up to this
+const MAX_THRESHOLD: u8 = 100;
+
+/// Frames of every module in `sensors` for `duration`, oldest first, blocking
+pub fn capture(sensors: &mut [SerialRadar], duration: Duration) -> HexarResult<Vec<SensorFrame>> {
+    let deadline = Instant::now() + duration;
+    let mut frames = Vec::new();
+    while Instant::now() < deadline {
+        for sensor in sensors.iter_mut() {
+            frames.extend(sensor.read_frame()?);
+        }
+        std::thread::sleep(CAPTURE_INTERVAL);
+    }
+    Ok(frames)
+}
+
+/// Record every LD2450 frame showing exactly one person as that person standing at `marker`
+/// in room coordinates. Returns the frames recorded.
+pub fn observe_at_marker(
+    calibrator: &mut PoseCalibrator,
+    frames: &[SensorFrame],
+    marker: Vector2<f32>,
+) -> usize {
+    let mut recorded = 0;
+    for frame in frames
+        .iter()
+        .filter(|frame| frame.model == SensorModel::Ld2450)
+    {
+        if let [person] = frame.measurements.as_slice() {
+            calibrator.add_correspondence(frame.antenna_id, person.position, marker);
+            recorded += 1;
+        }
+    }
+    recorded
+}
+
+/// Write the poses `calibrator` estimated into `config`, into a sensor port's own pose where
+/// it has one since that overrides the fusion entry. Returns the antennas updated.
+pub fn apply_poses(
+    calibrator: &PoseCalibrator,
+    config: &mut RadarConfig,
+) -> Vec<(u8, PoseEstimate)> {
+    let applied = calibrator.apply_to(&mut config.fusion);
+    for (antenna_id, estimate) in &applied {
+        let port_pose = config
+            .sensors
+            .iter_mut()
+            .filter(|sensor| sensor.antenna_id == *antenna_id)
+            .find_map(|sensor| sensor.pose.as_mut());
+        if let Some(pose) = port_pose {
+            pose.position = estimate.pose.position;
+            pose.heading = estimate.pose.heading;
+        }
+    }
+    applied
+}
+
+/// Whether the room is empty or someone walks and stands in it while gate energies are
+/// recorded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoomState {
+    Empty,
+    Occupied,
+}
+
+/// Highest energies seen on each gate
+#[derive(Debug, Clone, Copy, Default)]
+struct PeakEnergies {
+    motion: [u8; 14],
+    stationary: [u8; 14],
+    frames: usize,
+}
+
+impl PeakEnergies {
+    fn observe(&mut self, data: &EngineeringModeData) {
+        for (peak, &energy) in self.motion.iter_mut().zip(&data.moving_gates) {
+            *peak = (*peak).max(energy);
+        }
+        for (peak, &energy) in self.stationary.iter_mut().zip(&data.stationary_gates) {
+            *peak = (*peak).max(energy);
+        }
+        self.frames += 1;
+    }
+}
+
+/// Gate thresholds of one LD2412 from the energies it reports in engineering mode, first with
+/// the room empty and then with someone in it
+#[derive(Debug, Clone, Default)]
+pub struct SensitivityCalibrator {
+    empty: PeakEnergies,
+    occupied: PeakEnergies,
+}
+
+impl SensitivityCalibrator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn observe(&mut self, room: RoomState, data: &EngineeringModeData) {
+        match room {
+            RoomState::Empty => self.empty.observe(data),
+            RoomState::Occupied => self.occupied.observe(data),
+        }
+    }
+
+    /// Record the gate energies of an LD2412 frame, `false` for frames without them
+    pub fn observe_frame(&mut self, room: RoomState, frame: &SensorFrame) -> bool {
+        match frame.gate_energies() {
+            Some(data) => {
+                self.observe(room, &data);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn get_frame_count(&self, room: RoomState) -> usize {
+        match room {
+            RoomState::Empty => self.empty.frames,
+            RoomState::Occupied => self.occupied.frames,
+        }
+    }
+
+    /// Thresholds [`SENSITIVITY_MARGIN`] above the empty room on each gate, lowered to halfway
+    /// between the empty room and the person where that is closer. `None` until the empty room
+    /// was recorded.
+    pub fn sensitivity(&self) -> Option<GateSensitivity> {
+        if self.empty.frames == 0 {
+            return None;
+        }
+        let occupied = (self.occupied.frames > 0).then_some(&self.occupied);
+        let thresholds = |empty: &[u8; 14], person: Option<&[u8; 14]>| {
+            std::array::from_fn(|gate| threshold(empty[gate], person.map(|person| person[gate])))
+        };
+        Some(GateSensitivity {
+            motion: thresholds(&self.empty.motion, occupied.map(|peaks| &peaks.motion)),
+            stationary: thresholds(
+                &self.empty.stationary,
+                occupied.map(|peaks| &peaks.stationary),
+            ),
+        })
+    }
+
+    /// Gates on which the person never exceeded `sensitivity`, moving and stationary. A person
+    /// there goes unnoticed, or the gate is out of the room.
+    pub fn blind_gates(&self, sensitivity: &GateSensitivity) -> (Vec<usize>, Vec<usize>) {
+        let blind = |peaks: &[u8; 14], thresholds: &[u8; 14]| {
+            (0..14)
+                .filter(|&gate| peaks[gate] <= thresholds[gate])
+                .collect()
+        };
+        (
+            blind(&self.occupied.motion, &sensitivity.motion),
+            blind(&self.occupied.stationary, &sensitivity.stationary),
+        )
+    }
+}
+
+fn threshold(empty: u8, person: Option<u8>) -> u8 {
+    let threshold = empty.saturating_add(SENSITIVITY_MARGIN).min(MAX_THRESHOLD);
+    match person {
+        Some(person) if person > empty => threshold.min(empty + ((person - empty) / 2).max(1)),
+        _ => threshold,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fusion::SensorPose;
+    use crate::serial_radar::SensorMeasurement;
+
+    fn gates(energy: u8) -> EngineeringModeData {
+        EngineeringModeData {
+            b1: 0,
+            b2: 0,
+            moving_gates: [energy; 14],
+            stationary_gates: [energy; 14],
+            light: 0,
+        }
+    }
+
+    #[test]
+    fn test_sensitivity() {
+        let mut calibrator = SensitivityCalibrator::new();
+        assert!(calibrator.sensitivity().is_none());
+        calibrator.observe(RoomState::Empty, &gates(20));
+        let mut empty = gates(15);
+        empty.moving_gates[3] = 95;
+        calibrator.observe(RoomState::Empty, &empty);
+        assert_eq!(calibrator.sensitivity().unwrap().motion[3], 100);
+
+        let mut person = gates(60);
+        person.moving_gates[0] = 24;
+        person.stationary_gates[13] = 20;
+        calibrator.observe(RoomState::Occupied, &person);
+        let sensitivity = calibrator.sensitivity().unwrap();
+        assert_eq!(sensitivity.motion[..4], [22, 30, 30, 100]);
+        assert_eq!(sensitivity.stationary[13], 30);
+        assert_eq!(calibrator.blind_gates(&sensitivity), (vec![3], vec![13]));
+
+        // LD2412 frame in engineering mode: basic data, two bytes, 14 + 14 gates and the light
+        let mut payload = vec![0x01, 0xAA, 0x01, 0x64, 0x00, 0x30, 0x00, 0x00, 0x00, 0, 0];
+        payload.extend([40; 28]);
+        payload.extend([0x00, 0x55, 0x00]);
+        let frame = SensorFrame::parse(1, SensorModel::Ld2412, &payload);
+        assert!(calibrator.observe_frame(RoomState::Occupied, &frame));
+        assert_eq!(calibrator.get_frame_count(RoomState::Occupied), 2);
+    }
+
+    #[test]
+    fn test_marker_poses() {
+        let truth = SensorPose::new(Vector2::new(2.0, 0.5), 1.5, 0.3);
+        let markers = [
+            Vector2::new(1.0, 2.0),
+            Vector2::new(3.0, 3.0),
+            Vector2::new(2.0, 4.0),
+        ];
+        let mut calibrator = PoseCalibrator::new();
+        for marker in markers {
+            let mut frame = SensorFrame::parse(4, SensorModel::Ld2450, &[]);
+            let local = truth.to_local(marker);
+            frame.measurements = vec![SensorMeasurement {
+                position: local,
+                speed: Some(0.0),
+                moving: false,
+            }];
+            let mut crowded = frame.clone();
+            crowded.measurements.push(crowded.measurements[0]);
+            let frames = vec![frame; 4]
+                .into_iter()
+                .chain([crowded])
+                .collect::<Vec<_>>();
+            assert_eq!(observe_at_marker(&mut calibrator, &frames, marker), 4);
+        }
+
+        let mut config = RadarConfig::default();
+        let applied = apply_poses(&calibrator, &mut config);
+        assert_eq!(applied.len(), 1);
+        let pose = config.fusion.sensors[0].pose;
+        assert!((pose.position - truth.position).norm() < 1e-3);
+        assert!((pose.heading - truth.heading).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,185 @@
+use nalgebra::{Matrix2, Matrix2x4, Matrix4, Vector2, Vector4};
+
+use crate::track_history::{TrackHistory, TrackPoint};
+use crate::tracker::TrackerConfig;
+
+/// Variance of the initial velocity in (m/s)², about a walking speed either way
+const INITIAL_VELOCITY_VARIANCE: f32 = 4.0;
+
+/// Offline Rauch–Tung–Striebel smoother for recorded tracks. A constant velocity Kalman filter
+/// runs forward over the stored positions and a backward pass then corrects every state with
+/// the measurements that came after it, giving a trajectory no causal filter can match.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RtsSmoother {
+    /// White acceleration noise spectral density in m²/s³
+    process_noise: f32,
+    /// Variance of the recorded positions in m² along each axis
+    measurement_noise: f32,
+}
+
+impl Default for RtsSmoother {
+    fn default() -> Self {
+        Self::new(0.5, 0.05)
+    }
+}
+
+impl RtsSmoother {
+    pub fn new(process_noise: f32, measurement_noise: f32) -> Self {
+        Self {
+            process_noise: process_noise.max(1e-6),
+            measurement_noise: measurement_noise.max(1e-6),
+        }
+    }
+
+    /// Smoother with the noise levels the tracker filters with
+    pub fn from_config(config: &TrackerConfig) -> Self {
+        Self::new(config.process_noise, config.measurement_noise)
+    }
+
+    pub fn smooth(&self, history: &TrackHistory) -> Vec<TrackPoint> {
+        let points: Vec<TrackPoint> = history.iter().copied().collect();
+        self.smooth_points(&points)
+    }
+
+    /// Smoothed copy of `points`, oldest first, with positions and velocities replaced and
+    /// timestamps and heights kept
+    pub fn smooth_points(&self, points: &[TrackPoint]) -> Vec<TrackPoint> {
+        let Some(first) = points.first() else {
+            return Vec::new();
+        };
+        let observation = Matrix2x4::new(1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0);
+        let noise = Matrix2::identity() * self.measurement_noise;
+
+        // Forward pass, keeping the predicted and filtered estimates of every step
+        let mut state = Vector4::new(
+            first.position.x,
+            first.position.y,
+            first.velocity.x,
+            first.velocity.y,
+        );
+        let mut covariance = Matrix4::from_diagonal(&Vector4::new(
+            self.measurement_noise,
+            self.measurement_noise,
+            INITIAL_VELOCITY_VARIANCE,
+            INITIAL_VELOCITY_VARIANCE,
+        ));
+        let mut filtered = vec![(state, covariance)];
+        let mut predicted = vec![(state, covariance)];
+        let mut transitions = vec![Matrix4::identity()];
+
+        for window in points.windows(2) {
+            let dt = window[1]
+                .timestamp
+                .saturating_duration_since(window[0].timestamp)
+                .as_secs_f32();
+            let transition = transition(dt);
+            let predicted_state = transition * state;
+            let predicted_covariance =
+                transition * covariance * transition.transpose() + self.process_covariance(dt);
+
+            let innovation = window[1].position - observation * predicted_state;
+            let innovation_covariance =
+                observation * predicted_covariance * observation.transpose() + noise;
+            (state, covariance) = match innovation_covariance.try_inverse() {
+                Some(inverse) => {
+                    let gain = predicted_covariance * observation.transpose() * inverse;
+                    (
+                        predicted_state + gain * innovation,
+                        (Matrix4::identity() - gain * observation) * predicted_covariance,
+                    )
+                }
+                None => (predicted_state, predicted_covariance),
+            };
+
+            predicted.push((predicted_state, predicted_covariance));
+            filtered.push((state, covariance));
+            transitions.push(transition);
+        }
+
+        // Backward pass from the last estimate, which already saw every measurement
+        let mut smoothed = filtered.clone();
+        for k in (0..points.len() - 1).rev() {
+            let (predicted_state, predicted_covariance) = predicted[k + 1];
+            let Some(inverse) = predicted_covariance.try_inverse() else {
+                continue;
+            };
+            let (filtered_state, filtered_covariance) = filtered[k];
+            let gain = filtered_covariance * transitions[k + 1].transpose() * inverse;
+            let (next_state, next_covariance) = smoothed[k + 1];
+            smoothed[k] = (
+                filtered_state + gain * (next_state - predicted_state),
+                filtered_covariance
+                    + gain * (next_covariance - predicted_covariance) * gain.transpose(),
+            );
+        }
+
+        points
+            .iter()
+            .zip(smoothed)
+            .map(|(point, (state, _))| TrackPoint {
+                position: Vector2::new(state[0], state[1]),
+                velocity: Vector2::new(state[2], state[3]),
+                ..*point
+            })
+            .collect()
+    }
+
+    /// Discretised white acceleration noise over `dt`
+    fn process_covariance(&self, dt: f32) -> Matrix4<f32> {
+        let q = self.process_noise;
+        let (position, cross, velocity) = (q * dt.powi(3) / 3.0, q * dt.powi(2) / 2.0, q * dt);
+        Matrix4::new(
+            position, 0.0, cross, 0.0, //
+            0.0, position, 0.0, cross, //
+            cross, 0.0, velocity, 0.0, //
+            0.0, cross, 0.0, velocity,
+        )
+    }
+}
+
+fn transition(dt: f32) -> Matrix4<f32> {
+    let mut transition = Matrix4::identity();
+    transition[(0, 2)] = dt;
+    transition[(1, 3)] = dt;
+    transition
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_smoothing_reduces_noise() {
+        let start = Instant::now();
+        let truth = |t: f32| Vector2::new(0.8 * t, 1.0 + 0.3 * t);
+        let mut history = TrackHistory::default();
+        for step in 0..50 {
+            let t = step as f32 * 0.1;
+            // Deterministic jitter of ±10 cm
+            let jitter = if step % 2 == 0 { 0.1 } else { -0.1 };
+            history.push(TrackPoint {
+                timestamp: start + Duration::from_millis(step * 100),
+                position: truth(t) + Vector2::new(jitter, -jitter),
+                velocity: Vector2::zeros(),
+                height: Some(1.2),
+            });
+        }
+
+        let smoothed = RtsSmoother::default().smooth(&history);
+        assert_eq!(smoothed.len(), history.len());
+        assert_eq!(smoothed[10].height, Some(1.2));
+
+        let error = |points: &mut dyn Iterator<Item = &TrackPoint>| {
+            points
+                .enumerate()
+                .skip(5)
+                .take(40)
+                .map(|(step, p)| (p.position - truth(step as f32 * 0.1)).norm())
+                .sum::<f32>()
+        };
+        assert!(error(&mut smoothed.iter()) < error(&mut history.iter()) * 0.5);
+        assert!((smoothed[25].velocity - Vector2::new(0.8, 0.3)).norm() < 0.2);
+        assert!(RtsSmoother::default().smooth_points(&[]).is_empty());
+    }
+}
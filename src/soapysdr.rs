@@ -0,0 +1,321 @@
+use std::ffi::{CStr, CString};
+use std::io;
+use std::os::raw::{c_char, c_double, c_int, c_long, c_longlong, c_void};
+use std::ptr;
+
+use log::{debug, warn};
+
+use crate::signal_source::SignalSource;
+
+#[repr(C)]
+struct SoapySdrDevice {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct SoapySdrStream {
+    _private: [u8; 0],
+}
+
+const SOAPY_SDR_RX: c_int = 1;
+const READ_TIMEOUT_US: c_long = 100_000;
+
+// SoapySDR 0.8 C API
+#[link(name = "SoapySDR")]
+extern "C" {
+    fn SoapySDRDevice_lastError() -> *const c_char;
+    fn SoapySDRDevice_makeStrArgs(args: *const c_char) -> *mut SoapySdrDevice;
+    fn SoapySDRDevice_unmake(device: *mut SoapySdrDevice) -> c_int;
+    fn SoapySDRDevice_setSampleRate(
+        device: *mut SoapySdrDevice,
+        direction: c_int,
+        channel: usize,
+        rate: c_double,
+    ) -> c_int;
+    fn SoapySDRDevice_setFrequency(
+        device: *mut SoapySdrDevice,
+        direction: c_int,
+        channel: usize,
+        frequency: c_double,
+        args: *const c_void,
+    ) -> c_int;
+    fn SoapySDRDevice_setGainMode(
+        device: *mut SoapySdrDevice,
+        direction: c_int,
+        channel: usize,
+        automatic: bool,
+    ) -> c_int;
+    fn SoapySDRDevice_setGain(
+        device: *mut SoapySdrDevice,
+        direction: c_int,
+        channel: usize,
+        value: c_double,
+    ) -> c_int;
+    fn SoapySDRDevice_setupStream(
+        device: *mut SoapySdrDevice,
+        direction: c_int,
+        format: *const c_char,
+        channels: *const usize,
+        num_chans: usize,
+        args: *const c_void,
+    ) -> *mut SoapySdrStream;
+    fn SoapySDRDevice_closeStream(
+        device: *mut SoapySdrDevice,
+        stream: *mut SoapySdrStream,
+    ) -> c_int;
+    fn SoapySDRDevice_activateStream(
+        device: *mut SoapySdrDevice,
+        stream: *mut SoapySdrStream,
+        flags: c_int,
+        time_ns: c_longlong,
+        num_elems: usize,
+    ) -> c_int;
+    fn SoapySDRDevice_deactivateStream(
+        device: *mut SoapySdrDevice,
+        stream: *mut SoapySdrStream,
+        flags: c_int,
+        time_ns: c_longlong,
+    ) -> c_int;
+    fn SoapySDRDevice_readStream(
+        device: *mut SoapySdrDevice,
+        stream: *mut SoapySdrStream,
+        buffs: *const *mut c_void,
+        num_elems: usize,
+        flags: *mut c_int,
+        time_ns: *mut c_longlong,
+        timeout_us: c_long,
+    ) -> c_int;
+}
+
+#[derive(Debug, Clone)]
+pub struct SoapySdrConfig {
+    /// Device selection string, e.g. `driver=hackrf` or `driver=plutosdr,hostname=192.168.2.1`
+    pub args: String,
+    pub channel: usize,
+    pub sample_rate: f64,
+    /// Overall RX gain in dB, automatic gain when `None`
+    pub gain_db: Option<f64>,
+    /// Complex samples averaged per measurement
+    pub samples_per_reading: usize,
+}
+
+impl Default for SoapySdrConfig {
+    fn default() -> Self {
+        Self {
+            args: String::new(),
+            channel: 0,
+            sample_rate: 2_000_000.0,
+            gain_db: None,
+            samples_per_reading: 8192,
+        }
+    }
+}
+
+/// [`SignalSource`] for any device with a SoapySDR module (HackRF, LimeSDR, PlutoSDR, ...).
+/// Power is the mean I/Q magnitude over one block of CF32 samples in dBFS.
+#[derive(Debug)]
+pub struct SoapySdrSource {
+    device: *mut SoapySdrDevice,
+    stream: *mut SoapySdrStream,
+    channel: usize,
+    sample_rate: f64,
+    buffer: Vec<[f32; 2]>,
+}
+
+// The device and stream handles are only ever used through &mut self
+unsafe impl Send for SoapySdrSource {}
+
+fn last_error(what: &str) -> io::Error {
+    let message = unsafe {
+        let err = SoapySDRDevice_lastError();
+        if err.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(err).to_string_lossy().into_owned()
+        }
+    };
+
+    io::Error::other(format!("SoapySDR {} failed: {}", what, message))
+}
+
+fn check(ret: c_int, what: &str) -> io::Result<()> {
+    if ret != 0 {
+        Err(last_error(what))
+    } else {
+        Ok(())
+    }
+}
+
+impl SoapySdrSource {
+    pub fn open(config: &SoapySdrConfig) -> io::Result<Self> {
+        let args = CString::new(config.args.as_str())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let format = CString::new("CF32").unwrap_or_default();
+
+        let device = unsafe { SoapySDRDevice_makeStrArgs(args.as_ptr()) };
+        if device.is_null() {
+            return Err(last_error("make"));
+        }
+
+        // Built up front so the device is released by Drop on any error below
+        let mut source = Self {
+            device,
+            stream: ptr::null_mut(),
+            channel: config.channel,
+            sample_rate: config.sample_rate,
+            buffer: vec![[0.0; 2]; config.samples_per_reading.max(1)],
+        };
+
+        unsafe {
+            check(
+                SoapySDRDevice_setSampleRate(
+                    device,
+                    SOAPY_SDR_RX,
+                    config.channel,
+                    config.sample_rate,
+                ),
+                "setSampleRate",
+            )?;
+            check(
+                SoapySDRDevice_setGainMode(
+                    device,
+                    SOAPY_SDR_RX,
+                    config.channel,
+                    config.gain_db.is_none(),
+                ),
+                "setGainMode",
+            )?;
+            if let Some(gain) = config.gain_db {
+                check(
+                    SoapySDRDevice_setGain(device, SOAPY_SDR_RX, config.channel, gain),
+                    "setGain",
+                )?;
+            }
+
+            source.stream = SoapySDRDevice_setupStream(
+                device,
+                SOAPY_SDR_RX,
+                format.as_ptr(),
+                &config.channel,
+                1,
+                ptr::null(),
+            );
+            if source.stream.is_null() {
+                return Err(last_error("setupStream"));
+            }
+            check(
+                SoapySDRDevice_activateStream(device, source.stream, 0, 0, 0),
+                "activateStream",
+            )?;
+        }
+
+        debug!("Opened SoapySDR device '{}'", config.args);
+        Ok(source)
+    }
+
+    fn measure(&mut self, frequency: f32) -> io::Result<f32> {
+        self.tune(frequency)?;
+        let received = self.read_block()?;
+
+        let samples = self.buffer.get(..received).unwrap_or(&[]);
+        if samples.is_empty() {
+            return Ok(f32::NEG_INFINITY);
+        }
+
+        let power = samples.iter().map(|[i, q]| i * i + q * q).sum::<f32>() / samples.len() as f32;
+        Ok(10.0 * power.log10())
+    }
+
+    /// Retune and drop the first block, which still holds samples from the old frequency
+    fn tune(&mut self, frequency: f32) -> io::Result<()> {
+        unsafe {
+            check(
+                SoapySDRDevice_setFrequency(
+                    self.device,
+                    SOAPY_SDR_RX,
+                    self.channel,
+                    frequency as c_double * 1e6,
+                    ptr::null(),
+                ),
+                "setFrequency",
+            )?;
+        }
+
+        self.read_block()?;
+        Ok(())
+    }
+
+    fn read_block(&mut self) -> io::Result<usize> {
+        let buffs = [self.buffer.as_mut_ptr().cast::<c_void>()];
+        let mut flags = 0;
+        let mut time_ns = 0;
+
+        let ret = unsafe {
+            SoapySDRDevice_readStream(
+                self.device,
+                self.stream,
+                buffs.as_ptr(),
+                self.buffer.len(),
+                &mut flags,
+                &mut time_ns,
+                READ_TIMEOUT_US,
+            )
+        };
+
+        if ret < 0 {
+            Err(io::Error::other(format!(
+                "SoapySDR readStream failed ({})",
+                ret
+            )))
+        } else {
+            Ok(ret as usize)
+        }
+    }
+}
+
+impl SignalSource for SoapySdrSource {
+    fn read_power(&mut self, frequency: f32) -> f32 {
+        self.measure(frequency).unwrap_or_else(|e| {
+            warn!("SoapySDR reading at {:.2} MHz failed: {}", frequency, e);
+            f32::NEG_INFINITY
+        })
+    }
+
+    fn sample_rate(&self) -> Option<f32> {
+        Some(self.sample_rate as f32)
+    }
+
+    fn capture_iq(&mut self, frequency: f32, buf: &mut [[f32; 2]]) -> io::Result<usize> {
+        self.tune(frequency)?;
+
+        let mut captured = 0;
+        while captured < buf.len() {
+            let received = self.read_block()?;
+            let (Some(dst), Some(src)) = (buf.get_mut(captured..), self.buffer.get(..received))
+            else {
+                break;
+            };
+
+            let count = dst.len().min(src.len());
+            if count == 0 {
+                break;
+            }
+            dst[..count].copy_from_slice(&src[..count]);
+            captured += count;
+        }
+
+        Ok(captured)
+    }
+}
+
+impl Drop for SoapySdrSource {
+    fn drop(&mut self) {
+        unsafe {
+            if !self.stream.is_null() {
+                SoapySDRDevice_deactivateStream(self.device, self.stream, 0, 0);
+                SoapySDRDevice_closeStream(self.device, self.stream);
+            }
+            SoapySDRDevice_unmake(self.device);
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use std::f32::consts::TAU;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::time::Instant;
+
+use log::debug;
+use rustfft::num_complex::Complex32;
+use rustfft::{Fft, FftPlanner};
+
+use crate::peaks::{self, Peak};
+use crate::scanner::FrequencyRange;
+use crate::signal_source::SignalSource;
+
+/// Fraction of each capture kept, the band edges are attenuated by the SDR's anti-alias filter
+const USABLE_BANDWIDTH: f32 = 0.75;
+
+/// Power spectrum over a frequency range on a uniform bin grid
+#[derive(Debug, Clone)]
+pub struct Spectrum {
+    /// Center frequency of the first bin in MHz
+    pub start: f32,
+    /// Bin spacing in MHz
+    pub bin_width: f32,
+    /// Power per bin in dBFS
+    pub power: Vec<f32>,
+    pub timestamp: Instant,
+}
+
+impl Spectrum {
+    pub fn frequency(&self, bin: usize) -> f32 {
+        self.start + bin as f32 * self.bin_width
+    }
+
+    /// Peaks above `threshold` with at least `min_prominence` dB, strongest first
+    pub fn find_peaks(&self, threshold: f32, min_prominence: f32) -> Vec<Peak> {
+        peaks::find_peaks_by(&self.power, |bin| self.frequency(bin), threshold, min_prominence)
+    }
+}
+
+/// Wideband sweep that FFTs I/Q captures, so one retune measures most of the SDR's sample
+/// rate instead of a single frequency
+pub struct FftSweep {
+    fft: Arc<dyn Fft<f32>>,
+    window: Vec<f32>,
+    window_gain: f32,
+    iq: Vec<[f32; 2]>,
+    bins: Vec<Complex32>,
+}
+
+impl fmt::Debug for FftSweep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FftSweep")
+            .field("fft_size", &self.window.len())
+            .finish()
+    }
+}
+
+impl FftSweep {
+    pub fn new(fft_size: usize) -> Self {
+        let fft_size = fft_size.max(16);
+        let fft = FftPlanner::new().plan_fft_forward(fft_size);
+
+        // Hann window
+        let window: Vec<f32> = (0..fft_size)
+            .map(|n| 0.5 - 0.5 * (TAU * n as f32 / (fft_size - 1) as f32).cos())
+            .collect();
+        let window_gain = window.iter().sum();
+
+        Self {
+            fft,
+            window,
+            window_gain,
+            iq: vec![[0.0; 2]; fft_size],
+            bins: vec![Complex32::default(); fft_size],
+        }
+    }
+
+    pub fn get_fft_size(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn sweep(
+        &mut self,
+        source: &mut dyn SignalSource,
+        range: &FrequencyRange,
+    ) -> io::Result<Spectrum> {
+        let sample_rate = source.sample_rate().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "FFT sweep needs a signal source with I/Q capture",
+            )
+        })?;
+
+        let fft_size = self.get_fft_size();
+        let bin_width = sample_rate / fft_size as f32 / 1e6;
+        let usable = ((fft_size as f32 * USABLE_BANDWIDTH) as usize) & !1;
+        let first_usable = (fft_size - usable) / 2;
+        let total_bins = ((range.end - range.start) / bin_width).floor() as usize + 1;
+
+        let mut power = Vec::with_capacity(total_bins);
+        let mut center = range.start + (fft_size / 2 - first_usable) as f32 * bin_width;
+
+        while power.len() < total_bins {
+            let captured = source.capture_iq(center, &mut self.iq)?;
+            if captured < fft_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("short I/Q capture: {} of {} samples", captured, fft_size),
+                ));
+            }
+
+            self.transform();
+
+            // fftshift: bin k of the shifted spectrum is at center + (k - N/2) * bin_width
+            for k in first_usable..first_usable + usable {
+                if power.len() == total_bins {
+                    break;
+                }
+                let bin = self.bins[(k + fft_size / 2) % fft_size];
+                let level = bin.norm_sqr() / (self.window_gain * self.window_gain);
+                power.push(10.0 * (level + 1e-20).log10());
+            }
+
+            center += usable as f32 * bin_width;
+        }
+
+        debug!(
+            "FFT sweep {:.1}-{:.1} MHz: {} bins of {:.1} kHz",
+            range.start,
+            range.end,
+            power.len(),
+            bin_width * 1e3
+        );
+
+        Ok(Spectrum {
+            start: range.start,
+            bin_width,
+            power,
+            timestamp: Instant::now(),
+        })
+    }
+
+    fn transform(&mut self) {
+        for ((bin, [i, q]), w) in self.bins.iter_mut().zip(&self.iq).zip(&self.window) {
+            *bin = Complex32::new(i * w, q * w);
+        }
+        self.fft.process(&mut self.bins);
+
+        // Suppress the DC spike most direct conversion receivers have
+        let n = self.bins.len();
+        self.bins[0] = (self.bins[1] + self.bins[n - 1]) * 0.5;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signal_source::SimulatedSource;
+
+    #[test]
+    fn test_sweep_finds_simulated_emitter() {
+        let range = FrequencyRange {
+            start: 425.0,
+            end: 440.0,
+            step: 1.0,
+        };
+        let mut sweep = FftSweep::new(1024);
+        let spectrum = sweep.sweep(&mut SimulatedSource::default(), &range).unwrap();
+
+        assert_eq!(
+            spectrum.power.len(),
+            ((15.0 / spectrum.bin_width).floor() as usize) + 1
+        );
+
+        let peaks = spectrum.find_peaks(-60.0, 3.0);
+        assert_eq!(peaks.len(), 1);
+        assert!((peaks[0].center_freq - 433.0).abs() < 2.0 * spectrum.bin_width);
+        assert!((peaks[0].peak_db + 40.0).abs() < 2.0);
+    }
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use sd_notify::NotifyState;
+use tracing::{debug, info};
+
+/// Readiness, status and watchdog heartbeats for systemd. Does nothing unless the process was
+/// started by a `Type=notify` unit.
+#[derive(Debug)]
+pub struct Notifier {
+    /// `WatchdogSec` of the unit, when the watchdog is on
+    watchdog: Option<Duration>,
+    last_heartbeat: Option<Instant>,
+    status: String,
+}
+
+impl Notifier {
+    /// Watchdog settings from the environment systemd started the process with
+    pub fn from_env() -> Self {
+        let mut usec = 0;
+        let watchdog = sd_notify::watchdog_enabled(false, &mut usec)
+            .then(|| Duration::from_micros(usec))
+            .filter(|timeout| !timeout.is_zero());
+        if let Some(timeout) = watchdog {
+            info!("systemd watchdog expects a heartbeat every {:?}", timeout);
+        }
+        Self::with_watchdog(watchdog)
+    }
+
+    fn with_watchdog(watchdog: Option<Duration>) -> Self {
+        Self {
+            watchdog,
+            last_heartbeat: None,
+            status: String::new(),
+        }
+    }
+
+    /// How often heartbeats are sent, a quarter of the watchdog timeout. `None` without a
+    /// watchdog.
+    pub fn get_heartbeat_interval(&self) -> Option<Duration> {
+        self.watchdog.map(|timeout| timeout / 4)
+    }
+
+    /// Tell systemd the instance finished starting
+    pub fn ready(&mut self, status: &str) {
+        self.status = status.to_string();
+        send(&[NotifyState::Ready, NotifyState::Status(status)]);
+    }
+
+    /// Shown by `systemctl status`, sent only when it changed
+    pub fn set_status(&mut self, status: &str) {
+        if self.status != status {
+            self.status = status.to_string();
+            send(&[NotifyState::Status(status)]);
+        }
+    }
+
+    /// Prove the main loop is alive, at most once per heartbeat interval. Returns whether a
+    /// heartbeat was sent.
+    pub fn heartbeat(&mut self, now: Instant) -> bool {
+        let Some(interval) = self.get_heartbeat_interval() else {
+            return false;
+        };
+        if self
+            .last_heartbeat
+            .is_some_and(|last| now.saturating_duration_since(last) < interval)
+        {
+            return false;
+        }
+        self.last_heartbeat = Some(now);
+        send(&[NotifyState::Watchdog]);
+        true
+    }
+
+    /// Tell systemd the instance is shutting down
+    pub fn stopping(&mut self) {
+        send(&[NotifyState::Stopping, NotifyState::Status("Shutting down")]);
+    }
+}
+
+fn send(states: &[NotifyState]) {
+    // Failing to notify doesn't stop the radar, systemd acts on the missing notification
+    if let Err(e) = sd_notify::notify(false, states) {
+        debug!("Failed to notify systemd: {}", e);
+    }
+}
+
+/// A `Type=notify` service running `hexar start` in the foreground, restarted when it fails
+/// or its scan loop stops sending heartbeats. Written out by its `Display`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitFile {
+    pub executable: PathBuf,
+    /// Passed as `--profile`, for one unit per instance on the host
+    pub profile: Option<String>,
+    /// Passed as `--config`, the instance loads `config.toml` in its working directory without
+    pub config: Option<PathBuf>,
+    /// Where the PID file, control socket and logs end up unless configured absolute
+    pub working_directory: PathBuf,
+    pub user: Option<String>,
+    /// `WatchdogSec`, zero turns the watchdog off
+    pub watchdog: Duration,
+    /// Time to pass the safety checks and open the radar
+    pub start_timeout: Duration,
+    /// Time to shut down after SIGTERM
+    pub stop_timeout: Duration,
+}
+
+impl fmt::Display for UnitFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        match &self.profile {
+            Some(profile) => writeln!(f, "Description=Hexar radar controller ({})", profile)?,
+            None => writeln!(f, "Description=Hexar radar controller")?,
+        }
+        writeln!(f, "Wants=network-online.target")?;
+        writeln!(f, "After=network-online.target")?;
+        writeln!(f)?;
+        writeln!(f, "[Service]")?;
+        writeln!(f, "Type=notify")?;
+        write!(f, "ExecStart={}", self.executable.display())?;
+        if let Some(profile) = &self.profile {
+            write!(f, " --profile {}", profile)?;
+        }
+        if let Some(config) = &self.config {
+            write!(f, " --config {}", config.display())?;
+        }
+        writeln!(f, " start")?;
+        writeln!(f, "WorkingDirectory={}", self.working_directory.display())?;
+        if let Some(user) = &self.user {
+            writeln!(f, "User={}", user)?;
+        }
+        writeln!(f, "WatchdogSec={}", self.watchdog.as_secs())?;
+        writeln!(f, "TimeoutStartSec={}", self.start_timeout.as_secs())?;
+        writeln!(f, "TimeoutStopSec={}", self.stop_timeout.as_secs())?;
+        writeln!(f, "Restart=on-failure")?;
+        writeln!(f, "RestartSec=5")?;
+        writeln!(f)?;
+        writeln!(f, "[Install]")?;
+        writeln!(f, "WantedBy=multi-user.target")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_interval() {
+        let mut notifier = Notifier::with_watchdog(Some(Duration::from_secs(20)));
+        let start = Instant::now();
+        assert!(notifier.heartbeat(start));
+        assert!(!notifier.heartbeat(start + Duration::from_secs(4)));
+        assert!(notifier.heartbeat(start + Duration::from_secs(5)));
+
+        let mut notifier = Notifier::with_watchdog(None);
+        assert_eq!(notifier.get_heartbeat_interval(), None);
+        assert!(!notifier.heartbeat(start));
+    }
+
+    #[test]
+    fn test_unit_file() {
+        let unit = UnitFile {
+            executable: PathBuf::from("/usr/local/bin/hexar"),
+            profile: Some("roomA".to_string()),
+            config: Some(PathBuf::from("/etc/hexar/config.toml")),
+            working_directory: PathBuf::from("/var/lib/hexar"),
+            user: None,
+            watchdog: Duration::from_secs(30),
+            start_timeout: Duration::from_secs(30),
+            stop_timeout: Duration::from_secs(10),
+        }
+        .to_string();
+        assert!(unit.contains("Type=notify\n"));
+        assert!(
+            unit.contains("ExecStart=/usr/local/bin/hexar --profile roomA --config /etc/hexar/config.toml start\n")
+        );
+        assert!(unit.contains("WatchdogSec=30\n"));
+        assert!(!unit.contains("User="));
+    }
+}
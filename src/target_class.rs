@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use crate::ld2412::EngineeringModeData;
+use crate::track_history::TrackHistory;
+use crate::tracker::TrackedTarget;
+
+/// Range covered by one LD2412 distance gate at the default resolution, in metres
+pub const LD2412_GATE_LENGTH_M: f32 = 0.75;
+
+/// History points needed before a track is classified
+const MIN_POINTS: usize = 10;
+/// 90th percentile speed in m/s above which a track counts as moving
+const MOVING_SPEED: f32 = 0.2;
+/// Distance in metres a track must have covered over its history to count as having moved
+const MOVED_DISTANCE: f32 = 0.5;
+/// Stationary gate energy, as a fraction of the full scale, left by a breathing person
+const MICRO_MOTION_THRESHOLD: f32 = 0.2;
+/// Weight of a new micro-motion reading in its running average
+const MICRO_MOTION_SMOOTHING: f32 = 0.2;
+/// Largest reflection size in metres of a pet
+const PET_MAX_SIZE: f32 = 0.4;
+/// Highest a pet is tracked above the floor, in metres
+const PET_MAX_HEIGHT: f32 = 0.7;
+/// Standard deviation of the size, relative to its mean, above which size is not trusted
+const MAX_SIZE_SPREAD: f32 = 0.5;
+
+/// What a track most likely is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TargetClass {
+    /// Not observed for long enough yet
+    #[default]
+    Unknown,
+    Human,
+    /// Small and close to the floor
+    Pet,
+    /// Never moved and shows no breathing, like furniture or a curtain
+    StaticClutter,
+}
+
+/// Sensor readings gathered for one track besides its positions
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TrackFeatures {
+    size_count: u32,
+    size_mean: f32,
+    /// Sum of squared deviations from the mean, Welford's running variance
+    size_m2: f32,
+    /// Running average of the stationary gate energy at the track's range, 0 to 1
+    micro_motion: Option<f32>,
+}
+
+impl TrackFeatures {
+    /// Mean reflection size in metres, `None` without readings or when they disagree too much
+    /// to be trusted
+    pub fn get_size(&self) -> Option<f32> {
+        if self.size_count == 0 {
+            return None;
+        }
+        let spread = (self.size_m2 / self.size_count as f32).sqrt();
+        (spread <= self.size_mean * MAX_SIZE_SPREAD).then_some(self.size_mean)
+    }
+
+    pub fn get_micro_motion(&self) -> Option<f32> {
+        self.micro_motion
+    }
+
+    fn add_size(&mut self, size: f32) {
+        self.size_count += 1;
+        let delta = size - self.size_mean;
+        self.size_mean += delta / self.size_count as f32;
+        self.size_m2 += delta * (size - self.size_mean);
+    }
+
+    fn add_micro_motion(&mut self, energy: f32) {
+        self.micro_motion = Some(match self.micro_motion {
+            Some(average) => average + (energy - average) * MICRO_MOTION_SMOOTHING,
+            None => energy,
+        });
+    }
+}
+
+/// Labels tracks from their speed distribution, their reflection size and its consistency,
+/// and micro-motion energy at their range
+#[derive(Debug, Clone, Default)]
+pub struct TargetClassifier {
+    features: HashMap<u32, TrackFeatures>,
+}
+
+impl TargetClassifier {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the size of a target's reflection, e.g. an LD2450 distance resolution, in metres
+    pub fn observe_size(&mut self, target_id: u32, size: f32) {
+        if size.is_finite() && size > 0.0 {
+            self.features.entry(target_id).or_default().add_size(size);
+        }
+    }
+
+    /// Record micro-motion energy at the target's range as a fraction of the full scale
+    pub fn observe_micro_motion(&mut self, target_id: u32, energy: f32) {
+        if energy.is_finite() {
+            self.features
+                .entry(target_id)
+                .or_default()
+                .add_micro_motion(energy.clamp(0.0, 1.0));
+        }
+    }
+
+    pub fn get_features(&self, target_id: u32) -> Option<&TrackFeatures> {
+        self.features.get(&target_id)
+    }
+
+    /// Drop the readings of a target that is no longer tracked
+    pub fn forget(&mut self, target_id: u32) {
+        self.features.remove(&target_id);
+    }
+
+    pub fn clear(&mut self) {
+        self.features.clear();
+    }
+
+    pub fn classify(&self, target: &TrackedTarget, history: &TrackHistory) -> TargetClass {
+        if history.len() < MIN_POINTS {
+            return TargetClass::Unknown;
+        }
+        let features = self.features.get(&target.id).copied().unwrap_or_default();
+
+        let mut speeds: Vec<f32> = history.iter().map(|point| point.velocity.norm()).collect();
+        speeds.sort_by(f32::total_cmp);
+        let fast = speeds[speeds.len() * 9 / 10];
+        let moving = fast > MOVING_SPEED;
+
+        if !moving {
+            return match features.micro_motion {
+                Some(energy) if energy >= MICRO_MOTION_THRESHOLD => TargetClass::Human,
+                Some(_) => TargetClass::StaticClutter,
+                // A person walked in at some point, clutter was always there
+                None if history.path_length() < MOVED_DISTANCE => TargetClass::StaticClutter,
+                None => TargetClass::Human,
+            };
+        }
+
+        let small = features.get_size().is_some_and(|size| size < PET_MAX_SIZE);
+        let low = target
+            .vertical
+            .is_some_and(|vertical| vertical.height < PET_MAX_HEIGHT);
+        if small || low {
+            TargetClass::Pet
+        } else {
+            TargetClass::Human
+        }
+    }
+}
+
+/// Stationary energy of the LD2412 gate covering `distance_m`, as a fraction of the full scale
+pub fn gate_micro_motion(data: &EngineeringModeData, distance_m: f32) -> Option<f32> {
+    if distance_m.is_nan() || distance_m < 0.0 {
+        return None;
+    }
+    let gate = (distance_m / LD2412_GATE_LENGTH_M) as usize;
+    data.stationary_gates
+        .get(gate)
+        .map(|&energy| f32::from(energy.min(100)) / 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track_history::TrackPoint;
+    use nalgebra::Vector2;
+    use std::time::{Duration, Instant};
+
+    fn history(speed: f32) -> TrackHistory {
+        let start = Instant::now();
+        let mut history = TrackHistory::default();
+        for step in 0..20 {
+            history.push(TrackPoint {
+                timestamp: start + Duration::from_millis(step * 100),
+                position: Vector2::new(speed * step as f32 * 0.1, 0.0),
+                velocity: Vector2::new(speed, 0.0),
+                height: None,
+            });
+        }
+        history
+    }
+
+    #[test]
+    fn test_classification() {
+        let mut classifier = TargetClassifier::new();
+        let target = TrackedTarget::new(1, 0, Vector2::new(0.0, 0.0));
+        assert_eq!(
+            classifier.classify(&target, &TrackHistory::default()),
+            TargetClass::Unknown
+        );
+        assert_eq!(
+            classifier.classify(&target, &history(1.0)),
+            TargetClass::Human
+        );
+        assert_eq!(
+            classifier.classify(&target, &history(0.0)),
+            TargetClass::StaticClutter
+        );
+
+        // A still person keeps breathing
+        classifier.observe_micro_motion(1, 0.5);
+        assert_eq!(
+            classifier.classify(&target, &history(0.0)),
+            TargetClass::Human
+        );
+
+        for size in [0.25, 0.3, 0.28] {
+            classifier.observe_size(1, size);
+        }
+        assert_eq!(
+            classifier.classify(&target, &history(1.5)),
+            TargetClass::Pet
+        );
+        // Wildly varying sizes are not trusted
+        classifier.observe_size(1, 3.0);
+        assert_eq!(
+            classifier.classify(&target, &history(1.5)),
+            TargetClass::Human
+        );
+    }
+
+    #[test]
+    fn test_gate_micro_motion() {
+        let mut data = EngineeringModeData {
+            b1: 0,
+            b2: 0,
+            moving_gates: [0; 14],
+            stationary_gates: [0; 14],
+            light: 0,
+        };
+        data.stationary_gates[2] = 40;
+        assert_eq!(gate_micro_motion(&data, 1.6), Some(0.4));
+        assert_eq!(gate_micro_motion(&data, 20.0), None);
+        assert_eq!(gate_micro_motion(&data, f32::NAN), None);
+    }
+}
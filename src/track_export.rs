@@ -0,0 +1,277 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use nalgebra::Vector2;
+
+use crate::target_class::TargetClass;
+use crate::track_history::TrackPoint;
+use crate::track_store::Track;
+use crate::tracker::FallEvent;
+
+/// Track that ended, as written by a [`TrackExporter`]
+#[derive(Debug, Clone)]
+struct ExportedTrack {
+    id: u32,
+    antenna_id: u8,
+    class: TargetClass,
+    points: Vec<TrackPoint>,
+}
+
+/// Collects completed tracks and the fall events of their targets, and writes them to CSV or
+/// GeoJSON for offline analysis. Timestamps are written as seconds since the Unix epoch.
+#[derive(Debug, Clone)]
+pub struct TrackExporter {
+    /// The same moment on the monotonic and on the wall clock, to convert timestamps
+    anchor: (Instant, SystemTime),
+    tracks: Vec<ExportedTrack>,
+    fall_events: HashMap<u32, Vec<FallEvent>>,
+}
+
+impl Default for TrackExporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TrackExporter {
+    pub fn new() -> Self {
+        Self::with_anchor(Instant::now(), SystemTime::now())
+    }
+
+    /// Exporter converting `instant` to `wall_time`, for clocks other than the system one
+    pub fn with_anchor(instant: Instant, wall_time: SystemTime) -> Self {
+        Self {
+            anchor: (instant, wall_time),
+            tracks: Vec::new(),
+            fall_events: HashMap::new(),
+        }
+    }
+
+    /// Add a track, usually one returned by
+    /// [`MultiTargetTracker::take_completed_tracks`](crate::tracker::MultiTargetTracker::take_completed_tracks)
+    pub fn add_track(&mut self, track: &Track) {
+        self.tracks.push(ExportedTrack {
+            id: track.target.id,
+            antenna_id: track.target.antenna_id,
+            class: track.target.class,
+            points: track.history.iter().copied().collect(),
+        });
+    }
+
+    pub fn add_tracks<'a, I>(&mut self, tracks: I)
+    where
+        I: IntoIterator<Item = &'a Track>,
+    {
+        for track in tracks {
+            self.add_track(track);
+        }
+    }
+
+    pub fn record_fall_event(&mut self, event: &FallEvent) {
+        self.fall_events
+            .entry(event_target(event))
+            .or_default()
+            .push(event.clone());
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.tracks.clear();
+        self.fall_events.clear();
+    }
+
+    /// One row per track point, followed by one row per fall event of the track:
+    /// `track_id,antenna_id,class,timestamp,x,y,height,event`
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "track_id,antenna_id,class,timestamp,x,y,height,event"
+        )?;
+        for track in &self.tracks {
+            let class = class_name(track.class);
+            for point in &track.points {
+                writeln!(
+                    writer,
+                    "{},{},{},{:.3},{:.3},{:.3},{},",
+                    track.id,
+                    track.antenna_id,
+                    class,
+                    self.unix_seconds(point.timestamp),
+                    point.position.x,
+                    point.position.y,
+                    point
+                        .height
+                        .map(|h| format!("{:.3}", h))
+                        .unwrap_or_default(),
+                )?;
+            }
+            for event in self.events_of(track.id) {
+                let (name, at, position) = describe_event(event);
+                let (x, y) = position
+                    .map(|p| (format!("{:.3}", p.x), format!("{:.3}", p.y)))
+                    .unwrap_or_default();
+                writeln!(
+                    writer,
+                    "{},{},{},{:.3},{},{},,{}",
+                    track.id,
+                    track.antenna_id,
+                    class,
+                    self.unix_seconds(at),
+                    x,
+                    y,
+                    name,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A FeatureCollection with one LineString feature per track. Coordinates are room
+    /// coordinates in metres rather than longitude and latitude, so GIS tools should treat the
+    /// file as a local projected frame.
+    pub fn write_geojson<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "{{\"type\":\"FeatureCollection\",\"features\":[")?;
+        for (index, track) in self.tracks.iter().enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+            let coordinates: Vec<String> = track
+                .points
+                .iter()
+                .map(|p| format!("[{:.3},{:.3}]", p.position.x, p.position.y))
+                .collect();
+            let timestamps: Vec<String> = track
+                .points
+                .iter()
+                .map(|p| format!("{:.3}", self.unix_seconds(p.timestamp)))
+                .collect();
+            let falls: Vec<String> = self
+                .events_of(track.id)
+                .map(|event| {
+                    let (name, at, _) = describe_event(event);
+                    format!(
+                        "{{\"event\":\"{}\",\"timestamp\":{:.3}}}",
+                        name,
+                        self.unix_seconds(at)
+                    )
+                })
+                .collect();
+
+            write!(
+                writer,
+                "{{\"type\":\"Feature\",\"geometry\":{{\"type\":\"LineString\",\"coordinates\":[{}]}},\
+                 \"properties\":{{\"id\":{},\"antenna_id\":{},\"class\":\"{}\",\"timestamps\":[{}],\"fall_events\":[{}]}}}}",
+                coordinates.join(","),
+                track.id,
+                track.antenna_id,
+                class_name(track.class),
+                timestamps.join(","),
+                falls.join(","),
+            )?;
+        }
+        writeln!(writer, "]}}")
+    }
+
+    fn events_of(&self, target_id: u32) -> impl Iterator<Item = &FallEvent> {
+        self.fall_events.get(&target_id).into_iter().flatten()
+    }
+
+    fn unix_seconds(&self, instant: Instant) -> f64 {
+        let (anchor, wall_time) = self.anchor;
+        let wall_time = if instant >= anchor {
+            wall_time + (instant - anchor)
+        } else {
+            wall_time - (anchor - instant)
+        };
+        wall_time
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |since| since.as_secs_f64())
+    }
+}
+
+fn class_name(class: TargetClass) -> &'static str {
+    match class {
+        TargetClass::Unknown => "unknown",
+        TargetClass::Human => "human",
+        TargetClass::Pet => "pet",
+        TargetClass::StaticClutter => "static_clutter",
+    }
+}
+
+fn event_target(event: &FallEvent) -> u32 {
+    match *event {
+        FallEvent::FallSuspected { target_id, .. }
+        | FallEvent::FallConfirmed { target_id, .. }
+        | FallEvent::FallCleared { target_id, .. } => target_id,
+    }
+}
+
+fn describe_event(event: &FallEvent) -> (&'static str, Instant, Option<Vector2<f32>>) {
+    match *event {
+        FallEvent::FallSuspected { position, at, .. } => ("fall_suspected", at, Some(position)),
+        FallEvent::FallConfirmed { position, at, .. } => ("fall_confirmed", at, Some(position)),
+        FallEvent::FallCleared { at, .. } => ("fall_cleared", at, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::track_history::TrackHistory;
+    use crate::tracker::{TrackFilter, TrackedTarget};
+    use std::time::Duration;
+
+    #[test]
+    fn test_csv_and_geojson() {
+        let start = Instant::now();
+        let mut exporter =
+            TrackExporter::with_anchor(start, UNIX_EPOCH + Duration::from_secs(1_000));
+
+        let mut history = TrackHistory::default();
+        for step in 0..3 {
+            history.push(TrackPoint {
+                timestamp: start + Duration::from_millis(step * 500),
+                position: Vector2::new(step as f32, 1.0),
+                velocity: Vector2::new(2.0, 0.0),
+                height: None,
+            });
+        }
+        let mut target = TrackedTarget::new(7, 1, Vector2::new(2.0, 1.0));
+        target.class = TargetClass::Human;
+        exporter.add_track(&Track::new(
+            target,
+            TrackFilter::new(Default::default(), Vector2::zeros()),
+            history,
+        ));
+        exporter.record_fall_event(&FallEvent::FallSuspected {
+            target_id: 7,
+            probability: 0.9,
+            position: Vector2::new(2.0, 1.0),
+            landing: None,
+            at: start + Duration::from_secs(1),
+        });
+
+        let mut csv = Vec::new();
+        exporter.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(lines[2], "7,1,human,1000.500,1.000,1.000,,");
+        assert_eq!(lines[4], "7,1,human,1001.000,2.000,1.000,,fall_suspected");
+
+        let mut geojson = Vec::new();
+        exporter.write_geojson(&mut geojson).unwrap();
+        let geojson = String::from_utf8(geojson).unwrap();
+        assert!(geojson.contains("\"coordinates\":[[0.000,1.000],[1.000,1.000],[2.000,1.000]]"));
+        assert!(geojson
+            .contains("\"fall_events\":[{\"event\":\"fall_suspected\",\"timestamp\":1001.000}]"));
+    }
+}
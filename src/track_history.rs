@@ -0,0 +1,132 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use nalgebra::Vector2;
+
+/// Points kept per track by default, about 25 s at 10 updates per second
+pub const DEFAULT_TRACK_HISTORY_LENGTH: usize = 256;
+
+/// Filtered state of a track at one measurement update
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrackPoint {
+    pub timestamp: Instant,
+    pub position: Vector2<f32>,
+    pub velocity: Vector2<f32>,
+    /// Height above the floor for targets tracked in 3D
+    pub height: Option<f32>,
+}
+
+/// Past states of one track in a bounded ring buffer, oldest first
+#[derive(Debug, Clone)]
+pub struct TrackHistory {
+    points: VecDeque<TrackPoint>,
+    capacity: usize,
+}
+
+impl Default for TrackHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_TRACK_HISTORY_LENGTH)
+    }
+}
+
+impl TrackHistory {
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            points: VecDeque::with_capacity(capacity.min(DEFAULT_TRACK_HISTORY_LENGTH)),
+            capacity,
+        }
+    }
+
+    /// Append a point, replacing the newest one if it has the same timestamp
+    pub fn push(&mut self, point: TrackPoint) {
+        if self
+            .points
+            .back()
+            .is_some_and(|last| last.timestamp == point.timestamp)
+        {
+            self.points.pop_back();
+        } else if self.points.len() == self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
+    }
+
+    pub fn get_capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &TrackPoint> + ExactSizeIterator {
+        self.points.iter()
+    }
+
+    /// Points no older than `duration` before the newest one, oldest first
+    pub fn since(&self, duration: Duration) -> impl Iterator<Item = &TrackPoint> {
+        let newest = self.points.back().map(|point| point.timestamp);
+        self.points.iter().filter(move |point| {
+            newest.is_some_and(|newest| newest.duration_since(point.timestamp) <= duration)
+        })
+    }
+
+    /// Distance travelled over the retained points in metres
+    pub fn path_length(&self) -> f32 {
+        self.points
+            .iter()
+            .zip(self.points.iter().skip(1))
+            .map(|(a, b)| (b.position - a.position).norm())
+            .sum()
+    }
+
+    /// Path length over the time the retained points span, in m/s
+    pub fn average_speed(&self) -> f32 {
+        match (self.points.front(), self.points.back()) {
+            (Some(first), Some(last)) if last.timestamp > first.timestamp => {
+                self.path_length() / last.timestamp.duration_since(first.timestamp).as_secs_f32()
+            }
+            _ => 0.0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_history_queries() {
+        let start = Instant::now();
+        let point = |seconds: u64, x: f32| TrackPoint {
+            timestamp: start + Duration::from_secs(seconds),
+            position: Vector2::new(x, 0.0),
+            velocity: Vector2::new(1.0, 0.0),
+            height: None,
+        };
+
+        let mut history = TrackHistory::new(3);
+        for second in 0..5 {
+            history.push(point(second, second as f32));
+        }
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.iter().next().unwrap().position.x, 2.0);
+        assert_eq!(history.path_length(), 2.0);
+        assert_eq!(history.average_speed(), 1.0);
+        assert_eq!(history.since(Duration::from_secs(1)).count(), 2);
+
+        // Same timestamp refines the newest point
+        history.push(point(4, 4.5));
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.iter().last().unwrap().position.x, 4.5);
+    }
+}
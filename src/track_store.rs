@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+
+use nalgebra::Vector2;
+
+use crate::track_history::TrackHistory;
+use crate::tracker::{HeightFilter, TrackFilter, TrackedTarget};
+
+/// Side of a spatial grid cell in metres, about the association gate so a query rarely
+/// touches more than the neighbouring cells
+pub const DEFAULT_GRID_CELL_SIZE: f32 = 2.0;
+
+/// Slot of a track in a [`TrackStore`]. The generation changes every time the slot is reused,
+/// so a key kept after its track was removed never reaches the next one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TrackKey {
+    index: u32,
+    generation: u32,
+}
+
+/// Everything kept for one track
+#[derive(Debug, Clone)]
+pub struct Track {
+    pub target: TrackedTarget,
+    pub filter: TrackFilter,
+    /// Vertical filter of targets tracked in 3D
+    pub height_filter: Option<HeightFilter>,
+    pub history: TrackHistory,
+    /// Grid cell the track is indexed under
+    cell: (i32, i32),
+}
+
+impl Track {
+    pub fn new(target: TrackedTarget, filter: TrackFilter, history: TrackHistory) -> Self {
+        Self {
+            target,
+            filter,
+            height_filter: None,
+            history,
+            cell: (0, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Slot {
+    generation: u32,
+    track: Option<Track>,
+}
+
+/// Tracks in one slab with a generational index, plus a uniform grid over their positions for
+/// neighbourhood queries. Target IDs map to slots through a single lookup.
+#[derive(Debug, Clone)]
+pub struct TrackStore {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+    keys: HashMap<u32, TrackKey>,
+    cell_size: f32,
+    grid: HashMap<(i32, i32), Vec<TrackKey>>,
+}
+
+impl Default for TrackStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_GRID_CELL_SIZE)
+    }
+}
+
+impl TrackStore {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            slots: Vec::new(),
+            free: Vec::new(),
+            keys: HashMap::new(),
+            cell_size: cell_size.max(0.1),
+            grid: HashMap::new(),
+        }
+    }
+
+    /// Store a track, replacing any track with the same target ID
+    pub fn insert(&mut self, mut track: Track) -> TrackKey {
+        self.remove(track.target.id);
+        track.cell = self.cell_of(track.target.position);
+        let cell = track.cell;
+        let id = track.target.id;
+
+        let key = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.track = Some(track);
+                TrackKey {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    track: Some(track),
+                });
+                TrackKey {
+                    index: self.slots.len() as u32 - 1,
+                    generation: 0,
+                }
+            }
+        };
+        self.keys.insert(id, key);
+        self.grid.entry(cell).or_default().push(key);
+        key
+    }
+
+    pub fn remove(&mut self, target_id: u32) -> Option<Track> {
+        let key = self.keys.remove(&target_id)?;
+        let slot = &mut self.slots[key.index as usize];
+        let track = slot.track.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free.push(key.index);
+        self.unindex(key, track.cell);
+        Some(track)
+    }
+
+    pub fn key_of(&self, target_id: u32) -> Option<TrackKey> {
+        self.keys.get(&target_id).copied()
+    }
+
+    pub fn get_by_key(&self, key: TrackKey) -> Option<&Track> {
+        self.slots
+            .get(key.index as usize)
+            .filter(|slot| slot.generation == key.generation)?
+            .track
+            .as_ref()
+    }
+
+    pub fn get_by_key_mut(&mut self, key: TrackKey) -> Option<&mut Track> {
+        self.slots
+            .get_mut(key.index as usize)
+            .filter(|slot| slot.generation == key.generation)?
+            .track
+            .as_mut()
+    }
+
+    pub fn get(&self, target_id: u32) -> Option<&Track> {
+        self.get_by_key(self.key_of(target_id)?)
+    }
+
+    pub fn get_mut(&mut self, target_id: u32) -> Option<&mut Track> {
+        self.get_by_key_mut(self.key_of(target_id)?)
+    }
+
+    pub fn contains(&self, target_id: u32) -> bool {
+        self.keys.contains_key(&target_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Track> {
+        self.slots.iter().filter_map(|slot| slot.track.as_ref())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Track> {
+        self.slots.iter_mut().filter_map(|slot| slot.track.as_mut())
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.keys.keys().copied()
+    }
+
+    /// Move a track to the grid cell of its current position, after its target moved
+    pub fn reindex(&mut self, target_id: u32) {
+        let Some(key) = self.key_of(target_id) else {
+            return;
+        };
+        let cell_size = self.cell_size;
+        let Some(track) = self.get_by_key_mut(key) else {
+            return;
+        };
+        let cell = cell_at(track.target.position, cell_size);
+        let previous = std::mem::replace(&mut track.cell, cell);
+        if previous != cell {
+            self.unindex(key, previous);
+            self.grid.entry(cell).or_default().push(key);
+        }
+    }
+
+    /// Reindex every track, after a prediction step moved them all
+    pub fn reindex_all(&mut self) {
+        let ids: Vec<u32> = self.ids().collect();
+        for target_id in ids {
+            self.reindex(target_id);
+        }
+    }
+
+    /// Tracks closer than `radius` to `position`
+    pub fn within(&self, position: Vector2<f32>, radius: f32) -> Vec<&Track> {
+        let close = |track: &&Track| (track.target.position - position).norm() < radius;
+        let (min_x, min_y) = self.cell_of(position - Vector2::repeat(radius));
+        let (max_x, max_y) = self.cell_of(position + Vector2::repeat(radius));
+        let cells =
+            (i64::from(max_x) - i64::from(min_x) + 1) * (i64::from(max_y) - i64::from(min_y) + 1);
+
+        // A radius spanning more cells than there are tracks is cheaper to answer by scanning
+        if cells > self.len() as i64 {
+            return self.iter().filter(close).collect();
+        }
+        (min_x..=max_x)
+            .flat_map(|x| (min_y..=max_y).map(move |y| (x, y)))
+            .filter_map(|cell| self.grid.get(&cell))
+            .flatten()
+            .filter_map(|key| self.get_by_key(*key))
+            .filter(close)
+            .collect()
+    }
+
+    /// Closest track to `position` within `radius`
+    pub fn nearest(&self, position: Vector2<f32>, radius: f32) -> Option<&Track> {
+        self.within(position, radius).into_iter().min_by(|a, b| {
+            let a = (a.target.position - position).norm_squared();
+            let b = (b.target.position - position).norm_squared();
+            a.total_cmp(&b)
+        })
+    }
+
+    pub fn clear(&mut self) {
+        for slot in &mut self.slots {
+            if slot.track.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+            }
+        }
+        self.free = (0..self.slots.len() as u32).rev().collect();
+        self.keys.clear();
+        self.grid.clear();
+    }
+
+    fn cell_of(&self, position: Vector2<f32>) -> (i32, i32) {
+        cell_at(position, self.cell_size)
+    }
+
+    fn unindex(&mut self, key: TrackKey, cell: (i32, i32)) {
+        if let Some(keys) = self.grid.get_mut(&cell) {
+            keys.retain(|k| *k != key);
+            if keys.is_empty() {
+                self.grid.remove(&cell);
+            }
+        }
+    }
+}
+
+fn cell_at(position: Vector2<f32>, cell_size: f32) -> (i32, i32) {
+    let cell = position / cell_size;
+    // Saturating casts keep non-finite positions in the outermost cells
+    (cell.x.floor() as i32, cell.y.floor() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(id: u32, x: f32, y: f32) -> Track {
+        let position = Vector2::new(x, y);
+        Track::new(
+            TrackedTarget::new(id, 0, position),
+            TrackFilter::new(Default::default(), position),
+            TrackHistory::default(),
+        )
+    }
+
+    #[test]
+    fn test_generational_keys() {
+        let mut store = TrackStore::default();
+        let first = store.insert(track(1, 0.0, 0.0));
+        store.insert(track(2, 1.0, 0.0));
+        assert_eq!(store.len(), 2);
+
+        assert!(store.remove(1).is_some());
+        let reused = store.insert(track(3, 5.0, 5.0));
+        // The slot is reused, the stale key no longer resolves
+        assert!(store.get_by_key(first).is_none());
+        assert_eq!(store.get_by_key(reused).unwrap().target.id, 3);
+        assert!(store.get(1).is_none());
+        assert_eq!(store.iter().count(), 2);
+    }
+
+    #[test]
+    fn test_spatial_queries() {
+        let mut store = TrackStore::new(1.0);
+        store.insert(track(1, 0.0, 0.0));
+        store.insert(track(2, 3.5, 0.0));
+        store.insert(track(3, -0.5, -0.4));
+
+        let mut near: Vec<u32> = store
+            .within(Vector2::new(0.2, 0.0), 1.0)
+            .iter()
+            .map(|t| t.target.id)
+            .collect();
+        near.sort_unstable();
+        assert_eq!(near, vec![1, 3]);
+        assert_eq!(
+            store
+                .nearest(Vector2::new(3.0, 0.0), 1.0)
+                .map(|t| t.target.id),
+            Some(2)
+        );
+
+        store.get_mut(2).unwrap().target.position = Vector2::new(0.1, 0.1);
+        store.reindex(2);
+        assert_eq!(store.within(Vector2::new(0.0, 0.0), 0.5).len(), 2);
+        assert!(store.nearest(Vector2::new(3.0, 0.0), 1.0).is_none());
+    }
+}
@@ -1,15 +1,332 @@
 use std::collections::HashMap;
+use std::sync::{mpsc, Arc};
 use std::time::{Duration, Instant};
-use nalgebra::{Vector2, Matrix2};
+use nalgebra::{Vector2, Vector3, Matrix2, Matrix3};
 use log::{debug, info, warn};
-use smallvec::SmallVec;
+use thiserror::Error;
+
+use crate::association::{associate, Association};
+use crate::clock::{Clock, SystemClock};
+use crate::ekf::{ExtendedKalmanFilter, SensorOrigin};
+use crate::imm::ImmFilter;
+use crate::smoothing::RtsSmoother;
+use crate::target_class::{TargetClass, TargetClassifier};
+use crate::track_history::{TrackHistory, TrackPoint, DEFAULT_TRACK_HISTORY_LENGTH};
+use crate::track_store::{Track, TrackStore};
+
+/// Squared Mahalanobis distance beyond which a measurement is rejected, the 99.9% point of the
+/// chi-squared distribution with two degrees of freedom
+pub const DEFAULT_MAHALANOBIS_GATE: f32 = 13.82;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum TargetState {
     Tracking,
     Falling,
     Lost,
     Predicted,
+    /// No longer producing position updates, but the sensor still reports someone sitting or
+    /// lying still there
+    StationaryPresence,
+}
+
+/// Stage of a target's fall as reported through [`FallEvent`]s
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FallPhase {
+    #[default]
+    Normal,
+    /// The fall detector fired, waiting to see whether the person recovers
+    Suspected,
+    /// No recovery within the confirmation window
+    Confirmed,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FallEvent {
+    FallSuspected {
+        target_id: u32,
+        probability: f32,
+        position: Vector2<f32>,
+        /// Where the target is expected to hit the floor, for pre-impact alerts
+        landing: Option<LandingZone>,
+        at: Instant,
+    },
+    FallConfirmed {
+        target_id: u32,
+        position: Vector2<f32>,
+        at: Instant,
+    },
+    /// The person got up again, or a suspected fall was a false alarm
+    FallCleared {
+        target_id: u32,
+        at: Instant,
+    },
+}
+
+/// Whether a track has been seen often enough to be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TrackStatus {
+    /// New detection still waiting for M of N frames
+    Tentative,
+    Confirmed,
+}
+
+/// Why the identity of a track is uncertain, set while it is close to another track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TrackInteraction {
+    /// Both tracks fell onto one measurement, the one that lost it is coasting on its
+    /// prediction until they separate
+    Merged { with: u32 },
+    /// A second measurement appeared next to the track and started a new one
+    Split { with: u32 },
+}
+
+/// Default M-of-N confirmation: seen in 3 of the first 5 frames
+pub const DEFAULT_CONFIRM_HITS: u32 = 3;
+pub const DEFAULT_CONFIRM_WINDOW: u32 = 5;
+
+/// Frames in a row a confirmed track must miss before presence reports hold it in
+/// [`TargetState::StationaryPresence`], so a track briefly missed while moving keeps its velocity
+const PRESENCE_MISSED_FRAMES: u32 = 3;
+
+/// Smallest variance kept on the covariance diagonal, so the filter never becomes certain
+/// enough to make the innovation covariance singular
+const MIN_VARIANCE: f32 = 1e-6;
+
+/// Singular values below this fraction of the largest are dropped by the pseudo-inverse
+const PSEUDO_INVERSE_EPSILON: f32 = 1e-6;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum FilterError {
+    #[error("innovation covariance cannot be inverted")]
+    SingularCovariance,
+
+    #[error("measurement or filter state is not finite")]
+    NonFinite,
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum TrackerError {
+    #[error("antenna {antenna_id} out of range, the tracker has {antenna_count} antennas")]
+    InvalidAntenna { antenna_id: u8, antenna_count: u8 },
+
+    #[error("antenna {antenna_id} at maximum capacity ({capacity} targets)")]
+    AntennaSaturated { antenna_id: u8, capacity: usize },
+}
+
+/// Inverse of a symmetric innovation covariance through its Cholesky factor, falling back to
+/// the pseudo-inverse when it is not positive definite
+pub(crate) fn invert_covariance(covariance: &Matrix2<f32>) -> Result<Matrix2<f32>, FilterError> {
+    if !covariance.iter().all(|v| v.is_finite()) {
+        return Err(FilterError::NonFinite);
+    }
+    let inverse = match covariance.cholesky() {
+        Some(cholesky) => cholesky.inverse(),
+        None => covariance
+            .pseudo_inverse(PSEUDO_INVERSE_EPSILON)
+            .map_err(|_| FilterError::SingularCovariance)?,
+    };
+    if inverse.iter().all(|v| v.is_finite()) {
+        Ok(inverse)
+    } else {
+        Err(FilterError::SingularCovariance)
+    }
+}
+
+/// Restore symmetry lost to rounding and keep the variances positive
+fn condition_covariance(covariance: &mut Matrix6) {
+    *covariance = (*covariance + covariance.transpose()) * 0.5;
+    for i in 0..6 {
+        covariance[(i, i)] = covariance[(i, i)].max(MIN_VARIANCE);
+    }
+}
+
+/// Fall probability from which a target counts as falling unless configured otherwise
+pub const DEFAULT_FALL_PROBABILITY_THRESHOLD: f32 = 0.7;
+
+/// Shortest interval in seconds the speed implied by a measurement is computed over, so
+/// centimetre jitter between back-to-back updates doesn't look super-human
+const MIN_LIMIT_INTERVAL: f32 = 0.1;
+
+/// Confidence of a track that has only been seen once
+const TENTATIVE_CONFIDENCE: f32 = 0.2;
+
+/// Filter tuning and track management parameters of a [`MultiTargetTracker`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TrackerConfig {
+    /// Filter used for new tracks
+    pub filter: FilterKind,
+    /// Process noise variance added per prediction step
+    pub process_noise: f32,
+    /// Variance of position measurements in m² along each axis
+    pub measurement_noise: f32,
+    /// Initial state variance of a new track
+    pub initial_covariance: f32,
+    /// Squared Mahalanobis distance beyond which measurements are rejected
+    pub mahalanobis_gate: f32,
+    /// Furthest a measurement may be from a target, in metres, to be associated with it
+    pub association_gate_m: f32,
+    /// Detections needed within `confirm_window` frames to confirm a new track
+    pub confirm_hits: u32,
+    pub confirm_window: u32,
+    /// Past states kept per track for [`MultiTargetTracker::get_track_history`]
+    pub history_length: usize,
+    /// Fastest plausible human motion in m/s, measurements implying more are rejected
+    pub max_speed: f32,
+    /// Largest plausible acceleration in m/s², estimates are capped to it
+    pub max_acceleration: f32,
+    /// When tracks that stopped being detected are deleted
+    pub deletion: TrackDeletionPolicy,
+    /// Most targets tracked per antenna, further detections are dropped
+    pub max_targets_per_antenna: usize,
+    /// Antennas with a capacity other than `max_targets_per_antenna`
+    pub antenna_capacities: Vec<AntennaCapacity>,
+    /// Stationary energy at a track's range, 0 to 1, taken as someone still being there
+    pub presence_energy_threshold: f32,
+    /// Speed in m/s below which a detection near a track counts as someone still being there
+    pub presence_max_speed: f32,
+    pub fall_detector: FallDetectorConfig,
+}
+
+impl Default for TrackerConfig {
+    fn default() -> Self {
+        Self {
+            filter: FilterKind::default(),
+            process_noise: 0.1,
+            measurement_noise: 1.0,
+            initial_covariance: 100.0,
+            mahalanobis_gate: DEFAULT_MAHALANOBIS_GATE,
+            association_gate_m: 2.0,
+            confirm_hits: DEFAULT_CONFIRM_HITS,
+            confirm_window: DEFAULT_CONFIRM_WINDOW,
+            history_length: DEFAULT_TRACK_HISTORY_LENGTH,
+            max_speed: 6.0,
+            max_acceleration: 25.0,
+            deletion: TrackDeletionPolicy::default(),
+            max_targets_per_antenna: 8,
+            antenna_capacities: Vec::new(),
+            presence_energy_threshold: 0.2,
+            presence_max_speed: 0.1,
+            fall_detector: FallDetectorConfig::default(),
+        }
+    }
+}
+
+/// Target capacity of one antenna in the [`TrackerConfig`]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AntennaCapacity {
+    pub antenna_id: u8,
+    pub max_targets: usize,
+}
+
+/// Rules for deleting tracks that stopped being detected. A fall sensor in a small room wants
+/// to hold on to a still person for long, a busy space wants stale tracks gone quickly.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TrackDeletionPolicy {
+    /// Seconds without a detection before a tentative track is deleted
+    pub tentative_timeout_s: f32,
+    /// Seconds without a detection before a confirmed track is deleted
+    pub confirmed_timeout_s: f32,
+    /// Seconds without a presence report before a track in
+    /// [`TargetState::StationaryPresence`] is deleted
+    pub stationary_timeout_s: f32,
+    /// Confidence below which a coasting track is deleted
+    pub min_confidence: f32,
+    /// Predictions without an update after which a coasting track is deleted
+    pub max_predictions: u32,
+}
+
+impl Default for TrackDeletionPolicy {
+    fn default() -> Self {
+        Self {
+            tentative_timeout_s: 5.0,
+            confirmed_timeout_s: 30.0,
+            stationary_timeout_s: 30.0,
+            min_confidence: 0.1,
+            max_predictions: 10,
+        }
+    }
+}
+
+impl TrackDeletionPolicy {
+    /// Whether `target` should be deleted at `now`
+    pub fn is_expired(&self, target: &TrackedTarget, now: Instant) -> bool {
+        let stationary = target.state == TargetState::StationaryPresence;
+        let timeout = if stationary {
+            self.stationary_timeout_s
+        } else if target.is_confirmed() {
+            self.confirmed_timeout_s
+        } else {
+            self.tentative_timeout_s
+        };
+        let silent = now.saturating_duration_since(target.last_update.max(target.last_presence));
+        // Stationary targets are expected to coast, only their presence reports count
+        silent.as_secs_f32() > timeout.max(0.0)
+            || (!stationary
+                && (target.confidence < self.min_confidence || target.prediction_count > self.max_predictions))
+    }
+}
+
+/// Thresholds of the [`FallDetector`]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct FallDetectorConfig {
+    /// Vertical acceleration in m/s² below which the target counts as in free fall
+    pub gravity_threshold: f32,
+    /// Downward speed in m/s that counts towards a fall
+    pub velocity_threshold: f32,
+    /// Acceleration magnitude in m/s² that counts as a sudden change
+    pub acceleration_threshold: f32,
+    /// Fall probability from which a target is reported falling
+    pub fall_probability_threshold: f32,
+    /// How long a suspected fall or a recovery must persist before it is reported
+    pub confirmation_window_ms: u64,
+    /// Height in metres below which a 3D target counts as lying on the floor
+    pub lying_height_m: f32,
+    /// Speed in m/s at which a 2D target counts as having got up again
+    pub recovery_speed: f32,
+    /// How far ahead a fall trajectory is predicted when the impact time is unknown
+    pub prediction_horizon_ms: u64,
+    /// Interval between the points of a predicted fall trajectory
+    pub prediction_step_ms: u64,
+}
+
+impl Default for FallDetectorConfig {
+    fn default() -> Self {
+        Self {
+            gravity_threshold: -9.5,
+            velocity_threshold: 2.0,
+            acceleration_threshold: 15.0,
+            fall_probability_threshold: DEFAULT_FALL_PROBABILITY_THRESHOLD,
+            confirmation_window_ms: 500,
+            lying_height_m: 0.5,
+            recovery_speed: 0.3,
+            prediction_horizon_ms: 1000,
+            prediction_step_ms: 50,
+        }
+    }
+}
+
+/// Height above the floor and its rates, known when the sensors measure in 3D
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VerticalState {
+    pub height: f32,
+    pub velocity: f32,
+    pub acceleration: f32,
 }
 
 #[derive(Debug, Clone)]
@@ -22,13 +339,34 @@ pub struct TrackedTarget {
     pub state: TargetState,
     pub confidence: f32,
     pub last_update: Instant,
+    /// Last update or report of stationary presence at the target
+    pub last_presence: Instant,
     pub prediction_count: u32,
     pub fall_probability: f32,
+    pub status: TrackStatus,
+    /// Detections in the most recent frames, bit 0 the latest
+    pub hit_history: u32,
+    /// Frames since the track was created
+    pub frame_count: u32,
+    /// Vertical motion for targets tracked in 3D, `None` for floor-plane tracks
+    pub vertical: Option<VerticalState>,
+    pub fall_phase: FallPhase,
+    /// Set while the track may have swapped identity with a close one
+    pub interaction: Option<TrackInteraction>,
+    pub class: TargetClass,
+    /// Phase the target is moving to and since when, applied after the confirmation window
+    pending_fall_phase: Option<(FallPhase, Instant)>,
 }
 
 impl TrackedTarget {
     #[inline]
     pub fn new(id: u32, antenna_id: u8, position: Vector2<f32>) -> Self {
+        Self::new_at(id, antenna_id, position, Instant::now())
+    }
+
+    /// Target first seen at `timestamp`
+    #[inline]
+    pub fn new_at(id: u32, antenna_id: u8, position: Vector2<f32>, timestamp: Instant) -> Self {
         Self {
             id,
             antenna_id,
@@ -36,21 +374,63 @@ impl TrackedTarget {
             velocity: Vector2::zeros(),
             acceleration: Vector2::zeros(),
             state: TargetState::Tracking,
-            confidence: 1.0,
-            last_update: Instant::now(),
+            confidence: TENTATIVE_CONFIDENCE,
+            last_update: timestamp,
+            last_presence: timestamp,
             prediction_count: 0,
             fall_probability: 0.0,
+            status: TrackStatus::Tentative,
+            hit_history: 1,
+            frame_count: 1,
+            vertical: None,
+            fall_phase: FallPhase::Normal,
+            interaction: None,
+            class: TargetClass::Unknown,
+            pending_fall_phase: None,
         }
     }
 
+    /// Position including height for targets tracked in 3D
     #[inline]
-    pub fn update_position(&mut self, new_position: Vector2<f32>, dt: f32) {
+    pub fn get_position_3d(&self) -> Option<Vector3<f32>> {
+        self.vertical
+            .map(|vertical| Vector3::new(self.position.x, self.position.y, vertical.height))
+    }
+
+    /// Record whether the target was detected in a frame, keeping the last `window` frames
+    #[inline]
+    pub fn record_frame(&mut self, hit: bool, window: u32) {
+        let mask = if window >= 32 { u32::MAX } else { (1 << window) - 1 };
+        self.hit_history = ((self.hit_history << 1) | hit as u32) & mask;
+        self.frame_count = self.frame_count.saturating_add(1);
+    }
+
+    /// Detections within the recorded window
+    #[inline]
+    pub fn get_hit_count(&self) -> u32 {
+        self.hit_history.count_ones()
+    }
+
+    #[inline]
+    pub fn is_confirmed(&self) -> bool {
+        self.status == TrackStatus::Confirmed
+    }
+
+    /// Whether the track merged with or split from another one and has not separated yet
+    #[inline]
+    pub fn is_ambiguous(&self) -> bool {
+        self.interaction.is_some()
+    }
+
+    /// Move to `new_position`, measured at `now` and `dt` seconds after the last update
+    #[inline]
+    pub fn update_position(&mut self, new_position: Vector2<f32>, dt: f32, now: Instant) {
         if dt > 0.0 {
             let new_velocity = (new_position - self.position) / dt;
             self.acceleration = (new_velocity - self.velocity) / dt;
             self.velocity = new_velocity;
             self.position = new_position;
-            self.last_update = Instant::now();
+            self.last_update = now;
             self.prediction_count = 0;
             self.confidence = (self.confidence * 0.8 + 0.2).min(1.0);
         }
@@ -62,14 +442,29 @@ impl TrackedTarget {
         self.position + self.velocity * dt + 0.5 * self.acceleration * dt * dt
     }
 
+    /// Only confirmed targets can be falling. Uses the default threshold, see
+    /// [`FallDetector::is_falling`] for the configured one.
     #[inline]
     pub fn is_falling(&self) -> bool {
-        self.fall_probability > 0.7
+        self.is_confirmed() && self.fall_probability > DEFAULT_FALL_PROBABILITY_THRESHOLD
     }
 }
 
+/// Motion assumed by a [`KalmanFilter`] between measurements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum KinematicModel {
+    /// Standing still, velocity and acceleration are held at zero
+    Stationary,
+    ConstantVelocity,
+    ConstantAcceleration,
+}
+
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct KalmanFilter {
+    model: KinematicModel,
     // State vector: [x, y, vx, vy, ax, ay]
     state: Vector6,
     // Covariance matrix
@@ -83,18 +478,33 @@ pub struct KalmanFilter {
     measurement_matrix: Matrix2x6,
 }
 
-type Vector6 = nalgebra::SVector<f32, 6>;
-type Matrix6 = nalgebra::SMatrix<f32, 6, 6>;
+pub(crate) type Vector6 = nalgebra::SVector<f32, 6>;
+pub(crate) type Matrix6 = nalgebra::SMatrix<f32, 6, 6>;
 
 impl KalmanFilter {
     pub fn new(initial_position: Vector2<f32>) -> Self {
+        Self::with_model(initial_position, KinematicModel::ConstantAcceleration)
+    }
+
+    pub fn with_model(initial_position: Vector2<f32>, model: KinematicModel) -> Self {
+        Self::with_config(initial_position, model, &TrackerConfig::default())
+    }
+
+    /// Filter with the noise parameters of `config`. The stationary and constant-velocity
+    /// models put a tenth of the process noise on the position they hold or extrapolate.
+    pub fn with_config(initial_position: Vector2<f32>, model: KinematicModel, config: &TrackerConfig) -> Self {
         let mut state = Vector6::zeros();
         state[0] = initial_position.x;
         state[1] = initial_position.y;
 
-        let covariance = Matrix6::identity() * 100.0;
-        let process_noise = Matrix6::identity() * 0.1;
-        let measurement_noise = Matrix2::identity() * 1.0;
+        let q = config.process_noise;
+        let covariance = Matrix6::identity() * config.initial_covariance;
+        let process_noise = match model {
+            KinematicModel::Stationary => Matrix6::from_diagonal(&Vector6::new(0.1 * q, 0.1 * q, 0.0, 0.0, 0.0, 0.0)),
+            KinematicModel::ConstantVelocity => Matrix6::from_diagonal(&Vector6::new(0.1 * q, 0.1 * q, q, q, 0.0, 0.0)),
+            KinematicModel::ConstantAcceleration => Matrix6::identity() * q,
+        };
+        let measurement_noise = Matrix2::identity() * config.measurement_noise;
 
         // Pre-compute static matrices
         let measurement_matrix = Matrix2x6::new(
@@ -103,6 +513,7 @@ impl KalmanFilter {
         );
 
         Self {
+            model,
             state,
             covariance,
             process_noise,
@@ -117,21 +528,43 @@ impl KalmanFilter {
         // Update state transition matrix F
         let f = &mut self.state_transition;
         *f = Matrix6::identity();
-        f[(0, 2)] = dt;  // x += vx * dt
-        f[(1, 3)] = dt;  // y += vy * dt
-        f[(0, 4)] = 0.5 * dt * dt;  // x += 0.5 * ax * dt²
-        f[(1, 5)] = 0.5 * dt * dt;  // y += 0.5 * ay * dt²
-        f[(2, 4)] = dt;  // vx += ax * dt
-        f[(3, 5)] = dt;  // vy += ay * dt
+        match self.model {
+            KinematicModel::Stationary => {
+                // Motion states decay to zero
+                for i in 2..6 {
+                    f[(i, i)] = 0.0;
+                }
+            }
+            KinematicModel::ConstantVelocity => {
+                f[(0, 2)] = dt;  // x += vx * dt
+                f[(1, 3)] = dt;  // y += vy * dt
+                f[(4, 4)] = 0.0;
+                f[(5, 5)] = 0.0;
+            }
+            KinematicModel::ConstantAcceleration => {
+                f[(0, 2)] = dt;  // x += vx * dt
+                f[(1, 3)] = dt;  // y += vy * dt
+                f[(0, 4)] = 0.5 * dt * dt;  // x += 0.5 * ax * dt²
+                f[(1, 5)] = 0.5 * dt * dt;  // y += 0.5 * ay * dt²
+                f[(2, 4)] = dt;  // vx += ax * dt
+                f[(3, 5)] = dt;  // vy += ay * dt
+            }
+        }
 
         // Predict state
         self.state = *f * self.state;
         // Predict covariance
         self.covariance = *f * self.covariance * f.transpose() + self.process_noise;
+        condition_covariance(&mut self.covariance);
     }
 
+    /// Fuse a position measurement. On error the filter is left unchanged.
     #[inline]
-    pub fn update(&mut self, measurement: Vector2<f32>) {
+    pub fn update(&mut self, measurement: Vector2<f32>) -> Result<(), FilterError> {
+        if !measurement.iter().all(|v| v.is_finite()) {
+            return Err(FilterError::NonFinite);
+        }
+
         // Innovation
         let innovation = Vector2::new(
             measurement.x - self.state[0], 
@@ -143,16 +576,67 @@ impl KalmanFilter {
         let innovation_covariance = *h * self.covariance * h.transpose() + self.measurement_noise;
         
         // Kalman gain
-        let kalman_gain = self.covariance * h.transpose() * innovation_covariance.try_inverse().unwrap();
+        let kalman_gain = self.covariance * h.transpose() * invert_covariance(&innovation_covariance)?;
 
-        // Update state
-        let state_update = kalman_gain * innovation;
-        self.state[0] += state_update[0];
-        self.state[1] += state_update[1];
+        // Update state, velocity and acceleration follow from the position innovation
+        let state = self.state + kalman_gain * innovation;
+        if !state.iter().all(|v| v.is_finite()) {
+            return Err(FilterError::NonFinite);
+        }
+        self.state = state;
         
-        // Update covariance
-        let identity = Matrix6::identity();
-        self.covariance = (identity - kalman_gain * h) * self.covariance;
+        // Update covariance in Joseph form, which stays positive semi-definite under rounding
+        let correction = Matrix6::identity() - kalman_gain * h;
+        self.covariance = correction * self.covariance * correction.transpose()
+            + kalman_gain * self.measurement_noise * kalman_gain.transpose();
+        condition_covariance(&mut self.covariance);
+        Ok(())
+    }
+
+    /// Expected covariance of the innovation for the next measurement, S = H P Hᵀ + R
+    #[inline]
+    pub fn get_innovation_covariance(&self) -> Matrix2<f32> {
+        let h = &self.measurement_matrix;
+        *h * self.covariance * h.transpose() + self.measurement_noise
+    }
+
+    /// Squared Mahalanobis distance of `measurement` from the predicted position, `None` if the
+    /// innovation covariance is singular
+    pub fn mahalanobis_distance_squared(&self, measurement: Vector2<f32>) -> Option<f32> {
+        let innovation = measurement - self.get_position();
+        invert_covariance(&self.get_innovation_covariance())
+            .ok()
+            .map(|inverse| (innovation.transpose() * inverse * innovation)[(0, 0)])
+    }
+
+    /// Whether `measurement` is consistent enough with the filter to be fed into `update()`
+    #[inline]
+    pub fn gate(&self, measurement: Vector2<f32>, threshold: f32) -> bool {
+        self.mahalanobis_distance_squared(measurement)
+            .is_some_and(|distance| distance <= threshold)
+    }
+
+    pub fn get_model(&self) -> KinematicModel {
+        self.model
+    }
+
+    pub(crate) fn get_state(&self) -> (&Vector6, &Matrix6) {
+        (&self.state, &self.covariance)
+    }
+
+    pub(crate) fn get_measurement_noise(&self) -> Matrix2<f32> {
+        self.measurement_noise
+    }
+
+    /// Variance of position measurements in m² along each axis
+    pub fn set_measurement_noise(&mut self, variance: f32) {
+        self.measurement_noise = Matrix2::identity() * variance;
+    }
+
+    pub(crate) fn set_state(&mut self, state: Vector6, mut covariance: Matrix6) {
+        condition_covariance(&mut covariance);
+        self.state = state;
+        self.covariance = covariance;
     }
 
     #[inline]
@@ -173,164 +657,761 @@ impl KalmanFilter {
 
 type Matrix2x6 = nalgebra::SMatrix<f32, 2, 6>;
 
+/// Constant-acceleration Kalman filter on height, run next to the floor-plane filter of
+/// targets tracked in 3D
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeightFilter {
+    // State vector: [z, vz, az]
+    state: Vector3<f32>,
+    covariance: Matrix3<f32>,
+    process_noise: Matrix3<f32>,
+    measurement_noise: f32,
+}
+
+impl HeightFilter {
+    pub fn new(initial_height: f32) -> Self {
+        Self::with_config(initial_height, &TrackerConfig::default())
+    }
+
+    pub fn with_config(initial_height: f32, config: &TrackerConfig) -> Self {
+        Self {
+            state: Vector3::new(initial_height, 0.0, 0.0),
+            covariance: Matrix3::identity() * config.initial_covariance,
+            process_noise: Matrix3::identity() * config.process_noise,
+            measurement_noise: config.measurement_noise,
+        }
+    }
+
+    #[inline]
+    pub fn predict(&mut self, dt: f32) {
+        let f = Matrix3::new(
+            1.0, dt, 0.5 * dt * dt,
+            0.0, 1.0, dt,
+            0.0, 0.0, 1.0,
+        );
+        self.state = f * self.state;
+        self.covariance = f * self.covariance * f.transpose() + self.process_noise;
+    }
+
+    /// Fuse a height measurement. On error the filter is left unchanged.
+    #[inline]
+    pub fn update(&mut self, height: f32) -> Result<(), FilterError> {
+        if !height.is_finite() {
+            return Err(FilterError::NonFinite);
+        }
+        let innovation_variance = self.covariance[(0, 0)] + self.measurement_noise;
+        if !(innovation_variance > 0.0 && innovation_variance.is_finite()) {
+            return Err(FilterError::SingularCovariance);
+        }
+        let gain = self.covariance.column(0) / innovation_variance;
+        self.state += gain * (height - self.state[0]);
+        self.covariance -= gain * self.covariance.row(0);
+        self.covariance = (self.covariance + self.covariance.transpose()) * 0.5;
+        for i in 0..3 {
+            self.covariance[(i, i)] = self.covariance[(i, i)].max(MIN_VARIANCE);
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get_state(&self) -> VerticalState {
+        VerticalState {
+            height: self.state[0],
+            velocity: self.state[1],
+            acceleration: self.state[2],
+        }
+    }
+}
+
+/// Filter used for new tracks
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum FilterKind {
+    /// Single constant-acceleration Kalman filter
+    #[default]
+    ConstantAcceleration,
+    /// Interacting multiple models switching between stationary, constant-velocity and
+    /// constant-acceleration motion
+    Imm,
+    /// Extended Kalman filter fusing range and azimuth as seen from the sensor at the origin
+    Polar(SensorOrigin),
+}
+
+/// State estimator behind one track
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)] // Stored inline per track, most trackers use one kind
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TrackFilter {
+    Kalman(KalmanFilter),
+    Imm(ImmFilter),
+    Ekf(ExtendedKalmanFilter),
+}
+
+impl TrackFilter {
+    pub fn new(kind: FilterKind, initial_position: Vector2<f32>) -> Self {
+        Self::with_config(initial_position, &TrackerConfig { filter: kind, ..TrackerConfig::default() })
+    }
+
+    /// Filter of the kind and with the noise parameters in `config`
+    pub fn with_config(initial_position: Vector2<f32>, config: &TrackerConfig) -> Self {
+        match config.filter {
+            FilterKind::ConstantAcceleration => TrackFilter::Kalman(
+                KalmanFilter::with_config(initial_position, KinematicModel::ConstantAcceleration, config)
+            ),
+            FilterKind::Imm => TrackFilter::Imm(ImmFilter::with_config(initial_position, config)),
+            FilterKind::Polar(origin) => {
+                let (range, azimuth) = origin.to_polar(initial_position);
+                TrackFilter::Ekf(ExtendedKalmanFilter::with_config(origin, range, azimuth, config))
+            }
+        }
+    }
+
+    pub fn predict(&mut self, dt: f32) {
+        match self {
+            TrackFilter::Kalman(filter) => filter.predict(dt),
+            TrackFilter::Imm(filter) => filter.predict(dt),
+            TrackFilter::Ekf(filter) => filter.predict(dt),
+        }
+    }
+
+    pub fn update(&mut self, measurement: Vector2<f32>) -> Result<(), FilterError> {
+        match self {
+            TrackFilter::Kalman(filter) => filter.update(measurement),
+            TrackFilter::Imm(filter) => filter.update(measurement),
+            TrackFilter::Ekf(filter) => filter.update(measurement),
+        }
+    }
+
+    pub fn get_innovation_covariance(&self) -> Matrix2<f32> {
+        match self {
+            TrackFilter::Kalman(filter) => filter.get_innovation_covariance(),
+            TrackFilter::Imm(filter) => filter.get_innovation_covariance(),
+            TrackFilter::Ekf(filter) => filter.get_innovation_covariance(),
+        }
+    }
+
+    pub fn mahalanobis_distance_squared(&self, measurement: Vector2<f32>) -> Option<f32> {
+        let innovation = measurement - self.get_position();
+        invert_covariance(&self.get_innovation_covariance())
+            .ok()
+            .map(|inverse| (innovation.transpose() * inverse * innovation)[(0, 0)])
+    }
+
+    pub fn gate(&self, measurement: Vector2<f32>, threshold: f32) -> bool {
+        self.mahalanobis_distance_squared(measurement)
+            .is_some_and(|distance| distance <= threshold)
+    }
+
+    pub fn get_position(&self) -> Vector2<f32> {
+        match self {
+            TrackFilter::Kalman(filter) => filter.get_position(),
+            TrackFilter::Imm(filter) => filter.get_position(),
+            TrackFilter::Ekf(filter) => filter.get_position(),
+        }
+    }
+
+    pub fn get_velocity(&self) -> Vector2<f32> {
+        match self {
+            TrackFilter::Kalman(filter) => filter.get_velocity(),
+            TrackFilter::Imm(filter) => filter.get_velocity(),
+            TrackFilter::Ekf(filter) => filter.get_velocity(),
+        }
+    }
+
+    pub fn get_acceleration(&self) -> Vector2<f32> {
+        match self {
+            TrackFilter::Kalman(filter) => filter.get_acceleration(),
+            TrackFilter::Imm(filter) => filter.get_acceleration(),
+            TrackFilter::Ekf(filter) => filter.get_acceleration(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct FallDetector {
     gravity_threshold: f32,
     velocity_threshold: f32,
     acceleration_threshold: f32,
-    time_window: Duration, // Kept for future use
+    fall_probability_threshold: f32,
+    time_window: Duration,
+    lying_height: f32,
+    recovery_speed: f32,
+    prediction_horizon: Duration,
+    prediction_step: Duration,
+}
+
+impl Default for FallDetector {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl FallDetector {
     #[inline]
     pub fn new() -> Self {
+        Self::from_config(&FallDetectorConfig::default())
+    }
+
+    pub fn from_config(config: &FallDetectorConfig) -> Self {
         Self {
-            gravity_threshold: -9.5,  // m/s²
-            velocity_threshold: 2.0,   // m/s
-            acceleration_threshold: 15.0, // m/s²
-            time_window: Duration::from_millis(500),
+            gravity_threshold: config.gravity_threshold,
+            velocity_threshold: config.velocity_threshold,
+            acceleration_threshold: config.acceleration_threshold,
+            fall_probability_threshold: config.fall_probability_threshold,
+            time_window: Duration::from_millis(config.confirmation_window_ms),
+            lying_height: config.lying_height_m,
+            recovery_speed: config.recovery_speed,
+            prediction_horizon: Duration::from_millis(config.prediction_horizon_ms),
+            prediction_step: Duration::from_millis(config.prediction_step_ms.max(1)),
         }
     }
 
-    #[allow(dead_code)]
     #[inline]
     pub fn get_time_window(&self) -> Duration {
         self.time_window
     }
 
+    /// Whether a target with a fall in progress is up again: above lying height when its
+    /// height is known, moving again otherwise
+    fn has_recovered(&self, target: &TrackedTarget) -> bool {
+        target.fall_probability <= self.fall_probability_threshold
+            && match target.vertical {
+                Some(vertical) => vertical.height >= self.lying_height,
+                None => target.velocity.norm() >= self.recovery_speed,
+            }
+    }
+
+    /// Advance a target's [`FallPhase`] at `now`. A fall is suspected as soon as the target is
+    /// falling; confirming it and clearing it both require the condition to hold for the
+    /// confirmation window, so a single noisy frame neither raises nor cancels an alarm.
+    pub fn update_fall_phase(&self, target: &mut TrackedTarget, now: Instant) -> Option<FallEvent> {
+        let next = match target.fall_phase {
+            FallPhase::Normal if self.is_falling(target) => {
+                target.fall_phase = FallPhase::Suspected;
+                target.pending_fall_phase = None;
+                return Some(FallEvent::FallSuspected {
+                    target_id: target.id,
+                    probability: target.fall_probability,
+                    position: target.position,
+                    landing: None,
+                    at: now,
+                });
+            }
+            FallPhase::Normal => return None,
+            _ if self.has_recovered(target) => FallPhase::Normal,
+            _ => FallPhase::Confirmed,
+        };
+
+        if next == target.fall_phase {
+            target.pending_fall_phase = None;
+            return None;
+        }
+        let since = match target.pending_fall_phase {
+            Some((pending, since)) if pending == next => since,
+            _ => {
+                target.pending_fall_phase = Some((next, now));
+                now
+            }
+        };
+        if now.duration_since(since) < self.time_window {
+            return None;
+        }
+
+        target.fall_phase = next;
+        target.pending_fall_phase = None;
+        Some(match next {
+            FallPhase::Confirmed => FallEvent::FallConfirmed {
+                target_id: target.id,
+                position: target.position,
+                at: now,
+            },
+            _ => FallEvent::FallCleared { target_id: target.id, at: now },
+        })
+    }
+
     #[inline]
     pub fn analyze_fall_risk(&self, target: &TrackedTarget) -> f32 {
         let mut risk_score: f32 = 0.0;
 
+        // Real vertical motion for 3D targets, the y axis as a stand-in for 2D ones
+        let (vertical_velocity, vertical_acceleration, accel_magnitude, speed) = match target.vertical {
+            Some(vertical) => (
+                vertical.velocity,
+                vertical.acceleration,
+                (target.acceleration.norm_squared() + vertical.acceleration.powi(2)).sqrt(),
+                (target.velocity.norm_squared() + vertical.velocity.powi(2)).sqrt(),
+            ),
+            None => (
+                target.velocity.y,
+                target.acceleration.y,
+                target.acceleration.norm(),
+                target.velocity.norm(),
+            ),
+        };
+
         // Check for downward acceleration (free fall)
-        if target.acceleration.y < self.gravity_threshold {
+        if vertical_acceleration < self.gravity_threshold {
             risk_score += 0.4;
         }
 
         // Check for high downward velocity
-        if target.velocity.y < -self.velocity_threshold {
+        if vertical_velocity < -self.velocity_threshold {
             risk_score += 0.3;
         }
 
         // Check for sudden acceleration changes
-        let accel_magnitude = target.acceleration.norm();
         if accel_magnitude > self.acceleration_threshold {
             risk_score += 0.2;
         }
 
         // Check for rapid position changes
-        if target.velocity.norm() > self.velocity_threshold * 2.0 {
+        if speed > self.velocity_threshold * 2.0 {
             risk_score += 0.1;
         }
 
         risk_score.min(1.0)
     }
 
+    /// Whether a confirmed target's fall probability is above the configured threshold
     #[inline]
-    pub fn predict_fall_trajectory(&self, target: &TrackedTarget, time_steps: usize) -> SmallVec<[Vector2<f32>; 10]> {
-        let mut trajectory = SmallVec::new();
-        let mut position = target.position;
-        let mut velocity = target.velocity;
-        let gravity = Vector2::new(0.0, -9.81);
-        let dt = 0.05; // 50ms time steps
-
-        trajectory.reserve(time_steps);
-        
-        for _ in 0..time_steps {
-            velocity += gravity * dt;
-            position += velocity * dt;
-            trajectory.push(position);
+    pub fn is_falling(&self, target: &TrackedTarget) -> bool {
+        target.is_confirmed() && target.fall_probability > self.fall_probability_threshold
+    }
+
+    /// Update a target's fall probability and state
+    #[inline]
+    pub fn assess(&self, target: &mut TrackedTarget) {
+        target.fall_probability = self.analyze_fall_risk(target);
+        if target.fall_probability > self.fall_probability_threshold {
+            target.state = TargetState::Falling;
+        } else {
+            target.state = TargetState::Tracking;
+        }
+    }
+
+    /// Time until a 3D target reaches the floor from its vertical state, `None` when it is not
+    /// heading down or height is unknown
+    pub fn time_to_impact(&self, target: &TrackedTarget) -> Option<Duration> {
+        let vertical = target.vertical?;
+        // Solve height + v t + a t² / 2 = 0 for the first positive t
+        let (h, v, a) = (vertical.height.max(0.0), vertical.velocity, vertical.acceleration);
+        let t = if a.abs() < 1e-3 {
+            (v < 0.0).then(|| -h / v)?
+        } else {
+            let discriminant = v * v - 2.0 * a * h;
+            if discriminant < 0.0 {
+                return None;
+            }
+            let sqrt = discriminant.sqrt();
+            [(-v - sqrt) / a, (-v + sqrt) / a]
+                .into_iter()
+                .filter(|t| *t >= 0.0)
+                .min_by(f32::total_cmp)?
+        };
+        t.is_finite().then(|| Duration::from_secs_f32(t))
+    }
+
+    /// Propagate a target's filter until the expected impact, or over the configured horizon
+    /// when it can't be told, with the uncertainty of every step
+    pub fn predict_fall_trajectory(&self, target: &TrackedTarget, filter: &TrackFilter) -> FallTrajectory {
+        let time_to_impact = self.time_to_impact(target)
+            .map_or(self.prediction_horizon, |t| t.min(self.prediction_horizon));
+        let steps = (time_to_impact.as_secs_f32() / self.prediction_step.as_secs_f32()).ceil() as usize;
+
+        let mut filter = filter.clone();
+        let mut positions = Vec::with_capacity(steps);
+        let mut covariances = Vec::with_capacity(steps);
+        let mut elapsed = Duration::ZERO;
+        for _ in 0..steps {
+            let dt = self.prediction_step.min(time_to_impact - elapsed);
+            filter.predict(dt.as_secs_f32());
+            elapsed += dt;
+            positions.push(filter.get_position());
+            covariances.push(filter.get_innovation_covariance());
+        }
+
+        let landing = LandingZone::from_covariance(
+            positions.last().copied().unwrap_or(target.position),
+            covariances.last().copied().unwrap_or_else(|| filter.get_innovation_covariance()),
+            time_to_impact,
+        );
+        FallTrajectory {
+            step: self.prediction_step,
+            positions,
+            covariances,
+            landing,
+        }
+    }
+}
+
+/// Squared Mahalanobis radius of the 95% ellipse of a 2D Gaussian
+const LANDING_ZONE_CHI_SQUARED: f32 = 5.991;
+
+/// Where a falling target is expected to hit the floor, as the 95% ellipse of the predicted
+/// position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LandingZone {
+    pub centre: Vector2<f32>,
+    /// Half lengths of the major and minor axes in metres
+    pub semi_axes: (f32, f32),
+    /// Direction of the major axis in radians from the x axis
+    pub orientation: f32,
+    /// Time from the prediction to the impact, the prediction horizon when it is unknown
+    pub time_to_impact: Duration,
+}
+
+impl LandingZone {
+    pub fn from_covariance(centre: Vector2<f32>, covariance: Matrix2<f32>, time_to_impact: Duration) -> Self {
+        let eigen = ((covariance + covariance.transpose()) * 0.5).symmetric_eigen();
+        let (major, minor) = if eigen.eigenvalues[0] >= eigen.eigenvalues[1] { (0, 1) } else { (1, 0) };
+        let axis = |index: usize| (eigen.eigenvalues[index].max(0.0) * LANDING_ZONE_CHI_SQUARED).sqrt();
+        let direction = eigen.eigenvectors.column(major);
+        Self {
+            centre,
+            semi_axes: (axis(major), axis(minor)),
+            orientation: direction[1].atan2(direction[0]),
+            time_to_impact,
+        }
+    }
+
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        let (sin, cos) = self.orientation.sin_cos();
+        let offset = point - self.centre;
+        let (major, minor) = self.semi_axes;
+        let along = offset.x * cos + offset.y * sin;
+        let across = -offset.x * sin + offset.y * cos;
+        if major <= 0.0 || minor <= 0.0 {
+            return offset.norm() <= major.max(minor);
         }
+        (along / major).powi(2) + (across / minor).powi(2) <= 1.0
+    }
+}
 
-        trajectory
+/// Predicted path of a falling target from its current filter state
+#[derive(Debug, Clone, PartialEq)]
+pub struct FallTrajectory {
+    /// Time between consecutive points, the last step may be shorter
+    pub step: Duration,
+    pub positions: Vec<Vector2<f32>>,
+    /// Covariance of each predicted position, as a measurement would see it
+    pub covariances: Vec<Matrix2<f32>>,
+    pub landing: LandingZone,
+}
+
+/// One detection of a frame passed to [`MultiTargetTracker::process_frame`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub position: Vector2<f32>,
+    /// Size of the reflection in metres when the sensor reports it, fed to the classifier
+    pub size: Option<f32>,
+}
+
+impl Measurement {
+    pub fn new(position: Vector2<f32>) -> Self {
+        Self { position, size: None }
+    }
+}
+
+/// Targets of one antenna against its capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AntennaLoad {
+    pub antenna_id: u8,
+    pub targets: usize,
+    pub capacity: usize,
+    /// Detections dropped since the start because the antenna was full
+    pub rejected: u64,
+}
+
+impl AntennaLoad {
+    /// Fraction of the capacity in use, 1 when full
+    pub fn saturation(&self) -> f32 {
+        if self.capacity == 0 {
+            1.0
+        } else {
+            self.targets as f32 / self.capacity as f32
+        }
     }
 }
 
+/// What one call to [`MultiTargetTracker::process_frame`] changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameUpdate {
+    /// Target each measurement updated or created, `None` where it was rejected
+    pub assignments: Vec<Option<u32>>,
+    /// Targets created from measurements no track claimed
+    pub created: Vec<u32>,
+    /// Targets that got no measurement in this frame
+    pub missed: Vec<u32>,
+    /// Targets deleted, unconfirmed within the window or lost for too long
+    pub removed: Vec<u32>,
+}
+
 #[derive(Debug, Clone)]
 pub struct MultiTargetTracker {
-    targets: HashMap<u32, TrackedTarget>,
-    kalman_filters: HashMap<u32, KalmanFilter>,
+    tracks: TrackStore,
+    classifier: TargetClassifier,
+    fall_subscribers: Vec<mpsc::Sender<FallEvent>>,
     fall_detector: FallDetector,
     next_target_id: u32,
-    max_targets_per_antenna: usize,
-    antenna_count: u8, // Kept for validation
+    antenna_count: u8,
+    /// Detections dropped per antenna because it was at capacity
+    saturation_rejections: HashMap<u8, u64>,
+    config: TrackerConfig,
+    clock: Arc<dyn Clock>,
+    /// Tracks removed since the last [`MultiTargetTracker::take_completed_tracks`], `None`
+    /// unless they are kept
+    completed: Option<Vec<Track>>,
 }
 
 impl MultiTargetTracker {
     pub fn new(antenna_count: u8) -> Self {
-        Self {
-            targets: HashMap::new(),
-            kalman_filters: HashMap::new(),
-            fall_detector: FallDetector::new(),
+        Self::with_config(antenna_count, TrackerConfig::default())
+    }
+
+    pub fn with_config(antenna_count: u8, config: TrackerConfig) -> Self {
+        let mut tracker = Self {
+            tracks: TrackStore::default(),
+            classifier: TargetClassifier::new(),
+            fall_subscribers: Vec::new(),
+            fall_detector: FallDetector::from_config(&config.fall_detector),
             next_target_id: 0,
-            max_targets_per_antenna: 8,
             antenna_count,
-        }
+            saturation_rejections: HashMap::new(),
+            config,
+            clock: Arc::new(SystemClock),
+            completed: None,
+        };
+        tracker.set_confirmation(tracker.config.confirm_hits, tracker.config.confirm_window);
+        tracker
     }
 
-    #[allow(dead_code)]
-    pub fn get_antenna_count(&self) -> u8 {
-        self.antenna_count
+    /// Receive [`FallEvent`]s from now on. Any number of receivers can be open, dropped ones
+    /// are forgotten on the next event.
+    pub fn events(&mut self) -> mpsc::Receiver<FallEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.fall_subscribers.push(sender);
+        receiver
     }
 
-    #[inline]
-    pub fn add_target(&mut self, antenna_id: u8, position: Vector2<f32>) -> Option<u32> {
-        // Check antenna capacity
-        let current_count = self.targets.values()
-            .filter(|t| t.antenna_id == antenna_id)
-            .count();
-        
-        if current_count >= self.max_targets_per_antenna {
-            warn!("Antenna {} at maximum capacity ({} targets)", antenna_id, self.max_targets_per_antenna);
-            return None;
+    /// Send [`FallEvent`]s to an existing channel
+    pub fn subscribe(&mut self, sender: mpsc::Sender<FallEvent>) {
+        self.fall_subscribers.push(sender);
+    }
+
+    fn update_fall_phase(&mut self, target_id: u32, now: Instant) {
+        let Some(Track { target, filter, .. }) = self.tracks.get_mut(target_id) else {
+            return;
+        };
+        if let Some(mut event) = self.fall_detector.update_fall_phase(target, now) {
+            if let FallEvent::FallSuspected { landing, .. } = &mut event {
+                *landing = Some(self.fall_detector.predict_fall_trajectory(target, filter).landing);
+            }
+            info!("Fall event: {:?}", event);
+            self.fall_subscribers.retain(|sender| sender.send(event.clone()).is_ok());
+        }
+    }
+
+    /// Take timestamps from `clock` instead of the wall clock, e.g. a
+    /// [`SimulatedClock`](crate::clock::SimulatedClock) when replaying a recording
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    pub fn get_clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    pub fn get_config(&self) -> &TrackerConfig {
+        &self.config
+    }
+
+    pub fn get_filter_kind(&self) -> FilterKind {
+        self.config.filter
+    }
+
+    /// Filter used for targets added from now on
+    pub fn set_filter_kind(&mut self, kind: FilterKind) {
+        self.config.filter = kind;
+    }
+
+    /// Detections and frames needed to confirm a new target
+    pub fn get_confirmation(&self) -> (u32, u32) {
+        (self.config.confirm_hits, self.config.confirm_window)
+    }
+
+    /// Confirm new targets once seen in `hits` of their first `window` frames (at most 32).
+    /// A single hit confirms immediately.
+    pub fn set_confirmation(&mut self, hits: u32, window: u32) {
+        self.config.confirm_window = window.clamp(1, 32);
+        self.config.confirm_hits = hits.clamp(1, self.config.confirm_window);
+    }
+
+    pub fn get_mahalanobis_gate(&self) -> f32 {
+        self.config.mahalanobis_gate
+    }
+
+    /// Squared Mahalanobis distance from a target's prediction beyond which measurements are
+    /// rejected
+    pub fn set_mahalanobis_gate(&mut self, gate: f32) {
+        self.config.mahalanobis_gate = gate;
+    }
+
+    pub fn get_association_gate(&self) -> f32 {
+        self.config.association_gate_m
+    }
+
+    /// Furthest a measurement may be from a target, in metres, to be associated with it
+    pub fn set_association_gate(&mut self, gate: f32) {
+        self.config.association_gate_m = gate;
+    }
+
+    pub fn get_antenna_count(&self) -> u8 {
+        self.antenna_count
+    }
+
+    pub fn validate_antenna(&self, antenna_id: u8) -> Result<(), TrackerError> {
+        if antenna_id < self.antenna_count {
+            Ok(())
+        } else {
+            Err(TrackerError::InvalidAntenna { antenna_id, antenna_count: self.antenna_count })
+        }
+    }
+
+    /// Most targets `antenna_id` may track at once
+    pub fn get_antenna_capacity(&self, antenna_id: u8) -> usize {
+        self.config.antenna_capacities.iter()
+            .find(|capacity| capacity.antenna_id == antenna_id)
+            .map_or(self.config.max_targets_per_antenna, |capacity| capacity.max_targets)
+    }
+
+    pub fn set_antenna_capacity(&mut self, antenna_id: u8, max_targets: usize) {
+        let capacities = &mut self.config.antenna_capacities;
+        match capacities.iter_mut().find(|capacity| capacity.antenna_id == antenna_id) {
+            Some(capacity) => capacity.max_targets = max_targets,
+            None => capacities.push(AntennaCapacity { antenna_id, max_targets }),
+        }
+    }
+
+    /// Occupancy of every antenna against its capacity
+    pub fn get_antenna_loads(&self) -> Vec<AntennaLoad> {
+        (0..self.antenna_count)
+            .map(|antenna_id| AntennaLoad {
+                antenna_id,
+                targets: self.get_target_count_by_antenna(antenna_id),
+                capacity: self.get_antenna_capacity(antenna_id),
+                rejected: self.saturation_rejections.get(&antenna_id).copied().unwrap_or(0),
+            })
+            .collect()
+    }
+
+    #[inline]
+    pub fn add_target(&mut self, antenna_id: u8, position: Vector2<f32>) -> Option<u32> {
+        self.try_add_target(antenna_id, position)
+            .inspect_err(|e| warn!("Not adding target at ({:.2}, {:.2}): {}", position.x, position.y, e))
+            .ok()
+    }
+
+    /// Start a target, failing for an antenna the tracker doesn't have or one at capacity
+    pub fn try_add_target(&mut self, antenna_id: u8, position: Vector2<f32>) -> Result<u32, TrackerError> {
+        self.add_target_at(antenna_id, position, self.now())
+    }
+
+    fn add_target_at(&mut self, antenna_id: u8, position: Vector2<f32>, now: Instant) -> Result<u32, TrackerError> {
+        self.validate_antenna(antenna_id)?;
+        let capacity = self.get_antenna_capacity(antenna_id);
+        if self.get_target_count_by_antenna(antenna_id) >= capacity {
+            *self.saturation_rejections.entry(antenna_id).or_default() += 1;
+            return Err(TrackerError::AntennaSaturated { antenna_id, capacity });
         }
 
         let target_id = self.next_target_id;
         self.next_target_id += 1;
 
-        let target = TrackedTarget::new(target_id, antenna_id, position);
-        let kalman_filter = KalmanFilter::new(position);
+        let mut target = TrackedTarget::new_at(target_id, antenna_id, position, now);
+        if self.config.confirm_hits <= 1 {
+            target.status = TrackStatus::Confirmed;
+        }
+        let kalman_filter = TrackFilter::with_config(position, &self.config);
+        let history = TrackHistory::new(self.config.history_length);
 
-        self.targets.insert(target_id, target);
-        self.kalman_filters.insert(target_id, kalman_filter);
+        self.tracks.insert(Track::new(target, kalman_filter, history));
+        self.record_history(target_id);
 
         info!("Added target {} to antenna {} at ({:.2}, {:.2})", 
               target_id, antenna_id, position.x, position.y);
 
-        Some(target_id)
+        Ok(target_id)
     }
 
     #[inline]
     pub fn update_target(&mut self, target_id: u32, new_position: Vector2<f32>) -> bool {
-        if let (Some(target), Some(kalman_filter)) = 
-            (self.targets.get_mut(&target_id), self.kalman_filters.get_mut(&target_id)) {
+        self.update_target_at(target_id, new_position, self.now())
+    }
+
+    fn update_target_at(&mut self, target_id: u32, new_position: Vector2<f32>, now: Instant) -> bool {
+        if let Some(Track { target, filter: kalman_filter, .. }) = self.tracks.get_mut(target_id) {
             
-            let now = Instant::now();
-            let dt = (now - target.last_update).as_secs_f32();
+            let dt = now.saturating_duration_since(target.last_update).as_secs_f32();
             
             if dt > 0.0 {
-                // Update Kalman filter
-                kalman_filter.predict(dt);
-                kalman_filter.update(new_position);
+                // Reject measurements the prediction cannot explain, so one bad frame doesn't
+                // yank the track away
+                let mut predicted = kalman_filter.clone();
+                predicted.predict(dt);
+                if !predicted.gate(new_position, self.config.mahalanobis_gate) {
+                    warn!("Rejected measurement ({:.2}, {:.2}) for target {}: outside gate",
+                          new_position.x, new_position.y, target_id);
+                    return false;
+                }
+
+                // A corrupted frame can still sit inside a wide gate, reject what no person
+                // could have moved to
+                let implied_speed = (new_position - predicted.get_position()).norm() / dt.max(MIN_LIMIT_INTERVAL);
+                if implied_speed > self.config.max_speed {
+                    warn!("Rejected measurement ({:.2}, {:.2}) for target {}: implies {:.1} m/s",
+                          new_position.x, new_position.y, target_id, implied_speed);
+                    return false;
+                }
+
+                // Update Kalman filter, keeping the previous estimate if the update fails
+                if let Err(e) = predicted.update(new_position) {
+                    warn!("Skipped update of target {}: {}", target_id, e);
+                    return false;
+                }
+                *kalman_filter = predicted;
                 
                 // Update target with filtered values
                 let filtered_pos = kalman_filter.get_position();
-                target.update_position(filtered_pos, dt);
-                target.velocity = kalman_filter.get_velocity();
-                target.acceleration = kalman_filter.get_acceleration();
+                target.update_position(filtered_pos, dt, now);
+                target.last_presence = now;
+                target.velocity = cap_norm(kalman_filter.get_velocity(), self.config.max_speed);
+                target.acceleration = cap_norm(kalman_filter.get_acceleration(), self.config.max_acceleration);
                 
-                // Analyze fall risk
-                target.fall_probability = self.fall_detector.analyze_fall_risk(target);
-                if target.fall_probability > 0.7 {
-                    target.state = TargetState::Falling;
-                } else {
-                    target.state = TargetState::Tracking;
+                target.record_frame(true, self.config.confirm_window);
+                if target.status == TrackStatus::Tentative && target.get_hit_count() >= self.config.confirm_hits {
+                    target.status = TrackStatus::Confirmed;
+                    info!("Confirmed target {} after {} frames", target_id, target.frame_count);
                 }
+
+                // Analyze fall risk
+                self.fall_detector.assess(target);
                 
                 debug!("Updated target {}: pos=({:.2}, {:.2}), vel=({:.2}, {:.2}), fall_risk={:.2}", 
                        target_id, target.position.x, target.position.y, 
                        target.velocity.x, target.velocity.y, target.fall_probability);
                 
+                self.tracks.reindex(target_id);
+                self.record_history(target_id);
+                self.classify_target(target_id);
+                self.update_fall_phase(target_id, now);
                 true
             } else {
                 false
@@ -340,86 +1421,652 @@ impl MultiTargetTracker {
         }
     }
 
-    pub fn predict_all_targets(&mut self, prediction_time: Duration) {
-        let dt = prediction_time.as_secs_f32();
-        
-        for (target_id, target) in &mut self.targets {
-            if let Some(kalman_filter) = self.kalman_filters.get_mut(target_id) {
-                kalman_filter.predict(dt);
-                target.position = kalman_filter.get_position();
-                target.velocity = kalman_filter.get_velocity();
-                target.acceleration = kalman_filter.get_acceleration();
-                target.state = TargetState::Predicted;
-                target.prediction_count += 1;
-                target.confidence *= 0.9; // Decrease confidence with predictions
+    /// States of a target over the last `duration` of updates, oldest first
+    pub fn get_track_history(&self, target_id: u32, duration: Duration) -> Option<Vec<TrackPoint>> {
+        self.tracks.get(target_id)
+            .map(|track| track.history.since(duration).copied().collect())
+    }
+
+    /// Full retained history of a target, with path statistics
+    pub fn get_history(&self, target_id: u32) -> Option<&TrackHistory> {
+        self.tracks.get(target_id).map(|track| &track.history)
+    }
+
+    /// History of a target smoothed with the filter's noise levels, see [`RtsSmoother`]
+    pub fn get_smoothed_history(&self, target_id: u32) -> Option<Vec<TrackPoint>> {
+        self.tracks.get(target_id)
+            .map(|track| RtsSmoother::from_config(&self.config).smooth(&track.history))
+    }
+
+    fn record_history(&mut self, target_id: u32) {
+        if let Some(Track { target, history, .. }) = self.tracks.get_mut(target_id) {
+            history.push(TrackPoint {
+                timestamp: target.last_update,
+                position: target.position,
+                velocity: target.velocity,
+                height: target.vertical.map(|vertical| vertical.height),
+            });
+        }
+    }
+
+    fn classify_target(&mut self, target_id: u32) {
+        if let Some(Track { target, history, .. }) = self.tracks.get_mut(target_id) {
+            let class = self.classifier.classify(target, history);
+            if class != target.class {
+                debug!("Target {} classified as {:?}", target_id, class);
+                target.class = class;
             }
         }
     }
 
-    pub fn remove_lost_targets(&mut self, timeout: Duration) {
-        let now = Instant::now();
-        let mut to_remove = Vec::new();
+    /// Record the reflection size of a target in metres for classification
+    pub fn observe_size(&mut self, target_id: u32, size: f32) {
+        if self.tracks.contains(target_id) {
+            self.classifier.observe_size(target_id, size);
+        }
+    }
 
-        for (target_id, target) in &self.targets {
-            if now.duration_since(target.last_update) > timeout || 
-               target.confidence < 0.1 || 
-               target.prediction_count > 10 {
-                to_remove.push(*target_id);
+    /// Record micro-motion energy at a target's range, 0 to 1, for classification. Energy
+    /// above [`TrackerConfig::presence_energy_threshold`] also keeps a still target alive.
+    pub fn observe_micro_motion(&mut self, target_id: u32, energy: f32) {
+        if self.tracks.contains(target_id) {
+            self.classifier.observe_micro_motion(target_id, energy);
+            if energy >= self.config.presence_energy_threshold {
+                self.mark_present(target_id);
+            }
+        }
+    }
+
+    /// Record a detection reported at `speed` m/s, like an LD2450 target. A near-still one
+    /// keeps the closest target of the antenna within the association gate alive, which is
+    /// returned.
+    pub fn observe_stationary_detection(&mut self, antenna_id: u8, position: Vector2<f32>, speed: f32) -> Option<u32> {
+        if speed.abs() > self.config.presence_max_speed {
+            return None;
+        }
+        let target_id = self.tracks.within(position, self.config.association_gate_m)
+            .into_iter()
+            .filter(|track| track.target.antenna_id == antenna_id)
+            .min_by(|a, b| {
+                let a = (a.target.position - position).norm_squared();
+                let b = (b.target.position - position).norm_squared();
+                a.total_cmp(&b)
+            })
+            .map(|track| track.target.id)?;
+        self.mark_present(target_id);
+        Some(target_id)
+    }
+
+    /// Hold a target that stopped producing updates where it is, in
+    /// [`TargetState::StationaryPresence`]: one coasting on predictions, or a confirmed one the
+    /// latest frames missed when they come without predictions in between
+    fn mark_present(&mut self, target_id: u32) {
+        let now = self.now();
+        let Some(Track { target, filter, .. }) = self.tracks.get_mut(target_id) else {
+            return;
+        };
+        target.last_presence = now;
+        if target.state == TargetState::Falling || target.state == TargetState::StationaryPresence {
+            return;
+        }
+        let missed = target.is_confirmed() && target.hit_history.trailing_zeros() >= PRESENCE_MISSED_FRAMES;
+        if target.state == TargetState::Predicted || missed {
+            // The person stopped, drop the velocity the filter keeps coasting on
+            *filter = TrackFilter::with_config(target.position, &self.config);
+            target.velocity = Vector2::zeros();
+            target.acceleration = Vector2::zeros();
+            target.prediction_count = 0;
+            target.state = TargetState::StationaryPresence;
+            debug!("Target {} stationary, kept by presence", target_id);
+        }
+    }
+
+    /// Add a target measured in 3D, `position.z` being its height above the floor
+    pub fn add_target_3d(&mut self, antenna_id: u8, position: Vector3<f32>) -> Option<u32> {
+        let target_id = self.add_target(antenna_id, position.xy())?;
+        let filter = HeightFilter::with_config(position.z, &self.config);
+        if let Some(track) = self.tracks.get_mut(target_id) {
+            track.target.vertical = Some(filter.get_state());
+            track.height_filter = Some(filter);
+        }
+        self.record_history(target_id);
+        Some(target_id)
+    }
+
+    /// Update a target with a 3D measurement, the floor-plane part going through the same
+    /// gating as [`MultiTargetTracker::update_target`]. A 2D target starts tracking height.
+    pub fn update_target_3d(&mut self, target_id: u32, new_position: Vector3<f32>) -> bool {
+        let Some(dt) = self.get_target(target_id)
+            .map(|target| self.now().saturating_duration_since(target.last_update).as_secs_f32()) else {
+            return false;
+        };
+        if !self.update_target(target_id, new_position.xy()) {
+            return false;
+        }
+
+        let Some(Track { target, height_filter, .. }) = self.tracks.get_mut(target_id) else {
+            return false;
+        };
+        let filter = height_filter
+            .get_or_insert_with(|| HeightFilter::with_config(new_position.z, &self.config));
+        let mut predicted = filter.clone();
+        predicted.predict(dt);
+        match predicted.update(new_position.z) {
+            Ok(()) => *filter = predicted,
+            Err(e) => warn!("Skipped height update of target {}: {}", target_id, e),
+        }
+
+        target.vertical = Some(filter.get_state());
+        self.fall_detector.assess(target);
+        self.record_history(target_id);
+        let now = self.now();
+        self.update_fall_phase(target_id, now);
+        true
+    }
+
+    /// Match a frame of measurements to the current targets by Mahalanobis distance from each
+    /// target's prediction. Pairs further apart than `gate` metres or outside the Mahalanobis
+    /// gate are never matched.
+    pub fn associate(&self, measurements: &[Vector2<f32>], gate: f32) -> Association {
+        self.associate_at(measurements, gate, self.now(), None)
+    }
+
+    /// Association at `now`, restricted to the tracks of one antenna when given
+    fn associate_at(&self, measurements: &[Vector2<f32>], gate: f32, now: Instant, antenna_id: Option<u8>) -> Association {
+        let owned = |target: &TrackedTarget| antenna_id.is_none_or(|id| target.antenna_id == id);
+        let mut target_ids: Vec<u32> = self.targets()
+            .filter(|t| owned(t))
+            .map(|t| t.id)
+            .collect();
+        target_ids.sort_unstable();
+
+        // Only pairs the spatial index finds within the gate are scored, predicting each
+        // nearby track once
+        let mut predicted: HashMap<u32, TrackFilter> = HashMap::new();
+        let mut costs: HashMap<(u32, usize), f32> = HashMap::new();
+        for (index, &measurement) in measurements.iter().enumerate() {
+            for track in self.tracks.within(measurement, gate).into_iter().filter(|t| owned(&t.target)) {
+                let filter = predicted.entry(track.target.id).or_insert_with(|| {
+                    let mut filter = track.filter.clone();
+                    filter.predict(now.saturating_duration_since(track.target.last_update).as_secs_f32());
+                    filter
+                });
+                if let Some(distance) = filter.mahalanobis_distance_squared(measurement)
+                    .filter(|distance| *distance <= self.config.mahalanobis_gate) {
+                    costs.insert((track.target.id, index), distance);
+                }
+            }
+        }
+
+        associate(&target_ids, measurements.len(), |target_id, index| {
+            costs.get(&(target_id, index)).copied()
+        })
+    }
+
+    /// Update the targets with a whole frame of `(antenna_id, position)` measurements at once,
+    /// creating targets for measurements no target claims. Returns the target each measurement
+    /// updated or created, `None` where nothing changed.
+    ///
+    /// A track left without a measurement because a close track took the only one is taken to
+    /// be occluded: it coasts without counting a miss and both are flagged
+    /// [`TrackInteraction::Merged`]. A measurement appearing next to a track starts a new one
+    /// flagged [`TrackInteraction::Split`]. The flags clear once the tracks are `gate` apart.
+    pub fn update_targets(&mut self, measurements: &[(u8, Vector2<f32>)], gate: f32) -> Vec<Option<u32>> {
+        // Measurements of antennas the tracker doesn't have are dropped before association
+        let valid: Vec<usize> = (0..measurements.len())
+            .filter(|&index| {
+                let antenna_id = measurements[index].0;
+                self.validate_antenna(antenna_id)
+                    .inspect_err(|e| warn!("Dropped measurement: {}", e))
+                    .is_ok()
+            })
+            .collect();
+        let frame: Vec<(u8, Vector2<f32>)> = valid.iter().map(|&index| measurements[index]).collect();
+
+        let now = self.now();
+        let assignments = self.apply_frame(&frame, gate, now, None).assignments;
+        let mut updated = vec![None; measurements.len()];
+        for (index, target_id) in valid.into_iter().zip(assignments) {
+            updated[index] = target_id;
+        }
+        updated
+    }
+
+    /// Process one frame of `antenna_id` taken at `timestamp`: associate the measurements with
+    /// that antenna's tracks, update the matched ones, start tracks for the rest and delete
+    /// tracks that failed confirmation or expired under the [`TrackDeletionPolicy`]. Tracks of
+    /// other antennas are left alone.
+    pub fn process_frame(&mut self, antenna_id: u8, measurements: &[Measurement], timestamp: Instant) -> Result<FrameUpdate, TrackerError> {
+        self.validate_antenna(antenna_id)?;
+        let frame: Vec<(u8, Vector2<f32>)> = measurements.iter()
+            .map(|measurement| (antenna_id, measurement.position))
+            .collect();
+        let gate = self.config.association_gate_m;
+        let mut update = self.apply_frame(&frame, gate, timestamp, Some(antenna_id));
+
+        for (measurement, target_id) in measurements.iter().zip(&update.assignments) {
+            if let (Some(size), Some(target_id)) = (measurement.size, target_id) {
+                self.observe_size(*target_id, size);
+            }
+        }
+
+        for target_id in self.find_lost_targets(timestamp) {
+            if self.get_target(target_id).is_some_and(|t| t.antenna_id == antenna_id) {
+                self.remove_target(target_id);
+                info!("Removed lost target {}", target_id);
+                update.removed.push(target_id);
+            }
+        }
+        Ok(update)
+    }
+
+    fn apply_frame(&mut self, measurements: &[(u8, Vector2<f32>)], gate: f32, now: Instant, antenna_id: Option<u8>) -> FrameUpdate {
+        let positions: Vec<Vector2<f32>> = measurements.iter().map(|(_, p)| *p).collect();
+        let association = self.associate_at(&positions, gate, now, antenna_id);
+        let mut updated = vec![None; measurements.len()];
+
+        let mut missed = association.unassigned_tracks;
+        for (target_id, index) in association.assigned {
+            if self.update_target_at(target_id, positions[index], now) {
+                updated[index] = Some(target_id);
+            } else {
+                missed.push(target_id);
+            }
+        }
+        for &target_id in &missed {
+            let partner = self.get_target(target_id).and_then(|target| {
+                updated.iter().zip(&positions).find_map(|(owner, position)| {
+                    owner.filter(|_| (target.position - position).norm() < gate)
+                })
+            });
+            if let Some(partner) = partner {
+                debug!("Target {} merged with target {}, coasting", target_id, partner);
+                self.set_interaction(target_id, partner, |with| TrackInteraction::Merged { with });
+            } else if let Some(track) = self.tracks.get_mut(target_id) {
+                track.target.record_frame(false, self.config.confirm_window);
+            }
+        }
+        let removed = self.remove_failed_tentative_targets();
+
+        let mut created = Vec::new();
+        for index in association.unassigned_measurements {
+            let (antenna_id, position) = measurements[index];
+            let parent = self.tracks.nearest(position, gate).map(|track| track.target.id);
+            updated[index] = self.add_target_at(antenna_id, position, now)
+                .inspect_err(|e| debug!("Measurement {} starts no target: {}", index, e))
+                .ok();
+            if let Some(target_id) = updated[index] {
+                created.push(target_id);
+                if let Some(parent) = parent {
+                    debug!("Target {} split from target {}", target_id, parent);
+                    self.set_interaction(target_id, parent, |with| TrackInteraction::Split { with });
+                }
+            }
+        }
+
+        self.resolve_interactions(gate);
+        FrameUpdate {
+            assignments: updated,
+            created,
+            missed,
+            removed,
+        }
+    }
+
+    /// Flag two tracks as interacting with each other
+    fn set_interaction(&mut self, a: u32, b: u32, interaction: impl Fn(u32) -> TrackInteraction) {
+        if let Some(target) = self.target_mut(a) {
+            target.interaction = Some(interaction(b));
+        }
+        if let Some(target) = self.target_mut(b) {
+            target.interaction = Some(interaction(a));
+        }
+    }
+
+    /// Clear the flags of tracks that moved `gate` apart from their partner or lost it
+    fn resolve_interactions(&mut self, gate: f32) {
+        let separated: Vec<u32> = self.targets()
+            .filter(|t| match t.interaction {
+                Some(TrackInteraction::Merged { with } | TrackInteraction::Split { with }) => self.get_target(with)
+                    .is_none_or(|partner| (partner.position - t.position).norm() >= gate),
+                None => false,
+            })
+            .map(|t| t.id)
+            .collect();
+
+        for target_id in separated {
+            if let Some(target) = self.target_mut(target_id) {
+                target.interaction = None;
+                debug!("Target {} separated, identity unambiguous", target_id);
+            }
+        }
+    }
+
+    /// Drop tentative targets that were not confirmed within the confirmation window
+    fn remove_failed_tentative_targets(&mut self) -> Vec<u32> {
+        let window = self.config.confirm_window;
+        let failed: Vec<u32> = self.targets()
+            .filter(|t| t.status == TrackStatus::Tentative && t.frame_count >= window)
+            .map(|t| t.id)
+            .collect();
+
+        for &target_id in &failed {
+            self.remove_target(target_id);
+            debug!("Dropped unconfirmed target {}", target_id);
+        }
+        failed
+    }
+
+    /// Stop tracking a target, returning its last state
+    pub fn remove_target(&mut self, target_id: u32) -> Option<TrackedTarget> {
+        self.classifier.forget(target_id);
+        let track = self.tracks.remove(target_id)?;
+        let target = track.target.clone();
+        if let Some(completed) = &mut self.completed {
+            completed.push(track);
+        }
+        Some(target)
+    }
+
+    /// Keep removed tracks with their history for [`MultiTargetTracker::take_completed_tracks`].
+    /// They pile up until taken.
+    pub fn set_keep_completed_tracks(&mut self, keep: bool) {
+        if keep != self.completed.is_some() {
+            self.completed = keep.then(Vec::new);
+        }
+    }
+
+    /// Tracks removed since the last call, e.g. for a
+    /// [`TrackExporter`](crate::track_export::TrackExporter)
+    pub fn take_completed_tracks(&mut self) -> Vec<Track> {
+        self.completed.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    pub fn get_target(&self, target_id: u32) -> Option<&TrackedTarget> {
+        self.tracks.get(target_id).map(|track| &track.target)
+    }
+
+    fn target_mut(&mut self, target_id: u32) -> Option<&mut TrackedTarget> {
+        self.tracks.get_mut(target_id).map(|track| &mut track.target)
+    }
+
+    fn targets(&self) -> impl Iterator<Item = &TrackedTarget> {
+        self.tracks.iter().map(|track| &track.target)
+    }
+
+    /// Closest target to `position` within `radius` metres
+    pub fn find_nearest_target(&self, position: Vector2<f32>, radius: f32) -> Option<&TrackedTarget> {
+        self.tracks.nearest(position, radius).map(|track| &track.target)
+    }
+
+    /// Targets within `radius` metres of `position`
+    pub fn find_targets_within(&self, position: Vector2<f32>, radius: f32) -> Vec<&TrackedTarget> {
+        self.tracks.within(position, radius).into_iter().map(|track| &track.target).collect()
+    }
+
+    pub fn predict_all_targets(&mut self, prediction_time: Duration) {
+        let dt = prediction_time.as_secs_f32();
+        
+        for Track { target, filter: kalman_filter, height_filter, .. } in self.tracks.iter_mut() {
+            if target.state == TargetState::StationaryPresence {
+                continue;
+            }
+            kalman_filter.predict(dt);
+            target.position = kalman_filter.get_position();
+            target.velocity = kalman_filter.get_velocity();
+            target.acceleration = kalman_filter.get_acceleration();
+            if let Some(height_filter) = height_filter {
+                height_filter.predict(dt);
+                target.vertical = Some(height_filter.get_state());
             }
+            target.state = TargetState::Predicted;
+            target.prediction_count += 1;
+            target.confidence *= 0.9; // Decrease confidence with predictions
         }
+        self.tracks.reindex_all();
 
-        for target_id in to_remove {
-            self.targets.remove(&target_id);
-            self.kalman_filters.remove(&target_id);
+        // A person lying still produces no updates, confirm their fall as time passes
+        let now = self.now();
+        let falling: Vec<u32> = self.targets()
+            .filter(|t| t.fall_phase != FallPhase::Normal)
+            .map(|t| t.id)
+            .collect();
+        for target_id in falling {
+            self.update_fall_phase(target_id, now);
+        }
+    }
+
+    /// Delete the targets that expired under the configured [`TrackDeletionPolicy`], returning
+    /// their last states
+    pub fn remove_lost_targets(&mut self) -> Vec<TrackedTarget> {
+        let mut removed = Vec::new();
+        for target_id in self.find_lost_targets(self.now()) {
+            removed.extend(self.remove_target(target_id));
             info!("Removed lost target {}", target_id);
         }
+        removed
+    }
+
+    pub fn get_deletion_policy(&self) -> &TrackDeletionPolicy {
+        &self.config.deletion
+    }
+
+    pub fn set_deletion_policy(&mut self, policy: TrackDeletionPolicy) {
+        self.config.deletion = policy;
+    }
+
+    fn find_lost_targets(&self, now: Instant) -> Vec<u32> {
+        self.targets()
+            .filter(|target| self.config.deletion.is_expired(target, now))
+            .map(|target| target.id)
+            .collect()
     }
 
     pub fn get_falling_targets(&self) -> Vec<&TrackedTarget> {
-        self.targets.values()
-            .filter(|t| t.is_falling())
+        self.targets()
+            .filter(|t| self.fall_detector.is_falling(t))
+            .collect()
+    }
+
+    pub fn get_confirmed_targets(&self) -> Vec<&TrackedTarget> {
+        self.targets()
+            .filter(|t| t.is_confirmed())
+            .collect()
+    }
+
+    pub fn get_target_count_by_status(&self, status: TrackStatus) -> usize {
+        self.targets()
+            .filter(|t| t.status == status)
+            .count()
+    }
+
+    pub fn get_targets_by_class(&self, class: TargetClass) -> Vec<&TrackedTarget> {
+        self.targets()
+            .filter(|t| t.class == class)
+            .collect()
+    }
+
+    /// Confirmed targets not classified as a pet or clutter, for automations that must ignore
+    /// animals. Targets still unclassified are included.
+    pub fn get_human_targets(&self) -> Vec<&TrackedTarget> {
+        self.targets()
+            .filter(|t| t.is_confirmed() && matches!(t.class, TargetClass::Human | TargetClass::Unknown))
             .collect()
     }
 
     pub fn get_targets_by_antenna(&self, antenna_id: u8) -> Vec<&TrackedTarget> {
-        self.targets.values()
+        self.targets()
             .filter(|t| t.antenna_id == antenna_id)
             .collect()
     }
 
     pub fn get_target_count(&self) -> usize {
-        self.targets.len()
+        self.tracks.len()
     }
 
     pub fn get_target_count_by_antenna(&self, antenna_id: u8) -> usize {
-        self.targets.values()
+        self.targets()
             .filter(|t| t.antenna_id == antenna_id)
             .count()
     }
 
     pub fn get_all_targets(&self) -> Vec<&TrackedTarget> {
-        self.targets.values().collect()
+        self.targets().collect()
     }
 
-    #[inline]
-    pub fn get_fall_predictions(&self, target_id: u32, time_steps: usize) -> Option<SmallVec<[Vector2<f32>; 10]>> {
-        if let Some(target) = self.targets.get(&target_id) {
-            Some(self.fall_detector.predict_fall_trajectory(target, time_steps))
-        } else {
-            None
-        }
+    /// Predicted fall path and landing zone of a target from its current filter state
+    pub fn get_fall_trajectory(&self, target_id: u32) -> Option<FallTrajectory> {
+        self.tracks.get(target_id)
+            .map(|track| self.fall_detector.predict_fall_trajectory(&track.target, &track.filter))
     }
 
     pub fn clear_all_targets(&mut self) {
-        self.targets.clear();
-        self.kalman_filters.clear();
+        if let Some(completed) = &mut self.completed {
+            completed.extend(self.tracks.iter().cloned());
+        }
+        self.tracks.clear();
+        self.classifier.clear();
         info!("Cleared all tracked targets");
     }
 }
 
+/// State of one track in a [`TrackerSnapshot`]
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TargetSnapshot {
+    pub id: u32,
+    pub antenna_id: u8,
+    pub position: Vector2<f32>,
+    pub velocity: Vector2<f32>,
+    pub acceleration: Vector2<f32>,
+    pub state: TargetState,
+    pub confidence: f32,
+    /// Time between the last update and the snapshot
+    pub since_update: Duration,
+    /// Time between the last presence report and the snapshot
+    #[serde(default)]
+    pub since_presence: Duration,
+    pub prediction_count: u32,
+    pub fall_probability: f32,
+    pub status: TrackStatus,
+    pub hit_history: u32,
+    pub frame_count: u32,
+    pub vertical: Option<VerticalState>,
+    pub fall_phase: FallPhase,
+    pub interaction: Option<TrackInteraction>,
+    pub class: TargetClass,
+    pub filter: TrackFilter,
+    pub height_filter: Option<HeightFilter>,
+}
+
+/// Everything needed to resume tracking with the same identities after a restart. Track
+/// histories and pending fall phase changes are not kept.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct TrackerSnapshot {
+    pub taken_at: std::time::SystemTime,
+    pub next_target_id: u32,
+    pub config: TrackerConfig,
+    pub targets: Vec<TargetSnapshot>,
+}
+
+#[cfg(feature = "serde")]
+impl MultiTargetTracker {
+    pub fn snapshot(&self) -> TrackerSnapshot {
+        let mut targets: Vec<TargetSnapshot> = self.tracks.iter()
+            .map(|Track { target, filter, height_filter, .. }| TargetSnapshot {
+                id: target.id,
+                antenna_id: target.antenna_id,
+                position: target.position,
+                velocity: target.velocity,
+                acceleration: target.acceleration,
+                state: target.state,
+                confidence: target.confidence,
+                since_update: self.now().saturating_duration_since(target.last_update),
+                since_presence: self.now().saturating_duration_since(target.last_presence),
+                prediction_count: target.prediction_count,
+                fall_probability: target.fall_probability,
+                status: target.status,
+                hit_history: target.hit_history,
+                frame_count: target.frame_count,
+                vertical: target.vertical,
+                fall_phase: target.fall_phase,
+                interaction: target.interaction,
+                class: target.class,
+                filter: filter.clone(),
+                height_filter: height_filter.clone(),
+            })
+            .collect();
+        targets.sort_unstable_by_key(|target| target.id);
+
+        TrackerSnapshot {
+            taken_at: std::time::SystemTime::now(),
+            next_target_id: self.next_target_id,
+            config: self.config.clone(),
+            targets,
+        }
+    }
+
+    /// Tracker continuing from `snapshot`. Time passed since the snapshot was taken counts
+    /// towards each track's age, so tracks that went stale meanwhile are removed as lost.
+    pub fn restore(antenna_count: u8, snapshot: TrackerSnapshot) -> Self {
+        let mut tracker = Self::with_config(antenna_count, snapshot.config);
+        let downtime = snapshot.taken_at.elapsed().unwrap_or_default();
+        let now = tracker.now();
+
+        for saved in snapshot.targets {
+            let mut target = TrackedTarget::new_at(saved.id, saved.antenna_id, saved.position, now);
+            target.velocity = saved.velocity;
+            target.acceleration = saved.acceleration;
+            target.state = saved.state;
+            target.confidence = saved.confidence;
+            target.last_update = now.checked_sub(saved.since_update + downtime).unwrap_or(now);
+            target.last_presence = now.checked_sub(saved.since_presence + downtime).unwrap_or(now);
+            target.prediction_count = saved.prediction_count;
+            target.fall_probability = saved.fall_probability;
+            target.status = saved.status;
+            target.hit_history = saved.hit_history;
+            target.frame_count = saved.frame_count;
+            target.vertical = saved.vertical;
+            target.fall_phase = saved.fall_phase;
+            target.interaction = saved.interaction;
+            target.class = saved.class;
+
+            let mut track = Track::new(target, saved.filter, TrackHistory::new(tracker.config.history_length));
+            track.height_filter = saved.height_filter;
+            tracker.tracks.insert(track);
+            tracker.record_history(saved.id);
+        }
+        tracker.next_target_id = tracker.tracks.ids()
+            .map(|id| id + 1)
+            .fold(snapshot.next_target_id, u32::max);
+
+        info!("Restored {} targets from snapshot", tracker.tracks.len());
+        tracker
+    }
+}
+
+/// Scale `vector` down to a length of at most `max`
+fn cap_norm(vector: Vector2<f32>, max: f32) -> Vector2<f32> {
+    let norm = vector.norm();
+    if norm > max && norm > 0.0 {
+        vector * (max / norm)
+    } else {
+        vector
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::clock::SimulatedClock;
+
+    /// Clock driving `tracker`, tests advance it instead of sleeping
+    fn simulated_clock(tracker: &mut MultiTargetTracker) -> SimulatedClock {
+        let clock = SimulatedClock::new();
+        tracker.set_clock(Arc::new(clock.clone()));
+        clock
+    }
 
     #[test]
     fn test_target_creation() {
@@ -441,6 +2088,114 @@ mod tests {
         assert_eq!(tracker.get_target_count_by_antenna(0), 1);
     }
 
+    #[test]
+    fn test_antenna_validation_and_capacity() {
+        let config = TrackerConfig {
+            max_targets_per_antenna: 2,
+            antenna_capacities: vec![AntennaCapacity { antenna_id: 1, max_targets: 1 }],
+            ..TrackerConfig::default()
+        };
+        let mut tracker = MultiTargetTracker::with_config(2, config);
+        assert_eq!(
+            tracker.try_add_target(2, Vector2::new(0.0, 0.0)),
+            Err(TrackerError::InvalidAntenna { antenna_id: 2, antenna_count: 2 })
+        );
+        assert!(tracker.process_frame(5, &[], Instant::now()).is_err());
+
+        tracker.try_add_target(1, Vector2::new(0.0, 0.0)).unwrap();
+        assert_eq!(
+            tracker.try_add_target(1, Vector2::new(4.0, 0.0)),
+            Err(TrackerError::AntennaSaturated { antenna_id: 1, capacity: 1 })
+        );
+        // Out of range measurements are dropped, not assigned to other antennas' targets
+        assert_eq!(tracker.update_targets(&[(3, Vector2::new(0.0, 0.0))], 2.0), vec![None]);
+
+        let loads = tracker.get_antenna_loads();
+        assert_eq!(loads.len(), 2);
+        assert_eq!((loads[0].targets, loads[0].capacity), (0, 2));
+        assert_eq!((loads[1].targets, loads[1].rejected), (1, 1));
+        assert_eq!(loads[1].saturation(), 1.0);
+    }
+
+    #[test]
+    fn test_close_targets_keep_identity() {
+        let mut tracker = MultiTargetTracker::new(4);
+        let clock = simulated_clock(&mut tracker);
+        let a = tracker.add_target(0, Vector2::new(0.0, 0.0)).unwrap();
+        let b = tracker.add_target(0, Vector2::new(1.0, 0.0)).unwrap();
+        clock.advance(Duration::from_millis(5));
+
+        // Measurement for b comes first and is also within 2 m of a
+        let updated = tracker.update_targets(
+            &[(0, Vector2::new(1.1, 0.0)), (0, Vector2::new(0.1, 0.0)), (1, Vector2::new(8.0, 8.0))],
+            2.0,
+        );
+
+        assert_eq!(updated[0], Some(b));
+        assert_eq!(updated[1], Some(a));
+        assert!(updated[2].is_some_and(|id| id != a && id != b));
+        assert_eq!(tracker.get_target_count(), 3);
+    }
+
+    #[test]
+    fn test_merge_and_split() {
+        let mut tracker = MultiTargetTracker::new(1);
+        let clock = simulated_clock(&mut tracker);
+        let a = tracker.add_target(0, Vector2::new(0.0, 0.0)).unwrap();
+        let b = tracker.add_target(0, Vector2::new(1.0, 0.0)).unwrap();
+        clock.advance(Duration::from_millis(5));
+
+        // Two people crossing give a single detection between them
+        let updated = tracker.update_targets(&[(0, Vector2::new(0.4, 0.0))], 1.2);
+        assert_eq!(updated, vec![Some(a)]);
+        assert_eq!(tracker.get_target(a).unwrap().interaction, Some(TrackInteraction::Merged { with: b }));
+        assert_eq!(tracker.get_target(b).unwrap().interaction, Some(TrackInteraction::Merged { with: a }));
+        // The occluded track coasts instead of counting a miss
+        assert_eq!(tracker.get_target(b).unwrap().get_hit_count(), 1);
+        clock.advance(Duration::from_millis(5));
+
+        // They separate again, each keeping its identity
+        let updated = tracker.update_targets(&[(0, Vector2::new(-0.1, 0.0)), (0, Vector2::new(1.5, 0.0))], 1.2);
+        assert_eq!(updated, vec![Some(a), Some(b)]);
+        assert!(!tracker.get_target(a).unwrap().is_ambiguous() && !tracker.get_target(b).unwrap().is_ambiguous());
+
+        // A new detection next to a track starts a split track
+        clock.advance(Duration::from_millis(5));
+        let updated = tracker.update_targets(&[(0, Vector2::new(-0.1, 0.0)), (0, Vector2::new(-0.1, 0.5))], 1.2);
+        assert!(updated.contains(&Some(a)));
+        let c = updated.iter().flatten().copied().find(|&id| id != a).unwrap();
+        assert_ne!(c, b);
+        assert_eq!(tracker.get_target(c).unwrap().interaction, Some(TrackInteraction::Split { with: a }));
+    }
+
+    #[test]
+    fn test_m_of_n_confirmation() {
+        let mut tracker = MultiTargetTracker::new(1);
+        let clock = simulated_clock(&mut tracker);
+        let person = Vector2::new(0.0, 0.0);
+        let clutter = Vector2::new(5.0, 5.0);
+
+        for frame in 0..5 {
+            clock.advance(Duration::from_millis(2));
+            let mut measurements = vec![(0, person)];
+            if frame == 0 {
+                measurements.push((0, clutter));
+            }
+            tracker.update_targets(&measurements, 2.0);
+
+            if frame == 1 {
+                assert_eq!(tracker.get_target_count_by_status(TrackStatus::Tentative), 2);
+                assert!(tracker.get_confirmed_targets().is_empty());
+            }
+        }
+
+        // The person was seen in every frame, the clutter only once
+        let confirmed = tracker.get_confirmed_targets();
+        assert_eq!(confirmed.len(), 1);
+        assert!(confirmed[0].position.norm() < 0.5);
+        assert_eq!(tracker.get_target_count(), 1);
+    }
+
     #[test]
     fn test_fall_detector() {
         let detector = FallDetector::new();
@@ -452,12 +2207,342 @@ mod tests {
         assert!(risk > 0.5);
     }
 
+    #[test]
+    fn test_mahalanobis_gate_rejects_jumps() {
+        let mut tracker = MultiTargetTracker::new(1);
+        let clock = simulated_clock(&mut tracker);
+        let id = tracker.add_target(0, Vector2::new(0.0, 0.0)).unwrap();
+        for _ in 0..10 {
+            clock.advance(Duration::from_millis(2));
+            assert!(tracker.update_target(id, Vector2::new(0.05, 0.0)));
+        }
+
+        let filter = &tracker.tracks.get(id).unwrap().filter;
+        assert!(filter.get_innovation_covariance()[(0, 0)] < 100.0);
+        assert!(!filter.gate(Vector2::new(40.0, 0.0), DEFAULT_MAHALANOBIS_GATE));
+
+        clock.advance(Duration::from_millis(2));
+        assert!(!tracker.update_target(id, Vector2::new(40.0, 0.0)));
+        assert!(tracker.get_all_targets()[0].position.x < 1.0);
+        assert!(tracker.associate(&[Vector2::new(40.0, 0.0)], 100.0).assigned.is_empty());
+    }
+
+    #[test]
+    fn test_physical_limits() {
+        let mut tracker = MultiTargetTracker::new(1);
+        let clock = simulated_clock(&mut tracker);
+        let id = tracker.add_target(0, Vector2::new(0.0, 0.0)).unwrap();
+        clock.advance(Duration::from_millis(5));
+
+        // Inside the wide initial gate, but 50 m/s away
+        assert!(!tracker.update_target(id, Vector2::new(5.0, 0.0)));
+        assert!(tracker.update_target(id, Vector2::new(0.1, 0.0)));
+
+        let config = TrackerConfig {
+            max_speed: 1000.0,
+            max_acceleration: 5.0,
+            ..TrackerConfig::default()
+        };
+        let mut tracker = MultiTargetTracker::with_config(1, config);
+        let clock = simulated_clock(&mut tracker);
+        let id = tracker.add_target(0, Vector2::new(0.0, 0.0)).unwrap();
+        clock.advance(Duration::from_millis(5));
+        assert!(tracker.update_target(id, Vector2::new(5.0, 0.0)));
+        assert!(tracker.get_target(id).unwrap().acceleration.norm() <= 5.0 + 1e-3);
+    }
+
+    #[test]
+    fn test_simulated_clock() {
+        let clock = SimulatedClock::new();
+        let mut tracker = MultiTargetTracker::new(1);
+        tracker.set_clock(Arc::new(clock.clone()));
+        let id = tracker.add_target(0, Vector2::new(0.0, 0.0)).unwrap();
+
+        // No time passed, nothing to update
+        assert!(!tracker.update_target(id, Vector2::new(0.1, 0.0)));
+        clock.advance(Duration::from_millis(100));
+        assert!(tracker.update_target(id, Vector2::new(0.1, 0.0)));
+        assert_eq!(tracker.get_target(id).unwrap().last_update, clock.now());
+
+        clock.advance(Duration::from_secs(31));
+        tracker.remove_lost_targets();
+        assert_eq!(tracker.get_target_count(), 0);
+    }
+
+    #[test]
+    fn test_stationary_presence() {
+        let clock = SimulatedClock::new();
+        let mut tracker = MultiTargetTracker::new(1);
+        tracker.set_clock(Arc::new(clock.clone()));
+        let id = tracker.add_target(0, Vector2::new(1.0, 1.0)).unwrap();
+        clock.advance(Duration::from_millis(100));
+        assert!(tracker.update_target(id, Vector2::new(1.1, 1.0)));
+
+        // Sitting down: updates stop, the LD2412 still sees breathing at the target's range
+        for _ in 0..20 {
+            clock.advance(Duration::from_secs(5));
+            tracker.predict_all_targets(Duration::from_millis(100));
+            tracker.observe_micro_motion(id, 0.4);
+            tracker.remove_lost_targets();
+        }
+        let target = tracker.get_target(id).unwrap();
+        assert_eq!(target.state, TargetState::StationaryPresence);
+        assert_eq!(target.velocity, Vector2::zeros());
+
+        // A still LD2450 detection counts too, a weak energy reading does not
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(tracker.observe_stationary_detection(0, Vector2::new(1.2, 1.0), 0.0), Some(id));
+        assert_eq!(tracker.observe_stationary_detection(0, Vector2::new(1.2, 1.0), 0.5), None);
+        clock.advance(Duration::from_secs(20));
+        tracker.observe_micro_motion(id, 0.05);
+        tracker.remove_lost_targets();
+        assert_eq!(tracker.get_target_count(), 1);
+
+        clock.advance(Duration::from_secs(11));
+        tracker.remove_lost_targets();
+        assert_eq!(tracker.get_target_count(), 0);
+    }
+
+    #[test]
+    fn test_stationary_presence_between_frames() {
+        let clock = SimulatedClock::new();
+        let mut tracker = MultiTargetTracker::new(1);
+        tracker.set_clock(Arc::new(clock.clone()));
+        tracker.set_confirmation(1, 5);
+        let id = tracker.add_target(0, Vector2::new(1.0, 1.0)).unwrap();
+
+        // Missed by a frame while moving, a presence report only refreshes it
+        clock.advance(Duration::from_millis(100));
+        tracker.update_targets(&[], 2.0);
+        tracker.observe_micro_motion(id, 0.4);
+        assert_eq!(tracker.get_target(id).unwrap().state, TargetState::Tracking);
+
+        // Frames keep coming without it, nothing predicts in between
+        for _ in 0..2 {
+            clock.advance(Duration::from_millis(100));
+            tracker.update_targets(&[], 2.0);
+        }
+        tracker.observe_micro_motion(id, 0.4);
+        assert_eq!(tracker.get_target(id).unwrap().state, TargetState::StationaryPresence);
+    }
+
+    #[test]
+    fn test_deletion_policy() {
+        let clock = SimulatedClock::new();
+        let mut tracker = MultiTargetTracker::new(1);
+        tracker.set_clock(Arc::new(clock.clone()));
+        tracker.set_confirmation(1, 1);
+        let confirmed = tracker.add_target(0, Vector2::new(0.0, 0.0)).unwrap();
+        tracker.set_confirmation(3, 5);
+        let tentative = tracker.add_target(0, Vector2::new(5.0, 0.0)).unwrap();
+
+        clock.advance(Duration::from_secs(10));
+        tracker.remove_lost_targets();
+        assert!(tracker.get_target(confirmed).is_some());
+        assert!(tracker.get_target(tentative).is_none());
+
+        tracker.set_deletion_policy(TrackDeletionPolicy { confirmed_timeout_s: 5.0, ..TrackDeletionPolicy::default() });
+        tracker.remove_lost_targets();
+        assert_eq!(tracker.get_target_count(), 0);
+    }
+
+    #[test]
+    fn test_process_frame() {
+        let deletion = TrackDeletionPolicy { tentative_timeout_s: 1.0, ..TrackDeletionPolicy::default() };
+        let config = TrackerConfig { deletion, ..TrackerConfig::default() };
+        let mut tracker = MultiTargetTracker::with_config(2, config);
+        let start = Instant::now();
+        let other = tracker.add_target(1, Vector2::new(0.0, 0.0)).unwrap();
+
+        let frame = [Measurement::new(Vector2::new(0.1, 0.0)), Measurement::new(Vector2::new(3.0, 0.0))];
+        let first = tracker.process_frame(0, &frame, start).unwrap();
+        assert_eq!(first.created.len(), 2);
+        // Antenna 1's target is neither matched nor missed by antenna 0's frame
+        assert!(first.missed.is_empty());
+
+        let frame = [Measurement { position: Vector2::new(3.05, 0.0), size: Some(0.5) }];
+        let second = tracker.process_frame(0, &frame, start + Duration::from_millis(100)).unwrap();
+        assert_eq!(second.assignments, vec![Some(first.created[1])]);
+        assert_eq!(second.missed, vec![first.created[0]]);
+        assert!(tracker.classifier.get_features(first.created[1]).is_some());
+
+        tracker.set_keep_completed_tracks(true);
+        let third = tracker.process_frame(0, &[], start + Duration::from_secs(2)).unwrap();
+        assert_eq!(third.removed.len(), 2);
+        let completed = tracker.take_completed_tracks();
+        assert_eq!(completed.iter().map(|t| t.history.len()).sum::<usize>(), 3);
+        assert!(tracker.take_completed_tracks().is_empty());
+        assert_eq!(tracker.get_target_count(), 1);
+        assert!(tracker.get_target(other).is_some());
+    }
+
+    #[cfg(feature = "controller")]
+    #[test]
+    fn test_snapshot_restore() {
+        let config = TrackerConfig { filter: FilterKind::Imm, confirm_hits: 1, ..TrackerConfig::default() };
+        let mut tracker = MultiTargetTracker::with_config(2, config);
+        tracker.add_target(0, Vector2::new(1.0, 2.0)).unwrap();
+        let id = tracker.add_target_3d(1, Vector3::new(3.0, 1.0, 1.7)).unwrap();
+
+        let json = serde_json::to_string(&tracker.snapshot()).unwrap();
+        let snapshot: TrackerSnapshot = serde_json::from_str(&json).unwrap();
+        let mut restored = MultiTargetTracker::restore(2, snapshot);
+
+        assert_eq!(restored.get_target_count(), 2);
+        assert!(matches!(restored.tracks.get(id).unwrap().filter, TrackFilter::Imm(_)));
+        assert_eq!(restored.get_target(id).unwrap().vertical.map(|v| v.height), Some(1.7));
+        assert!((restored.tracks.get(id).unwrap().filter.get_position() - Vector2::new(3.0, 1.0)).norm() < 1e-5);
+        // Identities continue after the restored ones
+        assert_eq!(restored.add_target(0, Vector2::new(5.0, 5.0)), Some(2));
+    }
+
+    #[test]
+    fn test_vertical_fall_detection() {
+        let detector = FallDetector::new();
+
+        // Walking sideways fast is no fall once the real height is known
+        let mut walking = TrackedTarget::new(1, 0, Vector2::new(0.0, 0.0));
+        walking.velocity = Vector2::new(0.0, -3.0);
+        walking.acceleration = Vector2::new(0.0, -10.0);
+        assert!(detector.analyze_fall_risk(&walking) > 0.5);
+        walking.vertical = Some(VerticalState { height: 1.0, velocity: 0.0, acceleration: 0.0 });
+        assert!(detector.analyze_fall_risk(&walking) < 0.5);
+
+        let mut falling = TrackedTarget::new(2, 0, Vector2::new(0.0, 0.0));
+        falling.vertical = Some(VerticalState { height: 0.6, velocity: -3.0, acceleration: -10.0 });
+        assert!(detector.analyze_fall_risk(&falling) > 0.7);
+
+        let mut tracker = MultiTargetTracker::new(1);
+
+        let clock = simulated_clock(&mut tracker);
+        let id = tracker.add_target_3d(0, Vector3::new(1.0, 2.0, 1.7)).unwrap();
+        clock.advance(Duration::from_millis(2));
+        assert!(tracker.update_target_3d(id, Vector3::new(1.0, 2.0, 1.6)));
+        let position = tracker.get_all_targets()[0].get_position_3d().unwrap();
+        assert!(position.z < 1.7 && position.z > 1.5);
+
+        let history = tracker.get_track_history(id, Duration::from_secs(60)).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].height, Some(1.7));
+        assert_eq!(history[1].height, Some(position.z));
+        assert!(tracker.get_track_history(id + 1, Duration::from_secs(60)).is_none());
+    }
+
+    #[test]
+    fn test_tracker_config() {
+        let config = TrackerConfig {
+            filter: FilterKind::Imm,
+            confirm_hits: 1,
+            confirm_window: 99,
+            fall_detector: FallDetectorConfig {
+                fall_probability_threshold: 0.2,
+                ..FallDetectorConfig::default()
+            },
+            ..TrackerConfig::default()
+        };
+        let mut tracker = MultiTargetTracker::with_config(1, config);
+        assert_eq!(tracker.get_confirmation(), (1, 32));
+
+        let id = tracker.add_target(0, Vector2::new(0.0, 0.0)).unwrap();
+        assert!(matches!(tracker.tracks.get(id).unwrap().filter, TrackFilter::Imm(_)));
+        assert!(tracker.get_confirmed_targets().len() == 1);
+
+        // Moderate risk already counts as falling with the lowered threshold
+        tracker.target_mut(id).unwrap().fall_probability = 0.3;
+        assert_eq!(tracker.get_falling_targets().len(), 1);
+        assert!(!tracker.get_all_targets()[0].is_falling());
+    }
+
+    #[test]
+    fn test_kalman_update_errors_are_recoverable() {
+        let mut kf = KalmanFilter::new(Vector2::new(1.0, 2.0));
+        assert_eq!(kf.update(Vector2::new(f32::NAN, 0.0)), Err(FilterError::NonFinite));
+        assert_eq!(kf.get_position(), Vector2::new(1.0, 2.0));
+
+        // A filter driven to certainty with noiseless measurements stays invertible
+        kf.set_measurement_noise(0.0);
+        for _ in 0..50 {
+            kf.predict(0.01);
+            kf.update(Vector2::new(1.0, 2.0)).unwrap();
+        }
+        let (_, covariance) = kf.get_state();
+        assert_eq!(*covariance, covariance.transpose());
+        assert!(kf.gate(Vector2::new(1.0, 2.0), DEFAULT_MAHALANOBIS_GATE));
+
+        assert!(invert_covariance(&Matrix2::new(1.0, 1.0, 1.0, 1.0)).is_ok());
+        assert_eq!(invert_covariance(&Matrix2::new(f32::INFINITY, 0.0, 0.0, 1.0)), Err(FilterError::NonFinite));
+    }
+
+    #[test]
+    fn test_fall_events() {
+        let mut tracker = MultiTargetTracker::new(1);
+        tracker.set_confirmation(1, 1);
+        let events = tracker.events();
+        let id = tracker.add_target(0, Vector2::new(0.0, 1.0)).unwrap();
+        let start = Instant::now();
+        let window = tracker.fall_detector.get_time_window();
+
+        let target = tracker.target_mut(id).unwrap();
+        target.fall_probability = 0.9;
+        tracker.update_fall_phase(id, start);
+        assert!(matches!(events.try_recv(), Ok(FallEvent::FallSuspected { target_id, landing: Some(_), .. }) if target_id == id));
+
+        // Lying still after the impact, confirmed once the window has passed
+        tracker.target_mut(id).unwrap().fall_probability = 0.0;
+        tracker.update_fall_phase(id, start + window / 2);
+        assert!(events.try_recv().is_err());
+        tracker.update_fall_phase(id, start + window * 3 / 2);
+        assert!(matches!(events.try_recv(), Ok(FallEvent::FallConfirmed { .. })));
+
+        // A single step is not a recovery, walking away for the whole window is
+        let target = tracker.target_mut(id).unwrap();
+        target.velocity = Vector2::new(1.0, 0.0);
+        tracker.update_fall_phase(id, start + window * 2);
+        tracker.target_mut(id).unwrap().velocity = Vector2::zeros();
+        tracker.update_fall_phase(id, start + window * 3);
+        assert!(events.try_recv().is_err());
+
+        tracker.target_mut(id).unwrap().velocity = Vector2::new(1.0, 0.0);
+        tracker.update_fall_phase(id, start + window * 4);
+        tracker.update_fall_phase(id, start + window * 5);
+        assert!(matches!(events.try_recv(), Ok(FallEvent::FallCleared { .. })));
+        assert_eq!(tracker.get_target(id).unwrap().fall_phase, FallPhase::Normal);
+    }
+
+    #[test]
+    fn test_fall_trajectory() {
+        let detector = FallDetector::new();
+        let mut filter = TrackFilter::new(FilterKind::ConstantAcceleration, Vector2::new(0.0, 0.0));
+        for step in 1..=20 {
+            filter.predict(0.1);
+            filter.update(Vector2::new(step as f32 * 0.1, 0.0)).unwrap();
+        }
+        let mut target = TrackedTarget::new(0, 0, filter.get_position());
+
+        // Unknown height: predicted over the whole horizon
+        let trajectory = detector.predict_fall_trajectory(&target, &filter);
+        assert_eq!(trajectory.positions.len(), 20);
+        assert_eq!(trajectory.landing.time_to_impact, Duration::from_secs(1));
+        assert!(trajectory.covariances[19][(0, 0)] > trajectory.covariances[0][(0, 0)]);
+
+        // Dropping from 1 m at 2 m/s under gravity hits the floor after about 0.29 s
+        target.vertical = Some(VerticalState { height: 1.0, velocity: -2.0, acceleration: -9.81 });
+        let trajectory = detector.predict_fall_trajectory(&target, &filter);
+        let impact = trajectory.landing.time_to_impact.as_secs_f32();
+        assert!((impact - 0.2916).abs() < 1e-3);
+        assert_eq!(trajectory.positions.len(), 6);
+        let landing = trajectory.landing;
+        assert!((landing.centre.x - (2.0 + impact)).abs() < 0.1);
+        assert!(landing.contains(landing.centre));
+        assert!(!landing.contains(landing.centre + Vector2::new(0.0, landing.semi_axes.0 + 0.1)));
+    }
+
     #[test]
     fn test_kalman_filter() {
         let mut kf = KalmanFilter::new(Vector2::new(0.0, 0.0));
         
         kf.predict(0.1);
-        kf.update(Vector2::new(1.0, 1.0));
+        kf.update(Vector2::new(1.0, 1.0)).unwrap();
         
         let pos = kf.get_position();
         assert!(pos.x > 0.0);
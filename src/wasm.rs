@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+
+use smallvec::SmallVec;
+use wasm_bindgen::prelude::*;
+
+use crate::{FrameAccumulator, RadarLLFrame};
+
+#[wasm_bindgen]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameKind {
+    CommandAck = 0,
+    Ld2412Target = 1,
+    Ld2450Target = 2,
+}
+
+/// A parsed frame as seen from JavaScript
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct Frame {
+    kind: FrameKind,
+    opcode: u16,
+    data: Vec<u8>,
+}
+
+#[wasm_bindgen]
+impl Frame {
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> FrameKind {
+        self.kind
+    }
+
+    /// Only meaningful for command acknowledgements
+    #[wasm_bindgen(getter)]
+    pub fn opcode(&self) -> u16 {
+        self.opcode
+    }
+
+    /// Payload between the length field and the footer
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+}
+
+impl From<RadarLLFrame> for Frame {
+    fn from(frame: RadarLLFrame) -> Self {
+        let (kind, opcode, data) = match frame {
+            RadarLLFrame::CommandAckFrame(opcode, data) => {
+                (FrameKind::CommandAck, opcode, data.to_vec())
+            }
+            RadarLLFrame::TargetFrame(data) => (FrameKind::Ld2412Target, 0, data.to_vec()),
+            RadarLLFrame::TargetFrame2D(data) => (FrameKind::Ld2450Target, 0, data.to_vec()),
+        };
+
+        Self { kind, opcode, data }
+    }
+}
+
+/// Streaming parser for bytes read from a WebSerial port
+#[wasm_bindgen]
+#[derive(Debug, Default)]
+pub struct Parser {
+    accumulator: FrameAccumulator,
+}
+
+#[wasm_bindgen]
+impl Parser {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append received bytes, returning how many fit. Push the rest after draining frames.
+    pub fn push(&mut self, bytes: &[u8]) -> usize {
+        self.accumulator.push(bytes)
+    }
+
+    #[wasm_bindgen(js_name = nextFrame)]
+    pub fn next_frame(&mut self) -> Option<Frame> {
+        self.accumulator.next_frame().map(Frame::from)
+    }
+
+    #[wasm_bindgen(js_name = bufferedLen)]
+    pub fn buffered_len(&self) -> usize {
+        self.accumulator.buffered_len()
+    }
+
+    pub fn clear(&mut self) {
+        self.accumulator.clear();
+    }
+}
+
+/// Wire bytes of a command frame, for writing to the WebSerial port
+#[wasm_bindgen(js_name = commandFrame)]
+pub fn command_frame(opcode: u16, data: &[u8]) -> Vec<u8> {
+    RadarLLFrame::CommandAckFrame(opcode, SmallVec::from_slice(data))
+        .serialize()
+        .map(|bytes| bytes.to_vec())
+        .unwrap_or_default()
+}
@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::scanner::{FrequencyRange, SignalReading};
+#[cfg(feature = "fft")]
+use crate::sweep::Spectrum;
+
+#[derive(Debug, Clone)]
+pub struct WaterfallRow {
+    pub timestamp: SystemTime,
+    /// Power per bin in dB
+    pub power: Vec<f32>,
+}
+
+/// Time × frequency history of power measurements. Keeps at most `max_rows` sweeps, dropping
+/// the oldest, so memory stays bounded during continuous scans.
+#[derive(Debug, Clone)]
+pub struct Waterfall {
+    start: f32,
+    bin_width: f32,
+    bins: usize,
+    max_rows: usize,
+    rows: VecDeque<WaterfallRow>,
+}
+
+impl Waterfall {
+    /// `start` and `bin_width` in MHz, each row holds `bins` power values
+    pub fn new(start: f32, bin_width: f32, bins: usize, max_rows: usize) -> Self {
+        Self {
+            start,
+            bin_width,
+            bins,
+            max_rows: max_rows.max(1),
+            rows: VecDeque::with_capacity(max_rows.max(1)),
+        }
+    }
+
+    /// One bin per step of a stepped scan over `range`
+    pub fn from_range(range: &FrequencyRange, max_rows: usize) -> Self {
+        let bins = ((range.end - range.start) / range.step).floor() as usize + 1;
+        Self::new(range.start, range.step, bins, max_rows)
+    }
+
+    /// Append a sweep, padding or truncating it to the configured number of bins
+    pub fn push_row(&mut self, timestamp: SystemTime, power: &[f32]) {
+        let mut power = power.to_vec();
+        power.resize(self.bins, f32::NEG_INFINITY);
+
+        if self.rows.len() == self.max_rows {
+            self.rows.pop_front();
+        }
+        self.rows.push_back(WaterfallRow { timestamp, power });
+    }
+
+    /// Append readings from a stepped scan, each placed in the nearest bin. Bins without a
+    /// reading are left at -inf.
+    pub fn push_readings(&mut self, readings: &[SignalReading]) {
+        let mut power = vec![f32::NEG_INFINITY; self.bins];
+        for reading in readings {
+            let bin = ((reading.frequency - self.start) / self.bin_width).round();
+            if bin >= 0.0 {
+                if let Some(slot) = power.get_mut(bin as usize) {
+                    *slot = slot.max(reading.strength);
+                }
+            }
+        }
+
+        self.push_row(SystemTime::now(), &power);
+    }
+
+    #[cfg(feature = "fft")]
+    pub fn push_spectrum(&mut self, spectrum: &Spectrum) {
+        self.push_row(SystemTime::now(), &spectrum.power);
+    }
+
+    pub fn frequency(&self, bin: usize) -> f32 {
+        self.start + bin as f32 * self.bin_width
+    }
+
+    pub fn get_bins(&self) -> usize {
+        self.bins
+    }
+
+    pub fn get_row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Rows from oldest to newest
+    pub fn rows(&self) -> impl Iterator<Item = &WaterfallRow> {
+        self.rows.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.rows.clear();
+    }
+
+    /// One line per sweep: Unix timestamp in seconds, then the power of each bin. The header
+    /// line holds the bin frequencies in MHz.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(writer, "timestamp")?;
+        for bin in 0..self.bins {
+            write!(writer, ",{:.6}", self.frequency(bin))?;
+        }
+        writeln!(writer)?;
+
+        for row in &self.rows {
+            let seconds = row
+                .timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64();
+            write!(writer, "{:.3}", seconds)?;
+            for power in &row.power {
+                write!(writer, ",{:.2}", power)?;
+            }
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
+    /// Render as an RGB image, one pixel per bin horizontally and the newest sweep on top.
+    /// Power is mapped over `range` in dB, or over the data's own min/max when `None`.
+    #[cfg(feature = "png")]
+    pub fn write_png<W: Write>(&self, writer: W, range: Option<(f32, f32)>) -> io::Result<()> {
+        let (min_db, max_db) = range.unwrap_or_else(|| self.power_range());
+        let span = (max_db - min_db).max(f32::EPSILON);
+
+        let width = self.bins.max(1) as u32;
+        let height = self.rows.len().max(1) as u32;
+        let mut pixels = Vec::with_capacity(width as usize * height as usize * 3);
+
+        for row in self.rows.iter().rev() {
+            for &power in &row.power {
+                pixels.extend_from_slice(&heat_color((power - min_db) / span));
+            }
+        }
+        pixels.resize(width as usize * height as usize * 3, 0);
+
+        let mut encoder = png::Encoder::new(writer, width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut png| png.write_image_data(&pixels))
+            .map_err(io::Error::other)
+    }
+
+    #[cfg(feature = "png")]
+    fn power_range(&self) -> (f32, f32) {
+        self.rows
+            .iter()
+            .flat_map(|row| row.power.iter().copied())
+            .filter(|power| power.is_finite())
+            .fold((f32::MAX, f32::MIN), |(min, max), power| {
+                (min.min(power), max.max(power))
+            })
+    }
+}
+
+/// Black → blue → red → yellow → white heat map for `level` in 0..=1
+#[cfg(feature = "png")]
+pub(crate) fn heat_color(level: f32) -> [u8; 3] {
+    const STOPS: [[f32; 3]; 5] = [
+        [0.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0],
+        [1.0, 0.0, 0.0],
+        [1.0, 1.0, 0.0],
+        [1.0, 1.0, 1.0],
+    ];
+
+    let level = if level.is_nan() {
+        0.0
+    } else {
+        level.clamp(0.0, 1.0)
+    };
+    let position = level * (STOPS.len() - 1) as f32;
+    let index = (position as usize).min(STOPS.len() - 2);
+    let t = position - index as f32;
+
+    let [r, g, b] = [0, 1, 2].map(|c| {
+        let value = STOPS[index][c] + (STOPS[index + 1][c] - STOPS[index][c]) * t;
+        (value * 255.0).round() as u8
+    });
+    [r, g, b]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rows_are_bounded() {
+        let mut waterfall = Waterfall::new(433.0, 0.5, 3, 2);
+        for i in 0..5 {
+            waterfall.push_row(UNIX_EPOCH + Duration::from_secs(i), &[i as f32; 3]);
+        }
+
+        assert_eq!(waterfall.get_row_count(), 2);
+        assert_eq!(waterfall.rows().next().unwrap().power, vec![3.0; 3]);
+    }
+
+    #[test]
+    fn test_csv_export() {
+        let mut waterfall = Waterfall::new(433.0, 0.5, 3, 10);
+        waterfall.push_row(UNIX_EPOCH + Duration::from_secs(60), &[-80.0, -40.5]);
+
+        let mut csv = Vec::new();
+        waterfall.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("timestamp,433.000000,433.500000,434.000000")
+        );
+        assert_eq!(lines.next(), Some("60.000,-80.00,-40.50,-inf"));
+    }
+}
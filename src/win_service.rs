@@ -0,0 +1,188 @@
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tracing::{error, info};
+use windows_service::service::{
+    Service, ServiceAccess, ServiceControl, ServiceControlAccept, ServiceErrorControl,
+    ServiceExitCode, ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+};
+use windows_service::service_control_handler::{self, ServiceControlHandlerResult};
+use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+use windows_service::{define_windows_service, service_dispatcher};
+
+use crate::error::{HexarError, HexarResult};
+use crate::shutdown::ShutdownTrigger;
+
+/// How often a stopping service is checked for having stopped
+const STOP_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+type ServiceBody = Box<dyn FnOnce() -> Result<(), String> + Send>;
+
+/// What [`run`] hands to the service thread the service control manager starts
+static SERVICE: Mutex<Option<(String, ShutdownTrigger, ServiceBody)>> = Mutex::new(None);
+
+/// Name the service is registered under with the service control manager, `hexar` or
+/// `hexar-<profile>`
+pub fn service_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("hexar-{}", profile),
+        None => "hexar".to_string(),
+    }
+}
+
+fn service_error(e: windows_service::Error) -> HexarError {
+    HexarError::SystemError(format!("service control manager: {}", e))
+}
+
+/// Register `executable` as an automatically started service `name`, run with `arguments`
+pub fn install(name: &str, executable: PathBuf, arguments: Vec<OsString>) -> HexarResult<()> {
+    let manager = ServiceManager::local_computer(
+        None::<&str>,
+        ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+    )
+    .map_err(service_error)?;
+    let info = ServiceInfo {
+        name: name.into(),
+        display_name: format!("Hexar radar controller ({})", name).into(),
+        service_type: ServiceType::OWN_PROCESS,
+        start_type: ServiceStartType::AutoStart,
+        error_control: ServiceErrorControl::Normal,
+        executable_path: executable,
+        launch_arguments: arguments,
+        dependencies: Vec::new(),
+        account_name: None,
+        account_password: None,
+    };
+    let service = manager
+        .create_service(&info, ServiceAccess::CHANGE_CONFIG)
+        .map_err(service_error)?;
+    service
+        .set_description("Scans, tracks targets and raises alerts with the hexagonal radar")
+        .map_err(service_error)?;
+    info!("Installed the {} service", name);
+    Ok(())
+}
+
+/// Stop service `name` if it runs and remove its registration
+pub fn uninstall(name: &str) -> HexarResult<()> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .map_err(service_error)?;
+    let access = ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE;
+    let service = manager.open_service(name, access).map_err(service_error)?;
+    if service.query_status().map_err(service_error)?.current_state != ServiceState::Stopped {
+        service.stop().map_err(service_error)?;
+    }
+    // Removed by the service control manager once the last handle closes
+    service.delete().map_err(service_error)?;
+    info!("Uninstalled the {} service", name);
+    Ok(())
+}
+
+fn open_service(name: &str, access: ServiceAccess) -> HexarResult<Service> {
+    let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+        .map_err(service_error)?;
+    manager.open_service(name, access).map_err(service_error)
+}
+
+/// Have the service control manager start service `name`, the background instance of
+/// Windows
+pub fn start(name: &str) -> HexarResult<()> {
+    let service = open_service(name, ServiceAccess::START)?;
+    service.start(&[] as &[&OsStr]).map_err(service_error)?;
+    info!("Started the {} service", name);
+    Ok(())
+}
+
+/// Stop service `name` and wait until it stopped, `false` when it wasn't running
+pub fn stop(name: &str, timeout: Duration) -> HexarResult<bool> {
+    let service = open_service(name, ServiceAccess::QUERY_STATUS | ServiceAccess::STOP)?;
+    if service.query_status().map_err(service_error)?.current_state == ServiceState::Stopped {
+        return Ok(false);
+    }
+    service.stop().map_err(service_error)?;
+    let deadline = Instant::now() + timeout;
+    while service.query_status().map_err(service_error)?.current_state != ServiceState::Stopped {
+        if Instant::now() >= deadline {
+            return Err(HexarError::Timeout(format!(
+                "the {} service didn't stop within {:?}",
+                name, timeout
+            )));
+        }
+        std::thread::sleep(STOP_POLL_INTERVAL);
+    }
+    info!("Stopped the {} service", name);
+    Ok(true)
+}
+
+/// Whether service `name` is running, including while it starts or stops
+pub fn is_running(name: &str) -> HexarResult<bool> {
+    let service = open_service(name, ServiceAccess::QUERY_STATUS)?;
+    let status = service.query_status().map_err(service_error)?;
+    Ok(status.current_state != ServiceState::Stopped)
+}
+
+define_windows_service!(ffi_service_main, service_main);
+
+/// Hand this thread to the service control manager and run `body` as service `name`, pulling
+/// `trigger` when the manager stops it. Blocks until the service stopped, fails when the
+/// process wasn't started by the manager.
+pub fn run<E: fmt::Display>(
+    name: &str,
+    trigger: ShutdownTrigger,
+    body: impl FnOnce() -> Result<(), E> + Send + 'static,
+) -> HexarResult<()> {
+    let body: ServiceBody = Box::new(move || body().map_err(|e| e.to_string()));
+    *SERVICE.lock().unwrap_or_else(|e| e.into_inner()) = Some((name.to_string(), trigger, body));
+    service_dispatcher::start(name, ffi_service_main).map_err(service_error)
+}
+
+fn service_main(_arguments: Vec<OsString>) {
+    let Some((name, trigger, body)) = SERVICE.lock().unwrap_or_else(|e| e.into_inner()).take()
+    else {
+        return;
+    };
+    if let Err(e) = run_service(&name, trigger, body) {
+        error!("Windows service failed: {}", e);
+    }
+}
+
+fn run_service(
+    name: &str,
+    trigger: ShutdownTrigger,
+    body: ServiceBody,
+) -> windows_service::Result<()> {
+    let status_handle = service_control_handler::register(name, move |control| match control {
+        ServiceControl::Stop | ServiceControl::Shutdown => {
+            trigger.request();
+            ServiceControlHandlerResult::NoError
+        }
+        ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+        _ => ServiceControlHandlerResult::NotImplemented,
+    })?;
+    let status = |state: ServiceState, exit_code: ServiceExitCode| ServiceStatus {
+        service_type: ServiceType::OWN_PROCESS,
+        current_state: state,
+        controls_accepted: if state == ServiceState::Running {
+            ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN
+        } else {
+            ServiceControlAccept::empty()
+        },
+        exit_code,
+        checkpoint: 0,
+        wait_hint: Duration::ZERO,
+        process_id: None,
+    };
+    status_handle.set_service_status(status(ServiceState::Running, ServiceExitCode::NO_ERROR))?;
+
+    let exit_code = match body() {
+        Ok(()) => ServiceExitCode::NO_ERROR,
+        Err(e) => {
+            error!("Service stopped on error: {}", e);
+            ServiceExitCode::ServiceSpecific(1)
+        }
+    };
+    status_handle.set_service_status(status(ServiceState::Stopped, exit_code))
+}